@@ -0,0 +1,276 @@
+//! Analyzing the query files of `check`
+//!
+//! Each file is read, its `-- sqlsurge:` header and `[[overrides]]` applied,
+//! and analyzed as a whole, a statement at a time when it is very large, or
+//! not at all when it is over a size limit. Diagnostics are printed as each
+//! file is done, unless human output is grouped across files.
+
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use miette::{IntoDiagnostic, Result};
+use sqlsurge_core::dialect::DialectSpec;
+use sqlsurge_core::schema::Catalog;
+use sqlsurge_core::{AnalysisReport, Analyzer, DiagnosticKind};
+
+use crate::args::OutputFormat;
+use crate::config::{Config, DEFAULT_MAX_FILE_SIZE, DEFAULT_STREAMING_THRESHOLD};
+use crate::header::FileHeader;
+use crate::output::{FileDiagnostics, OutputFormatter, Profiler};
+use crate::status::RunStatus;
+
+/// Bytes read of a file skipped for its size, enough for a `-- sqlsurge:`
+/// header and the line its warning points at
+const HEAD_BYTES: u64 = 4096;
+
+/// How `check` analyzes its query files
+pub struct QueryCheck<'a> {
+    pub config: &'a Config,
+    /// The catalog, or `None` under `--no-schema`
+    pub schema: Option<&'a Catalog>,
+    pub spec: DialectSpec,
+    pub output_format: OutputFormat,
+    /// Files named on the command line, not through a glob, which are
+    /// checked whatever their size
+    pub explicit: Vec<PathBuf>,
+    pub hints: bool,
+    pub profiling: bool,
+    /// Human output is printed once all files are analyzed, grouped
+    pub grouped: bool,
+}
+
+/// What analyzing the query files found
+#[derive(Default)]
+pub struct QueryResults {
+    pub report: AnalysisReport,
+    /// Diagnostics of each file, when output is grouped
+    pub collected: Vec<FileDiagnostics>,
+}
+
+impl<'a> QueryCheck<'a> {
+    /// Analyze `files` in order, recording each in `status`
+    pub fn run(
+        &self,
+        files: &[PathBuf],
+        profiler: &mut Profiler,
+        status: &mut RunStatus,
+    ) -> Result<QueryResults> {
+        let mut results = QueryResults::default();
+        let mut analyzer = self.analyzer(self.spec, self.config);
+        for file in files {
+            self.check_file(file, &mut analyzer, &mut results, profiler, status)?;
+        }
+        Ok(results)
+    }
+
+    /// Analyzer for `check` with the given dialect and settings
+    fn analyzer(&self, spec: DialectSpec, config: &Config) -> Analyzer<'a> {
+        check_analyzer(self.schema, spec, config).with_profiling(self.profiling)
+    }
+
+    /// Analyze one query file with `analyzer`, or one for its own settings
+    fn check_file(
+        &self,
+        query_file: &Path,
+        analyzer: &mut Analyzer<'a>,
+        results: &mut QueryResults,
+        profiler: &mut Profiler,
+        status: &mut RunStatus,
+    ) -> Result<()> {
+        let config = self.config;
+        let explicit = self.explicit.iter().any(|f| f == query_file);
+        let max_file_size = config.max_file_size.unwrap_or(DEFAULT_MAX_FILE_SIZE);
+        let streaming_threshold = config
+            .streaming_threshold
+            .unwrap_or(DEFAULT_STREAMING_THRESHOLD);
+
+        // A file over the size limit is only read as far as its header
+        let size = fs::metadata(query_file).into_diagnostic()?.len();
+        let size = usize::try_from(size).unwrap_or(usize::MAX);
+        let mut oversized = size > max_file_size && !explicit;
+        let mut content = if oversized {
+            read_head(query_file)?
+        } else {
+            fs::read_to_string(query_file).into_diagnostic()?
+        };
+
+        // A `-- sqlsurge:` header overrides settings for this file
+        let (header, warnings) = FileHeader::parse(&content);
+        for warning in warnings {
+            eprintln!("Warning: {}: {}", query_file.display(), warning);
+        }
+        if let Some(schema_set) = &header.schema_set {
+            eprintln!(
+                "Warning: {}: schema-set '{}' ignored: no schema sets are configured",
+                query_file.display(),
+                schema_set
+            );
+        }
+        let file_spec = header.dialect.unwrap_or(self.spec);
+        let file_config = config.for_file(query_file);
+        let mut file_analyzer;
+        let mut analyzer = match &file_config {
+            None if file_spec == self.spec => analyzer,
+            _ => {
+                file_analyzer = self.analyzer(file_spec, file_config.as_ref().unwrap_or(config));
+                &mut file_analyzer
+            }
+        };
+        let size_warning = oversized
+            .then(|| analyzer.size_limit(size, &content))
+            .flatten();
+        if oversized && size_warning.is_none() {
+            // The rule is off: the file is analyzed whatever its size
+            content = fs::read_to_string(query_file).into_diagnostic()?;
+            oversized = false;
+        }
+        let name = query_file.display().to_string();
+        if explicit {
+            if let Some(limit) = analyzer.input_limit(&content) {
+                eprintln!(
+                    "Warning: {}: {}; checking it anyway as it was named explicitly",
+                    name,
+                    limit.message.trim_end_matches(" and was not analyzed")
+                );
+                file_analyzer = self
+                    .analyzer(file_spec, file_config.as_ref().unwrap_or(config))
+                    .with_max_file_size(usize::MAX)
+                    .with_max_statements_per_file(usize::MAX);
+                analyzer = &mut file_analyzer;
+            }
+        }
+        let formatter = OutputFormatter::new(self.output_format, name.clone())
+            .with_dialect(file_spec)
+            .with_explain_hints(self.hints)
+            .with_help_uri(config.help_uri.clone());
+        // Very large files are analyzed a statement at a time, printing
+        // diagnostics as they are found where the output format allows
+        let streamed = !self.grouped
+            && matches!(
+                self.output_format,
+                OutputFormat::Human | OutputFormat::Jsonl
+            )
+            && !oversized
+            && content.len() > streaming_threshold;
+        let parse_errors = file_config.as_ref().unwrap_or(config);
+        let report = &mut results.report;
+        let mut input_too_large = false;
+        let cache_hits = analyzer.ast_cache_hits();
+        let diagnostics = if oversized {
+            size_warning.into_iter().collect()
+        } else if content.len() > streaming_threshold {
+            let mut diagnostics = Vec::new();
+            analyzer.analyze_streaming(&content, |mut diagnostic| {
+                if !parse_errors.apply_parse_error_policy(&mut diagnostic) {
+                    return;
+                }
+                if streamed {
+                    // Printed as found, so only counted
+                    formatter.print_diagnostics(std::slice::from_ref(&diagnostic), &content);
+                    input_too_large |= diagnostic.kind == DiagnosticKind::InputTooLarge;
+                    report.count(&diagnostic);
+                } else {
+                    diagnostics.push(diagnostic);
+                }
+            });
+            diagnostics
+        } else {
+            let mut diagnostics = analyzer.analyze(&content);
+            diagnostics.retain_mut(|d| parse_errors.apply_parse_error_policy(d));
+            diagnostics
+        };
+        if let Some(profile) = analyzer.profile() {
+            profiler.add_file(name.clone(), profile.clone());
+        }
+
+        if !diagnostics.is_empty() && !streamed {
+            if self.grouped {
+                results.collected.push(FileDiagnostics {
+                    file: name.clone(),
+                    source: content,
+                    diagnostics: diagnostics.clone(),
+                });
+            } else {
+                formatter.print_diagnostics(&diagnostics, &content);
+            }
+        }
+        // A file over a size limit is skipped with its one warning
+        if input_too_large
+            || diagnostics
+                .first()
+                .is_some_and(|d| d.kind == DiagnosticKind::InputTooLarge)
+        {
+            status.files.oversized += 1;
+        } else {
+            status.files.checked += 1;
+        }
+        if analyzer.ast_cache_hits() > cache_hits {
+            status.files.cached += 1;
+        }
+        results.report.push(name, diagnostics);
+        status.record(&results.report);
+        Ok(())
+    }
+}
+
+/// Analyzer for `check` with the configured rules and the given dialect,
+/// checking against `catalog` or else without a schema
+pub fn check_analyzer<'a>(
+    catalog: Option<&'a Catalog>,
+    spec: DialectSpec,
+    config: &Config,
+) -> Analyzer<'a> {
+    let analyzer = match catalog {
+        Some(catalog) => Analyzer::with_dialect(catalog, spec.dialect),
+        None => Analyzer::without_catalog(spec.dialect),
+    };
+    // `disable` is shorthand for severity "off"; explicit severities win
+    let mut analyzer = analyzer
+        .with_enabled_rules(config.enable.clone())
+        .with_disabled_rules(config.disable.clone())
+        .with_rule_severity(config.severity.clone());
+    if let Some(max_depth) = config.max_depth {
+        analyzer = analyzer.with_max_depth(max_depth);
+    }
+    if let Some(max_statement_bytes) = config.max_statement_bytes {
+        analyzer = analyzer.with_max_statement_bytes(max_statement_bytes);
+    }
+    analyzer = analyzer.with_max_file_size(config.max_file_size.unwrap_or(DEFAULT_MAX_FILE_SIZE));
+    if let Some(max_statements) = config.max_statements_per_file {
+        analyzer = analyzer.with_max_statements_per_file(max_statements);
+    }
+    if let Some(capacity) = config.ast_cache {
+        analyzer = analyzer.with_ast_cache(capacity);
+    }
+    if let Some(budget) = config.suggestion_budget {
+        analyzer = analyzer.with_suggestion_budget(budget);
+    }
+    if let Some(version) = spec.version {
+        analyzer = analyzer.with_version(version);
+    }
+    if let Some(strictness) = config.strictness {
+        analyzer = analyzer.with_strictness(strictness);
+    }
+    if let Some(policy) = config.policy {
+        analyzer = analyzer.with_policy(policy);
+    }
+    if !config.placeholders.is_empty() {
+        analyzer = analyzer.with_placeholders(config.placeholders.iter().copied());
+    }
+    if let Some(identifier) = &config.placeholder_identifier {
+        analyzer = analyzer.with_placeholder_identifier(identifier);
+    }
+    analyzer
+}
+
+/// The start of the file at `path`, without reading the rest
+fn read_head(path: &Path) -> Result<String> {
+    let mut head = Vec::new();
+    fs::File::open(path)
+        .into_diagnostic()?
+        .take(HEAD_BYTES)
+        .read_to_end(&mut head)
+        .into_diagnostic()?;
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
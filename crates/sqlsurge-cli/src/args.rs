@@ -3,6 +3,8 @@
 use std::path::PathBuf;
 
 use clap::{Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use sqlsurge_core::SeverityOrOff;
 
 #[derive(Parser)]
 #[command(name = "sqlsurge")]
@@ -24,9 +26,12 @@ pub struct Args {
 #[derive(Subcommand)]
 pub enum Command {
     /// Check SQL files against schema definitions
-    Check {
-        /// SQL files to check (supports glob patterns)
-        files: Vec<PathBuf>,
+    Check(Box<CheckArgs>),
+
+    /// Describe the output columns of the last query in a SQL file
+    Describe {
+        /// SQL file containing the query
+        file: PathBuf,
 
         /// Schema definition files
         #[arg(short, long = "schema", value_name = "FILE")]
@@ -40,21 +45,39 @@ pub enum Command {
         #[arg(short, long = "config", value_name = "FILE")]
         config: Option<PathBuf>,
 
-        /// Disable specific rules (e.g., E0001, E0002)
-        #[arg(long = "disable", value_name = "RULE")]
-        disable: Vec<String>,
-
-        /// SQL dialect
-        #[arg(short, long, default_value = "postgresql")]
-        dialect: String,
+        /// SQL dialect, optionally with a target version (e.g. mysql@5.7) [default: postgresql]
+        #[arg(short, long)]
+        dialect: Option<String>,
 
         /// Output format
-        #[arg(short, long, value_enum)]
-        format: Option<OutputFormat>,
+        #[arg(short, long, value_enum, default_value_t = DescribeFormat::Human)]
+        format: DescribeFormat,
+    },
+
+    /// List the schema tables and views each query file depends on
+    Deps {
+        /// SQL files to inspect (supports glob patterns)
+        files: Vec<PathBuf>,
+
+        /// Schema definition files
+        #[arg(short, long = "schema", value_name = "FILE")]
+        schema: Vec<PathBuf>,
+
+        /// Directory containing schema files
+        #[arg(long = "schema-dir", value_name = "DIR")]
+        schema_dir: Option<PathBuf>,
 
-        /// Maximum number of errors before stopping
-        #[arg(long, default_value = "100")]
-        max_errors: usize,
+        /// Path to configuration file (default: sqlsurge.toml in current or parent directory)
+        #[arg(short, long = "config", value_name = "FILE")]
+        config: Option<PathBuf>,
+
+        /// SQL dialect, optionally with a target version (e.g. mysql@5.7) [default: postgresql]
+        #[arg(short, long)]
+        dialect: Option<String>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = DepsFormat::Human)]
+        format: DepsFormat,
     },
 
     /// Display schema information
@@ -62,6 +85,30 @@ pub enum Command {
         /// Schema definition files
         #[arg(required = true)]
         files: Vec<PathBuf>,
+
+        /// SQL dialect
+        #[arg(short, long, default_value = "postgresql")]
+        dialect: String,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = SchemaFormat::Human)]
+        format: SchemaFormat,
+
+        /// Schema files to compare with: report the tables whose columns are
+        /// in a different order and the columns whose types differ (exits
+        /// with 1 if any)
+        #[arg(long = "compare", value_name = "FILE")]
+        compare: Vec<PathBuf>,
+    },
+
+    /// Explain a diagnostic rule: what it reports, why, and how to fix it
+    Explain {
+        /// Rule code or name (e.g. E0006, ambiguous-column); all rules if omitted
+        rule: Option<String>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = ExplainFormat::Human)]
+        format: ExplainFormat,
     },
 
     /// Parse SQL and display AST (for debugging)
@@ -71,6 +118,117 @@ pub enum Command {
     },
 }
 
+/// Options of `sqlsurge check`
+#[derive(clap::Args)]
+pub struct CheckArgs {
+    /// SQL files to check (supports glob patterns)
+    pub files: Vec<PathBuf>,
+
+    /// Schema definition files ("-" reads the schema from stdin)
+    #[arg(short, long = "schema", value_name = "FILE")]
+    pub schema: Vec<PathBuf>,
+
+    /// Directory containing schema files
+    #[arg(long = "schema-dir", value_name = "DIR")]
+    pub schema_dir: Option<PathBuf>,
+
+    /// Schema definition as a string, e.g. "CREATE TABLE users (id INT)"
+    #[arg(long = "schema-inline", value_name = "SQL")]
+    pub schema_inline: Option<String>,
+
+    /// sqlx offline metadata (a .sqlx directory or sqlx-data.json) to derive
+    /// tables from, for tables the schema files don't define
+    #[arg(long = "schema-sqlx", value_name = "DIR")]
+    pub schema_sqlx: Option<PathBuf>,
+
+    /// Path to configuration file (default: sqlsurge.toml in current or parent directory)
+    #[arg(short, long = "config", value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Disable specific rules (e.g., E0001, E0002)
+    #[arg(long = "disable", value_name = "RULE")]
+    pub disable: Vec<String>,
+
+    /// Enable opt-in rules (e.g., W0001)
+    #[arg(long = "enable", value_name = "RULE")]
+    pub enable: Vec<String>,
+
+    /// Set the severity of a rule: error, warning, info, hint or off (e.g., E0002=warning)
+    #[arg(long = "severity", value_name = "RULE=LEVEL", value_parser = parse_rule_severity)]
+    pub severity: Vec<(String, SeverityOrOff)>,
+
+    /// SQL dialect, optionally with a target version (e.g. mysql@5.7) [default: postgresql]
+    #[arg(short, long)]
+    pub dialect: Option<String>,
+
+    /// Output format
+    #[arg(short, long, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// Report missing tables (E0001) as warnings, for checking queries
+    /// against an incomplete or empty schema
+    #[arg(long)]
+    pub allow_empty_schema: bool,
+
+    /// Check without a schema: report parse errors and rules that don't
+    /// need one, skipping table, column and type checks
+    #[arg(long, conflicts_with_all = ["schema", "schema_dir", "schema_inline", "schema_sqlx", "allow_empty_schema"])]
+    pub no_schema: bool,
+
+    /// Print each distinct problem (rule, message and source line) once,
+    /// listing the files it occurs in (human output only)
+    #[arg(long)]
+    pub dedupe: bool,
+
+    /// Organize human output by file or by rule
+    #[arg(long, value_enum, default_value_t = GroupBy::File)]
+    pub group_by: GroupBy,
+
+    /// Don't end human diagnostics with a `sqlsurge explain` pointer
+    #[arg(long)]
+    pub no_hints: bool,
+
+    /// What to do with warnings about the schema files [default: show]
+    #[arg(long, value_enum)]
+    pub schema_warnings: Option<SchemaWarnings>,
+
+    /// Skip query files larger than this many bytes, with a warning
+    /// (files named explicitly are still checked) [default: 67108864]
+    #[arg(long, value_name = "BYTES")]
+    pub max_file_size: Option<usize>,
+
+    /// Skip query files with more statements than this, with a warning
+    /// (files named explicitly are still checked)
+    #[arg(long, value_name = "COUNT")]
+    pub max_statements_per_file: Option<usize>,
+
+    /// Maximum number of errors before stopping
+    #[arg(long, default_value = "100")]
+    pub max_errors: usize,
+
+    /// Print time spent per analysis phase and the slowest files
+    #[arg(long)]
+    pub profile: bool,
+
+    /// Write the profile summary as JSON to a file (implies --profile)
+    #[arg(long, value_name = "FILE")]
+    pub profile_json: Option<PathBuf>,
+
+    /// Only check query files changed since the merge base with --base
+    /// (everything if a schema file changed)
+    #[arg(long)]
+    pub changed_only: bool,
+
+    /// Git ref to compare against for --changed-only [default: origin/main]
+    #[arg(long, value_name = "REF", requires = "changed_only")]
+    pub base: Option<String>,
+
+    /// Write a JSON summary of the run (counts, files, timing) to a file,
+    /// even if the run fails
+    #[arg(long, value_name = "FILE")]
+    pub status_file: Option<PathBuf>,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Default)]
 pub enum OutputFormat {
     /// Human-readable output with colors
@@ -78,6 +236,78 @@ pub enum OutputFormat {
     Human,
     /// JSON output
     Json,
+    /// JSON Lines output (one object per diagnostic, then a summary)
+    Jsonl,
     /// SARIF output (for GitHub Code Scanning)
     Sarif,
 }
+
+/// Handling of the diagnostics of the schema files (`--schema-warnings`)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaWarnings {
+    /// Print them, without failing the run
+    #[default]
+    Show,
+    /// Only count them in the summary
+    Hide,
+    /// Print them as errors, failing the run
+    Error,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Default)]
+pub enum GroupBy {
+    /// Diagnostics in file order
+    #[default]
+    File,
+    /// Diagnostics grouped under their rule code
+    Rule,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Default)]
+pub enum SchemaFormat {
+    /// Human-readable summary
+    #[default]
+    Human,
+    /// Full catalog as JSON
+    Json,
+    /// Markdown tables (for schema documentation)
+    Markdown,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Default)]
+pub enum ExplainFormat {
+    /// Plain text for the terminal
+    #[default]
+    Human,
+    /// Markdown (for documentation)
+    Markdown,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Default)]
+pub enum DescribeFormat {
+    /// Human-readable table
+    #[default]
+    Human,
+    /// JSON (for code generators)
+    Json,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Default)]
+pub enum DepsFormat {
+    /// Tables and views listed under each file
+    #[default]
+    Human,
+    /// JSON (for build systems)
+    Json,
+    /// Make rules from each query file to the schema files defining its dependencies
+    Makefile,
+}
+
+/// Parse a `RULE=LEVEL` severity override
+fn parse_rule_severity(s: &str) -> Result<(String, SeverityOrOff), String> {
+    let (rule, level) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Expected RULE=LEVEL (e.g. E0002=warning), got '{}'", s))?;
+    Ok((rule.trim().to_string(), level.trim().parse()?))
+}
@@ -0,0 +1,81 @@
+//! Files changed relative to a git base (`check --changed-only`)
+//!
+//! `git diff --name-only --merge-base <base>` lists the files changed since
+//! the branch point, including uncommitted changes. Paths are resolved
+//! against the repository root and canonicalized, so they can be compared
+//! with query and schema files given relative to the working directory.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Base ref when neither `--base` nor `changed_base` is set
+pub const DEFAULT_BASE: &str = "origin/main";
+
+/// Files changed since the merge base of `base` and HEAD
+pub struct ChangedFiles {
+    /// Changed paths, absolute; deleted files are included
+    paths: Vec<PathBuf>,
+}
+
+impl ChangedFiles {
+    /// Ask git for the changed files
+    ///
+    /// Fails with a message for the user when git is missing, the working
+    /// directory isn't in a repository, or the base ref doesn't exist.
+    pub fn from_git(base: &str) -> Result<Self, String> {
+        let root = git(&["rev-parse", "--show-toplevel"])?;
+        let root = PathBuf::from(root.trim());
+        let root = absolute(&root).unwrap_or(root);
+        let diff = git(&["diff", "--name-only", "-z", "--merge-base", base, "--"])?;
+        let paths = diff
+            .split('\0')
+            .filter(|name| !name.is_empty())
+            .map(|name| root.join(name))
+            .collect();
+        Ok(Self { paths })
+    }
+
+    /// Whether `file` (relative to the working directory) changed
+    pub fn contains(&self, file: &Path) -> bool {
+        absolute(file).is_some_and(|file| self.paths.contains(&file))
+    }
+
+    /// Whether `path`, or a file under it if it is a directory, changed
+    pub fn touches(&self, path: &Path) -> bool {
+        absolute(path).is_some_and(|path| self.paths.iter().any(|p| p.starts_with(&path)))
+    }
+
+    /// Whether a schema file, or a `.sql` file under the schema directory,
+    /// changed (including deleted ones)
+    pub fn touches_schema(&self, schema_files: &[PathBuf], schema_dir: Option<&Path>) -> bool {
+        let schema_dir = schema_dir.and_then(absolute);
+        schema_files.iter().any(|file| self.contains(file))
+            || schema_dir.is_some_and(|dir| {
+                self.paths
+                    .iter()
+                    .any(|p| p.starts_with(&dir) && p.extension().is_some_and(|e| e == "sql"))
+            })
+    }
+}
+
+/// Canonical path of an existing file or directory
+fn absolute(path: &Path) -> Option<PathBuf> {
+    path.canonicalize().ok()
+}
+
+/// Run git, returning its stdout
+fn git(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| format!("could not run git: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            stderr.lines().next().unwrap_or_default()
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|e| format!("git output is not UTF-8: {}", e))
+}
@@ -2,7 +2,23 @@
 
 use miette::{IntoDiagnostic, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use sqlsurge_core::{
+    Diagnostic, DiagnosticKind, DiagnosticOrigin, PlaceholderStyle, Severity, SeverityOrOff,
+    Strictness, WritePolicy,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::args::SchemaWarnings;
+
+/// Size in bytes above which query files are analyzed one statement at a time
+pub const DEFAULT_STREAMING_THRESHOLD: usize = 8 * 1024 * 1024;
+
+/// Default of `max_file_size`, in bytes
+///
+/// Above [`DEFAULT_STREAMING_THRESHOLD`], so files between the two are
+/// streamed rather than skipped.
+pub const DEFAULT_MAX_FILE_SIZE: usize = 64 * 1024 * 1024;
 
 /// Configuration for sqlsurge
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -15,11 +31,11 @@ pub struct Config {
     #[serde(default)]
     pub files: Vec<String>,
 
-    /// SQL dialect (currently only "postgresql" is supported)
+    /// SQL dialect, optionally with a target version (e.g. "mysql@5.7")
     #[serde(default)]
     pub dialect: Option<String>,
 
-    /// Output format (human, json, sarif)
+    /// Output format (human, json, jsonl, sarif)
     #[serde(default)]
     pub format: Option<String>,
 
@@ -27,16 +43,371 @@ pub struct Config {
     #[serde(default)]
     pub disable: Vec<String>,
 
+    /// Opt-in rules to enable (e.g., ["W0001"])
+    #[serde(default)]
+    pub enable: Vec<String>,
+
+    /// Per-rule severity: "error", "warning", "info", "hint" or "off"
+    /// (e.g., { E0002 = "warning" })
+    #[serde(default)]
+    pub severity: HashMap<String, SeverityOrOff>,
+
     /// Schema directory
     pub schema_dir: Option<String>,
+
+    /// Schema definition as a string, after the schema files, for
+    /// self-contained fixtures (`schema_inline = """CREATE TABLE ..."""`)
+    #[serde(default)]
+    pub schema_inline: Option<String>,
+
+    /// sqlx offline metadata (`.sqlx` directory or `sqlx-data.json`) to
+    /// derive tables from
+    #[serde(default)]
+    pub schema_sqlx: Option<String>,
+
+    /// MySQL: treat TINYINT(1) columns as BOOLEAN (default: true)
+    #[serde(default)]
+    pub tinyint1_as_boolean: Option<bool>,
+
+    /// Treat duplicate table/view/enum definitions across schema files as errors
+    /// (default: false)
+    #[serde(default)]
+    pub strict_duplicates: Option<bool>,
+
+    /// Maximum nesting depth of expressions and subqueries (default: 50)
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+
+    /// Statements larger than this many bytes are skipped with a warning
+    /// (default: 262144)
+    #[serde(default)]
+    pub max_statement_bytes: Option<usize>,
+
+    /// Query files larger than this many bytes are skipped with a warning,
+    /// unless named on the command line (default: 67108864)
+    #[serde(default)]
+    pub max_file_size: Option<usize>,
+
+    /// Query files with more statements than this are skipped with a
+    /// warning, unless named on the command line (default: no limit)
+    #[serde(default)]
+    pub max_statements_per_file: Option<usize>,
+
+    /// Keep up to this many parsed statements, reused when a later query
+    /// file repeats a statement at the same position (default: off)
+    #[serde(default)]
+    pub ast_cache: Option<usize>,
+
+    /// Query files larger than this many bytes are analyzed one statement
+    /// at a time, printing diagnostics as they are found (default: 8388608)
+    #[serde(default)]
+    pub streaming_threshold: Option<usize>,
+
+    /// Column names compared per "did you mean" lookup (default: 1000)
+    #[serde(default)]
+    pub suggestion_budget: Option<usize>,
+
+    /// Schema file warnings: "show" (default), "hide" or "error"
+    #[serde(default)]
+    pub schema_warnings: Option<SchemaWarnings>,
+
+    /// Schema statements that fail to parse: "skip" (default), "warn" or "error"
+    #[serde(default)]
+    pub schema_parse_errors: Option<SchemaParseErrors>,
+
+    /// Query statements that fail to parse: "error" (default) or "warn"
+    #[serde(default)]
+    pub query_parse_errors: Option<QueryParseErrors>,
+
+    /// SARIF `helpUri` of each rule, with `{code}` and `{name}` replaced
+    /// (default: the rule table in the README)
+    #[serde(default)]
+    pub help_uri: Option<String>,
+
+    /// Base config file to extend, relative to this file
+    #[serde(default)]
+    pub extends: Option<String>,
+
+    /// "pedantic" enables pedantic rules such as W0008 implicit-cast
+    #[serde(default)]
+    pub strictness: Option<Strictness>,
+
+    /// Statements query files may contain: "any" (default), "read-only"
+    /// or "ddl-forbidden"; usually set per path in `[[overrides]]`
+    #[serde(default)]
+    pub policy: Option<WritePolicy>,
+
+    /// Template placeholder syntaxes in query files: "psql" (`:var`,
+    /// `:'var'`) and/or "flyway" (`${var}`)
+    #[serde(default)]
+    pub placeholders: Vec<PlaceholderStyle>,
+
+    /// Dummy identifier for placeholders in name positions (default: "placeholder")
+    #[serde(default)]
+    pub placeholder_identifier: Option<String>,
+
+    /// Deprecated tables and columns with a migration note
+    /// (`[deprecated]` section: `"users.legacy_flags" = "use feature_flags"`)
+    #[serde(default)]
+    pub deprecated: HashMap<String, String>,
+
+    /// Signatures of functions the schema files don't define (`[functions]`
+    /// section: `my_score = { args = ["integer", "text"], returns = "numeric" }`)
+    #[serde(default)]
+    pub functions: HashMap<String, FunctionConfig>,
+
+    /// Git ref `check --changed-only` compares against (default: origin/main)
+    #[serde(default)]
+    pub changed_base: Option<String>,
+
+    /// Settings for query files matching a pattern (`[[overrides]]`);
+    /// the first matching entry applies
+    #[serde(default)]
+    pub overrides: Vec<Override>,
+}
+
+/// Handling of schema statements that fail to parse (`schema_parse_errors`)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaParseErrors {
+    /// Skip them silently, using the rest of the schema
+    #[default]
+    Skip,
+    /// Skip them with a parse error (E1000) warning, subject to `schema_warnings`
+    Warn,
+    /// Report them as errors, failing the run
+    Error,
+}
+
+/// Handling of query statements that fail to parse (`query_parse_errors`)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryParseErrors {
+    /// Report them as warnings, which don't fail the run
+    Warn,
+    /// Report them as errors, failing the run
+    #[default]
+    Error,
+}
+
+/// A function signature declared in config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct FunctionConfig {
+    /// Argument type names, in order
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// The last argument takes one or more values
+    #[serde(default)]
+    pub variadic: bool,
+
+    /// Return type name (unknown if omitted)
+    #[serde(default)]
+    pub returns: Option<String>,
+}
+
+/// Settings applied to query files matching `files`
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Override {
+    /// Query file glob patterns (e.g. ["legacy/**/*.sql"])
+    pub files: Vec<String>,
+
+    /// Strictness for the matching files
+    #[serde(default)]
+    pub strictness: Option<Strictness>,
+
+    /// Write policy for the matching files
+    #[serde(default)]
+    pub policy: Option<WritePolicy>,
+
+    /// Per-rule severity for the matching files, over the top-level `severity`
+    #[serde(default)]
+    pub severity: HashMap<String, SeverityOrOff>,
 }
 
 impl Config {
+    /// Apply `schema_parse_errors` or `query_parse_errors` to a parse error,
+    /// by the kind of input it was found in; returns whether it is reported
+    pub fn apply_parse_error_policy(&self, diagnostic: &mut Diagnostic) -> bool {
+        if diagnostic.kind != DiagnosticKind::ParseError {
+            return true;
+        }
+        match diagnostic.origin {
+            Some(DiagnosticOrigin::Schema) => match self.schema_parse_errors.unwrap_or_default() {
+                SchemaParseErrors::Skip => false,
+                SchemaParseErrors::Warn => true,
+                SchemaParseErrors::Error => {
+                    diagnostic.severity = Severity::Error;
+                    true
+                }
+            },
+            // An explicit `severity` for E1000 is applied by the analyzer first
+            Some(DiagnosticOrigin::Query) => {
+                if self.query_parse_errors.unwrap_or_default() == QueryParseErrors::Warn
+                    && diagnostic.severity == Severity::Error
+                {
+                    diagnostic.severity = Severity::Warning;
+                }
+                true
+            }
+            None => true,
+        }
+    }
+
     /// Load configuration from a TOML file
-    pub fn from_file(path: &PathBuf) -> Result<Self> {
+    ///
+    /// `${VAR}` references in paths are expanded from the environment, and a
+    /// base config named by `extends` is loaded and merged underneath.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        Self::load(path, &mut Vec::new())
+    }
+
+    /// Load a config file; `chain` holds the files extending it, for cycle detection
+    fn load(path: &Path, chain: &mut Vec<PathBuf>) -> Result<Self> {
+        let canonical = path.canonicalize().into_diagnostic()?;
+        if chain.contains(&canonical) {
+            return Err(miette::miette!(
+                "Config file {} extends itself (via {})",
+                path.display(),
+                chain
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            ));
+        }
+
         let contents = std::fs::read_to_string(path).into_diagnostic()?;
-        let config: Config = toml::from_str(&contents).into_diagnostic()?;
-        Ok(config)
+        let mut config: Config = toml::from_str(&contents).into_diagnostic()?;
+        config
+            .expand_env_vars()
+            .map_err(|e| miette::miette!("{} (in {})", e, path.display()))?;
+
+        match config.extends.take() {
+            Some(base) => {
+                let base_path = path.parent().unwrap_or(Path::new(".")).join(&base);
+                chain.push(canonical);
+                let mut base_config = Self::load(&base_path, chain)?;
+                chain.pop();
+                if let Some(base_dir) = Path::new(&base).parent() {
+                    base_config.rebase_paths(base_dir);
+                }
+                Ok(base_config.extend_with(config))
+            }
+            None => Ok(config),
+        }
+    }
+
+    /// Expand `${VAR}` in path values (schema, schema_dir, schema_sqlx,
+    /// files, extends, override files)
+    fn expand_env_vars(&mut self) -> std::result::Result<(), String> {
+        let override_files = self.overrides.iter_mut().flat_map(|o| o.files.iter_mut());
+        for value in self
+            .schema
+            .iter_mut()
+            .chain(self.files.iter_mut())
+            .chain(override_files)
+        {
+            *value = expand_env(value)?;
+        }
+        for value in self
+            .schema_dir
+            .iter_mut()
+            .chain(self.schema_sqlx.iter_mut())
+            .chain(self.extends.iter_mut())
+        {
+            *value = expand_env(value)?;
+        }
+        Ok(())
+    }
+
+    /// Make the relative paths of an extended config (schema, schema_dir,
+    /// schema_sqlx, files) relative to the config extending it, which names
+    /// it at `dir`
+    fn rebase_paths(&mut self, dir: &Path) {
+        if dir.as_os_str().is_empty() {
+            return;
+        }
+        for value in self
+            .schema
+            .iter_mut()
+            .chain(self.files.iter_mut())
+            .chain(self.schema_dir.iter_mut())
+            .chain(self.schema_sqlx.iter_mut())
+        {
+            if Path::new(value.as_str()).is_relative() {
+                *value = dir.join(value.as_str()).display().to_string();
+            }
+        }
+    }
+
+    /// Overlay `local` on this (base) config: local values win, lists are
+    /// concatenated without duplicates
+    fn extend_with(mut self, local: Config) -> Self {
+        fn concat(base: &mut Vec<String>, local: Vec<String>) {
+            for value in local {
+                if !base.contains(&value) {
+                    base.push(value);
+                }
+            }
+        }
+
+        concat(&mut self.schema, local.schema);
+        concat(&mut self.files, local.files);
+        concat(&mut self.disable, local.disable);
+        concat(&mut self.enable, local.enable);
+        self.severity.extend(local.severity);
+        self.deprecated.extend(local.deprecated);
+        self.functions.extend(local.functions);
+        self.dialect = local.dialect.or(self.dialect);
+        self.format = local.format.or(self.format);
+        self.schema_dir = local.schema_dir.or(self.schema_dir);
+        self.schema_inline = local.schema_inline.or(self.schema_inline);
+        self.schema_sqlx = local.schema_sqlx.or(self.schema_sqlx);
+        self.tinyint1_as_boolean = local.tinyint1_as_boolean.or(self.tinyint1_as_boolean);
+        self.strict_duplicates = local.strict_duplicates.or(self.strict_duplicates);
+        self.max_depth = local.max_depth.or(self.max_depth);
+        self.max_statement_bytes = local.max_statement_bytes.or(self.max_statement_bytes);
+        self.max_file_size = local.max_file_size.or(self.max_file_size);
+        self.max_statements_per_file = local
+            .max_statements_per_file
+            .or(self.max_statements_per_file);
+        self.ast_cache = local.ast_cache.or(self.ast_cache);
+        self.suggestion_budget = local.suggestion_budget.or(self.suggestion_budget);
+        self.streaming_threshold = local.streaming_threshold.or(self.streaming_threshold);
+        self.schema_warnings = local.schema_warnings.or(self.schema_warnings);
+        self.schema_parse_errors = local.schema_parse_errors.or(self.schema_parse_errors);
+        self.query_parse_errors = local.query_parse_errors.or(self.query_parse_errors);
+        self.help_uri = local.help_uri.or(self.help_uri);
+        self.strictness = local.strictness.or(self.strictness);
+        self.changed_base = local.changed_base.or(self.changed_base);
+        self.policy = local.policy.or(self.policy);
+        for style in local.placeholders {
+            if !self.placeholders.contains(&style) {
+                self.placeholders.push(style);
+            }
+        }
+        self.placeholder_identifier = local.placeholder_identifier.or(self.placeholder_identifier);
+        // Local overrides are tried first
+        let base_overrides = std::mem::replace(&mut self.overrides, local.overrides);
+        self.overrides.extend(base_overrides);
+        self
+    }
+
+    /// Settings for one query file, if an `[[overrides]]` entry matches it
+    ///
+    /// Returns `None` when the top-level settings apply unchanged.
+    pub fn for_file(&self, path: &Path) -> Option<Config> {
+        let entry = self.overrides.iter().find(|o| {
+            o.files
+                .iter()
+                .any(|pattern| glob::Pattern::new(pattern).is_ok_and(|p| p.matches_path(path)))
+        })?;
+        let mut config = self.clone();
+        config.strictness = entry.strictness.or(config.strictness);
+        config.policy = entry.policy.or(config.policy);
+        config.severity.extend(entry.severity.clone());
+        Some(config)
     }
 
     /// Try to find and load sqlsurge.toml in current directory or parent directories
@@ -67,6 +438,7 @@ impl Config {
         files: &[PathBuf],
         format: &Option<crate::args::OutputFormat>,
         disable: &[String],
+        enable: &[String],
     ) -> Self {
         // CLI args override config file
         if !schema.is_empty() {
@@ -89,6 +461,203 @@ impl Config {
             self.disable = disable.to_vec();
         }
 
+        if !enable.is_empty() {
+            self.enable = enable.to_vec();
+        }
+
         self
     }
+
+    /// Merge CLI severity overrides into the config, per rule
+    pub fn merge_severity(mut self, severity: Vec<(String, SeverityOrOff)>) -> Self {
+        self.severity.extend(severity);
+        self
+    }
+}
+
+/// Expand `${VAR}` references from the environment; unset variables are an error
+fn expand_env(value: &str) -> std::result::Result<String, String> {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| format!("Unterminated '${{' in config value '{}'", value))?;
+        let name = &after[..end];
+        let var = std::env::var(name).map_err(|_| {
+            format!(
+                "Environment variable '{}' used in config value '{}' is not set",
+                name, value
+            )
+        })?;
+        expanded.push_str(&var);
+        rest = &after[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write `files` into a fresh temporary directory named after the test
+    fn config_dir(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sqlsurge-config-{}", test));
+        let _ = std::fs::remove_dir_all(&dir);
+        for (name, contents) in files {
+            let path = dir.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_expand_env() {
+        std::env::set_var("SQLSURGE_TEST_SCHEMA_DIR", "/ci/schema");
+        assert_eq!(
+            expand_env("${SQLSURGE_TEST_SCHEMA_DIR}/*.sql").unwrap(),
+            "/ci/schema/*.sql"
+        );
+        assert_eq!(expand_env("plain/path.sql").unwrap(), "plain/path.sql");
+
+        let err = expand_env("${SQLSURGE_TEST_UNSET_VAR}/x").unwrap_err();
+        assert!(err.contains("'SQLSURGE_TEST_UNSET_VAR'"), "{}", err);
+        assert!(expand_env("${SQLSURGE_TEST_SCHEMA_DIR").is_err());
+    }
+
+    #[test]
+    fn test_default_limits_stream_before_skipping() {
+        // Files between the two are streamed; larger ones are skipped
+        let (threshold, max_file_size) = (DEFAULT_STREAMING_THRESHOLD, DEFAULT_MAX_FILE_SIZE);
+        assert!(threshold < max_file_size);
+    }
+
+    #[test]
+    fn test_extends_merge_precedence() {
+        std::env::set_var("SQLSURGE_TEST_SHARED", "schema");
+        let dir = config_dir(
+            "extends",
+            &[
+                (
+                    "shared/base.toml",
+                    r#"
+                        schema = ["${SQLSURGE_TEST_SHARED}.sql", "/abs/schema.sql"]
+                        schema_dir = "migrations"
+                        strict_duplicates = true
+                        disable = ["E0001", "E0002"]
+                        dialect = "mysql"
+                        format = "json"
+                        max_depth = 64
+                        max_statement_bytes = 1048576
+                        max_file_size = 10485760
+                        max_statements_per_file = 50000
+                        streaming_threshold = 65536
+
+                        [functions]
+                        my_score = { args = ["integer", "text"], returns = "numeric" }
+                        greatest_of = { args = ["integer"], variadic = true, returns = "integer" }
+                    "#,
+                ),
+                (
+                    "service/sqlsurge.toml",
+                    r#"
+                        extends = "../shared/base.toml"
+                        schema = ["local.sql", "../shared/schema.sql"]
+                        strict_duplicates = false
+                        disable = ["E0002", "E0003"]
+                        dialect = "postgresql"
+
+                        [functions]
+                        my_score = { args = ["bigint"], returns = "numeric" }
+                    "#,
+                ),
+            ],
+        );
+
+        let config = Config::from_file(&dir.join("service/sqlsurge.toml")).unwrap();
+        // Local config over the extended one; lists concatenate without
+        // duplicates, and relative paths of the extended one are from its directory
+        assert_eq!(
+            config.schema,
+            vec!["../shared/schema.sql", "/abs/schema.sql", "local.sql"]
+        );
+        assert_eq!(config.schema_dir.as_deref(), Some("../shared/migrations"));
+        assert_eq!(config.strict_duplicates, Some(false));
+        assert_eq!(config.disable, vec!["E0001", "E0002", "E0003"]);
+        assert_eq!(config.dialect.as_deref(), Some("postgresql"));
+        assert_eq!(config.format.as_deref(), Some("json"));
+        assert_eq!(config.max_depth, Some(64));
+        assert_eq!(config.max_statement_bytes, Some(1048576));
+        assert_eq!(config.max_file_size, Some(10485760));
+        assert_eq!(config.max_statements_per_file, Some(50000));
+        assert_eq!(config.streaming_threshold, Some(65536));
+        // Local function signatures replace the extended ones by name
+        assert_eq!(config.functions.len(), 2);
+        assert_eq!(config.functions["my_score"].args, vec!["bigint"]);
+        assert!(config.functions["greatest_of"].variadic);
+
+        // CLI arguments over both
+        let config = config.merge_with_args(
+            &[PathBuf::from("cli.sql")],
+            &None,
+            &[],
+            &Some(crate::args::OutputFormat::Sarif),
+            &[],
+            &[],
+        );
+        assert_eq!(config.schema, vec!["cli.sql"]);
+        assert_eq!(config.format.as_deref(), Some("sarif"));
+        assert_eq!(config.disable, vec!["E0001", "E0002", "E0003"]);
+    }
+
+    #[test]
+    fn test_overrides() {
+        let config: Config = toml::from_str(
+            r#"
+                strictness = "pedantic"
+                severity = { W0008 = "warning" }
+
+                [[overrides]]
+                files = ["legacy/**/*.sql"]
+                strictness = "default"
+
+                [[overrides]]
+                files = ["reports/*.sql", "legacy/**/*.sql"]
+                severity = { W0008 = "error" }
+                policy = "read-only"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.strictness, Some(Strictness::Pedantic));
+        assert!(config.for_file(Path::new("queries/a.sql")).is_none());
+
+        // The first matching entry applies
+        let legacy = config.for_file(Path::new("legacy/old/a.sql")).unwrap();
+        assert_eq!(legacy.strictness, Some(Strictness::Default));
+        assert_eq!(legacy.severity["W0008"], SeverityOrOff::Warning);
+
+        let reports = config.for_file(Path::new("reports/a.sql")).unwrap();
+        assert_eq!(reports.strictness, Some(Strictness::Pedantic));
+        assert_eq!(reports.severity["W0008"], SeverityOrOff::Error);
+        assert_eq!(reports.policy, Some(WritePolicy::ReadOnly));
+        assert_eq!(legacy.policy, None);
+    }
+
+    #[test]
+    fn test_extends_cycle() {
+        let dir = config_dir(
+            "extends-cycle",
+            &[
+                ("a.toml", r#"extends = "b.toml""#),
+                ("b.toml", r#"extends = "a.toml""#),
+            ],
+        );
+
+        let err = Config::from_file(&dir.join("a.toml")).unwrap_err();
+        assert!(err.to_string().contains("extends itself"), "{}", err);
+    }
 }
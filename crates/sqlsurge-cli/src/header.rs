@@ -0,0 +1,102 @@
+//! Per-file settings from a `-- sqlsurge:` comment header
+//!
+//! A query file may start with comment lines such as
+//!
+//! ```sql
+//! -- sqlsurge: dialect=mysql@5.7 schema-set=warehouse
+//! ```
+//!
+//! which override the configured settings for that file only. The header
+//! ends at the first line that is neither blank nor a `--` comment.
+
+use sqlsurge_core::dialect::DialectSpec;
+
+/// Settings read from a file header
+#[derive(Debug, Default, PartialEq)]
+pub struct FileHeader {
+    /// `dialect=...`: dialect (and version) to analyze the file with
+    pub dialect: Option<DialectSpec>,
+    /// `schema-set=...`: named schema catalog to analyze the file against
+    pub schema_set: Option<String>,
+}
+
+impl FileHeader {
+    /// Parse the header of `content`, returning it with warnings about
+    /// unknown keys and invalid values
+    pub fn parse(content: &str) -> (Self, Vec<String>) {
+        let mut header = Self::default();
+        let mut warnings = Vec::new();
+
+        let comments = content
+            .lines()
+            .map(str::trim)
+            .take_while(|line| line.is_empty() || line.starts_with("--"))
+            .filter_map(|line| line.strip_prefix("--"));
+        for comment in comments {
+            let Some(settings) = comment.trim_start().strip_prefix("sqlsurge:") else {
+                continue;
+            };
+            for setting in settings.split_whitespace() {
+                let Some((key, value)) = setting.split_once('=') else {
+                    warnings.push(format!(
+                        "ignoring '{}' in sqlsurge header: expected key=value",
+                        setting
+                    ));
+                    continue;
+                };
+                match key {
+                    "dialect" => match value.parse() {
+                        Ok(spec) => header.dialect = Some(spec),
+                        Err(e) => warnings.push(format!("ignoring sqlsurge header dialect: {}", e)),
+                    },
+                    "schema-set" => header.schema_set = Some(value.to_string()),
+                    _ => warnings.push(format!(
+                        "unknown key '{}' in sqlsurge header (expected dialect or schema-set)",
+                        key
+                    )),
+                }
+            }
+        }
+
+        (header, warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlsurge_core::dialect::DialectVersion;
+    use sqlsurge_core::SqlDialect;
+
+    #[test]
+    fn test_parse_header() {
+        let content =
+            "\n-- Monthly report\n-- sqlsurge: dialect=mysql@5.7 schema-set=warehouse\nSELECT 1;";
+        let (header, warnings) = FileHeader::parse(content);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(
+            header.dialect,
+            Some(DialectSpec {
+                dialect: SqlDialect::MySQL,
+                version: Some(DialectVersion::new(5, 7)),
+            })
+        );
+        assert_eq!(header.schema_set.as_deref(), Some("warehouse"));
+    }
+
+    #[test]
+    fn test_header_ends_at_first_statement() {
+        let (header, warnings) = FileHeader::parse("SELECT 1;\n-- sqlsurge: dialect=mysql\n");
+        assert_eq!(header, FileHeader::default());
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_header_warnings() {
+        let (header, warnings) =
+            FileHeader::parse("-- sqlsurge: dialect=oracle strict=yes mysql\nSELECT 1;");
+        assert_eq!(header.dialect, None);
+        assert_eq!(warnings.len(), 3, "{:?}", warnings);
+        assert!(warnings[1].contains("unknown key 'strict'"));
+    }
+}
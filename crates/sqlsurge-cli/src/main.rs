@@ -1,20 +1,43 @@
 //! sqlsurge CLI - SQL static analysis tool
 
+mod analyze;
 mod args;
+mod changed;
 mod config;
+mod header;
 mod output;
+mod schema;
+mod schema_source;
+mod status;
 
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::time::Instant;
 
 use clap::Parser;
 use miette::{IntoDiagnostic, Result};
-use sqlsurge_core::schema::SchemaBuilder;
-use sqlsurge_core::{Analyzer, SqlDialect};
-
-use crate::args::{Args, Command, OutputFormat};
+use sqlsurge_core::dialect::DialectSpec;
+use sqlsurge_core::rules;
+use sqlsurge_core::schema::{
+    column_order_differences, column_type_differences, Catalog, ColumnOrderDifference,
+    ColumnTypeDifference, ForeignKeyDef, QualifiedName, ReferentialAction, SchemaBuilder,
+};
+use sqlsurge_core::{
+    AnalysisReport, Analyzer, DiagnosticKind, OutputColumn, SeverityOrOff, SqlDialect,
+};
+
+use crate::analyze::{QueryCheck, QueryResults};
+use crate::args::{
+    Args, CheckArgs, Command, DescribeFormat, ExplainFormat, GroupBy, OutputFormat, SchemaFormat,
+    SchemaWarnings,
+};
+use crate::changed::ChangedFiles;
 use crate::config::Config;
-use crate::output::OutputFormatter;
+use crate::output::{FileDependencies, OutputFormatter, Profiler};
+use crate::schema::SchemaLoad;
+use crate::schema_source::SchemaSource;
+use crate::status::RunStatus;
 
 fn main() -> ExitCode {
     // Initialize tracing
@@ -43,177 +66,191 @@ fn main() -> ExitCode {
 }
 
 fn run(args: Args) -> Result<bool> {
+    let verbose = args.verbose;
     match args.command {
-        Command::Check {
-            files,
+        Command::Check(check_args) => {
+            let started = Instant::now();
+            let status_file = check_args.status_file.clone();
+            let mut status = RunStatus::new();
+            let result = check(*check_args, verbose, &mut status);
+            if let Some(path) = status_file {
+                status.finish(&result, started.elapsed());
+                status.write(&path).into_diagnostic()?;
+            }
+            result
+        }
+
+        Command::Describe {
+            file,
             schema,
             schema_dir,
             config: config_path,
-            disable,
             dialect,
             format,
-            ..
         } => {
-            // Parse and validate dialect
-            let dialect: SqlDialect = dialect.parse().map_err(|e: String| miette::miette!(e))?;
-            // Load configuration
-            let config = if let Some(path) = config_path {
-                // Load from specified path
-                Config::from_file(&path)?
-            } else {
-                // Try to find sqlsurge.toml
-                Config::find_and_load()?.unwrap_or_default()
+            let config = load_config(config_path)?.merge_with_args(
+                &schema,
+                &schema_dir,
+                &[],
+                &None,
+                &[],
+                &[],
+            );
+            let spec = dialect_spec(dialect.as_deref(), &config)?;
+            let Some(catalog) = schema::load_catalog(&config, spec)? else {
+                return Ok(true);
             };
 
-            // Merge CLI args with config (CLI takes precedence)
-            let config = config.merge_with_args(&schema, &schema_dir, &files, &format, &disable);
-
-            // Get schema files from config or CLI
-            let mut schema_files: Vec<std::path::PathBuf> =
-                config.schema.iter().map(std::path::PathBuf::from).collect();
-
-            if let Some(dir) = &config.schema_dir {
-                let pattern = format!("{}/**/*.sql", dir);
-                for path in glob::glob(&pattern).into_diagnostic()?.flatten() {
-                    schema_files.push(path);
-                }
-            }
-
-            if schema_files.is_empty() {
-                miette::bail!("No schema files specified. Use --schema, --schema-dir, or configure in sqlsurge.toml");
+            let content = fs::read_to_string(&file).into_diagnostic()?;
+            let mut analyzer = Analyzer::with_dialect(&catalog, spec.dialect);
+            if let Some(version) = spec.version {
+                analyzer = analyzer.with_version(version);
             }
-
-            // Determine output format
-            let output_format = if let Some(fmt_str) = &config.format {
-                match fmt_str.as_str() {
-                    "json" => OutputFormat::Json,
-                    "sarif" => OutputFormat::Sarif,
-                    _ => OutputFormat::Human,
+            match analyzer.infer_output_schema(&content) {
+                Ok(columns) => {
+                    match format {
+                        DescribeFormat::Human => print_columns_human(&columns),
+                        DescribeFormat::Json => {
+                            output::print_describe_json(&file.display().to_string(), &columns)
+                        }
+                    }
+                    Ok(false)
                 }
-            } else {
-                OutputFormat::Human
-            };
-
-            // Build schema catalog
-            let mut builder = SchemaBuilder::with_dialect(dialect);
-            for schema_file in &schema_files {
-                let content = fs::read_to_string(schema_file).into_diagnostic()?;
-                if let Err(diags) = builder.parse(&content) {
-                    let formatter =
-                        OutputFormatter::new(output_format, schema_file.display().to_string());
-                    formatter.print_diagnostics(&diags, &content);
-                    return Ok(true);
+                Err(diags) => {
+                    OutputFormatter::new(OutputFormat::Human, file.display().to_string())
+                        .print_diagnostics(&diags, &content);
+                    Ok(true)
                 }
             }
-            let (catalog, schema_diags) = builder.build();
-
-            if !schema_diags.is_empty() {
-                eprintln!(
-                    "Warning: Schema parsing produced {} warnings",
-                    schema_diags.len()
-                );
-            }
+        }
 
-            // Collect query files from config or CLI
-            let mut query_files = Vec::new();
-            let file_patterns: Vec<std::path::PathBuf> = if !config.files.is_empty() {
-                config.files.iter().map(std::path::PathBuf::from).collect()
-            } else {
-                vec![]
+        Command::Deps {
+            files,
+            schema,
+            schema_dir,
+            config: config_path,
+            dialect,
+            format,
+        } => {
+            let config = load_config(config_path)?.merge_with_args(
+                &schema,
+                &schema_dir,
+                &files,
+                &None,
+                &[],
+                &[],
+            );
+            let spec = dialect_spec(dialect.as_deref(), &config)?;
+            let Some(catalog) = schema::load_catalog(&config, spec)? else {
+                return Ok(true);
             };
 
-            for pattern in &file_patterns {
-                let pattern_str = pattern.display().to_string();
-                if pattern_str.contains('*') {
-                    for path in glob::glob(&pattern_str).into_diagnostic()?.flatten() {
-                        query_files.push(path);
-                    }
-                } else {
-                    query_files.push(pattern.clone());
-                }
-            }
-
+            let query_files = expand_file_patterns(&config.files)?;
             if query_files.is_empty() {
                 miette::bail!("No query files specified. Use positional arguments or configure in sqlsurge.toml");
             }
 
-            // Analyze each query file
-            let mut total_errors = 0;
-            let mut total_warnings = 0;
-            let mut analyzer = Analyzer::with_dialect(&catalog, dialect);
-
-            // Get disabled rules
-            let disabled_rules: std::collections::HashSet<String> =
-                config.disable.iter().cloned().collect();
-
+            let mut analyzer = analyze::check_analyzer(Some(&catalog), spec, &config);
+            let mut deps = Vec::new();
             for query_file in &query_files {
                 let content = fs::read_to_string(query_file).into_diagnostic()?;
-                let diagnostics = analyzer.analyze(&content);
-
-                // Filter out disabled rules
-                let filtered_diagnostics: Vec<_> = diagnostics
-                    .into_iter()
-                    .filter(|d| !disabled_rules.contains(d.code()))
+                let dependencies = analyzer.collect_dependencies(&content);
+                let mut schema_files: Vec<String> = dependencies
+                    .iter()
+                    .filter_map(|name| definition_file(&catalog, name))
+                    .map(str::to_string)
                     .collect();
+                schema_files.sort();
+                schema_files.dedup();
+                deps.push(FileDependencies {
+                    file: query_file.display().to_string(),
+                    dependencies: dependencies.into_iter().collect(),
+                    schema_files,
+                });
+            }
+            output::print_dependencies(&deps, format);
 
-                if !filtered_diagnostics.is_empty() {
-                    let formatter =
-                        OutputFormatter::new(output_format, query_file.display().to_string());
-                    formatter.print_diagnostics(&filtered_diagnostics, &content);
+            Ok(false)
+        }
 
-                    for diag in &filtered_diagnostics {
-                        match diag.severity {
-                            sqlsurge_core::Severity::Error => total_errors += 1,
-                            sqlsurge_core::Severity::Warning => total_warnings += 1,
-                            _ => {}
-                        }
+        Command::Schema {
+            files,
+            dialect,
+            format,
+            compare,
+        } => {
+            // Build and display schema information
+            let dialect = dialect
+                .parse::<DialectSpec>()
+                .map_err(|e: String| miette::miette!(e))?
+                .dialect;
+            let build = |files: &[PathBuf]| -> Result<Catalog> {
+                let mut builder = SchemaBuilder::with_dialect(dialect);
+                for schema_file in files {
+                    let content = fs::read_to_string(schema_file).into_diagnostic()?;
+                    let _ = builder.parse_named(&schema_file.display().to_string(), &content);
+                }
+                Ok(builder.build().0)
+            };
+            let catalog = build(&files)?;
+
+            if !compare.is_empty() {
+                let other = build(&compare)?;
+                let order = column_order_differences(&catalog, &other);
+                let types = column_type_differences(&catalog, &other, dialect);
+                match format {
+                    SchemaFormat::Json => {
+                        let json = serde_json::to_string_pretty(&serde_json::json!({
+                            "column_order": order,
+                            "column_types": types,
+                        }))
+                        .into_diagnostic()?;
+                        println!("{}", json);
+                    }
+                    SchemaFormat::Human | SchemaFormat::Markdown => {
+                        print_column_order_differences(&order);
+                        print_column_type_differences(&types);
                     }
                 }
+                return Ok(!order.is_empty() || !types.is_empty());
             }
 
-            // Print summary
-            if total_errors > 0 || total_warnings > 0 {
-                eprintln!();
-                eprintln!(
-                    "Found {} error(s), {} warning(s) in {} file(s)",
-                    total_errors,
-                    total_warnings,
-                    query_files.len()
-                );
-            } else {
-                eprintln!("All {} file(s) passed validation", query_files.len());
+            match format {
+                SchemaFormat::Human => print_schema_human(&catalog),
+                SchemaFormat::Json => {
+                    let json = serde_json::to_string_pretty(&catalog).into_diagnostic()?;
+                    println!("{}", json);
+                }
+                SchemaFormat::Markdown => print_schema_markdown(&catalog),
             }
 
-            Ok(total_errors > 0)
+            Ok(false)
         }
 
-        Command::Schema { files } => {
-            // Build and display schema information
-            let mut builder = SchemaBuilder::new();
-            for schema_file in &files {
-                let content = fs::read_to_string(schema_file).into_diagnostic()?;
-                let _ = builder.parse(&content);
-            }
-            let (catalog, _) = builder.build();
-
-            println!("Schema Information:");
-            println!("==================");
-            for (schema_name, schema) in &catalog.schemas {
-                println!("\nSchema: {}", schema_name);
-                for (table_name, table) in &schema.tables {
-                    println!("  Table: {}", table_name);
-                    for (col_name, col) in &table.columns {
-                        let nullable = if col.nullable { "NULL" } else { "NOT NULL" };
-                        println!(
-                            "    - {} {} {}",
-                            col_name,
-                            col.data_type.display_name(),
-                            nullable
-                        );
+        Command::Explain { rule, format } => {
+            let kinds = match rule {
+                Some(rule) => match rules::lookup(&rule) {
+                    Some(kind) => vec![kind],
+                    None => miette::bail!(
+                        "Unknown rule '{}'; expected a code such as E0006 or a name such as \
+                         ambiguous-column",
+                        rule
+                    ),
+                },
+                None => DiagnosticKind::ALL.to_vec(),
+            };
+            for (i, kind) in kinds.into_iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                match format {
+                    ExplainFormat::Human => print_explanation_human(kind),
+                    ExplainFormat::Markdown => {
+                        println!("## {}: {}\n", kind.code(), kind.name());
+                        print!("{}", rules::explanation(kind));
                     }
                 }
             }
-
             Ok(false)
         }
 
@@ -242,3 +279,466 @@ fn run(args: Args) -> Result<bool> {
         }
     }
 }
+
+/// Run `sqlsurge check`, recording what happened in `status`
+fn check(args: CheckArgs, verbose: u8, status: &mut RunStatus) -> Result<bool> {
+    let CheckArgs {
+        files,
+        schema,
+        schema_dir,
+        config: config_path,
+        disable,
+        enable,
+        severity,
+        dialect,
+        format,
+        dedupe,
+        group_by,
+        profile,
+        profile_json,
+        changed_only,
+        base,
+        allow_empty_schema,
+        no_schema,
+        schema_inline,
+        schema_sqlx,
+        no_hints,
+        schema_warnings,
+        max_file_size,
+        max_statements_per_file,
+        ..
+    } = args;
+    let profiling = profile || profile_json.is_some();
+    let mut profiler = Profiler::default();
+    // Merge CLI args with config (CLI takes precedence)
+    let mut config = load_config(config_path)?
+        .merge_with_args(&schema, &schema_dir, &files, &format, &disable, &enable)
+        .merge_severity(severity);
+    if schema_inline.is_some() {
+        config.schema_inline = schema_inline;
+    }
+    if let Some(dir) = schema_sqlx {
+        config.schema_sqlx = Some(dir.display().to_string());
+    }
+    config.max_file_size = max_file_size.or(config.max_file_size);
+    config.max_statements_per_file = max_statements_per_file.or(config.max_statements_per_file);
+    if no_schema {
+        // Schema settings of the config file don't apply either
+        config.schema.clear();
+        config.schema_dir = None;
+        config.schema_inline = None;
+        config.schema_sqlx = None;
+    }
+    if allow_empty_schema {
+        // An explicit severity for E0001 wins
+        config
+            .severity
+            .entry(DiagnosticKind::TableNotFound.code().to_string())
+            .or_insert(SeverityOrOff::Warning);
+    }
+    let spec = dialect_spec(dialect.as_deref(), &config)?;
+
+    let schema_sources = if no_schema {
+        Vec::new()
+    } else {
+        SchemaSource::collect(&config)?
+    };
+
+    // Determine output format
+    let output_format = if let Some(fmt_str) = &config.format {
+        match fmt_str.as_str() {
+            "json" => OutputFormat::Json,
+            "jsonl" => OutputFormat::Jsonl,
+            "sarif" => OutputFormat::Sarif,
+            _ => OutputFormat::Human,
+        }
+    } else {
+        OutputFormat::Human
+    };
+
+    // Build schema catalog; `schema_parse_errors` decides what becomes of
+    // the statements it skips
+    let hints = !no_hints;
+    let schema_profiler = profiling.then_some(&mut profiler);
+    let loaded = match schema::load(&schema_sources, &config, spec, schema_profiler)? {
+        SchemaLoad::Loaded(loaded) => *loaded,
+        SchemaLoad::Failed(failed) => {
+            OutputFormatter::new(output_format, failed.name.clone())
+                .with_explain_hints(hints)
+                .with_help_uri(config.help_uri.clone())
+                .print_diagnostics(&failed.diagnostics, &failed.content);
+            let mut report = AnalysisReport::new();
+            report.push(failed.name, failed.diagnostics);
+            status.record(&report);
+            return Ok(true);
+        }
+    };
+    let catalog = loaded.catalog;
+
+    let schema_policy = schema_warnings
+        .or(config.schema_warnings)
+        .unwrap_or_default();
+    let schema_report = schema::report(loaded.diagnostics, &loaded.sources, schema_policy, &config);
+    status.schema_warnings = schema_report.diagnostics().count();
+    schema::print_report(&schema_report, &loaded.sources, schema_policy, |name| {
+        OutputFormatter::new(output_format, name)
+            .with_explain_hints(hints)
+            .with_help_uri(config.help_uri.clone())
+    });
+
+    // Typically a schema directory holding only seed data: every table
+    // reference would fail, burying the actual problem
+    status.no_schema = no_schema;
+    status.empty_schema = !no_schema && catalog.is_empty();
+    if status.empty_schema {
+        eprintln!(
+            "Warning: schema contained no table definitions — all table references will fail"
+        );
+        if !allow_empty_schema {
+            eprintln!("  help: pass --allow-empty-schema to report missing tables as warnings");
+        }
+    }
+
+    // Collect query files from config or CLI
+    let mut query_files = expand_file_patterns(&config.files)?;
+
+    if query_files.is_empty() {
+        miette::bail!(
+            "No query files specified. Use positional arguments or configure in sqlsurge.toml"
+        );
+    }
+
+    // With --changed-only, skip unchanged query files unless the schema changed
+    let mut skipped = 0;
+    if changed_only {
+        let base = base
+            .or_else(|| config.changed_base.clone())
+            .unwrap_or_else(|| changed::DEFAULT_BASE.to_string());
+        let schema_dir = config.schema_dir.as_ref().map(PathBuf::from);
+        let schema_files: Vec<PathBuf> = schema_sources
+            .iter()
+            .filter_map(|s| s.path().map(PathBuf::from))
+            .collect();
+        match ChangedFiles::from_git(&base) {
+            Err(e) => eprintln!("Notice: {}; checking all files", e),
+            // Changes to a schema from stdin or inline can't be told
+            Ok(_) if schema_files.len() < schema_sources.len() => {
+                eprintln!("Notice: the schema is not read from files; checking all files");
+            }
+            Ok(changed)
+                if changed.touches_schema(&schema_files, schema_dir.as_deref())
+                    || config
+                        .schema_sqlx
+                        .as_ref()
+                        .is_some_and(|sqlx| changed.touches(Path::new(sqlx))) =>
+            {
+                eprintln!(
+                    "Notice: schema files changed since {}; checking all files",
+                    base
+                );
+            }
+            Ok(changed) => {
+                let total = query_files.len();
+                query_files.retain(|file| changed.contains(file));
+                skipped = total - query_files.len();
+            }
+        }
+    }
+    status.files.skipped = skipped;
+
+    // Analyze each query file; grouped human output is printed once all files are analyzed
+    let grouped = output_format == OutputFormat::Human && (dedupe || group_by == GroupBy::Rule);
+    let QueryResults { report, collected } = QueryCheck {
+        config: &config,
+        schema: (!no_schema).then_some(&catalog),
+        spec,
+        output_format,
+        explicit: files
+            .into_iter()
+            .filter(|f| !f.to_string_lossy().contains('*'))
+            .collect(),
+        hints,
+        profiling,
+        grouped,
+    }
+    .run(&query_files, &mut profiler, status)?;
+    let mut distinct = None;
+    if grouped {
+        let printed = output::print_grouped(&collected, group_by, dedupe, verbose > 0, hints);
+        if dedupe {
+            distinct = Some(printed);
+        }
+    }
+
+    // Print summary; only errors fail the check, info and hints never do
+    let counts = *report.severity_counts();
+    if output_format == OutputFormat::Jsonl {
+        output::print_jsonl_summary(
+            query_files.len(),
+            counts.error,
+            counts.warning,
+            status.files.oversized,
+        );
+    }
+    let mut skipped = if skipped > 0 {
+        format!(", {} unchanged file(s) skipped", skipped)
+    } else {
+        String::new()
+    };
+    if let Some(&oversized) = report
+        .rule_counts()
+        .get(DiagnosticKind::StatementTooLarge.code())
+    {
+        skipped.push_str(&format!(", {} oversized statement(s) skipped", oversized));
+    }
+    if status.files.oversized > 0 {
+        skipped.push_str(&format!(
+            ", {} file(s) skipped due to size limits",
+            status.files.oversized
+        ));
+    }
+    if status.empty_schema {
+        skipped.push_str("; the schema contained no table definitions");
+    }
+    if no_schema {
+        skipped.push_str("; schema validation skipped (--no-schema)");
+    }
+    let schema_counts = *schema_report.severity_counts();
+    if schema_counts.error > 0 || schema_counts.warning > 0 {
+        if schema_policy == SchemaWarnings::Hide && schema_counts.error == 0 {
+            skipped.push_str(&format!(
+                "; schema: {} warning(s) hidden",
+                schema_counts.warning
+            ));
+        } else if schema_policy == SchemaWarnings::Hide {
+            skipped.push_str(&format!(
+                "; schema: {} error(s), {} warning(s) hidden",
+                schema_counts.error, schema_counts.warning
+            ));
+        } else {
+            skipped.push_str(&format!(
+                "; schema: {} error(s), {} warning(s)",
+                schema_counts.error, schema_counts.warning
+            ));
+        }
+    }
+    if counts.error > 0 || counts.warning > 0 || schema_counts.error > 0 {
+        eprintln!();
+        eprintln!(
+            "Found {} error(s), {} warning(s) in {} file(s){}{}",
+            counts.error,
+            counts.warning,
+            query_files.len(),
+            distinct.map_or(String::new(), |n| format!(" ({} distinct problem(s))", n)),
+            skipped
+        );
+    } else {
+        eprintln!(
+            "All {} file(s) passed validation{}",
+            query_files.len(),
+            skipped
+        );
+    }
+    if profiling {
+        profiler.print_human();
+    }
+    if let Some(path) = profile_json {
+        fs::write(&path, profiler.to_json()).into_diagnostic()?;
+    }
+
+    Ok(report.has_errors() || schema_report.has_errors())
+}
+
+/// Resolve the dialect from the CLI, then the config file, then the default
+fn dialect_spec(cli: Option<&str>, config: &Config) -> Result<DialectSpec> {
+    match cli.or(config.dialect.as_deref()) {
+        Some(spec) => spec.parse().map_err(|e: String| miette::miette!(e)),
+        None => Ok(DialectSpec::default()),
+    }
+}
+
+/// Load the configuration file (explicit path, or sqlsurge.toml in current or parent directory)
+fn load_config(path: Option<PathBuf>) -> Result<Config> {
+    match path {
+        Some(path) => Config::from_file(&path),
+        None => Ok(Config::find_and_load()?.unwrap_or_default()),
+    }
+}
+
+/// Query files named by `patterns`, expanding glob patterns
+fn expand_file_patterns(patterns: &[String]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for pattern in patterns {
+        if pattern.contains('*') {
+            files.extend(glob::glob(pattern).into_diagnostic()?.flatten());
+        } else {
+            files.push(PathBuf::from(pattern));
+        }
+    }
+    Ok(files)
+}
+
+/// Schema file that defines the table or view `name`
+fn definition_file<'c>(catalog: &'c Catalog, name: &QualifiedName) -> Option<&'c str> {
+    let origin = match catalog.get_table(name) {
+        Some(table) => table.origin.as_ref(),
+        None => catalog.get_view(name)?.origin.as_ref(),
+    };
+    origin.map(|o| o.file.as_str())
+}
+
+/// Print output columns of a query as an aligned table
+fn print_columns_human(columns: &[OutputColumn]) {
+    let rows: Vec<(&str, String, &str)> = columns
+        .iter()
+        .map(|c| {
+            (
+                c.name.as_str(),
+                c.data_type.display_name(),
+                if c.nullable { "YES" } else { "NO" },
+            )
+        })
+        .collect();
+    let name_width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0).max(6);
+    let type_width = rows.iter().map(|r| r.1.len()).max().unwrap_or(0).max(4);
+
+    println!(
+        "{:<name_width$}  {:<type_width$}  Nullable",
+        "Column", "Type"
+    );
+    for (name, data_type, nullable) in rows {
+        println!(
+            "{:<name_width$}  {:<type_width$}  {}",
+            name, data_type, nullable
+        );
+    }
+}
+
+/// Print the tables whose columns `sqlsurge schema --compare` found in another order
+fn print_column_order_differences(differences: &[ColumnOrderDifference]) {
+    if differences.is_empty() {
+        println!("Column order matches in every shared table");
+        return;
+    }
+    for difference in differences {
+        println!(
+            "Table {}: columns are in a different order",
+            difference.table
+        );
+        println!("  schema:  {}", difference.left.join(", "));
+        println!("  compare: {}", difference.right.join(", "));
+    }
+}
+
+/// Print the columns whose types `sqlsurge schema --compare` found to differ
+fn print_column_type_differences(differences: &[ColumnTypeDifference]) {
+    if differences.is_empty() {
+        println!("Column types match in every shared table");
+        return;
+    }
+    for difference in differences {
+        println!(
+            "Table {}: column {} is {} in schema, {} in compare",
+            difference.table, difference.column, difference.left, difference.right
+        );
+    }
+}
+
+/// Print a rule's explanation for the terminal: code fences are dropped
+/// and their contents indented
+fn print_explanation_human(kind: DiagnosticKind) {
+    println!("\x1b[1m{} {}\x1b[0m\n", kind.code(), kind.name());
+    let mut in_code = false;
+    for line in rules::explanation(kind).lines() {
+        if line.starts_with("```") {
+            in_code = !in_code;
+        } else if in_code {
+            println!("    {}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+/// Print a plain-text summary of the catalog
+fn print_schema_human(catalog: &Catalog) {
+    println!("Schema Information:");
+    println!("==================");
+    for (schema_name, schema) in &catalog.schemas {
+        println!("\nSchema: {}", schema_name);
+        for (table_name, table) in &schema.tables {
+            match &table.comment {
+                Some(comment) => println!("  Table: {}  -- {}", table_name, comment),
+                None => println!("  Table: {}", table_name),
+            }
+            for col in table.ordered_columns() {
+                let nullable = if col.nullable { "NULL" } else { "NOT NULL" };
+                let comment = col
+                    .comment
+                    .as_ref()
+                    .map(|c| format!("  -- {}", c))
+                    .unwrap_or_default();
+                println!(
+                    "    - {} {} {}{}",
+                    col.name,
+                    col.data_type.display_name(),
+                    nullable,
+                    comment
+                );
+            }
+            for fk in &table.foreign_keys {
+                println!("    FOREIGN KEY {}", foreign_key_summary(fk));
+            }
+        }
+    }
+}
+
+/// `(cols) REFERENCES table (cols)` plus any non-default ON DELETE / ON UPDATE actions
+fn foreign_key_summary(fk: &ForeignKeyDef) -> String {
+    let mut summary = format!(
+        "({}) REFERENCES {} ({})",
+        fk.columns.join(", "),
+        fk.references_table,
+        fk.references_columns.join(", ")
+    );
+    for (event, action) in [("DELETE", fk.on_delete), ("UPDATE", fk.on_update)] {
+        if action != ReferentialAction::NoAction {
+            summary.push_str(&format!(" ON {} {}", event, action));
+        }
+    }
+    summary
+}
+
+/// Print the catalog as Markdown, one table per relation
+fn print_schema_markdown(catalog: &Catalog) {
+    // Pipes and newlines would break the table layout
+    let escape = |s: &str| s.replace('|', "\\|").replace('\n', " ");
+
+    for (schema_name, schema) in &catalog.schemas {
+        for (table_name, table) in &schema.tables {
+            println!("## {}.{}\n", schema_name, table_name);
+            if let Some(comment) = &table.comment {
+                println!("{}\n", comment);
+            }
+            println!("| Column | Type | Nullable | Comment |");
+            println!("|--------|------|----------|---------|");
+            for col in table.ordered_columns() {
+                println!(
+                    "| {} | {} | {} | {} |",
+                    col.name,
+                    col.data_type.display_name(),
+                    if col.nullable { "YES" } else { "NO" },
+                    col.comment.as_deref().map(escape).unwrap_or_default()
+                );
+            }
+            println!();
+            if !table.foreign_keys.is_empty() {
+                println!("Foreign keys:\n");
+                for fk in &table.foreign_keys {
+                    println!("- `{}`", foreign_key_summary(fk));
+                }
+                println!();
+            }
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! Grouped human output (`--dedupe`, `--group-by rule`)
+//!
+//! Generated copies of the same queries repeat one problem across many files.
+//! For grouped output, diagnostics are collected per file and printed once
+//! every file has been analyzed: `--dedupe` merges diagnostics with the same
+//! rule, message and source line into one entry listing the affected files,
+//! and `--group-by rule` orders entries by rule code. Only human output is
+//! grouped; machine-readable formats always list every diagnostic.
+
+use std::collections::HashMap;
+
+use sqlsurge_core::Diagnostic;
+
+use crate::args::{GroupBy, OutputFormat};
+
+use super::{get_source_line, offset_to_line_col, OutputFormatter};
+
+/// Files listed for a deduplicated problem (all of them with `--verbose`)
+const LISTED_FILES: usize = 5;
+
+/// Diagnostics of one analyzed file
+pub struct FileDiagnostics {
+    pub file: String,
+    pub source: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// A diagnostic as printed, with the other occurrences merged into it
+struct Entry<'a> {
+    file: &'a FileDiagnostics,
+    diagnostic: &'a Diagnostic,
+    /// Files of every occurrence, in order (repeats when a file has several)
+    occurrences: Vec<&'a str>,
+}
+
+/// Print collected diagnostics grouped as requested, returning the number of
+/// entries printed
+pub fn print_grouped(
+    files: &[FileDiagnostics],
+    group_by: GroupBy,
+    dedupe: bool,
+    list_all_files: bool,
+    explain_hints: bool,
+) -> usize {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut seen: HashMap<(&str, &str, &str), usize> = HashMap::new();
+    for file in files {
+        for diagnostic in &file.diagnostics {
+            if dedupe {
+                let key = dedupe_key(diagnostic, &file.source);
+                if let Some(&i) = seen.get(&key) {
+                    entries[i].occurrences.push(&file.file);
+                    continue;
+                }
+                seen.insert(key, entries.len());
+            }
+            entries.push(Entry {
+                file,
+                diagnostic,
+                occurrences: vec![&file.file],
+            });
+        }
+    }
+
+    if group_by == GroupBy::Rule {
+        // Stable: entries of a rule stay in file order
+        entries.sort_by_key(|e| e.diagnostic.code());
+    }
+
+    let mut current_rule = None;
+    for entry in &entries {
+        let code = entry.diagnostic.code();
+        if group_by == GroupBy::Rule && current_rule != Some(code) {
+            current_rule = Some(code);
+            let count: usize = entries
+                .iter()
+                .filter(|e| e.diagnostic.code() == code)
+                .map(|e| e.occurrences.len())
+                .sum();
+            eprintln!(
+                "\x1b[1m{} {}\x1b[0m: {} diagnostic(s)",
+                code,
+                entry.diagnostic.kind.name(),
+                count
+            );
+            eprintln!();
+        }
+
+        let note = (entry.occurrences.len() > 1)
+            .then(|| occurrences_note(&entry.occurrences, list_all_files));
+        OutputFormatter::new(OutputFormat::Human, entry.file.file.clone())
+            .with_explain_hints(explain_hints)
+            .print_human_diagnostic(entry.diagnostic, &entry.file.source, note.as_deref());
+    }
+    entries.len()
+}
+
+/// Rule, message and source line text: what makes two diagnostics the same problem
+fn dedupe_key<'a>(diagnostic: &'a Diagnostic, source: &'a str) -> (&'a str, &'a str, &'a str) {
+    let line = diagnostic.span.and_then(|span| {
+        let line = if span.line > 0 {
+            span.line
+        } else {
+            offset_to_line_col(source, span.offset).0
+        };
+        get_source_line(source, line)
+    });
+    (
+        diagnostic.code(),
+        &diagnostic.message,
+        line.map_or("", str::trim),
+    )
+}
+
+/// `found in 3 files: a.sql, b.sql, c.sql`
+fn occurrences_note(occurrences: &[&str], list_all_files: bool) -> String {
+    let mut files: Vec<&str> = Vec::new();
+    for file in occurrences {
+        if !files.contains(file) {
+            files.push(file);
+        }
+    }
+    let times = if occurrences.len() > files.len() {
+        format!(" {} times", occurrences.len())
+    } else {
+        String::new()
+    };
+    let shown = if list_all_files {
+        files.len()
+    } else {
+        files.len().min(LISTED_FILES)
+    };
+    let mut note = format!(
+        "found{} in {} file(s): {}",
+        times,
+        files.len(),
+        files[..shown].join(", ")
+    );
+    if shown < files.len() {
+        note.push_str(&format!(
+            " and {} more (use --verbose to list all)",
+            files.len() - shown
+        ));
+    }
+    note
+}
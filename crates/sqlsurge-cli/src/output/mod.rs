@@ -1,81 +1,326 @@
 //! Output formatting
 
-use sqlsurge_core::{Diagnostic, Severity};
+mod group;
+mod profile;
 
-use crate::args::OutputFormat;
+use std::io::Write;
+
+use sqlsurge_core::rules;
+use sqlsurge_core::schema::QualifiedName;
+use sqlsurge_core::{Diagnostic, OutputColumn, Severity};
+
+use crate::args::{DepsFormat, OutputFormat};
+
+pub use group::{print_grouped, FileDiagnostics};
+pub use profile::Profiler;
+
+use sqlsurge_core::json::{
+    get_source_line, offset_to_line_col, JsonDeps, JsonDescribe, JsonDiagnostic, JsonFileDeps,
+    JsonOutputColumn, JsonReport, JsonlRecord, FORMAT_VERSION,
+};
+
+/// Columns a tab is expanded to in source excerpts
+const TAB_WIDTH: usize = 4;
+
+/// Render a source line and the caret line under `length` characters from
+/// the 1-indexed `col`
+///
+/// Columns count characters, with a tab as one, while terminals render tabs
+/// as several cells; tabs are expanded to [`TAB_WIDTH`] spaces so the caret
+/// lines up. A span past the end of the line gets a single caret after it.
+fn annotate(source_line: &str, col: usize, length: usize) -> (String, String) {
+    let start = col.saturating_sub(1);
+    let end = start.saturating_add(length.max(1));
+    let mut rendered = String::with_capacity(source_line.len());
+    let mut padding = 0;
+    let mut underline = 0;
+    for (i, ch) in source_line.chars().enumerate() {
+        let width = if ch == '\t' {
+            rendered.push_str(&" ".repeat(TAB_WIDTH));
+            TAB_WIDTH
+        } else {
+            rendered.push(ch);
+            1
+        };
+        if i < start {
+            padding += width;
+        } else if i < end {
+            underline += width;
+        }
+    }
+    let caret = format!("{}{}", " ".repeat(padding), "^".repeat(underline.max(1)));
+    (rendered, caret)
+}
+
+/// Longest source line shown in full in excerpts
+const MAX_EXCERPT_CHARS: usize = 160;
+
+/// The part of a long source line shown around column `col`, and the number
+/// of columns it is shifted left by
+///
+/// Lines up to [`MAX_EXCERPT_CHARS`] are shown whole; longer ones (such as a
+/// generated statement on one line) are cut to a window starting a little
+/// before `col`, with `...` marking the cuts.
+fn excerpt(source_line: &str, col: usize) -> (String, usize) {
+    let len = source_line.chars().count();
+    if len <= MAX_EXCERPT_CHARS {
+        return (source_line.to_string(), 0);
+    }
+    let start = col.saturating_sub(1).saturating_sub(20).min(len);
+    let mut text = String::new();
+    if start > 0 {
+        text.push_str("...");
+    }
+    text.extend(source_line.chars().skip(start).take(MAX_EXCERPT_CHARS));
+    if start + MAX_EXCERPT_CHARS < len {
+        text.push_str("...");
+    }
+    // Columns move left by the characters cut, and right by the marker
+    let marker = if start > 0 { 3 } else { 0 };
+    (text, start - marker.min(start))
+}
+
+/// Add a secondary underline (`-`) under `length` characters from `col` to
+/// a caret line from [`annotate`], without covering its carets
+fn overlay(source_line: &str, marks: &str, col: usize, length: usize) -> String {
+    let (_, under) = annotate(source_line, col, length);
+    let mut marks: Vec<char> = marks.chars().collect();
+    for (i, ch) in under.chars().enumerate() {
+        if ch != '^' {
+            continue;
+        }
+        if i >= marks.len() {
+            marks.resize(i + 1, ' ');
+        }
+        if marks[i] == ' ' {
+            marks[i] = '-';
+        }
+    }
+    marks.into_iter().collect()
+}
 
 /// Output formatter for diagnostics
 pub struct OutputFormatter {
     format: OutputFormat,
     file_name: String,
+    /// Dialect the file was analyzed with, recorded in JSON and SARIF output
+    dialect: Option<String>,
+    /// Whether human output points at `sqlsurge explain` for each diagnostic
+    explain_hints: bool,
+    /// SARIF `helpUri` of the rules, with `{code}` and `{name}` replaced
+    help_uri: Option<String>,
+    /// "schema" when printing the diagnostics of a schema file
+    category: Option<&'static str>,
 }
 
 impl OutputFormatter {
     pub fn new(format: OutputFormat, file_name: String) -> Self {
-        Self { format, file_name }
+        Self {
+            format,
+            file_name,
+            dialect: None,
+            explain_hints: true,
+            help_uri: None,
+            category: None,
+        }
+    }
+
+    /// Mark the diagnostics as those of a schema file in JSON and SARIF output
+    pub fn for_schema(mut self) -> Self {
+        self.category = Some("schema");
+        self
+    }
+
+    /// A diagnostic's JSON shape, with the category if there is one
+    fn json_diagnostic<'a>(&'a self, diag: &'a Diagnostic, source: &'a str) -> JsonDiagnostic<'a> {
+        let mut json = JsonDiagnostic::new(&self.file_name, diag, source);
+        json.category = self.category;
+        json
+    }
+
+    /// Whether to end human diagnostics with a pointer to `sqlsurge explain`
+    pub fn with_explain_hints(mut self, explain_hints: bool) -> Self {
+        self.explain_hints = explain_hints;
+        self
+    }
+
+    /// Link rules to `template` in SARIF output instead of the README
+    pub fn with_help_uri(mut self, template: Option<String>) -> Self {
+        self.help_uri = template;
+        self
+    }
+
+    /// Record the dialect (e.g. "mysql@5.7") the file was analyzed with
+    pub fn with_dialect(mut self, dialect: impl std::fmt::Display) -> Self {
+        self.dialect = Some(dialect.to_string());
+        self
     }
 
     /// Print diagnostics in the configured format
     pub fn print_diagnostics(&self, diagnostics: &[Diagnostic], source: &str) {
         match self.format {
             OutputFormat::Human => self.print_human(diagnostics, source),
-            OutputFormat::Json => self.print_json(diagnostics),
+            OutputFormat::Json => self.print_json(diagnostics, source),
+            OutputFormat::Jsonl => self.print_jsonl(diagnostics, source),
             OutputFormat::Sarif => self.print_sarif(diagnostics),
         }
     }
 
     fn print_human(&self, diagnostics: &[Diagnostic], source: &str) {
         for diag in diagnostics {
-            let severity_str = match diag.severity {
-                Severity::Error => "\x1b[31merror\x1b[0m",
-                Severity::Warning => "\x1b[33mwarning\x1b[0m",
-                Severity::Info => "\x1b[34minfo\x1b[0m",
-            };
+            self.print_human_diagnostic(diag, source, None);
+        }
+    }
 
-            // Print main message
-            eprintln!("{}[{}]: {}", severity_str, diag.code(), diag.message);
+    /// Print one diagnostic, with an optional `= note:` line after any help
+    fn print_human_diagnostic(&self, diag: &Diagnostic, source: &str, note: Option<&str>) {
+        let severity_str = match diag.severity {
+            Severity::Error => "\x1b[31merror\x1b[0m",
+            Severity::Warning => "\x1b[33mwarning\x1b[0m",
+            Severity::Info => "\x1b[34minfo\x1b[0m",
+            Severity::Hint => "\x1b[36mhint\x1b[0m",
+        };
 
-            // Print file location if we have a span
-            if let Some(span) = &diag.span {
-                // Use line/column from span if available, otherwise compute from offset
-                let (line, col) = if span.line > 0 {
-                    (span.line, span.column)
-                } else {
-                    offset_to_line_col(source, span.offset)
-                };
-                eprintln!("  --> {}:{}:{}", self.file_name, line, col);
-
-                // Print source line with annotation
-                if let Some(source_line) = get_source_line(source, line) {
-                    eprintln!("   |");
-                    eprintln!("{:>3} | {}", line, source_line);
-
-                    // Print caret annotation
-                    let padding = " ".repeat(col.saturating_sub(1));
-                    let underline = "^".repeat(
-                        span.length
-                            .min(source_line.len().saturating_sub(col) + 1)
-                            .max(1),
-                    );
-                    eprintln!("   | {}{}", padding, underline);
+        // Print main message
+        eprintln!("{}[{}]: {}", severity_str, diag.code(), diag.message);
+
+        // Print file location if we have a span
+        if let Some(span) = &diag.span {
+            // Use line/column from span if available, otherwise compute from offset
+            let (line, col) = if span.line > 0 {
+                (span.line, span.column)
+            } else {
+                offset_to_line_col(source, span.offset)
+            };
+            eprintln!("  --> {}:{}:{}", self.file_name, line, col);
+
+            // Print source line with annotation; labels in this file are
+            // underlined with `-`, on the same line or on their own lines
+            if let Some(source_line) = get_source_line(source, line) {
+                let (source_line, shift) = excerpt(source_line, col);
+                let source_line = source_line.as_str();
+                let (rendered, mut caret) = annotate(source_line, col - shift, span.length);
+                let mut other_lines: Vec<(usize, String)> = Vec::new();
+                for label in diag.labels.iter().filter(|l| l.file.is_none()) {
+                    let label_span = &label.span;
+                    if label_span.line == line {
+                        if label_span.column > shift
+                            && label_span.column - shift <= source_line.chars().count()
+                        {
+                            let label_col = label_span.column - shift;
+                            caret = overlay(source_line, &caret, label_col, label_span.length);
+                        }
+                    } else if let Some(label_line) = get_source_line(source, label_span.line)
+                        .filter(|l| l.chars().count() <= MAX_EXCERPT_CHARS)
+                    {
+                        let marks =
+                            match other_lines.iter_mut().find(|(l, _)| *l == label_span.line) {
+                                Some((_, marks)) => marks,
+                                None => {
+                                    other_lines.push((label_span.line, String::new()));
+                                    &mut other_lines.last_mut().unwrap().1
+                                }
+                            };
+                        *marks = overlay(label_line, marks, label_span.column, label_span.length);
+                    }
+                }
+                eprintln!("   |");
+                eprintln!("{:>3} | {}", line, rendered);
+                eprintln!("   | {}", caret);
+                other_lines.sort();
+                for (label_line, marks) in other_lines {
+                    if let Some(label_source) = get_source_line(source, label_line) {
+                        let (rendered, _) = annotate(label_source, 1, 0);
+                        eprintln!("{:>3} | {}", label_line, rendered);
+                        eprintln!("   | {}", marks);
+                    }
                 }
             }
+        }
 
-            // Print help if available
-            if let Some(help) = &diag.help {
-                eprintln!("   = help: {}", help);
+        // Print help if available
+        if let Some(help) = &diag.help {
+            eprintln!("   = help: {}", help);
+        }
+        for fix in &diag.fixes {
+            let span = &fix.span;
+            if (span.end_line, span.end_column) == (span.line, span.column) {
+                eprintln!(
+                    "   = fix: {}: insert `{}` at {}:{}",
+                    fix.message, fix.replacement, span.line, span.column
+                );
+            } else {
+                eprintln!(
+                    "   = fix: {}: replace {}:{}-{}:{} with `{}`",
+                    fix.message,
+                    span.line,
+                    span.column,
+                    span.end_line,
+                    span.end_column,
+                    fix.replacement
+                );
             }
-
-            eprintln!();
         }
+        // Labels in this file are underlined above; one note per message
+        // lists their positions
+        let mut local: Vec<(&str, Vec<String>)> = Vec::new();
+        for label in &diag.labels {
+            if label.file.is_none() {
+                let at = format!("{}:{}", label.span.line, label.span.column);
+                match local.iter_mut().find(|(m, _)| *m == label.message) {
+                    Some((_, positions)) => positions.push(at),
+                    None => local.push((&label.message, vec![at])),
+                }
+                continue;
+            }
+            eprintln!("   = note: {}", label.message);
+            eprintln!(
+                "     --> {}:{}:{}",
+                label.file.as_deref().unwrap_or(&self.file_name),
+                label.span.line,
+                label.span.column
+            );
+        }
+        for (message, positions) in local {
+            eprintln!("   = note: {} ({})", message, positions.join(", "));
+        }
+        if let Some(note) = note {
+            eprintln!("   = note: {}", note);
+        }
+        if self.explain_hints {
+            eprintln!(
+                "   = note: for more info, run `sqlsurge explain {}`",
+                diag.code()
+            );
+        }
+
+        eprintln!();
     }
 
-    fn print_json(&self, diagnostics: &[Diagnostic]) {
-        let output = serde_json::json!({
-            "file": self.file_name,
-            "diagnostics": diagnostics
-        });
-        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    fn print_json(&self, diagnostics: &[Diagnostic], source: &str) {
+        let report = JsonReport {
+            version: FORMAT_VERSION,
+            file: &self.file_name,
+            dialect: self.dialect.as_deref(),
+            diagnostics: diagnostics
+                .iter()
+                .map(|d| self.json_diagnostic(d, source))
+                .collect(),
+        };
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    }
+
+    fn print_jsonl(&self, diagnostics: &[Diagnostic], source: &str) {
+        let mut stdout = std::io::stdout().lock();
+        for diag in diagnostics {
+            let record = JsonlRecord::Diagnostic {
+                version: FORMAT_VERSION,
+                diagnostic: Box::new(self.json_diagnostic(diag, source)),
+            };
+            let _ = writeln!(stdout, "{}", serde_json::to_string(&record).unwrap());
+        }
+        // Flush per file so consumers can process results as they arrive
+        let _ = stdout.flush();
     }
 
     fn print_sarif(&self, diagnostics: &[Diagnostic]) {
@@ -99,12 +344,33 @@ impl OutputFormatter {
                     }
                 }
 
-                serde_json::json!({
+                let related: Vec<serde_json::Value> = d
+                    .labels
+                    .iter()
+                    .enumerate()
+                    .map(|(id, label)| {
+                        serde_json::json!({
+                            "id": id,
+                            "message": { "text": label.message },
+                            "physicalLocation": {
+                                "artifactLocation": {
+                                    "uri": label.file.as_deref().unwrap_or(&self.file_name)
+                                },
+                                "region": {
+                                    "startLine": label.span.line,
+                                    "startColumn": label.span.column
+                                }
+                            }
+                        })
+                    })
+                    .collect();
+
+                let mut result = serde_json::json!({
                     "ruleId": d.code(),
                     "level": match d.severity {
                         Severity::Error => "error",
                         Severity::Warning => "warning",
-                        Severity::Info => "note",
+                        Severity::Info | Severity::Hint => "note",
                     },
                     "message": {
                         "text": d.message
@@ -112,49 +378,222 @@ impl OutputFormatter {
                     "locations": [{
                         "physicalLocation": location
                     }]
+                });
+                if !related.is_empty() {
+                    result["relatedLocations"] = serde_json::Value::Array(related);
+                }
+                if let Some(statement) = &d.statement {
+                    result["properties"] = serde_json::json!({
+                        "statementIndex": statement.index,
+                        "statementRegion": {
+                            "startLine": statement.span.line,
+                            "endLine": statement.span.end_line,
+                            "charOffset": statement.span.offset,
+                            "charLength": statement.span.length
+                        }
+                    });
+                }
+                if let Some(category) = self.category {
+                    result["properties"]["category"] = serde_json::json!(category);
+                }
+                result
+            })
+            .collect();
+
+        // One descriptor per rule reported, in order of first appearance
+        let mut kinds = Vec::new();
+        for d in diagnostics {
+            if !kinds.contains(&d.kind) {
+                kinds.push(d.kind);
+            }
+        }
+        let rules: Vec<serde_json::Value> = kinds
+            .into_iter()
+            .map(|kind| {
+                let help_uri = match &self.help_uri {
+                    Some(template) => template
+                        .replace("{code}", kind.code())
+                        .replace("{name}", kind.name()),
+                    None => rules::HELP_URI.to_string(),
+                };
+                serde_json::json!({
+                    "id": kind.code(),
+                    "name": kind.name(),
+                    "shortDescription": { "text": rules::summary(kind) },
+                    "help": {
+                        "text": rules::explanation(kind),
+                        "markdown": rules::explanation(kind)
+                    },
+                    "helpUri": help_uri
                 })
             })
             .collect();
 
-        let sarif = serde_json::json!({
+        let mut sarif = serde_json::json!({
             "$schema": "https://raw.githubusercontent.com/oasis-tcs/sarif-spec/master/Schemata/sarif-schema-2.1.0.json",
             "version": "2.1.0",
             "runs": [{
                 "tool": {
                     "driver": {
                         "name": "sqlsurge",
-                        "version": env!("CARGO_PKG_VERSION")
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rules
                     }
                 },
                 "results": results
             }]
         });
 
+        if let Some(dialect) = &self.dialect {
+            sarif["runs"][0]["properties"] = serde_json::json!({ "dialect": dialect });
+        }
+
         println!("{}", serde_json::to_string_pretty(&sarif).unwrap());
     }
 }
 
-/// Convert byte offset to line and column (1-indexed)
-fn offset_to_line_col(source: &str, offset: usize) -> (usize, usize) {
-    let mut line = 1;
-    let mut col = 1;
+/// Print the final summary line of `--format jsonl` output
+pub fn print_jsonl_summary(files: usize, errors: usize, warnings: usize, oversized: usize) {
+    let record = JsonlRecord::Summary {
+        version: FORMAT_VERSION,
+        files,
+        errors,
+        warnings,
+        oversized,
+    };
+    println!("{}", serde_json::to_string(&record).unwrap());
+}
+
+/// Print output columns of a query as JSON (`describe --format json`)
+pub fn print_describe_json(file: &str, columns: &[OutputColumn]) {
+    let report = JsonDescribe {
+        version: FORMAT_VERSION,
+        file,
+        columns: columns.iter().map(JsonOutputColumn::new).collect(),
+    };
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+}
 
-    for (i, ch) in source.char_indices() {
-        if i >= offset {
-            break;
+/// Tables and views a query file depends on (`deps`)
+pub struct FileDependencies {
+    pub file: String,
+    /// Schema-qualified names, sorted
+    pub dependencies: Vec<QualifiedName>,
+    /// Schema files defining the dependencies, sorted
+    pub schema_files: Vec<String>,
+}
+
+/// Print the dependencies of query files
+pub fn print_dependencies(files: &[FileDependencies], format: DepsFormat) {
+    match format {
+        DepsFormat::Human => {
+            for file in files {
+                println!("{}", file.file);
+                for name in &file.dependencies {
+                    println!("  {}", name);
+                }
+            }
         }
-        if ch == '\n' {
-            line += 1;
-            col = 1;
-        } else {
-            col += 1;
+        DepsFormat::Json => {
+            let report = JsonDeps {
+                version: FORMAT_VERSION,
+                files: files
+                    .iter()
+                    .map(|f| JsonFileDeps {
+                        file: &f.file,
+                        dependencies: f.dependencies.iter().map(|n| n.to_string()).collect(),
+                        schema_files: f.schema_files.iter().map(String::as_str).collect(),
+                    })
+                    .collect(),
+            };
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        }
+        DepsFormat::Makefile => {
+            for file in files {
+                let mut line = format!("{}:", make_escape(&file.file));
+                for schema_file in &file.schema_files {
+                    line.push(' ');
+                    line.push_str(&make_escape(schema_file));
+                }
+                println!("{}", line);
+            }
         }
     }
+}
 
-    (line, col)
+/// Escape a path for a make rule
+fn make_escape(path: &str) -> String {
+    path.replace(' ', "\\ ").replace('$', "$$")
 }
 
-/// Get a specific line from source (1-indexed)
-fn get_source_line(source: &str, line: usize) -> Option<&str> {
-    source.lines().nth(line.saturating_sub(1))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotate() {
+        assert_eq!(
+            annotate("SELECT nme FROM users", 8, 3),
+            (
+                "SELECT nme FROM users".to_string(),
+                "       ^^^".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_annotate_tab_indented_line() {
+        // Columns count a tab as one character
+        let (rendered, caret) = annotate("\t\tWHERE nme = 1", 9, 3);
+        assert_eq!(rendered, "        WHERE nme = 1");
+        assert_eq!(caret, "              ^^^");
+
+        // A span covering a tab underlines all of its cells
+        let (_, caret) = annotate("a\tb", 2, 2);
+        assert_eq!(caret, " ^^^^^");
+    }
+
+    #[test]
+    fn test_overlay_secondary_marks() {
+        let line = "SELECT x.id, x.name FROM users u";
+        let (_, caret) = annotate(line, 8, 1);
+        let marks = overlay(line, &caret, 14, 1);
+        assert_eq!(marks, "       ^     -");
+        // Carets are never covered
+        assert_eq!(overlay(line, &marks, 8, 2), "       ^-    -");
+        assert_eq!(overlay(line, "", 3, 2), "  --");
+    }
+
+    #[test]
+    fn test_excerpt_of_long_line() {
+        assert_eq!(excerpt("SELECT 1", 8), ("SELECT 1".to_string(), 0));
+
+        let line = format!("SELECT {} FROM t", "x, ".repeat(100));
+        let (text, shift) = excerpt(&line, 1);
+        assert_eq!(shift, 0);
+        assert!(text.starts_with("SELECT x, ") && text.ends_with("..."));
+        assert_eq!(text.chars().count(), MAX_EXCERPT_CHARS + 3);
+
+        // The column still points at the same text after the shift
+        let col = line.find("FROM").unwrap() + 1;
+        let (text, shift) = excerpt(&line, col);
+        assert!(text.starts_with("..."));
+        assert_eq!(&text[col - shift - 1..col - shift + 3], "FROM");
+    }
+
+    #[test]
+    fn test_annotate_last_character() {
+        assert_eq!(annotate("SELECT x", 8, 1).1, "       ^");
+        // The underline stops at the end of the line
+        assert_eq!(annotate("SELECT x", 8, 10).1, "       ^");
+    }
+
+    #[test]
+    fn test_annotate_column_past_end_of_line() {
+        // End positions can point just after the line, or further
+        assert_eq!(annotate("SELECT", 7, 1).1, "      ^");
+        assert_eq!(annotate("\tSELECT", 40, 3).1, "          ^");
+        assert_eq!(annotate("", 5, 0).1, "^");
+        assert_eq!(annotate("SELECT", 0, usize::MAX).1, "^^^^^^");
+    }
 }
@@ -0,0 +1,145 @@
+//! Profile summary (`check --profile`, `--profile-json`)
+//!
+//! Timings are collected while files are checked: schema building per schema
+//! file, and the analyzer's [`ProfileReport`] per query file. The summary
+//! lists the total and 95th percentile of each phase, the AST cache hits and
+//! misses (with `ast_cache` set) and the slowest files.
+
+use std::time::Duration;
+
+use sqlsurge_core::analyzer::{Phase, StatementProfile};
+use sqlsurge_core::json::{JsonFileProfile, JsonPhaseTiming, JsonProfile, FORMAT_VERSION};
+use sqlsurge_core::ProfileReport;
+
+/// Slowest files listed in the human summary
+const LISTED_FILES: usize = 10;
+
+/// Timings collected over a check run
+#[derive(Debug, Default)]
+pub struct Profiler {
+    /// Schema building time per schema file
+    schema: Vec<Duration>,
+    /// Analysis timings per query file, in check order
+    files: Vec<(String, ProfileReport)>,
+}
+
+impl Profiler {
+    pub fn add_schema_file(&mut self, elapsed: Duration) {
+        self.schema.push(elapsed);
+    }
+
+    pub fn add_file(&mut self, file: String, report: ProfileReport) {
+        self.files.push((file, report));
+    }
+
+    /// Total and p95 per phase, starting with schema building
+    fn phases(&self) -> Vec<(&'static str, Duration, Duration)> {
+        let mut phases = vec![summarize("schema", self.schema.clone())];
+        for phase in Phase::ALL {
+            let samples = match phase {
+                Phase::Parse => self.files.iter().map(|(_, r)| r.parse).collect(),
+                Phase::Names => self.statements().map(|s| s.names).collect(),
+                Phase::Types => self.statements().map(|s| s.types).collect(),
+                Phase::Rules => self.statements().map(|s| s.rules).collect(),
+            };
+            phases.push(summarize(phase.name(), samples));
+        }
+        phases
+    }
+
+    fn statements(&self) -> impl Iterator<Item = &StatementProfile> {
+        self.files.iter().flat_map(|(_, r)| &r.statements)
+    }
+
+    /// AST cache hits and misses over all query files
+    fn cache_counts(&self) -> (usize, usize) {
+        self.files.iter().fold((0, 0), |(hits, misses), (_, r)| {
+            (hits + r.cache_hits, misses + r.cache_misses)
+        })
+    }
+
+    /// Query files, slowest first
+    fn slowest(&self) -> Vec<&(String, ProfileReport)> {
+        let mut files: Vec<_> = self.files.iter().collect();
+        files.sort_by(|a, b| b.1.total().cmp(&a.1.total()).then_with(|| a.0.cmp(&b.0)));
+        files
+    }
+
+    /// Print the summary table to stderr
+    pub fn print_human(&self) {
+        let statements = self.statements().count();
+        eprintln!();
+        eprintln!(
+            "Profile ({} schema file(s), {} query file(s), {} statement(s))",
+            self.schema.len(),
+            self.files.len(),
+            statements
+        );
+        eprintln!("  {:<8} {:>12} {:>12}", "phase", "total", "p95");
+        for (name, total, p95) in self.phases() {
+            eprintln!("  {:<8} {:>12} {:>12}", name, ms(total), ms(p95));
+        }
+        let (hits, misses) = self.cache_counts();
+        if hits + misses > 0 {
+            eprintln!("  ast cache: {} hit(s), {} miss(es)", hits, misses);
+        }
+        let slowest = self.slowest();
+        if !slowest.is_empty() {
+            eprintln!("  slowest files:");
+            for (file, report) in slowest.into_iter().take(LISTED_FILES) {
+                eprintln!("  {:>12}  {}", ms(report.total()), file);
+            }
+        }
+    }
+
+    /// The summary as JSON
+    pub fn to_json(&self) -> String {
+        let (cache_hits, cache_misses) = self.cache_counts();
+        let profile = JsonProfile {
+            version: FORMAT_VERSION,
+            phases: self
+                .phases()
+                .into_iter()
+                .map(|(phase, total, p95)| JsonPhaseTiming {
+                    phase,
+                    total_ms: millis(total),
+                    p95_ms: millis(p95),
+                })
+                .collect(),
+            files: self
+                .slowest()
+                .into_iter()
+                .map(|(file, report)| JsonFileProfile {
+                    file,
+                    total_ms: millis(report.total()),
+                    statements: report.statements.len(),
+                })
+                .collect(),
+            cache_hits,
+            cache_misses,
+        };
+        serde_json::to_string_pretty(&profile).unwrap()
+    }
+}
+
+/// Total and 95th percentile (nearest rank) of `samples`
+fn summarize(
+    phase: &'static str,
+    mut samples: Vec<Duration>,
+) -> (&'static str, Duration, Duration) {
+    samples.sort();
+    let rank = (samples.len() * 95).div_ceil(100);
+    let p95 = samples
+        .get(rank.saturating_sub(1))
+        .copied()
+        .unwrap_or_default();
+    (phase, samples.iter().sum(), p95)
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn ms(duration: Duration) -> String {
+    format!("{:.3}ms", millis(duration))
+}
@@ -0,0 +1,266 @@
+//! Building the catalog from the configured schema sources
+//!
+//! `check` keeps the schema's diagnostics to report them by source under the
+//! `--schema-warnings` policy; the other commands only need the catalog.
+//! Either way the `[deprecated]` and `[functions]` config sections are
+//! applied to the built catalog.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Instant;
+
+use miette::Result;
+use sqlsurge_core::dialect::DialectSpec;
+use sqlsurge_core::schema::{Catalog, FunctionSig, QualifiedName, SchemaBuilder};
+use sqlsurge_core::{AnalysisReport, Diagnostic, Severity, SqlDialect, SqlType};
+
+use crate::args::{OutputFormat, SchemaWarnings};
+use crate::config::{Config, FunctionConfig};
+use crate::output::{OutputFormatter, Profiler};
+use crate::schema_source::SchemaSource;
+
+/// The catalog of `check`, with what its sources reported
+pub struct LoadedSchema {
+    pub catalog: Catalog,
+    /// Diagnostics with the name of the source they are in
+    pub diagnostics: Vec<(Option<String>, Diagnostic)>,
+    /// Name and content of each source, in order
+    pub sources: Vec<(String, String)>,
+}
+
+/// What became of the schema sources of `check`
+pub enum SchemaLoad {
+    Loaded(Box<LoadedSchema>),
+    /// A source failed to parse, which stops the run
+    Failed(FailedSource),
+}
+
+/// A schema source that failed to parse, with its diagnostics
+pub struct FailedSource {
+    pub name: String,
+    pub content: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Schema builder with the configured options
+fn builder(config: &Config, spec: DialectSpec) -> SchemaBuilder {
+    let mut builder = SchemaBuilder::with_dialect(spec.dialect)
+        .with_tinyint1_as_boolean(config.tinyint1_as_boolean.unwrap_or(true))
+        .with_strict_duplicates(config.strict_duplicates.unwrap_or(false))
+        .with_enabled_rules(config.enable.clone());
+    if let Some(version) = spec.version {
+        builder = builder.with_version(version);
+    }
+    builder
+}
+
+/// Build the catalog of `check` from `sources`, timing each in `profiler`
+///
+/// Statements skipped for failing to parse are reported, for
+/// `schema_parse_errors` to decide what becomes of them.
+pub fn load(
+    sources: &[SchemaSource],
+    config: &Config,
+    spec: DialectSpec,
+    mut profiler: Option<&mut Profiler>,
+) -> Result<SchemaLoad> {
+    let mut builder = builder(config, spec).with_skipped_statements(true);
+    let mut contents = Vec::new();
+    for source in sources {
+        let content = source.read()?;
+        let name = source.name();
+        let started = profiler.is_some().then(Instant::now);
+        let parsed = builder.parse_named(&name, &content);
+        if let (Some(profiler), Some(started)) = (profiler.as_deref_mut(), started) {
+            profiler.add_schema_file(started.elapsed());
+        }
+        if let Err(diagnostics) = parsed {
+            return Ok(SchemaLoad::Failed(FailedSource {
+                name,
+                content,
+                diagnostics,
+            }));
+        }
+        contents.push((name, content));
+    }
+    let (mut catalog, diagnostics) = builder.build_with_sources();
+    if let Some(dir) = &config.schema_sqlx {
+        add_sqlx_tables(&mut catalog, Path::new(dir))?;
+    }
+    apply_config(&mut catalog, config, spec.dialect)?;
+    Ok(SchemaLoad::Loaded(Box::new(LoadedSchema {
+        catalog,
+        diagnostics,
+        sources: contents,
+    })))
+}
+
+/// Build the catalog from the configured schema files
+///
+/// Returns `None` after printing the diagnostics if a schema file fails to parse.
+pub fn load_catalog(config: &Config, spec: DialectSpec) -> Result<Option<Catalog>> {
+    let mut builder = builder(config, spec);
+    for schema_source in &SchemaSource::collect(config)? {
+        let content = schema_source.read()?;
+        let source = schema_source.name();
+        if let Err(diags) = builder.parse_named(&source, &content) {
+            OutputFormatter::new(OutputFormat::Human, source).print_diagnostics(&diags, &content);
+            return Ok(None);
+        }
+    }
+    let mut catalog = builder.build().0;
+    apply_config(&mut catalog, config, spec.dialect)?;
+    Ok(Some(catalog))
+}
+
+/// Apply the `[deprecated]` and `[functions]` config sections
+fn apply_config(catalog: &mut Catalog, config: &Config, dialect: SqlDialect) -> Result<()> {
+    apply_deprecations(catalog, &config.deprecated);
+    apply_functions(catalog, &config.functions, dialect)
+}
+
+/// Add the tables derived from sqlx offline metadata that the schema files
+/// don't define
+fn add_sqlx_tables(catalog: &mut Catalog, dir: &Path) -> Result<()> {
+    let sqlx = Catalog::from_sqlx_metadata(dir).map_err(|err| miette::miette!("{}", err))?;
+    for name in sqlx.table_names() {
+        if !catalog.table_exists(&name) && !catalog.view_exists(&name) {
+            if let Some(table) = sqlx.get_table_arc(&name) {
+                catalog.add_table(table);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The diagnostics of the schema by schema source, in source order
+///
+/// Skipped statements are dropped or kept by `schema_parse_errors`. Under
+/// `--schema-warnings error`, warnings become errors.
+pub fn report(
+    diagnostics: Vec<(Option<String>, Diagnostic)>,
+    sources: &[(String, String)],
+    policy: SchemaWarnings,
+    config: &Config,
+) -> AnalysisReport {
+    let mut by_source: Vec<(String, Vec<Diagnostic>)> = sources
+        .iter()
+        .map(|(name, _)| (name.clone(), Vec::new()))
+        .collect();
+    for (source, mut diagnostic) in diagnostics {
+        if !config.apply_parse_error_policy(&mut diagnostic) {
+            continue;
+        }
+        if policy == SchemaWarnings::Error && diagnostic.severity == Severity::Warning {
+            diagnostic.severity = Severity::Error;
+        }
+        // Unnamed sources don't occur in the CLI; keep such diagnostics anyway
+        let name = source.unwrap_or_else(|| "<schema>".to_string());
+        match by_source.iter_mut().find(|(source, _)| *source == name) {
+            Some((_, list)) => list.push(diagnostic),
+            None => by_source.push((name, vec![diagnostic])),
+        }
+    }
+    let mut report = AnalysisReport::new();
+    for (name, diagnostics) in by_source {
+        if !diagnostics.is_empty() {
+            report.push(name, diagnostics);
+        }
+    }
+    report
+}
+
+/// Print the schema's report by source
+///
+/// Hidden warnings are only counted; errors, such as a column defined twice,
+/// are always printed.
+pub fn print_report(
+    report: &AnalysisReport,
+    sources: &[(String, String)],
+    policy: SchemaWarnings,
+    formatter: impl Fn(String) -> OutputFormatter,
+) {
+    for input in report.inputs() {
+        let diagnostics: Vec<Diagnostic> = input
+            .diagnostics
+            .iter()
+            .filter(|d| policy != SchemaWarnings::Hide || d.severity == Severity::Error)
+            .cloned()
+            .collect();
+        if diagnostics.is_empty() {
+            continue;
+        }
+        let content = sources
+            .iter()
+            .find(|(source, _)| *source == input.name)
+            .map_or("", |(_, content)| content.as_str());
+        formatter(input.name.clone())
+            .for_schema()
+            .print_diagnostics(&diagnostics, content);
+    }
+}
+
+/// Mark the tables and columns of the `[deprecated]` config section
+///
+/// `a.b` names column `b` of table `a`, or else table `b` of schema `a`.
+fn apply_deprecations(catalog: &mut Catalog, deprecated: &HashMap<String, String>) {
+    let mut entries: Vec<_> = deprecated.iter().collect();
+    entries.sort();
+    for (name, note) in entries {
+        let parts: Vec<&str> = name.split('.').collect();
+        let applied = match parts.as_slice() {
+            [table] => catalog.deprecate(&QualifiedName::new(*table), None, note),
+            [a, b] => {
+                catalog.deprecate(&QualifiedName::new(*a), Some(b), note)
+                    || catalog.deprecate(&QualifiedName::with_schema(*a, *b), None, note)
+            }
+            [schema, table, column] => catalog.deprecate(
+                &QualifiedName::with_schema(*schema, *table),
+                Some(column),
+                note,
+            ),
+            _ => false,
+        };
+        if !applied {
+            eprintln!(
+                "Warning: [deprecated] entry '{}' matches no table or column",
+                name
+            );
+        }
+    }
+}
+
+/// Add the signatures of the `[functions]` config section
+fn apply_functions(
+    catalog: &mut Catalog,
+    functions: &HashMap<String, FunctionConfig>,
+    dialect: SqlDialect,
+) -> Result<()> {
+    let mut entries: Vec<_> = functions.iter().collect();
+    entries.sort_by_key(|(name, _)| *name);
+    for (name, function) in entries {
+        let parse_type = |type_name: &String| {
+            SqlType::parse(type_name, dialect)
+                .map_err(|e| miette::miette!("[functions] entry '{}': {}", name, e))
+        };
+        let args = function
+            .args
+            .iter()
+            .map(parse_type)
+            .collect::<Result<Vec<_>>>()?;
+        let returns = match &function.returns {
+            Some(returns) => parse_type(returns)?,
+            None => SqlType::Unknown,
+        };
+        if function.variadic && args.is_empty() {
+            miette::bail!(
+                "[functions] entry '{}' is variadic but has no arguments",
+                name
+            );
+        }
+        let mut sig = FunctionSig::new(name, args, returns);
+        sig.variadic = function.variadic;
+        catalog.add_function(sig);
+    }
+    Ok(())
+}
@@ -0,0 +1,99 @@
+//! Where schema SQL is read from
+//!
+//! Besides files (`--schema`, `--schema-dir`, `schema` in sqlsurge.toml),
+//! a schema can be piped in with `--schema -` or given as a string with
+//! `--schema-inline` or `schema_inline`, e.g. when CI generates it with a
+//! migration tool. Diagnostics name those sources `<stdin>` and `<inline>`.
+
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use miette::{IntoDiagnostic, Result};
+
+use crate::config::Config;
+
+/// `--schema` value that reads the schema from stdin
+const STDIN: &str = "-";
+
+/// A source of schema SQL
+pub enum SchemaSource {
+    File(PathBuf),
+    Stdin,
+    Inline(String),
+}
+
+impl SchemaSource {
+    /// The configured schema sources: files, then the directory's files,
+    /// then the inline schema
+    pub fn collect(config: &Config) -> Result<Vec<SchemaSource>> {
+        let mut sources = Vec::new();
+        for schema in &config.schema {
+            let source = if schema == STDIN {
+                SchemaSource::Stdin
+            } else {
+                SchemaSource::File(PathBuf::from(schema))
+            };
+            sources.push(source);
+        }
+
+        if let Some(dir) = &config.schema_dir {
+            let pattern = format!("{}/**/*.sql", dir);
+            for path in glob::glob(&pattern).into_diagnostic()?.flatten() {
+                sources.push(SchemaSource::File(path));
+            }
+        }
+
+        if let Some(sql) = &config.schema_inline {
+            sources.push(SchemaSource::Inline(sql.clone()));
+        }
+
+        if sources.is_empty() && config.schema_sqlx.is_none() {
+            miette::bail!(
+                "No schema files specified. Use --schema, --schema-dir, --schema-inline, \
+                 --schema-sqlx, or configure in sqlsurge.toml (or check without a schema \
+                 with --no-schema)"
+            );
+        }
+        if sources.iter().any(|s| matches!(s, SchemaSource::Stdin))
+            && config.files.iter().any(|f| f == STDIN)
+        {
+            miette::bail!(
+                "The schema and the queries can't both be read from stdin; \
+                 pass one of them as a file"
+            );
+        }
+        Ok(sources)
+    }
+
+    /// Name of the source in diagnostics
+    pub fn name(&self) -> String {
+        match self {
+            SchemaSource::File(path) => path.display().to_string(),
+            SchemaSource::Stdin => "<stdin>".to_string(),
+            SchemaSource::Inline(_) => "<inline>".to_string(),
+        }
+    }
+
+    /// The file, if the source is one
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            SchemaSource::File(path) => Some(path),
+            SchemaSource::Stdin | SchemaSource::Inline(_) => None,
+        }
+    }
+
+    pub fn read(&self) -> Result<String> {
+        match self {
+            SchemaSource::File(path) => fs::read_to_string(path).into_diagnostic(),
+            SchemaSource::Stdin => {
+                let mut sql = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut sql)
+                    .into_diagnostic()?;
+                Ok(sql)
+            }
+            SchemaSource::Inline(sql) => Ok(sql.clone()),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! Machine-readable run status (`check --status-file`)
+//!
+//! Written at the end of every run, including runs that stop on an error,
+//! so CI wrappers can rely on the file being there. The file is written to a
+//! temporary path next to it and renamed into place, so readers never see a
+//! partial document.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Serialize;
+use sqlsurge_core::analyzer::SeverityCounts;
+use sqlsurge_core::AnalysisReport;
+
+/// Version of the status file format
+const STATUS_VERSION: u32 = 1;
+
+/// Summary of a check run
+#[derive(Debug, Default, Serialize)]
+pub struct RunStatus {
+    pub format_version: u32,
+    /// sqlsurge version
+    pub tool_version: &'static str,
+    /// No errors were found and the run completed
+    pub success: bool,
+    /// The run was incomplete or less reliable than usual: the schema was
+    /// empty or produced warnings, files were skipped (unchanged, or over a
+    /// size limit) or parsed from the AST cache, or the analysis of a
+    /// statement was cut short (W0002) or skipped (W0011)
+    pub degraded: bool,
+    /// Why the run stopped early, if it did
+    pub error: Option<String>,
+    /// Diagnostics by severity
+    pub diagnostics: SeverityCounts,
+    /// Diagnostics by rule code
+    pub rules: BTreeMap<String, usize>,
+    pub files: FileCounts,
+    /// Warnings produced while building the schema
+    pub schema_warnings: usize,
+    /// The schema defined no table or view
+    pub empty_schema: bool,
+    /// Queries were checked without a schema (`--no-schema`)
+    pub no_schema: bool,
+    pub wall_time_ms: f64,
+}
+
+/// Query file counts
+#[derive(Debug, Default, Serialize)]
+pub struct FileCounts {
+    /// Files analyzed
+    pub checked: usize,
+    /// Files not analyzed (unchanged with `--changed-only`)
+    pub skipped: usize,
+    /// Files with statements taken from the AST cache (`ast_cache`)
+    pub cached: usize,
+    /// Files not analyzed for being over `max_file_size` or
+    /// `max_statements_per_file`
+    pub oversized: usize,
+}
+
+impl RunStatus {
+    pub fn new() -> Self {
+        Self {
+            format_version: STATUS_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION"),
+            ..Self::default()
+        }
+    }
+
+    /// Take the diagnostic counts of the files reported so far
+    pub fn record(&mut self, report: &AnalysisReport) {
+        self.diagnostics = *report.severity_counts();
+        self.rules = report.rule_counts().clone();
+    }
+
+    /// Fill in the outcome of the run
+    pub fn finish<E: std::fmt::Display>(&mut self, result: &Result<bool, E>, elapsed: Duration) {
+        match result {
+            Ok(has_errors) => self.success = !has_errors,
+            Err(e) => {
+                self.success = false;
+                self.error = Some(e.to_string());
+            }
+        }
+        self.degraded = self.empty_schema
+            || self.no_schema
+            || self.schema_warnings > 0
+            || self.files.skipped > 0
+            || self.files.cached > 0
+            || self.files.oversized > 0
+            || self.rules.contains_key("W0002")
+            || self.rules.contains_key("W0011");
+        self.wall_time_ms = elapsed.as_secs_f64() * 1000.0;
+    }
+
+    /// Write the status as JSON, atomically
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+        fs::write(&temp, json + "\n")?;
+        fs::rename(&temp, path)
+    }
+}
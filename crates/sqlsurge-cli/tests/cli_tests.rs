@@ -0,0 +1,1728 @@
+//! End-to-end tests running the `sqlsurge` binary
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+/// Write `files` into a fresh directory named after the test
+///
+/// The directory is outside the repository, whose own sqlsurge.toml would
+/// otherwise be picked up by the tests that don't write one.
+fn fixture_dir(test: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join("sqlsurge-cli-tests").join(test);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    for (name, contents) in files {
+        std::fs::write(dir.join(name), contents).unwrap();
+    }
+    dir
+}
+
+/// Run `sqlsurge` with `args` in `dir`, where it looks for sqlsurge.toml
+fn sqlsurge(dir: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_sqlsurge"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+const SCHEMA: &str = "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL);";
+
+#[test]
+fn test_check_reports_type_mismatch() {
+    let dir = fixture_dir(
+        "check_reports_type_mismatch",
+        &[
+            ("schema.sql", SCHEMA),
+            ("query.sql", "SELECT id FROM users WHERE id = 'text';"),
+        ],
+    );
+
+    let output = sqlsurge(&dir, &["check", "--schema", "schema.sql", "query.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(stderr.contains("E0003"), "{}", stderr);
+}
+
+#[test]
+fn test_check_disable_type_mismatch() {
+    let dir = fixture_dir(
+        "check_disable_type_mismatch",
+        &[
+            ("schema.sql", SCHEMA),
+            ("query.sql", "SELECT id FROM users WHERE id = 'text';"),
+        ],
+    );
+
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "--schema",
+            "schema.sql",
+            "--disable",
+            "E0003",
+            "query.sql",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+    assert!(!stderr.contains("E0003"), "{}", stderr);
+}
+
+#[test]
+fn test_check_file_header_selects_dialect() {
+    let mysql_query = "SELECT `name` FROM users;";
+    let dir = fixture_dir(
+        "check_file_header_selects_dialect",
+        &[
+            ("schema.sql", SCHEMA),
+            (
+                "mysql.sql",
+                &format!("-- sqlsurge: dialect=mysql\n{}", mysql_query),
+            ),
+            ("postgres.sql", "SELECT \"name\" FROM users;"),
+            ("undeclared.sql", mysql_query),
+        ],
+    );
+
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "--schema",
+            "schema.sql",
+            "mysql.sql",
+            "postgres.sql",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+
+    // The same query fails under the configured dialect
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "--schema",
+            "schema.sql",
+            "--format",
+            "json",
+            "mysql.sql",
+            "undeclared.sql",
+        ],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("E1000"), "{}", stdout);
+    assert!(
+        stdout.contains("\"file\": \"undeclared.sql\""),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("\"dialect\": \"postgresql\""), "{}", stdout);
+    assert!(!stdout.contains("mysql.sql"), "{}", stdout);
+}
+
+#[test]
+fn test_check_file_header_unknown_key_warns() {
+    let dir = fixture_dir(
+        "check_file_header_unknown_key_warns",
+        &[
+            ("schema.sql", SCHEMA),
+            (
+                "query.sql",
+                "-- sqlsurge: dialect=mysql strict=yes\nSELECT id FROM users;",
+            ),
+        ],
+    );
+
+    let output = sqlsurge(&dir, &["check", "--schema", "schema.sql", "query.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+    assert!(stderr.contains("unknown key 'strict'"), "{}", stderr);
+}
+
+#[test]
+fn test_check_dedupe_across_files() {
+    let query = "SELECT nme FROM users;";
+    let dir = fixture_dir(
+        "check_dedupe_across_files",
+        &[
+            ("schema.sql", SCHEMA),
+            ("tenant_a.sql", query),
+            ("tenant_b.sql", query),
+            (
+                "tenant_c.sql",
+                "SELECT id FROM users WHERE id = 'x';\nSELECT nme FROM users;",
+            ),
+        ],
+    );
+    let files = ["tenant_a.sql", "tenant_b.sql", "tenant_c.sql"];
+
+    let mut args = vec!["check", "--schema", "schema.sql", "--dedupe"];
+    args.extend(files);
+    let output = sqlsurge(&dir, &args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert_eq!(stderr.matches("[E0002]: ").count(), 1, "{}", stderr);
+    assert!(
+        stderr.contains("found in 3 file(s): tenant_a.sql, tenant_b.sql, tenant_c.sql"),
+        "{}",
+        stderr
+    );
+    // The summary counts every diagnostic
+    assert!(
+        stderr.contains("Found 4 error(s), 0 warning(s) in 3 file(s) (2 distinct problem(s))"),
+        "{}",
+        stderr
+    );
+
+    let mut args = vec!["check", "--schema", "schema.sql", "--group-by", "rule"];
+    args.extend(files);
+    let output = sqlsurge(&dir, &args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let column_rule = stderr.find("E0002 column-not-found").expect(&stderr);
+    let type_rule = stderr.find("E0003 type-mismatch").expect(&stderr);
+    assert!(column_rule < type_rule, "{}", stderr);
+    assert_eq!(stderr.matches("[E0002]: ").count(), 3, "{}", stderr);
+
+    // Machine-readable output stays exhaustive
+    let mut args = vec!["check", "--schema", "schema.sql", "--dedupe", "-f", "jsonl"];
+    args.extend(files);
+    let output = sqlsurge(&dir, &args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.matches("\"E0002\"").count(), 3, "{}", stdout);
+}
+
+#[test]
+fn test_check_pedantic_overrides_and_fixes() {
+    let schema = format!("{}\nCREATE TABLE visits (user_id BIGINT NOT NULL);", SCHEMA);
+    let query = "SELECT name FROM users u, visits v WHERE u.id = v.user_id;";
+    let dir = fixture_dir(
+        "check_pedantic_overrides_and_fixes",
+        &[
+            ("schema.sql", &schema),
+            (
+                "sqlsurge.toml",
+                "schema = [\"schema.sql\"]\nstrictness = \"pedantic\"\n\n\
+                 [[overrides]]\nfiles = [\"legacy_*.sql\"]\nstrictness = \"default\"\n",
+            ),
+            ("report.sql", query),
+            ("legacy_report.sql", query),
+        ],
+    );
+
+    let output = sqlsurge(&dir, &["check", "report.sql", "legacy_report.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+    assert_eq!(stderr.matches("[W0008]: ").count(), 1, "{}", stderr);
+    assert!(stderr.contains("--> report.sql:1:42"), "{}", stderr);
+    assert!(
+        stderr.contains("= fix: Cast to bigint: insert `::bigint` at 1:46"),
+        "{}",
+        stderr
+    );
+
+    let output = sqlsurge(&dir, &["check", "-f", "json", "report.sql"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\"fixes\""), "{}", stdout);
+    assert!(
+        stdout.contains("\"replacement\": \"::bigint\""),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_check_unknown_alias_labels_other_references() {
+    let dir = fixture_dir(
+        "check_unknown_alias_labels_other_references",
+        &[
+            ("schema.sql", SCHEMA),
+            (
+                "query.sql",
+                "SELECT x.id, x.name\nFROM users u\nWHERE x.id > 1;",
+            ),
+        ],
+    );
+
+    let output = sqlsurge(&dir, &["check", "--schema", "schema.sql", "query.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stderr.matches("[E0001]").count(), 1, "{}", stderr);
+    assert!(
+        stderr.contains(
+            "  1 | SELECT x.id, x.name\n   |        ^     -\n  3 | WHERE x.id > 1;\n   |       -\n"
+        ),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("= note: 'x' is also referenced here (1:14, 3:7)"),
+        "{}",
+        stderr
+    );
+
+    let output = sqlsurge(
+        &dir,
+        &["check", "--schema", "schema.sql", "-f", "json", "query.sql"],
+    );
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let diagnostics = report["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1, "{}", report);
+    let labels = diagnostics[0]["labels"].as_array().unwrap();
+    let positions: Vec<(u64, u64)> = labels
+        .iter()
+        .map(|l| (l["line"].as_u64().unwrap(), l["column"].as_u64().unwrap()))
+        .collect();
+    assert_eq!(positions, vec![(1, 14), (3, 7)]);
+}
+
+#[test]
+fn test_check_column_not_found_notes_schema_location() {
+    let dir = fixture_dir(
+        "check_column_not_found_notes_schema_location",
+        &[
+            ("schema.sql", SCHEMA),
+            ("query.sql", "SELECT nickname FROM users;"),
+        ],
+    );
+
+    let output = sqlsurge(&dir, &["check", "--schema", "schema.sql", "query.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("= note: table 'users' defined here\n     --> schema.sql:1:14"),
+        "{}",
+        stderr
+    );
+
+    let args = [
+        "check",
+        "--schema",
+        "schema.sql",
+        "-f",
+        "sarif",
+        "query.sql",
+    ];
+    let output = sqlsurge(&dir, &args);
+    let sarif: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let related = &sarif["runs"][0]["results"][0]["relatedLocations"][0];
+    assert_eq!(
+        related["physicalLocation"]["artifactLocation"]["uri"],
+        "schema.sql"
+    );
+    assert_eq!(related["physicalLocation"]["region"]["startLine"], 1);
+}
+
+#[test]
+fn test_check_reports_statement_of_diagnostic() {
+    let dir = fixture_dir(
+        "check_reports_statement_of_diagnostic",
+        &[
+            ("schema.sql", SCHEMA),
+            (
+                "query.sql",
+                "SELECT id FROM users;\nSELECT nickname\n  FROM users;\nSELECT name FROM users;\n",
+            ),
+        ],
+    );
+
+    let output = sqlsurge(
+        &dir,
+        &["check", "--schema", "schema.sql", "-f", "json", "query.sql"],
+    );
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let diagnostics = report["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1, "{}", report);
+    let statement = &diagnostics[0]["statement"];
+    assert_eq!(statement["index"], 1, "{}", report);
+    assert_eq!(statement["start_offset"], 22);
+    assert_eq!(statement["end_offset"], 50);
+    assert_eq!(statement["line"], 2);
+    assert_eq!(statement["end_line"], 3);
+
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "--schema",
+            "schema.sql",
+            "-f",
+            "sarif",
+            "query.sql",
+        ],
+    );
+    let sarif: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let properties = &sarif["runs"][0]["results"][0]["properties"];
+    assert_eq!(properties["statementIndex"], 1, "{}", sarif);
+    assert_eq!(properties["statementRegion"]["startLine"], 2);
+    assert_eq!(properties["statementRegion"]["charLength"], 28);
+}
+
+#[test]
+fn test_check_placeholders_from_config() {
+    let query = "SELECT name FROM users WHERE id = :user_id AND name = :'name';";
+    let dir = fixture_dir(
+        "check_placeholders_from_config",
+        &[
+            ("schema.sql", SCHEMA),
+            ("query.sql", query),
+            ("sqlsurge.toml", "placeholders = [\"psql\"]\n"),
+        ],
+    );
+
+    let output = sqlsurge(&dir, &["check", "--schema", "schema.sql", "query.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+
+    std::fs::write(dir.join("sqlsurge.toml"), "").unwrap();
+    let output = sqlsurge(&dir, &["check", "--schema", "schema.sql", "query.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("[E1000]: "), "{}", stderr);
+}
+
+#[test]
+fn test_deps_json_and_makefile() {
+    let dir = fixture_dir(
+        "deps_json_and_makefile",
+        &[
+            ("users.sql", SCHEMA),
+            (
+                "orders.sql",
+                "CREATE TABLE orders (id INTEGER PRIMARY KEY, user_id INTEGER NOT NULL);\n\
+                 CREATE VIEW user_orders AS SELECT u.name, o.id FROM users u JOIN orders o ON o.user_id = u.id;",
+            ),
+            ("report.sql", "SELECT name FROM user_orders;"),
+            (
+                "names.sql",
+                "WITH orders AS (SELECT 1 AS id) SELECT name FROM users, orders;",
+            ),
+        ],
+    );
+
+    let args = [
+        "deps",
+        "--schema",
+        "users.sql",
+        "--schema",
+        "orders.sql",
+        "-f",
+        "json",
+        "report.sql",
+        "names.sql",
+    ];
+    let output = sqlsurge(&dir, &args);
+    assert_eq!(output.status.code(), Some(0));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["files"][0]["file"], "report.sql");
+    assert_eq!(
+        json["files"][0]["dependencies"],
+        serde_json::json!(["public.orders", "public.user_orders", "public.users"])
+    );
+    // The CTE shadows the orders table
+    assert_eq!(
+        json["files"][1]["dependencies"],
+        serde_json::json!(["public.users"])
+    );
+
+    let mut args = args;
+    args[6] = "makefile";
+    let output = sqlsurge(&dir, &args);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "report.sql: orders.sql users.sql\nnames.sql: users.sql\n"
+    );
+}
+
+#[test]
+fn test_check_deprecated_from_config_and_grandfathered() {
+    let query = "SELECT name FROM users;";
+    let dir = fixture_dir(
+        "check_deprecated_from_config_and_grandfathered",
+        &[
+            ("schema.sql", SCHEMA),
+            (
+                "sqlsurge.toml",
+                "schema = [\"schema.sql\"]\n\n\
+                 [deprecated]\n\"users.name\" = \"use display_name\"\n\"users.nope\" = \"\"\n\n\
+                 [[overrides]]\nfiles = [\"legacy_*.sql\"]\nseverity = { W0010 = \"off\" }\n",
+            ),
+            ("report.sql", query),
+            ("legacy_report.sql", query),
+        ],
+    );
+
+    let output = sqlsurge(&dir, &["check", "report.sql", "legacy_report.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // Warnings don't fail the check
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+    assert!(
+        stderr.contains("[deprecated] entry 'users.nope' matches no table or column"),
+        "{}",
+        stderr
+    );
+    assert_eq!(stderr.matches("[W0010]: ").count(), 1, "{}", stderr);
+    assert!(stderr.contains("--> report.sql:1:8"), "{}", stderr);
+    assert!(stderr.contains("use display_name"), "{}", stderr);
+}
+
+#[test]
+fn test_check_write_policy_overrides() {
+    let dir = fixture_dir(
+        "check_write_policy_overrides",
+        &[
+            ("schema.sql", SCHEMA),
+            (
+                "sqlsurge.toml",
+                "schema = [\"schema.sql\"]\npolicy = \"ddl-forbidden\"\n\n\
+                 [[overrides]]\nfiles = [\"report_*.sql\"]\npolicy = \"read-only\"\n",
+            ),
+            (
+                "report_active.sql",
+                "SELECT id FROM users;\nDELETE FROM users;",
+            ),
+            ("cleanup.sql", "DELETE FROM users;"),
+            ("reindex.sql", "SELECT id FROM users;\nDROP TABLE users;"),
+        ],
+    );
+
+    let output = sqlsurge(
+        &dir,
+        &["check", "report_active.sql", "cleanup.sql", "reindex.sql"],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert_eq!(stderr.matches("[E0015]: ").count(), 2, "{}", stderr);
+    assert!(
+        stderr.contains("Data-modifying statements are not allowed by the 'read-only' policy"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("--> report_active.sql:2:1"), "{}", stderr);
+    assert!(
+        stderr.contains("Schema changes are not allowed by the 'ddl-forbidden' policy"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("--> reindex.sql:2:1"), "{}", stderr);
+}
+
+#[test]
+fn test_check_profile() {
+    let dir = fixture_dir(
+        "check_profile",
+        &[
+            ("schema.sql", SCHEMA),
+            ("a.sql", "SELECT id FROM users;\nSELECT name FROM users;"),
+            ("b.sql", "SELECT nme FROM users;"),
+        ],
+    );
+
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "--schema",
+            "schema.sql",
+            "a.sql",
+            "b.sql",
+            "--profile-json",
+            "profile.json",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(
+        stderr.contains("Profile (1 schema file(s), 2 query file(s), 3 statement(s))"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("slowest files:"), "{}", stderr);
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("profile.json")).unwrap()).unwrap();
+    assert_eq!(json["version"], 1);
+    let phases: Vec<&str> = json["phases"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["phase"].as_str().unwrap())
+        .collect();
+    assert_eq!(phases, ["schema", "parse", "names", "types", "rules"]);
+    let files = json["files"].as_array().unwrap();
+    assert_eq!(files.len(), 2);
+    assert!(files
+        .iter()
+        .any(|f| f["file"] == "a.sql" && f["statements"] == 2));
+    assert_eq!(json["cache_hits"], 0);
+    assert!(!stderr.contains("ast cache:"), "{}", stderr);
+
+    // With the AST cache, a file repeating another is not parsed again
+    std::fs::write(dir.join("sqlsurge.toml"), "ast_cache = 100\n").unwrap();
+    std::fs::copy(dir.join("a.sql"), dir.join("c.sql")).unwrap();
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "--schema",
+            "schema.sql",
+            "a.sql",
+            "b.sql",
+            "c.sql",
+            "--profile-json",
+            "profile.json",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(
+        stderr.contains("ast cache: 2 hit(s), 3 miss(es)"),
+        "{}",
+        stderr
+    );
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("profile.json")).unwrap()).unwrap();
+    assert_eq!(json["cache_hits"], 2);
+    assert_eq!(json["cache_misses"], 3);
+}
+
+/// Run git in `dir`, panicking on failure
+fn git(dir: &PathBuf, args: &[&str]) {
+    let status = Command::new("git")
+        .current_dir(dir)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+#[test]
+fn test_check_changed_only() {
+    let broken = "SELECT nme FROM users;";
+    let dir = fixture_dir(
+        "check_changed_only",
+        &[
+            ("schema.ddl", SCHEMA),
+            ("a.sql", broken),
+            ("b.sql", broken),
+            ("c.sql", "SELECT id FROM users;"),
+            ("gone.sql", "SELECT id FROM users;"),
+        ],
+    );
+    git(&dir, &["init", "-q", "-b", "main"]);
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-q", "-m", "base"]);
+    git(&dir, &["checkout", "-q", "-b", "feature"]);
+    std::fs::write(dir.join("a.sql"), format!("{}\n", broken)).unwrap();
+    std::fs::remove_file(dir.join("gone.sql")).unwrap();
+    let args = [
+        "check",
+        "--schema",
+        "schema.ddl",
+        "*.sql",
+        "--changed-only",
+        "--base",
+        "main",
+    ];
+
+    // Only a.sql changed; b.sql has the same error but is skipped
+    let output = sqlsurge(&dir, &args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert_eq!(stderr.matches("[E0002]: ").count(), 1, "{}", stderr);
+    assert!(stderr.contains("--> a.sql:1:8"), "{}", stderr);
+    assert!(
+        stderr.contains("Found 1 error(s), 0 warning(s) in 1 file(s), 2 unchanged file(s) skipped"),
+        "{}",
+        stderr
+    );
+
+    // A schema change checks everything
+    std::fs::write(dir.join("schema.ddl"), format!("{}\n", SCHEMA)).unwrap();
+    let output = sqlsurge(&dir, &args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Notice: schema files changed since main; checking all files"),
+        "{}",
+        stderr
+    );
+    assert_eq!(stderr.matches("[E0002]: ").count(), 2, "{}", stderr);
+
+    // An unknown base falls back to a full run
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "-s",
+            "schema.ddl",
+            "c.sql",
+            "--changed-only",
+            "--base",
+            "nope",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+    assert!(stderr.contains("Notice: git diff"), "{}", stderr);
+    assert!(stderr.contains("checking all files"), "{}", stderr);
+    assert!(
+        stderr.contains("All 1 file(s) passed validation\n"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_check_status_file() {
+    let dir = fixture_dir(
+        "check_status_file",
+        &[
+            ("schema.sql", SCHEMA),
+            ("a.sql", "SELECT nme FROM users;"),
+            ("b.sql", "CREATE TABLE users AS SELECT 1 AS id;"),
+            ("c.sql", "SELECT id FROM users;"),
+        ],
+    );
+    let read_status = || -> serde_json::Value {
+        serde_json::from_str(&std::fs::read_to_string(dir.join("status.json")).unwrap()).unwrap()
+    };
+
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "-s",
+            "schema.sql",
+            "a.sql",
+            "b.sql",
+            "c.sql",
+            "--status-file",
+            "status.json",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    let status = read_status();
+    assert_eq!(status["format_version"], 1);
+    assert_eq!(status["tool_version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(status["success"], false);
+    assert_eq!(status["degraded"], false);
+    assert!(status["error"].is_null());
+    assert_eq!(
+        status["diagnostics"],
+        serde_json::json!({ "error": 1, "warning": 1, "info": 0, "hint": 0 })
+    );
+    assert_eq!(
+        status["rules"],
+        serde_json::json!({ "E0002": 1, "E0009": 1 })
+    );
+    assert_eq!(
+        status["files"],
+        serde_json::json!({ "checked": 3, "skipped": 0, "cached": 0, "oversized": 0 })
+    );
+    assert_eq!(status["schema_warnings"], 0);
+    assert!(status["wall_time_ms"].as_f64().unwrap() > 0.0);
+    assert!(!dir.join("status.json.tmp").exists());
+
+    // A run that fails partway still writes the file
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "-s",
+            "schema.sql",
+            "c.sql",
+            "missing.sql",
+            "--status-file",
+            "status.json",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(2));
+    let status = read_status();
+    assert_eq!(status["success"], false);
+    assert!(status["error"].is_string(), "{}", status);
+    assert_eq!(status["files"]["checked"], 1);
+    // A file repeating another is parsed from the AST cache
+    std::fs::write(dir.join("sqlsurge.toml"), "ast_cache = 100\n").unwrap();
+    std::fs::copy(dir.join("c.sql"), dir.join("d.sql")).unwrap();
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "-s",
+            "schema.sql",
+            "c.sql",
+            "d.sql",
+            "--status-file",
+            "status.json",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(0));
+    let status = read_status();
+    assert_eq!(status["success"], true);
+    assert_eq!(status["degraded"], true);
+    assert_eq!(status["files"]["checked"], 2);
+    assert_eq!(status["files"]["cached"], 1);
+}
+
+#[test]
+fn test_check_counts_oversized_statements() {
+    let big = format!(
+        "INSERT INTO users (id, name) VALUES {};\nSELECT nope FROM users;\n",
+        (0..100)
+            .map(|i| format!("({}, 'n{}')", i, i))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let dir = fixture_dir(
+        "oversized_statements",
+        &[
+            ("schema.sql", SCHEMA),
+            ("sqlsurge.toml", "max_statement_bytes = 1000\n"),
+            ("query.sql", &big),
+        ],
+    );
+
+    let output = sqlsurge(&dir, &["check", "--schema", "schema.sql", "query.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(stderr.contains("[W0011]: Statement of "), "{}", stderr);
+    // The statement after the skipped one is still checked
+    assert!(stderr.contains("--> query.sql:2:8"), "{}", stderr);
+    assert!(
+        stderr.contains(
+            "Found 1 error(s), 1 warning(s) in 1 file(s), 1 oversized statement(s) skipped"
+        ),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_check_skips_oversized_files() {
+    // A generated dump well over the limit, with an error that would be found
+    let dump: String = (0..500)
+        .map(|i| format!("INSERT INTO users (id, nme) VALUES ({}, 'n{}');\n", i, i))
+        .collect();
+    let dir = fixture_dir(
+        "oversized_files",
+        &[
+            ("schema.sql", SCHEMA),
+            ("query.sql", "SELECT id FROM users;\n"),
+            ("dump.sql", &dump),
+        ],
+    );
+    let read_status = || -> serde_json::Value {
+        serde_json::from_str(&std::fs::read_to_string(dir.join("status.json")).unwrap()).unwrap()
+    };
+
+    // Matched by a glob, the dump is skipped with one warning
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "-s",
+            "schema.sql",
+            "--max-file-size",
+            "10000",
+            "--status-file",
+            "status.json",
+            "*.sql",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+    assert!(
+        stderr.contains("[W0019]: Input of 24.2 KiB exceeds the size limit of 9.8 KiB"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("--> dump.sql:1:1"), "{}", stderr);
+    assert!(!stderr.contains("E0002"), "{}", stderr);
+    assert!(
+        stderr.contains("in 3 file(s), 1 file(s) skipped due to size limits"),
+        "{}",
+        stderr
+    );
+    let status = read_status();
+    assert_eq!(status["files"]["checked"], 2);
+    assert_eq!(status["files"]["oversized"], 1);
+    assert_eq!(status["degraded"], true);
+
+    // The statement count limit works the same, and shows in JSONL
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "-s",
+            "schema.sql",
+            "--max-statements-per-file",
+            "100",
+            "-f",
+            "jsonl",
+            "*.sql",
+        ],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Input of 500 statements exceeds the limit of 100"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("\"oversized\":1"), "{}", stdout);
+
+    // Named explicitly, it is checked anyway, after a warning
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "-s",
+            "schema.sql",
+            "--max-file-size",
+            "10000",
+            "dump.sql",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(
+        stderr.contains(
+            "Warning: dump.sql: Input of 24.2 KiB exceeds the size limit of 9.8 KiB \
+             (`max_file_size`); checking it anyway as it was named explicitly"
+        ),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("E0002"), "{}", stderr);
+    assert!(!stderr.contains("W0019"), "{}", stderr);
+    assert!(!stderr.contains("skipped due to size limits"), "{}", stderr);
+
+    // A skipped file is not read past its start: binary data after it is
+    // never decoded
+    let mut binary = dump.clone().into_bytes();
+    binary.extend([0xff, 0xfe, 0x00].repeat(1000));
+    std::fs::write(dir.join("dump.sql"), binary).unwrap();
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "-s",
+            "schema.sql",
+            "--max-file-size",
+            "10000",
+            "*.sql",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+    assert!(stderr.contains("[W0019]: Input of 27.1 KiB"), "{}", stderr);
+    assert!(stderr.contains("--> dump.sql:1:1"), "{}", stderr);
+}
+
+#[test]
+fn test_check_no_schema() {
+    let dir = fixture_dir(
+        "check_no_schema",
+        &[
+            ("typo.sql", "SELECT id, nme FROM users WHERE emial = 'a';\n"),
+            (
+                "missing_semicolon.sql",
+                "SELECT id FROM users\nSELECT nme FROM users;\n",
+            ),
+        ],
+    );
+
+    let output = sqlsurge(&dir, &["check", "--no-schema", "typo.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+    assert!(
+        stderr.contains("All 1 file(s) passed validation; schema validation skipped (--no-schema)"),
+        "{}",
+        stderr
+    );
+
+    let args = ["check", "--no-schema", "typo.sql", "missing_semicolon.sql"];
+    let output = sqlsurge(&dir, &args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert_eq!(stderr.matches("[E1000]: ").count(), 1, "{}", stderr);
+    assert!(!stderr.contains("[E0002]"), "{}", stderr);
+    assert!(
+        stderr.contains("Found 1 error(s), 0 warning(s) in 2 file(s); schema validation skipped"),
+        "{}",
+        stderr
+    );
+
+    let output = sqlsurge(
+        &dir,
+        &["check", "--no-schema", "-s", "schema.sql", "typo.sql"],
+    );
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_check_streams_large_files() {
+    let dir = fixture_dir(
+        "streams_large_files",
+        &[
+            ("schema.sql", SCHEMA),
+            ("sqlsurge.toml", "streaming_threshold = 10\n"),
+            (
+                "query.sql",
+                "CREATE TEMP TABLE t (x INT);\nSELECT x FROM t;\n\nSELECT id, nme FROM users;\n",
+            ),
+        ],
+    );
+
+    let args = [
+        "check",
+        "--schema",
+        "schema.sql",
+        "--status-file",
+        "status.json",
+        "query.sql",
+    ];
+    let output = sqlsurge(&dir, &args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert_eq!(stderr.matches("[E0002]: ").count(), 1, "{}", stderr);
+    assert!(stderr.contains("--> query.sql:4:12"), "{}", stderr);
+    assert!(
+        stderr.contains("Found 1 error(s), 0 warning(s) in 1 file(s)"),
+        "{}",
+        stderr
+    );
+    // Printed diagnostics are only counted, and still make the status
+    let status: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("status.json")).unwrap()).unwrap();
+    assert_eq!(status["diagnostics"]["error"], 1, "{}", status);
+    assert_eq!(status["rules"]["E0002"], 1, "{}", status);
+    assert_eq!(status["success"], false, "{}", status);
+
+    // A streamed file over the statement limit is counted as skipped
+    let args = [
+        "check",
+        "--schema",
+        "schema.sql",
+        "--max-statements-per-file",
+        "2",
+        "-f",
+        "jsonl",
+        "query*.sql",
+    ];
+    let output = sqlsurge(&dir, &args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.matches("W0019").count(), 1, "{}", stdout);
+    assert!(stdout.contains("\"oversized\":1"), "{}", stdout);
+
+    // Whole-document formats still print once, with the same positions
+    let args = ["check", "--schema", "schema.sql", "-f", "json", "query.sql"];
+    let output = sqlsurge(&dir, &args);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let diagnostics = json["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1, "{}", json);
+    assert_eq!(diagnostics[0]["line"], 4, "{}", json);
+    assert_eq!(diagnostics[0]["statement"]["start_offset"], 47, "{}", json);
+}
+
+#[test]
+fn test_check_declared_functions() {
+    let dir = fixture_dir(
+        "declared_functions",
+        &[
+            ("schema.sql", SCHEMA),
+            (
+                "sqlsurge.toml",
+                "[functions]\nmy_score = { args = [\"integer\", \"text\"], returns = \"numeric\" }\n",
+            ),
+            (
+                "query.sql",
+                "SELECT my_score(id) FROM users;\nSELECT my_score(name, name) FROM users;\nSELECT my_score(id, name) FROM users;\n",
+            ),
+        ],
+    );
+
+    let output = sqlsurge(&dir, &["check", "--schema", "schema.sql", "query.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(
+        stderr.contains("[E0017]: Function 'my_score' takes 2 argument(s), but got 1"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("[E0003]: Argument 1 of function 'my_score' must be integer, found text"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("Found 2 error(s)"), "{}", stderr);
+
+    // A type name that doesn't parse stops the run
+    std::fs::write(
+        dir.join("sqlsurge.toml"),
+        "[functions]\nmy_score = { args = [\"not a type\"] }\n",
+    )
+    .unwrap();
+    let output = sqlsurge(&dir, &["check", "--schema", "schema.sql", "query.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr);
+    assert!(
+        stderr.contains("[functions] entry 'my_score': 'not a type' is not a type name"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_check_empty_schema() {
+    let dir = fixture_dir(
+        "check_empty_schema",
+        // The config of the repository would add its schema files
+        &[
+            ("sqlsurge.toml", ""),
+            ("query.sql", "SELECT * FROM users;\n"),
+        ],
+    );
+    std::fs::create_dir(dir.join("seeds")).unwrap();
+    std::fs::write(
+        dir.join("seeds/users.sql"),
+        "INSERT INTO users (id, name) VALUES (1, 'a');\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("seeds/orders.sql"),
+        "INSERT INTO orders (id) VALUES (1);\n",
+    )
+    .unwrap();
+
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "--schema-dir",
+            "seeds",
+            "query.sql",
+            "--status-file",
+            "status.json",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(
+        stderr.contains(
+            "Warning: schema contained no table definitions — all table references will fail"
+        ),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("--allow-empty-schema"), "{}", stderr);
+    assert!(
+        stderr.contains("Found 2 error(s), 0 warning(s) in 1 file(s); the schema contained no table definitions"),
+        "{}",
+        stderr
+    );
+    let status: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("status.json")).unwrap()).unwrap();
+    assert_eq!(status["empty_schema"], true);
+    assert_eq!(status["degraded"], true);
+
+    // Missing tables become warnings
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "--schema-dir",
+            "seeds",
+            "query.sql",
+            "--allow-empty-schema",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+    assert!(stderr.contains("E0001"), "{}", stderr);
+    assert!(
+        stderr.contains("Found 0 error(s), 2 warning(s)"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_check_schema_from_stdin() {
+    let dir = fixture_dir(
+        "check_schema_from_stdin",
+        &[
+            ("sqlsurge.toml", ""),
+            ("query.sql", "SELECT nickname FROM users;"),
+        ],
+    );
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sqlsurge"))
+        .current_dir(&dir)
+        .args(["check", "--schema", "-", "query.sql"])
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(SCHEMA.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(stderr.contains("[E0002]: "), "{}", stderr);
+    assert!(stderr.contains("--> <stdin>:1:14"), "{}", stderr);
+
+    // Queries can't come from stdin as well
+    let output = sqlsurge(&dir, &["check", "--schema", "-", "-"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr);
+    assert!(
+        stderr.contains("The schema and the queries can't both be read from stdin"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_check_schema_inline() {
+    let dir = fixture_dir(
+        "check_schema_inline",
+        &[
+            ("sqlsurge.toml", ""),
+            ("query.sql", "SELECT nickname FROM users;"),
+        ],
+    );
+
+    let output = sqlsurge(&dir, &["check", "--schema-inline", SCHEMA, "query.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(stderr.contains("--> <inline>:1:14"), "{}", stderr);
+
+    // From the config, along with schema files
+    std::fs::write(
+        dir.join("sqlsurge.toml"),
+        format!(
+            "schema = [\"orders.sql\"]\nschema_inline = \"\"\"\n{}\n\"\"\"\n",
+            SCHEMA
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("orders.sql"),
+        "CREATE TABLE orders (id INTEGER PRIMARY KEY, user_id INTEGER);",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("query.sql"),
+        "SELECT u.name FROM users u JOIN orders o ON o.user_id = u.id;",
+    )
+    .unwrap();
+    let output = sqlsurge(&dir, &["check", "query.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+}
+
+#[test]
+fn test_check_schema_sqlx() {
+    let dir = fixture_dir(
+        "check_schema_sqlx",
+        &[
+            (
+                "schema.sql",
+                "CREATE TABLE orders (id BIGINT, user_id INTEGER);",
+            ),
+            (
+                "query.sql",
+                "SELECT id, emial FROM users;\nSELECT user_id FROM orders;",
+            ),
+        ],
+    );
+    std::fs::create_dir_all(dir.join(".sqlx")).unwrap();
+    std::fs::write(
+        dir.join(".sqlx/query-1.json"),
+        r#"{
+            "db_name": "PostgreSQL",
+            "query": "SELECT * FROM users WHERE id = $1",
+            "describe": {
+                "columns": [
+                    {"ordinal": 0, "name": "id", "type_info": "Int4"},
+                    {"ordinal": 1, "name": "email", "type_info": "Text"}
+                ],
+                "parameters": {"Left": ["Int4"]},
+                "nullable": [false, true]
+            },
+            "hash": "1"
+        }"#,
+    )
+    .unwrap();
+
+    // Tables come from the metadata and the schema files alike
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "-s",
+            "schema.sql",
+            "--schema-sqlx",
+            ".sqlx",
+            "query.sql",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(stderr.contains("Column 'emial' not found"), "{}", stderr);
+    assert!(stderr.contains("Did you mean 'email'?"), "{}", stderr);
+    assert!(stderr.contains("--> .sqlx/query-1.json"), "{}", stderr);
+    assert!(!stderr.contains("'orders'"), "{}", stderr);
+
+    // Metadata no table can be derived from is an error with guidance
+    std::fs::write(
+        dir.join(".sqlx/query-1.json"),
+        r#"{"query": "SELECT 1", "describe": {"columns": [{"name": "?column?", "type_info": "Int4"}], "nullable": [null]}}"#,
+    )
+    .unwrap();
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "-s",
+            "schema.sql",
+            "--schema-sqlx",
+            ".sqlx",
+            "query.sql",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr);
+    assert!(stderr.contains("pass the schema as SQL"), "{}", stderr);
+}
+
+#[test]
+fn test_check_schema_warnings() {
+    let dir = fixture_dir(
+        "check_schema_warnings",
+        &[
+            (
+                "schema.sql",
+                "CREATE TABLE users (id INTEGER PRIMARY KEY);\n\n\
+                 ALTER TABLE accounts ADD COLUMN name TEXT;\n",
+            ),
+            ("query.sql", "SELECT id FROM users;"),
+        ],
+    );
+
+    let output = sqlsurge(&dir, &["check", "-s", "schema.sql", "query.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+    assert!(
+        stderr.contains("ALTER TABLE references table 'accounts' which was not found in schema"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("--> schema.sql:3:13"), "{}", stderr);
+    assert!(
+        stderr.contains("All 1 file(s) passed validation; schema: 0 error(s), 1 warning(s)"),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("Schema parsing produced"), "{}", stderr);
+
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "-s",
+            "schema.sql",
+            "query.sql",
+            "--schema-warnings",
+            "error",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(
+        stderr.contains("error\x1b[0m[E0001]: ALTER TABLE"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("in 1 file(s); schema: 1 error(s), 0 warning(s)"),
+        "{}",
+        stderr
+    );
+
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "-s",
+            "schema.sql",
+            "query.sql",
+            "--schema-warnings",
+            "hide",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+    assert!(!stderr.contains("ALTER TABLE"), "{}", stderr);
+    assert!(stderr.contains("schema: 1 warning(s) hidden"), "{}", stderr);
+
+    // JSON output marks the schema's diagnostics
+    let output = sqlsurge(
+        &dir,
+        &["check", "-s", "schema.sql", "query.sql", "-f", "json"],
+    );
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["file"], "schema.sql");
+    assert_eq!(report["diagnostics"][0]["category"], "schema");
+    assert_eq!(report["diagnostics"][0]["line"], 3);
+}
+
+#[test]
+fn test_check_duplicate_column() {
+    let dir = fixture_dir(
+        "check_duplicate_column",
+        &[
+            (
+                "schema.sql",
+                "CREATE TABLE users (\n  id INTEGER PRIMARY KEY,\n  id TEXT\n);\n\n\
+                 ALTER TABLE accounts ADD COLUMN name TEXT;\n",
+            ),
+            ("query.sql", "SELECT nme FROM users;"),
+        ],
+    );
+
+    // The first definition is kept and the queries are still checked
+    let output = sqlsurge(&dir, &["check", "-s", "schema.sql", "query.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(
+        stderr.contains("Column 'id' is defined more than once in table 'users'"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("--> schema.sql:3:3"), "{}", stderr);
+    assert!(stderr.contains("Column 'nme' not found"), "{}", stderr);
+    assert!(
+        stderr.contains("in 1 file(s); schema: 1 error(s), 1 warning(s)"),
+        "{}",
+        stderr
+    );
+
+    // Hidden schema warnings stay hidden, but the error is printed
+    let output = sqlsurge(
+        &dir,
+        &[
+            "check",
+            "-s",
+            "schema.sql",
+            "query.sql",
+            "--schema-warnings",
+            "hide",
+        ],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(stderr.contains("defined more than once"), "{}", stderr);
+    assert!(!stderr.contains("ALTER TABLE"), "{}", stderr);
+    assert!(stderr.contains("Column 'nme' not found"), "{}", stderr);
+    assert!(
+        stderr.contains("schema: 1 error(s), 1 warning(s) hidden"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_check_query_parse_errors() {
+    let query = "SELECT id FROM users;\nCREATE OR REPLACE PROCEDURAL LANGUAGE plpgsql;\n";
+    let dir = fixture_dir(
+        "check_query_parse_errors",
+        &[("schema.sql", SCHEMA), ("query.sql", query)],
+    );
+
+    // Errors by default
+    let output = sqlsurge(&dir, &["check", "-s", "schema.sql", "query.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(stderr.contains("error\x1b[0m[E1000]"), "{}", stderr);
+
+    // Reported as warnings, which pass
+    std::fs::write(dir.join("sqlsurge.toml"), "query_parse_errors = \"warn\"\n").unwrap();
+    let output = sqlsurge(&dir, &["check", "-s", "schema.sql", "query.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+    assert!(stderr.contains("warning\x1b[0m[E1000]"), "{}", stderr);
+    assert!(
+        stderr.contains("Found 0 error(s), 1 warning(s) in 1 file(s)"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_check_schema_parse_errors() {
+    let schema = "CREATE TABLE users (id INTEGER PRIMARY KEY);\n\
+                  CREATE OR REPLACE PROCEDURAL LANGUAGE plpgsql;\n";
+    let dir = fixture_dir(
+        "check_schema_parse_errors",
+        &[
+            ("schema.sql", schema),
+            ("query.sql", "SELECT id FROM users;"),
+        ],
+    );
+
+    // Skipped silently by default
+    let output = sqlsurge(&dir, &["check", "-s", "schema.sql", "query.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+    assert!(!stderr.contains("E1000"), "{}", stderr);
+    assert!(
+        stderr.contains("All 1 file(s) passed validation\n"),
+        "{}",
+        stderr
+    );
+
+    std::fs::write(
+        dir.join("sqlsurge.toml"),
+        "schema_parse_errors = \"warn\"\n",
+    )
+    .unwrap();
+    let output = sqlsurge(&dir, &["check", "-s", "schema.sql", "query.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr);
+    assert!(stderr.contains("warning\x1b[0m[E1000]"), "{}", stderr);
+    assert!(stderr.contains("the statement was skipped"), "{}", stderr);
+    assert!(stderr.contains("--> schema.sql:2:1"), "{}", stderr);
+    assert!(
+        stderr.contains("passed validation; schema: 0 error(s), 1 warning(s)"),
+        "{}",
+        stderr
+    );
+
+    std::fs::write(
+        dir.join("sqlsurge.toml"),
+        "schema_parse_errors = \"error\"\n",
+    )
+    .unwrap();
+    let output = sqlsurge(&dir, &["check", "-s", "schema.sql", "query.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(stderr.contains("error\x1b[0m[E1000]"), "{}", stderr);
+    assert!(
+        stderr.contains("in 1 file(s); schema: 1 error(s), 0 warning(s)"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_schema_compare_column_order() {
+    let dir = fixture_dir(
+        "schema_compare_column_order",
+        &[
+            (
+                "migrations.sql",
+                "CREATE TABLE users (id INTEGER, email TEXT);\n\
+                 ALTER TABLE users ADD COLUMN name TEXT;\n",
+            ),
+            (
+                "dump.sql",
+                "CREATE TABLE users (id INTEGER, name TEXT, email TEXT);\n",
+            ),
+        ],
+    );
+
+    let output = sqlsurge(&dir, &["schema", "migrations.sql", "--compare", "dump.sql"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(
+        stdout.contains(
+            "Table users: columns are in a different order\n  \
+             schema:  id, email, name\n  compare: id, name, email"
+        ),
+        "{}",
+        stdout
+    );
+
+    // The schema JSON carries each column's position
+    let output = sqlsurge(&dir, &["schema", "migrations.sql", "--format", "json"]);
+    let catalog: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let columns = &catalog["schemas"]["public"]["tables"]["users"]["columns"];
+    assert_eq!(columns["name"]["ordinal"], 3);
+
+    let output = sqlsurge(&dir, &["schema", "dump.sql", "--compare", "dump.sql"]);
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn test_schema_compare_column_types() {
+    let dir = fixture_dir(
+        "schema_compare_column_types",
+        &[
+            (
+                "migrations.sql",
+                "CREATE TABLE users (id SERIAL, name TEXT, email VARCHAR(255));\n",
+            ),
+            (
+                "dump.sql",
+                "CREATE TABLE users (\n\
+                 id integer DEFAULT nextval('users_id_seq'::regclass) NOT NULL,\n\
+                 name text, email character varying(255));\n",
+            ),
+            (
+                "bigint.sql",
+                "CREATE TABLE users (id BIGSERIAL, name TEXT, email VARCHAR(255));\n",
+            ),
+            (
+                "mysql.sql",
+                "CREATE TABLE users (id INT AUTO_INCREMENT PRIMARY KEY, name TEXT);\n",
+            ),
+            (
+                "mysql_dump.sql",
+                "CREATE TABLE users (id int NOT NULL, name text, PRIMARY KEY (id));\n",
+            ),
+        ],
+    );
+
+    // Spellings of the same type don't differ
+    let output = sqlsurge(&dir, &["schema", "migrations.sql", "--compare", "dump.sql"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "{}", stdout);
+    assert!(
+        stdout.contains("Column types match in every shared table"),
+        "{}",
+        stdout
+    );
+
+    let output = sqlsurge(
+        &dir,
+        &[
+            "schema",
+            "migrations.sql",
+            "--compare",
+            "bigint.sql",
+            "--format",
+            "json",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(1));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["column_order"].as_array().unwrap().len(), 0);
+    assert_eq!(json["column_types"][0]["column"], "id");
+    assert_eq!(
+        json["column_types"][0]["right"],
+        "bigint DEFAULT nextval('users_id_seq')"
+    );
+
+    // MySQL columns are described without sequences
+    let output = sqlsurge(
+        &dir,
+        &[
+            "schema",
+            "mysql.sql",
+            "--compare",
+            "mysql_dump.sql",
+            "--dialect",
+            "mysql",
+            "--format",
+            "json",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(1));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["column_types"][0]["left"], "integer AUTO_INCREMENT");
+    assert_eq!(json["column_types"][0]["right"], "integer");
+}
+
+#[test]
+fn test_explain_rule() {
+    let dir = fixture_dir("explain_rule", &[]);
+
+    let output = sqlsurge(&dir, &["explain", "e0006"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "{}", stdout);
+    assert!(stdout.contains("E0006 ambiguous-column"), "{}", stdout);
+    // Code blocks are indented instead of fenced
+    assert!(stdout.contains("\n    SELECT id, total\n"), "{}", stdout);
+    assert!(!stdout.contains("```"), "{}", stdout);
+
+    let output = sqlsurge(&dir, &["explain", "ambiguous-column", "-f", "markdown"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.starts_with("## E0006: ambiguous-column\n\n"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("```sql"), "{}", stdout);
+
+    // Every rule, for documentation
+    let output = sqlsurge(&dir, &["explain", "-f", "markdown"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.matches("\n## ").count() + 1, 42, "{}", stdout);
+
+    let output = sqlsurge(&dir, &["explain", "E9999"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr);
+    assert!(stderr.contains("Unknown rule 'E9999'"), "{}", stderr);
+}
+
+#[test]
+fn test_check_points_at_explain() {
+    let dir = fixture_dir(
+        "check_points_at_explain",
+        &[
+            ("schema.sql", SCHEMA),
+            ("query.sql", "SELECT nickname FROM users;"),
+        ],
+    );
+
+    let output = sqlsurge(&dir, &["check", "--schema", "schema.sql", "query.sql"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("= note: for more info, run `sqlsurge explain E0002`"),
+        "{}",
+        stderr
+    );
+
+    let args = ["check", "--schema", "schema.sql", "query.sql", "--no-hints"];
+    let output = sqlsurge(&dir, &args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("[E0002]: "), "{}", stderr);
+    assert!(!stderr.contains("sqlsurge explain"), "{}", stderr);
+
+    // SARIF describes the rules reported, linked to help_uri
+    std::fs::write(
+        dir.join("sqlsurge.toml"),
+        "help_uri = \"https://docs.example.com/sql/{code}\"\n",
+    )
+    .unwrap();
+    let args = [
+        "check",
+        "--schema",
+        "schema.sql",
+        "-f",
+        "sarif",
+        "query.sql",
+    ];
+    let output = sqlsurge(&dir, &args);
+    let sarif: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let rules = sarif["runs"][0]["tool"]["driver"]["rules"]
+        .as_array()
+        .unwrap();
+    assert_eq!(rules.len(), 1, "{}", sarif);
+    assert_eq!(rules[0]["id"], "E0002");
+    assert_eq!(rules[0]["name"], "column-not-found");
+    assert_eq!(rules[0]["helpUri"], "https://docs.example.com/sql/E0002");
+    assert!(rules[0]["help"]["markdown"]
+        .as_str()
+        .unwrap()
+        .contains("```sql"));
+}
@@ -0,0 +1,59 @@
+//! Analysis of generated query files that repeat the same statements
+//!
+//! Run with `cargo bench -p sqlsurge-core --bench ast_cache`. Analyzes
+//! copies of one query file (as generated per tenant) with and without
+//! `Analyzer::with_ast_cache`; with the cache, only the first copy is parsed.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use sqlsurge_core::schema::SchemaBuilder;
+use sqlsurge_core::Analyzer;
+
+const FILES: usize = 200;
+const STATEMENTS: usize = 50;
+
+fn main() {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, tenant_id INTEGER NOT NULL, \
+             name TEXT NOT NULL, email TEXT);
+             CREATE TABLE orders (id INTEGER PRIMARY KEY, user_id INTEGER NOT NULL \
+             REFERENCES users(id), total NUMERIC(10, 2), created_at TIMESTAMP);",
+        )
+        .unwrap();
+    let (catalog, _) = builder.build();
+
+    let mut file = String::new();
+    for i in 0..STATEMENTS {
+        file.push_str(&format!(
+            "SELECT u.id, u.name, sum(o.total) AS total_{i}\n  FROM users u\n  \
+             JOIN orders o ON o.user_id = u.id\n WHERE u.tenant_id = $1 AND o.total > {i}\n \
+             GROUP BY u.id, u.name\n ORDER BY total_{i} DESC;\n"
+        ));
+    }
+
+    let uncached = time(|| {
+        let mut analyzer = Analyzer::new(&catalog);
+        for _ in 0..FILES {
+            black_box(analyzer.analyze(&file));
+        }
+    });
+    let cached = time(|| {
+        let mut analyzer = Analyzer::new(&catalog).with_ast_cache(STATEMENTS);
+        for _ in 0..FILES {
+            black_box(analyzer.analyze(&file));
+        }
+    });
+
+    println!("{} identical files of {} statements", FILES, STATEMENTS);
+    println!("  without cache {:>10.1?}", uncached);
+    println!("  with cache    {:>10.1?}", cached);
+}
+
+fn time(f: impl FnOnce()) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
@@ -0,0 +1,51 @@
+//! Cost of cloning a large catalog, e.g. to hand it to worker threads
+//!
+//! Run with `cargo bench -p sqlsurge-core --bench catalog_clone`. Compares
+//! `Catalog::clone` (shared tables) with deep-copying every definition.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use sqlsurge_core::schema::SchemaBuilder;
+
+const TABLES: usize = 2000;
+const ITERATIONS: u32 = 100;
+
+fn main() {
+    let mut schema = String::new();
+    for i in 0..TABLES {
+        schema.push_str(&format!(
+            "CREATE TABLE t{i} (id INTEGER PRIMARY KEY, name VARCHAR(100) NOT NULL, \
+             email TEXT, created_at TIMESTAMP, parent_id INTEGER REFERENCES t{i}(id));\n"
+        ));
+    }
+    let mut builder = SchemaBuilder::new();
+    builder.parse(&schema).unwrap();
+    let (catalog, _) = builder.build();
+
+    let shared = time(|| {
+        black_box(catalog.clone());
+    });
+    let deep = time(|| {
+        let tables: Vec<_> = catalog
+            .schemas
+            .values()
+            .flat_map(|s| s.tables.values())
+            .map(|t| (**t).clone())
+            .collect();
+        black_box(tables);
+    });
+
+    println!("catalog with {} tables", TABLES);
+    println!("  Catalog::clone     {:>10.1?} / iter", shared);
+    println!("  deep copy (before) {:>10.1?} / iter", deep);
+}
+
+fn time(mut f: impl FnMut()) -> Duration {
+    f();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed() / ITERATIONS
+}
@@ -0,0 +1,59 @@
+//! "Did you mean" suggestions for columns of a very wide table
+//!
+//! Run with `cargo bench -p sqlsurge-core --bench suggestions`. Analyzes
+//! statements that misspell columns of a synthetic 5000-column table, each
+//! typo referenced several times, so every E0002 looks for the closest of
+//! the 5000 names of the same length: once with the default candidate
+//! budget, which compares the first 1000, and once without a budget.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use sqlsurge_core::schema::SchemaBuilder;
+use sqlsurge_core::Analyzer;
+
+/// Five times the default budget, so the budget is what bounds a lookup
+const COLUMNS: usize = 5 * sqlsurge_core::analyzer::DEFAULT_SUGGESTION_BUDGET;
+const STATEMENTS: usize = 200;
+
+fn main() {
+    let columns: Vec<String> = (0..COLUMNS)
+        .map(|i| format!("metric_{:04}_value INTEGER", i))
+        .collect();
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(&format!("CREATE TABLE analytics ({});", columns.join(", ")))
+        .unwrap();
+    let (catalog, _) = builder.build();
+
+    let mut file = String::new();
+    for i in 0..STATEMENTS {
+        let typo = format!("metric_{:04}_valeu", (i * 7) % COLUMNS);
+        file.push_str(&format!(
+            "SELECT {typo}, {typo} + 1, sum({typo}) FROM analytics \
+             WHERE {typo} > 0 AND unknown_column_{i} IS NULL GROUP BY {typo};\n"
+        ));
+    }
+
+    let default = time(|| {
+        let mut analyzer = Analyzer::new(&catalog);
+        black_box(analyzer.analyze(&file));
+    });
+    let unbounded = time(|| {
+        let mut analyzer = Analyzer::new(&catalog).with_suggestion_budget(usize::MAX);
+        black_box(analyzer.analyze(&file));
+    });
+
+    println!(
+        "{} statements misspelling columns of a {}-column table",
+        STATEMENTS, COLUMNS
+    );
+    println!("  default budget {:>10.1?}", default);
+    println!("  no budget      {:>10.1?}", unbounded);
+}
+
+fn time(f: impl FnOnce()) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
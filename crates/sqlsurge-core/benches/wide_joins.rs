@@ -0,0 +1,86 @@
+//! Unqualified columns of a wide join
+//!
+//! Run with `cargo bench -p sqlsurge-core --bench wide_joins`. Analyzes a
+//! reporting query joining 15 tables of 300 columns each, whose select list
+//! and WHERE clause reference 2,000 columns without a table qualifier, so
+//! each reference looks up which relations in scope have the column. The
+//! same query with qualified references is the baseline: finding the
+//! relation of an unqualified column shouldn't cost much more than being
+//! told.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use sqlsurge_core::schema::SchemaBuilder;
+use sqlsurge_core::Analyzer;
+
+const TABLES: usize = 15;
+const COLUMNS: usize = 300;
+const REFERENCES: usize = 2000;
+const RUNS: usize = 10;
+
+fn main() {
+    let mut schema = String::new();
+    for t in 0..TABLES {
+        let columns: Vec<String> = (0..COLUMNS)
+            .map(|c| format!("t{t:02}_c{c:03} INTEGER"))
+            .collect();
+        schema.push_str(&format!(
+            "CREATE TABLE t{t:02} (id INTEGER PRIMARY KEY, {});\n",
+            columns.join(", ")
+        ));
+    }
+    let mut builder = SchemaBuilder::new();
+    builder.parse(&schema).unwrap();
+    let (catalog, _) = builder.build();
+
+    let unqualified = query(|t, c| format!("t{t:02}_c{c:03}"));
+    let qualified = query(|t, c| format!("t{t:02}.t{t:02}_c{c:03}"));
+
+    let mut analyzer = Analyzer::new(&catalog);
+    for query in [&unqualified, &qualified] {
+        let diagnostics = analyzer.analyze(query);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+    let unqualified = time(|| {
+        for _ in 0..RUNS {
+            black_box(analyzer.analyze(&unqualified));
+        }
+    });
+    let qualified = time(|| {
+        for _ in 0..RUNS {
+            black_box(analyzer.analyze(&qualified));
+        }
+    });
+
+    println!(
+        "{} column references over a {}-table join of {} columns each",
+        REFERENCES, TABLES, COLUMNS
+    );
+    println!("  unqualified {:>10.1?}", unqualified / RUNS as u32);
+    println!("  qualified   {:>10.1?}", qualified / RUNS as u32);
+}
+
+/// The reporting query, naming column `c` of table `t` with `column(t, c)`
+fn query(column: impl Fn(usize, usize) -> String) -> String {
+    let column = |i: usize| column(i % TABLES, (i / TABLES) % COLUMNS);
+    let select: Vec<String> = (0..REFERENCES / 2).map(column).collect();
+    let filter: Vec<String> = (REFERENCES / 2..REFERENCES)
+        .map(|i| format!("{} > 0", column(i)))
+        .collect();
+    let joins: String = (1..TABLES)
+        .map(|t| format!(" JOIN t{t:02} ON t{t:02}.id = t00.id"))
+        .collect();
+    format!(
+        "SELECT {} FROM t00{} WHERE {};",
+        select.join(", "),
+        joins,
+        filter.join(" OR ")
+    )
+}
+
+fn time(f: impl FnOnce()) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
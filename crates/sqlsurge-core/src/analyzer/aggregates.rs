@@ -0,0 +1,266 @@
+//! Aggregate call checks
+//!
+//! - `COUNT(DISTINCT a, b)` is MySQL syntax; PostgreSQL's COUNT takes a
+//!   single argument (E0013).
+//! - `COUNT(col)` on a NOT NULL column counts every row, same as the clearer
+//!   `COUNT(*)` (W0006).
+//! - `COUNT(col)` on a nullable column skips NULLs, which is easy to miss
+//!   once the count feeds arithmetic such as a ratio (W0007).
+//! - `array_agg(DISTINCT a ORDER BY b)` is rejected by PostgreSQL: with
+//!   DISTINCT, the aggregate's ORDER BY expressions must be among its
+//!   arguments (E0014).
+//!
+//! Columns of the nullable side of an outer join may be NULL whatever the
+//! schema says, so they count as nullable.
+
+use sqlparser::ast::{
+    DuplicateTreatment, Expr, Function, FunctionArg, FunctionArgExpr, FunctionArgumentClause,
+    FunctionArguments, Ident, JoinOperator, Select, Spanned, TableFactor,
+};
+use std::collections::HashMap;
+
+use crate::dialect::SqlDialect;
+use crate::error::{Diagnostic, DiagnosticKind, Span};
+use crate::schema::{Catalog, ColumnDef};
+
+use super::functions::{function_args, function_name};
+use super::ordering::same_expr;
+use super::resolver::CteDefinition;
+use super::scope::Scope;
+
+pub(super) struct AggregateChecker<'a> {
+    pub(super) catalog: &'a Catalog,
+    pub(super) dialect: SqlDialect,
+    pub(super) tables: &'a Scope,
+    pub(super) ctes: &'a HashMap<String, CteDefinition>,
+    /// Scope names on the nullable side of an outer join
+    pub(super) outer_joined: &'a [String],
+}
+
+impl AggregateChecker<'_> {
+    /// Check an aggregate call's argument list (E0013, W0006)
+    pub(super) fn check_call(&self, func: &Function) -> Option<Diagnostic> {
+        if function_name(func) != "count" {
+            return None;
+        }
+        let FunctionArguments::List(list) = &func.args else {
+            return None;
+        };
+
+        if list.duplicate_treatment == Some(DuplicateTreatment::Distinct) {
+            if list.args.len() > 1 && self.dialect == SqlDialect::PostgreSQL {
+                return Some(
+                    Diagnostic::error(
+                        DiagnosticKind::MultiArgumentCountDistinct,
+                        format!(
+                            "COUNT(DISTINCT ...) takes a single argument in {}",
+                            self.dialect.product_name()
+                        ),
+                    )
+                    .with_span(Span::from_sqlparser(&func.span()))
+                    .with_help(
+                        "Count distinct combinations with a row value: COUNT(DISTINCT (a, b))",
+                    ),
+                );
+            }
+            return None;
+        }
+
+        let (expr, column) = self.counted_column(func)?;
+        if column.nullable || self.is_outer_joined(expr) {
+            return None;
+        }
+        Some(
+            Diagnostic::info(
+                DiagnosticKind::CountNotNullColumn,
+                format!(
+                    "COUNT({}) counts every row: '{}' is NOT NULL",
+                    expr, column.name
+                ),
+            )
+            .with_span(Span::from_sqlparser(&func.span()))
+            .with_help("Use COUNT(*), which is equivalent and states the intent"),
+        )
+    }
+
+    /// Check the ORDER BY of a DISTINCT aggregate against its arguments (E0014)
+    pub(super) fn check_distinct_order_by(&self, func: &Function) -> Option<Diagnostic> {
+        let FunctionArguments::List(list) = &func.args else {
+            return None;
+        };
+        if self.dialect != SqlDialect::PostgreSQL
+            || list.duplicate_treatment != Some(DuplicateTreatment::Distinct)
+        {
+            return None;
+        }
+        let args = function_args(func);
+        let expr = list
+            .clauses
+            .iter()
+            .filter_map(|clause| match clause {
+                FunctionArgumentClause::OrderBy(order_by) => Some(order_by),
+                _ => None,
+            })
+            .flatten()
+            .map(|ob| &ob.expr)
+            .find(|expr| !args.iter().any(|arg| same_expr(expr, arg)))?;
+        Some(
+            Diagnostic::error(
+                DiagnosticKind::DistinctOrderBy,
+                format!(
+                    "ORDER BY expression '{}' must appear in the arguments of {}(DISTINCT ...)",
+                    expr,
+                    function_name(func)
+                ),
+            )
+            .with_span(Span::from_sqlparser(&expr.span()))
+            .with_help("Order by an aggregated argument, or remove DISTINCT"),
+        )
+    }
+
+    /// Check an operand of an arithmetic operator (W0007)
+    pub(super) fn check_arithmetic_operand(&self, operand: &Expr) -> Option<Diagnostic> {
+        let Expr::Function(func) = unnest(operand) else {
+            return None;
+        };
+        if function_name(func) != "count" {
+            return None;
+        }
+        let (expr, column) = self.counted_column(func)?;
+        if !column.nullable && !self.is_outer_joined(expr) {
+            return None;
+        }
+        Some(
+            Diagnostic::info(
+                DiagnosticKind::CountSkipsNulls,
+                format!(
+                    "COUNT({}) skips rows where '{}' is NULL, so this arithmetic only covers non-NULL rows",
+                    expr, column.name
+                ),
+            )
+            .with_span(Span::from_sqlparser(&func.span()))
+            .with_help("Use COUNT(*) to count all rows, or keep COUNT(col) if NULLs should be excluded"),
+        )
+    }
+
+    /// The column counted by a plain `COUNT(col)`, if it's a base table column
+    fn counted_column<'f>(&self, func: &'f Function) -> Option<(&'f Expr, &ColumnDef)> {
+        let FunctionArguments::List(list) = &func.args else {
+            return None;
+        };
+        if list.duplicate_treatment.is_some() {
+            return None;
+        }
+        let [FunctionArg::Unnamed(FunctionArgExpr::Expr(expr))] = list.args.as_slice() else {
+            return None;
+        };
+        let column = match unnest(expr) {
+            Expr::Identifier(column) => self.unqualified_column(column)?,
+            Expr::CompoundIdentifier(idents) => match idents.as_slice() {
+                [qualifier, column] => {
+                    self.tables
+                        .base_column(self.catalog, &qualifier.value, &column.value)?
+                        .1
+                }
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some((expr, column))
+    }
+
+    /// The column an unqualified name refers to, if only one scope has it
+    /// and that scope is a base table
+    fn unqualified_column(&self, column: &Ident) -> Option<&ColumnDef> {
+        let index = self.tables.column_index(self.catalog);
+        let mut found = None;
+        for name in index.relations_with(&column.value) {
+            if found.is_some() {
+                // Ambiguous references are reported by name resolution
+                return None;
+            }
+            found = Some(name);
+        }
+        // CTEs, and derived tables of unknown columns (which have any column)
+        for name in index.unindexed() {
+            let table_ref = &self.tables[name];
+            let has_column = table_ref.derived_columns.is_some()
+                || self.ctes.get(&table_ref.table.name).is_some_and(|cte| {
+                    cte.columns
+                        .iter()
+                        .any(|c| c.eq_ignore_ascii_case(&column.value))
+                });
+            if has_column {
+                if found.is_some() {
+                    // Ambiguous references are reported by name resolution
+                    return None;
+                }
+                found = Some(name);
+            }
+        }
+        self.tables
+            .base_column(self.catalog, found?, &column.value)
+            .map(|(_, column)| column)
+    }
+
+    /// Whether the counted column belongs to the nullable side of an outer join
+    fn is_outer_joined(&self, expr: &Expr) -> bool {
+        match unnest(expr) {
+            Expr::CompoundIdentifier(idents) => idents
+                .first()
+                .is_some_and(|q| self.outer_joined.contains(&q.value)),
+            Expr::Identifier(column) => self.outer_joined.iter().any(|name| {
+                self.tables
+                    .base_column(self.catalog, name, &column.value)
+                    .is_some()
+            }),
+            _ => false,
+        }
+    }
+}
+
+/// Scope names on the nullable side of an outer join in `select`'s FROM
+pub(super) fn outer_joined_names(select: &Select) -> Vec<String> {
+    let mut names = Vec::new();
+    for table in &select.from {
+        let mut preceding = vec![&table.relation];
+        for join in &table.joins {
+            match join.join_operator {
+                JoinOperator::LeftOuter(_) | JoinOperator::OuterApply => {
+                    names.extend(scope_name(&join.relation));
+                }
+                JoinOperator::RightOuter(_) => {
+                    names.extend(preceding.iter().filter_map(|f| scope_name(f)));
+                }
+                JoinOperator::FullOuter(_) => {
+                    names.extend(preceding.iter().filter_map(|f| scope_name(f)));
+                    names.extend(scope_name(&join.relation));
+                }
+                _ => {}
+            }
+            preceding.push(&join.relation);
+        }
+    }
+    names
+}
+
+/// The name a table factor is registered under in the scope
+fn scope_name(factor: &TableFactor) -> Option<String> {
+    match factor {
+        TableFactor::Table { name, alias, .. } => alias
+            .as_ref()
+            .map(|a| a.name.value.clone())
+            .or_else(|| name.0.last().map(|n| n.value.clone())),
+        TableFactor::Derived { alias, .. } | TableFactor::UNNEST { alias, .. } => {
+            alias.as_ref().map(|a| a.name.value.clone())
+        }
+        _ => None,
+    }
+}
+
+fn unnest(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Nested(inner) => unnest(inner),
+        _ => expr,
+    }
+}
@@ -0,0 +1,127 @@
+//! Parsed statements kept for inputs that repeat them
+//!
+//! Generated query files (one per tenant, say) often consist of the same
+//! statements, and parsing dominates their analysis. With
+//! [`Analyzer::with_ast_cache`](super::Analyzer::with_ast_cache), each
+//! statement that parses is kept, and a statement with the same text is
+//! taken from the cache instead of being parsed again.
+//!
+//! Parsed statements carry the line and column of every name, so the key
+//! includes where the statement starts: the same text at another position
+//! is parsed again, so that diagnostics point at the right place. Files
+//! generated from one template keep their statements in place.
+//!
+//! The least recently used statements are dropped once the cache is full.
+
+use sqlparser::ast::Statement;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use crate::dialect::SqlDialect;
+
+/// A statement's text, dialect and starting position
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) struct CacheKey {
+    pub(super) dialect: SqlDialect,
+    pub(super) line: usize,
+    pub(super) column: usize,
+    /// The statement, trimmed
+    pub(super) text: Box<str>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    statements: Vec<Arc<Statement>>,
+    /// When the entry was last used, as a value of `AstCache::clock`
+    used: u64,
+}
+
+/// Least recently used cache of parsed statements
+#[derive(Debug)]
+pub(super) struct AstCache {
+    capacity: usize,
+    entries: HashMap<CacheKey, Entry>,
+    /// Keys by the time they were last used, oldest first
+    recency: BTreeMap<u64, CacheKey>,
+    clock: u64,
+}
+
+impl AstCache {
+    /// A cache keeping up to `capacity` statements
+    pub(super) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// The statements parsed from `key`, marking them as recently used
+    pub(super) fn get(&mut self, key: &CacheKey) -> Option<Vec<Arc<Statement>>> {
+        let entry = self.entries.get_mut(key)?;
+        self.clock += 1;
+        let key = self
+            .recency
+            .remove(&entry.used)
+            .expect("every entry has a recency");
+        entry.used = self.clock;
+        self.recency.insert(self.clock, key);
+        Some(entry.statements.clone())
+    }
+
+    /// Keep the statements parsed from `key`, dropping the least recently
+    /// used entry if the cache is full
+    pub(super) fn insert(&mut self, key: CacheKey, statements: Vec<Arc<Statement>>) {
+        if self.capacity == 0 || self.entries.contains_key(&key) {
+            return;
+        }
+        if self.entries.len() >= self.capacity {
+            if let Some((_, oldest)) = self.recency.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.clock += 1;
+        self.recency.insert(self.clock, key.clone());
+        self.entries.insert(
+            key,
+            Entry {
+                statements,
+                used: self.clock,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(text: &str) -> CacheKey {
+        CacheKey {
+            dialect: SqlDialect::PostgreSQL,
+            line: 1,
+            column: 1,
+            text: text.into(),
+        }
+    }
+
+    #[test]
+    fn test_least_recently_used_is_dropped() {
+        let mut cache = AstCache::new(2);
+        cache.insert(key("a"), Vec::new());
+        cache.insert(key("b"), Vec::new());
+        // Using "a" makes "b" the oldest
+        assert!(cache.get(&key("a")).is_some());
+        cache.insert(key("c"), Vec::new());
+        assert_eq!(cache.entries.len(), 2);
+        assert!(cache.get(&key("b")).is_none());
+        assert!(cache.get(&key("a")).is_some());
+        assert!(cache.get(&key("c")).is_some());
+
+        // A different position is a different statement
+        let mut moved = key("a");
+        moved.line = 2;
+        assert!(cache.get(&moved).is_none());
+    }
+}
@@ -0,0 +1,720 @@
+//! Completion candidates for editor integrations
+//!
+//! Given a cursor offset in SQL text, lists the identifiers that are valid
+//! there: tables, views and CTEs after FROM/JOIN, columns of the relations in
+//! scope elsewhere, and enum values inside a string compared to an enum column.
+//!
+//! The statement under the cursor is usually incomplete, so the word being
+//! typed is replaced by a placeholder identifier and the statement is parsed
+//! again. If that fails, the prefix up to the cursor is tried (with open
+//! parentheses closed), and finally a keyword heuristic over the tokens.
+
+use serde::Serialize;
+use sqlparser::ast::{
+    visit_expressions, Delete, Expr, FromTable, Insert, ObjectName, Query, Select, SetExpr,
+    Statement, TableFactor, TableWithJoins, Visit, Visitor,
+};
+use sqlparser::keywords::Keyword;
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::{Token, Tokenizer};
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+
+use crate::dialect::SqlDialect;
+use crate::idents::object_name_to_qualified;
+use crate::schema::{split_sql_statements, Catalog, QualifiedName};
+use crate::types::SqlType;
+
+use super::output::{OutputColumn, OutputInferer};
+
+/// Identifier substituted for the word under the cursor
+const PLACEHOLDER: &str = "__sqlsurge_cursor__";
+
+/// What a completion candidate refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CandidateKind {
+    /// Table or view
+    Table,
+    Column,
+    Cte,
+    EnumValue,
+    Keyword,
+}
+
+/// A completion candidate
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Candidate {
+    pub kind: CandidateKind,
+    /// Text to insert
+    pub label: String,
+    /// Extra information, e.g. `integer (users)` for a column
+    pub detail: Option<String>,
+}
+
+impl Candidate {
+    fn new(kind: CandidateKind, label: impl Into<String>, detail: Option<String>) -> Self {
+        Self {
+            kind,
+            label: label.into(),
+            detail,
+        }
+    }
+}
+
+/// List the identifiers valid at byte `offset` of `sql`
+///
+/// Candidates are filtered by the partial word before the cursor
+/// (case-insensitively).
+///
+/// # Example
+///
+/// ```
+/// use sqlsurge_core::completion::{candidates, CandidateKind};
+/// use sqlsurge_core::schema::SchemaBuilder;
+/// use sqlsurge_core::SqlDialect;
+///
+/// let mut builder = SchemaBuilder::new();
+/// builder.parse("CREATE TABLE users (id INTEGER, name TEXT);").unwrap();
+/// let (catalog, _) = builder.build();
+///
+/// let sql = "SELECT na FROM users";
+/// let items = candidates(sql, "SELECT na".len(), &catalog, SqlDialect::PostgreSQL);
+/// assert_eq!(items[0].kind, CandidateKind::Column);
+/// assert_eq!(items[0].label, "name");
+/// ```
+pub fn candidates(
+    sql: &str,
+    offset: usize,
+    catalog: &Catalog,
+    dialect: SqlDialect,
+) -> Vec<Candidate> {
+    let mut offset = offset.min(sql.len());
+    while !sql.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let (stmt_start, stmt) = statement_at(sql, offset);
+    let cursor = offset - stmt_start;
+    let before = &stmt[..cursor];
+
+    // Inside a string literal: enum values of the compared column
+    if let Some(quote) = open_string_start(before) {
+        let string_end = stmt[cursor..]
+            .find('\'')
+            .map_or(stmt.len(), |pos| cursor + pos + 1);
+        let probe = format!("{}{}{}", &stmt[..quote], PLACEHOLDER, &stmt[string_end..]);
+        let tokens = significant_tokens(&stmt[..quote], dialect);
+        let Some((qualifier, column)) = compared_column(&tokens) else {
+            return Vec::new();
+        };
+        let scope = Scope::build(&probe, &stmt[..quote], catalog, dialect);
+        return filter(
+            enum_values(&scope, qualifier, &column, catalog),
+            &before[quote + 1..],
+        );
+    }
+
+    let word_start = before
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !is_identifier_char(*c))
+        .map_or(0, |(pos, c)| pos + c.len_utf8());
+    let word_end = stmt[cursor..]
+        .find(|c: char| !is_identifier_char(c))
+        .map_or(stmt.len(), |pos| cursor + pos);
+    let partial = &before[word_start..];
+    let tokens = significant_tokens(&stmt[..word_start], dialect);
+
+    let probe = format!(
+        "{}{}{}",
+        &stmt[..word_start],
+        PLACEHOLDER,
+        &stmt[word_end..]
+    );
+    let items = match classify(&tokens) {
+        Context::Start => keywords(&["SELECT", "INSERT", "UPDATE", "DELETE", "WITH"]),
+        Context::AfterRelation => keywords(&[
+            "JOIN", "LEFT", "INNER", "ON", "WHERE", "GROUP", "ORDER", "LIMIT",
+        ]),
+        Context::Relation => {
+            let scope = Scope::build(&probe, &stmt[..word_start], catalog, dialect);
+            relations(&scope, catalog)
+        }
+        Context::Column => {
+            let scope = Scope::build(&probe, &stmt[..word_start], catalog, dialect);
+            columns(&scope, None)
+        }
+        Context::Qualified(qualifier) => {
+            let scope = Scope::build(&probe, &stmt[..word_start], catalog, dialect);
+            columns(&scope, Some(&qualifier))
+        }
+    };
+    filter(items, partial)
+}
+
+/// The statement containing `offset`, with its byte offset in `sql`
+fn statement_at(sql: &str, offset: usize) -> (usize, &str) {
+    for stmt in split_sql_statements(sql) {
+        let start = stmt.as_ptr() as usize - sql.as_ptr() as usize;
+        if (start..=start + stmt.len()).contains(&offset) {
+            return (start, stmt);
+        }
+    }
+    // Between statements (e.g. right after a `;`)
+    (offset, "")
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Start of an unterminated single-quoted string in `text`, if any
+fn open_string_start(text: &str) -> Option<usize> {
+    let mut open = None;
+    for (idx, c) in text.char_indices() {
+        if c == '\'' {
+            open = match open {
+                Some(_) => None,
+                None => Some(idx),
+            };
+        }
+    }
+    open
+}
+
+/// Tokens of `text` without whitespace and comments (empty if it doesn't tokenize)
+fn significant_tokens(text: &str, dialect: SqlDialect) -> Vec<Token> {
+    let parser_dialect = dialect.parser_dialect();
+    Tokenizer::new(parser_dialect.as_ref(), text)
+        .tokenize()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|t| !matches!(t, Token::Whitespace(_)))
+        .collect()
+}
+
+/// Syntactic position of the cursor
+#[derive(Debug, PartialEq)]
+enum Context {
+    /// Start of a statement
+    Start,
+    /// After FROM, JOIN, INTO, UPDATE or a comma in a FROM list
+    Relation,
+    /// Right after a relation (and its alias)
+    AfterRelation,
+    /// An expression position
+    Column,
+    /// After `qualifier.`
+    Qualified(String),
+}
+
+fn keyword_of(token: &Token) -> Option<Keyword> {
+    match token {
+        Token::Word(w) if w.quote_style.is_none() => Some(w.keyword),
+        _ => None,
+    }
+}
+
+fn classify(tokens: &[Token]) -> Context {
+    if let [.., Token::Word(qualifier), Token::Period] = tokens {
+        return Context::Qualified(qualifier.value.clone());
+    }
+    for (idx, token) in tokens.iter().enumerate().rev() {
+        match keyword_of(token) {
+            Some(
+                Keyword::FROM | Keyword::JOIN | Keyword::INTO | Keyword::UPDATE | Keyword::TABLE,
+            ) => {
+                let rest = &tokens[idx + 1..];
+                return if rest.is_empty() || matches!(rest.last(), Some(Token::Comma)) {
+                    Context::Relation
+                } else if rest.iter().any(|t| matches!(t, Token::LParen)) {
+                    // e.g. the column list of INSERT INTO t (...)
+                    Context::Column
+                } else {
+                    Context::AfterRelation
+                };
+            }
+            Some(
+                Keyword::SELECT
+                | Keyword::WHERE
+                | Keyword::ON
+                | Keyword::AND
+                | Keyword::OR
+                | Keyword::NOT
+                | Keyword::BY
+                | Keyword::HAVING
+                | Keyword::SET
+                | Keyword::RETURNING
+                | Keyword::WHEN
+                | Keyword::THEN
+                | Keyword::ELSE
+                | Keyword::USING,
+            ) => return Context::Column,
+            _ => {}
+        }
+    }
+    if tokens.is_empty() {
+        Context::Start
+    } else {
+        Context::Column
+    }
+}
+
+/// The column compared to a string literal: `col = '`, `t.col IN ('a', '`
+fn compared_column(tokens: &[Token]) -> Option<(Option<String>, String)> {
+    let mut idx = tokens.len();
+    // Skip earlier list items and the comparison itself
+    while idx > 0 {
+        match &tokens[idx - 1] {
+            Token::Comma
+            | Token::LParen
+            | Token::SingleQuotedString(_)
+            | Token::Eq
+            | Token::Neq
+            | Token::Lt
+            | Token::Gt
+            | Token::LtEq
+            | Token::GtEq => idx -= 1,
+            Token::Word(w)
+                if matches!(w.keyword, Keyword::IN | Keyword::NOT) && w.quote_style.is_none() =>
+            {
+                idx -= 1
+            }
+            _ => break,
+        }
+    }
+    let Token::Word(column) = tokens.get(idx.checked_sub(1)?)? else {
+        return None;
+    };
+    let qualifier = match idx.checked_sub(3).map(|i| (&tokens[i], &tokens[i + 1])) {
+        Some((Token::Word(q), Token::Period)) => Some(q.value.clone()),
+        _ => None,
+    };
+    Some((qualifier, column.value.clone()))
+}
+
+/// A relation visible at the cursor
+struct ScopeRelation {
+    /// Name or alias it is visible under
+    name: String,
+    /// Underlying table name (or the alias for derived tables)
+    source: String,
+    columns: Vec<OutputColumn>,
+}
+
+/// Names visible at the cursor
+#[derive(Default)]
+struct Scope {
+    relations: Vec<ScopeRelation>,
+    ctes: Vec<String>,
+}
+
+impl Scope {
+    /// Build the scope of the placeholder in `probe`, falling back to the
+    /// statement prefix and then to a token heuristic
+    fn build(probe: &str, prefix: &str, catalog: &Catalog, dialect: SqlDialect) -> Self {
+        let parser_dialect = dialect.parser_dialect();
+        let closed_prefix = {
+            let open = prefix
+                .matches('(')
+                .count()
+                .saturating_sub(prefix.matches(')').count());
+            format!("{}{}{}", prefix, PLACEHOLDER, ")".repeat(open))
+        };
+        for text in [probe, closed_prefix.as_str()] {
+            if let Ok(statements) = Parser::parse_sql(parser_dialect.as_ref(), text) {
+                if let Some(scope) = statements
+                    .iter()
+                    .find_map(|stmt| Self::from_statement(stmt, catalog))
+                {
+                    return scope;
+                }
+            }
+        }
+        Self::from_tokens(&significant_tokens(probe, dialect), catalog)
+    }
+
+    /// Scope of the placeholder in a parsed statement
+    fn from_statement(stmt: &Statement, catalog: &Catalog) -> Option<Self> {
+        let mut finder = PlaceholderFinder::new(catalog);
+        let found = stmt.visit(&mut finder).is_break();
+        let mut scope = Scope::default();
+
+        if found && !finder.stack.is_empty() {
+            for frame in &finder.stack {
+                scope.ctes.extend(frame.ctes.iter().cloned());
+            }
+            // Innermost query first, then the queries it is correlated with
+            while let Some(frame) = finder.stack.pop() {
+                scope.relations.extend(frame.relations);
+                if !frame.correlated {
+                    break;
+                }
+            }
+            return Some(scope);
+        }
+
+        // Placeholder in a DML statement outside of any query
+        let inferer = &mut finder.inferer;
+        match stmt {
+            Statement::Update { table, from, .. } if found => {
+                scope.add_from(std::slice::from_ref(table), inferer);
+                if let Some(from) = from {
+                    scope.add_from(std::slice::from_ref(from), inferer);
+                }
+            }
+            Statement::Delete(Delete { from, using, .. }) if found => {
+                let (FromTable::WithFromKeyword(tables) | FromTable::WithoutKeyword(tables)) = from;
+                scope.add_from(tables, inferer);
+                if let Some(using) = using {
+                    scope.add_from(using, inferer);
+                }
+            }
+            Statement::Insert(Insert {
+                table_name,
+                columns,
+                ..
+            }) if found || columns.iter().any(|c| c.value == PLACEHOLDER) => {
+                scope.add_table(table_name, catalog);
+            }
+            _ if found => {}
+            _ => return None,
+        }
+        Some(scope)
+    }
+
+    /// Add the relations of a FROM clause
+    fn add_from(&mut self, from: &[TableWithJoins], inferer: &mut OutputInferer) {
+        for table_with_joins in from {
+            let factors = std::iter::once(&table_with_joins.relation)
+                .chain(table_with_joins.joins.iter().map(|j| &j.relation));
+            for factor in factors {
+                let Ok(relation) = inferer.relation(factor) else {
+                    continue;
+                };
+                let source = match factor {
+                    TableFactor::Table { name, .. } => object_name_to_qualified(name).name,
+                    _ => relation.name.clone(),
+                };
+                if source == PLACEHOLDER {
+                    continue;
+                }
+                self.relations.push(ScopeRelation {
+                    name: relation.name,
+                    source,
+                    columns: relation.columns,
+                });
+            }
+        }
+    }
+
+    /// Add a catalog table under its own name
+    fn add_table(&mut self, name: &ObjectName, catalog: &Catalog) {
+        let table_name = object_name_to_qualified(name);
+        if let Some(table) = catalog.get_table(&table_name) {
+            self.relations.push(ScopeRelation {
+                name: table_name.name.clone(),
+                source: table_name.name.clone(),
+                columns: table
+                    .ordered_columns()
+                    .into_iter()
+                    .map(|c| OutputColumn {
+                        name: c.name.clone(),
+                        data_type: c.data_type.clone(),
+                        nullable: c.nullable,
+                    })
+                    .collect(),
+            });
+        }
+    }
+
+    /// Heuristic scope for unparseable statements: `FROM/JOIN name [AS] [alias]`
+    fn from_tokens(tokens: &[Token], catalog: &Catalog) -> Self {
+        let mut scope = Scope::default();
+        for (idx, token) in tokens.iter().enumerate() {
+            if !matches!(
+                keyword_of(token),
+                Some(Keyword::FROM | Keyword::JOIN | Keyword::UPDATE | Keyword::INTO)
+            ) {
+                continue;
+            }
+            let Some(Token::Word(name)) = tokens.get(idx + 1) else {
+                continue;
+            };
+            let (table_name, next) = match (tokens.get(idx + 2), tokens.get(idx + 3)) {
+                (Some(Token::Period), Some(Token::Word(table))) => (
+                    QualifiedName::with_schema(&name.value, &table.value),
+                    idx + 4,
+                ),
+                _ => (QualifiedName::new(&name.value), idx + 2),
+            };
+            let alias = match (tokens.get(next), tokens.get(next + 1)) {
+                (Some(Token::Word(w)), Some(Token::Word(alias))) if w.keyword == Keyword::AS => {
+                    Some(alias)
+                }
+                (Some(Token::Word(alias)), _) if alias.keyword == Keyword::NoKeyword => Some(alias),
+                _ => None,
+            };
+            if let Some(table) = catalog.get_table(&table_name) {
+                scope.relations.push(ScopeRelation {
+                    name: alias.map_or_else(|| table_name.name.clone(), |a| a.value.clone()),
+                    source: table_name.name.clone(),
+                    columns: table
+                        .ordered_columns()
+                        .into_iter()
+                        .map(|c| OutputColumn {
+                            name: c.name.clone(),
+                            data_type: c.data_type.clone(),
+                            nullable: c.nullable,
+                        })
+                        .collect(),
+                });
+            }
+        }
+        scope
+    }
+}
+
+/// The SELECT of a query body that contains the placeholder (or the first one)
+fn placeholder_select(body: &SetExpr) -> Option<&Select> {
+    let mut selects = Vec::new();
+    collect_selects(body, &mut selects);
+    selects
+        .iter()
+        .copied()
+        .find(|select| {
+            visit_expressions(*select, |expr| {
+                if is_placeholder_expr(expr) {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })
+            .is_break()
+        })
+        .or_else(|| selects.first().copied())
+}
+
+fn collect_selects<'q>(body: &'q SetExpr, selects: &mut Vec<&'q Select>) {
+    match body {
+        SetExpr::Select(select) => selects.push(select),
+        SetExpr::Query(query) => collect_selects(&query.body, selects),
+        SetExpr::SetOperation { left, right, .. } => {
+            collect_selects(left, selects);
+            collect_selects(right, selects);
+        }
+        _ => {}
+    }
+}
+
+fn is_placeholder_expr(expr: &Expr) -> bool {
+    match expr {
+        Expr::Identifier(ident) => ident.value == PLACEHOLDER,
+        Expr::CompoundIdentifier(idents) => idents.last().is_some_and(|i| i.value == PLACEHOLDER),
+        _ => false,
+    }
+}
+
+/// Finds the placeholder, recording the scope of each query enclosing it
+///
+/// The relations of a query are inferred as it is entered, so the statement
+/// is walked once and no query is kept.
+struct PlaceholderFinder<'c> {
+    inferer: OutputInferer<'c>,
+    /// Enclosing queries, outermost first
+    stack: Vec<QueryFrame>,
+    /// The next query is an expression subquery
+    subquery_pending: bool,
+}
+
+/// What a query enclosing the placeholder brings into scope
+struct QueryFrame {
+    /// Names of the CTEs of its WITH clause
+    ctes: Vec<String>,
+    /// The relations of the SELECT holding the placeholder (or the first one)
+    relations: Vec<ScopeRelation>,
+    /// Whether it is an expression subquery, and so sees the columns of the
+    /// query around it
+    correlated: bool,
+    /// The CTEs defined before its WITH clause, restored when it is left
+    saved_ctes: Option<HashMap<String, Vec<OutputColumn>>>,
+}
+
+impl<'c> PlaceholderFinder<'c> {
+    fn new(catalog: &'c Catalog) -> Self {
+        Self {
+            inferer: OutputInferer::new(catalog),
+            stack: Vec::new(),
+            subquery_pending: false,
+        }
+    }
+}
+
+impl Visitor for PlaceholderFinder<'_> {
+    type Break = ();
+
+    fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<()> {
+        let correlated = std::mem::take(&mut self.subquery_pending);
+        let saved_ctes = query.with.as_ref().map(|with| {
+            let saved = self.inferer.saved_ctes();
+            self.inferer.define_ctes(with);
+            saved
+        });
+        let mut scope = Scope::default();
+        if let Some(select) = placeholder_select(&query.body) {
+            scope.add_from(&select.from, &mut self.inferer);
+        }
+        self.stack.push(QueryFrame {
+            ctes: query
+                .with
+                .iter()
+                .flat_map(|with| &with.cte_tables)
+                .map(|cte| cte.alias.name.value.clone())
+                .collect(),
+            relations: scope.relations,
+            correlated,
+            saved_ctes,
+        });
+        ControlFlow::Continue(())
+    }
+
+    fn post_visit_query(&mut self, _query: &Query) -> ControlFlow<()> {
+        if let Some(saved) = self.stack.pop().and_then(|frame| frame.saved_ctes) {
+            self.inferer.restore_ctes(saved);
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_relation(&mut self, relation: &ObjectName) -> ControlFlow<()> {
+        if relation.0.last().is_some_and(|i| i.value == PLACEHOLDER) {
+            return ControlFlow::Break(());
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_expr(&mut self, expr: &Expr) -> ControlFlow<()> {
+        if is_placeholder_expr(expr) {
+            return ControlFlow::Break(());
+        }
+        if matches!(
+            expr,
+            Expr::Subquery(_) | Expr::Exists { .. } | Expr::InSubquery { .. }
+        ) {
+            self.subquery_pending = true;
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+fn keywords(words: &[&str]) -> Vec<Candidate> {
+    words
+        .iter()
+        .map(|w| Candidate::new(CandidateKind::Keyword, *w, None))
+        .collect()
+}
+
+/// Tables, views and CTEs
+fn relations(scope: &Scope, catalog: &Catalog) -> Vec<Candidate> {
+    let mut items: Vec<Candidate> = scope
+        .ctes
+        .iter()
+        .map(|name| Candidate::new(CandidateKind::Cte, name, Some("cte".to_string())))
+        .collect();
+    let mut names: Vec<(String, QualifiedName)> = catalog
+        .table_or_view_names()
+        .into_iter()
+        .map(|name| (relation_label(&name, catalog), name))
+        .collect();
+    names.sort_by(|a, b| a.0.cmp(&b.0));
+    for (label, name) in names {
+        let detail = if catalog.get_view(&name).is_some() {
+            "view"
+        } else {
+            "table"
+        };
+        items.push(Candidate::new(
+            CandidateKind::Table,
+            label,
+            Some(detail.to_string()),
+        ));
+    }
+    items
+}
+
+/// A table or view name as a query must write it: schema-qualified outside
+/// the default schema
+fn relation_label(name: &QualifiedName, catalog: &Catalog) -> String {
+    match &name.schema {
+        Some(schema) if *schema != catalog.default_schema => name.to_string(),
+        _ => name.name.clone(),
+    }
+}
+
+/// Columns of the relations in scope, or of the one named `qualifier`
+fn columns(scope: &Scope, qualifier: Option<&str>) -> Vec<Candidate> {
+    scope
+        .relations
+        .iter()
+        .filter(|r| qualifier.map_or(true, |q| r.name.eq_ignore_ascii_case(q)))
+        .flat_map(|r| {
+            r.columns.iter().map(move |c| {
+                Candidate::new(
+                    CandidateKind::Column,
+                    &c.name,
+                    Some(format!("{} ({})", c.data_type.display_name(), r.source)),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Values of the enum type of a column in scope
+fn enum_values(
+    scope: &Scope,
+    qualifier: Option<String>,
+    column: &str,
+    catalog: &Catalog,
+) -> Vec<Candidate> {
+    let data_type = scope
+        .relations
+        .iter()
+        .filter(|r| {
+            qualifier
+                .as_deref()
+                .map_or(true, |q| r.name.eq_ignore_ascii_case(q))
+        })
+        .flat_map(|r| &r.columns)
+        .find(|c| c.name.eq_ignore_ascii_case(column))
+        .map(|c| &c.data_type);
+    let Some(SqlType::Custom(type_name)) = data_type else {
+        return Vec::new();
+    };
+    let enum_name = type_name.rsplit('.').next().unwrap_or(type_name);
+    catalog
+        .get_enum(enum_name)
+        .map(|def| {
+            def.values
+                .iter()
+                .map(|v| Candidate::new(CandidateKind::EnumValue, v, Some(def.name.clone())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Keep candidates starting with `partial`, or whose name does after a
+/// schema qualifier, without duplicates
+fn filter(items: Vec<Candidate>, partial: &str) -> Vec<Candidate> {
+    let partial = partial.to_lowercase();
+    let mut seen = std::collections::HashSet::new();
+    items
+        .into_iter()
+        .filter(|c| {
+            let label = c.label.to_lowercase();
+            label.starts_with(&partial)
+                || label
+                    .rsplit_once('.')
+                    .is_some_and(|(_, name)| name.starts_with(&partial))
+        })
+        .filter(|c| seen.insert(c.clone()))
+        .collect()
+}
@@ -0,0 +1,229 @@
+//! Cross-table comparison heuristic (W0005, opt-in)
+//!
+//! `... JOIN orders o ON o.user_id = u.id WHERE o.total > u.id` type-checks,
+//! but comparing two columns of different tables outside the join condition
+//! is usually a copy-paste error. A WHERE/HAVING comparison is flagged when
+//! its sides are columns of different base tables that no JOIN ON equality
+//! or declared foreign key relates. An `=` between two tables that aren't
+//! joined at all is taken as an implicit join (`FROM a, b WHERE a.x = b.y`).
+
+use sqlparser::ast::{BinaryOperator, Expr, Ident, JoinConstraint, JoinOperator, Select, Spanned};
+
+use crate::error::{Diagnostic, DiagnosticKind, Span};
+use crate::schema::{Catalog, QualifiedName};
+
+use super::intern::Name;
+use super::scope::Scope;
+
+/// A base table column: (table, lowercased column name)
+pub(super) type ColumnKey = (QualifiedName, String);
+
+/// Check the WHERE and HAVING comparisons of `select`
+///
+/// `from_names` are the scope names registered by this SELECT's FROM
+/// clause; columns of outer queries (correlated subqueries) are ignored.
+pub(super) fn check_select(
+    catalog: &Catalog,
+    tables: &Scope,
+    from_names: &[Name],
+    select: &Select,
+) -> Vec<Diagnostic> {
+    let checker = Checker::new(catalog, tables, from_names);
+
+    // Column pairs related by JOIN ON equalities
+    let mut related: Vec<(ColumnKey, ColumnKey)> = Vec::new();
+    for join in select.from.iter().flat_map(|t| &t.joins) {
+        if let Some(JoinConstraint::On(on)) = join_constraint(&join.join_operator) {
+            let mut comparisons = Vec::new();
+            collect_comparisons(on, &mut comparisons);
+            for (left, op, right) in comparisons {
+                if *op == BinaryOperator::Eq {
+                    if let (Some(l), Some(r)) = (checker.column(left), checker.column(right)) {
+                        related.push((l, r));
+                    }
+                }
+            }
+        }
+    }
+
+    let mut diagnostics = Vec::new();
+    let mut comparisons = Vec::new();
+    for clause in select.selection.iter().chain(&select.having) {
+        collect_comparisons(clause, &mut comparisons);
+    }
+    for (left, op, right) in comparisons {
+        let (Some(l), Some(r)) = (checker.column(left), checker.column(right)) else {
+            continue;
+        };
+        if l.0 == r.0 || is_related(&related, &l, &r) || checker.foreign_key_relates(&l, &r) {
+            continue;
+        }
+        let tables_joined = related
+            .iter()
+            .any(|(a, b)| (a.0 == l.0 && b.0 == r.0) || (a.0 == r.0 && b.0 == l.0))
+            || checker.tables_have_foreign_key(&l.0, &r.0);
+        if *op == BinaryOperator::Eq && !tables_joined {
+            continue;
+        }
+
+        diagnostics.push(
+            Diagnostic::info(
+                DiagnosticKind::CrossTableComparison,
+                format!(
+                    "Comparison '{} {} {}' relates columns of different tables outside a join",
+                    left, op, right
+                ),
+            )
+            .with_span(Span::from_sqlparser(&left.span().union(&right.span())))
+            .with_help(format!(
+                "'{}.{}' and '{}.{}' are not related by a JOIN condition or foreign key; \
+                 if this is intended, disable the rule with `-- sqlsurge-disable W0005`",
+                l.0.name, l.1, r.0.name, r.1
+            )),
+        );
+    }
+    diagnostics
+}
+
+/// Resolves column references to the base table columns of a FROM clause
+pub(super) struct Checker<'a> {
+    catalog: &'a Catalog,
+    tables: &'a Scope,
+    from_names: &'a [Name],
+}
+
+impl<'a> Checker<'a> {
+    pub(super) fn new(catalog: &'a Catalog, tables: &'a Scope, from_names: &'a [Name]) -> Self {
+        Self {
+            catalog,
+            tables,
+            from_names,
+        }
+    }
+
+    pub(super) fn catalog(&self) -> &'a Catalog {
+        self.catalog
+    }
+
+    /// The base table column `expr` refers to, if it is a plain column reference
+    pub(super) fn column(&self, expr: &Expr) -> Option<ColumnKey> {
+        match expr {
+            Expr::Nested(inner) => self.column(inner),
+            Expr::Identifier(column) => {
+                let mut matches = self
+                    .tables
+                    .column_index(self.catalog)
+                    .relations_with(&column.value)
+                    .iter()
+                    .filter(|name| self.from_names.contains(name))
+                    .filter_map(|name| self.table_with_column(name, column));
+                let found = matches.next()?;
+                // Ambiguous references are reported by name resolution
+                matches.next().is_none().then_some(found)
+            }
+            Expr::CompoundIdentifier(idents) => match idents.as_slice() {
+                [qualifier, column] if self.from_names.iter().any(|n| **n == *qualifier.value) => {
+                    self.table_with_column(&qualifier.value, column)
+                }
+                [schema, qualifier, column] => {
+                    let key = format!("{}.{}", schema.value, qualifier.value);
+                    if self.from_names.iter().any(|n| **n == *key) {
+                        return self.table_with_column(&key, column);
+                    }
+                    let written = QualifiedName::with_schema(&schema.value, &qualifier.value);
+                    self.from_names
+                        .iter()
+                        .any(|n| **n == *qualifier.value)
+                        .then(|| self.table_with_column(&qualifier.value, column))
+                        .flatten()
+                        .filter(|(table, _)| same_table(self.catalog, table, &written))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The key of `column` of the base table registered as `name`, if it has one
+    fn table_with_column(&self, name: &str, column: &Ident) -> Option<ColumnKey> {
+        let (table, column) = self.tables.base_column(self.catalog, name, &column.value)?;
+        Some((table.name.clone(), column.name.to_lowercase()))
+    }
+
+    /// Whether a foreign key of either table maps one column onto the other
+    fn foreign_key_relates(&self, a: &ColumnKey, b: &ColumnKey) -> bool {
+        let references = |from: &ColumnKey, to: &ColumnKey| {
+            self.catalog.get_table(&from.0).is_some_and(|table| {
+                table.foreign_keys.iter().any(|fk| {
+                    same_table(self.catalog, &fk.references_table, &to.0)
+                        && fk.columns.iter().zip(&fk.references_columns).any(|(c, r)| {
+                            c.eq_ignore_ascii_case(&from.1) && r.eq_ignore_ascii_case(&to.1)
+                        })
+                })
+            })
+        };
+        references(a, b) || references(b, a)
+    }
+
+    /// Whether either table has a foreign key to the other
+    fn tables_have_foreign_key(&self, a: &QualifiedName, b: &QualifiedName) -> bool {
+        let references = |from: &QualifiedName, to: &QualifiedName| {
+            self.catalog.get_table(from).is_some_and(|table| {
+                table
+                    .foreign_keys
+                    .iter()
+                    .any(|fk| same_table(self.catalog, &fk.references_table, to))
+            })
+        };
+        references(a, b) || references(b, a)
+    }
+}
+
+/// Whether `name` (as written in a foreign key) refers to the table `table`
+fn same_table(catalog: &Catalog, name: &QualifiedName, table: &QualifiedName) -> bool {
+    name.name == table.name
+        && name.schema.as_ref().unwrap_or(&catalog.default_schema)
+            == table.schema.as_ref().unwrap_or(&catalog.default_schema)
+}
+
+fn is_related(related: &[(ColumnKey, ColumnKey)], a: &ColumnKey, b: &ColumnKey) -> bool {
+    related
+        .iter()
+        .any(|(x, y)| (x == a && y == b) || (x == b && y == a))
+}
+
+/// Comparisons combined by AND/OR/NOT at the top of a condition
+///
+/// Subqueries are not entered; they have their own FROM clause.
+pub(super) fn collect_comparisons<'e>(
+    expr: &'e Expr,
+    out: &mut Vec<(&'e Expr, &'e BinaryOperator, &'e Expr)>,
+) {
+    match expr {
+        Expr::Nested(inner) => collect_comparisons(inner, out),
+        Expr::UnaryOp { expr, .. } => collect_comparisons(expr, out),
+        Expr::BinaryOp { left, op, right } => match op {
+            BinaryOperator::And | BinaryOperator::Or => {
+                collect_comparisons(left, out);
+                collect_comparisons(right, out);
+            }
+            BinaryOperator::Eq
+            | BinaryOperator::NotEq
+            | BinaryOperator::Lt
+            | BinaryOperator::LtEq
+            | BinaryOperator::Gt
+            | BinaryOperator::GtEq => out.push((left, op, right)),
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+pub(super) fn join_constraint(join_operator: &JoinOperator) -> Option<&JoinConstraint> {
+    use JoinOperator::*;
+    match join_operator {
+        Inner(c) | LeftOuter(c) | RightOuter(c) | FullOuter(c) | LeftSemi(c) | RightSemi(c)
+        | LeftAnti(c) | RightAnti(c) => Some(c),
+        CrossJoin | CrossApply | OuterApply | AsOf { .. } | Anti(_) | Semi(_) => None,
+    }
+}
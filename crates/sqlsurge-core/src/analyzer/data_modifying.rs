@@ -0,0 +1,277 @@
+//! Data-modifying statements in WITH (PostgreSQL)
+//!
+//! A CTE can be an INSERT, UPDATE or DELETE, whose columns are those of its
+//! RETURNING clause:
+//!
+//! ```sql
+//! WITH moved AS (DELETE FROM orders WHERE created_at < now() - interval '1 year' RETURNING *)
+//! INSERT INTO orders_archive SELECT * FROM moved
+//! ```
+//!
+//! sqlparser parses INSERT and UPDATE bodies (`SetExpr::Insert`,
+//! `SetExpr::Update`) but rejects DELETE. Before PostgreSQL input is parsed,
+//! each DELETE body is replaced by `SELECT 1`, keeping every line and
+//! column in place, and parsed on its own; once the input is parsed it is
+//! put back in place of the placeholder, as a `SetExpr::Update` (which holds
+//! any statement), so every pass treats it like an UPDATE body.
+
+use sqlparser::ast::helpers::attached_token::AttachedToken;
+use sqlparser::ast::{
+    FromTable, GroupByExpr, Query, Select, SetExpr, Statement, TableAlias, TableFactor,
+    TableWithJoins, VisitMut, VisitorMut,
+};
+use sqlparser::dialect::PostgreSqlDialect;
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::{Location, Token, TokenWithSpan, Tokenizer};
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+use super::hints::{apply_edits, is_word};
+
+/// A DELETE body of a CTE, parsed on its own
+pub(super) struct DeleteCte {
+    /// Start of the DELETE, where the placeholder's SELECT is
+    anchor: Location,
+    statement: Statement,
+}
+
+/// SQL text with the DELETE bodies of CTEs replaced
+pub(super) struct Rewritten {
+    pub sql: String,
+    pub deletes: Vec<DeleteCte>,
+}
+
+/// Replace the DELETE bodies of CTEs with placeholders, or `None` if `sql`
+/// has none
+pub(super) fn rewrite(sql: &str) -> Option<Rewritten> {
+    // Most input has none, and isn't tokenized
+    let keyword = b"delete";
+    if !sql
+        .as_bytes()
+        .windows(keyword.len())
+        .any(|w| w.eq_ignore_ascii_case(keyword))
+    {
+        return None;
+    }
+    let all = Tokenizer::new(&PostgreSqlDialect {}, sql)
+        .tokenize_with_location()
+        .ok()?;
+    let end_of_text = all.last()?.span.end;
+    let tokens: Vec<&TokenWithSpan> = all
+        .iter()
+        .filter(|t| !matches!(t.token, Token::Whitespace(_)))
+        .collect();
+
+    let mut edits = Vec::new();
+    let mut deletes = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let Some(start) = cte_body_start(&tokens, i) else {
+            i += 1;
+            continue;
+        };
+        let Some(end) = closing_paren(&tokens, start) else {
+            break;
+        };
+        // The DELETE alone, everything else blanked out
+        let alone = apply_edits(
+            sql,
+            &[
+                (Location::new(1, 1), tokens[start].span.start, ""),
+                (tokens[end].span.start, end_of_text, ""),
+            ],
+        );
+        if let Ok(mut statements) = Parser::parse_sql(&PostgreSqlDialect {}, &alone) {
+            if let [Statement::Delete(_)] = statements.as_slice() {
+                edits.push((tokens[start].span.start, tokens[start].span.end, "SELECT"));
+                edits.push((tokens[start + 1].span.start, tokens[end].span.start, "1"));
+                deletes.push(DeleteCte {
+                    anchor: tokens[start].span.start,
+                    statement: statements.remove(0),
+                });
+            }
+        }
+        i = end + 1;
+    }
+
+    if edits.is_empty() {
+        return None;
+    }
+    Some(Rewritten {
+        sql: apply_edits(sql, &edits),
+        deletes,
+    })
+}
+
+/// Index of the DELETE of `name AS [NOT MATERIALIZED] (DELETE`, if the
+/// tokens at `i` are one
+fn cte_body_start(tokens: &[&TokenWithSpan], i: usize) -> Option<usize> {
+    if !is_word(tokens[i], "AS") {
+        return None;
+    }
+    let mut j = i + 1;
+    if tokens.get(j).is_some_and(|t| is_word(t, "NOT")) {
+        j += 1;
+    }
+    if tokens.get(j).is_some_and(|t| is_word(t, "MATERIALIZED")) {
+        j += 1;
+    }
+    let opens = tokens.get(j).is_some_and(|t| t.token == Token::LParen);
+    let deletes = tokens.get(j + 1).is_some_and(|t| is_word(t, "DELETE"));
+    // A DELETE needs at least FROM and a table after it
+    (opens && deletes && j + 3 < tokens.len()).then_some(j + 1)
+}
+
+/// Index of the parenthesis closing the one before `start`
+fn closing_paren(tokens: &[&TokenWithSpan], start: usize) -> Option<usize> {
+    let mut depth = 1;
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        match token.token {
+            Token::LParen => depth += 1,
+            Token::RParen => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Put the DELETE bodies back in place of their placeholders
+pub(super) fn restore(statements: &mut [Arc<Statement>], deletes: &[DeleteCte]) {
+    struct Restorer<'a> {
+        deletes: &'a [DeleteCte],
+    }
+
+    impl VisitorMut for Restorer<'_> {
+        type Break = ();
+
+        fn pre_visit_query(&mut self, query: &mut Query) -> ControlFlow<()> {
+            let Some(with) = &mut query.with else {
+                return ControlFlow::Continue(());
+            };
+            for cte in &mut with.cte_tables {
+                let SetExpr::Select(select) = cte.query.body.as_ref() else {
+                    continue;
+                };
+                let start = select.select_token.0.span.start;
+                if let Some(delete) = self.deletes.iter().find(|d| d.anchor == start) {
+                    *cte.query.body = SetExpr::Update(delete.statement.clone());
+                }
+            }
+            ControlFlow::Continue(())
+        }
+    }
+
+    for statement in statements {
+        let _ = Arc::make_mut(statement).visit(&mut Restorer { deletes });
+    }
+}
+
+/// The SELECT equivalent to the RETURNING clause of an INSERT, UPDATE or
+/// DELETE: its items over the relations the statement reads, or `None` for
+/// other statements and those without RETURNING
+///
+/// For an INSERT that is its target table; for an UPDATE, the target and
+/// FROM tables; for a DELETE, the target and USING tables.
+pub(super) fn returning_select(statement: &Statement) -> Option<Select> {
+    let (returning, from, selection) = match statement {
+        Statement::Insert(insert) => {
+            let target = TableFactor::Table {
+                name: insert.table_name.clone(),
+                alias: insert.table_alias.clone().map(|name| TableAlias {
+                    name,
+                    columns: Vec::new(),
+                }),
+                args: None,
+                with_hints: Vec::new(),
+                version: None,
+                with_ordinality: false,
+                partitions: Vec::new(),
+                json_path: None,
+            };
+            let from = vec![TableWithJoins {
+                relation: target,
+                joins: Vec::new(),
+            }];
+            (&insert.returning, from, None)
+        }
+        Statement::Update {
+            table,
+            from,
+            selection,
+            returning,
+            ..
+        } => {
+            let from = std::iter::once(table).chain(from).cloned().collect();
+            (returning, from, selection.clone())
+        }
+        Statement::Delete(delete) => {
+            let (FromTable::WithFromKeyword(tables) | FromTable::WithoutKeyword(tables)) =
+                &delete.from;
+            let from = tables
+                .iter()
+                .chain(delete.using.iter().flatten())
+                .cloned()
+                .collect();
+            (&delete.returning, from, delete.selection.clone())
+        }
+        _ => return None,
+    };
+    Some(Select {
+        select_token: AttachedToken::empty(),
+        distinct: None,
+        top: None,
+        top_before_distinct: false,
+        projection: returning.clone()?,
+        into: None,
+        from,
+        lateral_views: Vec::new(),
+        prewhere: None,
+        selection,
+        group_by: GroupByExpr::Expressions(Vec::new(), Vec::new()),
+        cluster_by: Vec::new(),
+        distribute_by: Vec::new(),
+        sort_by: Vec::new(),
+        having: None,
+        named_window: Vec::new(),
+        qualify: None,
+        window_before_qualify: false,
+        value_table_mode: None,
+        connect_by: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delete_bodies_are_restored() {
+        let sql = "WITH moved AS (\n  DELETE FROM orders WHERE id < 5 RETURNING *\n), \
+                   kept AS MATERIALIZED (SELECT 1)\nSELECT * FROM moved";
+        let rewritten = rewrite(sql).unwrap();
+        assert_eq!(rewritten.sql.len(), sql.len());
+        let line = rewritten.sql.lines().nth(1).unwrap();
+        assert_eq!(line, format!("  SELECT 1{}", " ".repeat(35)));
+        assert_eq!(rewritten.deletes.len(), 1);
+        assert_eq!(rewritten.deletes[0].anchor, Location::new(2, 3));
+
+        let parsed = Parser::parse_sql(&PostgreSqlDialect {}, &rewritten.sql).unwrap();
+        let mut statements: Vec<Arc<Statement>> = parsed.into_iter().map(Arc::new).collect();
+        restore(&mut statements, &rewritten.deletes);
+        let Statement::Query(query) = statements[0].as_ref() else {
+            panic!("expected a query");
+        };
+        let body = &query.with.as_ref().unwrap().cte_tables[0].query.body;
+        let SetExpr::Update(Statement::Delete(delete)) = body.as_ref() else {
+            panic!("expected the DELETE back, got {}", body);
+        };
+        let select = returning_select(&Statement::Delete(delete.clone())).unwrap();
+        assert_eq!(select.from.len(), 1);
+        assert_eq!(select.projection.len(), 1);
+
+        assert!(rewrite("DELETE FROM orders WHERE id IN (SELECT 1)").is_none());
+    }
+}
@@ -0,0 +1,69 @@
+//! Duplicate output column names (W0015)
+//!
+//! `SELECT id, name AS id FROM users` and `SELECT * FROM users u JOIN
+//! orders o ON ...` return two columns named `id`. Databases allow it, but
+//! clients reading rows by column name (JSON serialization, ORMs, `row["id"]`)
+//! silently keep only one of them. Only the final output of a statement is
+//! checked: the arms of a set operation other than the first don't name
+//! columns, and subqueries report ambiguous references where they are used.
+
+use sqlparser::ast::Query;
+use std::collections::HashMap;
+
+use crate::error::{Diagnostic, DiagnosticKind};
+use crate::schema::Catalog;
+
+use super::projection::{OutputSource, ProjectionExpander};
+use super::resolver::CteDefinition;
+
+/// Check the output columns of a statement's query
+pub(super) fn check_query(
+    catalog: &Catalog,
+    ctes: &HashMap<String, CteDefinition>,
+    query: &Query,
+) -> Vec<Diagnostic> {
+    let Some(sources) = ProjectionExpander::new(catalog, ctes).query_sources(query) else {
+        return Vec::new();
+    };
+    let mut diagnostics = Vec::new();
+    for (idx, source) in sources.iter().enumerate() {
+        let Some(first) = sources[..idx]
+            .iter()
+            .find(|earlier| earlier.name.eq_ignore_ascii_case(&source.name))
+        else {
+            continue;
+        };
+        diagnostics.push(duplicate(first, source));
+    }
+    diagnostics
+}
+
+fn duplicate(first: &OutputSource, source: &OutputSource) -> Diagnostic {
+    let message = match (&first.relation, &source.relation) {
+        (Some(a), Some(b)) if a != b => format!(
+            "Output column '{}' appears more than once, from '{}' and '{}'",
+            source.name, a, b
+        ),
+        _ => format!("Output column '{}' appears more than once", source.name),
+    };
+    let alias = match &source.relation {
+        Some(relation) => format!("{}_{}", relation, source.name),
+        None => format!("{}_2", source.name),
+    };
+    let help = if source.wildcard {
+        format!(
+            "Clients reading columns by name see only one of them; list the columns \
+             instead of *, giving one a distinct alias (e.g. AS {})",
+            alias
+        )
+    } else {
+        format!(
+            "Clients reading columns by name see only one of them; give this one a \
+             distinct alias (e.g. AS {})",
+            alias
+        )
+    };
+    Diagnostic::warning(DiagnosticKind::DuplicateOutputColumn, message)
+        .with_span(source.span)
+        .with_help(help)
+}
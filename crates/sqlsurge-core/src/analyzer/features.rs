@@ -0,0 +1,273 @@
+//! Feature gates - flags SQL constructs the target server version lacks
+//!
+//! Only active for features that are never available in a dialect, or when a
+//! target version is configured (e.g. `--dialect mysql@5.7`).
+
+use sqlparser::ast::{
+    AlterTableOperation, ColumnDef, ColumnOption, Expr, GeneratedAs, Query, Spanned, Statement,
+    Visit, Visitor,
+};
+use sqlparser::dialect::MySqlDialect;
+use sqlparser::keywords::Keyword;
+use sqlparser::tokenizer::{Token, TokenWithSpan, Tokenizer};
+use std::collections::HashSet;
+use std::ops::ControlFlow;
+
+use crate::dialect::{DialectVersion, SqlDialect};
+use crate::error::{Diagnostic, DiagnosticKind, Span};
+
+/// SQL features that are not available in every dialect version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Feature {
+    CommonTableExpression,
+    WindowFunction,
+    Merge,
+    IdentityColumn,
+    AggregateFilter,
+}
+
+/// Availability of a feature in a dialect
+enum Availability {
+    Always,
+    Since(DialectVersion),
+    Never,
+}
+
+impl Feature {
+    fn description(&self) -> &'static str {
+        match self {
+            Feature::CommonTableExpression => "Common table expression (WITH)",
+            Feature::WindowFunction => "Window function (OVER)",
+            Feature::Merge => "MERGE statement",
+            Feature::IdentityColumn => "Identity column (GENERATED ... AS IDENTITY)",
+            Feature::AggregateFilter => "Aggregate FILTER clause",
+        }
+    }
+
+    fn availability(&self, dialect: SqlDialect) -> Availability {
+        match (self, dialect) {
+            (Feature::CommonTableExpression | Feature::WindowFunction, SqlDialect::MySQL) => {
+                Availability::Since(DialectVersion::new(8, 0))
+            }
+            (Feature::Merge, SqlDialect::PostgreSQL) => {
+                Availability::Since(DialectVersion::new(15, 0))
+            }
+            (Feature::IdentityColumn, SqlDialect::PostgreSQL) => {
+                Availability::Since(DialectVersion::new(10, 0))
+            }
+            (
+                Feature::Merge | Feature::IdentityColumn | Feature::AggregateFilter,
+                SqlDialect::MySQL,
+            ) => Availability::Never,
+            _ => Availability::Always,
+        }
+    }
+}
+
+/// Check a statement for features unavailable in the target dialect version
+pub(super) fn check_features(
+    stmt: &Statement,
+    dialect: SqlDialect,
+    version: Option<DialectVersion>,
+    max_depth: usize,
+) -> Vec<Diagnostic> {
+    let mut gate = FeatureGate {
+        dialect,
+        version,
+        depth: 0,
+        max_depth,
+        reported: HashSet::new(),
+        diagnostics: Vec::new(),
+    };
+    let _ = stmt.visit(&mut gate);
+    gate.diagnostics
+}
+
+/// Check a CREATE TABLE column of a schema for an identity column the target
+/// dialect version lacks
+pub(crate) fn check_schema_column(
+    column: &ColumnDef,
+    dialect: SqlDialect,
+    version: Option<DialectVersion>,
+) -> Option<Diagnostic> {
+    let mut gate = FeatureGate {
+        dialect,
+        version,
+        depth: 0,
+        max_depth: 0,
+        reported: HashSet::new(),
+        diagnostics: Vec::new(),
+    };
+    gate.check_column(column);
+    gate.diagnostics.pop()
+}
+
+/// Report an aggregate `FILTER (WHERE ...)` clause in a statement the
+/// MySQL parser rejected
+///
+/// MySQL has no FILTER clause, so the parser fails on it; naming the feature
+/// is more useful than the generic parse error. `sql` must keep the original
+/// line and column positions.
+pub(super) fn mysql_filter_clause(sql: &str) -> Option<Diagnostic> {
+    let tokens = Tokenizer::new(&MySqlDialect {}, sql)
+        .tokenize_with_location()
+        .ok()?;
+    let tokens: Vec<&TokenWithSpan> = tokens
+        .iter()
+        .filter(|t| !matches!(t.token, Token::Whitespace(_)))
+        .collect();
+    let filter = tokens
+        .windows(3)
+        .find_map(|w| match (&w[0].token, &w[1].token, &w[2].token) {
+            (Token::Word(f), Token::LParen, Token::Word(k))
+                if f.quote_style.is_none()
+                    && f.value.eq_ignore_ascii_case("FILTER")
+                    && k.keyword == Keyword::WHERE =>
+            {
+                Some(w[0])
+            }
+            _ => None,
+        })?;
+    Some(
+        Diagnostic::error(
+            DiagnosticKind::UnsupportedFeature,
+            format!(
+                "{} is not supported by {}",
+                Feature::AggregateFilter.description(),
+                SqlDialect::MySQL.product_name()
+            ),
+        )
+        .with_span(Span::from_sqlparser(&filter.span))
+        .with_help(
+            "Move the condition into the aggregate, e.g. SUM(CASE WHEN ... THEN 1 ELSE 0 END)",
+        ),
+    )
+}
+
+struct FeatureGate {
+    dialect: SqlDialect,
+    version: Option<DialectVersion>,
+    /// Expression depth; the walk stops past `max_depth` rather than
+    /// overflowing the stack (the resolvers report the truncation)
+    depth: usize,
+    max_depth: usize,
+    /// Each feature is reported once per statement
+    reported: HashSet<Feature>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl FeatureGate {
+    fn use_feature(&mut self, feature: Feature, span: sqlparser::tokenizer::Span) {
+        let product = self.dialect.product_name();
+        let diag = match (feature.availability(self.dialect), self.version) {
+            (Availability::Never, _) => Diagnostic::error(
+                DiagnosticKind::UnsupportedFeature,
+                format!("{} is not supported by {}", feature.description(), product),
+            ),
+            (Availability::Since(minimum), Some(version)) if version < minimum => {
+                Diagnostic::error(
+                    DiagnosticKind::UnsupportedFeature,
+                    format!(
+                        "{} requires {} {} or later (target is {} {})",
+                        feature.description(),
+                        product,
+                        minimum,
+                        product,
+                        version
+                    ),
+                )
+                .with_help(format!(
+                    "Rewrite the statement, or raise the target version (e.g. --dialect {}@{})",
+                    self.dialect, minimum
+                ))
+            }
+            _ => return,
+        };
+        if !self.reported.insert(feature) {
+            return;
+        }
+        let diag = if span == sqlparser::tokenizer::Span::empty() {
+            diag
+        } else {
+            diag.with_span(Span::from_sqlparser(&span))
+        };
+        self.diagnostics.push(diag);
+    }
+
+    fn check_column(&mut self, column: &ColumnDef) {
+        let is_identity = column.options.iter().any(|opt| {
+            matches!(
+                opt.option,
+                ColumnOption::Generated {
+                    generated_as: GeneratedAs::Always | GeneratedAs::ByDefault,
+                    generation_expr: None,
+                    ..
+                }
+            )
+        });
+        if is_identity {
+            self.use_feature(Feature::IdentityColumn, column.name.span);
+        }
+    }
+}
+
+impl Visitor for FeatureGate {
+    type Break = ();
+
+    fn pre_visit_statement(&mut self, stmt: &Statement) -> ControlFlow<()> {
+        match stmt {
+            Statement::Merge { table, .. } => {
+                self.use_feature(Feature::Merge, table.span());
+            }
+            Statement::CreateTable(create) => {
+                for column in &create.columns {
+                    self.check_column(column);
+                }
+            }
+            Statement::AlterTable { operations, .. } => {
+                for op in operations {
+                    if let AlterTableOperation::AddColumn { column_def, .. } = op {
+                        self.check_column(column_def);
+                    }
+                }
+            }
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<()> {
+        if let Some(cte) = query.with.as_ref().and_then(|w| w.cte_tables.first()) {
+            self.use_feature(Feature::CommonTableExpression, cte.alias.name.span);
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_expr(&mut self, expr: &Expr) -> ControlFlow<()> {
+        self.depth += 1;
+        if self.depth > self.max_depth {
+            return ControlFlow::Break(());
+        }
+        if let Expr::Function(func) = expr {
+            let span = func
+                .name
+                .0
+                .first()
+                .map_or(sqlparser::tokenizer::Span::empty(), |i| i.span);
+            if func.over.is_some() {
+                self.use_feature(Feature::WindowFunction, span);
+            }
+            // The MySQL parser rejects FILTER itself (see `mysql_filter_clause`);
+            // this covers dialects that parse it leniently
+            if func.filter.is_some() {
+                self.use_feature(Feature::AggregateFilter, span);
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn post_visit_expr(&mut self, _expr: &Expr) -> ControlFlow<()> {
+        self.depth -= 1;
+        ControlFlow::Continue(())
+    }
+}
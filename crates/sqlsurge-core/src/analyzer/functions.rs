@@ -0,0 +1,372 @@
+//! Function call helpers shared by the type resolver and output inference
+
+use sqlparser::ast::{
+    Expr, Function, FunctionArg, FunctionArgExpr, FunctionArguments, TableAlias, TableFactor,
+};
+
+use crate::idents::object_name_to_qualified;
+use crate::schema::{Catalog, FunctionSig};
+use crate::types::SqlType;
+
+/// Lowercased, unqualified name of a function (`pg_catalog.UNNEST` -> `unnest`)
+pub(super) fn function_name(func: &Function) -> String {
+    func.name
+        .0
+        .last()
+        .map(|i| i.value.to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Expression arguments of a function call (`*` and subquery arguments are skipped)
+pub(super) fn function_args(func: &Function) -> Vec<&Expr> {
+    match &func.args {
+        FunctionArguments::List(list) => arg_exprs(&list.args),
+        _ => Vec::new(),
+    }
+}
+
+fn arg_exprs(args: &[FunctionArg]) -> Vec<&Expr> {
+    args.iter()
+        .filter_map(|arg| match arg {
+            FunctionArg::Unnamed(FunctionArgExpr::Expr(e))
+            | FunctionArg::Named {
+                arg: FunctionArgExpr::Expr(e),
+                ..
+            } => Some(e),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The overloads declared in the catalog for a call, by its schema-qualified
+/// name: `audit.log_it(...)` only matches functions of `audit`
+pub(super) fn declared_overloads<'c>(catalog: &'c Catalog, func: &Function) -> &'c [FunctionSig] {
+    catalog.get_functions(&object_name_to_qualified(&func.name))
+}
+
+/// Whether a call is to a built-in function, which a declared function of
+/// the same name only adds overloads to
+pub(super) fn is_builtin_call(func: &Function) -> bool {
+    let name = object_name_to_qualified(&func.name);
+    let builtin_schema = match &name.schema {
+        None => true,
+        Some(schema) => ["pg_catalog", "information_schema"]
+            .iter()
+            .any(|s| s.eq_ignore_ascii_case(schema)),
+    };
+    builtin_schema
+        && BUILTIN_FUNCTIONS
+            .binary_search(&name.name.to_lowercase().as_str())
+            .is_ok()
+}
+
+/// Common built-in functions of PostgreSQL and MySQL, sorted for binary search
+///
+/// Their signatures aren't in the catalog; a schema declaring a function of
+/// the same name adds an overload rather than the only signature.
+const BUILTIN_FUNCTIONS: &[&str] = &[
+    "abs",
+    "age",
+    "array_agg",
+    "array_append",
+    "array_cat",
+    "array_length",
+    "array_position",
+    "array_remove",
+    "array_to_string",
+    "ascii",
+    "avg",
+    "bit_and",
+    "bit_or",
+    "bool_and",
+    "bool_or",
+    "btrim",
+    "cardinality",
+    "ceil",
+    "ceiling",
+    "char_length",
+    "character_length",
+    "chr",
+    "coalesce",
+    "concat",
+    "concat_ws",
+    "count",
+    "cume_dist",
+    "current_date",
+    "current_timestamp",
+    "currval",
+    "date_add",
+    "date_format",
+    "date_part",
+    "date_sub",
+    "date_trunc",
+    "datediff",
+    "decode",
+    "dense_rank",
+    "div",
+    "encode",
+    "every",
+    "exp",
+    "extract",
+    "first_value",
+    "floor",
+    "format",
+    "from_unixtime",
+    "gen_random_uuid",
+    "generate_series",
+    "generate_subscripts",
+    "greatest",
+    "group_concat",
+    "if",
+    "ifnull",
+    "initcap",
+    "isnull",
+    "json_agg",
+    "json_array_elements",
+    "json_arrayagg",
+    "json_build_array",
+    "json_build_object",
+    "json_extract",
+    "json_object",
+    "json_object_agg",
+    "json_objectagg",
+    "json_unquote",
+    "jsonb_agg",
+    "jsonb_array_elements",
+    "jsonb_build_array",
+    "jsonb_build_object",
+    "jsonb_object_agg",
+    "jsonb_set",
+    "lag",
+    "last_insert_id",
+    "last_value",
+    "lastval",
+    "lead",
+    "least",
+    "left",
+    "length",
+    "ln",
+    "log",
+    "log10",
+    "lower",
+    "lpad",
+    "ltrim",
+    "make_date",
+    "make_interval",
+    "max",
+    "md5",
+    "min",
+    "mod",
+    "nextval",
+    "now",
+    "nth_value",
+    "ntile",
+    "nullif",
+    "octet_length",
+    "percent_rank",
+    "position",
+    "pow",
+    "power",
+    "quote_ident",
+    "quote_literal",
+    "quote_nullable",
+    "rand",
+    "random",
+    "rank",
+    "regexp_matches",
+    "regexp_replace",
+    "regexp_split_to_array",
+    "regexp_split_to_table",
+    "repeat",
+    "replace",
+    "reverse",
+    "right",
+    "round",
+    "row_number",
+    "rpad",
+    "rtrim",
+    "setval",
+    "sign",
+    "split_part",
+    "sqrt",
+    "stddev",
+    "str_to_date",
+    "string_agg",
+    "string_to_array",
+    "strpos",
+    "substr",
+    "substring",
+    "sum",
+    "timestampdiff",
+    "to_char",
+    "to_date",
+    "to_json",
+    "to_jsonb",
+    "to_number",
+    "to_timestamp",
+    "translate",
+    "trim",
+    "trunc",
+    "truncate",
+    "unix_timestamp",
+    "unnest",
+    "upper",
+    "uuid",
+    "uuid_generate_v4",
+    "variance",
+    "version",
+];
+
+/// Return type of a call to a function declared in the catalog, if the
+/// overloads of its arity agree on one
+pub(super) fn declared_return_type(catalog: &Catalog, func: &Function) -> Option<SqlType> {
+    let arg_count = function_args(func).len();
+    let mut overloads = declared_overloads(catalog, func)
+        .iter()
+        .filter(|sig| sig.accepts(arg_count));
+    let returns = &overloads.next()?.returns;
+    overloads
+        .all(|sig| sig.returns == *returns)
+        .then(|| returns.clone())
+}
+
+/// Row type and nullability of a set-returning function used as a scalar
+/// (e.g. `SELECT unnest(tags) AS tag`), given the type of its first argument
+///
+/// Returns `None` for functions that are not set-returning.
+pub(super) fn set_returning_type(name: &str, first_arg: &SqlType) -> Option<(SqlType, bool)> {
+    match name {
+        // Array elements may be NULL
+        "unnest" => match first_arg {
+            SqlType::Array(element) => Some(((**element).clone(), true)),
+            _ => Some((SqlType::Unknown, true)),
+        },
+        "generate_series" => Some((first_arg.clone(), false)),
+        "generate_subscripts" => Some((SqlType::Integer, false)),
+        "jsonb_array_elements" => Some((SqlType::Jsonb, false)),
+        "json_array_elements" => Some((SqlType::Json, false)),
+        "jsonb_array_elements_text" | "json_array_elements_text" => Some((SqlType::Text, true)),
+        "jsonb_object_keys" | "json_object_keys" => Some((SqlType::Text, false)),
+        "regexp_split_to_table" => Some((SqlType::Text, false)),
+        "regexp_matches" => Some((SqlType::Array(Box::new(SqlType::Text)), false)),
+        _ => None,
+    }
+}
+
+/// A function called in FROM (`generate_series(1, 10)`, `LATERAL unnest(a)`)
+pub(super) struct TableFunctionCall<'a> {
+    /// Lowercased, unqualified function name
+    pub(super) name: String,
+    pub(super) args: Vec<&'a Expr>,
+    pub(super) alias: Option<&'a TableAlias>,
+    pub(super) with_ordinality: bool,
+}
+
+impl<'a> TableFunctionCall<'a> {
+    /// The function call of a FROM item, if it is one
+    pub(super) fn from_factor(factor: &'a TableFactor) -> Option<Self> {
+        let (name, args, alias, with_ordinality) = match factor {
+            TableFactor::Table {
+                name,
+                args: Some(args),
+                alias,
+                with_ordinality,
+                ..
+            } => (name, arg_exprs(&args.args), alias, *with_ordinality),
+            TableFactor::Function {
+                name, args, alias, ..
+            } => (name, arg_exprs(args), alias, false),
+            TableFactor::UNNEST {
+                array_exprs,
+                alias,
+                with_ordinality,
+                ..
+            } => {
+                return Some(Self {
+                    name: "unnest".to_string(),
+                    args: array_exprs.iter().collect(),
+                    alias: alias.as_ref(),
+                    with_ordinality: *with_ordinality,
+                })
+            }
+            _ => return None,
+        };
+        Some(Self {
+            name: name
+                .0
+                .last()
+                .map(|i| i.value.to_lowercase())
+                .unwrap_or_default(),
+            args,
+            alias: alias.as_ref(),
+            with_ordinality,
+        })
+    }
+
+    /// Name the function's rows are visible under: its alias, or the
+    /// function name
+    pub(super) fn visible_name(&self) -> String {
+        self.alias
+            .map_or_else(|| self.name.clone(), |a| a.name.value.clone())
+    }
+
+    /// Output column names of a well-known set-returning function (see
+    /// `columns`)
+    pub(super) fn column_names(&self) -> Option<Vec<String>> {
+        let arg_types = vec![SqlType::Unknown; self.args.len()];
+        let columns = self.columns(&arg_types)?;
+        Some(columns.into_iter().map(|(name, ..)| name).collect())
+    }
+
+    /// Output columns (name, type, nullability) of a well-known
+    /// set-returning function, given the types of its arguments
+    ///
+    /// Follows PostgreSQL's naming: a function returning a single scalar
+    /// column names it after the function, or after the alias in
+    /// `AS x`; functions returning several columns (`jsonb_each`, `unnest`
+    /// of several arrays) keep their own names. An alias column list then
+    /// renames the leading columns. Returns `None` for functions whose
+    /// output is not known.
+    pub(super) fn columns(&self, arg_types: &[SqlType]) -> Option<Vec<(String, SqlType, bool)>> {
+        let column = |name: &str, data_type: SqlType, nullable: bool| {
+            (name.to_string(), data_type, nullable)
+        };
+        let mut columns = match self.name.as_str() {
+            // Shorter arrays are padded with NULLs
+            "unnest" if self.args.len() > 1 => (0..self.args.len())
+                .map(|i| match arg_types.get(i) {
+                    Some(SqlType::Array(element)) => column("unnest", (**element).clone(), true),
+                    _ => column("unnest", SqlType::Unknown, true),
+                })
+                .collect(),
+            "json_each" => vec![
+                column("key", SqlType::Text, false),
+                column("value", SqlType::Json, false),
+            ],
+            "jsonb_each" => vec![
+                column("key", SqlType::Text, false),
+                column("value", SqlType::Jsonb, false),
+            ],
+            // JSON nulls come out as SQL NULLs
+            "json_each_text" | "jsonb_each_text" => vec![
+                column("key", SqlType::Text, false),
+                column("value", SqlType::Text, true),
+            ],
+            name => {
+                let first = arg_types.first().cloned().unwrap_or(SqlType::Unknown);
+                let (data_type, nullable) = set_returning_type(name, &first)?;
+                let name = self.alias.map_or(name, |a| a.name.value.as_str());
+                vec![column(name, data_type, nullable)]
+            }
+        };
+        if self.with_ordinality {
+            columns.push(column("ordinality", SqlType::BigInt, false));
+        }
+        if let Some(alias) = self.alias {
+            for (column, name) in columns.iter_mut().zip(&alias.columns) {
+                column.0 = name.name.value.clone();
+            }
+        }
+        Some(columns)
+    }
+}
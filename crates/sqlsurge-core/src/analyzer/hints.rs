@@ -0,0 +1,278 @@
+//! MySQL index hints (`USE/FORCE/IGNORE INDEX`) and `STRAIGHT_JOIN`
+//!
+//! sqlparser rejects index hints after a table and `STRAIGHT_JOIN` between
+//! tables. Before MySQL input is parsed, hints are blanked out and
+//! `STRAIGHT_JOIN` is rewritten to `JOIN` (an inner join that only fixes the
+//! join order), keeping every line and column in place so spans still point
+//! into the original text. The removed hints are then checked against the
+//! indexes of the table they follow.
+
+use sqlparser::ast::{ObjectName, Statement, TableFactor, Visit, Visitor};
+use sqlparser::dialect::MySqlDialect;
+use sqlparser::tokenizer::{Location, Token, TokenWithSpan, Tokenizer};
+use std::ops::ControlFlow;
+
+use crate::error::{Diagnostic, DiagnosticKind, Span};
+use crate::idents::object_name_to_qualified;
+use crate::schema::Catalog;
+
+use super::suggest::{levenshtein_distance, MAX_DISTANCE};
+
+/// An index hint removed from the SQL text
+pub(super) struct IndexHint {
+    /// End of the table name or alias the hint follows
+    anchor: Location,
+    /// `USE`, `FORCE` or `IGNORE` as written
+    action: String,
+    indexes: Vec<HintedIndex>,
+}
+
+/// An index named in a hint
+struct HintedIndex {
+    name: String,
+    /// As written, for messages
+    written: String,
+    span: Span,
+}
+
+/// SQL text with index hints and `STRAIGHT_JOIN` removed
+pub(super) struct Rewritten {
+    pub sql: String,
+    pub hints: Vec<IndexHint>,
+}
+
+/// Rewrite MySQL-only join syntax, or `None` if `sql` contains none
+pub(super) fn rewrite(sql: &str) -> Option<Rewritten> {
+    let tokens = Tokenizer::new(&MySqlDialect {}, sql)
+        .tokenize_with_location()
+        .ok()?;
+    let tokens: Vec<&TokenWithSpan> = tokens
+        .iter()
+        .filter(|t| !matches!(t.token, Token::Whitespace(_)))
+        .collect();
+
+    // (start, end, replacement) of each edit, in source order
+    let mut edits: Vec<(Location, Location, &str)> = Vec::new();
+    let mut hints = Vec::new();
+    // Index of the last token of the previous hint, and the anchor it used,
+    // so `USE INDEX (a) IGNORE INDEX (b)` attaches both to the same table
+    let mut previous_hint: Option<(usize, Location)> = None;
+
+    let mut i = 0;
+    while i < tokens.len() {
+        if is_word(tokens[i], "STRAIGHT_JOIN") {
+            // `SELECT STRAIGHT_JOIN ...` is a modifier; between tables it's a join
+            let modifier = i > 0 && is_word(tokens[i - 1], "SELECT");
+            let replacement = if modifier { "" } else { "JOIN" };
+            edits.push((tokens[i].span.start, tokens[i].span.end, replacement));
+        } else if i > 0
+            && ["USE", "FORCE", "IGNORE"]
+                .iter()
+                .any(|w| is_word(tokens[i], w))
+        {
+            if let Some((end, indexes)) = parse_hint(&tokens, i) {
+                let anchor = match previous_hint {
+                    Some((last, anchor)) if last + 1 == i => anchor,
+                    _ => tokens[i - 1].span.end,
+                };
+                hints.push(IndexHint {
+                    anchor,
+                    action: tokens[i].token.to_string(),
+                    indexes,
+                });
+                edits.push((tokens[i].span.start, tokens[end].span.end, ""));
+                previous_hint = Some((end, anchor));
+                i = end;
+            }
+        }
+        i += 1;
+    }
+
+    if edits.is_empty() {
+        return None;
+    }
+    Some(Rewritten {
+        sql: apply_edits(sql, &edits),
+        hints,
+    })
+}
+
+/// Apply (start, end, replacement) edits, in source order, to `sql`
+///
+/// The rest of each replaced range is blanked out, one space per character
+/// with line breaks kept, so every line and column stays in place.
+pub(super) fn apply_edits(sql: &str, edits: &[(Location, Location, &str)]) -> String {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(sql.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let mut out = String::with_capacity(sql.len());
+    let mut pos = 0;
+    for &(start, end, replacement) in edits {
+        let start = byte_offset(sql, &line_starts, start);
+        let end = byte_offset(sql, &line_starts, end);
+        out.push_str(&sql[pos..start]);
+        out.push_str(replacement);
+        // Blank out the rest, one space per character, keeping line breaks
+        out.extend(sql[start..end].chars().skip(replacement.len()).map(|c| {
+            if c == '\n' || c == '\r' {
+                c
+            } else {
+                ' '
+            }
+        }));
+        pos = end;
+    }
+    out.push_str(&sql[pos..]);
+    out
+}
+
+/// Parse `{USE|FORCE|IGNORE} {INDEX|KEY} [FOR {JOIN|ORDER BY|GROUP BY}] ([name, ...])`
+/// starting at `start`, returning the index of the closing parenthesis
+fn parse_hint(tokens: &[&TokenWithSpan], start: usize) -> Option<(usize, Vec<HintedIndex>)> {
+    let at = |i: usize| tokens.get(i).copied();
+    let mut i = start + 1;
+    if !at(i).is_some_and(|t| is_word(t, "INDEX") || is_word(t, "KEY")) {
+        return None;
+    }
+    i += 1;
+    if at(i).is_some_and(|t| is_word(t, "FOR")) {
+        i += 1;
+        let target = at(i)?;
+        if is_word(target, "ORDER") || is_word(target, "GROUP") {
+            if !at(i + 1).is_some_and(|t| is_word(t, "BY")) {
+                return None;
+            }
+            i += 1;
+        } else if !is_word(target, "JOIN") {
+            return None;
+        }
+        i += 1;
+    }
+    if at(i)?.token != Token::LParen {
+        return None;
+    }
+
+    let mut indexes = Vec::new();
+    loop {
+        i += 1;
+        let token = at(i)?;
+        match &token.token {
+            Token::RParen => return Some((i, indexes)),
+            Token::Comma => {}
+            Token::Word(word) => indexes.push(HintedIndex {
+                name: word.value.clone(),
+                written: word.to_string(),
+                span: Span::from_sqlparser(&token.span),
+            }),
+            _ => return None,
+        }
+    }
+}
+
+/// Whether `token` is the unquoted word `word` (case-insensitive)
+pub(super) fn is_word(token: &TokenWithSpan, word: &str) -> bool {
+    matches!(&token.token, Token::Word(w) if w.quote_style.is_none() && w.value.eq_ignore_ascii_case(word))
+}
+
+/// Byte offset of a (1-indexed) line and character column
+fn byte_offset(sql: &str, line_starts: &[usize], location: Location) -> usize {
+    let start = line_starts[location.line as usize - 1];
+    sql[start..]
+        .char_indices()
+        .nth(location.column as usize - 1)
+        .map_or(sql.len(), |(i, _)| start + i)
+}
+
+/// Check that the indexes named in `hints` exist on the tables they follow
+pub(super) fn check_index_hints(
+    stmt: &Statement,
+    catalog: &Catalog,
+    hints: &[IndexHint],
+) -> Vec<Diagnostic> {
+    let mut checker = HintChecker {
+        catalog,
+        hints,
+        diagnostics: Vec::new(),
+    };
+    let _ = stmt.visit(&mut checker);
+    checker.diagnostics
+}
+
+struct HintChecker<'a> {
+    catalog: &'a Catalog,
+    hints: &'a [IndexHint],
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Visitor for HintChecker<'_> {
+    type Break = ();
+
+    fn pre_visit_table_factor(&mut self, table_factor: &TableFactor) -> ControlFlow<()> {
+        if let TableFactor::Table { name, alias, .. } = table_factor {
+            let anchor = match alias {
+                Some(alias) => alias.name.span.end,
+                None => name.0.last().map_or(Location::new(0, 0), |n| n.span.end),
+            };
+            for hint in self.hints.iter().filter(|h| h.anchor == anchor) {
+                self.check_hint(name, hint);
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+impl HintChecker<'_> {
+    fn check_hint(&mut self, name: &ObjectName, hint: &IndexHint) {
+        // Unknown tables are reported by name resolution
+        let Some(table) = self.catalog.get_table(&object_name_to_qualified(name)) else {
+            return;
+        };
+        let known = table.index_names();
+
+        if known.is_empty() {
+            if let Some(first) = hint.indexes.first() {
+                self.diagnostics.push(
+                    Diagnostic::info(
+                        DiagnosticKind::IndexNotFound,
+                        format!(
+                            "{} INDEX hint on '{}' can't be checked: the table declares no indexes",
+                            hint.action.to_uppercase(),
+                            name
+                        ),
+                    )
+                    .with_span(first.span)
+                    .with_help("Add the table's CREATE INDEX statements to the schema"),
+                );
+            }
+            return;
+        }
+
+        for index in &hint.indexes {
+            if known.iter().any(|k| k.eq_ignore_ascii_case(&index.name)) {
+                continue;
+            }
+            let similar = known
+                .iter()
+                .map(|k| {
+                    (
+                        levenshtein_distance(&k.to_lowercase(), &index.name.to_lowercase()),
+                        k,
+                    )
+                })
+                .filter(|(distance, _)| *distance <= MAX_DISTANCE)
+                .min_by_key(|(distance, _)| *distance);
+            let help = match similar {
+                Some((_, k)) => format!("Did you mean '{}'?", k),
+                None => format!("Indexes on '{}': {}", name, known.join(", ")),
+            };
+            self.diagnostics.push(
+                Diagnostic::error(
+                    DiagnosticKind::IndexNotFound,
+                    format!("Index '{}' not found on table '{}'", index.written, name),
+                )
+                .with_span(index.span)
+                .with_help(help),
+            );
+        }
+    }
+}
@@ -0,0 +1,309 @@
+//! MySQL `INSERT ... SET col = value, ...`
+//!
+//! sqlparser has no INSERT form with SET assignments. Before MySQL input is
+//! parsed, the `INSERT [modifiers] [INTO]` of such a statement is rewritten
+//! to `UPDATE`, keeping every line and column in place, so the rest parses
+//! as the SET list of an UPDATE. Once parsed, each such UPDATE is turned
+//! back into the INSERT it stands for, with the assigned columns as its
+//! column list and their values as a single VALUES row, so every pass
+//! checks it like `INSERT INTO t (a, b) VALUES (1, 2)`. A trailing
+//! `ON DUPLICATE KEY UPDATE` becomes a comma continuing the SET list, and
+//! its assignments are moved back to the INSERT's `ON DUPLICATE KEY UPDATE`.
+
+use sqlparser::ast::{
+    AssignmentTarget, Ident, Insert, OnInsert, Query, SetExpr, Statement, TableFactor, Values,
+};
+use sqlparser::dialect::MySqlDialect;
+use sqlparser::tokenizer::{Location, Token, TokenWithSpan, Tokenizer};
+use std::sync::Arc;
+
+use super::hints::{apply_edits, is_word};
+use crate::dialect::SqlDialect;
+use crate::error::{Diagnostic, DiagnosticKind, Span};
+
+/// An `INSERT ... SET` rewritten to an UPDATE
+#[derive(Debug, Clone, Copy)]
+pub(super) struct InsertSet {
+    /// Start of the table name the INSERT writes to
+    anchor: Location,
+    /// Whether it was `INSERT IGNORE`
+    ignore: bool,
+    /// Assignments of the SET list when `ON DUPLICATE KEY UPDATE` follows,
+    /// whose assignments come after them
+    set_assignments: Option<usize>,
+}
+
+/// SQL text with `INSERT ... SET` statements rewritten
+pub(super) struct Rewritten {
+    pub sql: String,
+    pub inserts: Vec<InsertSet>,
+}
+
+/// Rewrite `INSERT ... SET` statements to UPDATE, or `None` if `sql` has none
+pub(super) fn rewrite(sql: &str) -> Option<Rewritten> {
+    // Most input has none, and isn't tokenized
+    let keyword = b"insert";
+    if !sql
+        .as_bytes()
+        .windows(keyword.len())
+        .any(|w| w.eq_ignore_ascii_case(keyword))
+    {
+        return None;
+    }
+    let tokens = Tokenizer::new(&MySqlDialect {}, sql)
+        .tokenize_with_location()
+        .ok()?;
+    let tokens: Vec<&TokenWithSpan> = tokens
+        .iter()
+        .filter(|t| !matches!(t.token, Token::Whitespace(_)))
+        .collect();
+
+    let mut edits = Vec::new();
+    let mut inserts = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if !is_word(token, "INSERT") {
+            continue;
+        }
+        let Some((table, set, ignore)) = set_form_table(&tokens, i) else {
+            continue;
+        };
+        edits.push((token.span.start, tokens[table].span.start, "UPDATE "));
+        let duplicate_key_update = duplicate_key_update(&tokens, set);
+        if let Some((on, _)) = duplicate_key_update {
+            edits.push((tokens[on].span.start, tokens[on + 3].span.end, ","));
+        }
+        inserts.push(InsertSet {
+            anchor: tokens[table].span.start,
+            ignore,
+            set_assignments: duplicate_key_update.map(|(_, count)| count),
+        });
+    }
+
+    if edits.is_empty() {
+        return None;
+    }
+    Some(Rewritten {
+        sql: apply_edits(sql, &edits),
+        inserts,
+    })
+}
+
+/// Indexes of the table name and SET of `INSERT [LOW_PRIORITY | DELAYED |
+/// HIGH_PRIORITY] [IGNORE] [INTO] name SET` at `i`, and whether it has
+/// IGNORE, if the tokens are one
+fn set_form_table(tokens: &[&TokenWithSpan], i: usize) -> Option<(usize, usize, bool)> {
+    let at = |j: usize| tokens.get(j).copied();
+    let mut j = i + 1;
+    if at(j).is_some_and(|t| {
+        ["LOW_PRIORITY", "DELAYED", "HIGH_PRIORITY"]
+            .iter()
+            .any(|w| is_word(t, w))
+    }) {
+        j += 1;
+    }
+    let ignore = at(j).is_some_and(|t| is_word(t, "IGNORE"));
+    if ignore {
+        j += 1;
+    }
+    if at(j).is_some_and(|t| is_word(t, "INTO")) {
+        j += 1;
+    }
+    let table = j;
+    // The name, possibly qualified
+    loop {
+        if !at(j).is_some_and(|t| matches!(t.token, Token::Word(_))) {
+            return None;
+        }
+        j += 1;
+        if at(j).is_some_and(|t| t.token == Token::Period) {
+            j += 1;
+        } else {
+            break;
+        }
+    }
+    at(j)
+        .is_some_and(|t| is_word(t, "SET"))
+        .then_some((table, j, ignore))
+}
+
+/// Index of the ON of an `ON DUPLICATE KEY UPDATE` ending the statement
+/// whose SET list starts at `set`, and the number of assignments before it
+fn duplicate_key_update(tokens: &[&TokenWithSpan], set: usize) -> Option<(usize, usize)> {
+    let mut depth = 0usize;
+    let mut assignments = 1;
+    for (j, token) in tokens.iter().enumerate().skip(set + 1) {
+        match token.token {
+            Token::LParen => depth += 1,
+            Token::RParen => depth = depth.saturating_sub(1),
+            Token::SemiColon if depth == 0 => return None,
+            Token::Comma if depth == 0 => assignments += 1,
+            _ if depth == 0
+                && is_word(token, "ON")
+                && ["DUPLICATE", "KEY", "UPDATE"]
+                    .iter()
+                    .enumerate()
+                    .all(|(k, w)| tokens.get(j + 1 + k).is_some_and(|t| is_word(t, w))) =>
+            {
+                return Some((j, assignments));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Report `INSERT ... SET` in a statement the PostgreSQL parser rejected
+/// (E0010)
+///
+/// `sql` must keep the original line and column positions.
+pub(super) fn postgres_insert_set(sql: &str) -> Option<Diagnostic> {
+    let dialect = SqlDialect::PostgreSQL.parser_dialect();
+    let tokens = Tokenizer::new(dialect.as_ref(), sql)
+        .tokenize_with_location()
+        .ok()?;
+    let tokens: Vec<&TokenWithSpan> = tokens
+        .iter()
+        .filter(|t| !matches!(t.token, Token::Whitespace(_)))
+        .collect();
+    let insert = tokens
+        .iter()
+        .enumerate()
+        .find(|(i, t)| is_word(t, "INSERT") && set_form_table(&tokens, *i).is_some())
+        .map(|(_, t)| *t)?;
+    Some(
+        Diagnostic::error(
+            DiagnosticKind::UnsupportedFeature,
+            format!(
+                "INSERT ... SET is not supported by {}",
+                SqlDialect::PostgreSQL.product_name()
+            ),
+        )
+        .with_span(Span::from_sqlparser(&insert.span))
+        .with_help("List the columns and their values, e.g. INSERT INTO t (a, b) VALUES (1, 2)"),
+    )
+}
+
+/// Turn the UPDATE statements standing for `INSERT ... SET` back into
+/// INSERTs
+pub(super) fn restore(statements: &mut [Arc<Statement>], inserts: &[InsertSet]) {
+    for statement in statements {
+        let Statement::Update {
+            table, assignments, ..
+        } = statement.as_ref()
+        else {
+            continue;
+        };
+        let TableFactor::Table { name, .. } = &table.relation else {
+            continue;
+        };
+        let Some(first) = name.0.first() else {
+            continue;
+        };
+        let Some(insert) = inserts.iter().find(|i| i.anchor == first.span.start) else {
+            continue;
+        };
+        let (assignments, on_duplicate) = match insert.set_assignments {
+            Some(count) if count < assignments.len() => {
+                let (set, update) = assignments.split_at(count);
+                (set, Some(OnInsert::DuplicateKeyUpdate(update.to_vec())))
+            }
+            _ => (assignments.as_slice(), None),
+        };
+        let columns: Vec<Ident> = assignments
+            .iter()
+            .filter_map(|a| match &a.target {
+                AssignmentTarget::ColumnName(column) => column.0.last().cloned(),
+                AssignmentTarget::Tuple(_) => None,
+            })
+            .collect();
+        let row = assignments.iter().map(|a| a.value.clone()).collect();
+        let source = Query {
+            with: None,
+            body: Box::new(SetExpr::Values(Values {
+                explicit_row: false,
+                rows: vec![row],
+            })),
+            order_by: None,
+            limit: None,
+            limit_by: Vec::new(),
+            offset: None,
+            fetch: None,
+            locks: Vec::new(),
+            for_clause: None,
+            settings: None,
+            format_clause: None,
+        };
+        *statement = Arc::new(Statement::Insert(Insert {
+            or: None,
+            ignore: insert.ignore,
+            into: true,
+            table_name: name.clone(),
+            table_alias: None,
+            columns,
+            overwrite: false,
+            source: Some(Box::new(source)),
+            partitioned: None,
+            after_columns: Vec::new(),
+            table: false,
+            on: on_duplicate,
+            returning: None,
+            replace_into: false,
+            priority: None,
+            insert_alias: None,
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlparser::parser::Parser;
+
+    #[test]
+    fn test_insert_set_becomes_insert() {
+        let sql = "INSERT IGNORE INTO shop.users\n  SET name = 'x', email = 'y';\n\
+                   UPDATE users SET name = 'z';\nINSERT INTO users (name) VALUES ('w');";
+        let rewritten = rewrite(sql).unwrap();
+        assert_eq!(rewritten.sql.len(), sql.len());
+        assert_eq!(
+            rewritten.sql.lines().next(),
+            Some("UPDATE             shop.users")
+        );
+        assert_eq!(rewritten.inserts.len(), 1);
+        assert_eq!(rewritten.inserts[0].anchor, Location::new(1, 20));
+
+        let parsed = Parser::parse_sql(&MySqlDialect {}, &rewritten.sql).unwrap();
+        let mut statements: Vec<Arc<Statement>> = parsed.into_iter().map(Arc::new).collect();
+        restore(&mut statements, &rewritten.inserts);
+        let Statement::Insert(insert) = statements[0].as_ref() else {
+            panic!("expected an INSERT, got {}", statements[0]);
+        };
+        assert!(insert.ignore);
+        assert_eq!(
+            insert.to_string(),
+            "INSERT IGNORE INTO shop.users (name, email) VALUES ('x', 'y')"
+        );
+        assert!(matches!(statements[1].as_ref(), Statement::Update { .. }));
+
+        // ON DUPLICATE KEY UPDATE is kept, after a SET list with commas in calls
+        let sql = "INSERT INTO users SET name = concat('a', 'b'), email = 'y'\n\
+                   ON DUPLICATE KEY UPDATE name = VALUES(name), email = 'z'";
+        let rewritten = rewrite(sql).unwrap();
+        assert_eq!(rewritten.sql.len(), sql.len());
+        assert_eq!(rewritten.inserts[0].set_assignments, Some(2));
+        let parsed = Parser::parse_sql(&MySqlDialect {}, &rewritten.sql).unwrap();
+        let mut statements: Vec<Arc<Statement>> = parsed.into_iter().map(Arc::new).collect();
+        restore(&mut statements, &rewritten.inserts);
+        assert_eq!(
+            statements[0].to_string(),
+            "INSERT INTO users (name, email) VALUES (concat('a', 'b'), 'y') \
+             ON DUPLICATE KEY UPDATE name = VALUES(name), email = 'z'"
+        );
+
+        assert!(rewrite("INSERT INTO users (name) VALUES ('SET')").is_none());
+
+        let gated = postgres_insert_set("INSERT INTO users SET name = 'x'").unwrap();
+        assert_eq!(gated.kind, DiagnosticKind::UnsupportedFeature);
+        assert!(postgres_insert_set("INSERT INTO users (name) VALUES ('x')").is_none());
+    }
+}
@@ -0,0 +1,93 @@
+//! Name interning for the resolver's scopes
+//!
+//! A query names the same few tables and aliases over and over, and the
+//! resolver saves its table scope around every subquery. Interned names are
+//! shared `Arc`s: each distinct name is allocated once per statement, repeated
+//! references to it are pointer-comparable, and copying a scope only bumps
+//! reference counts.
+
+use sqlparser::ast::ObjectName;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::idents::object_name_to_qualified;
+use crate::schema::QualifiedName;
+
+/// An interned identifier
+pub(super) type Name = Arc<str>;
+
+/// Interns identifiers and table names
+#[derive(Debug, Default)]
+pub(super) struct Interner {
+    strings: HashSet<Name>,
+    /// Unqualified table names
+    tables: HashMap<Name, Arc<QualifiedName>>,
+    /// Schema-qualified table names, by schema
+    qualified_tables: HashMap<Name, HashMap<Name, Arc<QualifiedName>>>,
+}
+
+impl Interner {
+    /// The shared copy of `s`
+    pub(super) fn intern(&mut self, s: &str) -> Name {
+        if let Some(name) = self.strings.get(s) {
+            return name.clone();
+        }
+        let name = Name::from(s);
+        self.strings.insert(name.clone());
+        name
+    }
+
+    /// The shared table name for `schema.name` (or just `name`)
+    pub(super) fn table(&mut self, schema: Option<&str>, name: &str) -> Arc<QualifiedName> {
+        let names = match schema {
+            Some(schema) => {
+                if !self.qualified_tables.contains_key(schema) {
+                    let schema = self.intern(schema);
+                    self.qualified_tables.insert(schema, HashMap::new());
+                }
+                self.qualified_tables
+                    .get_mut(schema)
+                    .expect("schema was just inserted")
+            }
+            None => &mut self.tables,
+        };
+        if let Some(table) = names.get(name) {
+            return table.clone();
+        }
+        let table = Arc::new(QualifiedName {
+            schema: schema.map(str::to_string),
+            name: name.to_string(),
+        });
+        names.insert(Name::from(name), table.clone());
+        table
+    }
+
+    /// The shared table name an `ObjectName` refers to (see
+    /// [`object_name_to_qualified`])
+    pub(super) fn object_name(&mut self, name: &ObjectName) -> Arc<QualifiedName> {
+        match name.0.as_slice() {
+            [table] => self.table(None, &table.value),
+            [schema, table] | [_, schema, table] => self.table(Some(&schema.value), &table.value),
+            _ => Arc::new(object_name_to_qualified(name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interned_names_are_shared() {
+        let mut interner = Interner::default();
+        let a = interner.intern("users");
+        let b = interner.intern("users");
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &interner.intern("orders")));
+
+        let t = interner.table(Some("app"), "users");
+        assert!(Arc::ptr_eq(&t, &interner.table(Some("app"), "users")));
+        assert!(!Arc::ptr_eq(&t, &interner.table(None, "users")));
+        assert_eq!(*t, QualifiedName::with_schema("app", "users"));
+    }
+}
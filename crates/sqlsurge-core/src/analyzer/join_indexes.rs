@@ -0,0 +1,80 @@
+//! Join columns without an index (W0018, opt-in performance hint)
+//!
+//! `JOIN orders o ON o.user_id = u.id` looks `orders` up by `user_id` for
+//! each user; unless an index leads with `user_id`, the database scans or
+//! hashes the whole table instead. Each column of a JOIN ON equality between
+//! base table columns is checked against the indexes, primary key and
+//! UNIQUE constraints of its table, and under MySQL its foreign keys, which
+//! InnoDB indexes.
+//!
+//! A catalog without any secondary index carries no index information (a
+//! snapshot taken without indexes, say), and nothing is reported rather
+//! than every join column.
+
+use sqlparser::ast::{BinaryOperator, JoinConstraint, Select, Spanned};
+
+use crate::dialect::SqlDialect;
+use crate::error::{Diagnostic, DiagnosticKind, Span};
+
+use super::cross_table::{collect_comparisons, join_constraint, Checker, ColumnKey};
+
+/// Check the JOIN ON equalities of `select`
+pub(super) fn check_joins(
+    checker: &Checker,
+    dialect: SqlDialect,
+    select: &Select,
+) -> Vec<Diagnostic> {
+    let mut comparisons = Vec::new();
+    for join in select.from.iter().flat_map(|t| &t.joins) {
+        if let Some(JoinConstraint::On(on)) = join_constraint(&join.join_operator) {
+            collect_comparisons(on, &mut comparisons);
+        }
+    }
+    comparisons.retain(|(_, op, _)| **op == BinaryOperator::Eq);
+    if comparisons.is_empty() || !checker.catalog().has_indexes() {
+        return Vec::new();
+    }
+
+    let mut diagnostics = Vec::new();
+    let mut reported: Vec<ColumnKey> = Vec::new();
+    for (left, _, right) in comparisons {
+        let (Some(l), Some(r)) = (checker.column(left), checker.column(right)) else {
+            continue;
+        };
+        for (expr, key) in [(left, l), (right, r)] {
+            if reported.contains(&key) || is_indexed(checker, dialect, &key) {
+                continue;
+            }
+            diagnostics.push(
+                Diagnostic::hint(
+                    DiagnosticKind::UnindexedJoinColumn,
+                    format!(
+                        "Join column '{}' is not the leading column of any index on '{}'",
+                        expr, key.0
+                    ),
+                )
+                .with_span(Span::from_sqlparser(&expr.span()))
+                .with_help(format!(
+                    "Rows of '{}' are looked up by '{}' for each row of the other side; \
+                     CREATE INDEX ON {} ({})",
+                    key.0, key.1, key.0, key.1
+                )),
+            );
+            reported.push(key);
+        }
+    }
+    diagnostics
+}
+
+fn is_indexed(checker: &Checker, dialect: SqlDialect, key: &ColumnKey) -> bool {
+    let Some(table) = checker.catalog().get_table(&key.0) else {
+        return true;
+    };
+    table.has_index_leading_with(&[&key.1])
+        || (dialect == SqlDialect::MySQL
+            && table.foreign_keys.iter().any(|fk| {
+                fk.columns
+                    .first()
+                    .is_some_and(|c| c.eq_ignore_ascii_case(&key.1))
+            }))
+}
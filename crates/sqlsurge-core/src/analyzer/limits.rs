@@ -0,0 +1,436 @@
+//! Row limit clauses - LIMIT, OFFSET and FETCH FIRST
+//!
+//! - Literal counts must be non-negative integers, and no count may reference
+//!   a column of the query (E0016). MySQL only takes integer literals and
+//!   parameters.
+//! - Syntax of the other dialect is reported as unsupported (E0010): FETCH
+//!   FIRST and `OFFSET n ROWS` under MySQL, MySQL's `LIMIT offset, count`
+//!   under PostgreSQL (which fails to parse; see [`limit_comma`]).
+//!
+//! Literals carry no location in the AST, so diagnostics point at the
+//! clause keyword, found by scanning the tokens of the input.
+
+use sqlparser::ast::{
+    Expr, OffsetRows, Query, Spanned, Statement, UnaryOperator, Value, Visit, Visitor,
+};
+use sqlparser::dialect::Dialect;
+use sqlparser::keywords::Keyword;
+use sqlparser::tokenizer::{Location, Token, TokenWithSpan, Tokenizer};
+use std::ops::ControlFlow;
+
+use crate::dialect::SqlDialect;
+use crate::error::{Diagnostic, DiagnosticKind, Span};
+
+/// A row limit clause
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Clause {
+    Limit,
+    Offset,
+    Fetch,
+}
+
+impl Clause {
+    fn name(&self) -> &'static str {
+        match self {
+            Clause::Limit => "LIMIT",
+            Clause::Offset => "OFFSET",
+            Clause::Fetch => "FETCH FIRST",
+        }
+    }
+
+    fn from_keyword(keyword: Keyword) -> Option<Self> {
+        match keyword {
+            Keyword::LIMIT => Some(Clause::Limit),
+            Keyword::OFFSET => Some(Clause::Offset),
+            Keyword::FETCH => Some(Clause::Fetch),
+            _ => None,
+        }
+    }
+}
+
+/// A significant (non-whitespace) token with its parenthesis depth
+struct DepthToken {
+    span: sqlparser::tokenizer::Span,
+    depth: usize,
+    clause: Option<Clause>,
+}
+
+/// Checks the row limit clauses of each statement of one input
+pub(super) struct RowLimitChecker<'s> {
+    dialect: SqlDialect,
+    parser_dialect: &'s dyn Dialect,
+    sql: &'s str,
+    /// Check literal values and column references (E0016)
+    values: bool,
+    /// Report syntax of the other dialect (E0010)
+    syntax: bool,
+    /// Expression depth; the walk stops past `max_depth` rather than
+    /// overflowing the stack (the resolvers report the truncation)
+    depth: usize,
+    max_depth: usize,
+    /// Tokens of `sql`, once a diagnostic needs a clause location
+    tokens: Option<Vec<DepthToken>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'s> RowLimitChecker<'s> {
+    pub(super) fn new(
+        dialect: SqlDialect,
+        parser_dialect: &'s dyn Dialect,
+        sql: &'s str,
+        values: bool,
+        syntax: bool,
+        max_depth: usize,
+    ) -> Self {
+        Self {
+            dialect,
+            parser_dialect,
+            sql,
+            values,
+            syntax,
+            depth: 0,
+            max_depth,
+            tokens: None,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Check the row limit clauses of every query in `stmt`
+    pub(super) fn check(&mut self, stmt: &Statement) -> Vec<Diagnostic> {
+        self.depth = 0;
+        let _ = stmt.visit(self);
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    fn check_query(&mut self, query: &Query) {
+        if let Some(limit) = &query.limit {
+            self.check_value(query, Clause::Limit, limit);
+        }
+        if let Some(offset) = &query.offset {
+            self.check_value(query, Clause::Offset, &offset.value);
+        }
+        if let Some(quantity) = query.fetch.as_ref().and_then(|f| f.quantity.as_ref()) {
+            self.check_value(query, Clause::Fetch, quantity);
+        }
+
+        if !self.syntax || self.dialect != SqlDialect::MySQL {
+            return;
+        }
+        let product = self.dialect.product_name();
+        if let Some(fetch) = &query.fetch {
+            let help = match &fetch.quantity {
+                Some(quantity) => format!("Use LIMIT {}", quantity),
+                None => "Use LIMIT 1".to_string(),
+            };
+            let diag = Diagnostic::error(
+                DiagnosticKind::UnsupportedFeature,
+                format!("FETCH FIRST is not supported by {}", product),
+            )
+            .with_help(help);
+            let diag = self.at_clause(diag, query, Clause::Fetch);
+            self.diagnostics.push(diag);
+        }
+        if let Some(offset) = &query.offset {
+            let diag = if offset.rows != OffsetRows::None {
+                Diagnostic::error(
+                    DiagnosticKind::UnsupportedFeature,
+                    format!("OFFSET ... ROWS is not supported by {}", product),
+                )
+                .with_help(format!("Drop ROWS: OFFSET {}", offset.value))
+            } else if query.limit.is_none() && query.fetch.is_none() {
+                Diagnostic::error(
+                    DiagnosticKind::UnsupportedFeature,
+                    format!("OFFSET without LIMIT is not supported by {}", product),
+                )
+                .with_help(format!(
+                    "Add a LIMIT larger than any table, e.g. LIMIT 18446744073709551615 OFFSET {}",
+                    offset.value
+                ))
+            } else {
+                return;
+            };
+            let diag = self.at_clause(diag, query, Clause::Offset);
+            self.diagnostics.push(diag);
+        }
+    }
+
+    /// Check the count or offset of a clause (E0016)
+    fn check_value(&mut self, query: &Query, clause: Clause, value: &Expr) {
+        if !self.values {
+            return;
+        }
+        let name = clause.name();
+        let mysql = self.dialect == SqlDialect::MySQL;
+
+        let mut columns = ColumnReferences {
+            depth: 0,
+            expr_depth: 0,
+            max_depth: self.max_depth,
+            found: Vec::new(),
+        };
+        let _ = value.visit(&mut columns);
+        if !columns.found.is_empty() {
+            for (column, span) in columns.found {
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        DiagnosticKind::InvalidRowLimit,
+                        format!("{} cannot reference column '{}'", name, column),
+                    )
+                    .with_span(Span::from_sqlparser(&span))
+                    .with_help(if mysql {
+                        "Use an integer literal or a parameter"
+                    } else {
+                        "Use a constant, a parameter or a scalar subquery"
+                    }),
+                );
+            }
+            return;
+        }
+
+        let diag = match value {
+            Expr::Value(Value::Placeholder(_) | Value::Null) => return,
+            Expr::Value(Value::Number(n, _)) if n.parse::<u64>().is_ok() => return,
+            Expr::UnaryOp {
+                op: UnaryOperator::Minus,
+                expr,
+            } if matches!(expr.as_ref(), Expr::Value(Value::Number(..))) => Diagnostic::error(
+                DiagnosticKind::InvalidRowLimit,
+                format!("{} must not be negative, found {}", name, value),
+            ),
+            // PostgreSQL rounds a numeric count to the nearest integer
+            Expr::Value(Value::Number(..)) if !mysql => Diagnostic::warning(
+                DiagnosticKind::InvalidRowLimit,
+                format!("{} {} is rounded to an integer", name, value),
+            )
+            .with_help("Use an integer"),
+            Expr::Value(Value::Number(..)) => Diagnostic::error(
+                DiagnosticKind::InvalidRowLimit,
+                format!("{} must be an integer, found {}", name, value),
+            ),
+            // PostgreSQL casts a quoted literal to bigint
+            Expr::Value(Value::SingleQuotedString(s)) if !mysql && s.parse::<u64>().is_ok() => {
+                return
+            }
+            Expr::Value(Value::SingleQuotedString(_)) => Diagnostic::error(
+                DiagnosticKind::InvalidRowLimit,
+                format!("{} must be an integer, found {}", name, value),
+            ),
+            _ if mysql => Diagnostic::error(
+                DiagnosticKind::InvalidRowLimit,
+                format!(
+                    "{} takes an integer literal or a parameter in MySQL, found {}",
+                    name, value
+                ),
+            )
+            .with_help("Compute the value in the application and pass it as a parameter"),
+            _ => return,
+        };
+        let diag = self.at_clause(diag, query, clause);
+        self.diagnostics.push(diag);
+    }
+
+    /// Point `diag` at the keyword of `query`'s clause, or at the query if
+    /// the keyword can't be found
+    fn at_clause(&mut self, diag: Diagnostic, query: &Query, clause: Clause) -> Diagnostic {
+        let span = self
+            .clause_keyword(query, clause)
+            // MySQL's `LIMIT offset, count` has no OFFSET keyword
+            .or_else(|| {
+                (clause == Clause::Offset)
+                    .then(|| self.clause_keyword(query, Clause::Limit))
+                    .flatten()
+            })
+            .unwrap_or_else(|| query.span());
+        if span.start.line == 0 {
+            return diag;
+        }
+        diag.with_span(Span::from_sqlparser(&span))
+    }
+
+    /// The clause keyword following the body of `query`, at the body's
+    /// parenthesis depth
+    fn clause_keyword(
+        &mut self,
+        query: &Query,
+        clause: Clause,
+    ) -> Option<sqlparser::tokenizer::Span> {
+        let body = query.body.span();
+        if body.start.line == 0 {
+            return None;
+        }
+        let tokens = self.tokens();
+        let before = |token: &DepthToken, location: Location| {
+            (token.span.start.line, token.span.start.column) < (location.line, location.column)
+        };
+        let first = tokens.iter().position(|t| !before(t, body.start))?;
+        let depth = tokens[first..]
+            .iter()
+            .take_while(|t| before(t, body.end))
+            .map(|t| t.depth)
+            .min()?;
+        tokens[first..]
+            .iter()
+            .skip_while(|t| before(t, body.end))
+            .take_while(|t| t.depth >= depth)
+            .find(|t| t.depth == depth && t.clause == Some(clause))
+            .map(|t| t.span)
+    }
+
+    fn tokens(&mut self) -> &[DepthToken] {
+        let (parser_dialect, sql) = (self.parser_dialect, self.sql);
+        self.tokens
+            .get_or_insert_with(|| depth_tokens(parser_dialect, sql))
+    }
+}
+
+/// Significant tokens of `sql` with their parenthesis depth (empty if it
+/// doesn't tokenize)
+fn depth_tokens(dialect: &dyn Dialect, sql: &str) -> Vec<DepthToken> {
+    let Ok(tokens) = Tokenizer::new(dialect, sql).tokenize_with_location() else {
+        return Vec::new();
+    };
+    let mut depth = 0usize;
+    let mut result = Vec::new();
+    for TokenWithSpan { token, span } in tokens {
+        let clause = match &token {
+            Token::Whitespace(_) => continue,
+            Token::LParen => {
+                depth += 1;
+                None
+            }
+            Token::RParen => {
+                depth = depth.saturating_sub(1);
+                None
+            }
+            Token::Word(w) if w.quote_style.is_none() => Clause::from_keyword(w.keyword),
+            _ => None,
+        };
+        // A closing parenthesis belongs to the outer level
+        let token_depth = if token == Token::LParen {
+            depth - 1
+        } else {
+            depth
+        };
+        result.push(DepthToken {
+            span,
+            depth: token_depth,
+            clause,
+        });
+    }
+    result
+}
+
+/// Report MySQL's `LIMIT offset, count` in a statement the PostgreSQL
+/// parser rejected (E0010)
+///
+/// `sql` must keep the original line and column positions.
+pub(super) fn limit_comma(sql: &str) -> Option<Diagnostic> {
+    let dialect = SqlDialect::PostgreSQL.parser_dialect();
+    let tokens = Tokenizer::new(dialect.as_ref(), sql)
+        .tokenize_with_location()
+        .ok()?;
+    let tokens: Vec<&TokenWithSpan> = tokens
+        .iter()
+        .filter(|t| !matches!(t.token, Token::Whitespace(_)))
+        .collect();
+    let is_count = |token: &Token| {
+        matches!(
+            token,
+            Token::Number(..) | Token::Placeholder(_) | Token::Char('?')
+        )
+    };
+    let (limit, offset, count) = tokens.windows(4).find_map(|w| {
+        match (&w[0].token, &w[1].token, &w[2].token, &w[3].token) {
+            (Token::Word(l), offset, Token::Comma, count)
+                if l.keyword == Keyword::LIMIT && is_count(offset) && is_count(count) =>
+            {
+                Some((w[0], offset, count))
+            }
+            _ => None,
+        }
+    })?;
+    Some(
+        Diagnostic::error(
+            DiagnosticKind::UnsupportedFeature,
+            format!(
+                "LIMIT offset, count is not supported by {}",
+                SqlDialect::PostgreSQL.product_name()
+            ),
+        )
+        .with_span(Span::from_sqlparser(&limit.span))
+        .with_help(format!("Use LIMIT {} OFFSET {}", count, offset)),
+    )
+}
+
+/// Column references in an expression, outside subqueries
+struct ColumnReferences {
+    /// Subquery depth
+    depth: usize,
+    /// Expression depth, bounded like the outer walk
+    expr_depth: usize,
+    max_depth: usize,
+    found: Vec<(String, sqlparser::tokenizer::Span)>,
+}
+
+impl Visitor for ColumnReferences {
+    type Break = ();
+
+    fn pre_visit_query(&mut self, _query: &Query) -> ControlFlow<()> {
+        self.depth += 1;
+        ControlFlow::Continue(())
+    }
+
+    fn post_visit_query(&mut self, _query: &Query) -> ControlFlow<()> {
+        self.depth -= 1;
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_expr(&mut self, expr: &Expr) -> ControlFlow<()> {
+        self.expr_depth += 1;
+        if self.expr_depth > self.max_depth {
+            return ControlFlow::Break(());
+        }
+        if self.depth == 0 {
+            match expr {
+                Expr::Identifier(ident) => self.found.push((ident.value.clone(), ident.span)),
+                Expr::CompoundIdentifier(idents) => {
+                    let name = idents
+                        .iter()
+                        .map(|i| i.value.as_str())
+                        .collect::<Vec<_>>()
+                        .join(".");
+                    self.found.push((name, expr.span()));
+                }
+                _ => {}
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn post_visit_expr(&mut self, _expr: &Expr) -> ControlFlow<()> {
+        self.expr_depth -= 1;
+        ControlFlow::Continue(())
+    }
+}
+
+impl Visitor for RowLimitChecker<'_> {
+    type Break = ();
+
+    fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<()> {
+        self.check_query(query);
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_expr(&mut self, _expr: &Expr) -> ControlFlow<()> {
+        self.depth += 1;
+        if self.depth > self.max_depth {
+            return ControlFlow::Break(());
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn post_visit_expr(&mut self, _expr: &Expr) -> ControlFlow<()> {
+        self.depth -= 1;
+        ControlFlow::Continue(())
+    }
+}
@@ -1,22 +1,190 @@
 //! SQL analyzer module
 
+mod aggregates;
+mod ast_cache;
+pub mod completion;
+mod cross_table;
+mod data_modifying;
+mod duplicate_columns;
+pub(crate) mod features;
+mod functions;
+mod hints;
+mod insert_set;
+mod intern;
+mod join_indexes;
+mod limits;
+mod nesting;
+mod nullability;
+mod ordering;
+mod output;
+mod overriding;
+mod placeholders;
+mod policy;
+mod profile;
+pub(crate) mod projection;
+mod refresh;
+mod report;
 mod resolver;
+mod sargability;
+mod scope;
+mod suggest;
 mod type_resolver;
 
-use sqlparser::parser::Parser;
+use serde::{Deserialize, Serialize};
+use sqlparser::ast::{ObjectName, OnCommit, Query, SetExpr, Statement};
+use sqlparser::dialect::Dialect;
+use sqlparser::parser::{Parser, ParserError};
+use sqlparser::tokenizer::{Location, Token, Tokenizer};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
 
-use crate::dialect::SqlDialect;
-use crate::error::{Diagnostic, DiagnosticKind, Span};
-use crate::schema::Catalog;
+use crate::dialect::{DialectVersion, SqlDialect};
+use crate::error::{
+    Diagnostic, DiagnosticKind, DiagnosticOrigin, Severity, SeverityOrOff, Span, StatementRef,
+};
+use crate::idents::object_name_to_qualified;
+use crate::json::offset_to_line_col;
+use crate::schema::{
+    is_copy_from_stdin, split_sql_statements, Catalog, ColumnDef, QualifiedName, SchemaBuilder,
+    TableDef,
+};
 
+use ast_cache::{AstCache, CacheKey};
+pub use nesting::DEFAULT_MAX_DEPTH;
+pub use output::OutputColumn;
+use output::{InferResult, OutputInferer};
+pub use placeholders::PlaceholderStyle;
+pub use policy::{classify, StatementClass, WritePolicy};
+use profile::Stopwatch;
+pub use profile::{Phase, ProfileReport, StatementProfile};
+pub use report::{AnalysisReport, InputReport, SeverityCounts};
 pub use resolver::NameResolver;
+pub use suggest::DEFAULT_SUGGESTION_BUDGET;
 use type_resolver::TypeResolver;
 
 /// SQL Analyzer - validates SQL against a schema catalog
 pub struct Analyzer<'a> {
     catalog: &'a Catalog,
     diagnostics: Vec<Diagnostic>,
+    /// Catalog tables and views referenced by the last analyzed input
+    relations: Vec<QualifiedName>,
     dialect: SqlDialect,
+    /// Opt-in rules that are enabled (by code or name)
+    enabled_rules: HashSet<String>,
+    /// Per-rule severity overrides (lowercased code or name)
+    rule_severity: HashMap<String, SeverityOrOff>,
+    /// Maximum nesting depth of expressions and subqueries
+    max_depth: usize,
+    /// Statements larger than this are skipped (W0011)
+    max_statement_bytes: usize,
+    /// Inputs larger than this are skipped whole (W0019)
+    max_file_size: usize,
+    /// Inputs with more statements than this are skipped whole (W0019)
+    max_statements_per_file: usize,
+    /// Target server version, for feature gating (None = latest)
+    version: Option<DialectVersion>,
+    /// Which analysis passes run
+    passes: AnalyzerPasses,
+    /// Whether pedantic rules are enabled
+    strictness: Strictness,
+    /// Statement classes the input may contain
+    policy: WritePolicy,
+    /// Whether phase timings are recorded
+    profiling: bool,
+    /// Phase timings of the last analyzed input (with profiling on)
+    profile: Option<ProfileReport>,
+    /// Template placeholder syntaxes replaced before parsing
+    placeholders: Vec<PlaceholderStyle>,
+    /// Dummy identifier for placeholders in name positions
+    placeholder_identifier: String,
+    /// Parsed statements reused when the same statement recurs
+    ast_cache: Option<AstCache>,
+    /// Statements taken from `ast_cache` so far
+    ast_cache_hits: usize,
+    /// Column names compared per "did you mean" lookup
+    suggestion_budget: usize,
+    /// Whether there is no schema to check against (see
+    /// [`Analyzer::without_catalog`])
+    schemaless: bool,
+}
+
+/// Default size limit of a statement, in bytes
+///
+/// Far above hand-written SQL; generated statements beyond it (e.g. IN lists
+/// of tens of thousands of values) are skipped rather than parsed slowly.
+pub const DEFAULT_MAX_STATEMENT_BYTES: usize = 256 * 1024;
+
+/// Deepest nesting analyzed on the caller's thread
+///
+/// sqlparser and the analysis recurse for each level of nested expressions
+/// and subqueries, with large stack frames in debug builds; 50 levels fit in
+/// a 2 MiB thread stack. A higher `max_depth`, such as the default, is
+/// analyzed on a thread with [`STACK_PER_LEVEL`] bytes of stack per level.
+const MAX_INLINE_DEPTH: usize = 50;
+const STACK_PER_LEVEL: usize = 64 * 1024;
+
+/// Lowest recursion limit of the parser
+///
+/// A lower `max_depth` lowers the parser's limit too, but not below this: a
+/// statement's own structure takes a few levels, and the analysis enforces
+/// `max_depth` itself.
+const MIN_PARSER_DEPTH: usize = 16;
+
+/// Default dummy identifier for placeholders in name positions
+const DEFAULT_PLACEHOLDER_IDENTIFIER: &str = "placeholder";
+
+/// Analysis passes run by [`Analyzer`] (all enabled by default)
+///
+/// Disabling passes saves their cost when only some checks are wanted, e.g.
+/// name checks only. The type pass always needs the scope built by name
+/// resolution, so with `names` off names are still resolved per statement,
+/// but their diagnostics are not reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnalyzerPasses {
+    /// Table and column name resolution (E0001, E0002, E0006, ...)
+    pub names: bool,
+    /// Type inference and checking (E0003, E0007, ...)
+    pub types: bool,
+    /// Features unavailable in the target dialect version (E0010)
+    pub features: bool,
+}
+
+impl Default for AnalyzerPasses {
+    fn default() -> Self {
+        Self {
+            names: true,
+            types: true,
+            features: true,
+        }
+    }
+}
+
+/// How strictly [`Analyzer`] checks queries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Strictness {
+    /// Errors plus the default-on warnings
+    #[default]
+    Default,
+    /// Also enable pedantic rules, such as implicit casts (W0008)
+    Pedantic,
+}
+
+impl FromStr for Strictness {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "default" => Ok(Self::Default),
+            "pedantic" => Ok(Self::Pedantic),
+            _ => Err(format!(
+                "unknown strictness '{}' (expected default or pedantic)",
+                s
+            )),
+        }
+    }
 }
 
 impl<'a> Analyzer<'a> {
@@ -35,7 +203,26 @@ impl<'a> Analyzer<'a> {
         Self {
             catalog,
             diagnostics: Vec::new(),
+            relations: Vec::new(),
             dialect: SqlDialect::default(),
+            enabled_rules: HashSet::new(),
+            rule_severity: HashMap::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_statement_bytes: DEFAULT_MAX_STATEMENT_BYTES,
+            max_file_size: usize::MAX,
+            max_statements_per_file: usize::MAX,
+            version: None,
+            passes: AnalyzerPasses::default(),
+            strictness: Strictness::default(),
+            policy: WritePolicy::default(),
+            profiling: false,
+            profile: None,
+            placeholders: Vec::new(),
+            placeholder_identifier: DEFAULT_PLACEHOLDER_IDENTIFIER.to_string(),
+            ast_cache: None,
+            ast_cache_hits: 0,
+            suggestion_budget: DEFAULT_SUGGESTION_BUDGET,
+            schemaless: false,
         }
     }
 
@@ -55,8 +242,718 @@ impl<'a> Analyzer<'a> {
         Self {
             catalog,
             diagnostics: Vec::new(),
+            relations: Vec::new(),
             dialect,
+            enabled_rules: HashSet::new(),
+            rule_severity: HashMap::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_statement_bytes: DEFAULT_MAX_STATEMENT_BYTES,
+            max_file_size: usize::MAX,
+            max_statements_per_file: usize::MAX,
+            version: None,
+            passes: AnalyzerPasses::default(),
+            strictness: Strictness::default(),
+            policy: WritePolicy::default(),
+            profiling: false,
+            profile: None,
+            placeholders: Vec::new(),
+            placeholder_identifier: DEFAULT_PLACEHOLDER_IDENTIFIER.to_string(),
+            ast_cache: None,
+            ast_cache_hits: 0,
+            suggestion_budget: DEFAULT_SUGGESTION_BUDGET,
+            schemaless: false,
+        }
+    }
+
+    /// Create an analyzer for when no schema is available yet
+    ///
+    /// Only parse errors and rules that don't need the schema run (see
+    /// [`DiagnosticKind::requires_schema`]): table and column existence,
+    /// types and nullability aren't checked, and neither is anything that
+    /// depends on them. Tables created earlier in the input don't change that.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sqlsurge_core::analyzer::Analyzer;
+    /// use sqlsurge_core::dialect::SqlDialect;
+    ///
+    /// let mut analyzer = Analyzer::without_catalog(SqlDialect::PostgreSQL);
+    /// assert!(analyzer.analyze("SELECT nme FROM users").is_empty());
+    /// assert_eq!(analyzer.analyze("SELEC 1").len(), 1);
+    /// ```
+    pub fn without_catalog(dialect: SqlDialect) -> Analyzer<'static> {
+        static POSTGRESQL: OnceLock<Catalog> = OnceLock::new();
+        static MYSQL: OnceLock<Catalog> = OnceLock::new();
+        let catalog = match dialect {
+            SqlDialect::PostgreSQL => &POSTGRESQL,
+            SqlDialect::MySQL => &MYSQL,
+        }
+        .get_or_init(|| Catalog::with_default_schema(dialect.default_schema()));
+        Analyzer {
+            schemaless: true,
+            ..Analyzer::with_dialect(catalog, dialect)
+        }
+    }
+
+    /// Whether the analyzer checks queries without a schema
+    /// ([`Analyzer::without_catalog`])
+    pub fn is_schemaless(&self) -> bool {
+        self.schemaless
+    }
+
+    /// Enable opt-in rules, by code (`W0001`), name (`duplicate-insert-key`)
+    /// or group (`performance`, `security`)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sqlsurge_core::analyzer::Analyzer;
+    /// use sqlsurge_core::schema::Catalog;
+    ///
+    /// let catalog = Catalog::default();
+    /// let analyzer = Analyzer::new(&catalog).with_enabled_rules(["duplicate-insert-key"]);
+    /// ```
+    pub fn with_enabled_rules<I, S>(mut self, rules: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.enabled_rules
+            .extend(rules.into_iter().map(|r| r.into().to_lowercase()));
+        self
+    }
+
+    /// Override the severity of rules, by code (`E0002`) or name (`column-not-found`)
+    ///
+    /// `SeverityOrOff::Off` suppresses a rule; any other level also enables
+    /// an opt-in rule. Later overrides of the same rule win.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sqlsurge_core::analyzer::Analyzer;
+    /// use sqlsurge_core::schema::Catalog;
+    /// use sqlsurge_core::SeverityOrOff;
+    ///
+    /// let catalog = Catalog::default();
+    /// let analyzer = Analyzer::new(&catalog).with_rule_severity([
+    ///     ("E0002", SeverityOrOff::Warning),
+    ///     ("ambiguous-column", SeverityOrOff::Off),
+    /// ]);
+    /// ```
+    pub fn with_rule_severity<I, S>(mut self, overrides: I) -> Self
+    where
+        I: IntoIterator<Item = (S, SeverityOrOff)>,
+        S: Into<String>,
+    {
+        self.rule_severity.extend(
+            overrides
+                .into_iter()
+                .map(|(rule, level)| (rule.into().to_lowercase(), level)),
+        );
+        self
+    }
+
+    /// Disable rules, by code or name (shorthand for a `SeverityOrOff::Off` override)
+    pub fn with_disabled_rules<I, S>(self, rules: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.with_rule_severity(rules.into_iter().map(|r| (r, SeverityOrOff::Off)))
+    }
+
+    /// Set the target server version of the dialect
+    ///
+    /// Statements using features the version lacks (e.g. CTEs before MySQL 8.0,
+    /// MERGE before PostgreSQL 15) are reported as `unsupported-feature`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sqlsurge_core::analyzer::Analyzer;
+    /// use sqlsurge_core::dialect::{DialectVersion, SqlDialect};
+    /// use sqlsurge_core::schema::Catalog;
+    ///
+    /// let catalog = Catalog::default();
+    /// let analyzer = Analyzer::with_dialect(&catalog, SqlDialect::MySQL)
+    ///     .with_version(DialectVersion::new(5, 7));
+    /// ```
+    pub fn with_version(mut self, version: DialectVersion) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Select which analysis passes run (default: all)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sqlsurge_core::analyzer::{Analyzer, AnalyzerPasses};
+    /// use sqlsurge_core::schema::Catalog;
+    ///
+    /// let catalog = Catalog::default();
+    /// let analyzer = Analyzer::new(&catalog).with_passes(AnalyzerPasses {
+    ///     types: false,
+    ///     ..AnalyzerPasses::default()
+    /// });
+    /// ```
+    pub fn with_passes(mut self, passes: AnalyzerPasses) -> Self {
+        self.passes = passes;
+        self
+    }
+
+    /// Set the strictness (default: [`Strictness::Default`])
+    ///
+    /// [`Strictness::Pedantic`] enables the pedantic opt-in rules; a rule
+    /// given an explicit severity keeps it either way.
+    pub fn with_strictness(mut self, strictness: Strictness) -> Self {
+        self.strictness = strictness;
+        self
+    }
+
+    /// Restrict the statements the input may contain (default: [`WritePolicy::Any`])
+    ///
+    /// Statements the policy forbids are reported as E0015, e.g. an UPDATE
+    /// (or a data-modifying CTE) in a file marked read-only.
+    pub fn with_policy(mut self, policy: WritePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Record how long each analysis phase takes (default: off)
+    ///
+    /// The timings of the last analyzed input are available from
+    /// [`profile`](Self::profile). With profiling off no timestamps are taken.
+    pub fn with_profiling(mut self, profiling: bool) -> Self {
+        self.profiling = profiling;
+        self
+    }
+
+    /// Keep up to `capacity` parsed statements for reuse (default: off)
+    ///
+    /// A statement recurring at the same position (in files generated from
+    /// one template, or an input analyzed again) is taken from the cache
+    /// instead of being parsed; diagnostics are the same either way. The
+    /// least recently used statements are dropped once the cache is full,
+    /// and a capacity of 0 turns the cache off. With profiling on, the
+    /// report counts the statements found in the cache and those parsed.
+    pub fn with_ast_cache(mut self, capacity: usize) -> Self {
+        self.ast_cache = (capacity > 0).then(|| AstCache::new(capacity));
+        self
+    }
+
+    /// Number of statements taken from the AST cache instead of being parsed,
+    /// over all inputs analyzed so far
+    pub fn ast_cache_hits(&self) -> usize {
+        self.ast_cache_hits
+    }
+
+    /// Phase timings of the last analyzed input, if profiling is on
+    pub fn profile(&self) -> Option<&ProfileReport> {
+        self.profile.as_ref()
+    }
+
+    /// Recognize template placeholders (psql `:var`, Flyway `${var}`) in queries
+    ///
+    /// Placeholders are replaced before parsing by a parameter marker, or by
+    /// a dummy identifier where a name is expected (see
+    /// [`with_placeholder_identifier`](Self::with_placeholder_identifier)).
+    /// Diagnostics keep pointing into the original text.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sqlsurge_core::analyzer::{Analyzer, PlaceholderStyle};
+    /// use sqlsurge_core::schema::SchemaBuilder;
+    ///
+    /// let mut builder = SchemaBuilder::new();
+    /// builder.parse("CREATE TABLE users (id INTEGER, tenant_id INTEGER);").unwrap();
+    /// let (catalog, _) = builder.build();
+    ///
+    /// let mut analyzer = Analyzer::new(&catalog).with_placeholders([PlaceholderStyle::Psql]);
+    /// assert!(analyzer.analyze("SELECT id FROM users WHERE tenant_id = :tenant_id").is_empty());
+    /// ```
+    pub fn with_placeholders(mut self, styles: impl IntoIterator<Item = PlaceholderStyle>) -> Self {
+        self.placeholders = styles.into_iter().collect();
+        self
+    }
+
+    /// Set the dummy identifier substituted for placeholders in name
+    /// positions, e.g. `FROM :"table"` (default: `placeholder`)
+    pub fn with_placeholder_identifier(mut self, identifier: impl Into<String>) -> Self {
+        self.placeholder_identifier = identifier.into();
+        self
+    }
+
+    /// Set the maximum nesting depth of expressions and subqueries (default:
+    /// [`DEFAULT_MAX_DEPTH`])
+    ///
+    /// Deeper parts of a query are not analyzed and a single `nesting-too-deep`
+    /// warning is reported instead, which protects against stack overflows on
+    /// pathological machine-generated SQL. Long flat `AND`/`OR`/arithmetic
+    /// chains don't count towards the limit. Above 50, inputs are analyzed on
+    /// a thread with a stack sized for the limit.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set how many column names a "did you mean" lookup compares (default: 1000)
+    ///
+    /// Names whose length is too different to be a typo don't count. On
+    /// very wide tables, a lower budget trades suggestions for the columns
+    /// past it for speed in files with many unknown columns.
+    pub fn with_suggestion_budget(mut self, budget: usize) -> Self {
+        self.suggestion_budget = budget;
+        self
+    }
+
+    /// Set the size limit of a statement in bytes (default: 256 KiB)
+    ///
+    /// Larger statements are not parsed; a `statement-too-large` warning
+    /// names each one and the rest of the input is analyzed as usual.
+    pub fn with_max_statement_bytes(mut self, max_statement_bytes: usize) -> Self {
+        self.max_statement_bytes = max_statement_bytes;
+        self
+    }
+
+    /// Set the size limit of a whole input in bytes (default: none)
+    ///
+    /// A larger input is not analyzed at all: an `input-too-large` warning
+    /// is its only diagnostic.
+    pub fn with_max_file_size(mut self, max_file_size: usize) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    /// Set the most statements an input may have (default: none)
+    ///
+    /// An input with more is not analyzed at all: an `input-too-large`
+    /// warning is its only diagnostic.
+    pub fn with_max_statements_per_file(mut self, max_statements: usize) -> Self {
+        self.max_statements_per_file = max_statements;
+        self
+    }
+
+    /// The `input-too-large` warning for `sql`, if it is over the file size
+    /// or statement count limit
+    ///
+    /// [`Analyzer::analyze`] and [`Analyzer::analyze_streaming`] report it
+    /// instead of analyzing such an input; callers can use it to tell
+    /// skipped inputs apart. It is `None` when the rule is turned off.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sqlsurge_core::analyzer::Analyzer;
+    /// use sqlsurge_core::schema::Catalog;
+    ///
+    /// let catalog = Catalog::default();
+    /// let analyzer = Analyzer::new(&catalog).with_max_statements_per_file(2);
+    /// assert!(analyzer.input_limit("SELECT 1; SELECT 2;").is_none());
+    /// let warning = analyzer.input_limit("SELECT 1; SELECT 2; SELECT 3;").unwrap();
+    /// assert_eq!(warning.code(), "W0019");
+    /// ```
+    pub fn input_limit(&self, sql: &str) -> Option<Diagnostic> {
+        if sql.len() > self.max_file_size {
+            return self.size_limit(sql.len(), sql);
+        }
+        if self.max_statements_per_file == usize::MAX {
+            return None;
+        }
+        let count = statement_ranges(sql).len();
+        if count <= self.max_statements_per_file {
+            return None;
+        }
+        let message = format!(
+            "Input of {} statements exceeds the limit of {} (`max_statements_per_file`) and was not analyzed",
+            count, self.max_statements_per_file
+        );
+        self.limit_warning(message, "max_statements_per_file", sql)
+    }
+
+    /// The `input-too-large` warning for an input of `size` bytes, if it is
+    /// over the file size limit
+    ///
+    /// Lets a caller skip a file without reading it: `head` is the start of
+    /// the input, where the warning points. It is `None` when the rule is
+    /// turned off.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sqlsurge_core::analyzer::Analyzer;
+    /// use sqlsurge_core::schema::Catalog;
+    ///
+    /// let catalog = Catalog::default();
+    /// let analyzer = Analyzer::new(&catalog).with_max_file_size(1024);
+    /// assert!(analyzer.size_limit(1024, "INSERT INTO t VALUES (1);").is_none());
+    /// let warning = analyzer.size_limit(4096, "INSERT INTO t VALUES (1);").unwrap();
+    /// assert_eq!(warning.code(), "W0019");
+    /// ```
+    pub fn size_limit(&self, size: usize, head: &str) -> Option<Diagnostic> {
+        if size <= self.max_file_size {
+            return None;
         }
+        let message = format!(
+            "Input of {} exceeds the size limit of {} (`max_file_size`) and was not analyzed",
+            format_size(size),
+            format_size(self.max_file_size)
+        );
+        self.limit_warning(message, "max_file_size", head)
+    }
+
+    /// The `input-too-large` warning naming `limit`, pointing at the first
+    /// line of `sql`
+    fn limit_warning(&self, message: String, limit: &str, sql: &str) -> Option<Diagnostic> {
+        let first_line = sql.find('\n').unwrap_or(sql.len());
+        let mut diagnostic = Diagnostic::warning(DiagnosticKind::InputTooLarge, message)
+            .with_span(Span::new(0, first_line.min(50)))
+            .with_help(format!(
+                "Raise `{}` if the file should be checked, exclude it from the checked \
+                 patterns, or name it on the command line to check it anyway",
+                limit
+            ));
+        self.apply_rule_config(&mut diagnostic)
+            .then_some(diagnostic)
+    }
+
+    /// Recursion limit of the parser for the configured `max_depth`
+    fn parser_depth(&self) -> usize {
+        self.max_depth.max(MIN_PARSER_DEPTH)
+    }
+
+    /// Parse `sql` with the parser recursion limit derived from `max_depth`
+    fn parse(&self, dialect: &dyn Dialect, sql: &str) -> Result<Vec<Statement>, ParserError> {
+        Parser::new(dialect)
+            .with_recursion_limit(self.parser_depth())
+            .try_with_sql(sql)?
+            .parse_statements()
+    }
+
+    /// Parse `text`, which starts at `line` and `column` of its input, with
+    /// the positions it has in the input
+    ///
+    /// The tokens are moved instead of padding the text, so a statement
+    /// costs the same to parse wherever it is in the input.
+    fn parse_at(
+        &self,
+        dialect: &dyn Dialect,
+        text: &str,
+        (line, column): (usize, usize),
+    ) -> Result<Vec<Statement>, ParserError> {
+        let shift = |location: &mut Location| {
+            // Line 0 means the position is unknown
+            if location.line == 1 {
+                location.column += column as u64 - 1;
+            }
+            if location.line > 0 {
+                location.line += line as u64 - 1;
+            }
+        };
+        let mut tokens = Tokenizer::new(dialect, text)
+            .tokenize_with_location()
+            .map_err(|mut e| {
+                shift(&mut e.location);
+                ParserError::from(e)
+            })?;
+        for token in &mut tokens {
+            shift(&mut token.span.start);
+            shift(&mut token.span.end);
+        }
+        Parser::new(dialect)
+            .with_recursion_limit(self.parser_depth())
+            .with_tokens_with_locations(tokens)
+            .parse_statements()
+    }
+
+    /// Configured severity override of a rule, if any
+    fn severity_override(&self, kind: DiagnosticKind) -> Option<SeverityOrOff> {
+        self.rule_severity
+            .get(&kind.code().to_lowercase())
+            .or_else(|| self.rule_severity.get(kind.name()))
+            .copied()
+    }
+
+    /// Whether diagnostics of a rule are reported at all
+    fn is_reported(&self, kind: DiagnosticKind) -> bool {
+        match self.severity_override(kind) {
+            Some(level) => level.severity().is_some(),
+            None => self.opt_in_enabled(kind),
+        }
+    }
+
+    /// Whether a rule is on by default or enabled (by code, name, group or strictness)
+    fn opt_in_enabled(&self, kind: DiagnosticKind) -> bool {
+        (self.strictness == Strictness::Pedantic && kind.is_pedantic())
+            || kind.is_enabled_by(&self.enabled_rules)
+    }
+
+    /// Apply severity overrides; returns whether the diagnostic is reported
+    /// (opt-in rules must be enabled, or given a severity)
+    fn apply_rule_config(&self, diag: &mut Diagnostic) -> bool {
+        diag.origin = Some(DiagnosticOrigin::Query);
+        match self.severity_override(diag.kind) {
+            Some(level) => match level.severity() {
+                Some(severity) => {
+                    diag.severity = severity;
+                    true
+                }
+                None => false,
+            },
+            None => self.opt_in_enabled(diag.kind),
+        }
+    }
+
+    /// Help for a parse error caused by an unquoted reserved keyword that is
+    /// also a table or column name in the schema (e.g. `SELECT order FROM ...`)
+    fn reserved_identifier_help(&self, sql: &str) -> Option<String> {
+        let dialect = self.dialect.parser_dialect();
+        let tokens = Tokenizer::new(dialect.as_ref(), sql).tokenize().ok()?;
+        let word = tokens.iter().find_map(|token| match token {
+            Token::Word(w)
+                if w.quote_style.is_none()
+                    && self.dialect.is_reserved(&w.value)
+                    && self.catalog.defines_identifier(&w.value) =>
+            {
+                Some(w.value.as_str())
+            }
+            _ => None,
+        })?;
+        Some(format!(
+            "'{}' is a reserved keyword in {} but also a name in the schema; quote it as {}",
+            word,
+            self.dialect.product_name(),
+            self.dialect.quote_identifier(word)
+        ))
+    }
+
+    /// Parse the statements of `sql` one at a time, reporting those that fail
+    ///
+    /// Each statement is parsed with its position in the file, so spans and
+    /// error locations stay correct. Returns the statements with their index
+    /// in `statement_ranges(sql)`.
+    fn parse_statements_individually(
+        &mut self,
+        sql: &str,
+        profile: &mut ProfileReport,
+    ) -> Vec<(usize, Arc<Statement>)> {
+        let mut statements = Vec::new();
+        let mut position = LineTracker::default();
+        for (index, range) in statement_ranges(sql).into_iter().enumerate() {
+            let start = self.diagnostics.len();
+            let at = position.advance(sql, range.start);
+            let parsed = self.parse_statement_at(sql, range.clone(), at, profile);
+            statements.extend(parsed.into_iter().map(|stmt| (index, stmt)));
+            self.attribute_statement(start, sql, index, range);
+        }
+        statements
+    }
+
+    /// Parse the statement of `sql` at `range`, which starts at line and
+    /// column `at`, reporting it if it fails
+    ///
+    /// Statements that parse are kept in the AST cache, if there is one.
+    fn parse_statement_at(
+        &mut self,
+        sql: &str,
+        range: Range<usize>,
+        at: (usize, usize),
+        profile: &mut ProfileReport,
+    ) -> Vec<Arc<Statement>> {
+        let trimmed = &sql[range.clone()];
+        let key = self.ast_cache.as_ref().map(|_| {
+            let (line, column) = at;
+            CacheKey {
+                dialect: self.dialect,
+                line,
+                column,
+                text: trimmed.into(),
+            }
+        });
+        if let (Some(cache), Some(key)) = (&mut self.ast_cache, &key) {
+            if let Some(statements) = cache.get(key) {
+                profile.cache_hits += 1;
+                self.ast_cache_hits += 1;
+                return statements;
+            }
+            profile.cache_misses += 1;
+        }
+        let statements: Vec<Arc<Statement>> = self
+            .parse_uncached(sql, range, at)
+            .into_iter()
+            .map(Arc::new)
+            .collect();
+        if let (Some(cache), Some(key)) = (&mut self.ast_cache, key) {
+            if !statements.is_empty() {
+                cache.insert(key, statements.clone());
+            }
+        }
+        statements
+    }
+
+    /// Parse the statement of `sql` at `range`, which starts at line and
+    /// column `at`, reporting it if it fails
+    fn parse_uncached(
+        &mut self,
+        sql: &str,
+        range: Range<usize>,
+        at: (usize, usize),
+    ) -> Vec<Statement> {
+        let dialect = self.dialect.parser_dialect();
+        let trimmed = &sql[range.clone()];
+        let offset = range.start;
+        // The splitter has already cut out the inline data, which leaves
+        // nothing to check
+        if is_copy_from_stdin(trimmed) {
+            return Vec::new();
+        }
+        if trimmed.len() > self.max_statement_bytes {
+            self.diagnostics.push(
+                Diagnostic::warning(
+                    DiagnosticKind::StatementTooLarge,
+                    format!(
+                        "Statement of {} bytes exceeds the limit of {} bytes and was not analyzed",
+                        trimmed.len(),
+                        self.max_statement_bytes
+                    ),
+                )
+                .with_span(Span::new(offset, trimmed.len().min(50)))
+                .with_help("Split the statement, or raise `max_statement_bytes`"),
+            );
+            return Vec::new();
+        }
+        match self.parse_at(dialect.as_ref(), trimmed, at) {
+            Ok(stmts) => stmts,
+            Err(ParserError::RecursionLimitExceeded) => {
+                self.diagnostics.push(
+                    Diagnostic::warning(
+                        DiagnosticKind::NestingTooDeep,
+                        format!(
+                            "Statement nests deeper than the parser's limit of {} levels and was not analyzed",
+                            self.parser_depth()
+                        ),
+                    )
+                    .with_span(Span::new(offset, trimmed.len().min(50))),
+                );
+                Vec::new()
+            }
+            Err(e) => {
+                let gated = match self.dialect {
+                    SqlDialect::MySQL => features::mysql_filter_clause(trimmed),
+                    SqlDialect::PostgreSQL => limits::limit_comma(trimmed)
+                        .or_else(|| insert_set::postgres_insert_set(trimmed)),
+                };
+                if let Some(mut diagnostic) = gated {
+                    let (line, column) = at;
+                    let origin = StatementOrigin {
+                        index: 0,
+                        offset,
+                        line,
+                        column,
+                    };
+                    origin.rebase(&mut diagnostic);
+                    self.diagnostics.push(diagnostic);
+                    return Vec::new();
+                }
+                let mut diagnostic =
+                    Diagnostic::error(DiagnosticKind::ParseError, format!("Parse error: {}", e))
+                        .with_span(Span::new(offset, trimmed.len().min(50)));
+                if let Some(help) = self.reserved_identifier_help(trimmed) {
+                    diagnostic = diagnostic.with_help(help);
+                }
+                self.diagnostics.push(diagnostic);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Attribute the diagnostics reported since `start` (and not attributed
+    /// yet) to the statement of `sql` at `range`, the `index`th of the input
+    fn attribute_statement(&mut self, start: usize, sql: &str, index: usize, range: Range<usize>) {
+        if self.diagnostics.len() == start {
+            return;
+        }
+        let (line, column) = offset_to_line_col(sql, range.start);
+        let (end_line, end_column) = offset_to_line_col(sql, range.end);
+        let statement = StatementRef {
+            index,
+            span: Span {
+                offset: range.start,
+                length: range.len(),
+                line,
+                column,
+                end_line,
+                end_column,
+            },
+        };
+        for diag in &mut self.diagnostics[start..] {
+            diag.statement.get_or_insert(statement);
+        }
+    }
+
+    /// Apply a DDL statement to the file-local session catalog
+    ///
+    /// Schema diagnostics (e.g. dropping a missing table) are not reported here;
+    /// they belong to schema validation, not query analysis.
+    fn apply_to_session(&self, session: &mut Option<Catalog>, stmt: &Statement) {
+        let base = session.take().unwrap_or_else(|| self.catalog.clone());
+        let mut builder = SchemaBuilder::from_catalog(base, self.dialect);
+        builder.apply_statement(stmt);
+        *session = Some(builder.build().0);
+    }
+
+    /// Add the table created by CREATE TABLE AS or SELECT INTO to the
+    /// file-local session catalog, with the output columns of its query
+    ///
+    /// Warns when the name is already taken; the new table replaces the old
+    /// one for the following statements (as a temporary table would shadow
+    /// it). Nothing is added when the output columns can't be inferred; the
+    /// query's own diagnostics explain why.
+    fn create_table_from_query(
+        &mut self,
+        session: &mut Option<Catalog>,
+        name: &ObjectName,
+        query: &Query,
+        if_not_exists: bool,
+    ) {
+        let qualified = object_name_to_qualified(name);
+        let catalog = session.as_ref().unwrap_or(self.catalog);
+        if catalog.qualify(&qualified).is_some() {
+            if if_not_exists {
+                return;
+            }
+            let mut diag = Diagnostic::warning(
+                DiagnosticKind::DuplicateDefinition,
+                format!(
+                    "Table '{}' already exists",
+                    self.dialect.display_name(&qualified)
+                ),
+            )
+            .with_help(
+                "Creating it again fails unless the new table is temporary and shadows it; \
+                 choose another name or drop the existing table first",
+            );
+            if let Some(ident) = name.0.last() {
+                diag = diag.with_span(Span::from_sqlparser(&ident.span));
+            }
+            self.diagnostics.push(diag);
+        }
+
+        let Ok(columns) = OutputInferer::new(catalog).query(query) else {
+            return;
+        };
+        let mut table = TableDef::new(qualified);
+        for column in columns {
+            let mut def = ColumnDef::new(&column.name, column.data_type);
+            def.nullable = column.nullable;
+            table.add_column(def);
+        }
+        session
+            .get_or_insert_with(|| self.catalog.clone())
+            .add_table(table);
     }
 
     /// Analyze a SQL query and return diagnostics
@@ -79,37 +976,772 @@ impl<'a> Analyzer<'a> {
     /// assert!(diagnostics.is_empty());
     /// ```
     pub fn analyze(&mut self, sql: &str) -> Vec<Diagnostic> {
+        self.analyze_source(sql, false).0
+    }
+
+    /// Analyze several named inputs, each on its own like [`Analyzer::analyze`]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sqlsurge_core::analyzer::Analyzer;
+    /// use sqlsurge_core::schema::SchemaBuilder;
+    ///
+    /// let mut builder = SchemaBuilder::new();
+    /// builder.parse("CREATE TABLE users (id INTEGER, name TEXT);").unwrap();
+    /// let (catalog, _) = builder.build();
+    ///
+    /// let mut analyzer = Analyzer::new(&catalog);
+    /// let report = analyzer.analyze_batch([
+    ///     ("ok.sql", "SELECT id FROM users"),
+    ///     ("bad.sql", "SELECT nope FROM users"),
+    /// ]);
+    /// assert!(report.has_errors());
+    /// assert_eq!(report.severity_counts().error, 1);
+    /// assert_eq!(report.diagnostics().next().unwrap().0, "bad.sql");
+    /// ```
+    pub fn analyze_batch<'i>(
+        &mut self,
+        inputs: impl IntoIterator<Item = (&'i str, &'i str)>,
+    ) -> AnalysisReport {
+        let mut report = AnalysisReport::new();
+        for (name, sql) in inputs {
+            report.push(name, self.analyze(sql));
+        }
+        report
+    }
+
+    /// Analyze `sql` one statement at a time, passing each diagnostic to
+    /// `sink` as soon as its statement is analyzed
+    ///
+    /// For very large inputs (generated seed files with hundreds of
+    /// thousands of statements): only one statement's AST is alive at a
+    /// time and diagnostics aren't collected, so memory stays proportional
+    /// to the largest statement rather than the input. The diagnostics are
+    /// those of [`Analyzer::analyze`], with positions in the whole input, in
+    /// statement order (`analyze` reports parse errors first), except that
+    /// a statement the splitter can't find the end of is parsed as one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sqlsurge_core::analyzer::Analyzer;
+    /// use sqlsurge_core::schema::SchemaBuilder;
+    ///
+    /// let mut builder = SchemaBuilder::new();
+    /// builder.parse("CREATE TABLE users (id INTEGER, name TEXT);").unwrap();
+    /// let (catalog, _) = builder.build();
+    ///
+    /// let mut analyzer = Analyzer::new(&catalog);
+    /// let mut found = Vec::new();
+    /// analyzer.analyze_streaming(
+    ///     "INSERT INTO users VALUES (1, 'a');\nINSERT INTO users (nme) VALUES ('b');",
+    ///     |diagnostic| found.push(diagnostic),
+    /// );
+    /// assert_eq!(found.len(), 1);
+    /// assert_eq!(found[0].span.unwrap().line, 2);
+    /// ```
+    pub fn analyze_streaming(&mut self, sql: &str, mut sink: impl FnMut(Diagnostic)) {
+        self.relations.clear();
+        if let Some(diagnostic) = self.input_limit(sql) {
+            self.profile = None;
+            sink(diagnostic);
+            return;
+        }
+        let Some(thread) = self.deep_stack_thread() else {
+            self.stream_statements(sql, &mut sink);
+            return;
+        };
+        // Nesting past what the caller's stack is known to hold: all
+        // statements are analyzed on one thread with a larger stack, and their
+        // diagnostics passed back to `sink` here
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::scope(|scope| {
+            let worker = thread
+                .spawn_scoped(scope, move || {
+                    self.stream_statements(sql, &mut |diagnostic| {
+                        // The receiver lives until the worker is joined
+                        let _ = sender.send(diagnostic);
+                    })
+                })
+                .expect("failed to spawn the analysis thread");
+            for diagnostic in receiver {
+                sink(diagnostic);
+            }
+            worker
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        })
+    }
+
+    /// [`Analyzer::analyze_streaming`] on the current thread
+    fn stream_statements(&mut self, sql: &str, sink: &mut dyn FnMut(Diagnostic)) {
+        let file_disabled = file_disabled_rules(sql);
+        let mut state = InputState::default();
+        let mut profile = self.profiling.then(ProfileReport::default);
+        let mut position = LineTracker::default();
+        for (index, range) in statement_ranges(sql).into_iter().enumerate() {
+            let (line, column) = position.advance(sql, range.start);
+            let origin = StatementOrigin {
+                index,
+                offset: range.start,
+                line,
+                column,
+            };
+            let (diagnostics, _) =
+                self.analyze_input_inline(&sql[range], false, &file_disabled, &mut state);
+            if let (Some(total), Some(statement)) = (&mut profile, self.profile.take()) {
+                total.absorb(statement);
+            }
+            for mut diagnostic in diagnostics {
+                origin.rebase(&mut diagnostic);
+                sink(diagnostic);
+            }
+        }
+        self.profile = profile;
+    }
+
+    /// Infer the output columns of the last query in `sql`
+    ///
+    /// Like psql, multi-statement input returns the shape of the last SELECT,
+    /// resolved against any tables created earlier in the input. Returns an
+    /// empty list if there is no SELECT, and the diagnostics if the input has
+    /// errors or the shape can't be determined.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sqlsurge_core::analyzer::Analyzer;
+    /// use sqlsurge_core::schema::SchemaBuilder;
+    /// use sqlsurge_core::types::SqlType;
+    ///
+    /// let mut builder = SchemaBuilder::new();
+    /// builder.parse("CREATE TABLE users (id INTEGER NOT NULL, name TEXT);").unwrap();
+    /// let (catalog, _) = builder.build();
+    ///
+    /// let mut analyzer = Analyzer::new(&catalog);
+    /// let columns = analyzer.infer_output_schema("SELECT id, upper(name) FROM users").unwrap();
+    /// assert_eq!(columns[0].name, "id");
+    /// assert_eq!(columns[0].data_type, SqlType::Integer);
+    /// assert!(!columns[0].nullable);
+    /// assert_eq!(columns[1].name, "upper");
+    /// ```
+    pub fn infer_output_schema(&mut self, sql: &str) -> Result<Vec<OutputColumn>, Vec<Diagnostic>> {
+        let (diagnostics, output) = self.analyze_source(sql, true);
+        if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+            return Err(diagnostics);
+        }
+        match output {
+            Some(Ok(columns)) => Ok(columns),
+            Some(Err(diagnostic)) => Err(vec![*diagnostic]),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Tables and views `sql` reads or writes, schema-qualified
+    ///
+    /// References to views are expanded to the relations their definitions
+    /// read, transitively; the views themselves are included. Objects
+    /// created by `sql` itself are not dependencies.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sqlsurge_core::analyzer::Analyzer;
+    /// use sqlsurge_core::schema::{QualifiedName, SchemaBuilder};
+    ///
+    /// let mut builder = SchemaBuilder::new();
+    /// builder
+    ///     .parse(
+    ///         "CREATE TABLE users (id INTEGER, name TEXT);
+    ///          CREATE VIEW user_names AS SELECT name FROM users;",
+    ///     )
+    ///     .unwrap();
+    /// let (catalog, _) = builder.build();
+    ///
+    /// let mut analyzer = Analyzer::new(&catalog);
+    /// let dependencies = analyzer.collect_dependencies("SELECT * FROM user_names");
+    /// assert_eq!(
+    ///     dependencies.into_iter().collect::<Vec<_>>(),
+    ///     vec![
+    ///         QualifiedName::with_schema("public", "user_names"),
+    ///         QualifiedName::with_schema("public", "users"),
+    ///     ]
+    /// );
+    /// ```
+    pub fn collect_dependencies(&mut self, sql: &str) -> BTreeSet<QualifiedName> {
+        self.analyze_source(sql, false);
+        let mut pending = std::mem::take(&mut self.relations);
+        let mut dependencies = BTreeSet::new();
+        while let Some(name) = pending.pop() {
+            let Some(qualified) = self.catalog.qualify(&name) else {
+                continue;
+            };
+            // Each view is expanded once, so cycles terminate
+            if !dependencies.insert(qualified.clone()) {
+                continue;
+            }
+            if let Some(view) = self.catalog.get_view(&qualified) {
+                pending.extend(view.dependencies.iter().cloned());
+            }
+        }
+        dependencies
+    }
+
+    /// Analyze all statements in `sql`, optionally inferring the output of the last query
+    fn analyze_source(
+        &mut self,
+        sql: &str,
+        infer_output: bool,
+    ) -> (Vec<Diagnostic>, Option<InferResult<Vec<OutputColumn>>>) {
+        self.relations.clear();
+        if let Some(diagnostic) = self.input_limit(sql) {
+            self.profile = None;
+            // Nor is its output inferred
+            let output = infer_output.then(|| Err(Box::new(diagnostic.clone())));
+            return (vec![diagnostic], output);
+        }
+        let file_disabled = file_disabled_rules(sql);
+        let mut state = InputState::default();
+        self.analyze_input(sql, infer_output, &file_disabled, &mut state)
+    }
+
+    /// Analyze all statements in `sql`, a whole input or a part of one,
+    /// continuing from `state`
+    ///
+    /// `file_disabled` are the rules turned off for the whole input.
+    fn analyze_input(
+        &mut self,
+        sql: &str,
+        infer_output: bool,
+        file_disabled: &HashSet<String>,
+        state: &mut InputState,
+    ) -> (Vec<Diagnostic>, Option<InferResult<Vec<OutputColumn>>>) {
+        let Some(thread) = self.deep_stack_thread() else {
+            return self.analyze_input_inline(sql, infer_output, file_disabled, state);
+        };
+        // Nesting past what the caller's stack is known to hold
+        std::thread::scope(|scope| {
+            thread
+                .spawn_scoped(scope, || {
+                    self.analyze_input_inline(sql, infer_output, file_disabled, state)
+                })
+                .expect("failed to spawn the analysis thread")
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        })
+    }
+
+    /// A thread with a stack for `max_depth`, if it may not fit the caller's
+    fn deep_stack_thread(&self) -> Option<std::thread::Builder> {
+        (self.max_depth > MAX_INLINE_DEPTH).then(|| {
+            std::thread::Builder::new().stack_size(self.max_depth.saturating_mul(STACK_PER_LEVEL))
+        })
+    }
+
+    /// [`Analyzer::analyze_input`] on the current thread
+    fn analyze_input_inline(
+        &mut self,
+        sql: &str,
+        infer_output: bool,
+        file_disabled: &HashSet<String>,
+        state: &mut InputState,
+    ) -> (Vec<Diagnostic>, Option<InferResult<Vec<OutputColumn>>>) {
         self.diagnostics.clear();
 
+        // Template placeholders are replaced before parsing
+        let templated = if self.placeholders.is_empty() {
+            None
+        } else {
+            placeholders::rewrite(
+                sql,
+                &self.placeholders,
+                self.dialect,
+                &self.placeholder_identifier,
+            )
+        };
+        let sql = templated.as_ref().map_or(sql, |t| t.sql.as_str());
+
+        // MySQL index hints and STRAIGHT_JOIN are removed before parsing
+        let rewritten = match self.dialect {
+            SqlDialect::MySQL => hints::rewrite(sql),
+            SqlDialect::PostgreSQL => None,
+        };
+        let (sql, index_hints) = match &rewritten {
+            Some(rewritten) => (rewritten.sql.as_str(), rewritten.hints.as_slice()),
+            None => (sql, &[][..]),
+        };
+
+        // So is PostgreSQL's OVERRIDING clause of INSERT
+        let overridden = match self.dialect {
+            SqlDialect::PostgreSQL => overriding::rewrite(sql),
+            SqlDialect::MySQL => None,
+        };
+        let (sql, insert_overrides) = match &overridden {
+            Some(rewritten) => (rewritten.sql.as_str(), rewritten.overrides.as_slice()),
+            None => (sql, &[][..]),
+        };
+
+        // And the DELETE bodies of CTEs, which are put back once parsed
+        let data_modifying = match self.dialect {
+            SqlDialect::PostgreSQL => data_modifying::rewrite(sql),
+            SqlDialect::MySQL => None,
+        };
+        let (sql, delete_ctes) = match &data_modifying {
+            Some(rewritten) => (rewritten.sql.as_str(), rewritten.deletes.as_slice()),
+            None => (sql, &[][..]),
+        };
+
+        // MySQL's INSERT ... SET is parsed as an UPDATE and turned back
+        let insert_set = match self.dialect {
+            SqlDialect::MySQL => insert_set::rewrite(sql),
+            SqlDialect::PostgreSQL => None,
+        };
+        let (sql, set_inserts) = match &insert_set {
+            Some(rewritten) => (rewritten.sql.as_str(), rewritten.inserts.as_slice()),
+            None => (sql, &[][..]),
+        };
+
+        // PostgreSQL's REFRESH MATERIALIZED VIEW leaves a placeholder, checked
+        // in its place
+        let refreshed = match self.dialect {
+            SqlDialect::PostgreSQL => refresh::rewrite(sql),
+            SqlDialect::MySQL => None,
+        };
+        let (sql, refreshes) = match &refreshed {
+            Some(rewritten) => (rewritten.sql.as_str(), rewritten.refreshes.as_slice()),
+            None => (sql, &[][..]),
+        };
+
         // Parse the SQL
+        let mut profile = ProfileReport::default();
+        let stopwatch = Stopwatch::start(self.profiling);
         let dialect = self.dialect.parser_dialect();
-        let statements = match Parser::parse_sql(dialect.as_ref(), sql) {
-            Ok(stmts) => stmts,
-            Err(e) => {
-                self.diagnostics.push(
-                    Diagnostic::error(DiagnosticKind::ParseError, format!("Parse error: {}", e))
-                        .with_span(Span::new(0, sql.len().min(50))),
-                );
-                return std::mem::take(&mut self.diagnostics);
+        let ranges = statement_ranges(sql);
+        // Each statement with its index in `ranges`, if known
+        // Some statement may be too large to parse, be in the AST cache, or
+        // be a COPY FROM stdin whose data the parser can't be given
+        let individually = self.ast_cache.is_some()
+            || ranges.iter().any(|range| {
+                range.len() > self.max_statement_bytes || is_copy_from_stdin(&sql[range.clone()])
+            });
+        let (mut statements, indexes): (Vec<_>, Vec<_>) = if individually {
+            self.parse_statements_individually(sql, &mut profile)
+                .into_iter()
+                .map(|(index, stmt)| (stmt, Some(index)))
+                .unzip()
+        } else {
+            match self.parse(dialect.as_ref(), sql) {
+                // The statements match the ranges unless the splitter disagrees
+                // with the parser
+                Ok(stmts) => {
+                    let matched = stmts.len() == ranges.len();
+                    let count = stmts.len();
+                    (
+                        stmts.into_iter().map(Arc::new).collect(),
+                        (0..count).map(|i| matched.then_some(i)).collect(),
+                    )
+                }
+                // Fall back to statement-by-statement parsing, so one unsupported
+                // statement doesn't hide problems in the rest of the file
+                Err(_) => self
+                    .parse_statements_individually(sql, &mut profile)
+                    .into_iter()
+                    .map(|(index, stmt)| (stmt, Some(index)))
+                    .unzip(),
             }
         };
+        if !delete_ctes.is_empty() {
+            data_modifying::restore(&mut statements, delete_ctes);
+        }
+        if !set_inserts.is_empty() {
+            insert_set::restore(&mut statements, set_inserts);
+        }
+        stopwatch.stop(&mut profile.parse);
+
+        let last_query = statements
+            .iter()
+            .rposition(|stmt| {
+                matches!(**stmt, Statement::Query(_)) && refresh::find(refreshes, stmt).is_none()
+            })
+            .filter(|_| infer_output);
+        let mut output = None;
+
+        // Statement start positions for policy errors; only usable when every
+        // statement parsed
+        let keywords = if self.policy == WritePolicy::Any {
+            Vec::new()
+        } else {
+            policy::statement_keywords(dialect.as_ref(), sql)
+        };
+        let keywords = Some(keywords.as_slice()).filter(|k| k.len() == statements.len());
+        let mut row_limits = limits::RowLimitChecker::new(
+            self.dialect,
+            dialect.as_ref(),
+            sql,
+            self.passes.names,
+            self.passes.features,
+            self.max_depth,
+        );
 
-        // Analyze each statement
-        for stmt in &statements {
-            // Phase 1: Name resolution
-            let mut resolver = NameResolver::new(self.catalog);
-            resolver.resolve_statement(stmt);
+        // Relations named by placeholders have any columns
+        let placeholder_relation = templated
+            .as_ref()
+            .filter(|t| t.names_relations())
+            .map(|_| self.placeholder_identifier.clone());
 
-            // Phase 2: Type inference and checking
-            let mut type_resolver = TypeResolver::new(self.catalog);
-            type_resolver.inherit_scope(&resolver);
-            type_resolver.check_statement(stmt);
+        // Analyze each statement in order
+        for (idx, stmt) in statements.iter().enumerate() {
+            let stmt: &Statement = stmt;
+            let catalog = state.session.as_ref().unwrap_or(self.catalog);
+            let mut timings = StatementProfile::default();
+            let first_diagnostic = self.diagnostics.len();
+
+            if let (Some(last), Statement::Query(query)) = (last_query, stmt) {
+                if idx == last {
+                    output = Some(OutputInferer::new(catalog).query(query));
+                }
+            }
+
+            // Phase 1: Name resolution (also builds the scope the type pass needs)
+            if self.passes.names || self.passes.types {
+                let stopwatch = Stopwatch::start(self.profiling);
+                let mut resolver = NameResolver::with_dialect(catalog, self.dialect)
+                    .with_max_depth(self.max_depth)
+                    .with_suggestion_budget(self.suggestion_budget)
+                    .with_schemaless(self.schemaless)
+                    .with_insert_overrides(insert_overrides)
+                    .with_placeholder_relation(placeholder_relation.as_deref());
+                resolver.resolve_statement(stmt);
+                stopwatch.stop(&mut timings.names);
+                for name in resolver.relations() {
+                    // Objects created earlier in the input aren't dependencies
+                    if self.catalog.qualify(name).is_some() && !self.relations.contains(name) {
+                        self.relations.push(name.clone());
+                    }
+                }
+
+                // Phase 2: Type inference and checking
+                let type_diagnostics = if self.passes.types && !self.schemaless {
+                    let stopwatch = Stopwatch::start(self.profiling);
+                    let mut type_resolver = TypeResolver::with_dialect(catalog, self.dialect)
+                        .with_max_depth(self.max_depth)
+                        .with_implicit_casts(self.is_reported(DiagnosticKind::ImplicitCast));
+                    type_resolver.inherit_scope(&resolver);
+                    type_resolver.check_statement(stmt);
+                    stopwatch.stop(&mut timings.types);
+                    type_resolver.into_diagnostics()
+                } else {
+                    Vec::new()
+                };
+
+                // Collect diagnostics from both phases
+                if self.passes.names {
+                    let mut diagnostics = resolver.into_diagnostics();
+                    // W0013 explains the cost of a comparison; one E0003
+                    // already reports is not repeated
+                    if self.is_reported(DiagnosticKind::TypeMismatch) {
+                        diagnostics.retain(|d| {
+                            d.kind != DiagnosticKind::CoercedColumnComparison
+                                || !type_diagnostics.iter().any(|t| {
+                                    t.kind == DiagnosticKind::TypeMismatch
+                                        && spans_overlap(t.span.as_ref(), d.span.as_ref())
+                                })
+                        });
+                    }
+                    self.diagnostics.extend(diagnostics);
+                }
+                self.diagnostics.extend(type_diagnostics);
+            }
+
+            let stopwatch = Stopwatch::start(self.profiling);
+            if !index_hints.is_empty() && self.passes.names && !self.schemaless {
+                self.diagnostics
+                    .extend(hints::check_index_hints(stmt, catalog, index_hints));
+            }
+
+            let refreshing = refresh::find(refreshes, stmt);
+            if let Some(refresh) = refreshing {
+                if self.passes.names && !self.schemaless {
+                    self.diagnostics
+                        .extend(refresh::check(refresh, catalog, self.dialect));
+                }
+            }
+
+            let keyword = keywords.map(|k| k[idx]);
+            let class = match refreshing {
+                Some(_) => StatementClass::Write,
+                None => classify(stmt),
+            };
+            if let Some(diag) = policy::check_policy(stmt, class, self.policy, keyword) {
+                self.diagnostics.push(diag);
+            }
 
-            // Collect diagnostics from both phases
-            self.diagnostics.extend(resolver.into_diagnostics());
-            self.diagnostics.extend(type_resolver.into_diagnostics());
+            self.diagnostics.extend(row_limits.check(stmt));
+
+            // Features unavailable in the target dialect version
+            if self.passes.features {
+                self.diagnostics.extend(features::check_features(
+                    stmt,
+                    self.dialect,
+                    self.version,
+                    self.max_depth,
+                ));
+            }
+            stopwatch.stop(&mut timings.rules);
+            profile.statements.push(timings);
+
+            // Phase 3: Apply schema changes for the following statements
+            match stmt {
+                Statement::CreateTable(create) => {
+                    if create.on_commit == Some(OnCommit::Drop) {
+                        state
+                            .on_commit_drop
+                            .push(object_name_to_qualified(&create.name));
+                    }
+                    match &create.query {
+                        // CREATE TABLE ... AS SELECT takes its columns from the query
+                        Some(query) if create.columns.is_empty() => self.create_table_from_query(
+                            &mut state.session,
+                            &create.name,
+                            query,
+                            create.if_not_exists,
+                        ),
+                        _ => self.apply_to_session(&mut state.session, stmt),
+                    }
+                }
+                // SELECT ... INTO new_table (PostgreSQL; MySQL's INTO sets variables)
+                Statement::Query(query) if self.dialect == SqlDialect::PostgreSQL => {
+                    if let SetExpr::Select(select) = query.body.as_ref() {
+                        if let Some(into) = &select.into {
+                            self.create_table_from_query(
+                                &mut state.session,
+                                &into.name,
+                                query,
+                                false,
+                            );
+                        }
+                    }
+                }
+                Statement::Drop { .. }
+                | Statement::AlterTable { .. }
+                | Statement::CreateView { .. }
+                | Statement::CreateIndex(_)
+                | Statement::CreateType { .. } => {
+                    self.apply_to_session(&mut state.session, stmt);
+                }
+                Statement::Commit { .. } => {
+                    if let Some(catalog) = state.session.as_mut() {
+                        for name in state.on_commit_drop.drain(..) {
+                            catalog.remove_table(&name);
+                        }
+                    }
+                }
+                // Transaction control and session settings (BEGIN, SET LOCAL, ...)
+                // don't affect name resolution
+                _ => {}
+            }
+
+            if let Some(index) = indexes[idx] {
+                self.attribute_statement(first_diagnostic, sql, index, ranges[index].clone());
+            }
+        }
+
+        self.profile = self.profiling.then_some(profile);
+
+        // Report truncated analysis once per file, not once per pass and statement
+        let mut diagnostics = std::mem::take(&mut self.diagnostics);
+        diagnostics.retain_mut(|d| {
+            if file_disabled.contains(&d.code().to_lowercase())
+                || file_disabled.contains(d.kind.name())
+            {
+                return false;
+            }
+            if templated.as_ref().is_some_and(|t| !t.restore(d)) {
+                return false;
+            }
+            if self.schemaless && d.kind.requires_schema() {
+                return false;
+            }
+            if d.kind == DiagnosticKind::NestingTooDeep {
+                if state.truncated {
+                    return false;
+                }
+                state.truncated = true;
+            }
+            self.apply_rule_config(d)
+        });
+        (diagnostics, output)
+    }
+}
+
+/// State of an input carried from one statement to the next
+#[derive(Default)]
+struct InputState {
+    /// DDL in the input (e.g. temp tables in a migration) is applied to a
+    /// copy of the catalog, so later statements see created objects and no
+    /// longer see dropped ones. The copy is only made once needed.
+    session: Option<Catalog>,
+    /// Temp tables created with ON COMMIT DROP, removed at the next COMMIT
+    on_commit_drop: Vec<QualifiedName>,
+    /// Whether truncated analysis (W0002) was reported
+    truncated: bool,
+}
+
+/// Where a statement analyzed on its own is in its input
+struct StatementOrigin {
+    index: usize,
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl StatementOrigin {
+    /// Move the positions of a diagnostic of the statement's text alone
+    /// to the input
+    fn rebase(&self, diagnostic: &mut Diagnostic) {
+        let spans = diagnostic
+            .span
+            .iter_mut()
+            .chain(diagnostic.fixes.iter_mut().map(|fix| &mut fix.span))
+            .chain(
+                diagnostic
+                    .labels
+                    .iter_mut()
+                    .filter(|label| label.file.is_none())
+                    .map(|label| &mut label.span),
+            );
+        for span in spans {
+            // Spans located by line and column mostly leave the offset unset
+            if span.line == 0 || span.offset > 0 {
+                span.offset += self.offset;
+            }
+            self.rebase_position(span);
+        }
+        if let Some(statement) = &mut diagnostic.statement {
+            statement.index += self.index;
+            statement.span.offset += self.offset;
+            self.rebase_position(&mut statement.span);
+        }
+        if diagnostic.kind == DiagnosticKind::ParseError {
+            self.rebase_parser_position(&mut diagnostic.message);
+        }
+    }
+
+    /// Move the "at Line: L, Column: C" sqlparser ends its errors with
+    fn rebase_parser_position(&self, message: &mut String) {
+        let Some(at) = message.rfind(" at Line: ") else {
+            return;
+        };
+        let position = &message[at + " at Line: ".len()..];
+        let Some((line, column)) = position.split_once(", Column: ") else {
+            return;
+        };
+        let (Ok(line), Ok(column)) = (line.parse::<usize>(), column.parse::<usize>()) else {
+            return;
+        };
+        let column = if line == 1 {
+            column + self.column - 1
+        } else {
+            column
+        };
+        message.truncate(at);
+        message.push_str(&format!(
+            " at Line: {}, Column: {}",
+            line + self.line - 1,
+            column
+        ));
+    }
+
+    fn rebase_position(&self, span: &mut Span) {
+        for (line, column) in [
+            (&mut span.line, &mut span.column),
+            (&mut span.end_line, &mut span.end_column),
+        ] {
+            // Line 0 means the position is unknown
+            if *line == 1 {
+                *column += self.column - 1;
+            }
+            if *line > 0 {
+                *line += self.line - 1;
+            }
+        }
+    }
+}
+
+/// Line and column of increasing offsets of an input, without rescanning
+/// it from the start
+#[derive(Default)]
+struct LineTracker {
+    offset: usize,
+    /// Line of `offset`, 0-based
+    line: usize,
+    /// Offset of the start of that line
+    line_start: usize,
+}
+
+impl LineTracker {
+    /// 1-based line and column (in characters) of `offset`, which must not
+    /// be before the previous one
+    fn advance(&mut self, sql: &str, offset: usize) -> (usize, usize) {
+        let skipped = &sql[self.offset..offset];
+        if let Some(last) = skipped.rfind('\n') {
+            self.line += skipped.matches('\n').count();
+            self.line_start = self.offset + last + 1;
         }
+        self.offset = offset;
+        (
+            self.line + 1,
+            sql[self.line_start..offset].chars().count() + 1,
+        )
+    }
+}
 
-        std::mem::take(&mut self.diagnostics)
+/// `bytes` for a message: exact below 1 KiB, else in KiB or MiB
+fn format_size(bytes: usize) -> String {
+    const KIB: f64 = 1024.0;
+    match bytes as f64 {
+        b if b < KIB => format!("{} bytes", bytes),
+        b if b < KIB * KIB => format!("{:.1} KiB", b / KIB),
+        b => format!("{:.1} MiB", b / (KIB * KIB)),
     }
 }
+
+/// Whether two statement-relative spans share a position
+fn spans_overlap(a: Option<&Span>, b: Option<&Span>) -> bool {
+    let (Some(a), Some(b)) = (a, b) else {
+        return false;
+    };
+    (a.line, a.column) < (b.end_line, b.end_column)
+        && (b.line, b.column) < (a.end_line, a.end_column)
+}
+
+/// Byte ranges of the statements of `sql`, trimmed, in order
+fn statement_ranges(sql: &str) -> Vec<Range<usize>> {
+    split_sql_statements(sql)
+        .into_iter()
+        .map(str::trim)
+        .filter(|stmt| !stmt.is_empty())
+        .map(|stmt| {
+            let start = stmt.as_ptr() as usize - sql.as_ptr() as usize;
+            start..start + stmt.len()
+        })
+        .collect()
+}
+
+/// Rules turned off for a whole input by `-- sqlsurge-disable <RULE>[, <RULE>...]`
+/// comments (codes or names, lowercased)
+fn file_disabled_rules(sql: &str) -> HashSet<String> {
+    sql.lines()
+        .filter_map(|line| {
+            let comment = line.split_once("--")?.1.trim_start();
+            comment.strip_prefix("sqlsurge-disable")
+        })
+        .flat_map(|rules| {
+            rules
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|rule| !rule.is_empty())
+                .map(str::to_lowercase)
+        })
+        .collect()
+}
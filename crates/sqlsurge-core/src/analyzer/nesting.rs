@@ -0,0 +1,67 @@
+//! Recursion depth tracking for AST walks
+//!
+//! Name resolution and type checking recurse into nested expressions and
+//! subqueries. Machine-generated SQL can nest deep enough to overflow the
+//! stack, so both passes stop descending past a limit and report it once.
+
+use crate::error::{Diagnostic, DiagnosticKind};
+
+/// Default maximum nesting depth of expressions and subqueries
+///
+/// Deep enough for machine-generated SQL such as ORM output, which nests far
+/// more than hand-written queries; inputs are analyzed on a thread with a
+/// stack sized for it.
+pub const DEFAULT_MAX_DEPTH: usize = 512;
+
+/// Current depth of a recursive walk, bounded by a maximum
+#[derive(Debug, Clone, Copy)]
+pub(super) struct NestingLimit {
+    depth: usize,
+    max_depth: usize,
+    exceeded: bool,
+}
+
+impl NestingLimit {
+    pub(super) fn new(max_depth: usize) -> Self {
+        Self {
+            depth: 0,
+            max_depth,
+            exceeded: false,
+        }
+    }
+
+    /// Enter one level; returns false if the limit is reached and the walk should stop
+    pub(super) fn enter(&mut self) -> bool {
+        if self.depth >= self.max_depth {
+            self.exceeded = true;
+            return false;
+        }
+        self.depth += 1;
+        true
+    }
+
+    /// Leave a level entered with `enter`
+    pub(super) fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    /// Warning to report if the limit was hit during the walk
+    pub(super) fn diagnostic(&self) -> Option<Diagnostic> {
+        self.exceeded.then(|| {
+            Diagnostic::warning(
+                DiagnosticKind::NestingTooDeep,
+                "Expression nesting too deep, analysis truncated",
+            )
+            .with_help(format!(
+                "Nesting exceeds the limit of {} levels; simplify the query or raise `max_depth`",
+                self.max_depth
+            ))
+        })
+    }
+}
+
+impl Default for NestingLimit {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_DEPTH)
+    }
+}
@@ -0,0 +1,135 @@
+//! NULL values inserted from a query into NOT NULL columns (E0004)
+//!
+//! `INSERT INTO t (name) SELECT u.name FROM orders o LEFT JOIN users u ...`
+//! fails at run time for the rows without a user. The output schema
+//! inference knows which projected values may be NULL: nullable columns,
+//! the null-extended side of an outer join, NULL literals, CASE without
+//! ELSE. COALESCE with a non-null fallback and a WHERE `x IS NOT NULL`
+//! filter make a value non-null.
+//!
+//! Only values whose nullability is known are checked: column references,
+//! literals and CASE/COALESCE/casts built from them. Function calls and
+//! parameters are left alone.
+
+use sqlparser::ast::{Expr, Ident, Query, SelectItem, SetExpr, Spanned, Value};
+use sqlparser::tokenizer::Span as SqlSpan;
+
+use crate::error::{Diagnostic, DiagnosticKind, Span};
+use crate::schema::{Catalog, QualifiedName, TableDef};
+use crate::types::SqlType;
+
+use super::functions::{function_args, function_name};
+use super::output::OutputInferer;
+
+/// Check the values an INSERT ... SELECT gives the NOT NULL columns of a table
+///
+/// A diagnostic points at the target column in the column list; without
+/// one, at the selected value, or at the table name for a NULL literal,
+/// which carries no span.
+pub(super) fn check_insert(
+    catalog: &Catalog,
+    table_name: &QualifiedName,
+    table_span: Option<Span>,
+    table_def: &TableDef,
+    columns: &[&Ident],
+    source: &Query,
+) -> Vec<Diagnostic> {
+    let SetExpr::Select(select) = source.body.as_ref() else {
+        return Vec::new();
+    };
+    let mut exprs = Vec::new();
+    for item in &select.projection {
+        match item {
+            SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => {
+                exprs.push(expr)
+            }
+            // Wildcards don't say which value goes to which column
+            _ => return Vec::new(),
+        }
+    }
+    let Ok(outputs) = OutputInferer::new(catalog).query(source) else {
+        return Vec::new();
+    };
+    let targets: Vec<(&str, Option<Span>)> = if columns.is_empty() {
+        table_def
+            .column_names()
+            .into_iter()
+            .map(|name| (name, None))
+            .collect()
+    } else {
+        columns
+            .iter()
+            .map(|c| (c.value.as_str(), Some(Span::from_sqlparser(&c.span))))
+            .collect()
+    };
+
+    let mut diagnostics = Vec::new();
+    for (((target, target_span), expr), output) in targets.into_iter().zip(exprs).zip(&outputs) {
+        let Some(col_def) = table_def.get_column(target) else {
+            continue;
+        };
+        if col_def.nullable || col_def.generated.is_some() || !output.nullable {
+            continue;
+        }
+        let known = output.data_type != SqlType::Unknown || is_null(expr);
+        if !known || !is_traceable(expr) {
+            continue;
+        }
+        let help = if is_null(expr) {
+            format!("Select a value for '{}' instead of NULL", col_def.name)
+        } else {
+            format!(
+                "Give a fallback with COALESCE({}, ...), or filter the rows with \
+                 WHERE {} IS NOT NULL",
+                expr, expr
+            )
+        };
+        let expr_span = expr.span();
+        let span = target_span
+            .or_else(|| (expr_span != SqlSpan::empty()).then(|| Span::from_sqlparser(&expr_span)))
+            .or(table_span);
+        let mut diag = Diagnostic::error(
+            DiagnosticKind::PotentialNullViolation,
+            format!(
+                "Column '{}' of table '{}' is NOT NULL, but the inserted value may be NULL",
+                col_def.name, table_name
+            ),
+        )
+        .with_help(help);
+        if let Some(span) = span {
+            diag = diag.with_span(span);
+        }
+        diagnostics.push(diag);
+    }
+    diagnostics
+}
+
+/// Whether the inferred nullability of an expression can be trusted
+fn is_traceable(expr: &Expr) -> bool {
+    match expr {
+        Expr::Identifier(_) | Expr::CompoundIdentifier(_) => true,
+        Expr::Value(value) => !matches!(value, Value::Placeholder(_)),
+        Expr::Nested(inner) | Expr::Cast { expr: inner, .. } => is_traceable(inner),
+        // Conditions only decide which branch is taken
+        Expr::Case {
+            results,
+            else_result,
+            ..
+        } => results
+            .iter()
+            .chain(else_result.as_deref())
+            .all(is_traceable),
+        Expr::Function(func) if function_name(func).eq_ignore_ascii_case("coalesce") => {
+            function_args(func).into_iter().all(is_traceable)
+        }
+        _ => false,
+    }
+}
+
+fn is_null(expr: &Expr) -> bool {
+    match expr {
+        Expr::Value(Value::Null) => true,
+        Expr::Nested(inner) => is_null(inner),
+        _ => false,
+    }
+}
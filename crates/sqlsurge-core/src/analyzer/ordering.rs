@@ -0,0 +1,265 @@
+//! Row order checks
+//!
+//! - `SELECT DISTINCT a FROM t ORDER BY b` is rejected by PostgreSQL: with
+//!   DISTINCT, ORDER BY expressions must appear in the select list (E0014).
+//! - LIMIT or FETCH FIRST without ORDER BY returns an arbitrary subset of
+//!   rows (W0009, opt-in). A WHERE clause that pins a primary key or unique
+//!   key with equalities selects at most one row, so it is deterministic
+//!   and not reported.
+
+use sqlparser::ast::{
+    BinaryOperator, Distinct, Expr, OrderByExpr, Query, Select, SelectItem, SetExpr, Spanned,
+    TableFactor,
+};
+use std::collections::HashMap;
+
+use crate::dialect::SqlDialect;
+use crate::error::{Diagnostic, DiagnosticKind, Span};
+use crate::idents::object_name_to_qualified;
+use crate::schema::Catalog;
+
+use super::resolver::CteDefinition;
+
+/// Check ORDER BY expressions of a `SELECT DISTINCT` (E0014)
+///
+/// `output_columns` are the query's output column names, used to match
+/// ORDER BY columns against a wildcard; `None` if they can't be determined.
+pub(super) fn check_distinct_order_by(
+    dialect: SqlDialect,
+    select: &Select,
+    order_by: &[OrderByExpr],
+    output_columns: Option<&[String]>,
+) -> Vec<Diagnostic> {
+    if dialect != SqlDialect::PostgreSQL || !matches!(select.distinct, Some(Distinct::Distinct)) {
+        return Vec::new();
+    }
+    let has_wildcard = select.projection.iter().any(|item| {
+        matches!(
+            item,
+            SelectItem::Wildcard(_) | SelectItem::QualifiedWildcard(..)
+        )
+    });
+    if has_wildcard && output_columns.is_none() {
+        return Vec::new();
+    }
+
+    order_by
+        .iter()
+        .map(|ob| unnest(&ob.expr))
+        .filter(|expr| !matches!(expr, Expr::Value(_)))
+        .filter(|expr| {
+            !select.projection.iter().any(|item| match item {
+                SelectItem::UnnamedExpr(selected) => same_expr(expr, selected),
+                SelectItem::ExprWithAlias {
+                    expr: selected,
+                    alias,
+                } => {
+                    let is_alias = match expr {
+                        Expr::Identifier(ident) => ident.value.eq_ignore_ascii_case(&alias.value),
+                        _ => false,
+                    };
+                    is_alias || same_expr(expr, selected)
+                }
+                SelectItem::Wildcard(_) | SelectItem::QualifiedWildcard(..) => column_name(expr)
+                    .is_some_and(|name| {
+                        output_columns
+                            .unwrap_or_default()
+                            .iter()
+                            .any(|c| c.eq_ignore_ascii_case(name))
+                    }),
+            })
+        })
+        .map(|expr| {
+            Diagnostic::error(
+                DiagnosticKind::DistinctOrderBy,
+                format!(
+                    "ORDER BY expression '{}' must appear in the select list of SELECT DISTINCT",
+                    expr
+                ),
+            )
+            .with_span(Span::from_sqlparser(&expr.span()))
+            .with_help(
+                "Add it to the select list, or sort in an outer query over the DISTINCT rows",
+            )
+        })
+        .collect()
+}
+
+/// Check for LIMIT / FETCH FIRST without ORDER BY (W0009)
+pub(super) fn check_unordered_limit(
+    catalog: &Catalog,
+    ctes: &HashMap<String, CteDefinition>,
+    query: &Query,
+) -> Option<Diagnostic> {
+    if query.order_by.as_ref().is_some_and(|o| !o.exprs.is_empty()) {
+        return None;
+    }
+    let (clause, span) = match (&query.limit, &query.fetch) {
+        (Some(limit), _) => ("LIMIT", Some(limit.span())),
+        (None, Some(fetch)) => ("FETCH FIRST", fetch.quantity.as_ref().map(|q| q.span())),
+        (None, None) => return None,
+    };
+    if let SetExpr::Select(select) = query.body.as_ref() {
+        if selects_single_row(catalog, ctes, select) {
+            return None;
+        }
+    }
+
+    // Literals carry no location; point at the query instead
+    let span = span
+        .filter(|s| s.start.line > 0)
+        .unwrap_or_else(|| query.span());
+    Some(
+        Diagnostic::warning(
+            DiagnosticKind::UnorderedLimit,
+            format!(
+                "{} without ORDER BY returns an unpredictable subset of rows",
+                clause
+            ),
+        )
+        .with_span(Span::from_sqlparser(&span))
+        .with_help(
+            "Add an ORDER BY that makes the row order unique, e.g. ending with the primary key",
+        ),
+    )
+}
+
+/// Whether `select` reads a single base table and its WHERE clause pins
+/// every column of one of its unique keys with `=`
+fn selects_single_row(
+    catalog: &Catalog,
+    ctes: &HashMap<String, CteDefinition>,
+    select: &Select,
+) -> bool {
+    let [from] = select.from.as_slice() else {
+        return false;
+    };
+    let TableFactor::Table {
+        name,
+        alias,
+        args: None,
+        ..
+    } = &from.relation
+    else {
+        return false;
+    };
+    let table_name = object_name_to_qualified(name);
+    if !from.joins.is_empty() || ctes.contains_key(&table_name.name) {
+        return false;
+    }
+    let Some(table) = catalog.get_table(&table_name) else {
+        return false;
+    };
+    let visible = alias
+        .as_ref()
+        .map_or(table_name.name.as_str(), |a| a.name.value.as_str());
+
+    let mut pinned = Vec::new();
+    if let Some(selection) = &select.selection {
+        collect_pinned_columns(selection, visible, &mut pinned);
+    }
+    table
+        .unique_keys()
+        .into_iter()
+        .chain(
+            table
+                .indexes
+                .iter()
+                .filter(|i| i.unique)
+                .map(|i| i.columns.iter().map(|c| c.as_str()).collect()),
+        )
+        .any(|key| {
+            key.iter()
+                .all(|column| pinned.iter().any(|p| p.eq_ignore_ascii_case(column)))
+        })
+}
+
+/// Columns of the table visible as `table` compared with `=` to a value in
+/// the AND-ed conjuncts of `expr`
+fn collect_pinned_columns<'a>(expr: &'a Expr, table: &str, pinned: &mut Vec<&'a str>) {
+    match unnest(expr) {
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => {
+            collect_pinned_columns(left, table, pinned);
+            collect_pinned_columns(right, table, pinned);
+        }
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::Eq,
+            right,
+        } => {
+            for (column, value) in [(left, right), (right, left)] {
+                if let Some(column) = table_column(column, table) {
+                    if is_value(value) {
+                        pinned.push(column);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The column name of a reference to a column of the table visible as `table`
+fn table_column<'a>(expr: &'a Expr, table: &str) -> Option<&'a str> {
+    match unnest(expr) {
+        Expr::Identifier(column) => Some(&column.value),
+        Expr::CompoundIdentifier(idents) => match idents.as_slice() {
+            [qualifier, column] if qualifier.value == table => Some(&column.value),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether `expr` is a literal or parameter (possibly cast)
+fn is_value(expr: &Expr) -> bool {
+    match unnest(expr) {
+        Expr::Value(_) => true,
+        Expr::Cast { expr, .. } | Expr::UnaryOp { expr, .. } => is_value(expr),
+        _ => false,
+    }
+}
+
+/// Whether two expressions are the same select list entry
+///
+/// Column references match on the column name (and on the qualifier when
+/// both are qualified); other expressions must be written the same way.
+pub(super) fn same_expr(a: &Expr, b: &Expr) -> bool {
+    let (a, b) = (unnest(a), unnest(b));
+    match (column_name(a), column_name(b)) {
+        (Some(a_name), Some(b_name)) => {
+            a_name.eq_ignore_ascii_case(b_name)
+                && match (a, b) {
+                    (Expr::CompoundIdentifier(a), Expr::CompoundIdentifier(b)) => {
+                        a.len() == b.len()
+                            && a.iter()
+                                .zip(b)
+                                .all(|(x, y)| x.value.eq_ignore_ascii_case(&y.value))
+                    }
+                    _ => true,
+                }
+        }
+        (None, None) => a.to_string().eq_ignore_ascii_case(&b.to_string()),
+        _ => false,
+    }
+}
+
+/// Column name of a column reference
+fn column_name(expr: &Expr) -> Option<&str> {
+    match expr {
+        Expr::Identifier(ident) => Some(&ident.value),
+        Expr::CompoundIdentifier(idents) => idents.last().map(|i| i.value.as_str()),
+        _ => None,
+    }
+}
+
+fn unnest(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Nested(inner) => unnest(inner),
+        _ => expr,
+    }
+}
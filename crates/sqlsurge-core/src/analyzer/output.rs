@@ -0,0 +1,751 @@
+//! Output schema inference - the columns, types and nullability a query returns
+//!
+//! Builds on the same relation rules as projection expansion (CTEs, views,
+//! derived tables, alias column lists, wildcards), but tracks a type and
+//! nullability for each column. Types that cannot be inferred are
+//! `SqlType::Unknown`; columns of unknown nullability are reported as nullable.
+
+use serde::{Deserialize, Serialize};
+use sqlparser::ast::{
+    BinaryOperator, Cte, Expr, Function, JoinOperator, Query, Select, SelectItem, SetExpr, Spanned,
+    TableAlias, TableFactor, UnaryOperator, Value, With,
+};
+use std::collections::HashMap;
+
+use crate::error::{Diagnostic, DiagnosticKind, Span};
+use crate::idents::object_name_to_qualified;
+use crate::schema::Catalog;
+use crate::types::SqlType;
+
+use super::data_modifying::returning_select;
+use super::functions::{
+    declared_return_type, function_args, function_name, set_returning_type, TableFunctionCall,
+};
+use super::projection::merged_columns;
+
+/// A column in the result of a query
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputColumn {
+    /// Column name (alias, column name, function name, or `?column?`)
+    pub name: String,
+    /// Inferred type (`SqlType::Unknown` if it could not be inferred)
+    pub data_type: SqlType,
+    /// Whether the column may be NULL
+    pub nullable: bool,
+}
+
+impl OutputColumn {
+    fn new(name: impl Into<String>, data_type: SqlType, nullable: bool) -> Self {
+        Self {
+            name: name.into(),
+            data_type,
+            nullable,
+        }
+    }
+
+    fn unknown(name: impl Into<String>) -> Self {
+        Self::new(name, SqlType::Unknown, true)
+    }
+}
+
+/// A relation in a FROM clause, visible under `name`
+pub(super) struct Relation {
+    pub(super) name: String,
+    pub(super) columns: Vec<OutputColumn>,
+}
+
+/// Infers output columns of queries against a catalog
+pub(super) struct OutputInferer<'a> {
+    catalog: &'a Catalog,
+    /// CTE name -> output columns
+    ctes: HashMap<String, Vec<OutputColumn>>,
+}
+
+/// Inference fails (with a diagnostic) when a relation's columns can't be determined
+pub(super) type InferResult<T> = Result<T, Box<Diagnostic>>;
+
+impl<'a> OutputInferer<'a> {
+    pub(super) fn new(catalog: &'a Catalog) -> Self {
+        Self {
+            catalog,
+            ctes: HashMap::new(),
+        }
+    }
+
+    /// Output columns of a query, including CTEs defined in its WITH clause
+    pub(super) fn query(&mut self, query: &Query) -> InferResult<Vec<OutputColumn>> {
+        let saved_ctes = self.ctes.clone();
+        let columns = match &query.with {
+            Some(with) => self
+                .try_define_ctes(with)
+                .and_then(|()| self.set_expr(&query.body)),
+            None => self.set_expr(&query.body),
+        };
+        self.ctes = saved_ctes;
+        columns
+    }
+
+    /// Register the CTEs of a WITH clause, failing on the first one whose
+    /// columns can't be inferred
+    fn try_define_ctes(&mut self, with: &With) -> InferResult<()> {
+        for cte in &with.cte_tables {
+            self.define_cte(cte, with.recursive)?;
+        }
+        Ok(())
+    }
+
+    /// The CTEs defined so far, to restore once those of an inner query go
+    /// out of scope
+    pub(super) fn saved_ctes(&self) -> HashMap<String, Vec<OutputColumn>> {
+        self.ctes.clone()
+    }
+
+    /// Go back to the CTEs returned by `saved_ctes`
+    pub(super) fn restore_ctes(&mut self, saved: HashMap<String, Vec<OutputColumn>>) {
+        self.ctes = saved;
+    }
+
+    /// Register the CTEs of a WITH clause for later relations, skipping
+    /// those whose columns can't be inferred
+    pub(super) fn define_ctes(&mut self, with: &With) {
+        for cte in &with.cte_tables {
+            let _ = self.define_cte(cte, with.recursive);
+        }
+    }
+
+    /// Infer and register one CTE
+    ///
+    /// The recursive term of a recursive CTE refers to the CTE itself, so its
+    /// columns are first taken from the non-recursive (leftmost) term.
+    fn define_cte(&mut self, cte: &Cte, recursive: bool) -> InferResult<()> {
+        let name = cte.alias.name.value.clone();
+        if recursive {
+            let mut anchor = cte.query.body.as_ref();
+            while let SetExpr::SetOperation { left, .. } = anchor {
+                anchor = left;
+            }
+            if let Ok(columns) = self.set_expr(anchor) {
+                self.ctes
+                    .insert(name.clone(), rename_columns(Some(&cte.alias), columns));
+            }
+        }
+        let columns = self.query(&cte.query)?;
+        self.ctes
+            .insert(name, rename_columns(Some(&cte.alias), columns));
+        Ok(())
+    }
+
+    fn set_expr(&mut self, set_expr: &SetExpr) -> InferResult<Vec<OutputColumn>> {
+        match set_expr {
+            SetExpr::Select(select) => self.select(select),
+            SetExpr::Query(query) => self.query(query),
+            SetExpr::SetOperation { left, right, .. } => {
+                // Names come from the left arm; a column is nullable if it is in any arm
+                let mut columns = self.set_expr(left)?;
+                let right = self.set_expr(right)?;
+                for (column, other) in columns.iter_mut().zip(right) {
+                    column.nullable |= other.nullable;
+                    if column.data_type == SqlType::Unknown {
+                        column.data_type = other.data_type;
+                    }
+                }
+                Ok(columns)
+            }
+            SetExpr::Values(values) => {
+                let width = values.rows.first().map_or(0, |row| row.len());
+                let columns = (0..width)
+                    .map(|idx| {
+                        let mut column = OutputColumn::new(
+                            format!("column{}", idx + 1),
+                            SqlType::Unknown,
+                            false,
+                        );
+                        for row in &values.rows {
+                            let Some(expr) = row.get(idx) else { continue };
+                            let (data_type, nullable) = self.expr_type(expr, &[]);
+                            column.nullable |= nullable;
+                            if column.data_type == SqlType::Unknown {
+                                column.data_type = data_type;
+                            }
+                        }
+                        column
+                    })
+                    .collect();
+                Ok(columns)
+            }
+            // A data-modifying CTE returns its RETURNING columns
+            SetExpr::Insert(statement) | SetExpr::Update(statement) => {
+                match returning_select(statement) {
+                    Some(select) => self.select(&select),
+                    None => Err(Box::new(Diagnostic::error(
+                        DiagnosticKind::ColumnNotFound,
+                        "Cannot determine the output columns of a statement without RETURNING",
+                    ))),
+                }
+            }
+            _ => Err(Box::new(Diagnostic::error(
+                DiagnosticKind::ColumnNotFound,
+                "Cannot determine the output columns of this statement",
+            ))),
+        }
+    }
+
+    fn select(&mut self, select: &Select) -> InferResult<Vec<OutputColumn>> {
+        let mut scope: Vec<Relation> = Vec::new();
+        // Index in `scope` of the first relation of each FROM item
+        let mut firsts = Vec::new();
+        for table_with_joins in &select.from {
+            scope.push(self.relation(&table_with_joins.relation)?);
+            let first = scope.len() - 1;
+            firsts.push(first);
+            for join in &table_with_joins.joins {
+                let mut relation = self.relation(&join.relation)?;
+                let (left_nullable, right_nullable) = join_nullability(&join.join_operator);
+                if left_nullable {
+                    for earlier in &mut scope[first..] {
+                        make_nullable(&mut earlier.columns);
+                    }
+                }
+                if right_nullable {
+                    make_nullable(&mut relation.columns);
+                }
+                scope.push(relation);
+            }
+        }
+        if let Some(selection) = &select.selection {
+            refine_not_null(&mut scope, selection);
+        }
+
+        // Columns of `*`, with USING / NATURAL join columns merged
+        let mut wildcard: Vec<OutputColumn> = Vec::new();
+        for (table_with_joins, &first) in select.from.iter().zip(&firsts) {
+            let mut columns = scope[first].columns.clone();
+            for (join, relation) in table_with_joins.joins.iter().zip(&scope[first + 1..]) {
+                columns =
+                    merge_join_columns(&join.join_operator, columns, relation.columns.clone());
+            }
+            wildcard.extend(columns);
+        }
+
+        let mut columns = Vec::new();
+        for item in &select.projection {
+            match item {
+                SelectItem::UnnamedExpr(expr) => {
+                    let (data_type, nullable) = self.expr_type(expr, &scope);
+                    columns.push(OutputColumn::new(expr_name(expr), data_type, nullable));
+                }
+                SelectItem::ExprWithAlias { expr, alias } => {
+                    let (data_type, nullable) = self.expr_type(expr, &scope);
+                    columns.push(OutputColumn::new(&alias.value, data_type, nullable));
+                }
+                SelectItem::Wildcard(_) => columns.extend(wildcard.iter().cloned()),
+                SelectItem::QualifiedWildcard(name, _) => {
+                    let qualifier = name.0.last().map(|i| i.value.as_str()).unwrap_or_default();
+                    let relation = find_relation(&scope, qualifier).ok_or_else(|| {
+                        Box::new(Diagnostic::error(
+                            DiagnosticKind::TableNotFound,
+                            format!("Table or alias '{}' not found in FROM clause", qualifier),
+                        ))
+                    })?;
+                    columns.extend(relation.columns.iter().cloned());
+                }
+            }
+        }
+        Ok(columns)
+    }
+
+    /// Columns of a single FROM-clause relation
+    pub(super) fn relation(&mut self, factor: &TableFactor) -> InferResult<Relation> {
+        if let Some(call) = TableFunctionCall::from_factor(factor) {
+            // Arguments referring to earlier FROM items (LATERAL) stay untyped
+            let arg_types: Vec<SqlType> = call
+                .args
+                .iter()
+                .map(|arg| self.expr_type(arg, &[]).0)
+                .collect();
+            let columns = match call.columns(&arg_types) {
+                Some(columns) => columns
+                    .into_iter()
+                    .map(|(name, data_type, nullable)| OutputColumn::new(name, data_type, nullable))
+                    .collect(),
+                None => alias_columns(call.alias).ok_or_else(|| {
+                    Box::new(
+                        Diagnostic::error(
+                            DiagnosticKind::TableNotFound,
+                            format!("Cannot determine the columns of '{}'", call.name),
+                        )
+                        .with_span(Span::from_sqlparser(&factor.span()))
+                        .with_help("Add a column alias list, e.g. `AS t(a, b)`"),
+                    )
+                })?,
+            };
+            return Ok(Relation {
+                name: call.visible_name(),
+                columns,
+            });
+        }
+
+        match factor {
+            TableFactor::Table { name, alias, .. } => {
+                let table_name = object_name_to_qualified(name);
+                let visible = alias
+                    .as_ref()
+                    .map(|a| a.name.value.clone())
+                    .unwrap_or_else(|| table_name.name.clone());
+                let columns = if let Some(columns) = self.ctes.get(&table_name.name) {
+                    Some(columns.clone())
+                } else if let Some(view) = self.catalog.get_view(&table_name) {
+                    Some(view.columns.iter().map(OutputColumn::unknown).collect())
+                } else {
+                    self.catalog.get_table(&table_name).map(|table| {
+                        table
+                            .ordered_columns()
+                            .into_iter()
+                            .map(|c| OutputColumn::new(&c.name, c.data_type.clone(), c.nullable))
+                            .collect()
+                    })
+                };
+                let columns = match columns {
+                    Some(columns) => rename_columns(alias.as_ref(), columns),
+                    None => alias_columns(alias.as_ref()).ok_or_else(|| {
+                        Box::new(
+                            Diagnostic::error(
+                                DiagnosticKind::TableNotFound,
+                                format!("Cannot determine the columns of '{}'", table_name),
+                            )
+                            .with_span(Span::from_sqlparser(&name.0[0].span))
+                            .with_help("Add a column alias list, e.g. `AS t(a, b)`"),
+                        )
+                    })?,
+                };
+                Ok(Relation {
+                    name: visible,
+                    columns,
+                })
+            }
+            TableFactor::Derived {
+                subquery, alias, ..
+            } => {
+                let columns = self.query(subquery)?;
+                Ok(Relation {
+                    name: alias
+                        .as_ref()
+                        .map(|a| a.name.value.clone())
+                        .unwrap_or_default(),
+                    columns: rename_columns(alias.as_ref(), columns),
+                })
+            }
+            TableFactor::NestedJoin {
+                table_with_joins,
+                alias,
+            } => {
+                let mut columns = self.relation(&table_with_joins.relation)?.columns;
+                for join in &table_with_joins.joins {
+                    let mut joined = self.relation(&join.relation)?.columns;
+                    let (left_nullable, right_nullable) = join_nullability(&join.join_operator);
+                    if left_nullable {
+                        make_nullable(&mut columns);
+                    }
+                    if right_nullable {
+                        make_nullable(&mut joined);
+                    }
+                    columns = merge_join_columns(&join.join_operator, columns, joined);
+                }
+                Ok(Relation {
+                    name: alias
+                        .as_ref()
+                        .map(|a| a.name.value.clone())
+                        .unwrap_or_default(),
+                    columns: rename_columns(alias.as_ref(), columns),
+                })
+            }
+            TableFactor::TableFunction { alias, .. } => {
+                let columns = alias_columns(alias.as_ref()).ok_or_else(|| {
+                    Box::new(
+                        Diagnostic::error(
+                            DiagnosticKind::TableNotFound,
+                            "Cannot determine the columns of a table function",
+                        )
+                        .with_help("Add a column alias list, e.g. `AS t(a, b)`"),
+                    )
+                })?;
+                Ok(Relation {
+                    name: alias
+                        .as_ref()
+                        .map(|a| a.name.value.clone())
+                        .unwrap_or_default(),
+                    columns,
+                })
+            }
+            _ => Err(Box::new(Diagnostic::error(
+                DiagnosticKind::TableNotFound,
+                "Cannot determine the columns of this FROM item",
+            ))),
+        }
+    }
+
+    /// Infer the type and nullability of an expression
+    fn expr_type(&mut self, expr: &Expr, scope: &[Relation]) -> (SqlType, bool) {
+        match expr {
+            Expr::Identifier(ident) => scope
+                .iter()
+                .flat_map(|r| &r.columns)
+                .find(|c| c.name.eq_ignore_ascii_case(&ident.value))
+                .map_or((SqlType::Unknown, true), |c| {
+                    (c.data_type.clone(), c.nullable)
+                }),
+            Expr::CompoundIdentifier(idents) if idents.len() >= 2 => {
+                let table = &idents[idents.len() - 2].value;
+                let column = &idents[idents.len() - 1].value;
+                find_relation(scope, table)
+                    .and_then(|r| {
+                        r.columns
+                            .iter()
+                            .find(|c| c.name.eq_ignore_ascii_case(column))
+                    })
+                    .map_or((SqlType::Unknown, true), |c| {
+                        (c.data_type.clone(), c.nullable)
+                    })
+            }
+            Expr::Value(value) => literal_type(value),
+            Expr::Cast {
+                expr, data_type, ..
+            } => (SqlType::from_ast(data_type), self.expr_type(expr, scope).1),
+            Expr::Nested(inner) => self.expr_type(inner, scope),
+            Expr::UnaryOp { op, expr } => {
+                let (data_type, nullable) = self.expr_type(expr, scope);
+                match op {
+                    UnaryOperator::Not => (SqlType::Boolean, nullable),
+                    _ => (data_type, nullable),
+                }
+            }
+            Expr::BinaryOp { .. } => self.binary_chain_type(expr, scope),
+            Expr::IsNull(_)
+            | Expr::IsNotNull(_)
+            | Expr::IsTrue(_)
+            | Expr::IsNotTrue(_)
+            | Expr::IsFalse(_)
+            | Expr::IsNotFalse(_)
+            | Expr::IsDistinctFrom(_, _)
+            | Expr::IsNotDistinctFrom(_, _)
+            | Expr::Exists { .. } => (SqlType::Boolean, false),
+            Expr::InList { .. }
+            | Expr::InSubquery { .. }
+            | Expr::Between { .. }
+            | Expr::Like { .. }
+            | Expr::ILike { .. }
+            | Expr::SimilarTo { .. } => (SqlType::Boolean, true),
+            Expr::Case {
+                results,
+                else_result,
+                ..
+            } => {
+                let mut data_type = SqlType::Unknown;
+                let mut nullable = else_result.is_none();
+                for result in results.iter().chain(else_result.as_deref()) {
+                    let (result_type, result_nullable) = self.expr_type(result, scope);
+                    nullable |= result_nullable;
+                    if data_type == SqlType::Unknown {
+                        data_type = result_type;
+                    }
+                }
+                (data_type, nullable)
+            }
+            Expr::Subquery(query) => match self.query(query) {
+                Ok(columns) if columns.len() == 1 => (columns[0].data_type.clone(), true),
+                _ => (SqlType::Unknown, true),
+            },
+            Expr::Function(func) => self.function_type(func, scope),
+            _ => (SqlType::Unknown, true),
+        }
+    }
+
+    /// Type of a left-deep chain of binary operations, walked iteratively
+    fn binary_chain_type(&mut self, expr: &Expr, scope: &[Relation]) -> (SqlType, bool) {
+        let mut chain = Vec::new();
+        let mut leaf = expr;
+        while let Expr::BinaryOp { left, op, right } = leaf {
+            chain.push((op, right));
+            leaf = left;
+        }
+
+        let (mut data_type, mut nullable) = self.expr_type(leaf, scope);
+        for (op, right) in chain.into_iter().rev() {
+            let (right_type, right_nullable) = self.expr_type(right, scope);
+            // JSON field access yields NULL for missing keys
+            nullable |= right_nullable || is_json_access(op);
+            data_type = match op {
+                BinaryOperator::Plus
+                | BinaryOperator::Minus
+                | BinaryOperator::Multiply
+                | BinaryOperator::Divide
+                | BinaryOperator::Modulo => {
+                    if data_type == SqlType::Unknown {
+                        right_type
+                    } else {
+                        data_type
+                    }
+                }
+                BinaryOperator::StringConcat
+                | BinaryOperator::LongArrow
+                | BinaryOperator::HashLongArrow => SqlType::Text,
+                BinaryOperator::Arrow | BinaryOperator::HashArrow
+                    if matches!(data_type, SqlType::Json | SqlType::Jsonb) =>
+                {
+                    data_type
+                }
+                BinaryOperator::Eq
+                | BinaryOperator::NotEq
+                | BinaryOperator::Lt
+                | BinaryOperator::LtEq
+                | BinaryOperator::Gt
+                | BinaryOperator::GtEq
+                | BinaryOperator::And
+                | BinaryOperator::Or => SqlType::Boolean,
+                _ => SqlType::Unknown,
+            };
+        }
+        (data_type, nullable)
+    }
+
+    /// Result type of declared and well-known functions
+    fn function_type(&mut self, func: &Function, scope: &[Relation]) -> (SqlType, bool) {
+        if let Some(returns) = declared_return_type(self.catalog, func) {
+            return (returns, true);
+        }
+        let name = function_name(func);
+        let name = name.as_str();
+        let args = function_args(func);
+        let first = args.first().map(|arg| self.expr_type(arg, scope));
+        match name {
+            "count" => (SqlType::BigInt, false),
+            "sum" => match first.map(|(t, _)| t) {
+                Some(SqlType::SmallInt | SqlType::Integer) => (SqlType::BigInt, true),
+                Some(t @ (SqlType::Real | SqlType::DoublePrecision)) => (t, true),
+                Some(SqlType::Unknown) | None => (SqlType::Unknown, true),
+                Some(_) => (
+                    SqlType::Decimal {
+                        precision: None,
+                        scale: None,
+                    },
+                    true,
+                ),
+            },
+            "avg" => match first.map(|(t, _)| t) {
+                Some(SqlType::Real | SqlType::DoublePrecision) => (SqlType::DoublePrecision, true),
+                Some(SqlType::Unknown) | None => (SqlType::Unknown, true),
+                Some(_) => (
+                    SqlType::Decimal {
+                        precision: None,
+                        scale: None,
+                    },
+                    true,
+                ),
+            },
+            "min" | "max" => (first.map_or(SqlType::Unknown, |(t, _)| t), true),
+            "coalesce" => {
+                let mut data_type = SqlType::Unknown;
+                let mut nullable = true;
+                for arg in args {
+                    let (arg_type, arg_nullable) = self.expr_type(arg, scope);
+                    if data_type == SqlType::Unknown {
+                        data_type = arg_type;
+                    }
+                    nullable &= arg_nullable;
+                }
+                (data_type, nullable)
+            }
+            "lower" | "upper" | "trim" | "btrim" | "ltrim" | "rtrim" | "substring" | "substr"
+            | "replace" | "left" | "right" | "lpad" | "rpad" => {
+                (SqlType::Text, first.map_or(true, |(_, n)| n))
+            }
+            "concat" | "concat_ws" => (SqlType::Text, false),
+            "length" | "char_length" | "character_length" | "octet_length" => {
+                (SqlType::Integer, first.map_or(true, |(_, n)| n))
+            }
+            "now" | "current_timestamp" => (
+                SqlType::Timestamp {
+                    precision: None,
+                    with_timezone: true,
+                },
+                false,
+            ),
+            "current_date" => (SqlType::Date, false),
+            "gen_random_uuid" | "uuid_generate_v4" => (SqlType::Uuid, false),
+            _ => {
+                let first_type = first.map_or(SqlType::Unknown, |(t, _)| t);
+                set_returning_type(name, &first_type).unwrap_or((SqlType::Unknown, true))
+            }
+        }
+    }
+}
+
+/// Whether an operator extracts a JSON field or element (`->`, `->>`, `#>`, `#>>`)
+fn is_json_access(op: &BinaryOperator) -> bool {
+    matches!(
+        op,
+        BinaryOperator::Arrow
+            | BinaryOperator::LongArrow
+            | BinaryOperator::HashArrow
+            | BinaryOperator::HashLongArrow
+    )
+}
+
+/// Postgres-style output name of an unaliased expression
+fn expr_name(expr: &Expr) -> String {
+    match expr {
+        Expr::Identifier(ident) => ident.value.clone(),
+        Expr::CompoundIdentifier(idents) => idents
+            .last()
+            .map(|i| i.value.clone())
+            .unwrap_or_else(|| "?column?".to_string()),
+        Expr::Function(func) => func
+            .name
+            .0
+            .last()
+            .map(|i| i.value.to_lowercase())
+            .unwrap_or_else(|| "?column?".to_string()),
+        Expr::Cast { expr, .. } | Expr::Nested(expr) => expr_name(expr),
+        Expr::Case { .. } => "case".to_string(),
+        _ => "?column?".to_string(),
+    }
+}
+
+/// Mark the columns a WHERE clause requires to be non-null as NOT NULL
+///
+/// Only `x IS NOT NULL` conditions joined with AND are followed: under OR or
+/// NOT a row may still pass with a NULL. An unqualified column is refined in
+/// every relation that has it, as the reference can't be ambiguous in a
+/// valid query.
+fn refine_not_null(scope: &mut [Relation], selection: &Expr) {
+    match selection {
+        Expr::Nested(inner) => refine_not_null(scope, inner),
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => {
+            refine_not_null(scope, left);
+            refine_not_null(scope, right);
+        }
+        Expr::IsNotNull(expr) => {
+            let (relations, column): (Vec<&mut Relation>, &str) = match expr.as_ref() {
+                Expr::Identifier(ident) => (scope.iter_mut().collect(), &ident.value),
+                Expr::CompoundIdentifier(idents) if idents.len() >= 2 => {
+                    let table = &idents[idents.len() - 2].value;
+                    (
+                        scope
+                            .iter_mut()
+                            .filter(|r| r.name.eq_ignore_ascii_case(table))
+                            .take(1)
+                            .collect(),
+                        &idents[idents.len() - 1].value,
+                    )
+                }
+                _ => return,
+            };
+            for relation in relations {
+                for c in &mut relation.columns {
+                    if c.name.eq_ignore_ascii_case(column) {
+                        c.nullable = false;
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Type and nullability of a literal
+fn literal_type(value: &Value) -> (SqlType, bool) {
+    match value {
+        Value::Number(n, _) if n.contains(['.', 'e', 'E']) => (
+            SqlType::Decimal {
+                precision: None,
+                scale: None,
+            },
+            false,
+        ),
+        Value::Number(_, _) => (SqlType::Integer, false),
+        Value::SingleQuotedString(_) | Value::DoubleQuotedString(_) => (SqlType::Text, false),
+        Value::Boolean(_) => (SqlType::Boolean, false),
+        _ => (SqlType::Unknown, true),
+    }
+}
+
+/// Whether each side of a join may be null-extended: (left, right)
+fn join_nullability(op: &JoinOperator) -> (bool, bool) {
+    match op {
+        JoinOperator::LeftOuter(_) => (false, true),
+        JoinOperator::RightOuter(_) => (true, false),
+        JoinOperator::FullOuter(_) => (true, true),
+        JoinOperator::OuterApply => (false, true),
+        _ => (false, false),
+    }
+}
+
+/// Columns of a join: merged USING / NATURAL columns first (NULL only when
+/// both sides are, as they coalesce), then the rest of each side
+fn merge_join_columns(
+    join_operator: &JoinOperator,
+    left: Vec<OutputColumn>,
+    right: Vec<OutputColumn>,
+) -> Vec<OutputColumn> {
+    let names =
+        |columns: &[OutputColumn]| columns.iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+    let merged = merged_columns(join_operator, &names(&left), &names(&right));
+    if merged.is_empty() {
+        return left.into_iter().chain(right).collect();
+    }
+    let find = |columns: &[OutputColumn], name: &str| {
+        columns
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(name))
+            .cloned()
+    };
+    let mut output: Vec<OutputColumn> = merged
+        .iter()
+        .map(|name| match (find(&left, name), find(&right, name)) {
+            (Some(mut l), Some(r)) => {
+                l.nullable &= r.nullable;
+                l
+            }
+            (Some(c), None) | (None, Some(c)) => c,
+            (None, None) => OutputColumn::unknown(name),
+        })
+        .collect();
+    let is_merged = |c: &OutputColumn| merged.iter().any(|m| m.eq_ignore_ascii_case(&c.name));
+    output.extend(left.into_iter().filter(|c| !is_merged(c)));
+    output.extend(right.into_iter().filter(|c| !is_merged(c)));
+    output
+}
+
+fn make_nullable(columns: &mut [OutputColumn]) {
+    for column in columns {
+        column.nullable = true;
+    }
+}
+
+fn find_relation<'r>(scope: &'r [Relation], name: &str) -> Option<&'r Relation> {
+    scope.iter().find(|r| r.name.eq_ignore_ascii_case(name))
+}
+
+/// Columns listed in an alias (`AS t(a, b)`), typed as unknown
+fn alias_columns(alias: Option<&TableAlias>) -> Option<Vec<OutputColumn>> {
+    alias.filter(|a| !a.columns.is_empty()).map(|a| {
+        a.columns
+            .iter()
+            .map(|c| OutputColumn::unknown(&c.name.value))
+            .collect()
+    })
+}
+
+/// Rename the leading columns with the alias column list (see `apply_alias_columns`)
+fn rename_columns(alias: Option<&TableAlias>, mut columns: Vec<OutputColumn>) -> Vec<OutputColumn> {
+    if let Some(alias) = alias {
+        for (column, name) in columns.iter_mut().zip(&alias.columns) {
+            column.name = name.name.value.clone();
+        }
+    }
+    columns
+}
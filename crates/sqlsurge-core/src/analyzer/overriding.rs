@@ -0,0 +1,137 @@
+//! PostgreSQL `INSERT ... OVERRIDING {SYSTEM|USER} VALUE`
+//!
+//! sqlparser rejects the OVERRIDING clause of INSERT. Before PostgreSQL
+//! input is parsed, the clause is blanked out, keeping every line and column
+//! in place, and remembered by the position of the table name (or alias)
+//! the INSERT writes to, so name resolution can apply it to identity
+//! columns:
+//!
+//! - `OVERRIDING SYSTEM VALUE` allows values for GENERATED ALWAYS identity
+//!   columns, which are otherwise an error (E0008)
+//! - `OVERRIDING USER VALUE` discards the values given for identity
+//!   columns, and the sequence supplies them instead (W0016)
+
+use sqlparser::dialect::PostgreSqlDialect;
+use sqlparser::tokenizer::{Location, Token, TokenWithSpan, Tokenizer};
+
+use super::hints::{apply_edits, is_word};
+
+/// Which values an OVERRIDING clause lets win for identity columns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Overriding {
+    /// `OVERRIDING SYSTEM VALUE`: the values given in the INSERT
+    SystemValue,
+    /// `OVERRIDING USER VALUE`: the identity sequence
+    UserValue,
+}
+
+/// An OVERRIDING clause removed from the SQL text
+#[derive(Debug, Clone, Copy)]
+pub(super) struct InsertOverride {
+    /// End of the table name or alias the INSERT writes to
+    pub anchor: Location,
+    pub overriding: Overriding,
+}
+
+/// SQL text with OVERRIDING clauses removed
+pub(super) struct Rewritten {
+    pub sql: String,
+    pub overrides: Vec<InsertOverride>,
+}
+
+/// Remove the OVERRIDING clauses of INSERT statements, or `None` if `sql`
+/// contains none
+pub(super) fn rewrite(sql: &str) -> Option<Rewritten> {
+    // Most input has none, and isn't tokenized
+    let keyword = b"overriding";
+    if !sql
+        .as_bytes()
+        .windows(keyword.len())
+        .any(|w| w.eq_ignore_ascii_case(keyword))
+    {
+        return None;
+    }
+    let tokens = Tokenizer::new(&PostgreSqlDialect {}, sql)
+        .tokenize_with_location()
+        .ok()?;
+    let tokens: Vec<&TokenWithSpan> = tokens
+        .iter()
+        .filter(|t| !matches!(t.token, Token::Whitespace(_)))
+        .collect();
+
+    let mut edits = Vec::new();
+    let mut overrides = Vec::new();
+    for (i, window) in tokens.windows(3).enumerate() {
+        if !is_word(window[0], "OVERRIDING") || !is_word(window[2], "VALUE") {
+            continue;
+        }
+        let overriding = if is_word(window[1], "SYSTEM") {
+            Overriding::SystemValue
+        } else if is_word(window[1], "USER") {
+            Overriding::UserValue
+        } else {
+            continue;
+        };
+        let Some(anchor) = target_end(&tokens[..i]) else {
+            continue;
+        };
+        overrides.push(InsertOverride { anchor, overriding });
+        edits.push((window[0].span.start, window[2].span.end, ""));
+    }
+
+    if edits.is_empty() {
+        return None;
+    }
+    Some(Rewritten {
+        sql: apply_edits(sql, &edits),
+        overrides,
+    })
+}
+
+/// End of the table name or alias before an OVERRIDING clause: the last of
+/// `tokens`, or the one before the column list they end with
+fn target_end(tokens: &[&TokenWithSpan]) -> Option<Location> {
+    let mut i = tokens.len().checked_sub(1)?;
+    if tokens[i].token == Token::RParen {
+        let mut depth = 0;
+        loop {
+            match tokens[i].token {
+                Token::RParen => depth += 1,
+                Token::LParen => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                break;
+            }
+            i = i.checked_sub(1)?;
+        }
+        i = i.checked_sub(1)?;
+    }
+    matches!(tokens[i].token, Token::Word(_)).then_some(tokens[i].span.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_keeps_positions() {
+        let sql = "INSERT INTO accounts (id, name)\n  OVERRIDING SYSTEM VALUE VALUES (1, 'x');\n\
+                   INSERT INTO public.accounts AS a overriding user value VALUES (2, 'y');";
+        let rewritten = rewrite(sql).unwrap();
+        assert_eq!(rewritten.sql.len(), sql.len());
+        assert_eq!(
+            rewritten.sql.lines().nth(1),
+            Some("                          VALUES (1, 'x');")
+        );
+
+        let overrides = &rewritten.overrides;
+        assert_eq!(overrides.len(), 2);
+        assert_eq!(overrides[0].overriding, Overriding::SystemValue);
+        assert_eq!(overrides[0].anchor, Location::new(1, 21));
+        assert_eq!(overrides[1].overriding, Overriding::UserValue);
+        assert_eq!(overrides[1].anchor, Location::new(3, 33));
+
+        assert!(rewrite("SELECT 'OVERRIDING SYSTEM VALUE' FROM t").is_none());
+    }
+}
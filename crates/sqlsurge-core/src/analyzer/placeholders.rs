@@ -0,0 +1,345 @@
+//! Template placeholders in query files (psql variables, Flyway/Liquibase)
+//!
+//! Migration and scripting tools leave their own variables in SQL: psql's
+//! `:name`, `:'name'` and `:"name"`, and Flyway/Liquibase's `${name}`. When
+//! placeholder styles are enabled, they are replaced before parsing:
+//!
+//! - where a value is expected, by a parameter marker (`$1`, or `?` for MySQL)
+//! - as a qualifier (`:'schema'.users`), by nothing, so the name resolves
+//!   through the search path
+//! - where a name is expected (after FROM, JOIN, INTO, UPDATE or TABLE, and
+//!   for `:"name"`), by a dummy identifier; a relation named by it has any
+//!   columns, and diagnostics pointing at the dummy are dropped
+//!
+//! Replacements are padded with spaces to the placeholder's width, so
+//! positions stay in place. Only a dummy identifier longer than its
+//! placeholder shifts the rest of its line, and diagnostics are mapped back
+//! for that. Input without placeholders is not rewritten at all.
+
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::dialect::SqlDialect;
+use crate::error::{Diagnostic, Span};
+
+/// A placeholder syntax recognized in query files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaceholderStyle {
+    /// psql variables: `:name`, `:'name'` and `:"name"`
+    Psql,
+    /// Flyway and Liquibase placeholders: `${name}`
+    Flyway,
+}
+
+impl FromStr for PlaceholderStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "psql" => Ok(Self::Psql),
+            "flyway" | "liquibase" => Ok(Self::Flyway),
+            _ => Err(format!(
+                "unknown placeholder style '{}' (expected psql or flyway)",
+                s
+            )),
+        }
+    }
+}
+
+/// Keywords after which a placeholder names a relation
+const NAME_KEYWORDS: &[&str] = &["FROM", "JOIN", "INTO", "UPDATE", "TABLE"];
+
+/// SQL text with placeholders replaced
+pub(super) struct Rewritten {
+    pub sql: String,
+    /// Dummy identifiers in `sql`: (line, start column, end column)
+    identifiers: Vec<(usize, usize, usize)>,
+    /// Replacements longer than their placeholder
+    shifts: Vec<Shift>,
+}
+
+/// A replacement that made its line longer than in the source
+struct Shift {
+    /// Byte offset in the rewritten text just after the replacement
+    offset: usize,
+    /// Line and column in the rewritten text just after the replacement
+    line: usize,
+    column: usize,
+    /// How many characters longer the replacement is
+    delta: usize,
+}
+
+/// A placeholder found in the source
+struct Placeholder {
+    range: Range<usize>,
+    /// `:"name"`, which psql always interpolates as an identifier
+    quoted_identifier: bool,
+}
+
+/// Replace placeholders of the given styles, or `None` if `sql` contains none
+pub(super) fn rewrite(
+    sql: &str,
+    styles: &[PlaceholderStyle],
+    dialect: SqlDialect,
+    identifier: &str,
+) -> Option<Rewritten> {
+    let found = find_placeholders(sql, styles, dialect);
+    if found.is_empty() {
+        return None;
+    }
+
+    let parameter = match dialect {
+        SqlDialect::PostgreSQL => "$1",
+        SqlDialect::MySQL => "?",
+    };
+    let mut out = String::with_capacity(sql.len());
+    let mut identifiers = Vec::new();
+    let mut shifts = Vec::new();
+    let mut pos = 0;
+    for placeholder in found {
+        let Range { start, mut end } = placeholder.range;
+        let names_relation = placeholder.quoted_identifier
+            || sql[..start].ends_with('.')
+            || previous_word(sql, start)
+                .is_some_and(|w| NAME_KEYWORDS.iter().any(|k| w.eq_ignore_ascii_case(k)));
+        let (replacement, is_identifier) = if sql[end..].starts_with('.') {
+            // A qualifier: dropped with its dot
+            end += 1;
+            ("", false)
+        } else if names_relation {
+            (identifier, true)
+        } else {
+            (parameter, false)
+        };
+        let width = sql[start..end].chars().count();
+        let written = replacement.chars().count();
+
+        out.push_str(&sql[pos..start]);
+        if is_identifier {
+            let (line, column) = position(&out);
+            identifiers.push((line, column, column + written));
+            if written > width {
+                shifts.push(Shift {
+                    offset: out.len() + replacement.len(),
+                    line,
+                    column: column + written,
+                    delta: written - width,
+                });
+            }
+        }
+        out.push_str(replacement);
+        out.extend(std::iter::repeat(' ').take(width.saturating_sub(written)));
+        pos = end;
+    }
+    out.push_str(&sql[pos..]);
+
+    Some(Rewritten {
+        sql: out,
+        identifiers,
+        shifts,
+    })
+}
+
+impl Rewritten {
+    /// Whether a dummy identifier was substituted for a placeholder
+    pub(super) fn names_relations(&self) -> bool {
+        !self.identifiers.is_empty()
+    }
+
+    /// Map a diagnostic on the rewritten text back onto the source
+    ///
+    /// Returns `false` for diagnostics pointing at a dummy identifier, or
+    /// labelling one, which say nothing about the source.
+    pub(super) fn restore(&self, diag: &mut Diagnostic) -> bool {
+        let on_dummy = |span: &Span| {
+            self.identifiers
+                .iter()
+                .any(|&(line, start, end)| span.line == line && (start..end).contains(&span.column))
+        };
+        if diag.span.as_ref().is_some_and(on_dummy)
+            || diag
+                .labels
+                .iter()
+                .any(|l| l.file.is_none() && on_dummy(&l.span))
+        {
+            return false;
+        }
+        if self.shifts.is_empty() {
+            return true;
+        }
+        if let Some(span) = &mut diag.span {
+            self.restore_span(span);
+        }
+        for label in diag.labels.iter_mut().filter(|l| l.file.is_none()) {
+            self.restore_span(&mut label.span);
+        }
+        for fix in &mut diag.fixes {
+            self.restore_span(&mut fix.span);
+        }
+        if let Some(statement) = &mut diag.statement {
+            let end = statement.span.offset + statement.span.length;
+            self.restore_span(&mut statement.span);
+            statement.span.length = end - self.delta(|s| s.offset <= end) - statement.span.offset;
+        }
+        true
+    }
+
+    fn restore_span(&self, span: &mut Span) {
+        let offset = span.offset;
+        span.offset -= self.delta(|s| s.offset <= offset);
+        let (line, column) = (span.line, span.column);
+        span.column -= self.delta(|s| s.line == line && s.column <= column);
+        let (end_line, end_column) = (span.end_line, span.end_column);
+        span.end_column -= self.delta(|s| s.line == end_line && s.column <= end_column);
+    }
+
+    fn delta(&self, applies: impl Fn(&Shift) -> bool) -> usize {
+        self.shifts
+            .iter()
+            .filter(|s| applies(s))
+            .map(|s| s.delta)
+            .sum()
+    }
+}
+
+/// Placeholders outside string literals, quoted identifiers and comments
+fn find_placeholders(
+    sql: &str,
+    styles: &[PlaceholderStyle],
+    dialect: SqlDialect,
+) -> Vec<Placeholder> {
+    let psql = styles.contains(&PlaceholderStyle::Psql);
+    let flyway = styles.contains(&PlaceholderStyle::Flyway);
+    let bytes = sql.as_bytes();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"' | b'`') => {
+                i = skip_quoted(bytes, i, quote, dialect == SqlDialect::MySQL)
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = sql[i..].find('\n').map_or(bytes.len(), |n| i + n);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = sql[i + 2..].find("*/").map_or(bytes.len(), |n| i + n + 4);
+            }
+            b'$' if flyway && bytes.get(i + 1) == Some(&b'{') => match sql[i + 2..].find('}') {
+                Some(n) if n > 0 && is_name(&sql[i + 2..i + 2 + n]) => {
+                    let end = i + n + 3;
+                    found.push(Placeholder {
+                        range: i..end,
+                        quoted_identifier: false,
+                    });
+                    i = end;
+                }
+                _ => i += 1,
+            },
+            b'$' if dialect == SqlDialect::PostgreSQL => i = skip_dollar_quoted(sql, i),
+            b':' if psql => {
+                let next = bytes.get(i + 1).copied();
+                if next == Some(b':') {
+                    // A `::type` cast
+                    i += 2;
+                    continue;
+                }
+                // `a[1:n]` slices and `x:y` aren't variables
+                let after_word =
+                    i > 0 && (bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'_');
+                let end = match next {
+                    _ if after_word => None,
+                    Some(c) if c.is_ascii_alphabetic() || c == b'_' => Some(
+                        sql[i + 1..]
+                            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                            .map_or(bytes.len(), |n| i + 1 + n),
+                    ),
+                    Some(quote @ (b'\'' | b'"')) => sql[i + 2..]
+                        .find(quote as char)
+                        .filter(|&n| n > 0 && is_name(&sql[i + 2..i + 2 + n]))
+                        .map(|n| i + n + 3),
+                    _ => None,
+                };
+                match end {
+                    Some(end) => {
+                        found.push(Placeholder {
+                            range: i..end,
+                            quoted_identifier: next == Some(b'"'),
+                        });
+                        i = end;
+                    }
+                    None => i += 1,
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    found
+}
+
+/// Whether `s` is a plain variable name
+fn is_name(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Index just past the quoted text starting at `start` (doubled quotes
+/// escape, and in MySQL strings also backslashes)
+fn skip_quoted(bytes: &[u8], start: usize, quote: u8, backslash_escapes: bool) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        if bytes[i] == quote {
+            if bytes.get(i + 1) == Some(&quote) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        if bytes[i] == b'\\' && backslash_escapes && quote != b'`' {
+            i += 1;
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+/// Index just past a `$tag$ ... $tag$` string starting at `start`, or past
+/// the `$` if it doesn't start one (e.g. a `$1` parameter)
+fn skip_dollar_quoted(sql: &str, start: usize) -> usize {
+    let rest = &sql[start + 1..];
+    let Some(tag_len) = rest.find('$') else {
+        return start + 1;
+    };
+    let tag = &rest[..tag_len];
+    if !tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        || tag.starts_with(|c: char| c.is_ascii_digit())
+    {
+        return start + 1;
+    }
+    let delimiter = &sql[start..start + tag_len + 2];
+    let body = start + delimiter.len();
+    sql[body..]
+        .find(delimiter)
+        .map_or(sql.len(), |n| body + n + delimiter.len())
+}
+
+/// The word before `index`, skipping whitespace
+fn previous_word(sql: &str, index: usize) -> Option<&str> {
+    let before = sql[..index].trim_end();
+    let start = before
+        .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .map_or(0, |i| i + 1);
+    let word = &before[start..];
+    (!word.is_empty()).then_some(word)
+}
+
+/// 1-indexed (line, column) of the end of `before`
+fn position(before: &str) -> (usize, usize) {
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
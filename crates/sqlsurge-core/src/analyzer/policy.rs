@@ -0,0 +1,224 @@
+//! Statement classification and write policies
+//!
+//! [`classify`] sorts a statement into reads, data writes and schema changes.
+//! A [`WritePolicy`] (e.g. `policy = "read-only"` for report queries) turns
+//! statements of a forbidden class into E0015 errors.
+
+use serde::{Deserialize, Serialize};
+use sqlparser::ast::{Query, SetExpr, Spanned, Statement};
+use sqlparser::dialect::Dialect;
+use sqlparser::tokenizer::{Token, Tokenizer};
+use std::str::FromStr;
+
+use crate::error::{Diagnostic, DiagnosticKind, Span};
+
+/// What a statement does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StatementClass {
+    /// Transaction control, session settings, SHOW, plain EXPLAIN, ...
+    Other,
+    /// SELECT (without INTO), VALUES, COPY ... TO
+    Read,
+    /// INSERT, UPDATE, DELETE, MERGE, TRUNCATE, COPY ... FROM, CALL, and
+    /// queries with a data-modifying CTE (`WITH x AS (UPDATE ... RETURNING ...)`);
+    /// also REFRESH MATERIALIZED VIEW, which sqlparser doesn't parse
+    Write,
+    /// CREATE, ALTER, DROP, COMMENT, GRANT, `SELECT ... INTO new_table`, ...
+    Ddl,
+}
+
+/// Classify a statement
+///
+/// # Example
+///
+/// ```
+/// use sqlparser::dialect::PostgreSqlDialect;
+/// use sqlparser::parser::Parser;
+/// use sqlsurge_core::analyzer::{classify, StatementClass};
+///
+/// let sql = "WITH moved AS (UPDATE users SET active = false RETURNING id) SELECT id FROM moved";
+/// let stmt = &Parser::parse_sql(&PostgreSqlDialect {}, sql).unwrap()[0];
+/// assert_eq!(classify(stmt), StatementClass::Write);
+/// ```
+pub fn classify(stmt: &Statement) -> StatementClass {
+    match stmt {
+        Statement::Query(query) => query_class(query),
+        Statement::Insert(_)
+        | Statement::Update { .. }
+        | Statement::Delete(_)
+        | Statement::Merge { .. }
+        | Statement::Truncate { .. }
+        | Statement::Call(_)
+        | Statement::LoadData { .. } => StatementClass::Write,
+        Statement::Copy { to: true, .. } => StatementClass::Read,
+        Statement::Copy { .. } => StatementClass::Write,
+        // EXPLAIN ANALYZE runs the statement
+        Statement::Explain {
+            statement,
+            analyze: true,
+            ..
+        } => classify(statement),
+        Statement::CreateTable(_)
+        | Statement::CreateView { .. }
+        | Statement::CreateIndex(_)
+        | Statement::CreateType { .. }
+        | Statement::CreateSchema { .. }
+        | Statement::CreateDatabase { .. }
+        | Statement::CreateSequence { .. }
+        | Statement::CreateFunction(_)
+        | Statement::CreateProcedure { .. }
+        | Statement::CreateTrigger { .. }
+        | Statement::CreateExtension { .. }
+        | Statement::CreateRole { .. }
+        | Statement::CreatePolicy { .. }
+        | Statement::CreateVirtualTable { .. }
+        | Statement::AlterTable { .. }
+        | Statement::AlterIndex { .. }
+        | Statement::AlterView { .. }
+        | Statement::AlterRole { .. }
+        | Statement::AlterPolicy { .. }
+        | Statement::Drop { .. }
+        | Statement::DropFunction { .. }
+        | Statement::DropProcedure { .. }
+        | Statement::DropTrigger { .. }
+        | Statement::DropPolicy { .. }
+        | Statement::Comment { .. }
+        | Statement::Grant { .. }
+        | Statement::Revoke { .. } => StatementClass::Ddl,
+        _ => StatementClass::Other,
+    }
+}
+
+/// Class of a query: a write if any CTE modifies data, DDL for SELECT INTO
+///
+/// sqlparser 0.53 parses INSERT and UPDATE in a CTE, but not DELETE.
+fn query_class(query: &Query) -> StatementClass {
+    let ctes = query
+        .with
+        .iter()
+        .flat_map(|with| &with.cte_tables)
+        .map(|cte| query_class(&cte.query));
+    ctes.chain(std::iter::once(set_expr_class(&query.body)))
+        .max()
+        .unwrap_or(StatementClass::Read)
+}
+
+fn set_expr_class(set_expr: &SetExpr) -> StatementClass {
+    match set_expr {
+        SetExpr::Select(select) if select.into.is_some() => StatementClass::Ddl,
+        SetExpr::Query(query) => query_class(query),
+        SetExpr::SetOperation { left, right, .. } => {
+            set_expr_class(left).max(set_expr_class(right))
+        }
+        SetExpr::Insert(stmt) | SetExpr::Update(stmt) => classify(stmt),
+        SetExpr::Select(_) | SetExpr::Values(_) | SetExpr::Table(_) => StatementClass::Read,
+    }
+}
+
+/// Statements a query file may contain
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WritePolicy {
+    /// No restriction
+    #[default]
+    Any,
+    /// Reads only: data writes and schema changes are errors
+    ReadOnly,
+    /// Reads and data writes; schema changes are errors
+    DdlForbidden,
+}
+
+impl WritePolicy {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Any => "any",
+            Self::ReadOnly => "read-only",
+            Self::DdlForbidden => "ddl-forbidden",
+        }
+    }
+
+    /// Whether statements of `class` are allowed
+    pub fn allows(self, class: StatementClass) -> bool {
+        match self {
+            Self::Any => true,
+            Self::ReadOnly => class <= StatementClass::Read,
+            Self::DdlForbidden => class != StatementClass::Ddl,
+        }
+    }
+}
+
+impl FromStr for WritePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "any" => Ok(Self::Any),
+            "read-only" => Ok(Self::ReadOnly),
+            "ddl-forbidden" => Ok(Self::DdlForbidden),
+            _ => Err(format!(
+                "unknown policy '{}' (expected any, read-only or ddl-forbidden)",
+                s
+            )),
+        }
+    }
+}
+
+/// Spans of the first token of each statement in `sql`
+///
+/// sqlparser's statement spans are partial (DELETE starts at the table name,
+/// DROP has none), so policy errors point at the leading keyword instead.
+pub(super) fn statement_keywords(dialect: &dyn Dialect, sql: &str) -> Vec<Span> {
+    let Ok(tokens) = Tokenizer::new(dialect, sql).tokenize_with_location() else {
+        return Vec::new();
+    };
+    let mut keywords = Vec::new();
+    let mut at_start = true;
+    for token in tokens {
+        match token.token {
+            Token::Whitespace(_) => {}
+            Token::SemiColon => at_start = true,
+            token_value if at_start => {
+                let start = token.span.start;
+                keywords.push(Span::with_location(
+                    start.line as usize,
+                    start.column as usize,
+                    token_value.to_string().len(),
+                ));
+                at_start = false;
+            }
+            _ => {}
+        }
+    }
+    keywords
+}
+
+/// Check a statement against the write policy (E0015)
+///
+/// `class` is what the statement does (see [`classify`]) and `keyword` its
+/// leading keyword from [`statement_keywords`], if known.
+pub(super) fn check_policy(
+    stmt: &Statement,
+    class: StatementClass,
+    policy: WritePolicy,
+    keyword: Option<Span>,
+) -> Option<Diagnostic> {
+    if policy.allows(class) {
+        return None;
+    }
+    let what = match class {
+        StatementClass::Ddl => "Schema changes are",
+        _ => "Data-modifying statements are",
+    };
+    let mut diag = Diagnostic::error(
+        DiagnosticKind::PolicyViolation,
+        format!("{} not allowed by the '{}' policy", what, policy.name()),
+    )
+    .with_help("Move the statement to a file whose policy allows it, or change the policy");
+    let span = stmt.span();
+    if let Some(keyword) = keyword {
+        diag = diag.with_span(keyword);
+    } else if span.start.line > 0 {
+        diag = diag.with_span(Span::from_sqlparser(&span));
+    }
+    Some(diag)
+}
@@ -0,0 +1,101 @@
+//! Per-phase timing of analysis
+//!
+//! With [`Analyzer::with_profiling`](super::Analyzer::with_profiling), the
+//! analyzer records how long parsing, name resolution, type checking and the
+//! remaining rules take for each statement. When profiling is off no
+//! timestamps are taken.
+
+use std::time::{Duration, Instant};
+
+/// An analysis phase
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Parsing the input (once per input, not per statement)
+    Parse,
+    /// Name resolution
+    Names,
+    /// Type inference and checking
+    Types,
+    /// Index hints, write policy and dialect feature checks
+    Rules,
+}
+
+impl Phase {
+    /// Phases in the order they run
+    pub const ALL: [Phase; 4] = [Phase::Parse, Phase::Names, Phase::Types, Phase::Rules];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Parse => "parse",
+            Phase::Names => "names",
+            Phase::Types => "types",
+            Phase::Rules => "rules",
+        }
+    }
+}
+
+/// Time spent on one statement
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatementProfile {
+    pub names: Duration,
+    pub types: Duration,
+    pub rules: Duration,
+}
+
+/// Timings of the last analyzed input
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileReport {
+    /// Time spent parsing the whole input
+    pub parse: Duration,
+    /// One entry per parsed statement, in order
+    pub statements: Vec<StatementProfile>,
+    /// Statements taken from the AST cache
+    /// (see [`Analyzer::with_ast_cache`](super::Analyzer::with_ast_cache))
+    pub cache_hits: usize,
+    /// Statements parsed with the AST cache on
+    pub cache_misses: usize,
+}
+
+impl ProfileReport {
+    /// Time spent in `phase` over all statements
+    pub fn phase_total(&self, phase: Phase) -> Duration {
+        match phase {
+            Phase::Parse => self.parse,
+            Phase::Names => self.statements.iter().map(|s| s.names).sum(),
+            Phase::Types => self.statements.iter().map(|s| s.types).sum(),
+            Phase::Rules => self.statements.iter().map(|s| s.rules).sum(),
+        }
+    }
+
+    /// Add the timings of another part of the same input
+    pub(super) fn absorb(&mut self, other: ProfileReport) {
+        self.parse += other.parse;
+        self.statements.extend(other.statements);
+        self.cache_hits += other.cache_hits;
+        self.cache_misses += other.cache_misses;
+    }
+
+    /// Time spent in all phases
+    pub fn total(&self) -> Duration {
+        Phase::ALL
+            .iter()
+            .map(|&phase| self.phase_total(phase))
+            .sum()
+    }
+}
+
+/// Measures one phase if profiling is on; a no-op otherwise
+pub(super) struct Stopwatch(Option<Instant>);
+
+impl Stopwatch {
+    pub(super) fn start(enabled: bool) -> Self {
+        Self(enabled.then(Instant::now))
+    }
+
+    /// Add the elapsed time to `slot`
+    pub(super) fn stop(self, slot: &mut Duration) {
+        if let Some(start) = self.0 {
+            *slot += start.elapsed();
+        }
+    }
+}
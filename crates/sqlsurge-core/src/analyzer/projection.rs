@@ -0,0 +1,447 @@
+//! Projection expansion - computes the output column names of a query
+//!
+//! Used wherever the width or names of a query's output matter: CTE, derived
+//! table and view column inference, alias list validation, and `*` expansion.
+//! Expansion returns `None` when the output cannot be determined (e.g. a
+//! wildcard over an unknown table-valued function without a column alias
+//! list).
+
+use sqlparser::ast::{
+    Expr, Ident, Join, JoinConstraint, JoinOperator, ObjectName, Query, Select, SelectItem,
+    SetExpr, Spanned, TableAlias, TableFactor,
+};
+use std::collections::HashMap;
+
+use crate::error::Span;
+use crate::idents::object_name_to_qualified;
+use crate::schema::{Catalog, QualifiedName};
+
+use super::data_modifying::returning_select;
+use super::functions::TableFunctionCall;
+use super::resolver::CteDefinition;
+
+/// A named output column of a query (see [`ProjectionExpander::query_sources`])
+pub(super) struct OutputSource {
+    pub(super) name: String,
+    /// Relation the column is taken from, when known
+    pub(super) relation: Option<String>,
+    /// The projection item producing it
+    pub(super) span: Span,
+    /// Whether it comes from a `*` or `t.*`
+    pub(super) wildcard: bool,
+}
+
+/// Computes output columns of queries against a catalog and the CTEs in scope
+pub(crate) struct ProjectionExpander<'a> {
+    catalog: &'a Catalog,
+    /// CTE name -> column names (outer CTEs plus any defined by nested queries)
+    ctes: HashMap<String, Vec<String>>,
+    /// Catalog views whose columns aren't known yet
+    pending: Vec<QualifiedName>,
+    /// First relation whose columns were needed but not found
+    missing: Option<QualifiedName>,
+}
+
+impl<'a> ProjectionExpander<'a> {
+    pub(super) fn new(catalog: &'a Catalog, ctes: &HashMap<String, CteDefinition>) -> Self {
+        Self {
+            catalog,
+            ctes: ctes
+                .iter()
+                .map(|(name, cte)| (name.clone(), cte.columns.clone()))
+                .collect(),
+            pending: Vec::new(),
+            missing: None,
+        }
+    }
+
+    /// Expander for the query of a view being defined, where the views in
+    /// `pending` count as not defined yet (see [`ProjectionExpander::missing`])
+    pub(crate) fn for_view(catalog: &'a Catalog, pending: Vec<QualifiedName>) -> Self {
+        Self {
+            catalog,
+            ctes: HashMap::new(),
+            pending,
+            missing: None,
+        }
+    }
+
+    /// The first catalog relation a `*` needed that isn't in the catalog
+    /// (or is pending), when expansion returned `None` because of one
+    pub(crate) fn missing(&self) -> Option<&QualifiedName> {
+        self.missing.as_ref()
+    }
+
+    /// Output columns of a query, including CTEs defined in its WITH clause
+    pub(crate) fn query_columns(&mut self, query: &Query) -> Option<Vec<String>> {
+        let saved_ctes = self.define_ctes(query);
+        let columns = self.set_expr_columns(&query.body);
+        if let Some(saved_ctes) = saved_ctes {
+            self.ctes = saved_ctes;
+        }
+        columns
+    }
+
+    /// Add the CTEs of a query's WITH clause, which are only visible within
+    /// it; returns the CTEs to restore afterwards
+    fn define_ctes(&mut self, query: &Query) -> Option<HashMap<String, Vec<String>>> {
+        let saved_ctes = query.with.as_ref().map(|_| self.ctes.clone());
+        if let Some(with) = &query.with {
+            for cte in &with.cte_tables {
+                let columns = self
+                    .query_columns(&cte.query)
+                    .map(|inferred| apply_alias_columns(Some(&cte.alias), inferred))
+                    .unwrap_or_else(|| alias_column_names(&cte.alias));
+                self.ctes.insert(cte.alias.name.value.clone(), columns);
+            }
+        }
+        saved_ctes
+    }
+
+    /// Named output columns of a query and the projection items producing
+    /// them; for UNION/INTERSECT/EXCEPT, those of the left arm
+    ///
+    /// Columns named after an expression (`count`, `?column?`) are left out.
+    pub(super) fn query_sources(&mut self, query: &Query) -> Option<Vec<OutputSource>> {
+        self.define_ctes(query);
+        let mut body = query.body.as_ref();
+        loop {
+            match body {
+                SetExpr::Select(select) => return self.select_sources(select),
+                SetExpr::Query(query) => return self.query_sources(query),
+                SetExpr::SetOperation { left, .. } => body = left,
+                _ => return None,
+            }
+        }
+    }
+
+    fn select_sources(&mut self, select: &Select) -> Option<Vec<OutputSource>> {
+        let mut sources = Vec::new();
+        for item in &select.projection {
+            let source = |name: &Ident, relation: Option<&Ident>, span| OutputSource {
+                name: name.value.clone(),
+                relation: relation.map(|r| r.value.clone()),
+                span: Span::from_sqlparser(&span),
+                wildcard: false,
+            };
+            match item {
+                SelectItem::UnnamedExpr(Expr::Identifier(ident)) => {
+                    sources.push(source(ident, None, ident.span));
+                }
+                SelectItem::UnnamedExpr(expr @ Expr::CompoundIdentifier(idents)) => {
+                    if let [.., relation, column] = idents.as_slice() {
+                        sources.push(source(column, Some(relation), expr.span()));
+                    }
+                }
+                SelectItem::ExprWithAlias { alias, .. } => {
+                    sources.push(source(alias, None, alias.span));
+                }
+                SelectItem::UnnamedExpr(_) => {}
+                SelectItem::Wildcard(options) => {
+                    let span = Span::from_sqlparser(&options.wildcard_token.0.span);
+                    for table_with_joins in &select.from {
+                        let columns = self
+                            .joined_sources(&table_with_joins.relation, &table_with_joins.joins)?;
+                        sources.extend(columns.into_iter().map(|(name, relation)| OutputSource {
+                            name,
+                            relation,
+                            span,
+                            wildcard: true,
+                        }));
+                    }
+                }
+                SelectItem::QualifiedWildcard(name, _) => {
+                    let factor = select
+                        .from
+                        .iter()
+                        .flat_map(|twj| {
+                            std::iter::once(&twj.relation)
+                                .chain(twj.joins.iter().map(|j| &j.relation))
+                        })
+                        .find(|factor| self.is_wildcard_target(factor, name))?;
+                    let relation = visible_name(factor);
+                    let span = Span::from_sqlparser(&item.span());
+                    sources.extend(self.relation_columns(factor)?.into_iter().map(|name| {
+                        OutputSource {
+                            name,
+                            relation: relation.clone(),
+                            span,
+                            wildcard: true,
+                        }
+                    }));
+                }
+            }
+        }
+        Some(sources)
+    }
+
+    /// Columns of a relation and its joins, with the relation each comes
+    /// from (none for the merged columns of a USING or NATURAL join)
+    fn joined_sources(
+        &mut self,
+        relation: &TableFactor,
+        joins: &[Join],
+    ) -> Option<Vec<(String, Option<String>)>> {
+        let name = visible_name(relation);
+        let mut columns: Vec<_> = self
+            .relation_columns(relation)?
+            .into_iter()
+            .map(|c| (c, name.clone()))
+            .collect();
+        for join in joins {
+            let name = visible_name(&join.relation);
+            let right = self.relation_columns(&join.relation)?;
+            let left: Vec<String> = columns.iter().map(|(c, _)| c.clone()).collect();
+            let merged = merged_columns(&join.join_operator, &left, &right);
+            let is_merged = |c: &String| merged.iter().any(|m| m.eq_ignore_ascii_case(c));
+            let mut output: Vec<_> = merged.iter().map(|c| (c.clone(), None)).collect();
+            output.extend(columns.into_iter().filter(|(c, _)| !is_merged(c)));
+            output.extend(
+                right
+                    .into_iter()
+                    .filter(|c| !is_merged(c))
+                    .map(|c| (c, name.clone())),
+            );
+            columns = output;
+        }
+        Some(columns)
+    }
+
+    /// Output columns of a set expression
+    ///
+    /// For UNION/INTERSECT/EXCEPT the left arm determines the column names.
+    pub(super) fn set_expr_columns(&mut self, set_expr: &SetExpr) -> Option<Vec<String>> {
+        match set_expr {
+            SetExpr::Select(select) => self.select_columns(select),
+            SetExpr::Query(query) => self.query_columns(query),
+            SetExpr::SetOperation { left, .. } => self.set_expr_columns(left),
+            SetExpr::Values(values) => values.rows.first().map(|row| {
+                (1..=row.len())
+                    .map(|idx| format!("column{}", idx))
+                    .collect()
+            }),
+            // A data-modifying CTE returns its RETURNING columns
+            SetExpr::Insert(statement) | SetExpr::Update(statement) => {
+                self.select_columns(&returning_select(statement)?)
+            }
+            _ => None,
+        }
+    }
+
+    /// Output columns of a SELECT projection, expanding wildcards
+    fn select_columns(&mut self, select: &Select) -> Option<Vec<String>> {
+        let mut columns = Vec::new();
+
+        for (idx, item) in select.projection.iter().enumerate() {
+            match item {
+                SelectItem::UnnamedExpr(_) | SelectItem::ExprWithAlias { .. } => {
+                    columns.extend(item_column_name(idx, item));
+                }
+                SelectItem::Wildcard(_) => {
+                    for table_with_joins in &select.from {
+                        columns.extend(
+                            self.joined_columns(
+                                &table_with_joins.relation,
+                                &table_with_joins.joins,
+                            )?,
+                        );
+                    }
+                }
+                SelectItem::QualifiedWildcard(name, _) => {
+                    let factor = select
+                        .from
+                        .iter()
+                        .flat_map(|twj| {
+                            std::iter::once(&twj.relation)
+                                .chain(twj.joins.iter().map(|j| &j.relation))
+                        })
+                        .find(|factor| self.is_wildcard_target(factor, name))?;
+                    columns.extend(self.relation_columns(factor)?);
+                }
+            }
+        }
+
+        Some(columns)
+    }
+
+    /// Whether the qualifier of `name.*` refers to `factor`
+    ///
+    /// `t.*` matches the name the relation is visible under; `schema.t.*`
+    /// only matches an unaliased catalog table or view of that schema.
+    fn is_wildcard_target(&self, factor: &TableFactor, name: &ObjectName) -> bool {
+        let Some(last) = name.0.last() else {
+            return false;
+        };
+        if visible_name(factor).as_deref() != Some(last.value.as_str()) {
+            return false;
+        }
+        if name.0.len() == 1 {
+            return true;
+        }
+        match factor {
+            TableFactor::Table {
+                name: table,
+                alias: None,
+                ..
+            } => {
+                let table = object_name_to_qualified(table);
+                let referenced = self.catalog.qualify(&object_name_to_qualified(name));
+                !self.ctes.contains_key(&table.name)
+                    && referenced.is_some()
+                    && referenced == self.catalog.qualify(&table)
+            }
+            _ => false,
+        }
+    }
+
+    /// Columns contributed by a single FROM-clause relation
+    pub(super) fn relation_columns(&mut self, factor: &TableFactor) -> Option<Vec<String>> {
+        if let Some(call) = TableFunctionCall::from_factor(factor) {
+            // Unknown functions are only known through their alias list
+            return call.column_names().or_else(|| {
+                call.alias
+                    .filter(|a| !a.columns.is_empty())
+                    .map(alias_column_names)
+            });
+        }
+
+        match factor {
+            TableFactor::Table { name, alias, .. } => {
+                let table_name = object_name_to_qualified(name);
+                let inferred = if let Some(columns) = self.ctes.get(&table_name.name) {
+                    // None are listed for a CTE of unknown columns
+                    (!columns.is_empty()).then(|| columns.clone())
+                } else if let Some(view) = self.catalog.get_view(&table_name) {
+                    (!self.pending.contains(&view.name)).then(|| view.columns.clone())
+                } else {
+                    self.catalog
+                        .get_table(&table_name)
+                        .map(|t| t.column_names().into_iter().map(String::from).collect())
+                };
+                match inferred {
+                    Some(columns) => Some(apply_alias_columns(alias.as_ref(), columns)),
+                    None => {
+                        let listed = alias
+                            .as_ref()
+                            .filter(|a| !a.columns.is_empty())
+                            .map(alias_column_names);
+                        if listed.is_none() && !self.ctes.contains_key(&table_name.name) {
+                            self.missing.get_or_insert(table_name);
+                        }
+                        listed
+                    }
+                }
+            }
+            TableFactor::Derived {
+                subquery, alias, ..
+            } => {
+                let inferred = self.query_columns(subquery)?;
+                Some(apply_alias_columns(alias.as_ref(), inferred))
+            }
+            TableFactor::TableFunction { alias, .. } => alias
+                .as_ref()
+                .filter(|a| !a.columns.is_empty())
+                .map(alias_column_names),
+            TableFactor::NestedJoin {
+                table_with_joins, ..
+            } => self.joined_columns(&table_with_joins.relation, &table_with_joins.joins),
+            _ => None,
+        }
+    }
+
+    /// Columns of a relation and its joins
+    ///
+    /// A USING or NATURAL join outputs each merged column once, first,
+    /// followed by the remaining columns of the left and then the right side.
+    fn joined_columns(&mut self, relation: &TableFactor, joins: &[Join]) -> Option<Vec<String>> {
+        let mut columns = self.relation_columns(relation)?;
+        for join in joins {
+            let right = self.relation_columns(&join.relation)?;
+            let merged = merged_columns(&join.join_operator, &columns, &right);
+            let is_merged = |c: &String| merged.iter().any(|m| m.eq_ignore_ascii_case(c));
+            let mut output = merged.clone();
+            output.extend(columns.into_iter().filter(|c| !is_merged(c)));
+            output.extend(right.into_iter().filter(|c| !is_merged(c)));
+            columns = output;
+        }
+        Some(columns)
+    }
+}
+
+/// Output column name of the non-wildcard projection item at `idx`
+///
+/// A bare or qualified column keeps its name, an aliased item takes the
+/// alias and any other expression is named after its position
+/// (`?column?2`).
+pub(super) fn item_column_name(idx: usize, item: &SelectItem) -> Option<String> {
+    match item {
+        SelectItem::UnnamedExpr(Expr::Identifier(ident)) => Some(ident.value.clone()),
+        SelectItem::UnnamedExpr(Expr::CompoundIdentifier(idents)) => {
+            idents.last().map(|col| col.value.clone())
+        }
+        SelectItem::ExprWithAlias { alias, .. } => Some(alias.value.clone()),
+        SelectItem::UnnamedExpr(_) => Some(format!("?column?{}", idx + 1)),
+        SelectItem::Wildcard(_) | SelectItem::QualifiedWildcard(..) => None,
+    }
+}
+
+/// Columns a join merges into one: its USING list, or for a NATURAL join the
+/// columns both sides have (in left-side order)
+pub(super) fn merged_columns(
+    join_operator: &JoinOperator,
+    left: &[String],
+    right: &[String],
+) -> Vec<String> {
+    use JoinOperator::*;
+    let constraint = match join_operator {
+        Inner(c) | LeftOuter(c) | RightOuter(c) | FullOuter(c) | LeftSemi(c) | RightSemi(c)
+        | LeftAnti(c) | RightAnti(c) => c,
+        CrossJoin | CrossApply | OuterApply | AsOf { .. } | Anti(_) | Semi(_) => return Vec::new(),
+    };
+    match constraint {
+        JoinConstraint::Using(columns) => columns.iter().map(|c| c.value.clone()).collect(),
+        JoinConstraint::Natural => left
+            .iter()
+            .filter(|l| right.iter().any(|r| r.eq_ignore_ascii_case(l)))
+            .cloned()
+            .collect(),
+        JoinConstraint::On(_) | JoinConstraint::None => Vec::new(),
+    }
+}
+
+/// Name a relation is visible under in its scope (alias, or bare table name)
+pub(super) fn visible_name(factor: &TableFactor) -> Option<String> {
+    if let Some(call) = TableFunctionCall::from_factor(factor) {
+        return Some(call.visible_name());
+    }
+    match factor {
+        TableFactor::Table { name, alias, .. } => Some(
+            alias
+                .as_ref()
+                .map(|a| a.name.value.clone())
+                .unwrap_or_else(|| object_name_to_qualified(name).name),
+        ),
+        TableFactor::Derived { alias, .. }
+        | TableFactor::TableFunction { alias, .. }
+        | TableFactor::NestedJoin { alias, .. } => alias.as_ref().map(|a| a.name.value.clone()),
+        _ => None,
+    }
+}
+
+/// Column names listed in an alias: `AS t(a, b)` -> `[a, b]`
+pub(super) fn alias_column_names(alias: &TableAlias) -> Vec<String> {
+    alias.columns.iter().map(|c| c.name.value.clone()).collect()
+}
+
+/// Rename the leading columns of `columns` with the alias column list
+///
+/// Following PostgreSQL, an alias list shorter than the relation only renames
+/// the leading columns; the remaining columns keep their names.
+pub(super) fn apply_alias_columns(alias: Option<&TableAlias>, columns: Vec<String>) -> Vec<String> {
+    let Some(alias) = alias.filter(|a| !a.columns.is_empty()) else {
+        return columns;
+    };
+    let mut renamed = alias_column_names(alias);
+    renamed.extend(columns.into_iter().skip(alias.columns.len()));
+    renamed
+}
@@ -0,0 +1,248 @@
+//! `REFRESH MATERIALIZED VIEW` (PostgreSQL)
+//!
+//! sqlparser has no such statement. Before PostgreSQL input is parsed, each
+//! one is replaced by `SELECT 1`, keeping every line and column in place,
+//! and recorded; where the placeholder is analyzed the recorded statement
+//! is checked instead: it must name a materialized view (E0001, E0019), and
+//! a CONCURRENTLY refresh needs a unique index on it (W0022).
+
+use sqlparser::ast::{Ident, ObjectName, SetExpr, Statement};
+use sqlparser::dialect::PostgreSqlDialect;
+use sqlparser::tokenizer::{Location, Token, TokenWithSpan, Tokenizer};
+
+use super::hints::{apply_edits, is_word};
+use crate::dialect::SqlDialect;
+use crate::error::{Diagnostic, DiagnosticKind, Span};
+use crate::idents::object_name_to_qualified;
+use crate::schema::{Catalog, ViewDef};
+
+/// A REFRESH MATERIALIZED VIEW statement replaced by a placeholder
+#[derive(Debug, Clone)]
+pub(super) struct Refresh {
+    /// Start of the REFRESH, where the placeholder's SELECT is
+    anchor: Location,
+    name: ObjectName,
+    concurrently: bool,
+}
+
+/// SQL text with REFRESH MATERIALIZED VIEW statements replaced
+pub(super) struct Rewritten {
+    pub sql: String,
+    pub refreshes: Vec<Refresh>,
+}
+
+/// Replace REFRESH MATERIALIZED VIEW statements with placeholders, or
+/// `None` if `sql` has none
+pub(super) fn rewrite(sql: &str) -> Option<Rewritten> {
+    // Most input has none, and isn't tokenized
+    let keyword = b"refresh";
+    if !sql
+        .as_bytes()
+        .windows(keyword.len())
+        .any(|w| w.eq_ignore_ascii_case(keyword))
+    {
+        return None;
+    }
+    let tokens = Tokenizer::new(&PostgreSqlDialect {}, sql)
+        .tokenize_with_location()
+        .ok()?;
+    let tokens: Vec<&TokenWithSpan> = tokens
+        .iter()
+        .filter(|t| !matches!(t.token, Token::Whitespace(_)))
+        .collect();
+
+    let mut edits = Vec::new();
+    let mut refreshes = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let starts_statement = i == 0 || tokens[i - 1].token == Token::SemiColon;
+        if !starts_statement || !is_word(token, "REFRESH") {
+            continue;
+        }
+        let Some((refresh, last)) = parse_refresh(&tokens, i) else {
+            continue;
+        };
+        edits.push((token.span.start, token.span.end, "SELECT"));
+        edits.push((tokens[i + 1].span.start, tokens[last].span.end, "1"));
+        refreshes.push(refresh);
+    }
+
+    if edits.is_empty() {
+        return None;
+    }
+    Some(Rewritten {
+        sql: apply_edits(sql, &edits),
+        refreshes,
+    })
+}
+
+/// Parse `REFRESH MATERIALIZED VIEW [CONCURRENTLY] name [WITH [NO] DATA]`
+/// at `i`, returning it and the index of its last token
+fn parse_refresh(tokens: &[&TokenWithSpan], i: usize) -> Option<(Refresh, usize)> {
+    let at = |j: usize| tokens.get(j).copied();
+    if !at(i + 1).is_some_and(|t| is_word(t, "MATERIALIZED"))
+        || !at(i + 2).is_some_and(|t| is_word(t, "VIEW"))
+    {
+        return None;
+    }
+    let mut j = i + 3;
+    let concurrently = at(j).is_some_and(|t| is_word(t, "CONCURRENTLY"));
+    if concurrently {
+        j += 1;
+    }
+    let mut parts = Vec::new();
+    loop {
+        let token = at(j)?;
+        let Token::Word(word) = &token.token else {
+            return None;
+        };
+        parts.push(Ident {
+            value: word.value.clone(),
+            quote_style: word.quote_style,
+            span: token.span,
+        });
+        if at(j + 1).is_some_and(|t| t.token == Token::Period) {
+            j += 2;
+        } else {
+            break;
+        }
+    }
+    let mut last = j;
+    if at(last + 1).is_some_and(|t| is_word(t, "WITH")) {
+        let no = at(last + 2).is_some_and(|t| is_word(t, "NO"));
+        let data = last + if no { 3 } else { 2 };
+        if !at(data).is_some_and(|t| is_word(t, "DATA")) {
+            return None;
+        }
+        last = data;
+    }
+    // Nothing else may follow
+    if at(last + 1).is_some_and(|t| t.token != Token::SemiColon) {
+        return None;
+    }
+    let refresh = Refresh {
+        anchor: tokens[i].span.start,
+        name: ObjectName(parts),
+        concurrently,
+    };
+    Some((refresh, last))
+}
+
+/// The REFRESH statement `stmt` is the placeholder of, if any
+pub(super) fn find<'a>(refreshes: &'a [Refresh], stmt: &Statement) -> Option<&'a Refresh> {
+    let Statement::Query(query) = stmt else {
+        return None;
+    };
+    let SetExpr::Select(select) = query.body.as_ref() else {
+        return None;
+    };
+    let start = select.select_token.0.span.start;
+    refreshes.iter().find(|r| r.anchor == start)
+}
+
+/// Check that a REFRESH names a materialized view, which a CONCURRENTLY
+/// refresh needs a unique index on
+pub(super) fn check(refresh: &Refresh, catalog: &Catalog, dialect: SqlDialect) -> Vec<Diagnostic> {
+    let name = object_name_to_qualified(&refresh.name);
+    let shown = dialect.display_name(&name);
+    let span = refresh
+        .name
+        .0
+        .last()
+        .map(|ident| Span::from_sqlparser(&ident.span));
+    let with_span = |diag: Diagnostic| match span {
+        Some(span) => diag.with_span(span),
+        None => diag,
+    };
+
+    let view = match catalog.get_view(&name) {
+        Some(view) if view.materialized => view,
+        Some(_) => {
+            return vec![with_span(
+                Diagnostic::error(
+                    DiagnosticKind::RelationKindMismatch,
+                    format!("'{}' is a view, not a materialized view", shown),
+                )
+                .with_help("Only materialized views can be refreshed"),
+            )]
+        }
+        None if catalog.table_exists(&name) => {
+            return vec![with_span(
+                Diagnostic::error(
+                    DiagnosticKind::RelationKindMismatch,
+                    format!("'{}' is a table, not a materialized view", shown),
+                )
+                .with_help("Only materialized views can be refreshed"),
+            )]
+        }
+        None => {
+            return vec![with_span(
+                Diagnostic::error(
+                    DiagnosticKind::TableNotFound,
+                    format!("Materialized view '{}' not found", shown),
+                )
+                .with_help("Check that the materialized view exists in your schema definition"),
+            )]
+        }
+    };
+
+    // Without index information in the catalog, a missing index says nothing
+    if !refresh.concurrently || !catalog.has_indexes() || has_refresh_key(view) {
+        return Vec::new();
+    }
+    vec![with_span(
+        Diagnostic::warning(
+            DiagnosticKind::RefreshWithoutUniqueIndex,
+            format!(
+                "REFRESH MATERIALIZED VIEW CONCURRENTLY needs a unique index on '{}', and it has none",
+                shown
+            ),
+        )
+        .with_help(format!(
+            "Create one, e.g. CREATE UNIQUE INDEX ON {} (...), or refresh without CONCURRENTLY",
+            shown
+        )),
+    )]
+}
+
+/// Whether a materialized view has a unique index on plain columns, as a
+/// concurrent refresh requires
+fn has_refresh_key(view: &ViewDef) -> bool {
+    view.indexes.iter().any(|index| {
+        index.unique
+            && (view.columns.is_empty()
+                || index
+                    .columns
+                    .iter()
+                    .all(|c| view.columns.iter().any(|v| v.eq_ignore_ascii_case(c))))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlparser::parser::Parser;
+
+    #[test]
+    fn test_refresh_becomes_placeholder() {
+        let sql = "REFRESH MATERIALIZED VIEW CONCURRENTLY reports.totals WITH DATA;\n\
+                   SELECT 'refresh';\nREFRESH MATERIALIZED VIEW totals";
+        let rewritten = rewrite(sql).unwrap();
+        assert_eq!(rewritten.sql.len(), sql.len());
+        assert!(rewritten.sql.starts_with("SELECT  1   "));
+        assert_eq!(rewritten.refreshes.len(), 2);
+        assert!(rewritten.refreshes[0].concurrently);
+        assert_eq!(rewritten.refreshes[0].name.to_string(), "reports.totals");
+        assert!(!rewritten.refreshes[1].concurrently);
+
+        let statements = Parser::parse_sql(&PostgreSqlDialect {}, &rewritten.sql).unwrap();
+        assert_eq!(statements.len(), 3);
+        assert!(find(&rewritten.refreshes, &statements[0]).is_some());
+        assert!(find(&rewritten.refreshes, &statements[1]).is_none());
+        assert_eq!(
+            find(&rewritten.refreshes, &statements[2]).map(|r| r.name.to_string()),
+            Some("totals".to_string())
+        );
+
+        assert!(rewrite("REFRESH MATERIALIZED VIEW totals WITH SOME DATA").is_none());
+    }
+}
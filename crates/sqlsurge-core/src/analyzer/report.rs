@@ -0,0 +1,219 @@
+//! Results of analyzing several inputs
+//!
+//! [`AnalysisReport`] keeps the diagnostics of each input (a file, or any
+//! named piece of SQL) and the aggregates embedders need to decide the
+//! outcome of a run: counts by severity and by rule code, whether any error
+//! was found, and the worst severity. The aggregates are computed on first
+//! use, and kept up to date as more inputs are added.
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::cell::OnceCell;
+use std::collections::BTreeMap;
+
+use crate::error::{Diagnostic, Severity};
+
+/// Diagnostics of one input
+#[derive(Debug, Clone, Serialize)]
+pub struct InputReport {
+    /// Name of the input, usually its path
+    pub name: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Diagnostic counts by severity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SeverityCounts {
+    pub error: usize,
+    pub warning: usize,
+    pub info: usize,
+    pub hint: usize,
+}
+
+impl SeverityCounts {
+    /// The count of `severity`
+    pub fn get(&self, severity: Severity) -> usize {
+        match severity {
+            Severity::Error => self.error,
+            Severity::Warning => self.warning,
+            Severity::Info => self.info,
+            Severity::Hint => self.hint,
+        }
+    }
+
+    fn add(&mut self, severity: Severity) {
+        let count = match severity {
+            Severity::Error => &mut self.error,
+            Severity::Warning => &mut self.warning,
+            Severity::Info => &mut self.info,
+            Severity::Hint => &mut self.hint,
+        };
+        *count += 1;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Aggregates {
+    severities: SeverityCounts,
+    rules: BTreeMap<String, usize>,
+}
+
+impl Aggregates {
+    fn add(&mut self, diagnostics: &[Diagnostic]) {
+        for diag in diagnostics {
+            self.severities.add(diag.severity);
+            *self.rules.entry(diag.code().to_string()).or_default() += 1;
+        }
+    }
+}
+
+/// Diagnostics of a batch of inputs, with aggregate counts
+///
+/// Serializes as `inputs` (name and diagnostics of each), `diagnostics`
+/// (counts by severity), `rules` (counts by rule code) and `has_errors`.
+#[derive(Debug, Clone, Default)]
+pub struct AnalysisReport {
+    inputs: Vec<InputReport>,
+    aggregates: OnceCell<Aggregates>,
+}
+
+impl AnalysisReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the diagnostics of an input
+    pub fn push(&mut self, name: impl Into<String>, diagnostics: Vec<Diagnostic>) {
+        if let Some(aggregates) = self.aggregates.get_mut() {
+            aggregates.add(&diagnostics);
+        }
+        self.inputs.push(InputReport {
+            name: name.into(),
+            diagnostics,
+        });
+    }
+
+    /// Count a diagnostic in the aggregates without keeping it, for one
+    /// handled as it was found (printed while streaming, say)
+    pub fn count(&mut self, diagnostic: &Diagnostic) {
+        self.aggregates();
+        if let Some(aggregates) = self.aggregates.get_mut() {
+            aggregates.add(std::slice::from_ref(diagnostic));
+        }
+    }
+
+    /// The inputs, in the order they were added
+    pub fn inputs(&self) -> &[InputReport] {
+        &self.inputs
+    }
+
+    /// Every diagnostic with the name of its input
+    pub fn diagnostics(&self) -> impl Iterator<Item = (&str, &Diagnostic)> {
+        self.inputs.iter().flat_map(|input| {
+            input
+                .diagnostics
+                .iter()
+                .map(move |diag| (input.name.as_str(), diag))
+        })
+    }
+
+    /// Diagnostic counts by severity
+    pub fn severity_counts(&self) -> &SeverityCounts {
+        &self.aggregates().severities
+    }
+
+    /// Diagnostic counts by rule code (`E0002`, ...)
+    pub fn rule_counts(&self) -> &BTreeMap<String, usize> {
+        &self.aggregates().rules
+    }
+
+    /// Whether any diagnostic is an error; only errors fail a check
+    pub fn has_errors(&self) -> bool {
+        self.severity_counts().error > 0
+    }
+
+    /// The most severe diagnostic level reported, if any
+    pub fn worst_severity(&self) -> Option<Severity> {
+        let counts = self.severity_counts();
+        [
+            Severity::Error,
+            Severity::Warning,
+            Severity::Info,
+            Severity::Hint,
+        ]
+        .into_iter()
+        .find(|&severity| counts.get(severity) > 0)
+    }
+
+    fn aggregates(&self) -> &Aggregates {
+        self.aggregates.get_or_init(|| {
+            let mut aggregates = Aggregates::default();
+            for input in &self.inputs {
+                aggregates.add(&input.diagnostics);
+            }
+            aggregates
+        })
+    }
+}
+
+impl Serialize for AnalysisReport {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut report = serializer.serialize_struct("AnalysisReport", 4)?;
+        report.serialize_field("inputs", &self.inputs)?;
+        report.serialize_field("diagnostics", self.severity_counts())?;
+        report.serialize_field("rules", self.rule_counts())?;
+        report.serialize_field("has_errors", &self.has_errors())?;
+        report.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::DiagnosticKind;
+
+    #[test]
+    fn test_aggregates_follow_pushed_inputs() {
+        let mut report = AnalysisReport::new();
+        assert_eq!(report.worst_severity(), None);
+        assert!(!report.has_errors());
+
+        report.push(
+            "a.sql",
+            vec![Diagnostic::warning(DiagnosticKind::NestingTooDeep, "deep")],
+        );
+        assert_eq!(report.worst_severity(), Some(Severity::Warning));
+        assert!(!report.has_errors());
+
+        // Counts are updated by a push
+        report.push(
+            "b.sql",
+            vec![
+                Diagnostic::error(DiagnosticKind::ColumnNotFound, "a"),
+                Diagnostic::error(DiagnosticKind::ColumnNotFound, "b"),
+            ],
+        );
+        assert!(report.has_errors());
+        assert_eq!(report.worst_severity(), Some(Severity::Error));
+        assert_eq!(
+            *report.severity_counts(),
+            SeverityCounts {
+                error: 2,
+                warning: 1,
+                ..SeverityCounts::default()
+            }
+        );
+        assert_eq!(report.rule_counts().get("E0002"), Some(&2));
+        assert_eq!(report.rule_counts().get("W0002"), Some(&1));
+
+        // Counted diagnostics are in the aggregates only
+        report.count(&Diagnostic::warning(DiagnosticKind::NestingTooDeep, "deep"));
+        report.push("c.sql", Vec::new());
+        assert_eq!(report.severity_counts().warning, 2);
+        assert_eq!(report.rule_counts().get("W0002"), Some(&2));
+        assert_eq!(report.diagnostics().count(), 3);
+
+        let names: Vec<&str> = report.diagnostics().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["a.sql", "b.sql", "b.sql"]);
+    }
+}
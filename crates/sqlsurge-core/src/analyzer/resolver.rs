@@ -1,30 +1,37 @@
 //! Name resolver - resolves table and column references
 
 use sqlparser::ast::{
-    Assignment, AssignmentTarget, Delete, Expr, GroupByExpr, Ident, Insert, ObjectName, Query,
-    Select, SelectItem, SetExpr, Statement, Subscript, TableFactor, TableWithJoins, Values,
+    Assignment, AssignmentTarget, BinaryOperator, Delete, Expr, Function, FunctionArgumentClause,
+    FunctionArguments, GroupByExpr, HavingBound, Ident, Insert, ObjectName, OrderByExpr, Query,
+    Select, SelectItem, SetExpr, Spanned, Statement, Subscript, TableAlias, TableFactor,
+    TableWithJoins, UnaryOperator, Value, Values,
 };
+use std::cell::RefCell;
 use std::collections::HashMap;
-
-use crate::error::{Diagnostic, DiagnosticKind, Span};
-use crate::schema::{Catalog, QualifiedName, TableDef};
-
-/// Resolved table reference in a query
-#[derive(Debug, Clone)]
-pub(super) struct TableRef {
-    /// The actual table definition
-    pub(super) table: QualifiedName,
-    /// Alias used in the query (if any)
-    ///
-    /// Note: Currently unused but reserved for future error message improvements
-    /// to show the user-specified alias in diagnostics instead of the table name.
-    #[allow(dead_code)]
-    pub(super) alias: Option<String>,
-    /// If this is a VIEW reference, the column names from the VIEW definition
-    pub(super) view_columns: Option<Vec<String>>,
-    /// If this is a derived table (subquery in FROM), the inferred column names
-    pub(super) derived_columns: Option<Vec<String>>,
-}
+use std::sync::Arc;
+
+use crate::dialect::SqlDialect;
+use crate::error::{Diagnostic, DiagnosticKind, Label, Severity, Span};
+use crate::idents::object_name_to_qualified;
+use crate::schema::{Catalog, IdentityKind, QualifiedName, TableDef};
+
+use super::aggregates::{self, AggregateChecker};
+use super::cross_table::{self, Checker};
+use super::duplicate_columns;
+use super::functions::TableFunctionCall;
+use super::intern::{Interner, Name};
+use super::join_indexes;
+use super::nesting::NestingLimit;
+use super::nullability;
+use super::ordering;
+use super::overriding::{InsertOverride, Overriding};
+use super::projection::{
+    alias_column_names, apply_alias_columns, item_column_name, merged_columns, visible_name,
+    ProjectionExpander,
+};
+use super::sargability;
+use super::scope::{Scope, TableRef};
+use super::suggest::{self, DEFAULT_SUGGESTION_BUDGET};
 
 /// CTE (Common Table Expression) definition
 #[derive(Debug, Clone)]
@@ -42,14 +49,75 @@ pub(super) struct CteDefinition {
 /// Name resolver for SQL queries
 pub struct NameResolver<'a> {
     catalog: &'a Catalog,
+    dialect: SqlDialect,
     /// Current scope's table references (alias/name -> TableRef)
-    pub(super) tables: HashMap<String, TableRef>,
+    pub(super) tables: Scope,
     /// CTEs available in current scope (name -> CteDefinition)
     pub(super) ctes: HashMap<String, CteDefinition>,
     /// SELECT aliases visible in ORDER BY (set before resolving ORDER BY)
     select_aliases: Vec<String>,
+    /// Names registered by the FROM clause being resolved (outer query tables
+    /// in `tables` may be shadowed, these may not be repeated)
+    from_names: Vec<Name>,
+    /// Scope names on the nullable side of an outer join in the current SELECT
+    outer_joined: Vec<String>,
+    /// Columns merged by USING / NATURAL joins in the current SELECT, with the
+    /// scope names whose copies they merge (unqualified references to them
+    /// aren't ambiguous)
+    merged_columns: Vec<(String, Vec<String>)>,
+    /// Catalog tables and views the statement reads or writes
+    relations: Vec<QualifiedName>,
     /// Collected diagnostics
     diagnostics: Vec<Diagnostic>,
+    /// Expression and subquery nesting depth
+    nesting: NestingLimit,
+    /// Shared copies of the names in scope
+    names: Interner,
+    /// Unknown table qualifiers reported in the statement, with the index of
+    /// their diagnostic; later references to the same name become its labels
+    unknown_qualifiers: HashMap<String, usize>,
+    /// Clause of the current SELECT (or UPDATE/DELETE) being resolved
+    clause: Clause,
+    /// Column names compared per "did you mean" lookup
+    suggestion_budget: usize,
+    /// Column suggestions made in the statement, by table and lowercased
+    /// unknown name: a repeated typo is looked up once
+    suggestions: RefCell<HashMap<(QualifiedName, String), Option<String>>>,
+    /// Whether tables missing from the catalog are taken to exist
+    schemaless: bool,
+    /// OVERRIDING clauses removed from the INSERT statements before parsing
+    insert_overrides: &'a [InsertOverride],
+    /// Dummy identifier substituted for template placeholders in name
+    /// positions, a relation of any columns
+    placeholder_relation: Option<&'a str>,
+}
+
+/// Clause whose expressions are being resolved
+///
+/// Some expressions are only allowed in some clauses, e.g. window functions
+/// in the select list and ORDER BY. Subqueries start over in `Other`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Clause {
+    /// Anything not listed below (ORDER BY, VALUES, SET, ...)
+    Other,
+    Select,
+    Where,
+    JoinOn,
+    GroupBy,
+    Having,
+}
+
+impl Clause {
+    /// The clause's name, if window functions are not allowed in it
+    fn forbids_window_functions(self) -> Option<&'static str> {
+        match self {
+            Clause::Where => Some("WHERE"),
+            Clause::JoinOn => Some("JOIN conditions"),
+            Clause::GroupBy => Some("GROUP BY"),
+            Clause::Having => Some("HAVING"),
+            Clause::Other | Clause::Select => None,
+        }
+    }
 }
 
 impl<'a> NameResolver<'a> {
@@ -57,22 +125,96 @@ impl<'a> NameResolver<'a> {
     ///
     /// The resolver will use the catalog to validate table and column references.
     pub fn new(catalog: &'a Catalog) -> Self {
+        Self::with_dialect(catalog, SqlDialect::default())
+    }
+
+    /// Create a new name resolver for the given catalog and SQL dialect
+    pub fn with_dialect(catalog: &'a Catalog, dialect: SqlDialect) -> Self {
         Self {
             catalog,
-            tables: HashMap::new(),
+            dialect,
+            tables: Scope::default(),
             select_aliases: Vec::new(),
+            from_names: Vec::new(),
+            outer_joined: Vec::new(),
+            merged_columns: Vec::new(),
             ctes: HashMap::new(),
+            relations: Vec::new(),
             diagnostics: Vec::new(),
+            nesting: NestingLimit::default(),
+            names: Interner::default(),
+            unknown_qualifiers: HashMap::new(),
+            clause: Clause::Other,
+            suggestion_budget: DEFAULT_SUGGESTION_BUDGET,
+            suggestions: RefCell::default(),
+            schemaless: false,
+            insert_overrides: &[],
+            placeholder_relation: None,
         }
     }
 
+    /// Set the maximum nesting depth of expressions and subqueries
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.nesting = NestingLimit::new(max_depth);
+        self
+    }
+
+    /// Set how many column names a "did you mean" lookup compares
+    pub fn with_suggestion_budget(mut self, budget: usize) -> Self {
+        self.suggestion_budget = budget;
+        self
+    }
+
+    /// Take tables missing from the catalog to exist, with any columns
+    ///
+    /// For checking queries without a schema: the relations are still
+    /// registered, so rules about aliases and clauses keep working.
+    pub fn with_schemaless(mut self, schemaless: bool) -> Self {
+        self.schemaless = schemaless;
+        self
+    }
+
+    /// Apply the OVERRIDING clauses removed from the input's INSERT
+    /// statements (see `overriding::rewrite`) to their identity columns
+    pub(super) fn with_insert_overrides(mut self, overrides: &'a [InsertOverride]) -> Self {
+        self.insert_overrides = overrides;
+        self
+    }
+
+    /// Take tables named `identifier`, the dummy substituted for template
+    /// placeholders (see `placeholders::rewrite`), to exist with any columns
+    pub(super) fn with_placeholder_relation(mut self, identifier: Option<&'a str>) -> Self {
+        self.placeholder_relation = identifier;
+        self
+    }
+
+    /// Whether `name` is the dummy relation of a template placeholder
+    fn is_placeholder_relation(&self, name: &QualifiedName) -> bool {
+        self.placeholder_relation
+            .is_some_and(|identifier| name.name == identifier)
+    }
+
     /// Resolve names in a statement
     ///
     /// Validates all table and column references in the statement against the catalog.
     /// Diagnostics are collected internally and can be retrieved with `into_diagnostics()`.
     pub fn resolve_statement(&mut self, stmt: &Statement) {
         match stmt {
-            Statement::Query(query) => self.resolve_query(query),
+            Statement::Query(query) => {
+                let start = self.diagnostics.len();
+                self.resolve_query(query);
+                // Names that don't resolve make the output columns unreliable
+                if !self.diagnostics[start..]
+                    .iter()
+                    .any(|d| d.severity == Severity::Error)
+                {
+                    self.diagnostics.extend(duplicate_columns::check_query(
+                        self.catalog,
+                        &self.ctes,
+                        query,
+                    ));
+                }
+            }
             Statement::Insert(insert) => {
                 self.resolve_insert(insert);
             }
@@ -81,71 +223,202 @@ impl<'a> NameResolver<'a> {
                 assignments,
                 from,
                 selection,
+                returning,
                 ..
             } => {
                 self.resolve_update(table, assignments, from.as_ref(), selection.as_ref());
+                self.resolve_returning(returning.as_deref());
             }
             Statement::Delete(delete) => {
                 self.resolve_delete(delete);
+                self.resolve_returning(delete.returning.as_deref());
+            }
+            Statement::CreateTable(create) => {
+                if let Some(query) = &create.query {
+                    self.resolve_query(query);
+                }
             }
             _ => {}
         }
     }
 
+    /// The OVERRIDING clause of an INSERT, found by the position of its
+    /// target table name or alias
+    fn insert_overriding(&self, insert: &Insert) -> Option<Overriding> {
+        let target = insert
+            .table_alias
+            .as_ref()
+            .or_else(|| insert.table_name.0.last())?;
+        self.insert_overrides
+            .iter()
+            .find(|o| o.anchor == target.span.end)
+            .map(|o| o.overriding)
+    }
+
     /// Resolve names in an INSERT statement
     fn resolve_insert(&mut self, insert: &Insert) {
         let table_name = object_name_to_qualified(&insert.table_name);
+        if self.check_materialized_view_write(&insert.table_name) {
+            return;
+        }
 
         // Check if table exists
         let table_def = if let Some(def) = self.catalog.get_table(&table_name) {
             def
+        } else if self.is_placeholder_relation(&table_name) {
+            return;
         } else {
             let table_span = insert
                 .table_name
                 .0
                 .last()
                 .map(|id| Span::from_sqlparser(&id.span));
-            let mut diag = Diagnostic::error(
-                DiagnosticKind::TableNotFound,
-                format!("Table '{}' not found", table_name),
-            )
-            .with_help("Check that the table exists in your schema definition");
-            if let Some(span) = table_span {
-                diag = diag.with_span(span);
-            }
-            self.diagnostics.push(diag);
+            self.report_table_not_found(&table_name, table_span);
             return;
         };
+        self.add_relation(&table_name);
+        if let Some(ident) = insert.table_name.0.last() {
+            self.check_deprecated(table_def, None, Span::from_sqlparser(&ident.span));
+        }
 
         // Check if specified columns exist
         let specified_columns: Vec<&Ident> = insert.columns.iter().collect();
         for col_ident in &specified_columns {
-            if !table_def.column_exists(&col_ident.value) {
-                let similar = find_similar_column(table_def, &col_ident.value);
+            if table_def.column_exists(&col_ident.value) {
+                self.check_deprecated(
+                    table_def,
+                    Some(&col_ident.value),
+                    Span::from_sqlparser(&col_ident.span),
+                );
+            } else {
+                let similar = self.column_suggestion(table_def, &col_ident.value);
                 let mut diag = Diagnostic::error(
                     DiagnosticKind::ColumnNotFound,
                     format!(
                         "Column '{}' not found in table '{}'",
-                        col_ident.value, table_name
+                        col_ident,
+                        self.dialect.display_name(&table_name)
                     ),
                 )
                 .with_span(Span::from_sqlparser(&col_ident.span));
                 if let Some(suggestion) = similar {
-                    diag = diag.with_help(format!("Did you mean '{}'?", suggestion));
+                    diag = diag.with_help(suggestion);
                 }
-                self.diagnostics.push(diag);
+                self.diagnostics
+                    .push(with_table_context(diag, table_def, self.dialect));
             }
         }
 
-        // Check column count vs value count
+        // Generated columns cannot be assigned (only DEFAULT is accepted),
+        // nor can GENERATED ALWAYS identity columns without OVERRIDING
+        let overriding = self.insert_overriding(insert);
         if let Some(source) = &insert.source {
             if let SetExpr::Values(Values { rows, .. }) = source.body.as_ref() {
-                let expected_count = if specified_columns.is_empty() {
-                    table_def.columns.len()
+                let targets: Vec<(&str, Option<Span>)> = if specified_columns.is_empty() {
+                    table_def
+                        .column_names()
+                        .into_iter()
+                        .map(|name| (name, None))
+                        .collect()
                 } else {
-                    specified_columns.len()
+                    specified_columns
+                        .iter()
+                        .map(|c| (c.value.as_str(), Some(Span::from_sqlparser(&c.span))))
+                        .collect()
                 };
+                for (idx, (col_name, col_span)) in targets.into_iter().enumerate() {
+                    let Some(col_def) = table_def.get_column(col_name) else {
+                        continue;
+                    };
+                    if col_def.requires_value() {
+                        let marker = rows
+                            .iter()
+                            .filter_map(|row| row.get(idx))
+                            .find(|value| is_default_keyword(value));
+                        if let Some(marker) = marker {
+                            self.diagnostics.push(
+                                null_violation(&table_name, &col_def.name)
+                                    .with_span(Span::from_sqlparser(&marker.span())),
+                            );
+                        }
+                    }
+                    if col_def.generated.is_none() && col_def.identity.is_none() {
+                        continue;
+                    }
+                    let assigned = rows
+                        .iter()
+                        .filter_map(|row| row.get(idx))
+                        .find(|value| !is_default_keyword(value));
+                    let Some(value) = assigned else {
+                        continue;
+                    };
+                    let span = col_span.unwrap_or_else(|| Span::from_sqlparser(&value.span()));
+                    let diag = match (&col_def.identity, overriding) {
+                        (None, _) => generated_column_assignment(&table_name, &col_def.name),
+                        (Some(_), Some(Overriding::UserValue)) => {
+                            ignored_identity_value(&table_name, &col_def.name)
+                        }
+                        (Some(IdentityKind::Always), None) => {
+                            identity_column_assignment(&table_name, &col_def.name)
+                        }
+                        (Some(_), _) => continue,
+                    };
+                    self.diagnostics.push(diag.with_span(span));
+                }
+            }
+        }
+
+        // DEFAULT VALUES: every column gets its default, so there is no count
+        // to check, but NOT NULL columns without one would receive NULL
+        if insert.source.is_none() {
+            let table_span = insert
+                .table_name
+                .0
+                .last()
+                .map(|id| Span::from_sqlparser(&id.span));
+            for col_def in table_def.ordered_columns() {
+                if col_def.requires_value() {
+                    let mut diag = null_violation(&table_name, &col_def.name);
+                    if let Some(span) = table_span {
+                        diag = diag.with_span(span);
+                    }
+                    self.diagnostics.push(diag);
+                }
+            }
+        }
+
+        // INSERT ... SELECT: values that may be NULL for NOT NULL columns
+        if let Some(source) = &insert.source {
+            let table_span = insert
+                .table_name
+                .0
+                .last()
+                .map(|id| Span::from_sqlparser(&id.span));
+            self.diagnostics.extend(nullability::check_insert(
+                self.catalog,
+                &table_name,
+                table_span,
+                table_def,
+                &specified_columns,
+                source,
+            ));
+        }
 
+        // Check column count vs value count
+        let expected_count = if specified_columns.is_empty() {
+            table_def.columns.len()
+        } else {
+            specified_columns.len()
+        };
+        // A table of no columns (`CREATE TABLE t ()`) only takes DEFAULT VALUES
+        let no_columns_help = (expected_count == 0).then(|| {
+            format!(
+                "Table '{}' has no columns; insert rows into it with INSERT INTO {} DEFAULT VALUES",
+                table_name, insert.table_name
+            )
+        });
+        if let Some(source) = &insert.source {
+            if let SetExpr::Values(Values { rows, .. }) = source.body.as_ref() {
                 for row in rows {
                     if row.len() != expected_count {
                         self.diagnostics.push(
@@ -157,7 +430,9 @@ impl<'a> NameResolver<'a> {
                                     expected_count
                                 ),
                             )
-                            .with_help(if specified_columns.is_empty() {
+                            .with_help(if let Some(help) = &no_columns_help {
+                                help.clone()
+                            } else if specified_columns.is_empty() {
                                 format!(
                                     "Table '{}' has {} columns. Specify columns explicitly or provide {} values",
                                     table_name, expected_count, expected_count
@@ -173,9 +448,153 @@ impl<'a> NameResolver<'a> {
                         self.resolve_expr(expr);
                     }
                 }
+
+                // Without a column list, the key is anchored on the table name
+                let table_span = insert
+                    .table_name
+                    .0
+                    .last()
+                    .map(|id| Span::from_sqlparser(&id.span));
+                let columns: Vec<(&str, Option<Span>)> = if specified_columns.is_empty() {
+                    table_def
+                        .column_names()
+                        .into_iter()
+                        .map(|name| (name, table_span))
+                        .collect()
+                } else {
+                    specified_columns
+                        .iter()
+                        .map(|c| (c.value.as_str(), Some(Span::from_sqlparser(&c.span))))
+                        .collect()
+                };
+                self.check_duplicate_insert_keys(table_def, &columns, rows);
             } else {
                 // INSERT ... SELECT - resolve the subquery
                 self.resolve_set_expr(&source.body);
+                let returned =
+                    ProjectionExpander::new(self.catalog, &self.ctes).query_columns(source);
+                if let Some(returned) = returned.filter(|c| c.len() != expected_count) {
+                    self.diagnostics.push(
+                        Diagnostic::error(
+                            DiagnosticKind::ColumnCountMismatch,
+                            format!(
+                                "INSERT query returns {} column(s) but {} column(s) were specified",
+                                returned.len(),
+                                expected_count
+                            ),
+                        )
+                        .with_span(Span::from_sqlparser(&source.body.span()))
+                        .with_help(if let Some(help) = &no_columns_help {
+                            help.clone()
+                        } else if specified_columns.is_empty() {
+                            format!(
+                                "Table '{}' has {} columns. Specify columns explicitly or select {} columns",
+                                table_name, expected_count, expected_count
+                            )
+                        } else {
+                            format!("Select {} column(s) to match the column list", expected_count)
+                        }),
+                    );
+                }
+            }
+        }
+
+        // RETURNING sees the target table alone
+        if let Some(returning) = &insert.returning {
+            let saved_tables = std::mem::take(&mut self.tables);
+            let saved_from_names = std::mem::take(&mut self.from_names);
+            let table = self.names.object_name(&insert.table_name);
+            let alias = insert
+                .table_alias
+                .as_ref()
+                .map(|a| self.names.intern(&a.value));
+            let name = alias
+                .clone()
+                .unwrap_or_else(|| self.names.intern(&table.name));
+            let span = insert
+                .table_alias
+                .as_ref()
+                .or_else(|| insert.table_name.0.last())
+                .map(|ident| Span::from_sqlparser(&ident.span));
+            self.register_table(
+                name,
+                TableRef {
+                    table,
+                    alias,
+                    view_columns: None,
+                    derived_columns: None,
+                    cte: false,
+                    span,
+                },
+            );
+            self.resolve_returning(Some(returning));
+            self.tables = saved_tables;
+            self.from_names = saved_from_names;
+        }
+    }
+
+    /// Resolve the items of a RETURNING clause in the statement's scope
+    fn resolve_returning(&mut self, returning: Option<&[SelectItem]>) {
+        for item in returning.into_iter().flatten() {
+            self.resolve_select_item(item);
+        }
+    }
+
+    /// Warn when a multi-row VALUES list repeats a primary key or unique value
+    ///
+    /// Only keys fully covered by the column list are checked, and only rows
+    /// whose key values are all literals take part. The warning points at the
+    /// key's first column in the column list, since literals carry no span.
+    fn check_duplicate_insert_keys(
+        &mut self,
+        table_def: &TableDef,
+        columns: &[(&str, Option<Span>)],
+        rows: &[Vec<Expr>],
+    ) {
+        if rows.len() < 2 {
+            return;
+        }
+
+        for key in table_def.unique_keys() {
+            let Some(indices) = key
+                .iter()
+                .map(|k| columns.iter().position(|(c, _)| c.eq_ignore_ascii_case(k)))
+                .collect::<Option<Vec<usize>>>()
+            else {
+                continue;
+            };
+
+            let mut seen: HashMap<Vec<String>, usize> = HashMap::new();
+            for (row_idx, row) in rows.iter().enumerate() {
+                let Some(values) = indices
+                    .iter()
+                    .map(|&i| row.get(i).and_then(literal_key_value))
+                    .collect::<Option<Vec<String>>>()
+                else {
+                    continue;
+                };
+
+                if let Some(first_row) = seen.get(&values) {
+                    let span = columns[indices[0]].1;
+                    let mut diag = Diagnostic::warning(
+                        DiagnosticKind::DuplicateInsertKey,
+                        format!(
+                            "Row {} repeats key ({}) = ({}) from row {} of INSERT into '{}'",
+                            row_idx + 1,
+                            key.join(", "),
+                            values.join(", "),
+                            first_row + 1,
+                            table_def.name
+                        ),
+                    )
+                    .with_help("This INSERT will fail with a unique constraint violation");
+                    if let Some(span) = span {
+                        diag = diag.with_span(span);
+                    }
+                    self.diagnostics.push(diag);
+                } else {
+                    seen.insert(values, row_idx);
+                }
             }
         }
     }
@@ -190,6 +609,9 @@ impl<'a> NameResolver<'a> {
     ) {
         // Resolve and register the table
         self.resolve_table_with_joins(table);
+        if let TableFactor::Table { name, .. } = &table.relation {
+            self.check_materialized_view_write(name);
+        }
 
         // Resolve FROM clause (PostgreSQL: UPDATE ... FROM ...)
         if let Some(from_table) = from {
@@ -204,34 +626,25 @@ impl<'a> NameResolver<'a> {
         for assignment in assignments {
             match &assignment.target {
                 AssignmentTarget::ColumnName(col_name) => {
-                    // Get the column identifier
-                    if let Some(col_ident) = col_name.0.last() {
-                        if let Some(def) = table_def {
-                            if !def.column_exists(&col_ident.value) {
-                                let similar = find_similar_column(def, &col_ident.value);
-                                let mut diag = Diagnostic::error(
-                                    DiagnosticKind::ColumnNotFound,
-                                    format!(
-                                        "Column '{}' not found in table '{}'",
-                                        col_ident.value,
-                                        table_name
-                                            .as_ref()
-                                            .map(|n| n.to_string())
-                                            .unwrap_or_default()
-                                    ),
-                                )
-                                .with_span(Span::from_sqlparser(&col_ident.span));
-                                if let Some(suggestion) = similar {
-                                    diag =
-                                        diag.with_help(format!("Did you mean '{}'?", suggestion));
-                                }
-                                self.diagnostics.push(diag);
+                    if let (Some(col_ident), Some(def)) = (col_name.0.last(), table_def) {
+                        self.check_assigned_column(def, col_ident, Some(&assignment.value));
+                    }
+                }
+                AssignmentTarget::Tuple(columns) => {
+                    // (a, b) = (1, 2) or (a, b) = (SELECT x, y ...)
+                    let values = match &assignment.value {
+                        Expr::Tuple(values) => Some(values.as_slice()),
+                        _ => None,
+                    };
+                    if let Some(def) = table_def {
+                        for (i, col_name) in columns.iter().enumerate() {
+                            if let Some(col_ident) = col_name.0.last() {
+                                let value = values.and_then(|v| v.get(i));
+                                self.check_assigned_column(def, col_ident, value);
                             }
                         }
                     }
-                }
-                AssignmentTarget::Tuple(_) => {
-                    // Tuple assignment (col1, col2) = (val1, val2) - not commonly used
+                    self.check_tuple_assignment_arity(columns, &assignment.value);
                 }
             }
 
@@ -241,10 +654,114 @@ impl<'a> NameResolver<'a> {
 
         // Resolve WHERE clause
         if let Some(where_expr) = selection {
-            self.resolve_expr(where_expr);
+            self.resolve_in_clause(Clause::Where, where_expr);
+        }
+        let from_names = self.from_names.clone();
+        self.check_sargability(&from_names, selection);
+    }
+
+    /// Check a column an UPDATE assigns `value` to: it must exist, not be
+    /// generated (unless set to DEFAULT), and may be deprecated
+    ///
+    /// `value` is `None` when the column takes its value from a subquery.
+    fn check_assigned_column(&mut self, def: &TableDef, col_ident: &Ident, value: Option<&Expr>) {
+        let span = Span::from_sqlparser(&col_ident.span);
+        if !def.column_exists(&col_ident.value) {
+            let similar = self.column_suggestion(def, &col_ident.value);
+            let mut diag = Diagnostic::error(
+                DiagnosticKind::ColumnNotFound,
+                format!(
+                    "Column '{}' not found in table '{}'",
+                    col_ident,
+                    self.dialect.display_name(&def.name)
+                ),
+            )
+            .with_span(span);
+            if let Some(suggestion) = similar {
+                diag = diag.with_help(suggestion);
+            }
+            self.diagnostics
+                .push(with_table_context(diag, def, self.dialect));
+        } else if def
+            .get_column(&col_ident.value)
+            .is_some_and(|c| c.generated.is_some())
+            && !value.is_some_and(is_default_keyword)
+        {
+            self.diagnostics
+                .push(generated_column_assignment(&def.name, &col_ident.value).with_span(span));
+        } else {
+            self.check_deprecated(def, Some(&col_ident.value), span);
         }
     }
 
+    /// Report a tuple assignment `(a, b) = ...` whose right-hand side
+    /// doesn't provide one value per column (E0005)
+    ///
+    /// A tuple or `ROW(...)` provides one value per element, a subquery one
+    /// per output column, and any other expression (parenthesized or not)
+    /// a single value.
+    fn check_tuple_assignment_arity(&mut self, targets: &[ObjectName], value: &Expr) {
+        let columns = targets.len();
+        let values = match value {
+            Expr::Tuple(values) => Some(values.len()),
+            Expr::Function(func)
+                if func.name.0.len() == 1 && func.name.0[0].value.eq_ignore_ascii_case("row") =>
+            {
+                match &func.args {
+                    FunctionArguments::List(list) => Some(list.args.len()),
+                    _ => None,
+                }
+            }
+            Expr::Subquery(_) => None,
+            _ => Some(1),
+        };
+        let (message, help) = match (value, values) {
+            (_, Some(values)) if values != columns => (
+                format!(
+                    "UPDATE assigns {} value(s) to {} column(s)",
+                    values, columns
+                ),
+                format!(
+                    "Provide {} values, one for each column in the list",
+                    columns
+                ),
+            ),
+            (Expr::Subquery(query), _) => {
+                let returned =
+                    ProjectionExpander::new(self.catalog, &self.ctes).query_columns(query);
+                match returned {
+                    Some(returned) if returned.len() != columns => (
+                        format!(
+                            "UPDATE subquery returns {} column(s) but {} column(s) were specified",
+                            returned.len(),
+                            columns
+                        ),
+                        format!(
+                            "Select {} columns, one for each column in the list",
+                            columns
+                        ),
+                    ),
+                    _ => return,
+                }
+            }
+            _ => return,
+        };
+        // A tuple of literals has no span; the column list does
+        let span = match value.span() {
+            span if span == sqlparser::tokenizer::Span::empty() => targets
+                .iter()
+                .map(|name| name.span())
+                .reduce(|a, b| a.union(&b))
+                .unwrap_or(span),
+            span => span,
+        };
+        self.diagnostics.push(
+            Diagnostic::error(DiagnosticKind::ColumnCountMismatch, message)
+                .with_span(Span::from_sqlparser(&span))
+                .with_help(help),
+        );
+    }
+
     /// Resolve names in a DELETE statement
     fn resolve_delete(&mut self, delete: &Delete) {
         // Get the table from the FROM clause
@@ -256,6 +773,9 @@ impl<'a> NameResolver<'a> {
         // Resolve and register tables from FROM clause
         for table in tables {
             self.resolve_table_with_joins(table);
+            if let TableFactor::Table { name, .. } = &table.relation {
+                self.check_materialized_view_write(name);
+            }
         }
 
         // Resolve USING clause (PostgreSQL: DELETE ... USING ...)
@@ -267,12 +787,94 @@ impl<'a> NameResolver<'a> {
 
         // Resolve WHERE clause
         if let Some(where_expr) = &delete.selection {
-            self.resolve_expr(where_expr);
+            self.resolve_in_clause(Clause::Where, where_expr);
+        }
+        let from_names = self.from_names.clone();
+        self.check_sargability(&from_names, delete.selection.as_ref());
+    }
+
+    /// Report an INSERT, UPDATE or DELETE target that is a materialized view
+    /// (E0019), returning whether it is one
+    fn check_materialized_view_write(&mut self, name: &ObjectName) -> bool {
+        let qualified = object_name_to_qualified(name);
+        if !self
+            .catalog
+            .get_view(&qualified)
+            .is_some_and(|view| view.materialized)
+        {
+            return false;
+        }
+        let mut diag = Diagnostic::error(
+            DiagnosticKind::RelationKindMismatch,
+            format!(
+                "Cannot change materialized view '{}'",
+                self.dialect.display_name(&qualified)
+            ),
+        )
+        .with_help(
+            "Materialized views are read-only: change the tables it is computed from, then run REFRESH MATERIALIZED VIEW",
+        );
+        if let Some(ident) = name.0.last() {
+            diag = diag.with_span(Span::from_sqlparser(&ident.span));
         }
+        self.diagnostics.push(diag);
+        true
+    }
+
+    /// Report a window function in a clause that doesn't allow them (E0018)
+    ///
+    /// Window functions are computed after WHERE, GROUP BY and HAVING, over
+    /// their result, so only the select list and ORDER BY may use them.
+    fn check_window_placement(&mut self, func: &Function) {
+        let Some(clause) = self.clause.forbids_window_functions() else {
+            return;
+        };
+        let help = match self.clause {
+            Clause::GroupBy => {
+                "Compute the window function in a subquery or CTE, then group by its result \
+                 in the outer query"
+            }
+            _ => {
+                "Compute the window function in a subquery or CTE, then filter on its result \
+                 in the outer query: WITH ranked AS (SELECT ..., row_number() OVER (...) AS rn \
+                 FROM ...) SELECT ... FROM ranked WHERE rn = 1"
+            }
+        };
+        self.diagnostics.push(
+            Diagnostic::error(
+                DiagnosticKind::MisplacedWindowFunction,
+                format!("Window functions are not allowed in {}", clause),
+            )
+            .with_span(Span::from_sqlparser(&func.span()))
+            .with_help(help),
+        );
+    }
+
+    /// Report comparisons that can't use an index on their column (W0012, W0013)
+    fn check_sargability(&mut self, from_names: &[Name], selection: Option<&Expr>) {
+        let checker = Checker::new(self.catalog, &self.tables, from_names);
+        self.diagnostics
+            .extend(sargability::check_where(&checker, self.dialect, selection));
     }
 
     /// Resolve names in a query
     fn resolve_query(&mut self, query: &Query) {
+        if !self.nesting.enter() {
+            return;
+        }
+        // CTEs are visible in the query and everything nested in it (derived
+        // tables clear the table scope, never the CTEs), but not outside it
+        let saved_ctes = query.with.as_ref().map(|_| self.ctes.clone());
+        let saved_clause = std::mem::replace(&mut self.clause, Clause::Other);
+        self.resolve_query_inner(query);
+        self.clause = saved_clause;
+        if let Some(ctes) = saved_ctes {
+            self.ctes = ctes;
+        }
+        self.nesting.leave();
+    }
+
+    fn resolve_query_inner(&mut self, query: &Query) {
         // Handle CTEs (WITH clause)
         if let Some(with) = &query.with {
             let is_recursive = with.recursive;
@@ -282,14 +884,15 @@ impl<'a> NameResolver<'a> {
 
                 // For recursive CTEs, infer columns and register the CTE *before*
                 // resolving the body, so the recursive part can reference itself.
-                let columns = if !cte.alias.columns.is_empty() {
-                    cte.alias
-                        .columns
-                        .iter()
-                        .map(|c| c.name.value.clone())
-                        .collect()
-                } else {
-                    self.infer_cte_columns(&cte.query.body)
+                let columns = match ProjectionExpander::new(self.catalog, &self.ctes)
+                    .query_columns(&cte.query)
+                {
+                    Some(inferred) => {
+                        self.check_alias_count(&cte.alias, inferred.len(), "CTE");
+                        apply_alias_columns(Some(&cte.alias), inferred)
+                    }
+                    None if !cte.alias.columns.is_empty() => alias_column_names(&cte.alias),
+                    None => self.infer_cte_columns(&cte.query.body),
                 };
 
                 if is_recursive {
@@ -325,37 +928,161 @@ impl<'a> NameResolver<'a> {
 
         // Resolve the main query body
         self.resolve_set_expr(&query.body);
+        self.diagnostics.extend(ordering::check_unordered_limit(
+            self.catalog,
+            &self.ctes,
+            query,
+        ));
 
         // Resolve ORDER BY clause (with SELECT aliases in scope)
         if let Some(order_by) = &query.order_by {
+            let output_columns =
+                ProjectionExpander::new(self.catalog, &self.ctes).set_expr_columns(&query.body);
+            if matches!(*query.body, SetExpr::SetOperation { .. }) {
+                self.resolve_set_operation_order_by(&query.body, &order_by.exprs, output_columns);
+                return;
+            }
+            if let SetExpr::Select(select) = query.body.as_ref() {
+                self.diagnostics.extend(ordering::check_distinct_order_by(
+                    self.dialect,
+                    select,
+                    &order_by.exprs,
+                    output_columns.as_deref(),
+                ));
+            }
+
             // Collect SELECT aliases so ORDER BY can reference them
             let saved_aliases = std::mem::take(&mut self.select_aliases);
             self.select_aliases = self.collect_select_aliases(&query.body);
             for ob in &order_by.exprs {
-                self.resolve_expr(&ob.expr);
+                match (order_by_ordinal(&ob.expr), &output_columns) {
+                    (Some(position), Some(columns)) => {
+                        let span = order_by_span(ob, &query.body);
+                        self.check_order_by_ordinal(position, columns.len(), span);
+                    }
+                    (Some(_), None) => {}
+                    (None, _) => self.resolve_expr(&ob.expr),
+                }
             }
             self.select_aliases = saved_aliases;
         }
     }
 
-    /// Collect aliases from SELECT projection for use in ORDER BY resolution
-    fn collect_select_aliases(&self, set_expr: &SetExpr) -> Vec<String> {
-        let mut aliases = Vec::new();
-        if let SetExpr::Select(select) = set_expr {
-            for item in &select.projection {
-                match item {
-                    SelectItem::ExprWithAlias { alias, .. } => {
-                        aliases.push(alias.value.clone());
+    /// Resolve ORDER BY of a UNION/INTERSECT/EXCEPT query
+    ///
+    /// ORDER BY binds to the output columns of the set operation, which are
+    /// named by its left arm, not to the tables of either arm.
+    fn resolve_set_operation_order_by(
+        &mut self,
+        body: &SetExpr,
+        exprs: &[OrderByExpr],
+        output_columns: Option<Vec<String>>,
+    ) {
+        // Output columns can't be determined (e.g. wildcard over an unknown relation)
+        let Some(columns) = output_columns else {
+            return;
+        };
+
+        let saved_tables = std::mem::take(&mut self.tables);
+        let saved_aliases = std::mem::replace(&mut self.select_aliases, columns.clone());
+        for ob in exprs {
+            match &ob.expr {
+                Expr::Identifier(ident)
+                    if !columns.iter().any(|c| c.eq_ignore_ascii_case(&ident.value)) =>
+                {
+                    self.diagnostics.push(
+                        Diagnostic::error(
+                            DiagnosticKind::ColumnNotFound,
+                            format!(
+                                "Column '{}' is not an output column of the set operation",
+                                ident.value
+                            ),
+                        )
+                        .with_span(Span::from_sqlparser(&ident.span))
+                        .with_help(format!(
+                            "ORDER BY on UNION/INTERSECT/EXCEPT can only use output columns ({}) or their positions",
+                            columns.join(", ")
+                        )),
+                    );
+                }
+                Expr::Identifier(_) => {}
+                Expr::CompoundIdentifier(idents) => {
+                    let name = idents.last().map(|i| i.value.as_str()).unwrap_or_default();
+                    self.diagnostics.push(
+                        Diagnostic::error(
+                            DiagnosticKind::ColumnNotFound,
+                            format!(
+                                "Qualified column '{}' cannot be used in ORDER BY of a set operation",
+                                idents
+                                    .iter()
+                                    .map(|i| i.value.as_str())
+                                    .collect::<Vec<_>>()
+                                    .join(".")
+                            ),
+                        )
+                        .with_span(Span::from_sqlparser(&ob.expr.span()))
+                        .with_help(format!(
+                            "Refer to the output column by name ('{}') or position",
+                            name
+                        )),
+                    );
+                }
+                expr => match order_by_ordinal(expr) {
+                    Some(position) => {
+                        let span = order_by_span(ob, body);
+                        self.check_order_by_ordinal(position, columns.len(), span);
                     }
-                    SelectItem::UnnamedExpr(Expr::Identifier(ident)) => {
-                        // Column name also acts as implicit alias
-                        aliases.push(ident.value.clone());
+                    // PostgreSQL only sorts a set operation by its output
+                    // columns, not by expressions over them
+                    None if self.dialect == SqlDialect::PostgreSQL => {
+                        self.diagnostics.push(
+                            Diagnostic::error(
+                                DiagnosticKind::ColumnNotFound,
+                                format!(
+                                    "Expression '{}' cannot be used in ORDER BY of a set operation",
+                                    expr
+                                ),
+                            )
+                            .with_span(order_by_span(ob, body))
+                            .with_help(format!(
+                                "Order by an output column ({}) or its position, or wrap the set operation in a subquery",
+                                columns.join(", ")
+                            )),
+                        );
                     }
-                    _ => {}
-                }
+                    None => self.resolve_expr(expr),
+                },
             }
         }
-        aliases
+        self.select_aliases = saved_aliases;
+        self.tables = saved_tables;
+    }
+
+    /// Check an `ORDER BY <position>` reference against the number of output columns
+    fn check_order_by_ordinal(&mut self, position: u64, column_count: usize, span: Span) {
+        if position >= 1 && position as usize <= column_count {
+            return;
+        }
+        self.diagnostics.push(
+            Diagnostic::error(
+                DiagnosticKind::ColumnNotFound,
+                format!("ORDER BY position {} is not in select list", position),
+            )
+            .with_span(span)
+            .with_help(format!(
+                "The query has {} output column{}",
+                column_count,
+                if column_count == 1 { "" } else { "s" }
+            )),
+        );
+    }
+
+    /// Collect aliases from SELECT projection for use in ORDER BY resolution
+    fn collect_select_aliases(&self, set_expr: &SetExpr) -> Vec<String> {
+        match set_expr {
+            SetExpr::Select(select) => projection_aliases(select),
+            _ => Vec::new(),
+        }
     }
 
     /// Infer column names from a SELECT body
@@ -369,30 +1096,12 @@ impl<'a> NameResolver<'a> {
 
         if let SetExpr::Select(select) = set_expr {
             for (idx, item) in select.projection.iter().enumerate() {
-                match item {
-                    SelectItem::UnnamedExpr(Expr::Identifier(ident)) => {
-                        columns.push(ident.value.clone());
-                    }
-                    SelectItem::ExprWithAlias { alias, .. } => {
-                        columns.push(alias.value.clone());
-                    }
-                    SelectItem::UnnamedExpr(Expr::CompoundIdentifier(idents)) => {
-                        // table.column -> use column name
-                        if let Some(col) = idents.last() {
-                            columns.push(col.value.clone());
-                        }
-                    }
-                    SelectItem::Wildcard(_) => {
-                        // Can't infer columns from * - would need to expand
-                        // For now, skip validation of CTE columns when * is used
-                    }
-                    SelectItem::QualifiedWildcard(_, _) => {
-                        // table.* - can't infer easily
-                    }
-                    _ => {
-                        // Other expressions - generate a name
-                        columns.push(format!("?column?{}", idx + 1));
-                    }
+                match item_column_name(idx, item) {
+                    Some(name) => columns.push(name),
+                    // The expander could not tell what the wildcard covers,
+                    // so any list would be incomplete: leave the columns
+                    // unknown rather than reject valid references
+                    None => return Vec::new(),
                 }
             }
         }
@@ -406,9 +1115,19 @@ impl<'a> NameResolver<'a> {
             SetExpr::Select(select) => self.resolve_select(select),
             SetExpr::Query(query) => self.resolve_query(query),
             SetExpr::SetOperation { left, right, .. } => {
+                // Each arm has its own FROM scope
+                let saved_tables = self.tables.clone();
                 self.resolve_set_expr(left);
+                self.tables = saved_tables;
                 self.resolve_set_expr(right);
             }
+            // A data-modifying CTE, or the INSERT or UPDATE after a WITH
+            // clause; its relations aren't part of the enclosing FROM clause
+            SetExpr::Insert(statement) | SetExpr::Update(statement) => {
+                let saved_from_names = std::mem::take(&mut self.from_names);
+                self.resolve_statement(statement);
+                self.from_names = saved_from_names;
+            }
             _ => {}
         }
     }
@@ -416,43 +1135,98 @@ impl<'a> NameResolver<'a> {
     /// Resolve names in a SELECT statement
     fn resolve_select(&mut self, select: &Select) {
         // First, resolve FROM clause to build table scope
+        let saved_from_names = std::mem::take(&mut self.from_names);
+        let saved_merged_columns = std::mem::take(&mut self.merged_columns);
         for table_with_joins in &select.from {
             self.resolve_table_with_joins(table_with_joins);
         }
+        let from_names = std::mem::replace(&mut self.from_names, saved_from_names);
+        self.diagnostics.extend(cross_table::check_select(
+            self.catalog,
+            &self.tables,
+            &from_names,
+            select,
+        ));
+        self.check_sargability(&from_names, select.selection.as_ref());
+        let checker = Checker::new(self.catalog, &self.tables, &from_names);
+        self.diagnostics
+            .extend(join_indexes::check_joins(&checker, self.dialect, select));
+        let saved_outer_joined = std::mem::replace(
+            &mut self.outer_joined,
+            aggregates::outer_joined_names(select),
+        );
 
         // Then resolve SELECT items
+        self.clause = Clause::Select;
         for item in &select.projection {
             self.resolve_select_item(item);
         }
 
         // Resolve WHERE clause
+        self.clause = Clause::Where;
         if let Some(selection) = &select.selection {
             self.resolve_expr(selection);
         }
 
-        // Resolve GROUP BY
+        // Resolve GROUP BY (output column names are accepted as well,
+        // e.g. `SELECT unnest(tags) AS tag ... GROUP BY tag`)
         match &select.group_by {
             GroupByExpr::All(_) => {}
             GroupByExpr::Expressions(exprs, _) => {
+                let saved_aliases =
+                    std::mem::replace(&mut self.select_aliases, projection_aliases(select));
+                self.clause = Clause::GroupBy;
                 for expr in exprs {
                     self.resolve_expr(expr);
                 }
+                self.select_aliases = saved_aliases;
             }
         }
 
         // Resolve HAVING
+        self.clause = Clause::Having;
         if let Some(having) = &select.having {
             self.resolve_expr(having);
         }
+        self.clause = Clause::Other;
+        self.outer_joined = saved_outer_joined;
+        self.merged_columns = saved_merged_columns;
     }
 
     /// Resolve a table reference in FROM clause
+    ///
+    /// Relations are registered left to right, and each join condition is
+    /// resolved once its relation is, against the relations to its left: an
+    /// ON clause can't reference a relation joined after it. Derived tables
+    /// resolve in a scope of their own, so they never see the ON clauses.
     fn resolve_table_with_joins(&mut self, table: &TableWithJoins) {
         self.resolve_table_factor(&table.relation);
 
+        // Names and columns of the relations joined so far, for USING/NATURAL
+        let mut expander = ProjectionExpander::new(self.catalog, &self.ctes);
+        let mut left_names: Vec<String> = visible_name(&table.relation).into_iter().collect();
+        let mut left_columns = expander
+            .relation_columns(&table.relation)
+            .unwrap_or_default();
+
         for join in &table.joins {
             self.resolve_table_factor(&join.relation);
-            // Resolve join condition
+
+            let right_columns = expander
+                .relation_columns(&join.relation)
+                .unwrap_or_default();
+            let right_name = visible_name(&join.relation);
+            for column in merged_columns(&join.join_operator, &left_columns, &right_columns) {
+                let names = left_names
+                    .iter()
+                    .cloned()
+                    .chain(right_name.clone())
+                    .collect();
+                self.merged_columns.push((column, names));
+            }
+            left_names.extend(right_name);
+            left_columns.extend(right_columns);
+
             self.resolve_join_condition(&join.join_operator);
         }
     }
@@ -471,7 +1245,7 @@ impl<'a> NameResolver<'a> {
         if let Some(constraint) = constraint {
             match constraint {
                 JoinConstraint::On(expr) => {
-                    self.resolve_expr(expr);
+                    self.resolve_in_clause(Clause::JoinOn, expr);
                 }
                 JoinConstraint::Using(columns) => {
                     // For USING clause, check that columns exist in both tables
@@ -486,77 +1260,70 @@ impl<'a> NameResolver<'a> {
 
     /// Resolve a table factor (table name, subquery, etc.)
     fn resolve_table_factor(&mut self, factor: &TableFactor) {
+        // Table-valued function call (e.g., generate_series(...))
+        if let Some(call) = TableFunctionCall::from_factor(factor) {
+            self.resolve_table_function(&call);
+            return;
+        }
+
         match factor {
-            TableFactor::Table {
-                name, alias, args, ..
-            } => {
-                let table_name = object_name_to_qualified(name);
-
-                // Table-valued function call (e.g., generate_series(...))
-                // Register alias if present, skip table existence check
-                if args.is_some() {
-                    let alias_name = alias.as_ref().map(|a| a.name.value.clone());
-                    if let Some(a_name) = alias_name {
-                        let columns = alias
-                            .as_ref()
-                            .map(|a| a.columns.iter().map(|c| c.name.value.clone()).collect())
-                            .filter(|cols: &Vec<String>| !cols.is_empty())
-                            .unwrap_or_default();
-                        self.tables.insert(
-                            a_name.clone(),
-                            TableRef {
-                                table: QualifiedName::new(&a_name),
-                                alias: Some(a_name),
-                                view_columns: None,
-                                derived_columns: Some(columns),
-                            },
-                        );
-                    }
-                    return;
-                }
+            TableFactor::Table { name, alias, .. } => {
+                let table_name = self.names.object_name(name);
 
-                // Check if it's a CTE first
-                let is_cte = self.ctes.contains_key(&table_name.name);
+                // Check if it's a CTE first; a schema-qualified name is never a CTE
+                let is_cte =
+                    table_name.schema.is_none() && self.ctes.contains_key(&table_name.name);
 
                 // Check if table or view exists (in catalog or as CTE)
                 let is_view = !is_cte && self.catalog.view_exists(&table_name);
-                if !is_cte && !is_view && !self.catalog.table_exists(&table_name) {
+                let unknown = !is_cte && !is_view && !self.catalog.table_exists(&table_name);
+                if unknown && !self.schemaless && !self.is_placeholder_relation(&table_name) {
                     // Get span from the last identifier (table name)
                     let table_span = name.0.last().map(|id| Span::from_sqlparser(&id.span));
-                    let mut diag = Diagnostic::error(
-                        DiagnosticKind::TableNotFound,
-                        format!("Table '{}' not found", table_name),
-                    )
-                    .with_help("Check that the table exists in your schema definition");
-                    if let Some(span) = table_span {
-                        diag = diag.with_span(span);
-                    }
-                    self.diagnostics.push(diag);
+                    self.report_table_not_found(&table_name, table_span);
                     return;
                 }
+                if !is_cte && !unknown {
+                    self.add_relation(&table_name);
+                    if let (Some(table_def), Some(ident)) =
+                        (self.catalog.get_table(&table_name), name.0.last())
+                    {
+                        self.check_deprecated(table_def, None, Span::from_sqlparser(&ident.span));
+                    }
+                }
 
-                // Get view columns if this is a view reference
+                // Get view columns if this is a view reference; a view whose
+                // columns could not be determined has none, and is unknown
                 let view_columns = if is_view {
                     self.catalog
                         .get_view(&table_name)
-                        .map(|v| v.columns.clone())
+                        .map(|v| Arc::from(v.columns.as_slice()))
                 } else {
                     None
                 };
+                let unknown_view = view_columns.as_deref().is_some_and(<[String]>::is_empty);
 
                 // Register table in scope
-                let alias_name = alias.as_ref().map(|a| a.name.value.clone());
-                let lookup_name = alias_name
-                    .clone()
-                    .unwrap_or_else(|| table_name.name.clone());
+                let alias_name = alias.as_ref().map(|a| self.names.intern(&a.name.value));
+                let lookup_name = match &alias_name {
+                    Some(alias_name) => alias_name.clone(),
+                    None => self.names.intern(&table_name.name),
+                };
+                let span = match alias {
+                    Some(a) => Some(Span::from_sqlparser(&a.name.span)),
+                    None => name.0.last().map(|id| Span::from_sqlparser(&id.span)),
+                };
 
-                self.tables.insert(
+                self.register_table(
                     lookup_name,
                     TableRef {
                         table: table_name,
                         alias: alias_name,
-                        view_columns,
-                        derived_columns: None,
+                        view_columns: view_columns.filter(|_| !unknown_view),
+                        // An empty list matches any column
+                        derived_columns: (unknown || unknown_view).then(|| Arc::from(Vec::new())),
+                        cte: is_cte,
+                        span,
                     },
                 );
             }
@@ -565,67 +1332,368 @@ impl<'a> NameResolver<'a> {
                 subquery,
                 alias,
             } => {
-                // Save current table scope so subquery resolution doesn't leak
-                let saved_tables = self.tables.clone();
-
-                // Non-LATERAL subqueries cannot reference outer FROM tables,
-                // so clear the table scope. LATERAL subqueries can see outer tables.
-                if !lateral {
-                    self.tables.clear();
-                }
+                // Resolve the subquery in a child scope. Non-LATERAL subqueries
+                // cannot reference outer FROM tables, so theirs starts empty;
+                // LATERAL subqueries start with the relations to their left.
+                let saved_tables = if *lateral {
+                    self.tables.clone()
+                } else {
+                    std::mem::take(&mut self.tables)
+                };
 
                 // Resolve subquery
                 self.resolve_query(subquery);
 
                 // Infer column names from the subquery projection
-                let derived_columns = self.infer_cte_columns(&subquery.body);
+                let expanded =
+                    ProjectionExpander::new(self.catalog, &self.ctes).query_columns(subquery);
+                let derived_columns = match &expanded {
+                    Some(columns) => columns.clone(),
+                    None => self.infer_cte_columns(&subquery.body),
+                };
 
-                // Restore table scope
+                // Back to the outer scope
                 self.tables = saved_tables;
 
                 // Register derived table alias in outer scope
                 if let Some(a) = alias {
-                    let alias_name = a.name.value.clone();
+                    let alias_name = self.names.intern(&a.name.value);
                     // Use explicit column aliases if provided: (SELECT ...) AS v(col1, col2)
-                    let columns = if !a.columns.is_empty() {
-                        a.columns.iter().map(|c| c.name.value.clone()).collect()
-                    } else {
-                        derived_columns
+                    let columns = match expanded {
+                        Some(_) => {
+                            self.check_alias_count(a, derived_columns.len(), "Derived table");
+                            apply_alias_columns(Some(a), derived_columns)
+                        }
+                        None if !a.columns.is_empty() => alias_column_names(a),
+                        None => derived_columns,
                     };
-                    self.tables.insert(
+                    let table = self.names.table(None, &alias_name);
+                    self.register_table(
                         alias_name.clone(),
                         TableRef {
-                            table: QualifiedName::new(&alias_name),
+                            table,
                             alias: Some(alias_name),
                             view_columns: None,
-                            derived_columns: Some(columns),
+                            derived_columns: Some(columns.into()),
+                            cte: false,
+                            span: Some(Span::from_sqlparser(&a.name.span)),
                         },
                     );
                 }
             }
-            TableFactor::TableFunction { alias, .. } | TableFactor::Function { alias, .. } => {
-                // Table-valued functions (e.g., generate_series, unnest)
-                // Register alias if present, with empty column list (skip column validation)
-                if let Some(a) = alias {
-                    let alias_name = a.name.value.clone();
-                    let columns = if !a.columns.is_empty() {
-                        a.columns.iter().map(|c| c.name.value.clone()).collect()
-                    } else {
-                        vec![] // No column inference possible for functions
-                    };
-                    self.tables.insert(
-                        alias_name.clone(),
-                        TableRef {
-                            table: QualifiedName::new(&alias_name),
-                            alias: Some(alias_name),
-                            view_columns: None,
-                            derived_columns: Some(columns),
-                        },
-                    );
-                }
+            TableFactor::TableFunction { alias: Some(a), .. } => {
+                // TABLE(expr): register the alias with its column list (an
+                // empty list skips column validation)
+                let alias_name = self.names.intern(&a.name.value);
+                let table = self.names.table(None, &alias_name);
+                self.register_table(
+                    alias_name.clone(),
+                    TableRef {
+                        table,
+                        alias: Some(alias_name),
+                        view_columns: None,
+                        derived_columns: Some(alias_column_names(a).into()),
+                        cte: false,
+                        span: Some(Span::from_sqlparser(&a.name.span)),
+                    },
+                );
+            }
+            _ => {}
+        }
+    }
+
+    /// Register a table-valued function call in scope
+    ///
+    /// Well-known set-returning functions register their output columns,
+    /// under the alias or else the function name. Other functions are only
+    /// visible through an alias, with the columns it lists (none means the
+    /// columns can't be validated).
+    fn resolve_table_function(&mut self, call: &TableFunctionCall) {
+        let columns = match (call.column_names(), call.alias) {
+            (Some(columns), alias) => {
+                if let Some(a) = alias {
+                    self.check_alias_count(a, columns.len(), "Function");
+                }
+                columns
+            }
+            (None, Some(a)) => alias_column_names(a),
+            (None, None) => return,
+        };
+        let name = self.names.intern(&call.visible_name());
+        let table = self.names.table(None, &name);
+        self.register_table(
+            name.clone(),
+            TableRef {
+                table,
+                alias: call.alias.map(|_| name.clone()),
+                view_columns: None,
+                derived_columns: Some(columns.into()),
+                cte: false,
+                span: call.alias.map(|a| Span::from_sqlparser(&a.name.span)),
+            },
+        );
+    }
+
+    /// Register a FROM item in scope under `name` (its alias, or the table name)
+    ///
+    /// The same name twice in one FROM clause would make references to it
+    /// ambiguous, and databases reject it (`FROM orders JOIN orders`).
+    fn register_table(&mut self, name: Name, table_ref: TableRef) {
+        let name = self.schema_scoped_name(name, &table_ref);
+        if self.from_names.contains(&name) {
+            let shown = self.dialect.display_identifier(&name);
+            let message = match &table_ref.alias {
+                Some(_) => format!("Table alias '{}' specified more than once", shown),
+                None => format!(
+                    "Table name '{}' specified more than once; use an alias",
+                    shown
+                ),
+            };
+            // An alias clashing across two relations gets one alias for each
+            let help = match self.tables.get(&name) {
+                Some(existing) if existing.table != table_ref.table => {
+                    let (first, second) =
+                        distinct_aliases(&existing.table.name, &table_ref.table.name);
+                    format!(
+                        "Give each reference a distinct alias, e.g. {} AS {} ... {} AS {}",
+                        self.dialect.display_name(&existing.table),
+                        first,
+                        self.dialect.display_name(&table_ref.table),
+                        second
+                    )
+                }
+                _ => format!(
+                    "Give each reference a distinct alias, e.g. {0} AS {1}1 ... {0} AS {1}2",
+                    self.dialect.display_name(&table_ref.table),
+                    table_ref.table.name
+                ),
+            };
+            let mut diag =
+                Diagnostic::error(DiagnosticKind::DuplicateTableReference, message).with_help(help);
+            if let Some(span) = table_ref.span {
+                diag = diag.with_span(span);
+            }
+            self.diagnostics.push(diag);
+        } else {
+            self.from_names.push(name.clone());
+        }
+        self.tables.insert(name, table_ref);
+    }
+
+    /// The scope name of a relation named like another one of the FROM clause
+    /// from a different schema
+    ///
+    /// `UPDATE billing.invoices ... FROM public.invoices` is valid: neither is
+    /// aliased, and references tell them apart by schema
+    /// (`billing.invoices.id`). Such relations are registered under their
+    /// schema-qualified names, which renames the first one when the second
+    /// arrives; any other relation keeps `name`.
+    fn schema_scoped_name(&mut self, name: Name, table_ref: &TableRef) -> Name {
+        let unaliased_relation =
+            |t: &TableRef| t.alias.is_none() && !t.cte && t.derived_columns.is_none();
+        if !unaliased_relation(table_ref) {
+            return name;
+        }
+        let Some(qualified) = self.catalog.qualify(&table_ref.table) else {
+            return name;
+        };
+        let key = self.names.intern(&qualified.to_string());
+        let bare = &table_ref.table.name;
+        if self
+            .from_names
+            .iter()
+            .any(|n| self.schema_qualified_refs(bare).contains(&n))
+        {
+            return key;
+        }
+        let Some(idx) = self.from_names.iter().position(|n| *n == name) else {
+            return name;
+        };
+        let existing_key = match self.tables.get(&name) {
+            Some(existing) if unaliased_relation(existing) => {
+                match self.catalog.qualify(&existing.table) {
+                    Some(existing_name) if existing_name != qualified => {
+                        self.names.intern(&existing_name.to_string())
+                    }
+                    _ => return name,
+                }
+            }
+            _ => return name,
+        };
+        let existing = self.tables.remove(&name).expect("registered in scope");
+        self.tables.insert(existing_key.clone(), existing);
+        self.from_names[idx] = existing_key;
+        key
+    }
+
+    /// Scope names of the relations named `name` that are registered under
+    /// their schema-qualified names (see `schema_scoped_name`), sorted
+    fn schema_qualified_refs(&self, name: &str) -> Vec<&Name> {
+        let mut keys: Vec<&Name> = self
+            .tables
+            .iter()
+            .filter(|(key, t)| {
+                t.alias.is_none() && !t.cte && t.table.name == name && key.as_ref() != name
+            })
+            .map(|(key, _)| key)
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    /// The schema-qualified forms of `name` when it is ambiguous, as shown in
+    /// diagnostics
+    fn ambiguous_table_name(&self, name: &str) -> Option<Vec<String>> {
+        let keys = self.schema_qualified_refs(name);
+        (!keys.is_empty()).then(|| keys.iter().map(|k| self.display_scope_name(k)).collect())
+    }
+
+    /// How a scope name is shown in diagnostics: a relation registered under
+    /// its schema-qualified name is shown as such
+    fn display_scope_name(&self, key: &str) -> String {
+        match self.tables.get(key) {
+            Some(t) if t.alias.is_none() && !t.cte && *key != t.table.name => self
+                .catalog
+                .qualify(&t.table)
+                .map(|name| self.dialect.display_name(&name))
+                .unwrap_or_else(|| key.to_string()),
+            _ => self.dialect.display_identifier(key),
+        }
+    }
+
+    /// Report a table reference that does not exist in the catalog
+    ///
+    /// When a relation with the same name exists in other schemas, the message
+    /// names them instead of the generic "not found".
+    fn report_table_not_found(&mut self, table_name: &QualifiedName, span: Option<Span>) {
+        let searched_schema = table_name
+            .schema
+            .as_deref()
+            .unwrap_or(&self.catalog.default_schema);
+        let candidates: Vec<&str> = self
+            .catalog
+            .schemas_containing(&table_name.name)
+            .into_iter()
+            .filter(|s| *s != searched_schema)
+            .collect();
+
+        let mut diag = if candidates.is_empty() {
+            Diagnostic::error(
+                DiagnosticKind::TableNotFound,
+                format!(
+                    "Table '{}' not found",
+                    self.dialect.display_name(table_name)
+                ),
+            )
+            .with_help("Check that the table exists in your schema definition")
+        } else {
+            let kind = if candidates.iter().all(|schema| {
+                self.catalog
+                    .view_exists(&QualifiedName::with_schema(*schema, &table_name.name))
+            }) {
+                "a view"
+            } else {
+                "a table"
+            };
+            // MySQL's default schema (the current database) has no name
+            let named: Vec<String> = candidates
+                .iter()
+                .filter(|s| !s.is_empty())
+                .map(|s| format!("'{}'", self.dialect.display_identifier(s)))
+                .collect();
+            let mut places = Vec::new();
+            if !named.is_empty() {
+                places.push(format!(
+                    "schema{} {}",
+                    if named.len() > 1 { "s" } else { "" },
+                    named.join(", ")
+                ));
             }
-            _ => {}
+            if candidates.contains(&"") {
+                places.push("the default schema".to_string());
+            }
+            let searched = if searched_schema.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    " in schema '{}'",
+                    self.dialect.display_identifier(searched_schema)
+                )
+            };
+            let qualified_forms = candidates
+                .iter()
+                .map(|s| {
+                    self.dialect
+                        .display_name(&QualifiedName::with_schema(*s, &table_name.name))
+                })
+                .collect::<Vec<_>>()
+                .join(" or ");
+            Diagnostic::error(
+                DiagnosticKind::TableNotFound,
+                format!(
+                    "Table '{}' not found{}; {} with that name exists in {}",
+                    self.dialect.display_identifier(&table_name.name),
+                    searched,
+                    kind,
+                    places.join(" and ")
+                ),
+            )
+            .with_help(format!(
+                "Qualify the name ({}) or add the schema to the search path",
+                qualified_forms
+            ))
+        };
+        for schema in candidates {
+            let candidate = QualifiedName::with_schema(schema, &table_name.name);
+            if let Some(table_def) = self.catalog.get_table(&candidate) {
+                diag = with_table_origin(diag, table_def, self.dialect);
+            }
+        }
+        if let Some(span) = span {
+            diag = diag.with_span(span);
+        }
+        self.diagnostics.push(diag);
+    }
+
+    /// Check an alias column list against the number of columns the relation provides
+    ///
+    /// PostgreSQL only rejects lists longer than the relation (a shorter list
+    /// renames the leading columns), while MySQL requires an exact match.
+    fn check_alias_count(&mut self, alias: &TableAlias, available: usize, relation_kind: &str) {
+        let specified = alias.columns.len();
+        if specified == 0 || specified == available {
+            return;
+        }
+        if specified < available && self.dialect == SqlDialect::PostgreSQL {
+            return;
         }
+
+        let span = alias
+            .columns
+            .iter()
+            .map(|c| c.name.span)
+            .reduce(|a, b| a.union(&b))
+            .unwrap_or(alias.name.span);
+        self.diagnostics.push(
+            Diagnostic::error(
+                DiagnosticKind::ColumnCountMismatch,
+                format!(
+                    "{} '{}' has {} column(s) available but {} column alias(es) were specified",
+                    relation_kind, alias.name.value, available, specified
+                ),
+            )
+            .with_span(Span::from_sqlparser(&span))
+            .with_help(match self.dialect {
+                SqlDialect::PostgreSQL => format!(
+                    "Provide at most {} column alias(es) to match the query output",
+                    available
+                ),
+                SqlDialect::MySQL => format!(
+                    "Provide exactly {} column alias(es) to match the query output",
+                    available
+                ),
+            }),
+        );
     }
 
     /// Resolve a SELECT item
@@ -633,22 +1701,7 @@ impl<'a> NameResolver<'a> {
         match item {
             SelectItem::UnnamedExpr(expr) => self.resolve_expr(expr),
             SelectItem::ExprWithAlias { expr, .. } => self.resolve_expr(expr),
-            SelectItem::QualifiedWildcard(name, _) => {
-                // table.*
-                if let Some(first_ident) = name.0.first() {
-                    let table_name = &first_ident.value;
-                    if !self.tables.contains_key(table_name.as_str()) {
-                        let table_span = Span::from_sqlparser(&first_ident.span);
-                        self.diagnostics.push(
-                            Diagnostic::error(
-                                DiagnosticKind::TableNotFound,
-                                format!("Table or alias '{}' not found in FROM clause", table_name),
-                            )
-                            .with_span(table_span),
-                        );
-                    }
-                }
-            }
+            SelectItem::QualifiedWildcard(name, _) => self.resolve_qualified_wildcard(name),
             SelectItem::Wildcard(_) => {
                 // * - valid if we have at least one table
                 if self.tables.is_empty() {
@@ -661,9 +1714,218 @@ impl<'a> NameResolver<'a> {
         }
     }
 
+    /// Resolve the qualifier of `t.*` or `schema.t.*`
+    ///
+    /// A schema-qualified wildcard must name an unaliased catalog table or
+    /// view in the FROM clause; once a relation is aliased only the alias
+    /// refers to it.
+    fn resolve_qualified_wildcard(&mut self, name: &ObjectName) {
+        let Some(last) = name.0.last() else {
+            return;
+        };
+        if let [schema, table] = name.0.as_slice() {
+            let key = format!("{}.{}", schema.value, table.value);
+            if self.tables.contains_key(key.as_str()) {
+                return;
+            }
+        }
+        let Some(table_ref) = self.tables.get(last.value.as_str()) else {
+            if let Some(tables) = self.ambiguous_table_name(&last.value) {
+                self.report_ambiguous_table(last, &tables, ".*");
+            } else if self.dialect != SqlDialect::MySQL
+                || self.aliased_relation(&last.value).is_none()
+            {
+                self.report_unknown_qualifier(last, ".*");
+            }
+            return;
+        };
+        if name.0.len() == 1 {
+            return;
+        }
+
+        let is_catalog_relation = table_ref.alias.is_none() && !table_ref.cte;
+        let referenced = self.catalog.qualify(&object_name_to_qualified(name));
+        if is_catalog_relation
+            && referenced.is_some()
+            && referenced == self.catalog.qualify(&table_ref.table)
+        {
+            return;
+        }
+
+        self.diagnostics.push(
+            Diagnostic::error(
+                DiagnosticKind::TableNotFound,
+                format!("Table '{}' not found in FROM clause", name),
+            )
+            .with_span(Span::from_sqlparser(&name.0[0].span)),
+        );
+    }
+
+    /// Report a column or wildcard qualifier that names nothing in scope
+    ///
+    /// Each unknown name is reported once per statement, on its first
+    /// reference; the other references are added to that error as labels.
+    /// `rest` is the qualified part (`.column` or `.*`), for the help.
+    fn report_unknown_qualifier(&mut self, qualifier: &Ident, rest: &str) {
+        let span = Span::from_sqlparser(&qualifier.span);
+        if let Some(&idx) = self.unknown_qualifiers.get(&qualifier.value) {
+            let diag = &mut self.diagnostics[idx];
+            diag.labels.push(Label {
+                message: format!("'{}' is also referenced here", qualifier),
+                span,
+                file: None,
+            });
+            return;
+        }
+
+        let mut aliases: Vec<&Name> = self
+            .aliased_refs(&qualifier.value)
+            .filter_map(|(_, t)| t.alias.as_ref())
+            .collect();
+        aliases.sort();
+        let diag = match aliases.as_slice() {
+            [] => {
+                let diag = Diagnostic::error(
+                    DiagnosticKind::TableNotFound,
+                    format!("Table or alias '{}' not found in FROM clause", qualifier),
+                )
+                .with_span(span);
+                match self.similar_scope_name(&qualifier.value) {
+                    Some(similar) => diag.with_help(format!("Did you mean '{}'?", similar)),
+                    None => diag,
+                }
+            }
+            [alias] => Diagnostic::error(
+                DiagnosticKind::TableNotFound,
+                format!(
+                    "Table '{}' is aliased in the FROM clause and can't be referenced by name",
+                    qualifier
+                ),
+            )
+            .with_span(span)
+            .with_help(format!(
+                "table '{}' is aliased as '{}'; use the alias: '{}{}'",
+                qualifier, alias, alias, rest
+            )),
+            _ => Diagnostic::error(
+                DiagnosticKind::TableNotFound,
+                format!(
+                    "Table '{}' is aliased in the FROM clause and can't be referenced by name",
+                    qualifier
+                ),
+            )
+            .with_span(span)
+            .with_help(format!(
+                "table '{}' is aliased as {}; use one of the aliases",
+                qualifier,
+                aliases
+                    .iter()
+                    .map(|a| format!("'{}'", a))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        };
+        self.unknown_qualifiers
+            .insert(qualifier.value.clone(), self.diagnostics.len());
+        self.diagnostics.push(diag);
+    }
+
+    /// Report a qualifier naming relations of several schemas
+    /// (`invoices.id` with `billing.invoices` and `public.invoices` in scope)
+    fn report_ambiguous_table(&mut self, qualifier: &Ident, tables: &[String], rest: &str) {
+        self.diagnostics.push(
+            Diagnostic::error(
+                DiagnosticKind::AmbiguousColumn,
+                format!(
+                    "Table reference '{}' is ambiguous (found in: {})",
+                    qualifier,
+                    tables.join(", ")
+                ),
+            )
+            .with_span(Span::from_sqlparser(&qualifier.span))
+            .with_help(format!(
+                "Qualify the reference with the schema: {}{}",
+                tables[0], rest
+            )),
+        );
+    }
+
+    /// Relations in scope named `name` that were given an alias
+    /// (`FROM users u` for `users`), with their scope names
+    ///
+    /// PostgreSQL hides the name of an aliased relation; MySQL still accepts
+    /// it as a column qualifier, when only one relation has that name.
+    fn aliased_refs<'s>(&'s self, name: &'s str) -> impl Iterator<Item = (&'s Name, &'s TableRef)> {
+        self.tables.iter().filter(move |(key, t)| {
+            t.alias.is_some()
+                && t.derived_columns.is_none()
+                && t.table.name == name
+                && key.as_ref() != name
+        })
+    }
+
+    /// The scope name of the only relation named `name` that was aliased
+    fn aliased_relation(&self, name: &str) -> Option<Name> {
+        let mut refs = self.aliased_refs(name);
+        match (refs.next(), refs.next()) {
+            (Some((key, _)), None) => Some(key.clone()),
+            _ => None,
+        }
+    }
+
+    /// "Did you mean" help for a misspelled column, noting the column's
+    /// comment if it has one
+    fn column_suggestion(&self, table: &TableDef, name: &str) -> Option<String> {
+        let key = (table.name.clone(), name.to_lowercase());
+        if let Some(help) = self.suggestions.borrow().get(&key) {
+            return help.clone();
+        }
+        let help =
+            suggest::closest(name, table.column_names(), self.suggestion_budget).map(|similar| {
+                let help = format!("Did you mean '{}'?", similar);
+                match table.get_column(similar).and_then(|c| c.comment.as_deref()) {
+                    Some(comment) => format!("{} ({}: {})", help, similar, comment),
+                    None => help,
+                }
+            });
+        self.suggestions.borrow_mut().insert(key, help.clone());
+        help
+    }
+
+    /// The table name or alias in scope closest to `name` (for suggestions)
+    fn similar_scope_name(&self, name: &str) -> Option<Name> {
+        let name_lower = name.to_lowercase();
+        self.tables
+            .keys()
+            .map(|key| {
+                let distance = suggest::levenshtein_distance(&name_lower, &key.to_lowercase());
+                (distance, key)
+            })
+            .filter(|(distance, _)| *distance <= suggest::MAX_DISTANCE)
+            .min()
+            .map(|(_, key)| key.clone())
+    }
+
+    /// Resolve an expression of `clause`, then return to the current clause
+    fn resolve_in_clause(&mut self, clause: Clause, expr: &Expr) {
+        let saved_clause = std::mem::replace(&mut self.clause, clause);
+        self.resolve_expr(expr);
+        self.clause = saved_clause;
+    }
+
     /// Resolve an expression
     fn resolve_expr(&mut self, expr: &Expr) {
+        if !self.nesting.enter() {
+            return;
+        }
+        self.resolve_expr_inner(expr);
+        self.nesting.leave();
+    }
+
+    fn resolve_expr_inner(&mut self, expr: &Expr) {
         match expr {
+            // DEFAULT in VALUES / SET parses as an identifier
+            Expr::Identifier(_) if is_default_keyword(expr) => {}
             Expr::Identifier(ident) => {
                 // Simple column name - must exist in one of the tables
                 self.resolve_column(None, ident);
@@ -674,15 +1936,29 @@ impl<'a> NameResolver<'a> {
                     [table, column] => {
                         self.resolve_column(Some(table), column);
                     }
-                    [_schema, table, column] => {
-                        self.resolve_column(Some(table), column);
+                    [schema, table, column] => {
+                        self.resolve_schema_qualified_column(schema, table, column);
                     }
                     _ => {}
                 }
             }
-            Expr::BinaryOp { left, right, .. } => {
-                self.resolve_expr(left);
-                self.resolve_expr(right);
+            Expr::BinaryOp { .. } => {
+                // Walk left-deep chains (`a OR b OR c ...`) iteratively so that
+                // long generated conditions don't consume stack
+                let mut rights = Vec::new();
+                let mut leaf = expr;
+                while let Expr::BinaryOp { left, op, right } = leaf {
+                    if is_arithmetic(op) {
+                        self.check_count_operand(left);
+                        self.check_count_operand(right);
+                    }
+                    rights.push(right);
+                    leaf = left;
+                }
+                self.resolve_expr(leaf);
+                for right in rights.into_iter().rev() {
+                    self.resolve_expr(right);
+                }
             }
             Expr::UnaryOp { expr, .. } => {
                 self.resolve_expr(expr);
@@ -691,7 +1967,19 @@ impl<'a> NameResolver<'a> {
                 self.resolve_expr(inner);
             }
             Expr::Function(func) => {
+                if let Some(diagnostic) = self.aggregate_checker().check_call(func) {
+                    self.diagnostics.push(diagnostic);
+                }
+                let distinct_order = self.aggregate_checker().check_distinct_order_by(func);
+                self.diagnostics.extend(distinct_order);
+                if func.over.is_some() {
+                    self.check_window_placement(func);
+                }
                 self.resolve_function_args_list(&func.args);
+                // Ordered-set aggregates: percentile_cont(0.5) WITHIN GROUP (ORDER BY ...)
+                for ob in &func.within_group {
+                    self.resolve_expr(&ob.expr);
+                }
                 // Resolve FILTER (WHERE ...) clause
                 if let Some(filter) = &func.filter {
                     self.resolve_expr(filter);
@@ -910,6 +2198,38 @@ impl<'a> NameResolver<'a> {
                     _ => {}
                 }
             }
+            // array_agg(x ORDER BY y), string_agg(x, ',' ORDER BY y LIMIT n)
+            for clause in &arg_list.clauses {
+                match clause {
+                    FunctionArgumentClause::OrderBy(order_by) => {
+                        for ob in order_by {
+                            self.resolve_expr(&ob.expr);
+                        }
+                    }
+                    FunctionArgumentClause::Limit(expr)
+                    | FunctionArgumentClause::Having(HavingBound(_, expr)) => {
+                        self.resolve_expr(expr);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn aggregate_checker(&self) -> AggregateChecker<'_> {
+        AggregateChecker {
+            catalog: self.catalog,
+            dialect: self.dialect,
+            tables: &self.tables,
+            ctes: &self.ctes,
+            outer_joined: &self.outer_joined,
+        }
+    }
+
+    /// Hint when COUNT(nullable_col) feeds arithmetic
+    fn check_count_operand(&mut self, operand: &Expr) {
+        if let Some(diagnostic) = self.aggregate_checker().check_arithmetic_operand(operand) {
+            self.diagnostics.push(diagnostic);
         }
     }
 
@@ -921,7 +2241,7 @@ impl<'a> NameResolver<'a> {
         if let Some(table_id) = table_ident {
             let table_alias = &table_id.value;
             // Qualified column reference (table.column)
-            if let Some(table_ref) = self.tables.get(table_alias) {
+            if let Some(table_ref) = self.tables.get(table_alias.as_str()) {
                 // Check derived table first
                 if let Some(derived_cols) = &table_ref.derived_columns {
                     // Empty column list means we can't validate (e.g., table-valued functions)
@@ -936,21 +2256,22 @@ impl<'a> NameResolver<'a> {
                                 DiagnosticKind::ColumnNotFound,
                                 format!(
                                     "Column '{}' not found in subquery '{}'",
-                                    column_name, table_alias
+                                    column_ident, table_id
                                 ),
                             )
                             .with_span(column_span),
                         );
                     }
-                } else if let Some(cte) = self.ctes.get(&table_ref.table.name) {
-                    // Validate against CTE columns
-                    if !cte.columns.contains(column_name) {
+                } else if let Some(cte) = self.cte_of(table_ref) {
+                    // Validate against CTE columns (empty = unknown)
+                    if !cte.columns.is_empty() && !cte.columns.contains(column_name) {
                         self.diagnostics.push(
                             Diagnostic::error(
                                 DiagnosticKind::ColumnNotFound,
                                 format!(
                                     "Column '{}' not found in CTE '{}'",
-                                    column_name, table_ref.table
+                                    column_ident,
+                                    self.dialect.display_name(&table_ref.table)
                                 ),
                             )
                             .with_span(column_span),
@@ -967,73 +2288,71 @@ impl<'a> NameResolver<'a> {
                                 DiagnosticKind::ColumnNotFound,
                                 format!(
                                     "Column '{}' not found in view '{}'",
-                                    column_name, table_ref.table
+                                    column_ident,
+                                    self.dialect.display_name(&table_ref.table)
                                 ),
                             )
                             .with_span(column_span),
                         );
                     }
                 } else if let Some(table_def) = self.catalog.get_table(&table_ref.table) {
-                    if !table_def.column_exists(column_name) {
-                        let similar = find_similar_column(table_def, column_name);
+                    if table_def.column_exists(column_name) {
+                        self.check_deprecated(table_def, Some(column_name), column_span);
+                    } else {
+                        let similar = self.column_suggestion(table_def, column_name);
                         let mut diag = Diagnostic::error(
                             DiagnosticKind::ColumnNotFound,
                             format!(
                                 "Column '{}' not found in table '{}'",
-                                column_name, table_ref.table
+                                column_ident,
+                                self.dialect.display_name(&table_ref.table)
                             ),
                         )
                         .with_span(column_span);
                         if let Some(suggestion) = similar {
-                            diag = diag.with_help(format!("Did you mean '{}'?", suggestion));
+                            diag = diag.with_help(suggestion);
                         }
-                        self.diagnostics.push(diag);
+                        self.diagnostics
+                            .push(with_table_context(diag, table_def, self.dialect));
                     }
                 }
+            } else if let Some(tables) = self.ambiguous_table_name(table_alias) {
+                self.report_ambiguous_table(table_id, &tables, &format!(".{}", column_ident));
+            } else if let Some(alias) = self
+                .aliased_relation(table_alias)
+                .filter(|_| self.dialect == SqlDialect::MySQL)
+            {
+                let alias_ident = Ident {
+                    value: alias.to_string(),
+                    quote_style: None,
+                    span: table_id.span,
+                };
+                self.resolve_column(Some(&alias_ident), column_ident);
             } else {
-                let table_span = Span::from_sqlparser(&table_id.span);
-                self.diagnostics.push(
-                    Diagnostic::error(
-                        DiagnosticKind::TableNotFound,
-                        format!("Table or alias '{}' not found in FROM clause", table_alias),
-                    )
-                    .with_span(table_span),
-                );
+                self.report_unknown_qualifier(table_id, &format!(".{}", column_ident));
             }
         } else {
-            // Unqualified column reference - search all tables in scope
-            let mut found_in: Vec<&str> = Vec::new();
-
-            for (name, table_ref) in &self.tables {
-                // Check derived table first
-                if let Some(derived_cols) = &table_ref.derived_columns {
-                    // Empty column list = can't validate, assume match
-                    if derived_cols.is_empty()
-                        || derived_cols
-                            .iter()
-                            .any(|c| c.eq_ignore_ascii_case(column_name))
-                    {
-                        found_in.push(name);
-                    }
-                } else if let Some(cte) = self.ctes.get(&table_ref.table.name) {
-                    // Check CTE
-                    if cte.columns.contains(column_name) {
-                        found_in.push(name);
-                    }
-                } else if let Some(view_cols) = &table_ref.view_columns {
-                    // Check VIEW columns
-                    if view_cols
-                        .iter()
-                        .any(|c| c.eq_ignore_ascii_case(column_name))
-                    {
-                        found_in.push(name);
-                    }
-                } else if let Some(table_def) = self.catalog.get_table(&table_ref.table) {
-                    if table_def.column_exists(column_name) {
-                        found_in.push(name);
-                    }
+            // Unqualified column reference - look it up in the scope's index
+            let index = self.tables.column_index(self.catalog);
+            let mut found_in: Vec<&str> = index
+                .relations_with(column_name)
+                .iter()
+                .map(|name| &**name)
+                .collect();
+            // Relations of unknown columns may have it too, but only count
+            // when no other relation has it
+            let mut unknown_columns = false;
+            for name in index.unindexed() {
+                let table_ref = &self.tables[name];
+                if self.has_unknown_columns(table_ref) {
+                    unknown_columns = true;
+                } else if self.relation_has_column(table_ref, column_name) {
+                    found_in.push(name);
                 }
             }
+            if found_in.is_empty() && unknown_columns {
+                return;
+            }
 
             match found_in.len() {
                 0 => {
@@ -1048,129 +2367,449 @@ impl<'a> NameResolver<'a> {
 
                     // Column not found in any table
                     let mut suggestions = Vec::new();
+                    let mut table_defs = Vec::new();
                     for table_ref in self.tables.values() {
                         if let Some(table_def) = self.catalog.get_table(&table_ref.table) {
-                            if let Some(s) = find_similar_column(table_def, column_name) {
+                            if let Some(s) = self.column_suggestion(table_def, column_name) {
                                 suggestions.push(s);
                             }
+                            table_defs.push(table_def);
                         }
                     }
 
                     let mut diag = Diagnostic::error(
                         DiagnosticKind::ColumnNotFound,
-                        format!("Column '{}' not found", column_name),
+                        format!("Column '{}' not found", column_ident),
                     )
                     .with_span(column_span);
                     if !suggestions.is_empty() {
-                        diag = diag.with_help(format!("Did you mean '{}'?", suggestions[0]));
+                        diag = diag.with_help(suggestions.swap_remove(0));
+                    }
+                    // Columns are only listed when the query reads a single table
+                    table_defs.sort_by_key(|t| t.name.to_string());
+                    table_defs.dedup_by(|a, b| a.name == b.name);
+                    if let [table_def] = table_defs.as_slice() {
+                        diag = with_table_context(diag, table_def, self.dialect);
+                    } else {
+                        for table_def in table_defs {
+                            diag = with_table_origin(diag, table_def, self.dialect);
+                        }
                     }
                     self.diagnostics.push(diag);
                 }
                 1 => {
                     // Found in exactly one table - OK
+                    let table_ref = &self.tables[found_in[0]];
+                    if let Some(table_def) = self
+                        .catalog
+                        .get_table(&table_ref.table)
+                        .filter(|_| table_ref.is_base_table())
+                    {
+                        self.check_deprecated(table_def, Some(column_name), column_span);
+                    }
+                }
+                _ if self.merged_columns.iter().any(|(column, names)| {
+                    column.eq_ignore_ascii_case(column_name)
+                        && found_in.iter().all(|f| names.iter().any(|n| n == f))
+                }) =>
+                {
+                    // One column merged by USING / NATURAL JOIN
                 }
                 _ => {
-                    // Ambiguous - found in multiple tables
-                    self.diagnostics.push(
-                        Diagnostic::error(
-                            DiagnosticKind::AmbiguousColumn,
-                            format!(
-                                "Column '{}' is ambiguous (found in tables: {})",
-                                column_name,
-                                found_in.join(", ")
-                            ),
-                        )
-                        .with_span(column_span)
-                        .with_help(format!(
-                            "Qualify the column with a table name: {}.{}",
-                            found_in[0], column_name
-                        )),
-                    );
+                    // Ambiguous - found in multiple tables (named by alias when aliased)
+                    let mut candidates: Vec<(String, Option<Span>)> = found_in
+                        .iter()
+                        .map(|t| (self.display_scope_name(t), self.tables[*t].span))
+                        .collect();
+                    candidates.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+                    self.diagnostics
+                        .push(ambiguous_column(column_ident, column_span, &candidates));
                 }
             }
         }
     }
 
-    /// Consume the resolver and return collected diagnostics
+    /// Resolve a `schema.table.column` reference
+    ///
+    /// The relation is the one registered under the schema-qualified name, or
+    /// else the one named `table`, which must then be that schema's table.
+    fn resolve_schema_qualified_column(&mut self, schema: &Ident, table: &Ident, column: &Ident) {
+        let key = format!("{}.{}", schema.value, table.value);
+        if self.tables.contains_key(key.as_str()) {
+            let qualifier = Ident {
+                value: key,
+                quote_style: None,
+                span: schema.span.union(&table.span),
+            };
+            self.resolve_column(Some(&qualifier), column);
+            return;
+        }
+        if let Some(table_ref) = self
+            .tables
+            .get(table.value.as_str())
+            .filter(|t| t.alias.is_none() && !t.cte && t.derived_columns.is_none())
+        {
+            let written = QualifiedName::with_schema(&schema.value, &table.value);
+            let referenced = self.catalog.qualify(&written);
+            if referenced.is_none() || referenced != self.catalog.qualify(&table_ref.table) {
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        DiagnosticKind::TableNotFound,
+                        format!(
+                            "Table '{}' not found in FROM clause",
+                            self.dialect.display_name(&written)
+                        ),
+                    )
+                    .with_span(Span::from_sqlparser(&schema.span.union(&table.span)))
+                    .with_help(format!(
+                        "The FROM clause has '{}'",
+                        self.dialect.display_name(&table_ref.table)
+                    )),
+                );
+                return;
+            }
+        }
+        self.resolve_column(Some(table), column);
+    }
+
+    /// The definition of the CTE a relation refers to
+    fn cte_of(&self, table_ref: &TableRef) -> Option<&CteDefinition> {
+        if !table_ref.cte {
+            return None;
+        }
+        self.ctes.get(&table_ref.table.name)
+    }
+
+    /// Whether a relation in scope has a column, for unqualified references
+    ///
+    /// Exactly one source of columns applies, in this order: a derived
+    /// table's inferred columns, a CTE's columns, a view's columns, or the
+    /// catalog table's. An empty derived or CTE column list is unknown, and
+    /// matches any column.
+    fn relation_has_column(&self, table_ref: &TableRef, column: &str) -> bool {
+        let has = |columns: &[String]| columns.iter().any(|c| c.eq_ignore_ascii_case(column));
+        if let Some(columns) = &table_ref.derived_columns {
+            columns.is_empty() || has(columns)
+        } else if table_ref.cte {
+            self.cte_of(table_ref)
+                .map_or(true, |cte| cte.columns.is_empty() || has(&cte.columns))
+        } else if let Some(columns) = &table_ref.view_columns {
+            has(columns)
+        } else {
+            self.catalog
+                .get_table(&table_ref.table)
+                .is_some_and(|table| table.column_exists(column))
+        }
+    }
+
+    /// Whether a relation's columns are unknown: a derived table or CTE whose
+    /// column list is empty
+    fn has_unknown_columns(&self, table_ref: &TableRef) -> bool {
+        if let Some(columns) = &table_ref.derived_columns {
+            columns.is_empty()
+        } else if table_ref.cte {
+            self.cte_of(table_ref)
+                .map_or(true, |cte| cte.columns.is_empty())
+        } else {
+            false
+        }
+    }
+
+    /// Warn about a reference to a deprecated table, or a deprecated column of
+    /// it (W0010)
+    fn check_deprecated(&mut self, table_def: &TableDef, column: Option<&str>, span: Span) {
+        let (what, note) = match column {
+            Some(column) => {
+                let Some(note) = table_def
+                    .get_column(column)
+                    .and_then(|c| c.deprecated.as_deref())
+                else {
+                    return;
+                };
+                (
+                    format!(
+                        "Column '{}.{}' is deprecated",
+                        self.dialect.display_name(&table_def.name),
+                        column
+                    ),
+                    note,
+                )
+            }
+            None => {
+                let Some(note) = table_def.deprecated.as_deref() else {
+                    return;
+                };
+                (
+                    format!(
+                        "Table '{}' is deprecated",
+                        self.dialect.display_name(&table_def.name)
+                    ),
+                    note,
+                )
+            }
+        };
+        let diag = Diagnostic::warning(DiagnosticKind::DeprecatedUsage, what)
+            .with_span(span)
+            .with_help(if note.is_empty() {
+                "It is marked deprecated in the schema; avoid it in new queries".to_string()
+            } else {
+                note.to_string()
+            });
+        self.diagnostics
+            .push(with_table_origin(diag, table_def, self.dialect));
+    }
+
+    /// Catalog tables and views referenced by the resolved statements (CTEs
+    /// excluded), in order of first reference
+    pub fn relations(&self) -> &[QualifiedName] {
+        &self.relations
+    }
+
+    fn add_relation(&mut self, name: &QualifiedName) {
+        if !self.relations.contains(name) {
+            self.relations.push(name.clone());
+        }
+    }
+
     /// Consume the resolver and return collected diagnostics
     ///
     /// Returns all diagnostics collected during name resolution.
-    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+    pub fn into_diagnostics(mut self) -> Vec<Diagnostic> {
+        self.diagnostics.extend(self.nesting.diagnostic());
         self.diagnostics
     }
 }
 
-/// Convert ObjectName to QualifiedName
-fn object_name_to_qualified(name: &ObjectName) -> QualifiedName {
-    match name.0.as_slice() {
-        [table] => QualifiedName::new(&table.value),
-        [schema, table] => QualifiedName::with_schema(&schema.value, &table.value),
-        [_catalog, schema, table] => QualifiedName::with_schema(&schema.value, &table.value),
-        _ => QualifiedName::new(name.to_string()),
+/// Most qualified forms an ambiguous column's help suggests
+const MAX_QUALIFICATIONS: usize = 4;
+
+/// An unqualified column found in several relations, by scope name as shown
+/// to the user and position in the FROM clause
+fn ambiguous_column(
+    column: &Ident,
+    span: Span,
+    candidates: &[(String, Option<Span>)],
+) -> Diagnostic {
+    let names: Vec<&str> = candidates.iter().map(|(name, _)| name.as_str()).collect();
+    let mut qualified: Vec<String> = names
+        .iter()
+        .take(MAX_QUALIFICATIONS)
+        .map(|name| format!("{}.{}", name, column))
+        .collect();
+    let choices = if names.len() > MAX_QUALIFICATIONS {
+        format!("{}, ...", qualified.join(", "))
+    } else {
+        let last = qualified.pop().unwrap_or_default();
+        format!("{} or {}", qualified.join(", "), last)
+    };
+    let mut diag = Diagnostic::error(
+        DiagnosticKind::AmbiguousColumn,
+        format!(
+            "Column '{}' is ambiguous (found in tables: {})",
+            column,
+            names.join(", ")
+        ),
+    )
+    .with_span(span)
+    .with_help(format!("Qualify the column with a table name: {}", choices));
+    for (name, relation_span) in candidates {
+        if let Some(relation_span) = relation_span {
+            diag = diag.with_label(
+                format!("'{}' has a column '{}'", name, column),
+                *relation_span,
+            );
+        }
     }
+    diag
 }
 
-/// Get table name from TableFactor
-fn table_with_joins_to_name(factor: &TableFactor) -> Option<QualifiedName> {
-    match factor {
-        TableFactor::Table { name, .. } => Some(object_name_to_qualified(name)),
-        _ => None,
+/// Aliases for two relations named `first` and `second`: their initials,
+/// numbered if they share one
+fn distinct_aliases(first: &str, second: &str) -> (String, String) {
+    let initial = |name: &str| {
+        name.chars()
+            .find(|c| c.is_alphanumeric())
+            .map_or_else(|| "t".to_string(), |c| c.to_lowercase().to_string())
+    };
+    let (a, b) = (initial(first), initial(second));
+    if a == b {
+        (format!("{}1", a), format!("{}2", b))
+    } else {
+        (a, b)
     }
 }
 
-/// Find a similar column name (for suggestions)
-fn find_similar_column(table: &TableDef, name: &str) -> Option<String> {
-    let name_lower = name.to_lowercase();
-    let mut best_match: Option<(usize, &str)> = None;
+/// Error for an INSERT that gives a GENERATED ALWAYS identity column a value
+fn identity_column_assignment(table_name: &QualifiedName, column: &str) -> Diagnostic {
+    Diagnostic::error(
+        DiagnosticKind::GeneratedColumnAssignment,
+        format!(
+            "Cannot insert into column '{}' of table '{}', which is GENERATED ALWAYS AS IDENTITY",
+            column, table_name
+        ),
+    )
+    .with_help(
+        "Its values come from the identity sequence; remove it or use DEFAULT, \
+         or add OVERRIDING SYSTEM VALUE to keep the given values",
+    )
+}
 
-    for col_name in table.columns.keys() {
-        let col_lower = col_name.to_lowercase();
-        let distance = levenshtein_distance(&name_lower, &col_lower);
+/// Note for identity column values that OVERRIDING USER VALUE discards
+fn ignored_identity_value(table_name: &QualifiedName, column: &str) -> Diagnostic {
+    Diagnostic::info(
+        DiagnosticKind::IgnoredIdentityValue,
+        format!(
+            "Values for identity column '{}' of table '{}' are ignored (OVERRIDING USER VALUE)",
+            column, table_name
+        ),
+    )
+    .with_help(
+        "The identity sequence supplies the values; remove the column or use DEFAULT, \
+         or drop OVERRIDING USER VALUE to keep the given values",
+    )
+}
 
-        // Only suggest if reasonably similar (distance <= 3)
-        if distance <= 3 && (best_match.is_none() || distance < best_match.unwrap().0) {
-            best_match = Some((distance, col_name));
-        }
+/// Error for an INSERT/UPDATE that writes to a generated column
+fn generated_column_assignment(table_name: &QualifiedName, column: &str) -> Diagnostic {
+    Diagnostic::error(
+        DiagnosticKind::GeneratedColumnAssignment,
+        format!(
+            "Cannot assign to generated column '{}' of table '{}'",
+            column, table_name
+        ),
+    )
+    .with_help("Generated columns are computed from other columns; remove it or use DEFAULT")
+}
+
+/// Comparable text of a literal key value, or `None` if the value isn't a known constant
+///
+/// NULLs never conflict in unique constraints, and parameters, DEFAULT and
+/// expressions can't be compared statically.
+fn literal_key_value(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Value(Value::Null | Value::Placeholder(_)) => None,
+        Expr::Value(value) => Some(value.to_string()),
+        Expr::UnaryOp {
+            op: UnaryOperator::Minus,
+            expr: inner,
+        } if matches!(inner.as_ref(), Expr::Value(Value::Number(..))) => Some(expr.to_string()),
+        Expr::Nested(inner) => literal_key_value(inner),
+        _ => None,
     }
+}
 
-    best_match.map(|(_, name)| name.to_string())
+/// Output column names of a SELECT that ORDER BY and GROUP BY may refer to
+fn projection_aliases(select: &Select) -> Vec<String> {
+    let mut aliases = Vec::new();
+    for item in &select.projection {
+        match item {
+            SelectItem::ExprWithAlias { alias, .. } => {
+                aliases.push(alias.value.clone());
+            }
+            SelectItem::UnnamedExpr(Expr::Identifier(ident)) => {
+                // Column name also acts as implicit alias
+                aliases.push(ident.value.clone());
+            }
+            _ => {}
+        }
+    }
+    aliases
 }
 
-/// Simple Levenshtein distance implementation
-fn levenshtein_distance(a: &str, b: &str) -> usize {
-    let a_chars: Vec<char> = a.chars().collect();
-    let b_chars: Vec<char> = b.chars().collect();
-    let m = a_chars.len();
-    let n = b_chars.len();
+/// Position referenced by `ORDER BY <n>`, if the expression is an integer literal
+fn order_by_ordinal(expr: &Expr) -> Option<u64> {
+    match expr {
+        Expr::Value(Value::Number(n, _)) => n.parse().ok(),
+        _ => None,
+    }
+}
 
-    if m == 0 {
-        return n;
+/// The span of an ORDER BY item, or of the query it orders for a bare
+/// position, since literals have no span
+fn order_by_span(ob: &OrderByExpr, body: &SetExpr) -> Span {
+    match ob.span() {
+        span if span == sqlparser::tokenizer::Span::empty() => Span::from_sqlparser(&body.span()),
+        span => Span::from_sqlparser(&span),
     }
-    if n == 0 {
-        return m;
+}
+
+/// Whether an operator is one of the arithmetic operators `+ - * / %`
+fn is_arithmetic(op: &BinaryOperator) -> bool {
+    matches!(
+        op,
+        BinaryOperator::Plus
+            | BinaryOperator::Minus
+            | BinaryOperator::Multiply
+            | BinaryOperator::Divide
+            | BinaryOperator::Modulo
+    )
+}
+
+/// E0004 for a NOT NULL column without a default that an INSERT leaves to
+/// its default
+fn null_violation(table_name: &QualifiedName, column: &str) -> Diagnostic {
+    Diagnostic::error(
+        DiagnosticKind::PotentialNullViolation,
+        format!(
+            "Column '{}' of table '{}' is NOT NULL and has no default",
+            column, table_name
+        ),
+    )
+    .with_help(format!("Provide a value for '{}'", column))
+}
+
+/// Whether an expression is the bare `DEFAULT` keyword (as in `VALUES (DEFAULT)`)
+fn is_default_keyword(expr: &Expr) -> bool {
+    matches!(expr, Expr::Identifier(ident) if ident.quote_style.is_none() && ident.value.eq_ignore_ascii_case("DEFAULT"))
+}
+
+/// Get table name from TableFactor
+fn table_with_joins_to_name(factor: &TableFactor) -> Option<QualifiedName> {
+    match factor {
+        TableFactor::Table { name, .. } => Some(object_name_to_qualified(name)),
+        _ => None,
     }
+}
 
-    let mut dp = vec![vec![0; n + 1]; m + 1];
+/// Relate a column-not-found diagnostic to the table the column was looked up in
+///
+/// Points a label at the table's CREATE TABLE when the schema recorded where
+/// it is; otherwise, unless there is already a suggestion, the help lists the
+/// table's columns.
+fn with_table_context(diag: Diagnostic, table: &TableDef, dialect: SqlDialect) -> Diagnostic {
+    const MAX_LISTED_COLUMNS: usize = 15;
 
-    for (i, row) in dp.iter_mut().enumerate().take(m + 1) {
-        row[0] = i;
+    if table.origin.is_some() || diag.help.is_some() {
+        return with_table_origin(diag, table, dialect);
     }
-    for (j, val) in dp[0].iter_mut().enumerate() {
-        *val = j;
+    let names = table.column_names();
+    let mut list = names
+        .iter()
+        .take(MAX_LISTED_COLUMNS)
+        .map(|name| dialect.display_identifier(name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if names.len() > MAX_LISTED_COLUMNS {
+        list.push_str(&format!(
+            ", ... ({} more)",
+            names.len() - MAX_LISTED_COLUMNS
+        ));
     }
+    diag.with_help(format!(
+        "Table '{}' has columns: {}",
+        dialect.display_name(&table.name),
+        list
+    ))
+}
 
-    for i in 1..=m {
-        for j in 1..=n {
-            let cost = if a_chars[i - 1] == b_chars[j - 1] {
-                0
-            } else {
-                1
-            };
-            dp[i][j] = (dp[i - 1][j] + 1)
-                .min(dp[i][j - 1] + 1)
-                .min(dp[i - 1][j - 1] + cost);
-        }
+/// Add a "defined here" label at the table's definition, if its origin is known
+fn with_table_origin(diag: Diagnostic, table: &TableDef, dialect: SqlDialect) -> Diagnostic {
+    match &table.origin {
+        Some(origin) => diag.with_label_in(
+            format!("table '{}' defined here", dialect.display_name(&table.name)),
+            &origin.file,
+            Span::insertion(origin.line, origin.column),
+        ),
+        None => diag,
     }
-
-    dp[m][n]
 }
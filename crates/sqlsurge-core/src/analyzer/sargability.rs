@@ -0,0 +1,169 @@
+//! Index-defeating comparisons (performance hints, opt-in)
+//!
+//! `WHERE lower(email) = 'x'` and `WHERE created_at::date = '2024-01-01'` are
+//! correct, but the database has to compute the expression for every row:
+//! an index on the column can't be used without an expression index (W0012).
+//! Under MySQL, `WHERE code = 123` on a string column converts the column
+//! (not the literal) to a number on every row, with the same effect (W0013).
+//!
+//! Without index information every such comparison of a base table column
+//! with a literal or parameter is flagged; both rules are hints, enabled
+//! together as the `performance` group.
+
+use sqlparser::ast::{Expr, Spanned, UnaryOperator, Value};
+
+use crate::dialect::SqlDialect;
+use crate::error::{Diagnostic, DiagnosticKind, Span};
+use crate::types::SqlType;
+
+use super::cross_table::{collect_comparisons, Checker, ColumnKey};
+use super::functions::{function_args, function_name};
+
+/// Check the comparisons of a WHERE clause
+pub(super) fn check_where(
+    checker: &Checker,
+    dialect: SqlDialect,
+    selection: Option<&Expr>,
+) -> Vec<Diagnostic> {
+    let mut comparisons = Vec::new();
+    if let Some(selection) = selection {
+        collect_comparisons(selection, &mut comparisons);
+    }
+
+    let mut diagnostics = Vec::new();
+    for (left, _, right) in comparisons {
+        for (side, value) in [(left, right), (right, left)] {
+            if !is_constant(value) {
+                continue;
+            }
+            if let Some(diag) = wrapped_column(checker, side) {
+                diagnostics.push(diag);
+            } else if dialect == SqlDialect::MySQL {
+                diagnostics.extend(coerced_column(checker, side, value));
+            }
+        }
+    }
+    diagnostics
+}
+
+/// W0012: a column inside a function call or cast
+fn wrapped_column(checker: &Checker, expr: &Expr) -> Option<Diagnostic> {
+    let (message, rewrite) = match expr {
+        Expr::Nested(inner) => return wrapped_column(checker, inner),
+        Expr::Function(func) => {
+            let column = function_args(func)
+                .into_iter()
+                .find(|arg| checker.column(arg).is_some())?;
+            let name = function_name(func);
+            let message = format!(
+                "Column '{}' is wrapped in {}(), so an index on it can't be used",
+                column, name
+            );
+            let rewrite = match name.to_lowercase().as_str() {
+                "lower" | "upper" => format!(
+                    "Store '{}' in one case, or compare it with a case-insensitive collation",
+                    column
+                ),
+                _ => format!(
+                    "Compare '{}' itself and apply the inverse of {}() to the value",
+                    column, name
+                ),
+            };
+            (message, rewrite)
+        }
+        Expr::Cast {
+            expr: column,
+            data_type,
+            ..
+        } if checker.column(column).is_some() => {
+            let target = SqlType::from_ast(data_type);
+            let message = format!(
+                "Column '{}' is cast to {}, so an index on it can't be used",
+                column,
+                target.display_name()
+            );
+            let rewrite = if target == SqlType::Date {
+                format!(
+                    "Compare '{}' with the range of the day: >= the date AND < the next day",
+                    column
+                )
+            } else {
+                format!("Compare '{}' itself and cast the value instead", column)
+            };
+            (message, rewrite)
+        }
+        _ => return None,
+    };
+    Some(
+        Diagnostic::hint(DiagnosticKind::NonSargablePredicate, message)
+            .with_span(Span::from_sqlparser(&expr.span()))
+            .with_help(format!(
+                "{}, or create an expression index on ({})",
+                rewrite, expr
+            )),
+    )
+}
+
+/// W0013: a string column compared with a number (MySQL)
+///
+/// MySQL compares a string and a number as numbers, converting the column
+/// value of every row; a string compared with a numeric column converts
+/// the string once, and is fine.
+fn coerced_column(checker: &Checker, column: &Expr, value: &Expr) -> Option<Diagnostic> {
+    let key = checker.column(column)?;
+    let column_type = column_type(checker, &key)?;
+    let is_string = matches!(
+        column_type,
+        SqlType::Char { .. } | SqlType::Varchar { .. } | SqlType::Text
+    );
+    if !is_string || !is_number(value) {
+        return None;
+    }
+    Some(
+        Diagnostic::hint(
+            DiagnosticKind::CoercedColumnComparison,
+            format!(
+                "Comparing {} column '{}' with the number {} converts the column on every row",
+                column_type.display_name(),
+                column,
+                value
+            ),
+        )
+        .with_span(Span::from_sqlparser(&column.span()))
+        .with_help(format!(
+            "MySQL compares a string with a number as numbers, so an index on '{}' can't be \
+             used; compare with a string instead: '{}'",
+            column, value
+        )),
+    )
+}
+
+fn column_type(checker: &Checker, key: &ColumnKey) -> Option<SqlType> {
+    let table = checker.catalog().get_table(&key.0)?;
+    Some(table.get_column(&key.1)?.data_type.clone())
+}
+
+/// A literal or a parameter, whose conversion is done once per query
+fn is_constant(expr: &Expr) -> bool {
+    match expr {
+        Expr::Value(_) | Expr::TypedString { .. } => true,
+        Expr::Nested(inner) => is_constant(inner),
+        Expr::UnaryOp {
+            op: UnaryOperator::Minus | UnaryOperator::Plus,
+            expr,
+        } => is_number(expr),
+        _ => false,
+    }
+}
+
+fn is_number(expr: &Expr) -> bool {
+    match expr {
+        Expr::Value(Value::Number(..)) => true,
+        Expr::Nested(inner) => is_number(inner),
+        Expr::UnaryOp {
+            op: UnaryOperator::Minus | UnaryOperator::Plus,
+            expr,
+        } => is_number(expr),
+        _ => false,
+    }
+}
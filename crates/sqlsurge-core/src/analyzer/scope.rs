@@ -0,0 +1,216 @@
+//! The table scope of the name and type resolvers, with a reverse index of
+//! its columns
+//!
+//! An unqualified column could come from any relation in scope, and a wide
+//! reporting query (a dozen joined tables of hundreds of columns, thousands
+//! of unqualified references) would search every column list for every
+//! reference. The index maps each column name to the relations that have
+//! it; it is built on the first lookup and dropped whenever a relation is
+//! added or removed, so a scope is indexed once however many references it
+//! resolves. Saved and restored scopes (around subqueries) keep theirs.
+
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::error::Span;
+use crate::schema::{Catalog, ColumnDef, QualifiedName, TableDef};
+
+use super::intern::Name;
+
+/// Resolved table reference in a query
+///
+/// Fields are shared, so saving a scope doesn't copy names or column lists.
+#[derive(Debug, Clone)]
+pub(super) struct TableRef {
+    /// The actual table definition
+    pub(super) table: Arc<QualifiedName>,
+    /// Alias used in the query (if any)
+    pub(super) alias: Option<Name>,
+    /// If this is a VIEW reference, the column names from the VIEW definition
+    pub(super) view_columns: Option<Arc<[String]>>,
+    /// If this is a derived table (subquery in FROM), the inferred column names
+    pub(super) derived_columns: Option<Arc<[String]>>,
+    /// Whether this is a CTE reference (its columns are in the CTE definition)
+    pub(super) cte: bool,
+    /// Where the FROM clause names the relation: its alias, or else the
+    /// table name (none for unaliased table functions)
+    pub(super) span: Option<Span>,
+}
+
+impl TableRef {
+    /// Whether this is a catalog table (not a CTE, view or derived table)
+    pub(super) fn is_base_table(&self) -> bool {
+        !self.cte && self.view_columns.is_none() && self.derived_columns.is_none()
+    }
+}
+
+/// Relations in scope by alias or name, read through `Deref`
+#[derive(Debug, Clone, Default)]
+pub(super) struct Scope {
+    relations: HashMap<Name, TableRef>,
+    index: OnceCell<Arc<ColumnIndex>>,
+}
+
+/// Scope names by ASCII-lowercased column name
+#[derive(Debug, Default)]
+pub(super) struct ColumnIndex {
+    columns: HashMap<String, Vec<Name>>,
+    /// CTEs and derived tables of unknown columns, which are checked on
+    /// every lookup
+    unindexed: Vec<Name>,
+}
+
+impl Scope {
+    pub(super) fn insert(&mut self, name: Name, table_ref: TableRef) -> Option<TableRef> {
+        self.index.take();
+        self.relations.insert(name, table_ref)
+    }
+
+    pub(super) fn remove(&mut self, name: &str) -> Option<TableRef> {
+        self.index.take();
+        self.relations.remove(name)
+    }
+
+    /// The catalog table registered as `name` and its `column`, if it is a
+    /// base table that has one
+    pub(super) fn base_column<'c>(
+        &self,
+        catalog: &'c Catalog,
+        name: &str,
+        column: &str,
+    ) -> Option<(&'c TableDef, &'c ColumnDef)> {
+        let table_ref = self.relations.get(name)?;
+        if !table_ref.is_base_table() {
+            return None;
+        }
+        let table = catalog.get_table(&table_ref.table)?;
+        Some((table, table.get_column(column)?))
+    }
+
+    /// The column index of the relations in scope, built on first use
+    pub(super) fn column_index(&self, catalog: &Catalog) -> &ColumnIndex {
+        self.index
+            .get_or_init(|| Arc::new(ColumnIndex::build(&self.relations, catalog)))
+    }
+}
+
+impl Deref for Scope {
+    type Target = HashMap<Name, TableRef>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.relations
+    }
+}
+
+impl ColumnIndex {
+    /// Index the columns of each relation, from the same source
+    /// `NameResolver::relation_has_column` uses
+    ///
+    /// CTE columns are in the resolver's CTE definitions, which can change
+    /// while the scope doesn't, so CTEs aren't indexed. A catalog table
+    /// missing from the catalog has no columns.
+    fn build(relations: &HashMap<Name, TableRef>, catalog: &Catalog) -> Self {
+        let mut index = Self::default();
+        for (name, table_ref) in relations {
+            let columns: Vec<&str> = if let Some(columns) = &table_ref.derived_columns {
+                if columns.is_empty() {
+                    index.unindexed.push(name.clone());
+                    continue;
+                }
+                columns.iter().map(String::as_str).collect()
+            } else if table_ref.cte {
+                index.unindexed.push(name.clone());
+                continue;
+            } else if let Some(columns) = &table_ref.view_columns {
+                columns.iter().map(String::as_str).collect()
+            } else if let Some(table) = catalog.get_table(&table_ref.table) {
+                table.column_names()
+            } else {
+                continue;
+            };
+            for column in columns {
+                let names = index
+                    .columns
+                    .entry(column.to_ascii_lowercase())
+                    .or_default();
+                // A derived table can name a column twice
+                if !names.contains(name) {
+                    names.push(name.clone());
+                }
+            }
+        }
+        index
+    }
+
+    /// Scope names of the indexed relations that have `column`
+    pub(super) fn relations_with(&self, column: &str) -> &[Name] {
+        self.columns
+            .get(&column.to_ascii_lowercase())
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Scope names whose columns aren't indexed
+    pub(super) fn unindexed(&self) -> &[Name] {
+        &self.unindexed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{CatalogBuilder, QualifiedName};
+    use crate::types::SqlType;
+
+    fn relation(table: &str, derived_columns: Option<&[&str]>) -> TableRef {
+        TableRef {
+            table: Arc::new(QualifiedName::new(table)),
+            alias: None,
+            view_columns: None,
+            derived_columns: derived_columns
+                .map(|columns| columns.iter().map(|c| c.to_string()).collect()),
+            cte: false,
+            span: None,
+        }
+    }
+
+    #[test]
+    fn test_index_follows_scope_changes() {
+        let (catalog, _) = CatalogBuilder::new()
+            .table("users", |t| {
+                t.column("id", SqlType::Integer, |c| c)
+                    .column("Name", SqlType::Text, |c| c)
+            })
+            .table("orders", |t| {
+                t.column("id", SqlType::Integer, |c| c)
+                    .column("user_id", SqlType::Integer, |c| c)
+            })
+            .build();
+
+        let mut scope = Scope::default();
+        scope.insert(Name::from("u"), relation("users", None));
+        assert_eq!(
+            scope.column_index(&catalog).relations_with("ID"),
+            ["u".into()]
+        );
+        assert_eq!(
+            scope.column_index(&catalog).relations_with("name"),
+            ["u".into()]
+        );
+
+        scope.insert(Name::from("orders"), relation("orders", None));
+        scope.insert(Name::from("d"), relation("d", Some(&[])));
+        let index = scope.column_index(&catalog);
+        let mut with_id = index.relations_with("id").to_vec();
+        with_id.sort();
+        assert_eq!(with_id, [Name::from("orders"), Name::from("u")]);
+        assert_eq!(index.unindexed(), [Name::from("d")]);
+
+        scope.remove("u");
+        assert!(scope
+            .column_index(&catalog)
+            .relations_with("name")
+            .is_empty());
+    }
+}
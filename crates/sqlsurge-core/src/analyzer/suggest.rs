@@ -0,0 +1,123 @@
+//! "Did you mean" suggestions by edit distance
+//!
+//! Tables can have hundreds of columns and a bad file reports many unknown
+//! names, so finding the closest name is bounded: candidates whose length
+//! alone puts them too far away are skipped, the distance computation stops
+//! as soon as it can't beat the best match so far, and at most a budget of
+//! candidates is compared per lookup.
+
+/// Largest edit distance that is still suggested
+pub(super) const MAX_DISTANCE: usize = 3;
+
+/// Default number of candidates compared per lookup
+pub const DEFAULT_SUGGESTION_BUDGET: usize = 1000;
+
+/// The candidate closest to `name` (ignoring case) within [`MAX_DISTANCE`],
+/// the first one on ties
+///
+/// Candidates skipped by their length don't count towards `budget`.
+pub(super) fn closest<'c>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'c str>,
+    budget: usize,
+) -> Option<&'c str> {
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let mut best: Option<(usize, &str)> = None;
+    let mut compared = 0;
+    for candidate in candidates {
+        // A better match must be strictly closer than the best so far
+        let max = match best {
+            Some((0, _)) => break,
+            Some((distance, _)) => distance - 1,
+            None => MAX_DISTANCE,
+        };
+        if candidate.chars().count().abs_diff(name.len()) > max {
+            continue;
+        }
+        if compared == budget {
+            break;
+        }
+        compared += 1;
+        let lower: Vec<char> = candidate.to_lowercase().chars().collect();
+        if let Some(distance) = bounded_distance(&name, &lower, max) {
+            best = Some((distance, candidate));
+        }
+    }
+    best.map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance of `a` and `b`, if it is at most `max`
+///
+/// Rows of the distance table are computed one at a time; once every entry
+/// of a row exceeds `max`, so does the result.
+pub(super) fn bounded_distance(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, &a_char) in a.iter().enumerate() {
+        current[0] = i + 1;
+        let mut row_min = current[0];
+        for (j, &b_char) in b.iter().enumerate() {
+            let cost = usize::from(a_char != b_char);
+            current[j + 1] = (previous[j + 1] + 1)
+                .min(current[j] + 1)
+                .min(previous[j] + cost);
+            row_min = row_min.min(current[j + 1]);
+        }
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    Some(previous[b.len()]).filter(|&distance| distance <= max)
+}
+
+/// Levenshtein distance of `a` and `b`
+pub(super) fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    bounded_distance(&a, &b, a.len().max(b.len())).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    #[test]
+    fn test_bounded_distance_gives_up_past_max() {
+        assert_eq!(levenshtein_distance("kitten", "sitting"), 3);
+        assert_eq!(levenshtein_distance("", "abc"), 3);
+        assert_eq!(
+            bounded_distance(&chars("kitten"), &chars("sitting"), 3),
+            Some(3)
+        );
+        assert_eq!(
+            bounded_distance(&chars("kitten"), &chars("sitting"), 2),
+            None
+        );
+        assert_eq!(bounded_distance(&chars("a"), &chars("abcde"), 3), None);
+    }
+
+    #[test]
+    fn test_closest_prefers_first_of_the_nearest() {
+        let columns = ["user_name", "username", "user_id", "email"];
+        assert_eq!(closest("usrname", columns, 100), Some("username"));
+        assert_eq!(closest("EMAL", columns, 100), Some("email"));
+        assert_eq!(closest("created_at", columns, 100), None);
+        // user_idx and user_ids are both one edit away
+        assert_eq!(
+            closest("user_idz", ["user_idx", "user_ids"], 100),
+            Some("user_idx")
+        );
+
+        // Only `budget` candidates of a plausible length are compared
+        assert_eq!(closest("emal", columns, 1), None);
+        assert_eq!(closest("emal", columns, 2), Some("email"));
+    }
+}
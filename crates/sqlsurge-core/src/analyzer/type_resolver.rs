@@ -4,10 +4,13 @@
 //!
 //! **Supported:**
 //! - WHERE clause type checking (E0003)
-//! - JOIN condition type checking (E0007)
+//! - JOIN condition type checking (E0007), including the implicit joins of
+//!   `UPDATE ... FROM` and `DELETE ... USING`
 //! - Binary operators: comparisons (=, !=, <, >, <=, >=), arithmetic (+, -, *, /, %)
 //! - Nested expressions: `(a + b) * 2 = c`
 //! - Numeric type compatibility (INTEGER → BIGINT implicit casts)
+//! - COALESCE/NULLIF/GREATEST/LEAST arguments must share a type, which is
+//!   the type of the call
 //!
 //! **TODO (Not Yet Implemented):**
 //! - INSERT VALUES type checking: `INSERT INTO users (id) VALUES ('text')` → E0003
@@ -24,14 +27,27 @@
 //! - ROI for remaining features: INSERT/UPDATE (~15%), CAST (~5%), others (~5%)
 //! - Type inference is performed in a separate pass after name resolution
 
-use sqlparser::ast::{BinaryOperator, Expr, Query, Select, Spanned, Statement, Value};
+use sqlparser::ast::{
+    Assignment, AssignmentTarget, BinaryOperator, Expr, Function, Insert, Query, Select, SetExpr,
+    Spanned, Statement, TableFactor, TableWithJoins, UnaryOperator, Value,
+};
+use sqlparser::tokenizer::Span as SqlSpan;
 use std::collections::HashMap;
 
+use crate::dialect::SqlDialect;
 use crate::error::{Diagnostic, DiagnosticKind, Span};
-use crate::schema::{Catalog, QualifiedName};
+use crate::idents::object_name_to_qualified;
+use crate::schema::{Catalog, FunctionSig};
 use crate::types::{SqlType, TypeCompatibility};
 
+use super::functions::{
+    declared_overloads, declared_return_type, function_args, function_name, is_builtin_call,
+    set_returning_type,
+};
+use super::intern::Name;
+use super::nesting::NestingLimit;
 use super::resolver::NameResolver;
+use super::scope::Scope;
 
 /// Expression type inference result
 #[derive(Debug, Clone, PartialEq)]
@@ -42,48 +58,59 @@ enum ExpressionType {
     Unknown,
 }
 
-/// Reference to a table available in the current scope
-#[derive(Debug, Clone)]
-struct TableRef {
-    /// Qualified table name in catalog
-    table_name: QualifiedName,
-    /// If this is a VIEW, the column names from the view definition
-    view_columns: Option<Vec<String>>,
-    /// If this is a derived table, the inferred column names
-    derived_columns: Option<Vec<String>>,
-}
-
 /// Type resolver for SQL expressions
 pub struct TypeResolver<'a> {
     catalog: &'a Catalog,
     /// Current scope's table references (alias or name -> TableRef)
-    tables: HashMap<String, TableRef>,
+    tables: Scope,
     /// Collected diagnostics
     diagnostics: Vec<Diagnostic>,
+    /// SQL dialect (affects dialect-specific coercions)
+    dialect: SqlDialect,
+    /// Expression nesting depth
+    nesting: NestingLimit,
+    /// Types of SELECT output aliases (lowercased), visible in GROUP BY / ORDER BY
+    select_aliases: HashMap<String, ExpressionType>,
+    /// Whether comparisons relying on implicit casts are reported (W0008)
+    implicit_casts: bool,
 }
 
 impl<'a> TypeResolver<'a> {
     /// Create a new type resolver
+    #[cfg(test)]
     pub fn new(catalog: &'a Catalog) -> Self {
+        Self::with_dialect(catalog, SqlDialect::default())
+    }
+
+    /// Create a new type resolver for the given dialect
+    pub fn with_dialect(catalog: &'a Catalog, dialect: SqlDialect) -> Self {
         Self {
             catalog,
-            tables: HashMap::new(),
+            tables: Scope::default(),
             diagnostics: Vec::new(),
+            dialect,
+            nesting: NestingLimit::default(),
+            select_aliases: HashMap::new(),
+            implicit_casts: false,
         }
     }
 
+    /// Report comparisons that rely on an implicit cast (W0008, pedantic)
+    pub fn with_implicit_casts(mut self, enabled: bool) -> Self {
+        self.implicit_casts = enabled;
+        self
+    }
+
+    /// Set the maximum nesting depth of expressions
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.nesting = NestingLimit::new(max_depth);
+        self
+    }
+
     /// Inherit scope from a NameResolver
     /// This allows TypeResolver to access the same table context as NameResolver
     pub fn inherit_scope(&mut self, resolver: &NameResolver) {
-        // Copy table references from NameResolver
-        for (key, name_table_ref) in &resolver.tables {
-            let type_table_ref = TableRef {
-                table_name: name_table_ref.table.clone(),
-                view_columns: name_table_ref.view_columns.clone(),
-                derived_columns: name_table_ref.derived_columns.clone(),
-            };
-            self.tables.insert(key.clone(), type_table_ref);
-        }
+        self.tables = resolver.tables.clone();
     }
 
     /// Check types in a statement
@@ -92,33 +119,31 @@ impl<'a> TypeResolver<'a> {
             Statement::Query(query) => {
                 self.check_query(query);
             }
-            Statement::Insert { .. } => {
-                // TODO: Check INSERT value types against column types
-                // Example: INSERT INTO users (id, name) VALUES ('text', 123)
-                //          should error because id expects INTEGER, not TEXT
-                // Implementation: Extract columns and values, infer value types, compare with column types
-                // Estimated effort: 1-1.5 hours
-                // ROI: High (85%) - common error type
+            Statement::Insert(insert) => {
+                // TODO: only UNSIGNED ranges are checked; value types aren't
+                // compared with column types yet (VALUES ('text') into INTEGER)
+                self.check_insert_values(insert);
             }
             Statement::Update {
-                selection: Some(expr),
+                table,
+                assignments,
+                selection,
                 ..
             } => {
-                // TODO: Check SET assignment types
-                // Example: UPDATE users SET id = 'text' WHERE ...
-                //          should error because id is INTEGER
-                // Implementation: Extract assignments, infer right-hand side types, compare with column types
-                // Estimated effort: 1 hour
-                // ROI: High (85%) - common error type
-                self.check_expr_recursive(expr);
-            }
-            Statement::Update { .. } => {
-                // No WHERE clause, nothing to check yet
+                // TODO: likewise, SET values aren't compared with column types
+                self.check_update_assignments(table, assignments);
+                if let Some(expr) = selection {
+                    self.check_joining_where(expr);
+                }
             }
             Statement::Delete(delete) => {
-                // WHERE condition type checking is already implemented
                 if let Some(ref selection) = delete.selection {
-                    self.check_expr_recursive(selection);
+                    self.check_joining_where(selection);
+                }
+            }
+            Statement::CreateTable(create) => {
+                if let Some(query) = &create.query {
+                    self.check_query(query);
                 }
             }
             _ => {}
@@ -130,6 +155,16 @@ impl<'a> TypeResolver<'a> {
         // Check the main body
         if let sqlparser::ast::SetExpr::Select(select) = &*query.body {
             self.check_select(select);
+
+            // ORDER BY may refer to output columns by alias
+            if let Some(order_by) = &query.order_by {
+                let aliases = self.projection_alias_types(select);
+                let saved_aliases = std::mem::replace(&mut self.select_aliases, aliases);
+                for ob in &order_by.exprs {
+                    self.check_expr_recursive(&ob.expr);
+                }
+                self.select_aliases = saved_aliases;
+            }
         }
         // TODO: Handle UNION, INTERSECT, EXCEPT
     }
@@ -162,7 +197,29 @@ impl<'a> TypeResolver<'a> {
             self.check_expr_recursive(selection);
         }
 
-        // TODO: Check HAVING, GROUP BY, etc.
+        // Check GROUP BY (output aliases are in scope)
+        if let sqlparser::ast::GroupByExpr::Expressions(exprs, _) = &select.group_by {
+            let aliases = self.projection_alias_types(select);
+            let saved_aliases = std::mem::replace(&mut self.select_aliases, aliases);
+            for expr in exprs {
+                self.check_expr_recursive(expr);
+            }
+            self.select_aliases = saved_aliases;
+        }
+
+        // TODO: Check HAVING
+    }
+
+    /// Inferred types of the aliased expressions in a SELECT list
+    fn projection_alias_types(&mut self, select: &Select) -> HashMap<String, ExpressionType> {
+        let mut aliases = HashMap::new();
+        for item in &select.projection {
+            if let sqlparser::ast::SelectItem::ExprWithAlias { expr, alias } = item {
+                let expr_type = self.infer_expr_type(expr);
+                aliases.insert(alias.value.to_lowercase(), expr_type);
+            }
+        }
+        aliases
     }
 
     /// Check types in a JOIN condition
@@ -200,14 +257,9 @@ impl<'a> TypeResolver<'a> {
                     if let (ExpressionType::Known(lt), ExpressionType::Known(rt)) =
                         (left_type, right_type)
                     {
-                        // Check compatibility in both directions (comparison is symmetric)
-                        let compat_lr = lt.is_compatible_with(&rt);
-                        let compat_rl = rt.is_compatible_with(&lt);
-
-                        // If either direction allows implicit cast, the comparison is valid
-                        if compat_lr == TypeCompatibility::ExplicitCast
-                            && compat_rl == TypeCompatibility::ExplicitCast
-                        {
+                        if self.is_comparable(&lt, &rt) {
+                            self.check_implicit_cast(left, right, &lt, &rt);
+                        } else {
                             let span = Span::from_sqlparser(&left.span());
                             self.diagnostics.push(
                                 Diagnostic::error(
@@ -243,6 +295,76 @@ impl<'a> TypeResolver<'a> {
         }
     }
 
+    /// Check the WHERE clause of an UPDATE or DELETE, which joins the target
+    /// table with the tables of `UPDATE ... FROM` / `DELETE ... USING`
+    ///
+    /// Top-level comparisons between columns of two different relations are
+    /// the join conditions, and are checked like a JOIN ON (E0007); the rest
+    /// of the clause is checked as usual.
+    fn check_joining_where(&mut self, expr: &Expr) {
+        if !self.nesting.enter() {
+            return;
+        }
+        match expr {
+            Expr::BinaryOp {
+                left,
+                op: BinaryOperator::And,
+                right,
+            } => {
+                self.check_joining_where(left);
+                self.check_joining_where(right);
+            }
+            Expr::Nested(inner) => self.check_joining_where(inner),
+            Expr::BinaryOp { left, op, right }
+                if self.is_comparison_operator(op) && self.joins_relations(left, right) =>
+            {
+                self.check_join_on_expr(expr);
+            }
+            _ => self.check_expr(expr),
+        }
+        self.nesting.leave();
+    }
+
+    /// Whether both sides are columns, of two different relations in scope
+    fn joins_relations(&self, left: &Expr, right: &Expr) -> bool {
+        match (self.column_relation(left), self.column_relation(right)) {
+            (Some(l), Some(r)) => l != r,
+            _ => false,
+        }
+    }
+
+    /// The scope key of the relation a column reference belongs to, if known
+    fn column_relation(&self, expr: &Expr) -> Option<&Name> {
+        match expr {
+            Expr::Nested(inner) => self.column_relation(inner),
+            Expr::CompoundIdentifier(parts) if parts.len() == 2 => self
+                .tables
+                .get_key_value(parts[0].value.as_str())
+                .map(|(key, _)| key),
+            Expr::Identifier(ident) => {
+                let mut owners = self.scope_candidates(&ident.value).filter(|key| {
+                    let table_ref = &self.tables[*key];
+                    match table_ref
+                        .derived_columns
+                        .as_ref()
+                        .or(table_ref.view_columns.as_ref())
+                    {
+                        Some(columns) => columns.iter().any(|c| c == &ident.value),
+                        None => self
+                            .catalog
+                            .get_table(&table_ref.table)
+                            .is_some_and(|def| def.get_column(&ident.value).is_some()),
+                    }
+                });
+                match (owners.next(), owners.next()) {
+                    (Some(key), None) => Some(key),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
     /// Check if an operator is a comparison operator
     fn is_comparison_operator(&self, op: &BinaryOperator) -> bool {
         matches!(
@@ -258,13 +380,17 @@ impl<'a> TypeResolver<'a> {
 
     /// Recursively check types in an expression
     fn check_expr_recursive(&mut self, expr: &Expr) {
+        if !self.nesting.enter() {
+            return;
+        }
+        self.check_expr(expr);
+        self.nesting.leave();
+    }
+
+    fn check_expr(&mut self, expr: &Expr) {
         match expr {
-            Expr::BinaryOp { left, op, right } => {
-                // Check the binary operation
-                self.check_binary_op(left, op, right);
-                // Recursively check subexpressions
-                self.check_expr_recursive(left);
-                self.check_expr_recursive(right);
+            Expr::BinaryOp { .. } => {
+                self.check_binary_chain(expr);
             }
             Expr::Nested(inner) => {
                 self.check_expr_recursive(inner);
@@ -304,19 +430,229 @@ impl<'a> TypeResolver<'a> {
                     self.check_expr_recursive(else_res);
                 }
             }
+            Expr::Function(func) => {
+                self.check_function_call(func);
+                self.check_common_type_arguments(func);
+                for arg in function_args(func) {
+                    self.check_expr_recursive(arg);
+                }
+            }
             _ => {
                 // Base case: leaf expressions like identifiers, literals
             }
         }
     }
 
+    /// Check a call to a function declared in the catalog: the argument
+    /// count (E0017) and, except under MySQL (which converts arguments like
+    /// assignments), the argument types (E0003)
+    ///
+    /// Undeclared functions are not checked, nor are built-in functions a
+    /// schema adds overloads to.
+    fn check_function_call(&mut self, func: &Function) {
+        let name = function_name(func);
+        let overloads = declared_overloads(self.catalog, func);
+        if overloads.is_empty() || is_builtin_call(func) {
+            return;
+        }
+        let args = function_args(func);
+        let declared = overloads
+            .iter()
+            .map(FunctionSig::display)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let name_span = func.name.0.last().map(|i| Span::from_sqlparser(&i.span));
+
+        let candidates: Vec<&FunctionSig> = overloads
+            .iter()
+            .filter(|sig| sig.accepts(args.len()))
+            .collect();
+        if candidates.is_empty() {
+            let mut counts: Vec<String> = overloads
+                .iter()
+                .map(|sig| match sig.variadic {
+                    true => format!("at least {}", sig.args.len()),
+                    false => sig.args.len().to_string(),
+                })
+                .collect();
+            counts.dedup();
+            let mut diag = Diagnostic::error(
+                DiagnosticKind::FunctionArgumentCount,
+                format!(
+                    "Function '{}' takes {} argument(s), but got {}",
+                    name,
+                    counts.join(" or "),
+                    args.len()
+                ),
+            )
+            .with_help(format!("Declared as {}", declared));
+            if let Some(span) = name_span {
+                diag = diag.with_span(span);
+            }
+            self.diagnostics.push(diag);
+            return;
+        }
+        if self.dialect == SqlDialect::MySQL {
+            return;
+        }
+
+        let arg_types: Vec<ExpressionType> =
+            args.iter().map(|arg| self.infer_expr_type(arg)).collect();
+        let mismatches: Vec<Option<(usize, &SqlType, &SqlType)>> = candidates
+            .iter()
+            .map(|sig| argument_mismatch(sig, &args, &arg_types))
+            .collect();
+        // Report the first mismatch of the first overload of this arity
+        let Some(Some((index, expected, found))) = mismatches
+            .iter()
+            .all(Option::is_some)
+            .then(|| mismatches[0])
+        else {
+            return;
+        };
+        self.diagnostics.push(
+            Diagnostic::error(
+                DiagnosticKind::TypeMismatch,
+                format!(
+                    "Argument {} of function '{}' must be {}, found {}",
+                    index + 1,
+                    name,
+                    expected.display_name(),
+                    found.display_name()
+                ),
+            )
+            .with_span(Span::from_sqlparser(&args[index].span()))
+            .with_help(format!("Declared as {}", declared)),
+        );
+    }
+
+    /// Check that the arguments of COALESCE, NULLIF, GREATEST and LEAST
+    /// have a common type (E0003)
+    ///
+    /// The first argument that can't be matched with the type of those
+    /// before it is reported. NULL and parameters match anything; an
+    /// argument of unknown type ends the check.
+    fn check_common_type_arguments(&mut self, func: &Function) {
+        let name = function_name(func);
+        if !is_common_type_function(&name) || !declared_overloads(self.catalog, func).is_empty() {
+            return;
+        }
+        if let Err((arg, common, found)) = self.common_argument_type(func) {
+            self.diagnostics.push(
+                Diagnostic::error(
+                    DiagnosticKind::TypeMismatch,
+                    format!(
+                        "{} types {} and {} cannot be matched",
+                        name.to_uppercase(),
+                        common.display_name(),
+                        found.display_name()
+                    ),
+                )
+                // A literal has no span; the call does
+                .with_span(Span::from_sqlparser(&non_empty_span(arg, func.span())))
+                .with_help(format!(
+                    "All arguments must have a common type; cast this one to {} \
+                     (e.g. CAST({} AS {}))",
+                    common.display_name(),
+                    arg,
+                    common.display_name()
+                )),
+            );
+        }
+    }
+
+    /// The common type of the arguments of a COALESCE-like call (`None` when
+    /// no argument has a known type), or the first argument that doesn't
+    /// match the type of the preceding ones, with both types
+    fn common_argument_type<'f>(
+        &mut self,
+        func: &'f Function,
+    ) -> Result<Option<SqlType>, (&'f Expr, SqlType, SqlType)> {
+        let mut common: Option<SqlType> = None;
+        for arg in function_args(func) {
+            if is_wildcard_argument(arg) {
+                continue;
+            }
+            let ExpressionType::Known(arg_type) = self.infer_expr_type(arg) else {
+                return Ok(None);
+            };
+            common = Some(match common {
+                None => arg_type,
+                Some(common) => match self.unify(&common, &arg_type) {
+                    Some(unified) => unified,
+                    None => return Err((arg, common, arg_type)),
+                },
+            });
+        }
+        Ok(common)
+    }
+
+    /// The type two values convert to when combined, if they can be
+    ///
+    /// The narrower type is implicitly cast to the wider one.
+    fn unify(&self, a: &SqlType, b: &SqlType) -> Option<SqlType> {
+        if a.compatibility_in(b, self.dialect) != TypeCompatibility::ExplicitCast {
+            Some(b.clone())
+        } else if b.compatibility_in(a, self.dialect) != TypeCompatibility::ExplicitCast
+            || self.is_comparable(a, b)
+        {
+            Some(a.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Check a left-deep chain of binary operations (`a + b + c`, `x OR y OR z`)
+    ///
+    /// The chain is walked iteratively and each operand type is inferred once,
+    /// so long generated chains cost neither stack nor quadratic time.
+    /// Diagnostics are reported outermost operation first, then operands left to right.
+    fn check_binary_chain(&mut self, expr: &Expr) {
+        let mut chain = Vec::new();
+        let mut leaf = expr;
+        while let Expr::BinaryOp { left, op, right } = leaf {
+            chain.push((left.as_ref(), op, right.as_ref()));
+            leaf = left;
+        }
+
+        // Infer types bottom-up, checking each operation along the way
+        let mut left_type = self.infer_expr_type(leaf);
+        let mut found = vec![Vec::new(); chain.len()];
+        for (i, (left, op, right)) in chain.iter().enumerate().rev() {
+            let right_type = self.infer_expr_type(right);
+            let start = self.diagnostics.len();
+            self.check_binary_op(left, op, right, &left_type, &right_type);
+            found[i] = self.diagnostics.split_off(start);
+            left_type = self.binary_op_result_type(left_type, op, right_type);
+        }
+        self.diagnostics.extend(found.into_iter().flatten());
+
+        // Recursively check operands
+        self.check_expr_recursive(leaf);
+        for (_, _, right) in chain.iter().rev() {
+            self.check_expr_recursive(right);
+        }
+    }
+
     /// Check type compatibility in a binary operation
-    fn check_binary_op(&mut self, left: &Expr, op: &BinaryOperator, right: &Expr) {
-        let left_type = self.infer_expr_type(left);
-        let right_type = self.infer_expr_type(right);
+    fn check_binary_op(
+        &mut self,
+        left: &Expr,
+        op: &BinaryOperator,
+        right: &Expr,
+        left_type: &ExpressionType,
+        right_type: &ExpressionType,
+    ) {
+        if self.is_comparison_operator(op) {
+            self.check_unsigned_comparison(left, right);
+            self.check_unsigned_comparison(right, left);
+        }
 
         // Only check if both types are known
         if let (ExpressionType::Known(lt), ExpressionType::Known(rt)) = (left_type, right_type) {
+            if self.is_comparison_operator(op) && self.is_comparable(lt, rt) {
+                self.check_implicit_cast(left, right, lt, rt);
+            }
             match op {
                 // Comparison operators
                 BinaryOperator::Eq
@@ -324,30 +660,25 @@ impl<'a> TypeResolver<'a> {
                 | BinaryOperator::Lt
                 | BinaryOperator::LtEq
                 | BinaryOperator::Gt
-                | BinaryOperator::GtEq => {
-                    // Check compatibility in both directions (comparison is symmetric)
-                    let compat_lr = lt.is_compatible_with(&rt);
-                    let compat_rl = rt.is_compatible_with(&lt);
-
-                    // If either direction allows implicit cast, the comparison is valid
-                    if compat_lr == TypeCompatibility::ExplicitCast
-                        && compat_rl == TypeCompatibility::ExplicitCast
-                    {
-                        // Types are not implicitly compatible in either direction
-                        let span = Span::from_sqlparser(&left.span());
-                        self.diagnostics.push(
-                            Diagnostic::error(
-                                DiagnosticKind::TypeMismatch,
-                                format!(
-                                    "Type mismatch: cannot compare {} with {}",
-                                    lt.display_name(),
-                                    rt.display_name()
-                                ),
-                            )
-                            .with_span(span)
-                            .with_help("Types are not implicitly compatible. Consider using explicit CAST."),
-                        );
-                    }
+                | BinaryOperator::GtEq
+                    if !self.is_comparable(lt, rt) =>
+                {
+                    // Types are not implicitly compatible in either direction
+                    let span = Span::from_sqlparser(&left.span());
+                    self.diagnostics.push(
+                        Diagnostic::error(
+                            DiagnosticKind::TypeMismatch,
+                            format!(
+                                "Type mismatch: cannot compare {} with {}",
+                                lt.display_name(),
+                                rt.display_name()
+                            ),
+                        )
+                        .with_span(span)
+                        .with_help(
+                            "Types are not implicitly compatible. Consider using explicit CAST.",
+                        ),
+                    );
                 }
                 // Arithmetic operators
                 BinaryOperator::Plus
@@ -356,7 +687,7 @@ impl<'a> TypeResolver<'a> {
                 | BinaryOperator::Divide
                 | BinaryOperator::Modulo => {
                     // Check if both types are numeric
-                    if !self.is_numeric_type(&lt) {
+                    if !self.is_numeric_type(lt) {
                         let span = Span::from_sqlparser(&left.span());
                         self.diagnostics.push(
                             Diagnostic::error(
@@ -369,7 +700,7 @@ impl<'a> TypeResolver<'a> {
                             .with_span(span),
                         );
                     }
-                    if !self.is_numeric_type(&rt) {
+                    if !self.is_numeric_type(rt) {
                         let span = Span::from_sqlparser(&right.span());
                         self.diagnostics.push(
                             Diagnostic::error(
@@ -395,10 +726,208 @@ impl<'a> TypeResolver<'a> {
         }
     }
 
+    /// Check whether two types can be compared without an explicit cast
+    fn is_comparable(&self, lt: &SqlType, rt: &SqlType) -> bool {
+        // Check compatibility in both directions (comparison is symmetric).
+        // If either direction allows implicit cast, the comparison is valid
+        if lt.compatibility_in(rt, self.dialect) != TypeCompatibility::ExplicitCast
+            || rt.compatibility_in(lt, self.dialect) != TypeCompatibility::ExplicitCast
+        {
+            return true;
+        }
+
+        // MySQL's BOOLEAN is TINYINT(1), so booleans and integers compare freely
+        self.dialect == SqlDialect::MySQL
+            && matches!(
+                (lt.without_unsigned(), rt.without_unsigned()),
+                (SqlType::Boolean, other) | (other, SqlType::Boolean)
+                    if self.is_integer_type(other)
+            )
+    }
+
+    /// Pedantic: report a comparison that relies on an implicit cast (W0008)
+    ///
+    /// Literals, NULL and parameters take the type of the other side, and
+    /// types differing only in a modifier (varchar(50) vs varchar(100)) need
+    /// no conversion, so neither is reported.
+    fn check_implicit_cast(&mut self, left: &Expr, right: &Expr, lt: &SqlType, rt: &SqlType) {
+        if !self.implicit_casts
+            || is_literal(left)
+            || is_literal(right)
+            || lt.without_modifiers() == rt.without_modifiers()
+        {
+            return;
+        }
+        // The narrower side is converted to the wider type
+        let (narrow, target) =
+            if lt.compatibility_in(rt, self.dialect) == TypeCompatibility::ImplicitCast {
+                (left, rt)
+            } else if rt.compatibility_in(lt, self.dialect) == TypeCompatibility::ImplicitCast {
+                (right, lt)
+            } else {
+                return;
+            };
+        let target = target.display_name();
+
+        let mut diag = Diagnostic::warning(
+            DiagnosticKind::ImplicitCast,
+            format!(
+                "Comparison of {} with {} relies on an implicit cast",
+                lt.display_name(),
+                rt.display_name()
+            ),
+        )
+        .with_span(Span::from_sqlparser(&left.span().union(&right.span())));
+        if self.dialect == SqlDialect::PostgreSQL && is_cast_operand(narrow) {
+            let end = narrow.span().end;
+            diag = diag
+                .with_help(format!("Cast explicitly: {}::{}", narrow, target))
+                .with_fix(
+                    format!("Cast to {}", target),
+                    Span::insertion(end.line as usize, end.column as usize),
+                    format!("::{}", target),
+                );
+        } else {
+            diag = diag.with_help(format!("Cast explicitly: CAST({} AS {})", narrow, target));
+        }
+        self.diagnostics.push(diag);
+    }
+
+    /// Warn when an UNSIGNED column is compared to a negative literal
+    fn check_unsigned_comparison(&mut self, column: &Expr, literal: &Expr) {
+        if !is_negative_literal(literal) {
+            return;
+        }
+        if let ExpressionType::Known(col_type) = self.infer_expr_type(column) {
+            if col_type.is_unsigned() {
+                self.diagnostics.push(
+                    Diagnostic::warning(
+                        DiagnosticKind::TypeMismatch,
+                        format!(
+                            "Comparing {} column '{}' with negative value {}",
+                            col_type.display_name(),
+                            column,
+                            literal
+                        ),
+                    )
+                    // Literals have no span; the column does
+                    .with_span(Span::from_sqlparser(&column.span().union(&literal.span())))
+                    .with_help("UNSIGNED columns cannot hold negative values"),
+                );
+            }
+        }
+    }
+
+    /// Warn when a negative literal is inserted into an UNSIGNED column
+    fn check_insert_values(&mut self, insert: &Insert) {
+        let table_name = object_name_to_qualified(&insert.table_name);
+        let Some(table_def) = self.catalog.get_table(&table_name) else {
+            return;
+        };
+        let Some(source) = &insert.source else {
+            return;
+        };
+        let SetExpr::Values(values) = source.body.as_ref() else {
+            return;
+        };
+
+        // Literals have no span: a value is reported at its target column,
+        // or at the table name when no columns are listed
+        let columns: Vec<(&str, SqlSpan)> = if insert.columns.is_empty() {
+            let span = insert.table_name.span();
+            table_def
+                .column_names()
+                .into_iter()
+                .map(|name| (name, span))
+                .collect()
+        } else {
+            insert
+                .columns
+                .iter()
+                .map(|c| (c.value.as_str(), c.span))
+                .collect()
+        };
+
+        for row in &values.rows {
+            for ((col_name, col_span), expr) in columns.iter().zip(row) {
+                let Some(col_def) = table_def.get_column(col_name) else {
+                    continue;
+                };
+                if col_def.data_type.is_unsigned() && is_negative_literal(expr) {
+                    self.diagnostics.push(
+                        Diagnostic::warning(
+                            DiagnosticKind::TypeMismatch,
+                            format!(
+                                "Inserting negative value {} into {} column '{}'",
+                                expr,
+                                col_def.data_type.display_name(),
+                                self.dialect.display_identifier(&col_def.name)
+                            ),
+                        )
+                        .with_span(Span::from_sqlparser(&non_empty_span(expr, *col_span)))
+                        .with_help("UNSIGNED columns cannot hold negative values"),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Warn when an UPDATE sets an UNSIGNED column to a negative literal
+    fn check_update_assignments(&mut self, table: &TableWithJoins, assignments: &[Assignment]) {
+        let TableFactor::Table { name, .. } = &table.relation else {
+            return;
+        };
+        let Some(table_def) = self.catalog.get_table(&object_name_to_qualified(name)) else {
+            return;
+        };
+        for assignment in assignments {
+            let AssignmentTarget::ColumnName(target) = &assignment.target else {
+                continue;
+            };
+            let Some(column) = target.0.last() else {
+                continue;
+            };
+            let Some(col_def) = table_def.get_column(&column.value) else {
+                continue;
+            };
+            if col_def.data_type.is_unsigned() && is_negative_literal(&assignment.value) {
+                self.diagnostics.push(
+                    Diagnostic::warning(
+                        DiagnosticKind::TypeMismatch,
+                        format!(
+                            "Setting {} column '{}' to negative value {}",
+                            col_def.data_type.display_name(),
+                            self.dialect.display_identifier(&col_def.name),
+                            assignment.value
+                        ),
+                    )
+                    // Literals have no span; the target column does
+                    .with_span(Span::from_sqlparser(&non_empty_span(
+                        &assignment.value,
+                        column.span,
+                    )))
+                    .with_help("UNSIGNED columns cannot hold negative values"),
+                );
+            }
+        }
+    }
+
+    /// Check if a type is an integer type
+    fn is_integer_type(&self, sql_type: &SqlType) -> bool {
+        matches!(
+            sql_type.without_unsigned(),
+            SqlType::TinyInt
+                | SqlType::SmallInt
+                | SqlType::MediumInt
+                | SqlType::Integer
+                | SqlType::BigInt
+        )
+    }
+
     /// Check if a type is numeric
     fn is_numeric_type(&self, sql_type: &SqlType) -> bool {
         matches!(
-            sql_type,
+            sql_type.without_unsigned(),
             SqlType::TinyInt
                 | SqlType::SmallInt
                 | SqlType::MediumInt
@@ -411,12 +940,22 @@ impl<'a> TypeResolver<'a> {
     }
 
     /// Consume the resolver and return collected diagnostics
-    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+    pub fn into_diagnostics(mut self) -> Vec<Diagnostic> {
+        self.diagnostics.extend(self.nesting.diagnostic());
         self.diagnostics
     }
 
     /// Infer the type of an expression
     fn infer_expr_type(&mut self, expr: &Expr) -> ExpressionType {
+        if !self.nesting.enter() {
+            return ExpressionType::Unknown;
+        }
+        let expr_type = self.infer_expr_type_inner(expr);
+        self.nesting.leave();
+        expr_type
+    }
+
+    fn infer_expr_type_inner(&mut self, expr: &Expr) -> ExpressionType {
         match expr {
             Expr::Value(value) => self.infer_literal_type(value),
             Expr::Identifier(ident) => self.infer_column_type_from_ident(&ident.value),
@@ -433,29 +972,72 @@ impl<'a> TypeResolver<'a> {
                 // Recursively infer type of nested expression
                 self.infer_expr_type(inner)
             }
-            Expr::BinaryOp { left, op, right } => {
-                // Infer result type of binary operation
-                self.infer_binary_op_result_type(left, op, right)
+            Expr::BinaryOp { .. } => {
+                // Infer result type of a (possibly long, left-deep) operation chain
+                let mut chain = Vec::new();
+                let mut leaf = expr;
+                while let Expr::BinaryOp { left, op, right } = leaf {
+                    chain.push((op, right));
+                    leaf = left;
+                }
+                let mut result = self.infer_expr_type(leaf);
+                for (op, right) in chain.into_iter().rev() {
+                    let right_type = self.infer_expr_type(right);
+                    result = self.binary_op_result_type(result, op, right_type);
+                }
+                result
+            }
+            Expr::Function(func) => {
+                // Declared functions, then set-returning ones (unnest,
+                // generate_series, ...)
+                let name = function_name(func);
+                if let Some(returns) = declared_return_type(self.catalog, func) {
+                    return match returns {
+                        SqlType::Unknown => ExpressionType::Unknown,
+                        returns => ExpressionType::Known(returns),
+                    };
+                }
+                if is_common_type_function(&name) {
+                    // NULLIF returns its first argument
+                    let common = match name.as_str() {
+                        "nullif" => function_args(func)
+                            .first()
+                            .map_or(ExpressionType::Unknown, |arg| self.infer_expr_type(arg)),
+                        _ => match self.common_argument_type(func) {
+                            Ok(Some(common)) => ExpressionType::Known(common),
+                            _ => ExpressionType::Unknown,
+                        },
+                    };
+                    return common;
+                }
+                let first_type = match function_args(func).first() {
+                    Some(arg) => match self.infer_expr_type(arg) {
+                        ExpressionType::Known(t) => t,
+                        ExpressionType::Unknown => SqlType::Unknown,
+                    },
+                    None => SqlType::Unknown,
+                };
+                match set_returning_type(&function_name(func), &first_type) {
+                    Some((t, _)) if t != SqlType::Unknown => ExpressionType::Known(t),
+                    _ => ExpressionType::Unknown,
+                }
             }
             // TODO: Add support for more expression types:
             // - Expr::Cast => Return the target type directly (easy, 30 min, ROI 60%)
-            // - Expr::Function => Lookup function signature table (complex, 2-3 hours, ROI 40%)
+            // - Expr::Function => Lookup function signature table for scalar and aggregate functions
             // - Expr::Case => Infer from THEN/ELSE branches (medium, 1-1.5 hours, ROI 20%)
             // - Expr::Subquery => Infer from SELECT projection (complex, 4-6 hours, ROI 15%)
             _ => ExpressionType::Unknown,
         }
     }
 
-    /// Infer the result type of a binary operation
-    fn infer_binary_op_result_type(
-        &mut self,
-        left: &Expr,
+    /// Result type of a binary operation given its operand types
+    fn binary_op_result_type(
+        &self,
+        left_type: ExpressionType,
         op: &BinaryOperator,
-        right: &Expr,
+        right_type: ExpressionType,
     ) -> ExpressionType {
-        let left_type = self.infer_expr_type(left);
-        let right_type = self.infer_expr_type(right);
-
         match (left_type, right_type) {
             (ExpressionType::Known(lt), ExpressionType::Known(rt)) => {
                 match op {
@@ -484,6 +1066,15 @@ impl<'a> TypeResolver<'a> {
                     BinaryOperator::And | BinaryOperator::Or => {
                         ExpressionType::Known(SqlType::Boolean)
                     }
+                    // JSON access: `->>` yields text, `->` keeps the JSON type
+                    BinaryOperator::LongArrow | BinaryOperator::HashLongArrow => {
+                        ExpressionType::Known(SqlType::Text)
+                    }
+                    BinaryOperator::Arrow | BinaryOperator::HashArrow
+                        if matches!(lt, SqlType::Json | SqlType::Jsonb) =>
+                    {
+                        ExpressionType::Known(lt)
+                    }
                     _ => ExpressionType::Unknown,
                 }
             }
@@ -511,12 +1102,20 @@ impl<'a> TypeResolver<'a> {
         }
     }
 
+    /// Scope names of the relations that may have an unqualified column:
+    /// those the scope's column index lists for it, and those it doesn't index
+    fn scope_candidates<'s>(&'s self, column: &str) -> impl Iterator<Item = &'s Name> {
+        let index = self.tables.column_index(self.catalog);
+        index.relations_with(column).iter().chain(index.unindexed())
+    }
+
     /// Infer type from an unqualified column identifier
     fn infer_column_type_from_ident(&self, col_name: &str) -> ExpressionType {
         // Search through all tables in scope to find the column
         let mut found_type: Option<SqlType> = None;
 
-        for table_ref in self.tables.values() {
+        for key in self.scope_candidates(col_name) {
+            let table_ref = &self.tables[key];
             // Check if this is a derived table or view
             if let Some(ref derived_cols) = table_ref.derived_columns {
                 if derived_cols.contains(&col_name.to_string()) {
@@ -530,7 +1129,7 @@ impl<'a> TypeResolver<'a> {
                 }
             } else {
                 // Regular table - look up in catalog
-                if let Some(table_def) = self.catalog.get_table(&table_ref.table_name) {
+                if let Some(table_def) = self.catalog.get_table(&table_ref.table) {
                     if let Some(col_def) = table_def.get_column(col_name) {
                         if found_type.is_some() {
                             // Column is ambiguous (exists in multiple tables)
@@ -542,13 +1141,36 @@ impl<'a> TypeResolver<'a> {
             }
         }
 
-        found_type.map_or(ExpressionType::Unknown, ExpressionType::Known)
+        match found_type {
+            Some(t) => ExpressionType::Known(t),
+            // Output alias (in GROUP BY / ORDER BY)
+            None => self
+                .select_aliases
+                .get(&col_name.to_lowercase())
+                .cloned()
+                .unwrap_or(ExpressionType::Unknown),
+        }
     }
 
     /// Infer type from a qualified column identifier (table.column)
     fn infer_column_type_qualified(&self, table_name: &str, col_name: &str) -> ExpressionType {
-        // Look up table in scope
-        if let Some(table_ref) = self.tables.get(table_name) {
+        // Look up table in scope; MySQL also accepts the name of an aliased
+        // table, when only one table has that name
+        let table_ref = self.tables.get(table_name).or_else(|| {
+            if self.dialect != SqlDialect::MySQL {
+                return None;
+            }
+            let mut refs = self.tables.iter().filter(|(key, t)| {
+                t.derived_columns.is_none()
+                    && t.table.name == table_name
+                    && key.as_ref() != table_name
+            });
+            match (refs.next(), refs.next()) {
+                (Some((_, t)), None) => Some(t),
+                _ => None,
+            }
+        });
+        if let Some(table_ref) = table_ref {
             // Check if this is a derived table or view
             if table_ref.derived_columns.is_some() || table_ref.view_columns.is_some() {
                 // We can't infer types for derived tables or views yet
@@ -556,7 +1178,7 @@ impl<'a> TypeResolver<'a> {
             }
 
             // Regular table - look up in catalog
-            if let Some(table_def) = self.catalog.get_table(&table_ref.table_name) {
+            if let Some(table_def) = self.catalog.get_table(&table_ref.table) {
                 if let Some(col_def) = table_def.get_column(col_name) {
                     return ExpressionType::Known(col_def.data_type.clone());
                 }
@@ -567,6 +1189,90 @@ impl<'a> TypeResolver<'a> {
     }
 }
 
+/// The first argument of a call whose type can't be converted implicitly to
+/// the declared one, with the declared and the found type
+///
+/// Quoted literals are untyped and convert to any type, so they always match.
+fn argument_mismatch<'t>(
+    sig: &'t FunctionSig,
+    args: &[&Expr],
+    arg_types: &'t [ExpressionType],
+) -> Option<(usize, &'t SqlType, &'t SqlType)> {
+    args.iter()
+        .zip(arg_types)
+        .enumerate()
+        .find_map(|(index, (arg, arg_type))| {
+            let ExpressionType::Known(found) = arg_type else {
+                return None;
+            };
+            let expected = sig.arg_type(index)?;
+            let untyped = matches!(arg, Expr::Value(Value::SingleQuotedString(_)));
+            let unknown = matches!(expected, SqlType::Unknown | SqlType::Custom(_));
+            (!untyped
+                && !unknown
+                && found.is_compatible_with(expected) == TypeCompatibility::ExplicitCast)
+                .then_some((index, expected, found))
+        })
+}
+
+/// Functions whose arguments are converted to one common type
+fn is_common_type_function(name: &str) -> bool {
+    matches!(name, "coalesce" | "nullif" | "greatest" | "least")
+}
+
+/// NULL or a parameter, which take the type of the other arguments
+fn is_wildcard_argument(expr: &Expr) -> bool {
+    match expr {
+        Expr::Value(Value::Null | Value::Placeholder(_)) => true,
+        Expr::Nested(inner) => is_wildcard_argument(inner),
+        _ => false,
+    }
+}
+
+/// Whether `expr` is a literal, NULL or a parameter placeholder
+fn is_literal(expr: &Expr) -> bool {
+    match expr {
+        Expr::Value(_) => true,
+        Expr::UnaryOp { expr, .. } | Expr::Nested(expr) => is_literal(expr),
+        _ => false,
+    }
+}
+
+/// Whether `::type` can be appended to `expr` without parenthesizing it
+fn is_cast_operand(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Identifier(_)
+            | Expr::CompoundIdentifier(_)
+            | Expr::Function(_)
+            | Expr::Nested(_)
+            | Expr::Cast { .. }
+    )
+}
+
+/// The span of `expr`, or `fallback` where it has none (literals don't)
+fn non_empty_span(expr: &Expr, fallback: SqlSpan) -> SqlSpan {
+    match expr.span() {
+        span if span == SqlSpan::empty() => fallback,
+        span => span,
+    }
+}
+
+/// Whether an expression is a negative numeric literal like `-1`
+fn is_negative_literal(expr: &Expr) -> bool {
+    match expr {
+        Expr::UnaryOp {
+            op: UnaryOperator::Minus,
+            expr,
+        } => {
+            matches!(expr.as_ref(), Expr::Value(Value::Number(n, _)) if n.parse::<f64>().is_ok_and(|v| v != 0.0))
+        }
+        Expr::Value(Value::Number(n, _)) => n.starts_with('-'),
+        Expr::Nested(inner) => is_negative_literal(inner),
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1018,6 +1724,63 @@ mod tests {
         assert_eq!(diagnostics[0].kind, DiagnosticKind::TypeMismatch);
     }
 
+    fn check_sql(schema_sql: &str, sql: &str) -> Vec<Diagnostic> {
+        let mut builder = SchemaBuilder::new();
+        builder.parse(schema_sql).unwrap();
+        let (catalog, _) = builder.build();
+
+        let dialect = crate::dialect::SqlDialect::PostgreSQL.parser_dialect();
+        let statements = sqlparser::parser::Parser::parse_sql(dialect.as_ref(), sql).unwrap();
+
+        let mut name_resolver = super::super::resolver::NameResolver::new(&catalog);
+        name_resolver.resolve_statement(&statements[0]);
+
+        let mut type_resolver = TypeResolver::new(&catalog);
+        type_resolver.inherit_scope(&name_resolver);
+        type_resolver.check_statement(&statements[0]);
+        type_resolver.into_diagnostics()
+    }
+
+    #[test]
+    fn test_coalesce_arguments_share_a_type() {
+        let schema_sql = "CREATE TABLE data (id INTEGER, total BIGINT, label TEXT);";
+
+        // INTEGER widens to BIGINT; NULL and parameters match anything
+        let diagnostics = check_sql(
+            schema_sql,
+            "SELECT COALESCE(id, NULL, total, $1), GREATEST(id, total, 0) FROM data",
+        );
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+        let diagnostics = check_sql(schema_sql, "SELECT COALESCE(id, 'none') FROM data");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::TypeMismatch);
+        assert_eq!(
+            diagnostics[0].message,
+            "COALESCE types integer and text cannot be matched"
+        );
+        // A literal has no position of its own: the call's is used
+        let span = diagnostics[0].span.unwrap();
+        assert_eq!((span.line, span.column), (1, 8));
+
+        let diagnostics = check_sql(schema_sql, "SELECT LEAST(total, id, label) FROM data");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "LEAST types bigint and text cannot be matched"
+        );
+        assert_eq!(diagnostics[0].span.unwrap().column, 25);
+
+        // The nested call is BIGINT, which doesn't compare with TEXT
+        let diagnostics = check_sql(
+            schema_sql,
+            "SELECT * FROM data WHERE COALESCE(COALESCE(id, total), 0) = label",
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::TypeMismatch);
+        assert!(diagnostics[0].message.contains("bigint"));
+    }
+
     #[test]
     fn test_complex_join_conditions() {
         let schema_sql = r#"
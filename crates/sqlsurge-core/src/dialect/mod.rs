@@ -1,10 +1,14 @@
 //! SQL dialect support
 
+mod reserved;
+
 use sqlparser::dialect::{Dialect, MySqlDialect, PostgreSqlDialect};
 use std::str::FromStr;
 
+use crate::schema::QualifiedName;
+
 /// Supported SQL dialects
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum SqlDialect {
     #[default]
     PostgreSQL,
@@ -27,6 +31,68 @@ impl SqlDialect {
             SqlDialect::MySQL => "",
         }
     }
+
+    /// Product name for messages (e.g. "PostgreSQL")
+    pub fn product_name(&self) -> &'static str {
+        match self {
+            SqlDialect::PostgreSQL => "PostgreSQL",
+            SqlDialect::MySQL => "MySQL",
+        }
+    }
+
+    /// Whether `word` is a reserved keyword, i.e. must be quoted to be used
+    /// as a table or column name (case-insensitive)
+    pub fn is_reserved(&self, word: &str) -> bool {
+        let words = match self {
+            SqlDialect::PostgreSQL => reserved::POSTGRESQL,
+            SqlDialect::MySQL => reserved::MYSQL,
+        };
+        words
+            .binary_search(&word.to_ascii_uppercase().as_str())
+            .is_ok()
+    }
+
+    /// An identifier in its minimal written form, for diagnostics: bare when
+    /// the dialect would read it back unchanged, quoted otherwise
+    ///
+    /// PostgreSQL folds bare identifiers to lowercase, so `userName` is shown
+    /// as `"userName"`; MySQL keeps the case and only quotes special names.
+    pub fn display_identifier(&self, name: &str) -> String {
+        let letter = |c: char| match self {
+            SqlDialect::PostgreSQL => c.is_ascii_lowercase(),
+            SqlDialect::MySQL => c.is_ascii_alphabetic(),
+        };
+        let mut chars = name.chars();
+        let bare = chars.next().is_some_and(|c| letter(c) || c == '_')
+            && chars.all(|c| letter(c) || c.is_ascii_digit() || c == '_' || c == '$')
+            && !self.is_reserved(name);
+        if bare {
+            name.to_string()
+        } else {
+            self.quote_identifier(name)
+        }
+    }
+
+    /// A (possibly schema-qualified) name in its minimal written form, for
+    /// diagnostics; an empty schema (MySQL's default) is left out
+    pub fn display_name(&self, name: &QualifiedName) -> String {
+        match name.schema.as_deref().filter(|s| !s.is_empty()) {
+            Some(schema) => format!(
+                "{}.{}",
+                self.display_identifier(schema),
+                self.display_identifier(&name.name)
+            ),
+            None => self.display_identifier(&name.name),
+        }
+    }
+
+    /// Quote an identifier for this dialect (`"order"` / `` `order` ``)
+    pub fn quote_identifier(&self, name: &str) -> String {
+        match self {
+            SqlDialect::PostgreSQL => format!("\"{}\"", name.replace('"', "\"\"")),
+            SqlDialect::MySQL => format!("`{}`", name.replace('`', "``")),
+        }
+    }
 }
 
 impl FromStr for SqlDialect {
@@ -56,3 +122,111 @@ impl std::fmt::Display for SqlDialect {
         }
     }
 }
+
+/// Target server version, e.g. MySQL 5.7 or PostgreSQL 12
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DialectVersion {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl DialectVersion {
+    pub const fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+}
+
+impl FromStr for DialectVersion {
+    type Err = String;
+
+    /// Parse `MAJOR` or `MAJOR.MINOR` (e.g. "16", "5.7")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid dialect version: '{}'. Expected e.g. 5.7 or 16", s);
+        let mut parts = s.trim().splitn(2, '.');
+        let major = parts
+            .next()
+            .and_then(|p| p.parse().ok())
+            .ok_or_else(invalid)?;
+        let minor = match parts.next() {
+            Some(p) => p.parse().map_err(|_| invalid())?,
+            None => 0,
+        };
+        Ok(Self { major, minor })
+    }
+}
+
+impl std::fmt::Display for DialectVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// A dialect with an optional target version, written `mysql@5.7`
+///
+/// Without a version, all features of the dialect are assumed available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DialectSpec {
+    pub dialect: SqlDialect,
+    pub version: Option<DialectVersion>,
+}
+
+impl FromStr for DialectSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('@') {
+            Some((dialect, version)) => Ok(Self {
+                dialect: dialect.parse()?,
+                version: Some(version.parse()?),
+            }),
+            None => Ok(Self {
+                dialect: s.parse()?,
+                version: None,
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for DialectSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.version {
+            Some(version) => write!(f, "{}@{}", self.dialect, version),
+            None => write!(f, "{}", self.dialect),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_identifier() {
+        let pg = SqlDialect::PostgreSQL;
+        assert_eq!(pg.display_identifier("users"), "users");
+        assert_eq!(pg.display_identifier("user_id2"), "user_id2");
+        assert_eq!(pg.display_identifier("userName"), "\"userName\"");
+        assert_eq!(pg.display_identifier("order"), "\"order\"");
+        assert_eq!(pg.display_identifier("2fa"), "\"2fa\"");
+        assert_eq!(pg.display_identifier("first name"), "\"first name\"");
+
+        let mysql = SqlDialect::MySQL;
+        assert_eq!(mysql.display_identifier("userName"), "userName");
+        assert_eq!(mysql.display_identifier("order"), "`order`");
+        assert_eq!(mysql.display_identifier("first-name"), "`first-name`");
+    }
+
+    #[test]
+    fn test_display_name() {
+        let pg = SqlDialect::PostgreSQL;
+        assert_eq!(pg.display_name(&QualifiedName::new("users")), "users");
+        assert_eq!(
+            pg.display_name(&QualifiedName::with_schema("Sales", "Orders")),
+            "\"Sales\".\"Orders\""
+        );
+        assert_eq!(
+            pg.display_name(&QualifiedName::with_schema("public", "users")),
+            "public.users"
+        );
+    }
+}
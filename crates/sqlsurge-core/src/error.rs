@@ -2,6 +2,7 @@
 
 use miette::SourceSpan;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 
 /// Source location span
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -14,6 +15,12 @@ pub struct Span {
     pub line: usize,
     /// Column number (1-indexed)
     pub column: usize,
+    /// End line number (1-indexed, 0 if unknown)
+    #[serde(default)]
+    pub end_line: usize,
+    /// End column number (1-indexed, exclusive, 0 if unknown)
+    #[serde(default)]
+    pub end_column: usize,
 }
 
 impl Span {
@@ -24,6 +31,8 @@ impl Span {
             length,
             line: 0,
             column: 0,
+            end_line: 0,
+            end_column: 0,
         }
     }
 
@@ -34,6 +43,20 @@ impl Span {
             length,
             line,
             column,
+            end_line: line,
+            end_column: column + length,
+        }
+    }
+
+    /// Zero-length span at a line and column, for insertions
+    pub fn insertion(line: usize, column: usize) -> Self {
+        Self {
+            offset: 0,
+            length: 0,
+            line,
+            column,
+            end_line: line,
+            end_column: column,
         }
     }
 
@@ -51,6 +74,8 @@ impl Span {
             length,
             line: start.line as usize,
             column: start.column as usize,
+            end_line: end.line as usize,
+            end_column: end.column as usize,
         }
     }
 }
@@ -68,6 +93,50 @@ pub enum Severity {
     Error,
     Warning,
     Info,
+    /// Advisory; rendered subtly by editors and never fails a check
+    Hint,
+}
+
+/// Configured level of a rule: any severity, or `off` to suppress it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SeverityOrOff {
+    Error,
+    Warning,
+    Info,
+    Hint,
+    Off,
+}
+
+impl SeverityOrOff {
+    /// The severity to report with, or `None` if the rule is off
+    pub fn severity(self) -> Option<Severity> {
+        match self {
+            SeverityOrOff::Error => Some(Severity::Error),
+            SeverityOrOff::Warning => Some(Severity::Warning),
+            SeverityOrOff::Info => Some(Severity::Info),
+            SeverityOrOff::Hint => Some(Severity::Hint),
+            SeverityOrOff::Off => None,
+        }
+    }
+}
+
+impl std::str::FromStr for SeverityOrOff {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "error" => Ok(SeverityOrOff::Error),
+            "warning" | "warn" => Ok(SeverityOrOff::Warning),
+            "info" => Ok(SeverityOrOff::Info),
+            "hint" => Ok(SeverityOrOff::Hint),
+            "off" => Ok(SeverityOrOff::Off),
+            _ => Err(format!(
+                "Unknown severity: '{}'. Expected error, warning, info, hint or off",
+                s
+            )),
+        }
+    }
 }
 
 /// Diagnostic message for SQL analysis
@@ -79,6 +148,47 @@ pub struct Diagnostic {
     pub span: Option<Span>,
     pub help: Option<String>,
     pub labels: Vec<Label>,
+    /// Machine-applicable edits that resolve the diagnostic
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fixes: Vec<Fix>,
+    /// The statement of the input the diagnostic belongs to, set by the analyzer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub statement: Option<StatementRef>,
+    /// Whether a schema or a query input produced the diagnostic, set by the
+    /// schema builder and the analyzer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<DiagnosticOrigin>,
+}
+
+/// The kind of input a diagnostic was found in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticOrigin {
+    /// Schema definitions, read by `SchemaBuilder`
+    Schema,
+    /// Queries, checked by `Analyzer`
+    Query,
+}
+
+/// Position of a statement in its input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatementRef {
+    /// Index of the statement in the input (0-based, counting statements
+    /// that failed to parse)
+    pub index: usize,
+    /// The statement's text, from its first token to its end
+    pub span: Span,
+}
+
+/// A suggested edit: replace the text at `span` with `replacement`
+///
+/// An insertion has a zero-length span.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fix {
+    /// What the edit does, e.g. "Cast to bigint"
+    pub message: String,
+    pub span: Span,
+    pub replacement: String,
 }
 
 /// Label for source annotations
@@ -86,6 +196,9 @@ pub struct Diagnostic {
 pub struct Label {
     pub message: String,
     pub span: Span,
+    /// Source the span points into, when not the diagnostic's own (e.g. a schema file)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
 }
 
 impl Diagnostic {
@@ -97,6 +210,9 @@ impl Diagnostic {
             span: None,
             help: None,
             labels: Vec::new(),
+            fixes: Vec::new(),
+            statement: None,
+            origin: None,
         }
     }
 
@@ -108,6 +224,37 @@ impl Diagnostic {
             span: None,
             help: None,
             labels: Vec::new(),
+            fixes: Vec::new(),
+            statement: None,
+            origin: None,
+        }
+    }
+
+    pub fn info(kind: DiagnosticKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            severity: Severity::Info,
+            message: message.into(),
+            span: None,
+            help: None,
+            labels: Vec::new(),
+            fixes: Vec::new(),
+            statement: None,
+            origin: None,
+        }
+    }
+
+    pub fn hint(kind: DiagnosticKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            severity: Severity::Hint,
+            message: message.into(),
+            span: None,
+            help: None,
+            labels: Vec::new(),
+            fixes: Vec::new(),
+            statement: None,
+            origin: None,
         }
     }
 
@@ -121,10 +268,40 @@ impl Diagnostic {
         self
     }
 
+    pub fn with_fix(
+        mut self,
+        message: impl Into<String>,
+        span: Span,
+        replacement: impl Into<String>,
+    ) -> Self {
+        self.fixes.push(Fix {
+            message: message.into(),
+            span,
+            replacement: replacement.into(),
+        });
+        self
+    }
+
     pub fn with_label(mut self, message: impl Into<String>, span: Span) -> Self {
         self.labels.push(Label {
             message: message.into(),
             span,
+            file: None,
+        });
+        self
+    }
+
+    /// Add a label pointing into another source, such as a schema file
+    pub fn with_label_in(
+        mut self,
+        message: impl Into<String>,
+        file: impl Into<String>,
+        span: Span,
+    ) -> Self {
+        self.labels.push(Label {
+            message: message.into(),
+            span,
+            file: Some(file.into()),
         });
         self
     }
@@ -152,11 +329,125 @@ pub enum DiagnosticKind {
     AmbiguousColumn,
     /// E0007: JOIN type mismatch
     JoinTypeMismatch,
+    /// E0008: Assignment to a generated (computed) column
+    GeneratedColumnAssignment,
+    /// E0009: Table, view or type defined more than once in the schema
+    DuplicateDefinition,
+    /// E0010: Feature not available in the target dialect version
+    UnsupportedFeature,
+    /// E0011: Same table name or alias used twice in one FROM clause
+    DuplicateTableReference,
+    /// E0012: Index hint names an index the table doesn't have
+    IndexNotFound,
+    /// E0013: COUNT(DISTINCT a, b) in a dialect that takes a single argument
+    MultiArgumentCountDistinct,
+    /// E0014: ORDER BY expression missing from the select list of SELECT DISTINCT
+    DistinctOrderBy,
+    /// E0015: Statement not allowed by the file's write policy (e.g. UPDATE in a read-only file)
+    PolicyViolation,
+    /// E0016: LIMIT / OFFSET / FETCH count that is negative, not an integer or references a column
+    InvalidRowLimit,
+    /// E0017: Call to a declared function with the wrong number of arguments
+    FunctionArgumentCount,
+    /// E0018: Window function in WHERE, GROUP BY, HAVING or a JOIN condition
+    MisplacedWindowFunction,
+    /// E0019: INSERT, UPDATE or DELETE on a materialized view, or REFRESH of a relation that isn't one
+    RelationKindMismatch,
+    /// W0001: Multi-row INSERT repeats a primary key / unique value (opt-in)
+    DuplicateInsertKey,
+    /// W0002: Query nested deeper than the analysis limit
+    NestingTooDeep,
+    /// W0003: Table or column named after a reserved keyword
+    ReservedIdentifier,
+    /// W0004: Foreign key action that fails at runtime (SET NULL on a NOT NULL column, ...)
+    InvalidReferentialAction,
+    /// W0005: WHERE/HAVING compares columns of tables no join relates (opt-in)
+    CrossTableComparison,
+    /// W0006: COUNT(col) on a NOT NULL column, equivalent to COUNT(*)
+    CountNotNullColumn,
+    /// W0007: COUNT(col) of a nullable column used in arithmetic
+    CountSkipsNulls,
+    /// W0008: Comparison relying on an implicit cast (opt-in, pedantic)
+    ImplicitCast,
+    /// W0009: LIMIT / FETCH FIRST without ORDER BY (opt-in)
+    UnorderedLimit,
+    /// W0010: Reference to a table or column marked deprecated
+    DeprecatedUsage,
+    /// W0011: Statement larger than the analyzer's size limit, not analyzed
+    StatementTooLarge,
+    /// W0012: Column inside a function call or cast compared with a value (opt-in, performance)
+    NonSargablePredicate,
+    /// W0013: String column compared with a number under MySQL (opt-in, performance)
+    CoercedColumnComparison,
+    /// W0014: Dynamic SQL built by string concatenation in a function body (opt-in, security)
+    DynamicSqlConcatenation,
+    /// W0015: Two output columns of a query with the same name
+    DuplicateOutputColumn,
+    /// W0016: Identity column values that OVERRIDING USER VALUE discards
+    IgnoredIdentityValue,
+    /// W0017: Foreign key whose columns no index leads with (opt-in, performance)
+    UnindexedForeignKey,
+    /// W0018: JOIN equality column that no index leads with (opt-in, performance)
+    UnindexedJoinColumn,
+    /// W0019: Input over the file size or statement count limit, not analyzed
+    InputTooLarge,
+    /// W0020: View referencing a table by a name several schemas define (opt-in, security)
+    AmbiguousViewReference,
+    /// W0021: View referencing a table outside the default schema without qualifying it (opt-in, security)
+    SearchPathViewReference,
+    /// W0022: REFRESH MATERIALIZED VIEW CONCURRENTLY of a view without a unique index
+    RefreshWithoutUniqueIndex,
     /// Parse error
     ParseError,
 }
 
 impl DiagnosticKind {
+    /// Every rule, in code order
+    pub const ALL: [DiagnosticKind; 42] = [
+        DiagnosticKind::TableNotFound,
+        DiagnosticKind::ColumnNotFound,
+        DiagnosticKind::TypeMismatch,
+        DiagnosticKind::PotentialNullViolation,
+        DiagnosticKind::ColumnCountMismatch,
+        DiagnosticKind::AmbiguousColumn,
+        DiagnosticKind::JoinTypeMismatch,
+        DiagnosticKind::GeneratedColumnAssignment,
+        DiagnosticKind::DuplicateDefinition,
+        DiagnosticKind::UnsupportedFeature,
+        DiagnosticKind::DuplicateTableReference,
+        DiagnosticKind::IndexNotFound,
+        DiagnosticKind::MultiArgumentCountDistinct,
+        DiagnosticKind::DistinctOrderBy,
+        DiagnosticKind::PolicyViolation,
+        DiagnosticKind::InvalidRowLimit,
+        DiagnosticKind::FunctionArgumentCount,
+        DiagnosticKind::MisplacedWindowFunction,
+        DiagnosticKind::RelationKindMismatch,
+        DiagnosticKind::DuplicateInsertKey,
+        DiagnosticKind::NestingTooDeep,
+        DiagnosticKind::ReservedIdentifier,
+        DiagnosticKind::InvalidReferentialAction,
+        DiagnosticKind::CrossTableComparison,
+        DiagnosticKind::CountNotNullColumn,
+        DiagnosticKind::CountSkipsNulls,
+        DiagnosticKind::ImplicitCast,
+        DiagnosticKind::UnorderedLimit,
+        DiagnosticKind::DeprecatedUsage,
+        DiagnosticKind::StatementTooLarge,
+        DiagnosticKind::NonSargablePredicate,
+        DiagnosticKind::CoercedColumnComparison,
+        DiagnosticKind::DynamicSqlConcatenation,
+        DiagnosticKind::DuplicateOutputColumn,
+        DiagnosticKind::IgnoredIdentityValue,
+        DiagnosticKind::UnindexedForeignKey,
+        DiagnosticKind::UnindexedJoinColumn,
+        DiagnosticKind::InputTooLarge,
+        DiagnosticKind::AmbiguousViewReference,
+        DiagnosticKind::SearchPathViewReference,
+        DiagnosticKind::RefreshWithoutUniqueIndex,
+        DiagnosticKind::ParseError,
+    ];
+
     pub fn code(&self) -> &'static str {
         match self {
             DiagnosticKind::TableNotFound => "E0001",
@@ -166,6 +457,40 @@ impl DiagnosticKind {
             DiagnosticKind::ColumnCountMismatch => "E0005",
             DiagnosticKind::AmbiguousColumn => "E0006",
             DiagnosticKind::JoinTypeMismatch => "E0007",
+            DiagnosticKind::GeneratedColumnAssignment => "E0008",
+            DiagnosticKind::DuplicateDefinition => "E0009",
+            DiagnosticKind::UnsupportedFeature => "E0010",
+            DiagnosticKind::DuplicateTableReference => "E0011",
+            DiagnosticKind::IndexNotFound => "E0012",
+            DiagnosticKind::MultiArgumentCountDistinct => "E0013",
+            DiagnosticKind::DistinctOrderBy => "E0014",
+            DiagnosticKind::PolicyViolation => "E0015",
+            DiagnosticKind::InvalidRowLimit => "E0016",
+            DiagnosticKind::FunctionArgumentCount => "E0017",
+            DiagnosticKind::MisplacedWindowFunction => "E0018",
+            DiagnosticKind::RelationKindMismatch => "E0019",
+            DiagnosticKind::DuplicateInsertKey => "W0001",
+            DiagnosticKind::NestingTooDeep => "W0002",
+            DiagnosticKind::ReservedIdentifier => "W0003",
+            DiagnosticKind::InvalidReferentialAction => "W0004",
+            DiagnosticKind::CrossTableComparison => "W0005",
+            DiagnosticKind::CountNotNullColumn => "W0006",
+            DiagnosticKind::CountSkipsNulls => "W0007",
+            DiagnosticKind::ImplicitCast => "W0008",
+            DiagnosticKind::UnorderedLimit => "W0009",
+            DiagnosticKind::DeprecatedUsage => "W0010",
+            DiagnosticKind::StatementTooLarge => "W0011",
+            DiagnosticKind::NonSargablePredicate => "W0012",
+            DiagnosticKind::CoercedColumnComparison => "W0013",
+            DiagnosticKind::DynamicSqlConcatenation => "W0014",
+            DiagnosticKind::DuplicateOutputColumn => "W0015",
+            DiagnosticKind::IgnoredIdentityValue => "W0016",
+            DiagnosticKind::UnindexedForeignKey => "W0017",
+            DiagnosticKind::UnindexedJoinColumn => "W0018",
+            DiagnosticKind::InputTooLarge => "W0019",
+            DiagnosticKind::AmbiguousViewReference => "W0020",
+            DiagnosticKind::SearchPathViewReference => "W0021",
+            DiagnosticKind::RefreshWithoutUniqueIndex => "W0022",
             DiagnosticKind::ParseError => "E1000",
         }
     }
@@ -179,7 +504,119 @@ impl DiagnosticKind {
             DiagnosticKind::ColumnCountMismatch => "column-count-mismatch",
             DiagnosticKind::AmbiguousColumn => "ambiguous-column",
             DiagnosticKind::JoinTypeMismatch => "join-type-mismatch",
+            DiagnosticKind::GeneratedColumnAssignment => "generated-column-assignment",
+            DiagnosticKind::DuplicateDefinition => "duplicate-definition",
+            DiagnosticKind::UnsupportedFeature => "unsupported-feature",
+            DiagnosticKind::DuplicateTableReference => "duplicate-table-reference",
+            DiagnosticKind::IndexNotFound => "index-not-found",
+            DiagnosticKind::MultiArgumentCountDistinct => "multi-argument-count-distinct",
+            DiagnosticKind::DistinctOrderBy => "distinct-order-by",
+            DiagnosticKind::PolicyViolation => "policy-violation",
+            DiagnosticKind::InvalidRowLimit => "invalid-row-limit",
+            DiagnosticKind::FunctionArgumentCount => "function-argument-count",
+            DiagnosticKind::MisplacedWindowFunction => "misplaced-window-function",
+            DiagnosticKind::RelationKindMismatch => "relation-kind-mismatch",
+            DiagnosticKind::DuplicateInsertKey => "duplicate-insert-key",
+            DiagnosticKind::NestingTooDeep => "nesting-too-deep",
+            DiagnosticKind::ReservedIdentifier => "reserved-identifier",
+            DiagnosticKind::InvalidReferentialAction => "invalid-referential-action",
+            DiagnosticKind::CrossTableComparison => "cross-table-comparison",
+            DiagnosticKind::CountNotNullColumn => "count-not-null-column",
+            DiagnosticKind::CountSkipsNulls => "count-skips-nulls",
+            DiagnosticKind::ImplicitCast => "implicit-cast",
+            DiagnosticKind::UnorderedLimit => "unordered-limit",
+            DiagnosticKind::DeprecatedUsage => "deprecated-usage",
+            DiagnosticKind::StatementTooLarge => "statement-too-large",
+            DiagnosticKind::NonSargablePredicate => "non-sargable-predicate",
+            DiagnosticKind::CoercedColumnComparison => "coerced-column-comparison",
+            DiagnosticKind::DynamicSqlConcatenation => "dynamic-sql-concatenation",
+            DiagnosticKind::DuplicateOutputColumn => "duplicate-output-column",
+            DiagnosticKind::IgnoredIdentityValue => "ignored-identity-value",
+            DiagnosticKind::UnindexedForeignKey => "unindexed-foreign-key",
+            DiagnosticKind::UnindexedJoinColumn => "unindexed-join-column",
+            DiagnosticKind::InputTooLarge => "input-too-large",
+            DiagnosticKind::AmbiguousViewReference => "ambiguous-view-reference",
+            DiagnosticKind::SearchPathViewReference => "search-path-view-reference",
+            DiagnosticKind::RefreshWithoutUniqueIndex => "refresh-without-unique-index",
             DiagnosticKind::ParseError => "parse-error",
         }
     }
+
+    /// Whether this rule only runs when explicitly enabled
+    pub fn is_opt_in(&self) -> bool {
+        matches!(
+            self,
+            DiagnosticKind::DuplicateInsertKey
+                | DiagnosticKind::CrossTableComparison
+                | DiagnosticKind::ImplicitCast
+                | DiagnosticKind::UnorderedLimit
+                | DiagnosticKind::NonSargablePredicate
+                | DiagnosticKind::CoercedColumnComparison
+                | DiagnosticKind::DynamicSqlConcatenation
+                | DiagnosticKind::UnindexedForeignKey
+                | DiagnosticKind::UnindexedJoinColumn
+                | DiagnosticKind::AmbiguousViewReference
+                | DiagnosticKind::SearchPathViewReference
+        )
+    }
+
+    /// Group of opt-in rules this rule is enabled with, besides its code and name
+    pub fn group(&self) -> Option<&'static str> {
+        match self {
+            DiagnosticKind::NonSargablePredicate
+            | DiagnosticKind::CoercedColumnComparison
+            | DiagnosticKind::UnindexedForeignKey
+            | DiagnosticKind::UnindexedJoinColumn => Some("performance"),
+            DiagnosticKind::DynamicSqlConcatenation
+            | DiagnosticKind::AmbiguousViewReference
+            | DiagnosticKind::SearchPathViewReference => Some("security"),
+            _ => None,
+        }
+    }
+
+    /// Whether this rule is on by default or enabled by `rules` (lowercase
+    /// codes, names and groups)
+    pub(crate) fn is_enabled_by(&self, rules: &HashSet<String>) -> bool {
+        !self.is_opt_in()
+            || rules.contains(&self.code().to_lowercase())
+            || rules.contains(self.name())
+            || self.group().is_some_and(|group| rules.contains(group))
+    }
+
+    /// Whether this rule checks queries against the schema (table and column
+    /// existence, column types, keys and nullability), so it can't run
+    /// without one (see `Analyzer::without_catalog`)
+    pub fn requires_schema(&self) -> bool {
+        matches!(
+            self,
+            DiagnosticKind::TableNotFound
+                | DiagnosticKind::ColumnNotFound
+                | DiagnosticKind::TypeMismatch
+                | DiagnosticKind::PotentialNullViolation
+                | DiagnosticKind::ColumnCountMismatch
+                | DiagnosticKind::AmbiguousColumn
+                | DiagnosticKind::JoinTypeMismatch
+                | DiagnosticKind::GeneratedColumnAssignment
+                | DiagnosticKind::DuplicateDefinition
+                | DiagnosticKind::IndexNotFound
+                | DiagnosticKind::FunctionArgumentCount
+                | DiagnosticKind::CrossTableComparison
+                | DiagnosticKind::CountNotNullColumn
+                | DiagnosticKind::CountSkipsNulls
+                | DiagnosticKind::ImplicitCast
+                | DiagnosticKind::UnorderedLimit
+                | DiagnosticKind::DeprecatedUsage
+                | DiagnosticKind::NonSargablePredicate
+                | DiagnosticKind::CoercedColumnComparison
+                | DiagnosticKind::IgnoredIdentityValue
+                | DiagnosticKind::UnindexedJoinColumn
+                | DiagnosticKind::RelationKindMismatch
+                | DiagnosticKind::RefreshWithoutUniqueIndex
+        )
+    }
+
+    /// Whether this opt-in rule is enabled by the pedantic strictness level
+    pub fn is_pedantic(&self) -> bool {
+        matches!(self, DiagnosticKind::ImplicitCast)
+    }
 }
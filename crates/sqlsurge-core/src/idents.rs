@@ -0,0 +1,54 @@
+//! Identifier helpers shared by the schema builder and the analyzer
+
+use sqlparser::ast::ObjectName;
+
+use crate::schema::QualifiedName;
+
+/// Convert sqlparser ObjectName to our QualifiedName
+///
+/// `table`, `schema.table` and `catalog.schema.table` (the catalog part is
+/// dropped) are recognized. Any other name is kept whole, as written, so it
+/// matches no catalog relation and diagnostics show it the way the query
+/// spells it.
+pub(crate) fn object_name_to_qualified(name: &ObjectName) -> QualifiedName {
+    match name.0.as_slice() {
+        [table] => QualifiedName::new(&table.value),
+        [schema, table] => QualifiedName::with_schema(&schema.value, &table.value),
+        [_catalog, schema, table] => QualifiedName::with_schema(&schema.value, &table.value),
+        _ => QualifiedName::new(name.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlparser::ast::Ident;
+
+    fn name(parts: &[&str]) -> ObjectName {
+        ObjectName(parts.iter().map(|p| Ident::new(*p)).collect())
+    }
+
+    #[test]
+    fn test_object_name_to_qualified() {
+        assert_eq!(
+            object_name_to_qualified(&name(&["users"])),
+            QualifiedName::new("users")
+        );
+        assert_eq!(
+            object_name_to_qualified(&name(&["shop", "users"])),
+            QualifiedName::with_schema("shop", "users")
+        );
+        assert_eq!(
+            object_name_to_qualified(&name(&["db", "shop", "users"])),
+            QualifiedName::with_schema("shop", "users")
+        );
+
+        // Four parts and more stay whole, quotes included
+        let mut four = name(&["x", "db", "shop"]);
+        four.0.push(Ident::with_quote('"', "Users"));
+        assert_eq!(
+            object_name_to_qualified(&four),
+            QualifiedName::new("x.db.shop.\"Users\"")
+        );
+    }
+}
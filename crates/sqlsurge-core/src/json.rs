@@ -0,0 +1,296 @@
+//! JSON and JSON Lines output shapes
+//!
+//! These structs define the stable, versioned shape of the CLI's
+//! `--format json` and `--format jsonl`, also returned by the `sqlsurge-ffi`
+//! library. Adding fields is backwards compatible; renaming or removing
+//! fields requires bumping [`FORMAT_VERSION`].
+//!
+//! `json` prints one [`JsonReport`] per file with diagnostics.
+//! `jsonl` prints one compact [`JsonlRecord::Diagnostic`] line per diagnostic,
+//! followed by a final [`JsonlRecord::Summary`] line.
+
+use serde::Serialize;
+
+use crate::{Diagnostic, DiagnosticKind, OutputColumn, Severity, Span, SqlType};
+
+/// Version of the JSON output shape
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Diagnostics for a single file (`--format json`)
+#[derive(Debug, Serialize)]
+pub struct JsonReport<'a> {
+    pub version: u32,
+    pub file: &'a str,
+    /// Dialect the file was analyzed with, e.g. "mysql@5.7"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dialect: Option<&'a str>,
+    pub diagnostics: Vec<JsonDiagnostic<'a>>,
+}
+
+/// A single diagnostic with resolved positions
+#[derive(Debug, Serialize)]
+pub struct JsonDiagnostic<'a> {
+    pub file: &'a str,
+    /// Rule code, e.g. "E0002"
+    pub code: &'static str,
+    /// Rule name, e.g. "column-not-found"
+    pub rule: &'static str,
+    /// Diagnostic kind (kept for compatibility with pre-versioned output)
+    pub kind: DiagnosticKind,
+    pub severity: Severity,
+    pub message: &'a str,
+    pub help: Option<&'a str>,
+    /// Start line (1-indexed)
+    pub line: Option<usize>,
+    /// Start column (1-indexed)
+    pub column: Option<usize>,
+    /// End line (1-indexed)
+    pub end_line: Option<usize>,
+    /// End column (1-indexed, exclusive)
+    pub end_column: Option<usize>,
+    /// Text of the source line containing the start position
+    pub source_line: Option<&'a str>,
+    /// Raw span (kept for compatibility with pre-versioned output)
+    pub span: Option<Span>,
+    pub labels: Vec<JsonLabel<'a>>,
+    /// Machine-applicable edits resolving the diagnostic
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fixes: Vec<JsonFix<'a>>,
+    /// The statement of the file the diagnostic belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statement: Option<JsonStatement>,
+    /// "schema" for a diagnostic of a schema file (absent for query files)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<&'static str>,
+}
+
+/// Position of a statement in its file
+#[derive(Debug, Serialize)]
+pub struct JsonStatement {
+    /// Index of the statement in the file (0-based)
+    pub index: usize,
+    /// Byte offsets of the statement's text (end exclusive)
+    pub start_offset: usize,
+    pub end_offset: usize,
+    /// First and last line (1-indexed)
+    pub line: usize,
+    pub end_line: usize,
+}
+
+/// A suggested edit: replace the text between the positions with `replacement`
+/// (an insertion when start and end are equal)
+#[derive(Debug, Serialize)]
+pub struct JsonFix<'a> {
+    pub message: &'a str,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    pub replacement: &'a str,
+}
+
+/// Secondary annotation of a diagnostic
+#[derive(Debug, Serialize)]
+pub struct JsonLabel<'a> {
+    pub message: &'a str,
+    /// File the label points into, when not the diagnostic's file (e.g. a schema file)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<&'a str>,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Output columns of a query (`describe --format json`)
+#[derive(Debug, Serialize)]
+pub struct JsonDescribe<'a> {
+    pub version: u32,
+    pub file: &'a str,
+    pub columns: Vec<JsonOutputColumn<'a>>,
+}
+
+/// Dependencies of query files (`deps --format json`)
+#[derive(Debug, Serialize)]
+pub struct JsonDeps<'a> {
+    pub version: u32,
+    pub files: Vec<JsonFileDeps<'a>>,
+}
+
+/// Tables and views a query file depends on
+#[derive(Debug, Serialize)]
+pub struct JsonFileDeps<'a> {
+    pub file: &'a str,
+    /// Schema-qualified names, sorted
+    pub dependencies: Vec<String>,
+    /// Schema files defining the dependencies, sorted
+    pub schema_files: Vec<&'a str>,
+}
+
+/// Phase timings of a check run (`check --profile-json`)
+#[derive(Debug, Serialize)]
+pub struct JsonProfile<'a> {
+    pub version: u32,
+    /// Per phase: "schema", then the [`Phase`](crate::analyzer::Phase) names
+    pub phases: Vec<JsonPhaseTiming>,
+    /// Query files, slowest first
+    pub files: Vec<JsonFileProfile<'a>>,
+    /// Statements taken from the AST cache (`ast_cache` in sqlsurge.toml)
+    pub cache_hits: usize,
+    /// Statements parsed with the AST cache on
+    pub cache_misses: usize,
+}
+
+/// Time spent in one phase over the run
+#[derive(Debug, Serialize)]
+pub struct JsonPhaseTiming {
+    pub phase: &'static str,
+    pub total_ms: f64,
+    /// 95th percentile per schema file (schema), per query file (parse) or
+    /// per statement (other phases)
+    pub p95_ms: f64,
+}
+
+/// Time spent on one query file
+#[derive(Debug, Serialize)]
+pub struct JsonFileProfile<'a> {
+    pub file: &'a str,
+    pub total_ms: f64,
+    pub statements: usize,
+}
+
+/// A single output column
+#[derive(Debug, Serialize)]
+pub struct JsonOutputColumn<'a> {
+    pub name: &'a str,
+    /// Display name of the type, e.g. "varchar(100)" ("unknown" if not inferred)
+    #[serde(rename = "type")]
+    pub type_name: String,
+    pub nullable: bool,
+    /// Structured type
+    pub data_type: &'a SqlType,
+}
+
+/// One line of `--format jsonl` output
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum JsonlRecord<'a> {
+    Diagnostic {
+        version: u32,
+        #[serde(flatten)]
+        diagnostic: Box<JsonDiagnostic<'a>>,
+    },
+    Summary {
+        version: u32,
+        files: usize,
+        errors: usize,
+        warnings: usize,
+        /// Files skipped for being over a size limit (W0019)
+        oversized: usize,
+    },
+}
+
+impl<'a> JsonDiagnostic<'a> {
+    pub fn new(file: &'a str, diag: &'a Diagnostic, source: &'a str) -> Self {
+        let position = diag.span.map(|span| resolve_position(&span, source));
+        Self {
+            file,
+            code: diag.code(),
+            rule: diag.kind.name(),
+            kind: diag.kind,
+            severity: diag.severity,
+            message: &diag.message,
+            help: diag.help.as_deref(),
+            line: position.map(|p| p.0),
+            column: position.map(|p| p.1),
+            end_line: position.map(|p| p.2),
+            end_column: position.map(|p| p.3),
+            source_line: position.and_then(|p| get_source_line(source, p.0)),
+            span: diag.span,
+            labels: diag
+                .labels
+                .iter()
+                .map(|label| {
+                    let (line, column, _, _) = resolve_position(&label.span, source);
+                    JsonLabel {
+                        message: &label.message,
+                        file: label.file.as_deref(),
+                        line,
+                        column,
+                    }
+                })
+                .collect(),
+            fixes: diag
+                .fixes
+                .iter()
+                .map(|fix| {
+                    let (line, column, end_line, end_column) = resolve_position(&fix.span, source);
+                    JsonFix {
+                        message: &fix.message,
+                        line,
+                        column,
+                        end_line,
+                        end_column,
+                        replacement: &fix.replacement,
+                    }
+                })
+                .collect(),
+            statement: diag.statement.map(|statement| JsonStatement {
+                index: statement.index,
+                start_offset: statement.span.offset,
+                end_offset: statement.span.offset + statement.span.length,
+                line: statement.span.line,
+                end_line: statement.span.end_line,
+            }),
+            category: None,
+        }
+    }
+}
+
+impl<'a> JsonOutputColumn<'a> {
+    pub fn new(column: &'a OutputColumn) -> Self {
+        Self {
+            name: &column.name,
+            type_name: column.data_type.display_name(),
+            nullable: column.nullable,
+            data_type: &column.data_type,
+        }
+    }
+}
+
+/// Resolve (line, column, end_line, end_column) for a span
+fn resolve_position(span: &Span, source: &str) -> (usize, usize, usize, usize) {
+    let (line, column) = if span.line > 0 {
+        (span.line, span.column)
+    } else {
+        offset_to_line_col(source, span.offset)
+    };
+    if span.end_line > 0 {
+        (line, column, span.end_line, span.end_column)
+    } else {
+        (line, column, line, column + span.length)
+    }
+}
+
+/// Convert byte offset to line and column (1-indexed)
+pub fn offset_to_line_col(source: &str, offset: usize) -> (usize, usize) {
+    let mut line = 1;
+    let mut col = 1;
+
+    for (i, ch) in source.char_indices() {
+        if i >= offset {
+            break;
+        }
+        if ch == '\n' {
+            line += 1;
+            col = 1;
+        } else {
+            col += 1;
+        }
+    }
+
+    (line, col)
+}
+
+/// Get a specific line from source (1-indexed)
+pub fn get_source_line(source: &str, line: usize) -> Option<&str> {
+    source.lines().nth(line.saturating_sub(1))
+}
@@ -6,11 +6,21 @@
 pub mod analyzer;
 pub mod dialect;
 pub mod error;
+mod idents;
+pub mod json;
+pub mod rules;
 pub mod schema;
+#[cfg(feature = "test-util")]
+pub mod testing;
 pub mod types;
 
-pub use analyzer::Analyzer;
+pub use analyzer::{
+    classify, completion, AnalysisReport, Analyzer, AnalyzerPasses, OutputColumn, PlaceholderStyle,
+    ProfileReport, StatementClass, Strictness, WritePolicy,
+};
 pub use dialect::SqlDialect;
-pub use error::{Diagnostic, DiagnosticKind, Severity, Span};
+pub use error::{
+    Diagnostic, DiagnosticKind, DiagnosticOrigin, Fix, Severity, SeverityOrOff, Span, StatementRef,
+};
 pub use schema::{Catalog, ColumnDef, QualifiedName, Schema, TableDef};
-pub use types::SqlType;
+pub use types::{SqlType, TypeParseError};
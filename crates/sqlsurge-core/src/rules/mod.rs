@@ -0,0 +1,112 @@
+//! Rule documentation
+//!
+//! Every rule has an extended explanation (what it reports, why it matters,
+//! how to fix it, with examples), written as Markdown next to this module
+//! and compiled in, so `sqlsurge explain`, SARIF output and editor
+//! integrations serve the same text. The first paragraph of an explanation
+//! is the rule's one-line summary.
+
+use crate::error::DiagnosticKind;
+
+/// Where the rules are documented online (the default SARIF `helpUri`)
+pub const HELP_URI: &str = "https://github.com/yukikotani231/sqlsurge#diagnostic-rules";
+
+/// The rule with a code (`E0006`) or name (`ambiguous-column`), in any case
+pub fn lookup(rule: &str) -> Option<DiagnosticKind> {
+    DiagnosticKind::ALL.into_iter().find(|kind| {
+        kind.code().eq_ignore_ascii_case(rule) || kind.name().eq_ignore_ascii_case(rule)
+    })
+}
+
+/// The one-line summary of a rule
+pub fn summary(kind: DiagnosticKind) -> &'static str {
+    let text = explanation(kind);
+    text.split("\n\n").next().unwrap_or(text).trim()
+}
+
+/// The extended explanation of a rule, as Markdown
+pub fn explanation(kind: DiagnosticKind) -> &'static str {
+    match kind {
+        DiagnosticKind::TableNotFound => include_str!("E0001.md"),
+        DiagnosticKind::ColumnNotFound => include_str!("E0002.md"),
+        DiagnosticKind::TypeMismatch => include_str!("E0003.md"),
+        DiagnosticKind::PotentialNullViolation => include_str!("E0004.md"),
+        DiagnosticKind::ColumnCountMismatch => include_str!("E0005.md"),
+        DiagnosticKind::AmbiguousColumn => include_str!("E0006.md"),
+        DiagnosticKind::JoinTypeMismatch => include_str!("E0007.md"),
+        DiagnosticKind::GeneratedColumnAssignment => include_str!("E0008.md"),
+        DiagnosticKind::DuplicateDefinition => include_str!("E0009.md"),
+        DiagnosticKind::UnsupportedFeature => include_str!("E0010.md"),
+        DiagnosticKind::DuplicateTableReference => include_str!("E0011.md"),
+        DiagnosticKind::IndexNotFound => include_str!("E0012.md"),
+        DiagnosticKind::MultiArgumentCountDistinct => include_str!("E0013.md"),
+        DiagnosticKind::DistinctOrderBy => include_str!("E0014.md"),
+        DiagnosticKind::PolicyViolation => include_str!("E0015.md"),
+        DiagnosticKind::InvalidRowLimit => include_str!("E0016.md"),
+        DiagnosticKind::FunctionArgumentCount => include_str!("E0017.md"),
+        DiagnosticKind::MisplacedWindowFunction => include_str!("E0018.md"),
+        DiagnosticKind::RelationKindMismatch => include_str!("E0019.md"),
+        DiagnosticKind::DuplicateInsertKey => include_str!("W0001.md"),
+        DiagnosticKind::NestingTooDeep => include_str!("W0002.md"),
+        DiagnosticKind::ReservedIdentifier => include_str!("W0003.md"),
+        DiagnosticKind::InvalidReferentialAction => include_str!("W0004.md"),
+        DiagnosticKind::CrossTableComparison => include_str!("W0005.md"),
+        DiagnosticKind::CountNotNullColumn => include_str!("W0006.md"),
+        DiagnosticKind::CountSkipsNulls => include_str!("W0007.md"),
+        DiagnosticKind::ImplicitCast => include_str!("W0008.md"),
+        DiagnosticKind::UnorderedLimit => include_str!("W0009.md"),
+        DiagnosticKind::DeprecatedUsage => include_str!("W0010.md"),
+        DiagnosticKind::StatementTooLarge => include_str!("W0011.md"),
+        DiagnosticKind::NonSargablePredicate => include_str!("W0012.md"),
+        DiagnosticKind::CoercedColumnComparison => include_str!("W0013.md"),
+        DiagnosticKind::DynamicSqlConcatenation => include_str!("W0014.md"),
+        DiagnosticKind::DuplicateOutputColumn => include_str!("W0015.md"),
+        DiagnosticKind::IgnoredIdentityValue => include_str!("W0016.md"),
+        DiagnosticKind::UnindexedForeignKey => include_str!("W0017.md"),
+        DiagnosticKind::UnindexedJoinColumn => include_str!("W0018.md"),
+        DiagnosticKind::InputTooLarge => include_str!("W0019.md"),
+        DiagnosticKind::AmbiguousViewReference => include_str!("W0020.md"),
+        DiagnosticKind::SearchPathViewReference => include_str!("W0021.md"),
+        DiagnosticKind::RefreshWithoutUniqueIndex => include_str!("W0022.md"),
+        DiagnosticKind::ParseError => include_str!("E1000.md"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_rule_is_documented() {
+        for (i, kind) in DiagnosticKind::ALL.into_iter().enumerate() {
+            assert!(
+                !DiagnosticKind::ALL[..i].contains(&kind),
+                "{} listed twice",
+                kind.code()
+            );
+            let summary = summary(kind);
+            assert!(
+                !summary.is_empty() && !summary.contains('\n'),
+                "{}",
+                kind.code()
+            );
+            // More than the summary: why it matters and how to fix it
+            assert!(
+                explanation(kind).len() > 2 * summary.len(),
+                "{}",
+                kind.code()
+            );
+        }
+    }
+
+    #[test]
+    fn test_lookup_by_code_or_name() {
+        assert_eq!(lookup("E0006"), Some(DiagnosticKind::AmbiguousColumn));
+        assert_eq!(lookup("e0006"), Some(DiagnosticKind::AmbiguousColumn));
+        assert_eq!(
+            lookup("ambiguous-column"),
+            Some(DiagnosticKind::AmbiguousColumn)
+        );
+        assert_eq!(lookup("E9999"), None);
+    }
+}
@@ -1,44 +1,196 @@
 //! Schema builder - converts SQL AST to Catalog
 
 use sqlparser::ast::{
-    AlterTableOperation, ColumnOption, ColumnOptionDef, ObjectName, Statement, TableConstraint,
-    UserDefinedTypeRepresentation,
+    visit_expressions, AlterTableOperation, ArgMode, CastKind, ColumnOption, ColumnOptionDef,
+    CommentDef, CommentObject, CreateFunction, CreateIndex, DataType, Expr,
+    GeneratedExpressionMode, Ident, MySQLColumnPosition, ObjectName, ObjectType, Query, Spanned,
+    Statement, TableConstraint, UserDefinedTypeRepresentation, Value, Visit, Visitor,
 };
+use sqlparser::dialect::Dialect;
+use sqlparser::keywords::Keyword;
 use sqlparser::parser::Parser;
-use sqlparser::tokenizer::Token;
+use sqlparser::tokenizer::{Location, Token};
+use std::collections::{HashMap, HashSet};
+use std::ops::ControlFlow;
+use std::sync::Arc;
 
-use crate::dialect::SqlDialect;
-use crate::error::{Diagnostic, DiagnosticKind};
+use crate::analyzer::features;
+use crate::analyzer::projection::ProjectionExpander;
+use crate::dialect::{DialectVersion, SqlDialect};
+use crate::error::{Diagnostic, DiagnosticKind, DiagnosticOrigin, Span};
+use crate::idents::object_name_to_qualified;
 use crate::schema::{
-    Catalog, CheckConstraintDef, ColumnDef, DefaultValue, EnumTypeDef, ForeignKeyDef, IdentityKind,
-    PrimaryKeyDef, QualifiedName, TableDef, UniqueConstraintDef, ViewDef,
+    Catalog, CheckConstraintDef, ColumnDef, DefaultValue, EnumTypeDef, ForeignKeyDef, FunctionSig,
+    GeneratedColumn, IdentityKind, IndexDef, PrimaryKeyDef, QualifiedName, ReferentialAction,
+    SchemaOrigin, TableDef, UniqueConstraintDef, ViewDef,
 };
-use crate::types::SqlType;
+use crate::types::{is_builtin_type_name, SqlType};
+
+use super::dynamic_sql;
 
 /// Builder for constructing a Catalog from SQL schema definitions
 pub struct SchemaBuilder {
     catalog: Catalog,
     diagnostics: Vec<Diagnostic>,
+    /// Errors in `diagnostics` the catalog recovers from (e.g. a duplicate
+    /// column whose first definition is kept), which don't fail `parse`
+    recovered_errors: usize,
     dialect: SqlDialect,
+    /// Target server version, for feature gating (None = latest)
+    version: Option<DialectVersion>,
+    /// Treat MySQL `TINYINT(1)` columns as BOOLEAN
+    tinyint1_as_boolean: bool,
+    /// Report duplicate definitions as errors instead of warnings
+    strict_duplicates: bool,
+    /// Warn about statements skipped for failing to parse (fallback parsing)
+    report_skipped: bool,
+    /// Opt-in rules enabled (lowercase codes, names and groups)
+    enabled_rules: HashSet<String>,
+    /// Check foreign key targets once all sources are parsed
+    check_foreign_keys: bool,
+    /// Name of the source currently being parsed (see `parse_named`)
+    source: Option<String>,
+    /// Named source of each diagnostic attributed so far (see `attribute_diagnostics`)
+    diagnostic_sources: Vec<Option<String>>,
+    /// Line and column offset of a statement parsed on its own (fallback parsing)
+    position_offset: (usize, usize),
+    /// Inputs that failed to parse as a whole (see `fallback_parses`)
+    fallback_parses: usize,
+    /// Where each table/view/enum was first defined, keyed by namespace and name
+    definitions: HashMap<String, Definition>,
+    /// Views whose `*` referenced relations not defined yet, re-resolved by `build()`
+    pending_views: Vec<PendingView>,
+    /// Names of user-defined types the catalog doesn't model (composite, range, ...)
+    other_types: Vec<String>,
+    /// Base type of each CREATE DOMAIN, by lowercased name
+    domains: HashMap<String, SqlType>,
+}
+
+/// A view whose columns could not be inferred when it was defined
+struct PendingView {
+    /// Relation key (see `relation_key`)
+    key: String,
+    name: QualifiedName,
+    query: Box<sqlparser::ast::Query>,
+}
+
+/// Origin of a schema object definition, for duplicate reporting
+struct Definition {
+    kind: &'static str,
+    origin: String,
 }
 
 impl SchemaBuilder {
     pub fn new() -> Self {
-        Self {
-            catalog: Catalog::new(),
-            diagnostics: Vec::new(),
-            dialect: SqlDialect::default(),
-        }
+        Self::with_dialect(SqlDialect::default())
     }
 
+    /// Create a builder for a dialect, which is used to parse the schema,
+    /// to interpret its types and for the catalog's default schema
     pub fn with_dialect(dialect: SqlDialect) -> Self {
         Self {
-            catalog: Catalog::new(),
+            catalog: Catalog::with_default_schema(dialect.default_schema()),
             diagnostics: Vec::new(),
+            recovered_errors: 0,
             dialect,
+            version: None,
+            tinyint1_as_boolean: true,
+            strict_duplicates: false,
+            report_skipped: false,
+            enabled_rules: HashSet::new(),
+            check_foreign_keys: false,
+            source: None,
+            diagnostic_sources: Vec::new(),
+            position_offset: (0, 0),
+            fallback_parses: 0,
+            definitions: HashMap::new(),
+            pending_views: Vec::new(),
+            other_types: Vec::new(),
+            domains: HashMap::new(),
         }
     }
 
+    /// Create a builder that continues from an existing catalog
+    ///
+    /// Used by the analyzer to track DDL statements within a query file.
+    pub(crate) fn from_catalog(catalog: Catalog, dialect: SqlDialect) -> Self {
+        Self {
+            catalog,
+            ..Self::with_dialect(dialect)
+        }
+    }
+
+    /// Apply a single already-parsed statement to the catalog
+    pub(crate) fn apply_statement(&mut self, stmt: &Statement) {
+        self.process_statement(stmt);
+    }
+
+    /// Set the target server version of the dialect
+    ///
+    /// Columns using features the version lacks (identity columns before
+    /// PostgreSQL 10) are reported as `unsupported-feature`.
+    pub fn with_version(mut self, version: DialectVersion) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Set whether MySQL `TINYINT(1)` columns are typed as BOOLEAN (default: true)
+    ///
+    /// `TINYINT(1)` is MySQL's conventional boolean (and what `BOOLEAN` is an
+    /// alias for). Has no effect for other dialects.
+    pub fn with_tinyint1_as_boolean(mut self, enabled: bool) -> Self {
+        self.tinyint1_as_boolean = enabled;
+        self
+    }
+
+    /// Set whether duplicate table/view/enum definitions are errors (default: warnings)
+    pub fn with_strict_duplicates(mut self, strict: bool) -> Self {
+        self.strict_duplicates = strict;
+        self
+    }
+
+    /// Set whether statements skipped for failing to parse are reported as
+    /// parse error (E1000) warnings (default: skipped silently)
+    ///
+    /// Schema files often hold statements sqlparser can't parse (functions,
+    /// triggers, vendor extensions), which are skipped so the rest is used.
+    pub fn with_skipped_statements(mut self, report: bool) -> Self {
+        self.report_skipped = report;
+        self
+    }
+
+    /// Set whether `build()` checks that foreign keys reference existing
+    /// tables and columns (default: false)
+    ///
+    /// Off by default, as a schema dump often references tables defined
+    /// elsewhere. [`CatalogBuilder`](super::CatalogBuilder) turns it on.
+    pub fn with_foreign_key_checks(mut self, check: bool) -> Self {
+        self.check_foreign_keys = check;
+        self
+    }
+
+    /// Enable opt-in schema rules, by code (`W0014`), name
+    /// (`dynamic-sql-concatenation`) or group (`security`)
+    pub fn with_enabled_rules<I, S>(mut self, rules: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.enabled_rules
+            .extend(rules.into_iter().map(|r| r.into().to_lowercase()));
+        self
+    }
+
+    /// Parse SQL schema definitions from a named source (e.g. a file path)
+    ///
+    /// The name is used to attribute definitions in diagnostics.
+    pub fn parse_named(&mut self, name: &str, sql: &str) -> Result<(), Vec<Diagnostic>> {
+        self.source = Some(name.to_string());
+        let result = self.parse(sql);
+        self.source = None;
+        result
+    }
+
     /// Parse SQL schema definitions and build the catalog
     pub fn parse(&mut self, sql: &str) -> Result<(), Vec<Diagnostic>> {
         let dialect = self.dialect.parser_dialect();
@@ -52,21 +204,40 @@ impl SchemaBuilder {
             }
             Err(_) => {
                 // Fall back to statement-by-statement parsing to skip unsupported syntax
+                self.fallback_parses += 1;
                 self.parse_statements_individually(sql);
             }
         }
+        if DiagnosticKind::DynamicSqlConcatenation.is_enabled_by(&self.enabled_rules) {
+            self.diagnostics.extend(dynamic_sql::check(sql));
+        }
+        self.attribute_diagnostics(self.source.clone());
 
-        if self
+        let errors = self
             .diagnostics
             .iter()
-            .any(|d| d.severity == crate::error::Severity::Error)
-        {
-            Err(std::mem::take(&mut self.diagnostics))
+            .filter(|d| d.severity == crate::error::Severity::Error)
+            .count();
+        if errors > self.recovered_errors {
+            self.diagnostic_sources.clear();
+            self.recovered_errors = 0;
+            let mut diagnostics = std::mem::take(&mut self.diagnostics);
+            for diagnostic in &mut diagnostics {
+                diagnostic.origin = Some(DiagnosticOrigin::Schema);
+            }
+            Err(diagnostics)
         } else {
             Ok(())
         }
     }
 
+    /// Number of parsed inputs that failed to parse as a whole, and were
+    /// parsed statement by statement instead, skipping statements that
+    /// failed on their own
+    pub fn fallback_parses(&self) -> usize {
+        self.fallback_parses
+    }
+
     /// Parse SQL statements individually, skipping those that fail to parse.
     /// This allows sqlsurge to handle schema files containing unsupported syntax
     /// (e.g., CREATE FUNCTION, CREATE TRIGGER, CREATE DOMAIN) by gracefully
@@ -80,67 +251,407 @@ impl SchemaBuilder {
                 continue;
             }
 
-            match Parser::parse_sql(dialect.as_ref(), trimmed) {
+            let offset = trimmed.as_ptr() as usize - sql.as_ptr() as usize;
+            let before = &sql[..offset];
+            let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+            self.position_offset = (
+                before.matches('\n').count(),
+                before[line_start..].chars().count(),
+            );
+
+            let parsed = Parser::parse_sql(dialect.as_ref(), trimmed).or_else(|e| {
+                match strip_not_valid(dialect.as_ref(), trimmed) {
+                    Some(stripped) => Parser::parse_sql(dialect.as_ref(), &stripped),
+                    None => Err(e),
+                }
+            });
+            if parsed.is_err() {
+                if let Some((name, base)) = domain_definition(dialect.as_ref(), trimmed) {
+                    let name = object_name_to_qualified(&name).name.to_lowercase();
+                    self.domains.insert(name, SqlType::from_ast(&base));
+                    continue;
+                }
+            }
+            match parsed {
                 Ok(stmts) => {
                     for stmt in stmts {
                         self.process_statement(&stmt);
                     }
                 }
-                Err(_) => {
-                    // Silently skip unparseable statements (functions, triggers, etc.)
+                // Skip unparseable statements (functions, triggers,
+                // ALTER TABLE ... VALIDATE CONSTRAINT, etc.)
+                Err(e) if self.report_skipped => {
+                    // The error's position is in the statement; the span says where it is
+                    let message = e.to_string();
+                    let message = message
+                        .rfind(" at Line: ")
+                        .map_or(message.as_str(), |at| &message[..at]);
+                    let (line, column) = self.position_offset;
+                    let length = trimmed.lines().next().map_or(0, |l| l.chars().count());
+                    self.diagnostics.push(
+                        Diagnostic::warning(
+                            DiagnosticKind::ParseError,
+                            format!("Parse error: {}; the statement was skipped", message),
+                        )
+                        .with_span(Span::with_location(
+                            line + 1,
+                            column + 1,
+                            length.min(50),
+                        )),
+                    );
                 }
+                Err(_) => {}
             }
         }
+        self.position_offset = (0, 0);
+    }
+
+    /// Where a definition at `location` is, when parsing a named source
+    fn origin(&self, location: Location) -> Option<SchemaOrigin> {
+        let file = self.source.clone()?;
+        let (line, column) = self.position(location)?;
+        Some(SchemaOrigin { file, line, column })
+    }
+
+    /// Where a foreign key names the table it references
+    fn reference_origin(&self, foreign_table: &ObjectName) -> Option<SchemaOrigin> {
+        self.origin(foreign_table.span().start)
+    }
+
+    /// Line and column of `location` in the input being parsed
+    fn position(&self, location: Location) -> Option<(usize, usize)> {
+        if location.line == 0 {
+            return None;
+        }
+        let (line_offset, column_offset) = self.position_offset;
+        let line = location.line as usize;
+        let column = location.column as usize + if line == 1 { column_offset } else { 0 };
+        Some((line + line_offset, column))
+    }
+
+    /// A parser span as a diagnostic span in the input being parsed
+    fn span(&self, span: sqlparser::tokenizer::Span) -> Option<Span> {
+        let (line, column) = self.position(span.start)?;
+        let (end_line, end_column) = self.position(span.end).unwrap_or((line, column));
+        let mut span = Span::from_sqlparser(&span);
+        (span.line, span.column, span.end_line, span.end_column) =
+            (line, column, end_line, end_column);
+        Some(span)
+    }
+
+    /// Record the source of the diagnostics reported since the last call
+    fn attribute_diagnostics(&mut self, source: Option<String>) {
+        self.diagnostic_sources
+            .resize(self.diagnostics.len(), source);
+    }
+
+    /// Report a diagnostic found by `build()` at the definition it is about,
+    /// underlining `length` characters there
+    fn report_at(&mut self, diagnostic: Diagnostic, origin: Option<SchemaOrigin>, length: usize) {
+        self.attribute_diagnostics(None);
+        let diagnostic = match &origin {
+            Some(o) if diagnostic.span.is_none() => {
+                diagnostic.with_span(Span::with_location(o.line, o.column, length))
+            }
+            _ => diagnostic,
+        };
+        self.diagnostics.push(diagnostic);
+        self.diagnostic_sources.push(origin.map(|o| o.file));
     }
 
     /// Process a single SQL statement
+    ///
+    /// Diagnostics it reports without a position point at the statement.
     fn process_statement(&mut self, stmt: &Statement) {
+        let reported = self.diagnostics.len();
+        self.process_statement_inner(stmt);
+        if let Some(span) = self.span(stmt.span()) {
+            for diag in &mut self.diagnostics[reported..] {
+                diag.span.get_or_insert(span);
+            }
+        }
+    }
+
+    fn process_statement_inner(&mut self, stmt: &Statement) {
+        self.check_duplicate_definition(stmt);
+
         match stmt {
             Statement::CreateTable(create) => {
                 self.process_create_table(create);
             }
+            Statement::Drop {
+                object_type,
+                if_exists,
+                names,
+                ..
+            } => {
+                self.process_drop(object_type, names, *if_exists);
+            }
+            Statement::CreateIndex(create) => {
+                self.process_create_index(create);
+            }
             Statement::CreateType {
                 name,
                 representation,
             } => {
                 self.process_create_type(name, representation);
             }
+            Statement::CreateFunction(create) => {
+                self.process_create_function(create);
+            }
             Statement::CreateView {
                 name,
                 columns,
                 query,
                 materialized,
+                if_not_exists,
                 ..
             } => {
-                self.process_create_view(name, columns, query, *materialized);
+                let exists = self.relation_exists(&object_name_to_qualified(name));
+                if !(*if_not_exists && exists) {
+                    self.process_create_view(name, columns, query, *materialized);
+                }
             }
             Statement::AlterTable {
-                name, operations, ..
+                name,
+                if_exists,
+                operations,
+                ..
+            } => {
+                self.process_alter_table(name, operations, *if_exists);
+            }
+            Statement::Comment {
+                object_type,
+                object_name,
+                comment,
+                if_exists,
             } => {
-                self.process_alter_table(name, operations);
+                self.process_comment(object_type, object_name, comment.as_deref(), *if_exists);
             }
             _ => {}
         }
     }
 
+    /// Record a CREATE TABLE/VIEW/TYPE and report it if the object was already defined
+    ///
+    /// Tables and views share a namespace. `CREATE OR REPLACE` and
+    /// `IF NOT EXISTS` are intentional redefinitions and are not reported.
+    fn check_duplicate_definition(&mut self, stmt: &Statement) {
+        let (kind, key, name, or_replace, if_not_exists) = match stmt {
+            Statement::CreateTable(create) => {
+                let name = object_name_to_qualified(&create.name);
+                (
+                    "Table",
+                    self.relation_key(&name),
+                    name.to_string(),
+                    create.or_replace,
+                    create.if_not_exists,
+                )
+            }
+            Statement::CreateView {
+                name,
+                or_replace,
+                if_not_exists,
+                ..
+            } => {
+                let name = object_name_to_qualified(name);
+                (
+                    "View",
+                    self.relation_key(&name),
+                    name.to_string(),
+                    *or_replace,
+                    *if_not_exists,
+                )
+            }
+            Statement::CreateType {
+                name,
+                representation: UserDefinedTypeRepresentation::Enum { .. },
+            } => {
+                // Enums are stored by bare name
+                let name = object_name_to_qualified(name).name;
+                ("Enum type", format!("type:{}", name), name, false, false)
+            }
+            _ => return,
+        };
+
+        let origin = self.describe_origin(stmt);
+        if let Some(previous) = self.definitions.get(&key) {
+            if if_not_exists {
+                // Skipped: the first definition stays in effect
+                return;
+            }
+            if !or_replace {
+                let message = format!(
+                    "{} '{}' is defined more than once: first as {} {}, then {}",
+                    kind,
+                    name,
+                    match previous.kind {
+                        "Enum type" => "an enum type",
+                        "View" => "a view",
+                        _ => "a table",
+                    },
+                    previous.origin,
+                    origin
+                );
+                let diag = if self.strict_duplicates {
+                    Diagnostic::error(DiagnosticKind::DuplicateDefinition, message)
+                } else {
+                    Diagnostic::warning(DiagnosticKind::DuplicateDefinition, message)
+                };
+                // Only views can be redefined in place with CREATE OR REPLACE
+                let help = match kind {
+                    "View" => "remove one of them or use CREATE OR REPLACE VIEW",
+                    "Table" => "remove one of them, DROP TABLE before redefining it, or use CREATE TABLE IF NOT EXISTS to keep the first",
+                    _ => "remove one of them or DROP TYPE before redefining it",
+                };
+                self.diagnostics.push(diag.with_help(format!(
+                    "The later definition replaces the earlier one; {}",
+                    help
+                )));
+            }
+        }
+        self.definitions.insert(key, Definition { kind, origin });
+    }
+
+    /// Namespace key for a table or view (fully qualified with the default schema)
+    fn relation_key(&self, name: &QualifiedName) -> String {
+        format!(
+            "relation:{}.{}",
+            name.schema
+                .as_deref()
+                .unwrap_or(&self.catalog.default_schema),
+            name.name
+        )
+    }
+
+    /// Describe where a statement came from: a snippet plus the source name when known
+    fn describe_origin(&self, stmt: &Statement) -> String {
+        const MAX_SNIPPET: usize = 60;
+        let text = stmt.to_string();
+        let snippet = match text.char_indices().nth(MAX_SNIPPET) {
+            Some((idx, _)) => format!("{}...", &text[..idx]),
+            None => text,
+        };
+        match &self.source {
+            Some(source) => format!("in {} (`{}`)", source, snippet),
+            None => format!("by `{}`", snippet),
+        }
+    }
+
     /// Process CREATE TABLE statement
     fn process_create_table(&mut self, create: &sqlparser::ast::CreateTable) {
+        let table = self.build_table_def(create);
+
+        // IF NOT EXISTS keeps the first definition. Only report a conflict when
+        // the skipped definition has columns the existing table lacks; the
+        // existing table having more columns is expected after later ALTERs.
+        if create.if_not_exists && self.relation_exists(&table.name) {
+            if let Some(existing) = self.catalog.get_table(&table.name) {
+                let conflicting = table.columns.values().any(|col| {
+                    existing.get_column(&col.name).map_or(true, |e| {
+                        e.data_type.canonical(self.dialect) != col.data_type.canonical(self.dialect)
+                    })
+                });
+                if conflicting {
+                    self.diagnostics.push(
+                        Diagnostic::info(
+                            DiagnosticKind::DuplicateDefinition,
+                            format!(
+                                "CREATE TABLE IF NOT EXISTS for '{}' was skipped, but its definition ({}) differs from the existing one ({})",
+                                table.name,
+                                column_signature(&table),
+                                column_signature(existing)
+                            ),
+                        )
+                        .with_help("The existing definition is kept"),
+                    );
+                }
+            }
+            return;
+        }
+
+        let name_span = create.name.0.last().map_or(create.name.span(), |n| n.span);
+        self.check_reserved_name("Table", &table.name.name, &table.name.name, name_span);
+        // A repeated column was reported as a duplicate; its first definition is checked
+        let mut checked = HashSet::new();
+        for column in &create.columns {
+            if checked.insert(&column.name.value) {
+                self.check_reserved_name(
+                    "Column",
+                    &format!("{}.{}", table.name, column.name.value),
+                    &column.name.value,
+                    column.name.span,
+                );
+            }
+        }
+        self.catalog.add_table(table);
+    }
+
+    /// Warn when a table or column is named after a reserved keyword, which
+    /// every query then has to quote; `span` is where the name is written
+    fn check_reserved_name(
+        &mut self,
+        kind: &str,
+        display: &str,
+        name: &str,
+        span: sqlparser::tokenizer::Span,
+    ) {
+        if !self.dialect.is_reserved(name) {
+            return;
+        }
+        let mut diag = Diagnostic::warning(
+            DiagnosticKind::ReservedIdentifier,
+            format!(
+                "{} '{}' is named after the reserved keyword {} in {}",
+                kind,
+                display,
+                name.to_uppercase(),
+                self.dialect.product_name()
+            ),
+        )
+        .with_help(format!(
+            "Queries must quote it as {}; consider renaming it",
+            self.dialect.quote_identifier(name)
+        ));
+        if let Some(span) = self.span(span) {
+            diag = diag.with_span(span);
+        }
+        self.diagnostics.push(diag);
+    }
+
+    /// Build a TableDef from a CREATE TABLE statement without adding it to the catalog
+    fn build_table_def(&mut self, create: &sqlparser::ast::CreateTable) -> TableDef {
         let name = object_name_to_qualified(&create.name);
         let mut table = TableDef::new(name);
+        table.origin = self.origin(create.name.span().start);
+        table.set_comment(create.comment.as_ref().map(|c| match c {
+            CommentDef::WithEq(text)
+            | CommentDef::WithoutEq(text)
+            | CommentDef::AfterColumnDefsWithoutEq(text) => text.clone(),
+        }));
 
-        // Process columns
+        // Process columns; `CREATE TABLE t ()` has none
         for column in &create.columns {
             let col_name = column.name.value.clone();
-            let data_type = SqlType::from_ast(&column.data_type);
-
-            let mut col_def = ColumnDef::new(&col_name, data_type);
+            if table.column_exists(&col_name) {
+                self.report_duplicate_column(&table.name, &column.name);
+                continue;
+            }
+            let mut col_def = self.new_column(&col_name, &column.data_type);
+            col_def.origin = self.origin(column.name.span.start);
+            if let Some(mut diag) =
+                features::check_schema_column(column, self.dialect, self.version)
+            {
+                diag.span = self.span(column.name.span);
+                self.diagnostics.push(diag);
+            }
 
             // Process column options
             for option in &column.options {
                 self.process_column_option(&mut col_def, &mut table, option);
             }
 
-            table.columns.insert(col_name, col_def);
+            table.add_column(col_def);
         }
 
         // Process table constraints
@@ -148,7 +659,130 @@ impl SchemaBuilder {
             self.process_table_constraint(&mut table, constraint);
         }
 
-        self.catalog.add_table(table);
+        for column in &create.columns {
+            self.validate_generated_column(&table, column);
+        }
+
+        table
+    }
+
+    /// Report a column defined twice in a table, whose first definition is kept
+    fn report_duplicate_column(&mut self, table: &QualifiedName, column: &Ident) {
+        let mut diag = Diagnostic::error(
+            DiagnosticKind::DuplicateDefinition,
+            format!(
+                "Column '{}' is defined more than once in table '{}'",
+                column.value, table
+            ),
+        )
+        .with_help("The first definition is kept; remove or rename the other one");
+        if let Some(span) = self.span(column.span) {
+            diag = diag.with_span(span);
+        }
+        self.diagnostics.push(diag);
+        self.recovered_errors += 1;
+    }
+
+    /// Whether a table or view with this name exists
+    fn relation_exists(&self, name: &QualifiedName) -> bool {
+        self.catalog.table_exists(name) || self.catalog.view_exists(name)
+    }
+
+    /// Process DROP TABLE / DROP VIEW / DROP TYPE
+    fn process_drop(&mut self, object_type: &ObjectType, names: &[ObjectName], if_exists: bool) {
+        for name in names {
+            let qualified = object_name_to_qualified(name);
+            if *object_type == ObjectType::Index {
+                // Unnamed indexes get generated names that aren't tracked,
+                // so dropping an unknown index isn't reported
+                self.catalog.remove_index(&qualified);
+                continue;
+            }
+            let (kind, dropped, key) = match object_type {
+                ObjectType::Table => (
+                    "table",
+                    self.catalog.remove_table(&qualified).is_some(),
+                    self.relation_key(&qualified),
+                ),
+                ObjectType::View => (
+                    "view",
+                    self.catalog.remove_view(&qualified).is_some(),
+                    self.relation_key(&qualified),
+                ),
+                ObjectType::Type => (
+                    "type",
+                    self.catalog.remove_enum(&qualified.name).is_some(),
+                    format!("type:{}", qualified.name),
+                ),
+                _ => continue,
+            };
+
+            if dropped {
+                self.pending_views.retain(|v| v.key != key);
+                self.definitions.remove(&key);
+            } else if !if_exists {
+                let mut diag = Diagnostic::warning(
+                    DiagnosticKind::TableNotFound,
+                    format!(
+                        "DROP {} references {} '{}' which was not found in schema",
+                        kind.to_uppercase(),
+                        kind,
+                        name
+                    ),
+                )
+                .with_help("Use DROP ... IF EXISTS if the object may not exist");
+                if let Some(span) = self.span(name.span()) {
+                    diag = diag.with_span(span);
+                }
+                self.diagnostics.push(diag);
+            }
+        }
+    }
+
+    /// Process CREATE INDEX statement
+    ///
+    /// An unnamed index (PostgreSQL) is recorded under the name PostgreSQL
+    /// generates for it, `orders_user_id_idx`.
+    fn process_create_index(&mut self, create: &CreateIndex) {
+        let table_name = object_name_to_qualified(&create.table_name);
+        // Materialized views can be indexed too
+        let (relation, indexes) = match self.catalog.get_table_mut(&table_name) {
+            Some(table) => (table.name.name.clone(), &mut table.indexes),
+            None => match self.catalog.get_view_mut(&table_name) {
+                Some(view) if view.materialized => (view.name.name.clone(), &mut view.indexes),
+                _ => return,
+            },
+        };
+        let columns: Vec<String> = create
+            .columns
+            .iter()
+            .map(|c| match &c.expr {
+                Expr::Identifier(ident) => ident.value.clone(),
+                expr => expr.to_string(),
+            })
+            .collect();
+        let name = match create.name.as_ref().and_then(|n| n.0.last()) {
+            Some(name) => name.value.clone(),
+            None => {
+                let parts: Vec<&str> = create
+                    .columns
+                    .iter()
+                    .map(|c| match &c.expr {
+                        Expr::Identifier(ident) => ident.value.as_str(),
+                        _ => "expr",
+                    })
+                    .collect();
+                format!("{}_{}_idx", relation, parts.join("_"))
+            }
+        };
+        if create.if_not_exists && indexes.iter().any(|i| i.name == name) {
+            return;
+        }
+        indexes.push(IndexDef {
+            name,
+            columns,
+            unique: create.unique,
+        });
     }
 
     /// Process CREATE VIEW statement
@@ -160,91 +794,142 @@ impl SchemaBuilder {
         materialized: bool,
     ) {
         let qualified = object_name_to_qualified(name);
+        let key = self.relation_key(&qualified);
+        self.pending_views.retain(|v| v.key != key);
 
-        // Determine column names: explicit column list or inferred from SELECT
+        // Determine column names: explicit column list or inferred from SELECT.
+        // Schema files aren't ordered, so a `*` over a relation defined later
+        // is resolved again once everything is parsed.
         let column_names = if !columns.is_empty() {
             columns.iter().map(|c| c.name.value.clone()).collect()
         } else {
-            self.infer_view_columns(&query.body)
+            match self.infer_view_columns(query) {
+                Ok(column_names) => {
+                    if column_names.is_empty() {
+                        let diagnostic = self.unknown_view_columns(&qualified);
+                        self.diagnostics.push(diagnostic);
+                    }
+                    column_names
+                }
+                Err(_) => {
+                    self.pending_views.push(PendingView {
+                        key,
+                        name: qualified.clone(),
+                        query: Box::new(query.clone()),
+                    });
+                    Vec::new()
+                }
+            }
         };
 
         let view = ViewDef {
             name: qualified,
             columns: column_names,
             materialized,
+            dependencies: query_relations(query),
+            query: Some(query.to_string()),
+            indexes: Vec::new(),
+            origin: self.origin(name.span().start),
         };
         self.catalog.add_view(view);
     }
 
-    /// Infer column names from a SELECT body for VIEW definition
-    fn infer_view_columns(&self, set_expr: &sqlparser::ast::SetExpr) -> Vec<String> {
-        use sqlparser::ast::{Expr, SelectItem, SetExpr};
+    /// Infer the column names of a view from its query
+    ///
+    /// Uses the analyzer's projection expansion, so views over set
+    /// operations (named by their left arm), VALUES and CTEs get the same
+    /// columns as a derived table would. Fails with the name of the
+    /// relation if a wildcard refers to a table or view that isn't (fully)
+    /// defined yet; columns that can't be determined otherwise are unknown
+    /// (empty), which the analyzer matches with any column.
+    fn infer_view_columns(&self, query: &Query) -> Result<Vec<String>, QualifiedName> {
+        let pending = self.pending_views.iter().map(|v| v.name.clone()).collect();
+        let mut expander = ProjectionExpander::for_view(&self.catalog, pending);
+        match expander.query_columns(query) {
+            Some(columns) => Ok(columns),
+            None => match expander.missing() {
+                Some(name) => Err(name.clone()),
+                None => Ok(Vec::new()),
+            },
+        }
+    }
 
-        let mut columns = Vec::new();
+    /// Warning for a view whose columns could not be determined; queries
+    /// may use any column of it
+    fn unknown_view_columns(&self, name: &QualifiedName) -> Diagnostic {
+        Diagnostic::warning(
+            DiagnosticKind::UnsupportedFeature,
+            format!(
+                "Columns of view '{}' could not be determined; queries may use any column of it",
+                self.dialect.display_name(name)
+            ),
+        )
+        .with_help("Name the view's columns: CREATE VIEW name (column, ...) AS ...")
+    }
 
-        if let SetExpr::Select(select) = set_expr {
-            for item in &select.projection {
-                match item {
-                    SelectItem::UnnamedExpr(Expr::Identifier(ident)) => {
-                        columns.push(ident.value.clone());
-                    }
-                    SelectItem::ExprWithAlias { alias, .. } => {
-                        columns.push(alias.value.clone());
-                    }
-                    SelectItem::UnnamedExpr(Expr::CompoundIdentifier(idents)) => {
-                        if let Some(col) = idents.last() {
-                            columns.push(col.value.clone());
-                        }
-                    }
-                    SelectItem::Wildcard(_) => {
-                        // Expand * by looking up FROM tables in the catalog
-                        for table_with_joins in &select.from {
-                            self.expand_wildcard_columns(&table_with_joins.relation, &mut columns);
+    /// Re-infer the columns of views whose wildcards referenced relations that
+    /// were defined later, until no more can be resolved
+    fn resolve_pending_views(&mut self) {
+        loop {
+            let mut progressed = false;
+            let mut i = 0;
+            while i < self.pending_views.len() {
+                match self.infer_view_columns(&self.pending_views[i].query) {
+                    Ok(columns) => {
+                        let view = self.pending_views.remove(i);
+                        if columns.is_empty() {
+                            let origin = self
+                                .catalog
+                                .get_view(&view.name)
+                                .and_then(|v| v.origin.clone());
+                            let diagnostic = self.unknown_view_columns(&view.name);
+                            self.report_at(diagnostic, origin, view.name.name.chars().count());
                         }
-                    }
-                    SelectItem::QualifiedWildcard(name, _) => {
-                        // table.* - try to expand from the specified table
-                        let table_name = object_name_to_qualified(name);
-                        if let Some(table_def) = self.catalog.get_table(&table_name) {
-                            for col_name in table_def.columns.keys() {
-                                columns.push(col_name.clone());
-                            }
+                        if let Some(view_def) = self.catalog.get_view_mut(&view.name) {
+                            view_def.columns = columns;
                         }
+                        progressed = true;
                     }
-                    _ => {
-                        // Other expressions without alias - generate placeholder
-                        columns.push(format!("?column?{}", columns.len() + 1));
-                    }
+                    Err(_) => i += 1,
                 }
             }
+            if !progressed {
+                break;
+            }
         }
 
-        columns
-    }
-
-    /// Expand wildcard columns from a table factor
-    fn expand_wildcard_columns(
-        &self,
-        factor: &sqlparser::ast::TableFactor,
-        columns: &mut Vec<String>,
-    ) {
-        use sqlparser::ast::TableFactor;
-        if let TableFactor::Table { name, .. } = factor {
-            let table_name = object_name_to_qualified(name);
-            if let Some(table_def) = self.catalog.get_table(&table_name) {
-                for col_name in table_def.columns.keys() {
-                    columns.push(col_name.clone());
-                }
-            } else if let Some(view_def) = self.catalog.get_view(&table_name) {
-                for col_name in &view_def.columns {
-                    columns.push(col_name.clone());
-                }
-            }
+        for view in std::mem::take(&mut self.pending_views) {
+            let missing = self
+                .infer_view_columns(&view.query)
+                .err()
+                .map(|name| self.dialect.display_name(&name))
+                .unwrap_or_default();
+            let origin = self
+                .catalog
+                .get_view(&view.name)
+                .and_then(|v| v.origin.clone());
+            self.report_at(
+                Diagnostic::warning(
+                    DiagnosticKind::TableNotFound,
+                    format!(
+                        "View '{}' references '{}' which was not found in schema; its columns could not be determined",
+                        self.dialect.display_name(&view.name),
+                        missing
+                    ),
+                ),
+                origin,
+                view.name.name.chars().count(),
+            );
         }
     }
 
     /// Process ALTER TABLE statement
-    fn process_alter_table(&mut self, name: &ObjectName, operations: &[AlterTableOperation]) {
+    fn process_alter_table(
+        &mut self,
+        name: &ObjectName,
+        operations: &[AlterTableOperation],
+        if_exists: bool,
+    ) {
         // Skip ALTER TABLE if it contains no schema-affecting operations.
         // Operations like OWNER TO, ENABLE/DISABLE TRIGGER, etc. don't affect
         // the schema catalog and should not produce warnings.
@@ -256,6 +941,9 @@ impl SchemaBuilder {
                     | AlterTableOperation::RenameColumn { .. }
                     | AlterTableOperation::RenameTable { .. }
                     | AlterTableOperation::AddConstraint(_)
+                    | AlterTableOperation::DropConstraint { .. }
+                    | AlterTableOperation::DropPrimaryKey
+                    | AlterTableOperation::RenameConstraint { .. }
             )
         });
 
@@ -267,25 +955,57 @@ impl SchemaBuilder {
 
         // Check if table exists
         if !self.catalog.table_exists(&table_name) {
-            self.diagnostics.push(
-                Diagnostic::warning(
-                    DiagnosticKind::TableNotFound,
-                    format!(
-                        "ALTER TABLE references table '{}' which was not found in schema",
-                        table_name
-                    ),
-                )
-                .with_help("Ensure the CREATE TABLE statement appears before ALTER TABLE"),
-            );
+            if if_exists {
+                return;
+            }
+            let mut diag = Diagnostic::warning(
+                DiagnosticKind::TableNotFound,
+                format!(
+                    "ALTER TABLE references table '{}' which was not found in schema",
+                    name
+                ),
+            )
+            .with_help("Ensure the CREATE TABLE statement appears before ALTER TABLE");
+            if let Some(span) = self.span(name.span()) {
+                diag = diag.with_span(span);
+            }
+            self.diagnostics.push(diag);
             return;
         }
 
         for operation in operations {
             match operation {
-                AlterTableOperation::AddColumn { column_def, .. } => {
+                AlterTableOperation::AddColumn {
+                    column_def,
+                    if_not_exists,
+                    column_position,
+                    ..
+                } => {
                     let col_name = column_def.name.value.clone();
-                    let data_type = SqlType::from_ast(&column_def.data_type);
-                    let mut col = ColumnDef::new(&col_name, data_type);
+                    if *if_not_exists
+                        && self
+                            .catalog
+                            .get_table(&table_name)
+                            .is_some_and(|t| t.column_exists(&col_name))
+                    {
+                        continue;
+                    }
+                    if self
+                        .catalog
+                        .get_table(&table_name)
+                        .is_some_and(|t| t.column_exists(&col_name))
+                    {
+                        self.report_duplicate_column(&table_name, &column_def.name);
+                        continue;
+                    }
+                    self.check_reserved_name(
+                        "Column",
+                        &format!("{}.{}", table_name, col_name),
+                        &col_name,
+                        column_def.name.span,
+                    );
+                    let mut col = self.new_column(&col_name, &column_def.data_type);
+                    col.origin = self.origin(column_def.name.span.start);
 
                     // Process column options
                     // We need a temporary mutable table reference for check constraints
@@ -297,60 +1017,115 @@ impl SchemaBuilder {
                             ColumnOption::Default(expr) => {
                                 col.default = Some(expr_to_default(expr));
                             }
-                            ColumnOption::Unique { is_primary, .. } => {
-                                if *is_primary {
-                                    col.is_primary_key = true;
-                                    col.nullable = false;
-                                }
+                            ColumnOption::Comment(text) => col.set_comment(Some(text.clone())),
+                            ColumnOption::Unique { is_primary, .. } if *is_primary => {
+                                col.is_primary_key = true;
+                                col.nullable = false;
                             }
                             ColumnOption::Generated {
                                 generated_as,
-                                generation_expr,
+                                generation_expr: None,
                                 ..
                             } => {
-                                if generation_expr.is_none() {
-                                    use sqlparser::ast::GeneratedAs;
-                                    let kind = match generated_as {
-                                        GeneratedAs::Always => IdentityKind::Always,
-                                        GeneratedAs::ByDefault => IdentityKind::ByDefault,
-                                        _ => continue,
-                                    };
-                                    col.identity = Some(kind);
-                                    col.nullable = false;
-                                }
+                                use sqlparser::ast::GeneratedAs;
+                                let kind = match generated_as {
+                                    GeneratedAs::Always => IdentityKind::Always,
+                                    GeneratedAs::ByDefault => IdentityKind::ByDefault,
+                                    _ => continue,
+                                };
+                                col.identity = Some(kind);
+                                col.nullable = false;
+                            }
+                            ColumnOption::Generated {
+                                generation_expr: Some(expr),
+                                generation_expr_mode,
+                                ..
+                            } => {
+                                col.generated =
+                                    Some(generated_column(expr, generation_expr_mode.as_ref()));
                             }
                             _ => {}
                         }
                     }
 
+                    let reference_origin =
+                        column_def.options.iter().find_map(|o| match &o.option {
+                            ColumnOption::ForeignKey { foreign_table, .. } => {
+                                self.reference_origin(foreign_table)
+                            }
+                            _ => None,
+                        });
                     if let Some(table) = self.catalog.get_table_mut(&table_name) {
                         // Collect check constraints from column options
                         for option in &column_def.options {
-                            if let ColumnOption::Check(expr) = &option.option {
-                                let check = CheckConstraintDef {
-                                    name: option.name.as_ref().map(|n| n.value.clone()),
-                                    expression: expr.to_string(),
-                                };
-                                table.check_constraints.push(check);
+                            match &option.option {
+                                ColumnOption::Check(expr) => {
+                                    let check = CheckConstraintDef {
+                                        name: option.name.as_ref().map(|n| n.value.clone()),
+                                        expression: expr.to_string(),
+                                    };
+                                    table.check_constraints.push(check);
+                                }
+                                ColumnOption::Unique {
+                                    is_primary: false, ..
+                                } => {
+                                    table.unique_constraints.push(UniqueConstraintDef {
+                                        name: option.name.as_ref().map(|n| n.value.clone()),
+                                        columns: vec![col_name.clone()],
+                                    });
+                                }
+                                ColumnOption::ForeignKey { .. } => {
+                                    table.foreign_keys.extend(inline_foreign_key_def(
+                                        &col_name,
+                                        option,
+                                        reference_origin.clone(),
+                                    ));
+                                }
+                                _ => {}
+                            }
+                        }
+                        match column_position {
+                            Some(MySQLColumnPosition::First) => table.insert_column(0, col),
+                            Some(MySQLColumnPosition::After(after)) => {
+                                let index = table
+                                    .columns
+                                    .get_index_of(&after.value)
+                                    .map_or(table.columns.len(), |i| i + 1);
+                                table.insert_column(index, col);
                             }
+                            None => table.add_column(col),
                         }
-                        table.columns.insert(col_name, col);
+                    }
+                    if let Some(table) = self.catalog.get_table_arc(&table_name) {
+                        self.validate_generated_column(&table, column_def);
                     }
                 }
-                AlterTableOperation::DropColumn { column_name, .. } => {
+                AlterTableOperation::DropColumn {
+                    column_name,
+                    cascade,
+                    ..
+                } => {
+                    let key = self.catalog.get_table(&table_name).is_some_and(|table| {
+                        table.primary_key.as_ref().map_or_else(
+                            || {
+                                table
+                                    .get_column(&column_name.value)
+                                    .is_some_and(|c| c.is_primary_key)
+                            },
+                            |pk| pk.columns.contains(&column_name.value),
+                        )
+                    });
                     if let Some(table) = self.catalog.get_table_mut(&table_name) {
-                        table.columns.shift_remove(&column_name.value);
+                        table.drop_column(&column_name.value);
                     }
+                    self.drop_referencing_foreign_keys(&table_name, column_name, key, *cascade);
                 }
                 AlterTableOperation::RenameColumn {
                     old_column_name,
                     new_column_name,
                 } => {
                     if let Some(table) = self.catalog.get_table_mut(&table_name) {
-                        if let Some(mut col) = table.columns.shift_remove(&old_column_name.value) {
-                            col.name = new_column_name.value.clone();
-                            table.columns.insert(new_column_name.value.clone(), col);
-                        }
+                        table.rename_column(&old_column_name.value, &new_column_name.value);
                     }
                 }
                 AlterTableOperation::RenameTable {
@@ -364,12 +1139,28 @@ impl SchemaBuilder {
                         .clone();
                     if let Some(schema) = self.catalog.schemas.get_mut(&schema_name) {
                         if let Some(mut table) = schema.tables.shift_remove(&table_name.name) {
-                            table.name = new_qualified.clone();
+                            Arc::make_mut(&mut table).name = new_qualified.clone();
                             schema.tables.insert(new_qualified.name, table);
                         }
                     }
                 }
                 AlterTableOperation::AddConstraint(constraint) => {
+                    // The database rejects a constraint on a missing column
+                    let missing = self
+                        .catalog
+                        .get_table(&table_name)
+                        .map(|table| added_constraint_columns(table, constraint))
+                        .unwrap_or_default();
+                    if !missing.is_empty() {
+                        self.diagnostics.extend(missing);
+                        continue;
+                    }
+                    let reference_origin = match constraint {
+                        TableConstraint::ForeignKey { foreign_table, .. } => {
+                            self.reference_origin(foreign_table)
+                        }
+                        _ => None,
+                    };
                     if let Some(table) = self.catalog.get_table_mut(&table_name) {
                         // Reuse the same constraint processing logic
                         match constraint {
@@ -386,27 +1177,22 @@ impl SchemaBuilder {
                                 }
                                 table.primary_key = Some(pk);
                             }
-                            TableConstraint::ForeignKey {
+                            TableConstraint::ForeignKey { .. } => {
+                                table
+                                    .foreign_keys
+                                    .extend(foreign_key_def(constraint, reference_origin));
+                            }
+                            TableConstraint::Unique {
                                 columns,
-                                foreign_table,
-                                referred_columns,
                                 name,
+                                index_name,
                                 ..
                             } => {
-                                let fk = crate::schema::ForeignKeyDef {
-                                    name: name.as_ref().map(|n| n.value.clone()),
-                                    columns: columns.iter().map(|c| c.value.clone()).collect(),
-                                    references_table: object_name_to_qualified(foreign_table),
-                                    references_columns: referred_columns
-                                        .iter()
-                                        .map(|c| c.value.clone())
-                                        .collect(),
-                                };
-                                table.foreign_keys.push(fk);
-                            }
-                            TableConstraint::Unique { columns, name, .. } => {
                                 let unique = UniqueConstraintDef {
-                                    name: name.as_ref().map(|n| n.value.clone()),
+                                    name: name
+                                        .as_ref()
+                                        .or(index_name.as_ref())
+                                        .map(|n| n.value.clone()),
                                     columns: columns.iter().map(|c| c.value.clone()).collect(),
                                 };
                                 table.unique_constraints.push(unique);
@@ -418,10 +1204,54 @@ impl SchemaBuilder {
                                 };
                                 table.check_constraints.push(check);
                             }
-                            _ => {}
+                            TableConstraint::Index { .. }
+                            | TableConstraint::FulltextOrSpatial { .. } => {
+                                table.indexes.extend(index_def(constraint));
+                            }
                         }
                     }
                 }
+                AlterTableOperation::DropConstraint {
+                    if_exists, name, ..
+                } => {
+                    let dialect = self.dialect;
+                    let dropped = self
+                        .catalog
+                        .get_table_mut(&table_name)
+                        .is_some_and(|table| match find_constraint(table, &name.value, dialect) {
+                            Some(constraint) => {
+                                remove_constraint(table, constraint);
+                                true
+                            }
+                            None => false,
+                        });
+                    if !dropped && !*if_exists {
+                        self.constraint_not_found("DROP CONSTRAINT", name, &table_name);
+                    }
+                }
+                AlterTableOperation::DropPrimaryKey => {
+                    if let Some(table) = self.catalog.get_table_mut(&table_name) {
+                        remove_constraint(table, TableConstraintRef::PrimaryKey);
+                    }
+                }
+                AlterTableOperation::RenameConstraint { old_name, new_name } => {
+                    let dialect = self.dialect;
+                    let renamed = self
+                        .catalog
+                        .get_table_mut(&table_name)
+                        .is_some_and(|table| {
+                            match find_constraint(table, &old_name.value, dialect) {
+                                Some(constraint) => {
+                                    rename_constraint(table, constraint, &new_name.value);
+                                    true
+                                }
+                                None => false,
+                            }
+                        });
+                    if !renamed {
+                        self.constraint_not_found("RENAME CONSTRAINT", old_name, &table_name);
+                    }
+                }
                 _ => {
                     // Other ALTER TABLE operations - not yet supported
                 }
@@ -429,6 +1259,86 @@ impl SchemaBuilder {
         }
     }
 
+    /// Remove the foreign keys on or referencing a dropped column
+    ///
+    /// Like the database, a foreign key of the table over the column goes with
+    /// it. Foreign keys of other tables referencing it (`key` if it was the
+    /// primary key, which `REFERENCES t` without columns means) only do with
+    /// CASCADE; without it the database refuses the drop, which is reported.
+    fn drop_referencing_foreign_keys(
+        &mut self,
+        table_name: &QualifiedName,
+        column: &Ident,
+        key: bool,
+        cascade: bool,
+    ) {
+        let qualified = self.catalog.qualify(table_name);
+        let mut dropped: Vec<(QualifiedName, usize)> = Vec::new();
+        for table in self
+            .catalog
+            .schemas
+            .values()
+            .flat_map(|s| s.tables.values())
+        {
+            let own = self.catalog.qualify(&table.name) == qualified;
+            for (idx, fk) in table.foreign_keys.iter().enumerate() {
+                if own && fk.columns.contains(&column.value) {
+                    dropped.push((table.name.clone(), idx));
+                    continue;
+                }
+                let referenced = self.catalog.qualify(&fk.references_table) == qualified
+                    && if fk.references_columns.is_empty() {
+                        key
+                    } else {
+                        fk.references_columns.contains(&column.value)
+                    };
+                if !referenced {
+                    continue;
+                }
+                if !cascade {
+                    let mut diag = Diagnostic::warning(
+                        DiagnosticKind::ColumnNotFound,
+                        format!(
+                            "Column '{}.{}' is dropped but {} references it",
+                            table_name,
+                            column.value,
+                            constraint_description("FOREIGN KEY", &fk.name, table)
+                        ),
+                    )
+                    .with_help("Drop the foreign key first, or use DROP COLUMN ... CASCADE");
+                    if let Some(span) = self.span(column.span) {
+                        diag = diag.with_span(span);
+                    }
+                    self.diagnostics.push(diag);
+                }
+                dropped.push((table.name.clone(), idx));
+            }
+        }
+        // Highest index first, so the others stay valid
+        for (table_name, idx) in dropped.into_iter().rev() {
+            if let Some(table) = self.catalog.get_table_mut(&table_name) {
+                table.foreign_keys.remove(idx);
+            }
+        }
+    }
+
+    fn constraint_not_found(&mut self, operation: &str, name: &Ident, table_name: &QualifiedName) {
+        let mut diag = Diagnostic::warning(
+            DiagnosticKind::TableNotFound,
+            format!(
+                "{} references constraint '{}' which was not found on table '{}'",
+                operation,
+                name.value,
+                self.dialect.display_name(table_name)
+            ),
+        )
+        .with_help("Use DROP CONSTRAINT IF EXISTS if the constraint may not exist");
+        if let Some(span) = self.span(name.span) {
+            diag = diag.with_span(span);
+        }
+        self.diagnostics.push(diag);
+    }
+
     /// Process CREATE TYPE statement
     fn process_create_type(
         &mut self,
@@ -445,7 +1355,188 @@ impl SchemaBuilder {
                 self.catalog.add_enum(enum_def);
             }
             _ => {
-                // Composite types and others - not yet supported
+                // Composite types and others - not yet supported, but
+                // remembered so casts to them aren't reported
+                self.other_types.push(qualified.name.to_lowercase());
+            }
+        }
+    }
+
+    /// Record the signature of a CREATE FUNCTION (best effort)
+    ///
+    /// OUT arguments are not passed in calls; arguments with a default make
+    /// an overload without them. Trigger functions can't be called, and a
+    /// `void` function returns no usable type.
+    fn process_create_function(&mut self, create: &CreateFunction) {
+        let Some(return_type) = &create.return_type else {
+            return;
+        };
+        // Calls are matched by schema, so the signature keeps it
+        let name = object_name_to_qualified(&create.name).to_string();
+        let returns = match return_type.to_string().to_lowercase().as_str() {
+            "trigger" | "event_trigger" => return,
+            "void" => SqlType::Unknown,
+            _ => self.column_type(return_type),
+        };
+        let mut args = Vec::new();
+        let mut required = None;
+        for arg in create.args.iter().flatten() {
+            if arg.mode == Some(ArgMode::Out) {
+                continue;
+            }
+            if arg.default_expr.is_some() && required.is_none() {
+                required = Some(args.len());
+            }
+            args.push(self.column_type(&arg.data_type));
+        }
+        for count in required.unwrap_or(args.len())..=args.len() {
+            self.catalog.add_function(FunctionSig::new(
+                &name,
+                args[..count].to_vec(),
+                returns.clone(),
+            ));
+        }
+    }
+
+    /// A column of the declared type, before its options are applied
+    fn new_column(&self, name: &str, data_type: &DataType) -> ColumnDef {
+        let mut column = ColumnDef::new(name, self.column_type(data_type));
+        column.auto_increment = matches!(
+            data_type,
+            DataType::Custom(type_name, _) if type_name.0.last().is_some_and(|t| {
+                ["serial", "serial2", "serial4", "serial8", "smallserial", "bigserial"]
+                    .contains(&t.value.to_lowercase().as_str())
+            })
+        );
+        column
+    }
+
+    /// Map a column's declared type to a SqlType, applying dialect conventions
+    fn column_type(&self, data_type: &DataType) -> SqlType {
+        match data_type {
+            DataType::TinyInt(Some(1))
+                if self.dialect == SqlDialect::MySQL && self.tinyint1_as_boolean =>
+            {
+                SqlType::Boolean
+            }
+            _ => SqlType::from_ast(data_type),
+        }
+    }
+
+    /// Process COMMENT ON TABLE / COMMENT ON COLUMN
+    ///
+    /// A comment whose target does not exist usually means the documentation
+    /// has drifted from the schema, so it is reported as a warning.
+    fn process_comment(
+        &mut self,
+        object_type: &CommentObject,
+        object_name: &ObjectName,
+        comment: Option<&str>,
+        if_exists: bool,
+    ) {
+        let comment = comment.map(str::to_string);
+        match object_type {
+            CommentObject::Table => {
+                let table_name = object_name_to_qualified(object_name);
+                if let Some(table) = self.catalog.get_table_mut(&table_name) {
+                    table.set_comment(comment);
+                } else if !if_exists && !self.catalog.view_exists(&table_name) {
+                    self.diagnostics.push(
+                        Diagnostic::warning(
+                            DiagnosticKind::TableNotFound,
+                            format!(
+                                "COMMENT ON TABLE references table '{}' which was not found in schema",
+                                object_name
+                            ),
+                        )
+                        .with_help("Remove the stale comment or fix the table name"),
+                    );
+                }
+            }
+            CommentObject::Column => {
+                let Some((column, table_parts)) = object_name.0.split_last() else {
+                    return;
+                };
+                let table_object = ObjectName(table_parts.to_vec());
+                let table_name = object_name_to_qualified(&table_object);
+                let Some(table) = self.catalog.get_table_mut(&table_name) else {
+                    if !if_exists {
+                        self.diagnostics.push(
+                            Diagnostic::warning(
+                                DiagnosticKind::TableNotFound,
+                                format!(
+                                    "COMMENT ON COLUMN references table '{}' which was not found in schema",
+                                    table_object
+                                ),
+                            )
+                            .with_help("Remove the stale comment or fix the table name"),
+                        );
+                    }
+                    return;
+                };
+                let column_def = table
+                    .columns
+                    .iter_mut()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(&column.value))
+                    .map(|(_, col)| col);
+                match column_def {
+                    Some(col) => col.set_comment(comment),
+                    None if !if_exists => {
+                        self.diagnostics.push(
+                            Diagnostic::warning(
+                                DiagnosticKind::ColumnNotFound,
+                                format!(
+                                    "COMMENT ON COLUMN references column '{}' which was not found in schema",
+                                    object_name
+                                ),
+                            )
+                            .with_help("Remove the stale comment or fix the column name"),
+                        );
+                    }
+                    None => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Check that a computed column's expression only references columns of its own table
+    fn validate_generated_column(&mut self, table: &TableDef, column: &sqlparser::ast::ColumnDef) {
+        for option in &column.options {
+            let ColumnOption::Generated {
+                generation_expr: Some(expr),
+                ..
+            } = &option.option
+            else {
+                continue;
+            };
+
+            let mut missing = Vec::new();
+            let _ = visit_expressions(expr, |e| {
+                let ident = match e {
+                    Expr::Identifier(ident) => Some(ident),
+                    Expr::CompoundIdentifier(idents) => idents.last(),
+                    _ => None,
+                };
+                if let Some(ident) = ident {
+                    if !table.column_exists(&ident.value) {
+                        missing.push(ident.value.clone());
+                    }
+                }
+                ControlFlow::<()>::Continue(())
+            });
+
+            for name in missing {
+                self.diagnostics.push(
+                    Diagnostic::warning(
+                        DiagnosticKind::ColumnNotFound,
+                        format!(
+                            "Generated column '{}.{}' references column '{}' which does not exist in the table",
+                            table.name, column.name.value, name
+                        ),
+                    )
+                    .with_help("Generated columns can only reference columns of the same table"),
+                );
             }
         }
     }
@@ -458,6 +1549,12 @@ impl SchemaBuilder {
         option: &ColumnOptionDef,
     ) {
         match &option.option {
+            ColumnOption::ForeignKey { foreign_table, .. } => {
+                let origin = self.reference_origin(foreign_table);
+                table
+                    .foreign_keys
+                    .extend(inline_foreign_key_def(&col.name, option, origin));
+            }
             ColumnOption::Null => {
                 col.nullable = true;
             }
@@ -467,11 +1564,19 @@ impl SchemaBuilder {
             ColumnOption::Default(expr) => {
                 col.default = Some(expr_to_default(expr));
             }
-            ColumnOption::Unique { is_primary, .. } => {
-                if *is_primary {
-                    col.is_primary_key = true;
-                    col.nullable = false;
-                }
+            // MySQL inline COMMENT 'text'
+            ColumnOption::Comment(text) => {
+                col.set_comment(Some(text.clone()));
+            }
+            ColumnOption::Unique { is_primary, .. } if *is_primary => {
+                col.is_primary_key = true;
+                col.nullable = false;
+            }
+            ColumnOption::Unique { .. } => {
+                table.unique_constraints.push(UniqueConstraintDef {
+                    name: option.name.as_ref().map(|n| n.value.clone()),
+                    columns: vec![col.name.clone()],
+                });
             }
             ColumnOption::Check(expr) => {
                 let check = CheckConstraintDef {
@@ -480,31 +1585,37 @@ impl SchemaBuilder {
                 };
                 table.check_constraints.push(check);
             }
+            // IDENTITY columns (no generation expression = IDENTITY, not computed)
             ColumnOption::Generated {
                 generated_as,
-                generation_expr,
+                generation_expr: None,
                 ..
             } => {
-                // IDENTITY columns (no generation expression = IDENTITY, not computed)
-                if generation_expr.is_none() {
-                    use sqlparser::ast::GeneratedAs;
-                    let kind = match generated_as {
-                        GeneratedAs::Always => IdentityKind::Always,
-                        GeneratedAs::ByDefault => IdentityKind::ByDefault,
-                        _ => return,
-                    };
-                    col.identity = Some(kind);
-                    col.nullable = false; // IDENTITY columns are implicitly NOT NULL
-                }
+                use sqlparser::ast::GeneratedAs;
+                let kind = match generated_as {
+                    GeneratedAs::Always => IdentityKind::Always,
+                    GeneratedAs::ByDefault => IdentityKind::ByDefault,
+                    _ => return,
+                };
+                col.identity = Some(kind);
+                col.nullable = false; // IDENTITY columns are implicitly NOT NULL
+            }
+            // Computed columns: GENERATED ALWAYS AS (expr) STORED, MySQL AS (expr) [VIRTUAL|STORED]
+            ColumnOption::Generated {
+                generation_expr: Some(expr),
+                generation_expr_mode,
+                ..
+            } => {
+                col.generated = Some(generated_column(expr, generation_expr_mode.as_ref()));
             }
-            ColumnOption::DialectSpecific(tokens) => {
-                // MySQL AUTO_INCREMENT
+            // MySQL AUTO_INCREMENT
+            ColumnOption::DialectSpecific(tokens)
                 if tokens
                     .iter()
-                    .any(|t| matches!(t, Token::Word(w) if w.value == "AUTO_INCREMENT"))
-                {
-                    col.nullable = false; // AUTO_INCREMENT implies NOT NULL
-                }
+                    .any(|t| matches!(t, Token::Word(w) if w.value == "AUTO_INCREMENT")) =>
+            {
+                col.nullable = false; // AUTO_INCREMENT implies NOT NULL
+                col.auto_increment = true;
             }
             _ => {}
         }
@@ -527,24 +1638,23 @@ impl SchemaBuilder {
                 }
                 table.primary_key = Some(pk);
             }
-            TableConstraint::ForeignKey {
+            TableConstraint::ForeignKey { foreign_table, .. } => {
+                let origin = self.reference_origin(foreign_table);
+                table
+                    .foreign_keys
+                    .extend(foreign_key_def(constraint, origin));
+            }
+            TableConstraint::Unique {
                 columns,
-                foreign_table,
-                referred_columns,
                 name,
+                index_name,
                 ..
             } => {
-                let fk = ForeignKeyDef {
-                    name: name.as_ref().map(|n| n.value.clone()),
-                    columns: columns.iter().map(|c| c.value.clone()).collect(),
-                    references_table: object_name_to_qualified(foreign_table),
-                    references_columns: referred_columns.iter().map(|c| c.value.clone()).collect(),
-                };
-                table.foreign_keys.push(fk);
-            }
-            TableConstraint::Unique { columns, name, .. } => {
                 let unique = UniqueConstraintDef {
-                    name: name.as_ref().map(|n| n.value.clone()),
+                    name: name
+                        .as_ref()
+                        .or(index_name.as_ref())
+                        .map(|n| n.value.clone()),
                     columns: columns.iter().map(|c| c.value.clone()).collect(),
                 };
                 table.unique_constraints.push(unique);
@@ -556,13 +1666,481 @@ impl SchemaBuilder {
                 };
                 table.check_constraints.push(check);
             }
-            _ => {}
+            TableConstraint::Index { .. } | TableConstraint::FulltextOrSpatial { .. } => {
+                table.indexes.extend(index_def(constraint));
+            }
+        }
+    }
+
+    /// Fill in the referenced columns of `REFERENCES table` (no column list)
+    /// with the referenced table's primary key
+    fn resolve_foreign_key_columns(&mut self) {
+        let unresolved: Vec<(QualifiedName, usize, QualifiedName)> = self
+            .catalog
+            .schemas
+            .values()
+            .flat_map(|schema| schema.tables.values())
+            .flat_map(|table| {
+                table
+                    .foreign_keys
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, fk)| fk.references_columns.is_empty())
+                    .map(|(idx, fk)| (table.name.clone(), idx, fk.references_table.clone()))
+            })
+            .collect();
+
+        for (table_name, idx, referenced) in unresolved {
+            // Table-level PRIMARY KEY keeps its column order; inline ones only mark columns
+            let Some(pk) = self
+                .catalog
+                .get_table(&referenced)
+                .map(|t| match &t.primary_key {
+                    Some(pk) => pk.columns.clone(),
+                    None => t
+                        .columns
+                        .values()
+                        .filter(|c| c.is_primary_key)
+                        .map(|c| c.name.clone())
+                        .collect(),
+                })
+            else {
+                continue;
+            };
+            if let Some(table) = self.catalog.get_table_mut(&table_name) {
+                table.foreign_keys[idx].references_columns = pk;
+            }
+        }
+    }
+
+    /// Warn about foreign keys whose columns, referenced table or referenced
+    /// columns don't exist in the final schema, at the referenced table name
+    fn validate_foreign_keys(&mut self) {
+        let mut reports = Vec::new();
+        for table in self
+            .catalog
+            .schemas
+            .values()
+            .flat_map(|s| s.tables.values())
+        {
+            for fk in &table.foreign_keys {
+                let at = match &fk.origin {
+                    Some(origin) => (
+                        Some(origin.clone()),
+                        fk.references_table.to_string().chars().count(),
+                    ),
+                    None => (table.origin.clone(), table.name.name.chars().count()),
+                };
+                let what = constraint_description("FOREIGN KEY", &fk.name, table);
+                for diagnostic in key_constraint_columns(&what, table, &fk.columns) {
+                    reports.push((diagnostic, at.clone()));
+                }
+                let Some(referenced) = self.catalog.get_table(&fk.references_table) else {
+                    reports.push((
+                        Diagnostic::warning(
+                            DiagnosticKind::TableNotFound,
+                            format!(
+                                "{} references table '{}' which was not found in schema",
+                                what, fk.references_table
+                            ),
+                        )
+                        .with_help("Define the referenced table, or fix the reference"),
+                        at.clone(),
+                    ));
+                    continue;
+                };
+                for column in &fk.references_columns {
+                    if !referenced.column_exists(column) {
+                        reports.push((
+                            Diagnostic::warning(
+                                DiagnosticKind::ColumnNotFound,
+                                format!(
+                                    "{} references column '{}.{}' which does not exist",
+                                    what, fk.references_table, column
+                                ),
+                            )
+                            .with_help("Reference columns of the referenced table"),
+                            at.clone(),
+                        ));
+                    }
+                }
+            }
+        }
+        for (diagnostic, (origin, length)) in reports {
+            self.report_at(diagnostic, origin, length);
+        }
+    }
+
+    /// Hint at foreign keys whose columns no index leads with (W0017)
+    ///
+    /// PostgreSQL doesn't index the referencing columns, so deleting a
+    /// referenced row scans the referencing table. MySQL creates the index
+    /// itself, and isn't checked. Foreign keys with missing columns are
+    /// reported by `validate_foreign_keys`.
+    fn check_foreign_key_indexes(&mut self) {
+        if self.dialect == SqlDialect::MySQL {
+            return;
+        }
+        let mut reports = Vec::new();
+        for table in self
+            .catalog
+            .schemas
+            .values()
+            .flat_map(|s| s.tables.values())
+        {
+            for fk in &table.foreign_keys {
+                if fk.columns.is_empty()
+                    || !fk.columns.iter().all(|c| table.column_exists(c))
+                    || table.has_index_leading_with(&fk.columns)
+                {
+                    continue;
+                }
+                let what = constraint_description("FOREIGN KEY", &fk.name, table);
+                let columns = fk.columns.join(", ");
+                reports.push((
+                    Diagnostic::hint(
+                        DiagnosticKind::UnindexedForeignKey,
+                        format!("{} ({}) has no index on its columns", what, columns),
+                    )
+                    .with_help(format!(
+                        "PostgreSQL doesn't index referencing columns, so deleting a row of \
+                         '{}' scans '{}': CREATE INDEX ON {} ({})",
+                        fk.references_table, table.name, table.name, columns
+                    )),
+                    (table.origin.clone(), table.name.name.chars().count()),
+                ));
+            }
+        }
+        for (diagnostic, (origin, length)) in reports {
+            self.report_at(diagnostic, origin, length);
+        }
+    }
+
+    /// Report unqualified table references in view queries that depend on
+    /// the `search_path` the view is created with: names defined in several
+    /// schemas (W0020), and names defined only outside the default schema
+    /// (W0021). Not checked under MySQL, which has no search path.
+    fn check_view_references(&mut self) {
+        if self.dialect == SqlDialect::MySQL {
+            return;
+        }
+        let ambiguous = DiagnosticKind::AmbiguousViewReference.is_enabled_by(&self.enabled_rules);
+        let search_path =
+            DiagnosticKind::SearchPathViewReference.is_enabled_by(&self.enabled_rules);
+        let default_schema = self.catalog.default_schema.as_str();
+        let mut reports = Vec::new();
+        for view in self.catalog.schemas.values().flat_map(|s| s.views.values()) {
+            let view_name = self.dialect.display_name(&view.name);
+            for dependency in view.dependencies.iter().filter(|d| d.schema.is_none()) {
+                let schemas = self.catalog.schemas_containing(&dependency.name);
+                let diagnostic = match schemas.as_slice() {
+                    [] => continue,
+                    [schema] if *schema != default_schema && search_path => Diagnostic::hint(
+                        DiagnosticKind::SearchPathViewReference,
+                        format!(
+                            "View '{}' references '{}' without a schema, but it is only defined in schema '{}'",
+                            view_name, dependency.name, schema
+                        ),
+                    )
+                    .with_help(format!(
+                        "The view only works with '{}' on the search_path; qualify the reference as {}.{}",
+                        schema, schema, dependency.name
+                    )),
+                    [_] => continue,
+                    _ if ambiguous => Diagnostic::warning(
+                        DiagnosticKind::AmbiguousViewReference,
+                        format!(
+                            "View '{}' references '{}' without a schema, but it is defined in schemas {}",
+                            view_name,
+                            dependency.name,
+                            schemas
+                                .iter()
+                                .map(|s| format!("'{}'", s))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    )
+                    .with_help(format!(
+                        "Which one the view reads depends on the search_path it is created with; \
+                         qualify the reference (e.g. {}.{})",
+                        schemas
+                            .iter()
+                            .find(|s| **s == default_schema)
+                            .unwrap_or(&schemas[0]),
+                        dependency.name
+                    )),
+                    _ => continue,
+                };
+                reports.push((
+                    diagnostic,
+                    (view.origin.clone(), view.name.name.chars().count()),
+                ));
+            }
+        }
+        for (diagnostic, (origin, length)) in reports {
+            self.report_at(diagnostic, origin, length);
+        }
+    }
+
+    /// Warn about foreign key actions that are bound to fail at runtime:
+    /// SET NULL on a NOT NULL column, or SET DEFAULT on a column without a default
+    fn validate_referential_actions(&mut self) {
+        let mut reports = Vec::new();
+        for schema in self.catalog.schemas.values() {
+            for table in schema.tables.values() {
+                for fk in &table.foreign_keys {
+                    for (event, action) in [("DELETE", fk.on_delete), ("UPDATE", fk.on_update)] {
+                        let problem = |col: &ColumnDef| match action {
+                            ReferentialAction::SetNull if !col.nullable => {
+                                Some("is declared NOT NULL")
+                            }
+                            ReferentialAction::SetDefault if col.default.is_none() => {
+                                Some("has no default")
+                            }
+                            _ => None,
+                        };
+                        for column in &fk.columns {
+                            let Some(col) = table.get_column(column) else {
+                                continue;
+                            };
+                            let Some(reason) = problem(col) else {
+                                continue;
+                            };
+                            reports.push((
+                                Diagnostic::warning(
+                                    DiagnosticKind::InvalidReferentialAction,
+                                    format!(
+                                        "Foreign key on '{}.{}' uses ON {} {}, but the column {}",
+                                        table.name, column, event, action, reason
+                                    ),
+                                )
+                                .with_help(format!(
+                                    "ON {} {} will fail at runtime when rows in '{}' are {}d",
+                                    event,
+                                    action,
+                                    fk.references_table,
+                                    event.to_lowercase()
+                                )),
+                                col.origin.clone(),
+                                col.name.chars().count(),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        for (diagnostic, origin, length) in reports {
+            self.report_at(diagnostic, origin, length);
+        }
+    }
+
+    /// Validate column defaults and constraints against the final schema
+    ///
+    /// Defaults and CHECK expressions are stored as text and re-parsed here,
+    /// so columns dropped or types changed by later statements are taken
+    /// into account. Reported: `::type` casts to unknown types, enum defaults
+    /// that aren't a label of the enum, and CHECK, PRIMARY KEY and UNIQUE
+    /// constraints referencing missing (dropped or renamed) columns.
+    fn validate_defaults_and_constraints(&mut self) {
+        let dialect = self.dialect.parser_dialect();
+        let parse = |text: &str| {
+            Parser::new(dialect.as_ref())
+                .try_with_sql(text)
+                .and_then(|mut parser| parser.parse_expr())
+                .ok()
+        };
+        let mut diagnostics = Vec::new();
+        for table in self
+            .catalog
+            .schemas
+            .values()
+            .flat_map(|s| s.tables.values())
+        {
+            for column in table.columns.values() {
+                let text = match &column.default {
+                    Some(DefaultValue::Literal(text))
+                    | Some(DefaultValue::Expression(text))
+                    | Some(DefaultValue::NextVal(text)) => text,
+                    _ => continue,
+                };
+                let Some(expr) = parse(text) else { continue };
+                let what = format!("Default of column '{}.{}'", table.name, column.name);
+                let at = (column.origin.clone(), column.name.chars().count());
+                for diagnostic in self
+                    .check_cast_types(&what, &expr)
+                    .into_iter()
+                    .chain(self.check_enum_default(&what, &column.data_type, &expr))
+                {
+                    diagnostics.push((diagnostic, at.clone()));
+                }
+            }
+            let at = (table.origin.clone(), table.name.name.chars().count());
+            for check in &table.check_constraints {
+                let Some(expr) = parse(&check.expression) else {
+                    continue;
+                };
+                let what = constraint_description("CHECK constraint", &check.name, table);
+                for diagnostic in self
+                    .check_cast_types(&what, &expr)
+                    .into_iter()
+                    .chain(check_constraint_columns(&what, table, &expr))
+                {
+                    diagnostics.push((diagnostic, at.clone()));
+                }
+            }
+            let keys = table
+                .primary_key
+                .iter()
+                .map(|pk| ("PRIMARY KEY", &pk.name, &pk.columns))
+                .chain(
+                    table
+                        .unique_constraints
+                        .iter()
+                        .map(|u| ("UNIQUE constraint", &u.name, &u.columns)),
+                );
+            for (kind, name, columns) in keys {
+                let what = constraint_description(kind, name, table);
+                for diagnostic in key_constraint_columns(&what, table, columns) {
+                    diagnostics.push((diagnostic, at.clone()));
+                }
+            }
+        }
+        for (diagnostic, (origin, length)) in diagnostics {
+            self.report_at(diagnostic, origin, length);
+        }
+    }
+
+    /// Warn about `::type` casts in `expr` whose target type isn't defined
+    fn check_cast_types(&self, what: &str, expr: &Expr) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let _ = visit_expressions(expr, |e| {
+            if let Expr::Cast {
+                kind: CastKind::DoubleColon,
+                data_type,
+                ..
+            } = e
+            {
+                if let Some(name) = self.unknown_type(&SqlType::from_ast(data_type)) {
+                    diagnostics.push(
+                        Diagnostic::warning(
+                            DiagnosticKind::TypeMismatch,
+                            format!("{} casts to type '{}', which is not defined", what, name),
+                        )
+                        .with_help("Define the type (CREATE TYPE) or fix the cast"),
+                    );
+                }
+            }
+            ControlFlow::<()>::Continue(())
+        });
+        diagnostics
+    }
+
+    /// The name of a user-defined type `ty` refers to, if it isn't defined
+    fn unknown_type<'t>(&self, ty: &'t SqlType) -> Option<&'t str> {
+        match ty {
+            SqlType::Array(inner) => self.unknown_type(inner),
+            SqlType::Custom(name) => {
+                let bare = name.rsplit('.').next().unwrap_or(name).to_lowercase();
+                let known = self.catalog.enum_exists(&bare)
+                    || self.other_types.contains(&bare)
+                    || self.domains.contains_key(&bare)
+                    || is_builtin_type_name(name);
+                (!known).then_some(name.as_str())
+            }
+            _ => None,
+        }
+    }
+
+    /// The type a domain is defined over, through domains over domains;
+    /// other types are their own base type
+    fn base_type<'t>(&'t self, mut ty: &'t SqlType) -> &'t SqlType {
+        // Bounded, in case of a cycle
+        for _ in 0..=self.domains.len() {
+            let SqlType::Custom(name) = ty else { break };
+            let bare = name.rsplit('.').next().unwrap_or(name).to_lowercase();
+            match self.domains.get(&bare) {
+                Some(base) => ty = base,
+                None => break,
+            }
         }
+        ty
+    }
+
+    /// Warn when an enum column's default (`'x'` or `'x'::type`) isn't a label of the enum
+    fn check_enum_default(&self, what: &str, ty: &SqlType, expr: &Expr) -> Option<Diagnostic> {
+        let SqlType::Custom(name) = self.base_type(ty) else {
+            return None;
+        };
+        let enum_def = self
+            .catalog
+            .get_enum(name.rsplit('.').next().unwrap_or(name))?;
+        let literal = match expr {
+            Expr::Cast { expr, .. } => expr.as_ref(),
+            expr => expr,
+        };
+        let Expr::Value(Value::SingleQuotedString(label)) = literal else {
+            return None;
+        };
+        if enum_def.values.contains(label) {
+            return None;
+        }
+        Some(
+            Diagnostic::warning(
+                DiagnosticKind::TypeMismatch,
+                format!(
+                    "{} is '{}', which is not a label of enum '{}'",
+                    what, label, enum_def.name
+                ),
+            )
+            .with_help(format!(
+                "Labels of '{}': {}",
+                enum_def.name,
+                enum_def.values.join(", ")
+            )),
+        )
     }
 
     /// Consume the builder and return the catalog
     pub fn build(self) -> (Catalog, Vec<Diagnostic>) {
-        (self.catalog, self.diagnostics)
+        let (catalog, diagnostics) = self.build_with_sources();
+        let diagnostics = diagnostics.into_iter().map(|(_, d)| d).collect();
+        (catalog, diagnostics)
+    }
+
+    /// Consume the builder and return the catalog, with each diagnostic
+    /// paired with the name of the source it is in (see `parse_named`)
+    ///
+    /// Diagnostics found once all sources are parsed, such as a foreign key
+    /// action its column can't take, are placed at the definition concerned.
+    /// The source is `None` for unnamed sources and definitions in them.
+    pub fn build_with_sources(mut self) -> (Catalog, Vec<(Option<String>, Diagnostic)>) {
+        self.attribute_diagnostics(None);
+        self.resolve_pending_views();
+        self.resolve_foreign_key_columns();
+        if self.check_foreign_keys {
+            self.validate_foreign_keys();
+        }
+        if DiagnosticKind::UnindexedForeignKey.is_enabled_by(&self.enabled_rules) {
+            self.check_foreign_key_indexes();
+        }
+        if DiagnosticKind::AmbiguousViewReference.is_enabled_by(&self.enabled_rules)
+            || DiagnosticKind::SearchPathViewReference.is_enabled_by(&self.enabled_rules)
+        {
+            self.check_view_references();
+        }
+        self.validate_referential_actions();
+        self.validate_defaults_and_constraints();
+        self.attribute_diagnostics(None);
+        let diagnostics = self
+            .diagnostic_sources
+            .into_iter()
+            .zip(self.diagnostics)
+            .map(|(source, mut diagnostic)| {
+                diagnostic.origin = Some(DiagnosticOrigin::Schema);
+                (source, diagnostic)
+            })
+            .collect();
+        (self.catalog, diagnostics)
     }
 
     /// Get a reference to the current catalog
@@ -578,13 +2156,461 @@ impl Default for SchemaBuilder {
     }
 }
 
-/// Convert sqlparser ObjectName to our QualifiedName
-fn object_name_to_qualified(name: &ObjectName) -> QualifiedName {
-    match name.0.as_slice() {
-        [table] => QualifiedName::new(&table.value),
-        [schema, table] => QualifiedName::with_schema(&schema.value, &table.value),
-        [_catalog, schema, table] => QualifiedName::with_schema(&schema.value, &table.value),
-        _ => QualifiedName::new(name.to_string()),
+/// "CHECK constraint 'name' on 'table'", for diagnostics about a constraint
+fn constraint_description(kind: &str, name: &Option<String>, table: &TableDef) -> String {
+    match name {
+        Some(name) => format!("{} '{}' on '{}'", kind, name, table.name),
+        None => format!("{} on '{}'", kind, table.name),
+    }
+}
+
+/// Warn about columns of an `ALTER TABLE ... ADD CONSTRAINT` that `table` lacks
+fn added_constraint_columns(table: &TableDef, constraint: &TableConstraint) -> Vec<Diagnostic> {
+    let name = |name: &Option<Ident>| name.as_ref().map(|n| n.value.clone());
+    match constraint {
+        TableConstraint::PrimaryKey {
+            name: n, columns, ..
+        } => {
+            let what = constraint_description("PRIMARY KEY", &name(n), table);
+            let columns: Vec<String> = columns.iter().map(|c| c.value.clone()).collect();
+            key_constraint_columns(&what, table, &columns)
+        }
+        TableConstraint::Unique {
+            name: n,
+            index_name,
+            columns,
+            ..
+        } => {
+            let what =
+                constraint_description("UNIQUE constraint", &name(n).or(name(index_name)), table);
+            let columns: Vec<String> = columns.iter().map(|c| c.value.clone()).collect();
+            key_constraint_columns(&what, table, &columns)
+        }
+        TableConstraint::Check { name: n, expr, .. } => {
+            let what = constraint_description("CHECK constraint", &name(n), table);
+            check_constraint_columns(&what, table, expr)
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Warn about key columns of a PRIMARY KEY or UNIQUE constraint that its table lacks
+fn key_constraint_columns(what: &str, table: &TableDef, columns: &[String]) -> Vec<Diagnostic> {
+    columns
+        .iter()
+        .filter(|name| !table.column_exists(name))
+        .map(|name| missing_constraint_column(what, name))
+        .collect()
+}
+
+/// Warn about columns a CHECK constraint references that its table lacks
+fn check_constraint_columns(what: &str, table: &TableDef, expr: &Expr) -> Vec<Diagnostic> {
+    let mut missing: Vec<String> = Vec::new();
+    let _ = visit_expressions(expr, |e| {
+        let ident = match e {
+            Expr::Identifier(ident) => Some(ident),
+            Expr::CompoundIdentifier(idents) => idents.last(),
+            _ => None,
+        };
+        if let Some(ident) = ident {
+            if !table.column_exists(&ident.value) && !missing.contains(&ident.value) {
+                missing.push(ident.value.clone());
+            }
+        }
+        ControlFlow::<()>::Continue(())
+    });
+    missing
+        .iter()
+        .map(|name| missing_constraint_column(what, name))
+        .collect()
+}
+
+fn missing_constraint_column(what: &str, name: &str) -> Diagnostic {
+    Diagnostic::warning(
+        DiagnosticKind::ColumnNotFound,
+        format!(
+            "{} references column '{}' which does not exist in the table",
+            what, name
+        ),
+    )
+    .with_help("Drop or update the constraint when removing or renaming a column")
+}
+
+/// Tables and views a view query reads (function calls in FROM included),
+/// excluding its own CTEs
+fn query_relations(query: &Query) -> Vec<QualifiedName> {
+    #[derive(Default)]
+    struct Relations {
+        ctes: Vec<String>,
+        names: Vec<QualifiedName>,
+    }
+
+    impl Visitor for Relations {
+        type Break = ();
+
+        fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<()> {
+            if let Some(with) = &query.with {
+                self.ctes
+                    .extend(with.cte_tables.iter().map(|c| c.alias.name.value.clone()));
+            }
+            ControlFlow::Continue(())
+        }
+
+        fn pre_visit_relation(&mut self, relation: &ObjectName) -> ControlFlow<()> {
+            let name = object_name_to_qualified(relation);
+            if !self.names.contains(&name) {
+                self.names.push(name);
+            }
+            ControlFlow::Continue(())
+        }
+    }
+
+    let mut relations = Relations::default();
+    let _ = query.visit(&mut relations);
+    let Relations { ctes, mut names } = relations;
+    names.retain(|n| n.schema.is_some() || !ctes.contains(&n.name));
+    names
+}
+
+/// Build a ForeignKeyDef from a table-level FOREIGN KEY constraint
+fn foreign_key_def(
+    constraint: &TableConstraint,
+    origin: Option<SchemaOrigin>,
+) -> Option<ForeignKeyDef> {
+    let TableConstraint::ForeignKey {
+        name,
+        columns,
+        foreign_table,
+        referred_columns,
+        on_delete,
+        on_update,
+        ..
+    } = constraint
+    else {
+        return None;
+    };
+    Some(ForeignKeyDef {
+        name: name.as_ref().map(|n| n.value.clone()),
+        columns: columns.iter().map(|c| c.value.clone()).collect(),
+        references_table: object_name_to_qualified(foreign_table),
+        references_columns: referred_columns.iter().map(|c| c.value.clone()).collect(),
+        on_delete: referential_action(on_delete.as_ref()),
+        on_update: referential_action(on_update.as_ref()),
+        origin,
+    })
+}
+
+/// A constraint stored on a table
+#[derive(Debug, Clone, Copy)]
+enum TableConstraintRef {
+    PrimaryKey,
+    ForeignKey(usize),
+    Unique(usize),
+    Check(usize),
+}
+
+/// Find the constraint named `name` on `table`
+///
+/// Unnamed constraints are matched by the name the database generates for
+/// them, as migrations often drop those (`orders_user_id_fkey` in
+/// PostgreSQL, `orders_ibfk_1` in MySQL).
+fn find_constraint(
+    table: &TableDef,
+    name: &str,
+    dialect: SqlDialect,
+) -> Option<TableConstraintRef> {
+    let is = |n: &Option<String>| n.as_ref().is_some_and(|n| n.eq_ignore_ascii_case(name));
+    // An inline PRIMARY KEY is only recorded on its column
+    if table.primary_key.is_some() || table.columns.values().any(|c| c.is_primary_key) {
+        let pk_name = table.primary_key.as_ref().and_then(|pk| pk.name.clone());
+        let generated = match dialect {
+            SqlDialect::PostgreSQL => format!("{}_pkey", table.name.name),
+            SqlDialect::MySQL => "PRIMARY".to_string(),
+        };
+        if is(&pk_name) || (pk_name.is_none() && generated.eq_ignore_ascii_case(name)) {
+            return Some(TableConstraintRef::PrimaryKey);
+        }
+    }
+    if let Some(i) = table.foreign_keys.iter().position(|fk| is(&fk.name)) {
+        return Some(TableConstraintRef::ForeignKey(i));
+    }
+    if let Some(i) = table.unique_constraints.iter().position(|u| is(&u.name)) {
+        return Some(TableConstraintRef::Unique(i));
+    }
+    if let Some(i) = table.check_constraints.iter().position(|c| is(&c.name)) {
+        return Some(TableConstraintRef::Check(i));
+    }
+
+    // Generated names of unnamed constraints
+    let unnamed = |names: Vec<Option<&String>>| -> Vec<usize> {
+        names
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| n.is_none())
+            .map(|(i, _)| i)
+            .collect()
+    };
+    let fks = unnamed(table.foreign_keys.iter().map(|f| f.name.as_ref()).collect());
+    let uniques = unnamed(
+        table
+            .unique_constraints
+            .iter()
+            .map(|u| u.name.as_ref())
+            .collect(),
+    );
+    let checks = unnamed(
+        table
+            .check_constraints
+            .iter()
+            .map(|c| c.name.as_ref())
+            .collect(),
+    );
+    let lower = name.to_lowercase();
+    let table_prefix = format!("{}_", table.name.name.to_lowercase());
+    match dialect {
+        SqlDialect::PostgreSQL => {
+            let columns_name = |columns: &[String]| {
+                format!("{}{}", table_prefix, columns.join("_")).to_lowercase()
+            };
+            if let Some(&i) = fks.iter().find(|&&i| {
+                lower == format!("{}_fkey", columns_name(&table.foreign_keys[i].columns))
+            }) {
+                return Some(TableConstraintRef::ForeignKey(i));
+            }
+            if let Some(&i) = uniques.iter().find(|&&i| {
+                lower == format!("{}_key", columns_name(&table.unique_constraints[i].columns))
+            }) {
+                return Some(TableConstraintRef::Unique(i));
+            }
+            // `<table>_check`, or `<table>_<column>_check` for a check on one column
+            let middle = lower.strip_prefix(&table_prefix)?.strip_suffix("check")?;
+            let column = middle.strip_suffix('_').unwrap_or(middle);
+            checks
+                .iter()
+                .find(|&&i| {
+                    column.is_empty()
+                        || table.check_constraints[i]
+                            .expression
+                            .to_lowercase()
+                            .contains(column)
+                })
+                .map(|&i| TableConstraintRef::Check(i))
+        }
+        SqlDialect::MySQL => {
+            // `<table>_ibfk_<n>` / `<table>_chk_<n>` number unnamed constraints
+            // from 1; an unnamed UNIQUE is named after its first column
+            let rest = lower.strip_prefix(&table_prefix);
+            let nth = |prefix: &str, candidates: &[usize]| {
+                rest?
+                    .strip_prefix(prefix)?
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|n| candidates.get(n.checked_sub(1)?).copied())
+            };
+            if let Some(i) = nth("ibfk_", &fks) {
+                return Some(TableConstraintRef::ForeignKey(i));
+            }
+            if let Some(i) = nth("chk_", &checks) {
+                return Some(TableConstraintRef::Check(i));
+            }
+            uniques
+                .iter()
+                .find(|&&i| {
+                    table.unique_constraints[i]
+                        .columns
+                        .first()
+                        .is_some_and(|c| c.eq_ignore_ascii_case(name))
+                })
+                .map(|&i| TableConstraintRef::Unique(i))
+        }
+    }
+}
+
+fn remove_constraint(table: &mut TableDef, constraint: TableConstraintRef) {
+    match constraint {
+        TableConstraintRef::PrimaryKey => {
+            // The columns stay NOT NULL
+            table.primary_key = None;
+            for col in table.columns.values_mut() {
+                col.is_primary_key = false;
+            }
+        }
+        TableConstraintRef::ForeignKey(i) => {
+            table.foreign_keys.remove(i);
+        }
+        TableConstraintRef::Unique(i) => {
+            table.unique_constraints.remove(i);
+        }
+        TableConstraintRef::Check(i) => {
+            table.check_constraints.remove(i);
+        }
+    }
+}
+
+fn rename_constraint(table: &mut TableDef, constraint: TableConstraintRef, new_name: &str) {
+    let name = match constraint {
+        TableConstraintRef::PrimaryKey => {
+            let columns = table
+                .columns
+                .values()
+                .filter(|c| c.is_primary_key)
+                .map(|c| c.name.clone())
+                .collect();
+            &mut table
+                .primary_key
+                .get_or_insert(PrimaryKeyDef {
+                    name: None,
+                    columns,
+                })
+                .name
+        }
+        TableConstraintRef::ForeignKey(i) => &mut table.foreign_keys[i].name,
+        TableConstraintRef::Unique(i) => &mut table.unique_constraints[i].name,
+        TableConstraintRef::Check(i) => &mut table.check_constraints[i].name,
+    };
+    *name = Some(new_name.to_string());
+}
+
+/// Remove PostgreSQL's `NOT VALID` from an `ALTER TABLE ... ADD CONSTRAINT`
+///
+/// sqlparser doesn't parse it; the constraint exists either way (only its
+/// validation of existing rows is deferred), so it's dropped from the text.
+fn strip_not_valid(dialect: &dyn Dialect, sql: &str) -> Option<String> {
+    let tokens = sqlparser::tokenizer::Tokenizer::new(dialect, sql)
+        .tokenize()
+        .ok()?;
+    let is_word = |token: &Token, word: &str| matches!(token, Token::Word(w) if w.quote_style.is_none() && w.value.eq_ignore_ascii_case(word));
+    let mut significant = tokens.iter().filter(|t| !matches!(t, Token::Whitespace(_)));
+    if !significant.next().is_some_and(|t| is_word(t, "ALTER")) {
+        return None;
+    }
+
+    let mut out = String::with_capacity(sql.len());
+    let mut stripped = false;
+    let mut i = 0;
+    while i < tokens.len() {
+        if is_word(&tokens[i], "NOT") {
+            let next = (i + 1..tokens.len()).find(|&j| !matches!(tokens[j], Token::Whitespace(_)));
+            if let Some(j) = next.filter(|&j| is_word(&tokens[j], "VALID")) {
+                stripped = true;
+                i = j + 1;
+                continue;
+            }
+        }
+        out.push_str(&tokens[i].to_string());
+        i += 1;
+    }
+    stripped.then_some(out)
+}
+
+/// Name and base type of a `CREATE DOMAIN name [AS] type ...` statement
+///
+/// sqlparser doesn't parse CREATE DOMAIN; only the part up to the type is
+/// read, so defaults and constraints of the domain are ignored.
+fn domain_definition(dialect: &dyn Dialect, sql: &str) -> Option<(ObjectName, DataType)> {
+    let tokens = sqlparser::tokenizer::Tokenizer::new(dialect, sql)
+        .tokenize()
+        .ok()?;
+    let is_word = |token: &Token, word: &str| matches!(token, Token::Word(w) if w.quote_style.is_none() && w.value.eq_ignore_ascii_case(word));
+    let mut significant = tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| !matches!(t, Token::Whitespace(_)));
+    if !significant
+        .next()
+        .is_some_and(|(_, t)| is_word(t, "CREATE"))
+    {
+        return None;
+    }
+    let (domain, _) = significant.next().filter(|(_, t)| is_word(t, "DOMAIN"))?;
+
+    let mut parser = Parser::new(dialect).with_tokens(tokens[domain + 1..].to_vec());
+    let name = parser.parse_object_name(false).ok()?;
+    let _ = parser.parse_keyword(Keyword::AS);
+    let base = parser.parse_data_type().ok()?;
+    Some((name, base))
+}
+
+/// Build an IndexDef from a MySQL `INDEX`/`KEY` (or FULLTEXT/SPATIAL) table definition
+///
+/// Like MySQL, an unnamed index is named after its first column.
+fn index_def(constraint: &TableConstraint) -> Option<IndexDef> {
+    let (name, columns) = match constraint {
+        TableConstraint::Index { name, columns, .. } => (name, columns),
+        TableConstraint::FulltextOrSpatial {
+            opt_index_name,
+            columns,
+            ..
+        } => (opt_index_name, columns),
+        _ => return None,
+    };
+    let name = name.as_ref().or(columns.first())?;
+    Some(IndexDef {
+        name: name.value.clone(),
+        columns: columns.iter().map(|c| c.value.clone()).collect(),
+        unique: false,
+    })
+}
+
+/// Build a ForeignKeyDef from an inline `REFERENCES table [(column)]` column option
+///
+/// An omitted column list is left empty here and resolved to the referenced
+/// table's primary key by `build()`, as the table may be defined later.
+fn inline_foreign_key_def(
+    column: &str,
+    option: &ColumnOptionDef,
+    origin: Option<SchemaOrigin>,
+) -> Option<ForeignKeyDef> {
+    let ColumnOption::ForeignKey {
+        foreign_table,
+        referred_columns,
+        on_delete,
+        on_update,
+        ..
+    } = &option.option
+    else {
+        return None;
+    };
+    Some(ForeignKeyDef {
+        name: option.name.as_ref().map(|n| n.value.clone()),
+        columns: vec![column.to_string()],
+        references_table: object_name_to_qualified(foreign_table),
+        references_columns: referred_columns.iter().map(|c| c.value.clone()).collect(),
+        on_delete: referential_action(on_delete.as_ref()),
+        on_update: referential_action(on_update.as_ref()),
+        origin,
+    })
+}
+
+/// Convert a sqlparser referential action (absent means NO ACTION)
+fn referential_action(action: Option<&sqlparser::ast::ReferentialAction>) -> ReferentialAction {
+    use sqlparser::ast::ReferentialAction as Action;
+    match action {
+        None | Some(Action::NoAction) => ReferentialAction::NoAction,
+        Some(Action::Restrict) => ReferentialAction::Restrict,
+        Some(Action::Cascade) => ReferentialAction::Cascade,
+        Some(Action::SetNull) => ReferentialAction::SetNull,
+        Some(Action::SetDefault) => ReferentialAction::SetDefault,
+    }
+}
+
+/// Compact "name type, ..." summary of a table's columns, for comparing definitions
+fn column_signature(table: &TableDef) -> String {
+    table
+        .columns
+        .values()
+        .map(|c| format!("{} {}", c.name, c.data_type.display_name()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Build a GeneratedColumn from a generation expression
+///
+/// Without an explicit STORED/VIRTUAL modifier the column is virtual (MySQL's default).
+fn generated_column(expr: &Expr, mode: Option<&GeneratedExpressionMode>) -> GeneratedColumn {
+    GeneratedColumn {
+        expression: expr.to_string(),
+        stored: matches!(mode, Some(GeneratedExpressionMode::Stored)),
     }
 }
 
@@ -610,8 +2636,12 @@ fn expr_to_default(expr: &sqlparser::ast::Expr) -> DefaultValue {
 }
 
 /// Split SQL text into individual statements by semicolons,
-/// respecting string literals and dollar-quoted strings.
-fn split_sql_statements(sql: &str) -> Vec<&str> {
+/// respecting string literals (including `E'...'` escape strings),
+/// dollar-quoted strings and comments.
+///
+/// The data block following `COPY ... FROM stdin;` (as emitted by pg_dump)
+/// is skipped up to its `\.` terminator, so its rows never become statements.
+pub(crate) fn split_sql_statements(sql: &str) -> Vec<&str> {
     let mut statements = Vec::new();
     let mut start = 0;
     let bytes = sql.as_bytes();
@@ -621,10 +2651,15 @@ fn split_sql_statements(sql: &str) -> Vec<&str> {
     while i < len {
         match bytes[i] {
             b'\'' => {
-                // Skip single-quoted string
+                // Skip single-quoted string; E'...' strings also allow backslash escapes
+                let backslash_escapes = i > 0
+                    && matches!(bytes[i - 1], b'E' | b'e')
+                    && (i < 2 || !is_identifier_byte(bytes[i - 2]));
                 i += 1;
                 while i < len {
-                    if bytes[i] == b'\'' {
+                    if backslash_escapes && bytes[i] == b'\\' {
+                        i += 2;
+                    } else if bytes[i] == b'\'' {
                         i += 1;
                         if i < len && bytes[i] == b'\'' {
                             i += 1; // escaped quote ''
@@ -636,8 +2671,14 @@ fn split_sql_statements(sql: &str) -> Vec<&str> {
                     }
                 }
             }
+            // `$` inside an identifier (e.g. `a$b`) does not start a dollar quote
+            b'$' if i > 0 && is_identifier_byte(bytes[i - 1]) => {
+                i += 1;
+            }
             b'$' => {
-                // Check for dollar-quoted string ($$...$$ or $tag$...$tag$)
+                // Check for dollar-quoted string ($$...$$ or $tag$...$tag$).
+                // Only the opening tag can close it, so differently tagged
+                // quotes nested in a function body are skipped as content.
                 if let Some(tag_end) = find_dollar_tag_end(sql, i) {
                     let tag = &sql[i..=tag_end];
                     i = tag_end + 1;
@@ -673,8 +2714,11 @@ fn split_sql_statements(sql: &str) -> Vec<&str> {
                 if !stmt.trim().is_empty() {
                     statements.push(stmt);
                 }
-                start = i + 1;
                 i += 1;
+                if is_copy_from_stdin(stmt) {
+                    i = skip_copy_data(sql, i);
+                }
+                start = i;
             }
             _ => {
                 i += 1;
@@ -691,9 +2735,13 @@ fn split_sql_statements(sql: &str) -> Vec<&str> {
     statements
 }
 
+pub(super) fn is_identifier_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$'
+}
+
 /// Find the end of a dollar-quote tag starting at position `start`.
 /// Returns the index of the closing `$` if a valid tag is found.
-fn find_dollar_tag_end(sql: &str, start: usize) -> Option<usize> {
+pub(super) fn find_dollar_tag_end(sql: &str, start: usize) -> Option<usize> {
     let bytes = sql.as_bytes();
     let len = bytes.len();
     // Tag is $<identifier>$ or just $$
@@ -701,6 +2749,10 @@ fn find_dollar_tag_end(sql: &str, start: usize) -> Option<usize> {
     if i < len && bytes[i] == b'$' {
         return Some(i); // $$ tag
     }
+    // A tag can't start with a digit ($1 is a parameter)
+    if i < len && bytes[i].is_ascii_digit() {
+        return None;
+    }
     // Look for $identifier$
     while i < len && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
         i += 1;
@@ -712,10 +2764,57 @@ fn find_dollar_tag_end(sql: &str, start: usize) -> Option<usize> {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
+/// Whether a statement is `COPY ... FROM stdin`, which is followed by inline data
+pub(crate) fn is_copy_from_stdin(stmt: &str) -> bool {
+    let mut rest = stmt.trim_start();
+    // Skip leading comments (pg_dump puts a comment header before each COPY)
+    loop {
+        if rest.starts_with("--") {
+            rest = rest.find('\n').map_or("", |pos| &rest[pos..]).trim_start();
+        } else if rest.starts_with("/*") {
+            rest = rest
+                .find("*/")
+                .map_or("", |pos| &rest[pos + 2..])
+                .trim_start();
+        } else {
+            break;
+        }
+    }
+
+    let words: Vec<String> = rest
+        .split_whitespace()
+        .map(|w| w.to_ascii_lowercase())
+        .collect();
+    words.first().map(String::as_str) == Some("copy")
+        && words.windows(2).any(|w| w[0] == "from" && w[1] == "stdin")
+}
+
+/// Skip the data block of `COPY ... FROM stdin` starting at `pos`.
+/// Returns the position after the `\.` terminator line (or the end of input).
+fn skip_copy_data(sql: &str, pos: usize) -> usize {
+    // Data starts on the line after the COPY statement
+    let Some(newline) = sql[pos..].find('\n') else {
+        return sql.len();
+    };
+    let mut line_start = pos + newline + 1;
+    while line_start < sql.len() {
+        let line_end = sql[line_start..]
+            .find('\n')
+            .map_or(sql.len(), |p| line_start + p);
+        let line = sql[line_start..line_end].trim_end_matches('\r');
+        let next = (line_end + 1).min(sql.len());
+        if line == "\\." {
+            return next;
+        }
+        line_start = next;
+    }
+    sql.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
     #[test]
     fn test_parse_simple_table() {
         let sql = r#"
@@ -762,6 +2861,35 @@ mod tests {
 
         let table = catalog.get_table(&QualifiedName::new("orders")).unwrap();
         assert_eq!(table.columns.len(), 3);
+        assert_eq!(table.foreign_keys.len(), 1);
+        let fk = &table.foreign_keys[0];
+        assert_eq!(fk.columns, vec!["user_id"]);
+        assert_eq!(fk.references_table, QualifiedName::new("users"));
+        assert_eq!(fk.references_columns, vec!["id"]);
+    }
+
+    #[test]
+    fn test_inline_foreign_key_defaults_to_primary_key() {
+        // The referenced table is defined after the reference
+        let sql = r#"
+            CREATE TABLE orders (
+                id SERIAL PRIMARY KEY,
+                user_id INTEGER REFERENCES users ON DELETE CASCADE
+            );
+            ALTER TABLE orders ADD COLUMN editor_id INTEGER REFERENCES users;
+            CREATE TABLE users (uid INTEGER PRIMARY KEY, name TEXT);
+        "#;
+
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        let (catalog, _) = builder.build();
+
+        let table = catalog.get_table(&QualifiedName::new("orders")).unwrap();
+        assert_eq!(table.foreign_keys.len(), 2);
+        assert_eq!(table.foreign_keys[0].references_columns, vec!["uid"]);
+        assert_eq!(table.foreign_keys[0].on_delete, ReferentialAction::Cascade);
+        assert_eq!(table.foreign_keys[1].columns, vec!["editor_id"]);
+        assert_eq!(table.foreign_keys[1].references_columns, vec!["uid"]);
     }
 
     #[test]
@@ -779,6 +2907,71 @@ mod tests {
         assert!(stmts[0].contains("hello; world"));
     }
 
+    #[test]
+    fn test_split_escape_strings() {
+        let sql = r"INSERT INTO t VALUES (E'it\'s; here', E'a''b;\n'); SELECT 1;";
+        let stmts = split_sql_statements(sql);
+        assert_eq!(stmts.len(), 2, "{:?}", stmts);
+
+        // A trailing `e` in an identifier does not start an escape string
+        let sql = r"SELECT name'x\'; SELECT 2;";
+        let stmts = split_sql_statements(sql);
+        assert_eq!(stmts.len(), 2, "{:?}", stmts);
+    }
+
+    #[test]
+    fn test_split_nested_dollar_quotes() {
+        let sql = r#"
+            CREATE FUNCTION f() RETURNS void AS $outer$
+            BEGIN
+                EXECUTE $$ SELECT 1; $$;
+                PERFORM $inner$ ; $inner$;
+            END;
+            $outer$ LANGUAGE plpgsql;
+            SELECT price$usd, $1 FROM t;
+            CREATE TABLE t (id INT);
+        "#;
+        let stmts = split_sql_statements(sql);
+        assert_eq!(stmts.len(), 3, "{:?}", stmts);
+        assert!(stmts[0].contains("$outer$ LANGUAGE"));
+    }
+
+    #[test]
+    fn test_split_pg_dump_excerpt() {
+        let sql = "--\n\
+            -- Name: f(); Type: FUNCTION\n\
+            --\n\
+            CREATE FUNCTION public.f() RETURNS text AS $body$\n\
+            BEGIN RETURN $$a;b$$; END;\n\
+            $body$ LANGUAGE plpgsql;\n\
+            \n\
+            CREATE TABLE public.notes (id integer NOT NULL, body text);\n\
+            \n\
+            --\n\
+            -- Data for Name: notes; Type: TABLE DATA\n\
+            --\n\
+            COPY public.notes (id, body) FROM stdin;\n\
+            1\tfirst; with semicolon\n\
+            2\tE'quote; \\\\ backslash\n\
+            3\t$$ unterminated dollar\n\
+            \\.\n\
+            \n\
+            COMMENT ON TABLE public.notes IS E'Notes; it\\'s here';\n\
+            ALTER TABLE ONLY public.notes ADD CONSTRAINT notes_pkey PRIMARY KEY (id);\n";
+        let stmts = split_sql_statements(sql);
+        assert_eq!(stmts.len(), 5, "{:?}", stmts);
+        assert!(stmts[2].trim_end().ends_with("FROM stdin"));
+        assert!(stmts[3].trim_start().starts_with("COMMENT ON TABLE"));
+
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        let (catalog, _) = builder.build();
+        let table = catalog
+            .get_table(&QualifiedName::with_schema("public", "notes"))
+            .unwrap();
+        assert_eq!(table.columns.len(), 2);
+    }
+
     #[test]
     fn test_parse_with_unsupported_statements() {
         let sql = r#"
@@ -806,6 +2999,31 @@ mod tests {
         assert!(catalog.table_exists(&QualifiedName::new("category")));
     }
 
+    #[test]
+    fn test_report_skipped_statements() {
+        let sql = "CREATE TABLE actor (id INTEGER);\n\n  CREATE OR REPLACE PROCEDURAL LANGUAGE plpgsql;\n";
+
+        // Skipped silently by default
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        assert!(builder.build().1.is_empty());
+
+        let mut builder = SchemaBuilder::new().with_skipped_statements(true);
+        builder.parse_named("schema.sql", sql).unwrap();
+        let (catalog, diagnostics) = builder.build_with_sources();
+        assert!(catalog.table_exists(&QualifiedName::new("actor")));
+        assert_eq!(diagnostics.len(), 1);
+        let (source, diagnostic) = &diagnostics[0];
+        assert_eq!(source.as_deref(), Some("schema.sql"));
+        assert_eq!(diagnostic.kind, DiagnosticKind::ParseError);
+        assert_eq!(diagnostic.severity, crate::error::Severity::Warning);
+        assert_eq!(diagnostic.origin, Some(DiagnosticOrigin::Schema));
+        assert!(diagnostic.message.ends_with("; the statement was skipped"));
+        assert!(!diagnostic.message.contains(" at Line: "));
+        let span = diagnostic.span.unwrap();
+        assert_eq!((span.line, span.column), (3, 3));
+    }
+
     #[test]
     fn test_parse_sakila_like_schema() {
         // Simulates Sakila-style schema with mixed supported/unsupported statements
@@ -901,4 +3119,1067 @@ mod tests {
         assert!(catalog.table_exists(&QualifiedName::new("users")));
         assert!(catalog.table_exists(&QualifiedName::new("posts")));
     }
+
+    #[test]
+    fn test_comment_on_table_and_column() {
+        let sql = r#"
+            CREATE TABLE users (id SERIAL PRIMARY KEY, email TEXT NOT NULL);
+            COMMENT ON TABLE users IS 'registered accounts';
+            COMMENT ON COLUMN users.email IS 'primary contact address';
+            COMMENT ON COLUMN users.emial IS 'typo';
+            COMMENT ON TABLE accounts IS 'gone';
+        "#;
+
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        let (catalog, diagnostics) = builder.build();
+
+        let table = catalog.get_table(&QualifiedName::new("users")).unwrap();
+        assert_eq!(table.comment.as_deref(), Some("registered accounts"));
+        assert_eq!(
+            table.get_column("email").unwrap().comment.as_deref(),
+            Some("primary contact address")
+        );
+
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics[0].message.contains("users.emial"));
+        assert_eq!(diagnostics[1].kind, DiagnosticKind::TableNotFound);
+    }
+
+    #[test]
+    fn test_dialect_shapes_catalog() {
+        let sql = "CREATE TABLE flags (id INT PRIMARY KEY, active TINYINT(1) NOT NULL);";
+        let build = |dialect| {
+            let mut builder = SchemaBuilder::with_dialect(dialect);
+            builder.parse(sql).unwrap();
+            builder.build().0
+        };
+
+        let pg = build(SqlDialect::PostgreSQL);
+        assert_eq!(pg.default_schema, "public");
+        let active = pg.get_table("flags").unwrap().get_column("active").unwrap();
+        assert_eq!(active.data_type, SqlType::TinyInt);
+
+        let mysql = build(SqlDialect::MySQL);
+        assert_eq!(mysql.default_schema, "");
+        let active = mysql.get_table("flags").unwrap().get_column("active");
+        assert_eq!(active.unwrap().data_type, SqlType::Boolean);
+
+        // Backquoted names are MySQL syntax
+        let sql = "CREATE TABLE `order` (id INT UNSIGNED PRIMARY KEY);";
+        let mut builder = SchemaBuilder::with_dialect(SqlDialect::MySQL);
+        builder.parse(sql).unwrap();
+        let (catalog, _) = builder.build();
+        let id = catalog
+            .get_table("order")
+            .unwrap()
+            .get_column("id")
+            .unwrap();
+        assert_eq!(id.data_type, SqlType::Unsigned(Box::new(SqlType::Integer)));
+
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        assert_eq!(builder.fallback_parses(), 1);
+        assert!(!builder.build().0.table_exists("order"));
+    }
+
+    #[test]
+    fn test_mysql_table_options_parse_as_a_whole() {
+        // As written by mysqldump
+        let sql = "
+            CREATE TABLE users (
+                id BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+                email VARCHAR(255) NOT NULL,
+                PRIMARY KEY (id)
+            ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+            CREATE TABLE posts (
+                id INT NOT NULL,
+                user_id BIGINT UNSIGNED NOT NULL
+            ) ENGINE=InnoDB AUTO_INCREMENT=42 DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_0900_ai_ci;
+        ";
+        let mut builder = SchemaBuilder::with_dialect(SqlDialect::MySQL);
+        builder.parse(sql).unwrap();
+        assert_eq!(builder.fallback_parses(), 0);
+        let (catalog, diagnostics) = builder.build();
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        assert!(catalog.table_exists("users"));
+        assert!(catalog.table_exists("posts"));
+    }
+
+    #[test]
+    fn test_mysql_inline_column_comment() {
+        let sql = "CREATE TABLE users (id INT PRIMARY KEY, email VARCHAR(255) COMMENT 'login email') COMMENT 'accounts';";
+
+        let mut builder = SchemaBuilder::with_dialect(SqlDialect::MySQL);
+        builder.parse(sql).unwrap();
+        let (catalog, _) = builder.build();
+
+        let table = catalog.get_table(&QualifiedName::new("users")).unwrap();
+        assert_eq!(table.comment.as_deref(), Some("accounts"));
+        assert_eq!(
+            table.get_column("email").unwrap().comment.as_deref(),
+            Some("login email")
+        );
+    }
+
+    #[test]
+    fn test_generated_columns() {
+        let sql = r#"
+            CREATE TABLE people (
+                id SERIAL PRIMARY KEY,
+                first_name TEXT,
+                last_name TEXT,
+                full_name TEXT GENERATED ALWAYS AS (first_name || ' ' || last_name) STORED,
+                bad_name TEXT GENERATED ALWAYS AS (first_name || middle_name) STORED,
+                seq INTEGER GENERATED ALWAYS AS IDENTITY
+            );
+        "#;
+
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        let (catalog, diagnostics) = builder.build();
+
+        let table = catalog.get_table(&QualifiedName::new("people")).unwrap();
+        let generated = table.get_column("full_name").unwrap().generated.as_ref();
+        assert!(generated.is_some_and(|g| g.stored && g.expression.contains("last_name")));
+
+        // Identity columns are not computed columns
+        let seq = table.get_column("seq").unwrap();
+        assert!(seq.generated.is_none());
+        assert!(seq.identity.is_some());
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+        assert!(diagnostics[0].message.contains("middle_name"));
+    }
+
+    #[test]
+    fn test_default_casts_and_enum_labels() {
+        let sql = r#"
+            CREATE TYPE status AS ENUM ('pending', 'shipped');
+            CREATE TYPE money_pair AS (amount NUMERIC, currency TEXT);
+            CREATE SEQUENCE orders_id_seq;
+            CREATE TABLE orders (
+                id INTEGER DEFAULT nextval('orders_id_seq'::regclass),
+                status status DEFAULT 'pending'::status,
+                fallback status DEFAULT 'lost',
+                tags TEXT[] DEFAULT '{}'::text[],
+                price money_pair DEFAULT '(0,EUR)'::money_pair,
+                state TEXT DEFAULT 'new'::order_state
+            );
+            CREATE DOMAIN posint AS integer CHECK (VALUE > 0);
+            CREATE DOMAIN order_status status;
+            CREATE TABLE items (
+                quantity posint DEFAULT 0::posint,
+                owner name DEFAULT current_user::regrole::name,
+                status order_status DEFAULT 'gone'
+            );
+        "#;
+
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        let (_, diagnostics) = builder.build();
+
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages.len(), 3, "{:?}", messages);
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.kind == DiagnosticKind::TypeMismatch
+                && d.severity == crate::error::Severity::Warning));
+        assert_eq!(
+            messages[0],
+            "Default of column 'orders.fallback' is 'lost', which is not a label of enum 'status'"
+        );
+        assert_eq!(
+            messages[1],
+            "Default of column 'orders.state' casts to type 'order_state', which is not defined"
+        );
+        // A domain over an enum takes the enum's labels
+        assert_eq!(
+            messages[2],
+            "Default of column 'items.status' is 'gone', which is not a label of enum 'status'"
+        );
+    }
+
+    #[test]
+    fn test_check_constraint_columns() {
+        let sql = r#"
+            CREATE TABLE orders (
+                id INTEGER PRIMARY KEY,
+                quantity INTEGER CHECK (quantity > 0),
+                discount INTEGER,
+                CONSTRAINT chk_discount CHECK (discount BETWEEN 0 AND 100 AND orders.quantity > 0)
+            );
+            ALTER TABLE orders DROP COLUMN discount;
+            ALTER TABLE orders ADD CONSTRAINT chk_typo CHECK (quantiy < 1000);
+        "#;
+
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        let (catalog, diagnostics) = builder.build();
+
+        // The added constraint is reported at once, the orphaned one at the end
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "CHECK constraint 'chk_typo' on 'orders' references column 'quantiy' which does not exist in the table",
+                "CHECK constraint 'chk_discount' on 'orders' references column 'discount' which does not exist in the table",
+            ]
+        );
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.kind == DiagnosticKind::ColumnNotFound));
+        // Like the database, the rejected constraint isn't added
+        let orders = catalog.get_table(&QualifiedName::new("orders")).unwrap();
+        assert_eq!(orders.check_constraints.len(), 2);
+    }
+
+    #[test]
+    fn test_key_constraint_columns() {
+        let sql = r#"
+            CREATE TABLE memberships (
+                team_id INTEGER,
+                user_id INTEGER,
+                email TEXT,
+                PRIMARY KEY (team_id, user_id),
+                CONSTRAINT memberships_email_key UNIQUE (email)
+            );
+            ALTER TABLE memberships RENAME COLUMN user_id TO member_id;
+            ALTER TABLE memberships DROP COLUMN email;
+            ALTER TABLE memberships ADD CONSTRAINT uq_member UNIQUE (team_id, memberid);
+        "#;
+
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        let (catalog, diagnostics) = builder.build();
+
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "UNIQUE constraint 'uq_member' on 'memberships' references column 'memberid' which does not exist in the table",
+                "UNIQUE constraint 'memberships_email_key' on 'memberships' references column 'email' which does not exist in the table",
+            ]
+        );
+        let memberships = catalog
+            .get_table(&QualifiedName::new("memberships"))
+            .unwrap();
+        assert_eq!(memberships.unique_constraints.len(), 1);
+        // The renamed column stays in the primary key
+        assert_eq!(
+            memberships.primary_key.as_ref().unwrap().columns,
+            ["team_id", "member_id"]
+        );
+    }
+
+    #[test]
+    fn test_drop_column_drops_foreign_keys() {
+        let sql = r#"
+            CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT UNIQUE);
+            CREATE TABLE orders (
+                id INTEGER PRIMARY KEY,
+                user_id INTEGER REFERENCES users,
+                user_email TEXT,
+                CONSTRAINT fk_email FOREIGN KEY (user_email) REFERENCES users (email)
+            );
+            CREATE TABLE invoices (order_id INTEGER REFERENCES orders (id));
+            ALTER TABLE users DROP COLUMN id;
+            ALTER TABLE orders DROP COLUMN user_email;
+            ALTER TABLE orders DROP COLUMN id CASCADE;
+        "#;
+
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        let (catalog, diagnostics) = builder.build();
+
+        // Only a referenced column dropped without CASCADE is reported
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        assert_eq!(
+            diagnostics[0].message,
+            "Column 'users.id' is dropped but FOREIGN KEY on 'orders' references it"
+        );
+        let span = diagnostics[0].span.unwrap();
+        assert_eq!((span.line, span.column), (10, 43));
+
+        // No foreign key is left dangling
+        for table in ["orders", "invoices"] {
+            let table = catalog.get_table(&QualifiedName::new(table)).unwrap();
+            assert!(table.foreign_keys.is_empty(), "{:?}", table.foreign_keys);
+        }
+    }
+
+    #[test]
+    fn test_rename_column_renames_key_columns() {
+        let sql = r#"
+            CREATE TABLE categories (
+                id INTEGER PRIMARY KEY,
+                parent_id INTEGER,
+                slug TEXT,
+                UNIQUE (parent_id, slug),
+                FOREIGN KEY (parent_id) REFERENCES categories (id)
+            );
+            CREATE INDEX categories_slug_idx ON categories (slug);
+            ALTER TABLE categories RENAME COLUMN id TO category_id;
+            ALTER TABLE categories RENAME COLUMN slug TO handle;
+        "#;
+
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        let (catalog, diagnostics) = builder.build();
+
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        let categories = catalog
+            .get_table(&QualifiedName::new("categories"))
+            .unwrap();
+        assert_eq!(
+            categories.unique_constraints[0].columns,
+            ["parent_id", "handle"]
+        );
+        assert_eq!(categories.indexes[0].columns, ["handle"]);
+        // A self-reference follows the referenced column too
+        assert_eq!(
+            categories.foreign_keys[0].references_columns,
+            ["category_id"]
+        );
+    }
+
+    #[test]
+    fn test_foreign_key_targets() {
+        let sql = r#"
+            CREATE TABLE orders (
+                id INTEGER PRIMARY KEY,
+                user_id INTEGER REFERENCES users (id),
+                coupon_id INTEGER REFERENCES coupons (code),
+                CONSTRAINT fk_owner FOREIGN KEY (owner_id) REFERENCES accounts (id)
+            );
+            CREATE TABLE users (id INTEGER PRIMARY KEY);
+            CREATE TABLE coupons (id INTEGER PRIMARY KEY);
+        "#;
+
+        // Off by default: a dump may reference tables defined elsewhere
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        let (_, diagnostics) = builder.build();
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+        let mut builder = SchemaBuilder::new().with_foreign_key_checks(true);
+        builder.parse_named("schema.sql", sql).unwrap();
+        let (_, diagnostics) = builder.build();
+
+        // Tables defined later are found
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "FOREIGN KEY on 'orders' references column 'coupons.code' which does not exist",
+                "FOREIGN KEY 'fk_owner' on 'orders' references column 'owner_id' which does not exist in the table",
+                "FOREIGN KEY 'fk_owner' on 'orders' references table 'accounts' which was not found in schema",
+            ]
+        );
+        // Each points at the table its foreign key references
+        let spans: Vec<_> = diagnostics
+            .iter()
+            .map(|d| d.span.map(|s| (s.line, s.column, s.length)))
+            .collect();
+        assert_eq!(
+            spans,
+            [Some((5, 46, 7)), Some((6, 71, 8)), Some((6, 71, 8))]
+        );
+    }
+
+    #[test]
+    fn test_mysql_generated_columns() {
+        let sql = r#"
+            CREATE TABLE items (
+                price DECIMAL(10, 2),
+                qty INT,
+                total DECIMAL(10, 2) AS (price * qty) STORED,
+                label VARCHAR(50) GENERATED ALWAYS AS (CONCAT('#', qty)) VIRTUAL
+            );
+        "#;
+
+        let mut builder = SchemaBuilder::with_dialect(SqlDialect::MySQL);
+        builder.parse(sql).unwrap();
+        let (catalog, diagnostics) = builder.build();
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+        let table = catalog.get_table(&QualifiedName::new("items")).unwrap();
+        assert!(
+            table
+                .get_column("total")
+                .unwrap()
+                .generated
+                .as_ref()
+                .unwrap()
+                .stored
+        );
+        assert!(
+            !table
+                .get_column("label")
+                .unwrap()
+                .generated
+                .as_ref()
+                .unwrap()
+                .stored
+        );
+    }
+
+    #[test]
+    fn test_duplicate_definitions() {
+        let mut builder = SchemaBuilder::new();
+        builder
+            .parse_named("a.sql", "CREATE TABLE users (id INT, email TEXT);")
+            .unwrap();
+        builder
+            .parse_named(
+                "b.sql",
+                r#"
+                CREATE TABLE public.users (id INT);
+                CREATE VIEW active AS SELECT id FROM users;
+                CREATE OR REPLACE VIEW active AS SELECT id FROM users;
+                CREATE TABLE IF NOT EXISTS users (id INT);
+                CREATE VIEW active AS SELECT 1 AS id;
+                CREATE TYPE mood AS ENUM ('happy');
+                CREATE TYPE mood AS ENUM ('sad');
+            "#,
+            )
+            .unwrap();
+        let (_, diagnostics) = builder.build();
+
+        assert_eq!(diagnostics.len(), 3, "{:?}", diagnostics);
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.kind == DiagnosticKind::DuplicateDefinition
+                && d.severity == crate::error::Severity::Warning));
+        assert!(diagnostics[0].message.contains("in a.sql"));
+        assert!(diagnostics[0].message.contains("then in b.sql"));
+        // Only views have CREATE OR REPLACE
+        let help = |i: usize| diagnostics[i].help.as_deref().unwrap();
+        assert!(
+            help(0).contains("CREATE TABLE IF NOT EXISTS"),
+            "{}",
+            help(0)
+        );
+        assert!(!help(0).contains("OR REPLACE"), "{}", help(0));
+        assert!(diagnostics[1].message.starts_with("View 'active'"));
+        assert!(help(1).contains("CREATE OR REPLACE VIEW"), "{}", help(1));
+        assert!(diagnostics[2].message.starts_with("Enum type 'mood'"));
+        assert!(help(2).contains("DROP TYPE"), "{}", help(2));
+    }
+
+    #[test]
+    fn test_definition_origins() {
+        let origin = |file: &str, line, column| {
+            Some(SchemaOrigin {
+                file: file.to_string(),
+                line,
+                column,
+            })
+        };
+        let mut builder = SchemaBuilder::new();
+        builder
+            .parse_named(
+                "schema.sql",
+                "CREATE TABLE users (\n  id INT,\n  email TEXT\n);\nALTER TABLE users ADD COLUMN name TEXT;",
+            )
+            .unwrap();
+        // An unparseable statement makes the builder parse statement by statement
+        builder
+            .parse_named(
+                "more.sql",
+                "CREATE FUNCTION f() RETURNS trigger AS $$ x $$ LANGUAGE sql STRICT STRICT;\n\n  CREATE TABLE orders (id INT);",
+            )
+            .unwrap();
+        builder.parse("CREATE TABLE audit (id INT);").unwrap();
+        let (catalog, _) = builder.build();
+
+        let users = catalog.get_table(&QualifiedName::new("users")).unwrap();
+        assert_eq!(users.origin, origin("schema.sql", 1, 14));
+        assert_eq!(users.columns["email"].origin, origin("schema.sql", 3, 3));
+        assert_eq!(users.columns["name"].origin, origin("schema.sql", 5, 30));
+
+        let orders = catalog.get_table(&QualifiedName::new("orders")).unwrap();
+        assert_eq!(orders.origin, origin("more.sql", 3, 16));
+
+        // Unnamed sources record no origin
+        let audit = catalog.get_table(&QualifiedName::new("audit")).unwrap();
+        assert_eq!(audit.origin, None);
+    }
+
+    #[test]
+    fn test_diagnostic_sources() {
+        let mut builder = SchemaBuilder::new();
+        builder
+            .parse_named(
+                "schema.sql",
+                "CREATE TABLE users (id INT);\n\nALTER TABLE accounts ADD COLUMN name TEXT;",
+            )
+            .unwrap();
+        builder
+            .parse_named(
+                "orders.sql",
+                "CREATE TABLE orders (\n  id INT,\n  user_id INT NOT NULL REFERENCES users ON DELETE SET NULL\n);",
+            )
+            .unwrap();
+        let (_, diagnostics) = builder.build_with_sources();
+
+        let located: Vec<(Option<&str>, usize, usize)> = diagnostics
+            .iter()
+            .map(|(source, d)| {
+                let span = d.span.unwrap();
+                (source.as_deref(), span.line, span.column)
+            })
+            .collect();
+        // The ALTER TABLE points at the table name; the foreign key action,
+        // found after parsing, at its column
+        assert_eq!(
+            located,
+            vec![(Some("schema.sql"), 3, 13), (Some("orders.sql"), 3, 3)]
+        );
+    }
+
+    #[test]
+    fn test_strict_duplicate_definitions() {
+        let mut builder = SchemaBuilder::new().with_strict_duplicates(true);
+        let result = builder.parse("CREATE TABLE users (id INT); CREATE VIEW users AS SELECT 1;");
+        let diagnostics = result.unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, crate::error::Severity::Error);
+        assert!(diagnostics[0]
+            .message
+            .contains("first as a table by `CREATE TABLE users"));
+    }
+
+    #[test]
+    fn test_idempotent_migration_applied_twice() {
+        let migration = r#"
+            CREATE TABLE IF NOT EXISTS users (id SERIAL PRIMARY KEY, email TEXT NOT NULL);
+            ALTER TABLE users ADD COLUMN IF NOT EXISTS last_login TIMESTAMP;
+            ALTER TABLE IF EXISTS legacy_users ADD COLUMN note TEXT;
+            DROP TABLE IF EXISTS tmp_import;
+            CREATE TABLE tmp_import (id INT, payload TEXT);
+            DROP VIEW IF EXISTS active_users;
+            CREATE VIEW active_users AS SELECT id, email FROM users;
+            CREATE OR REPLACE VIEW recent_users AS SELECT id FROM users;
+        "#;
+
+        let mut once = SchemaBuilder::new();
+        once.parse(migration).unwrap();
+        let (once_catalog, once_diags) = once.build();
+
+        let mut twice = SchemaBuilder::new();
+        twice.parse(migration).unwrap();
+        twice.parse(migration).unwrap();
+        let (twice_catalog, twice_diags) = twice.build();
+
+        assert!(once_diags.is_empty(), "{:?}", once_diags);
+        assert!(twice_diags.is_empty(), "{:?}", twice_diags);
+        // Dropped and re-created objects move to the end, so compare ignoring order
+        let snapshot = |catalog: &Catalog| {
+            let schema = &catalog.schemas["public"];
+            let mut objects: Vec<String> = schema
+                .tables
+                .values()
+                .map(|t| format!("{:?}", t))
+                .chain(schema.views.values().map(|v| format!("{:?}", v)))
+                .collect();
+            objects.sort();
+            objects
+        };
+        assert_eq!(snapshot(&once_catalog), snapshot(&twice_catalog));
+
+        let users = twice_catalog
+            .get_table(&QualifiedName::new("users"))
+            .unwrap();
+        assert_eq!(users.column_names(), vec!["id", "email", "last_login"]);
+    }
+
+    #[test]
+    fn test_column_ordinals_follow_alter_table() {
+        let sql = r#"
+            CREATE TABLE users (id INT, login TEXT, email TEXT, age INT);
+            ALTER TABLE users RENAME COLUMN login TO username;
+            ALTER TABLE users DROP COLUMN email;
+            ALTER TABLE users ADD COLUMN created_at TIMESTAMP;
+        "#;
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        let (catalog, _) = builder.build();
+        let users = catalog.get_table(&QualifiedName::new("users")).unwrap();
+        // A renamed column keeps its position
+        assert_eq!(
+            users.column_names(),
+            vec!["id", "username", "age", "created_at"]
+        );
+        let ordinals: Vec<usize> = users.ordered_columns().iter().map(|c| c.ordinal).collect();
+        assert_eq!(ordinals, vec![1, 2, 3, 4]);
+
+        let sql = r#"
+            CREATE TABLE users (id INT, email TEXT);
+            ALTER TABLE users ADD COLUMN tenant_id INT FIRST;
+            ALTER TABLE users ADD COLUMN name TEXT AFTER id;
+        "#;
+        let mut builder = SchemaBuilder::with_dialect(SqlDialect::MySQL);
+        builder.parse(sql).unwrap();
+        let (catalog, _) = builder.build();
+        let users = catalog.get_table(&QualifiedName::new("users")).unwrap();
+        assert_eq!(
+            users.column_names(),
+            vec!["tenant_id", "id", "name", "email"]
+        );
+        assert_eq!(users.get_column("email").unwrap().ordinal, 4);
+    }
+
+    #[test]
+    fn test_if_not_exists_keeps_first_definition() {
+        let sql = r#"
+            CREATE TABLE users (id INT, email TEXT);
+            CREATE TABLE IF NOT EXISTS users (id INT, name TEXT);
+            DROP TABLE missing;
+        "#;
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        let (catalog, diagnostics) = builder.build();
+
+        let users = catalog.get_table(&QualifiedName::new("users")).unwrap();
+        assert_eq!(users.columns.len(), 2);
+
+        assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+        assert_eq!(diagnostics[0].severity, crate::error::Severity::Info);
+        assert!(diagnostics[0].message.contains("differs"));
+        assert_eq!(diagnostics[1].severity, crate::error::Severity::Warning);
+        assert!(diagnostics[1].message.contains("DROP TABLE"));
+        let span = diagnostics[1].span.unwrap();
+        assert_eq!((span.line, span.column), (4, 24));
+    }
+
+    #[test]
+    fn test_view_wildcard_resolved_after_later_table() {
+        let mut builder = SchemaBuilder::new();
+        builder
+            .parse("CREATE VIEW active_users AS SELECT * FROM users;")
+            .unwrap();
+        builder
+            .parse("CREATE TABLE users (id INT, name TEXT);")
+            .unwrap();
+        let (catalog, diagnostics) = builder.build();
+
+        let view = catalog
+            .get_view(&QualifiedName::new("active_users"))
+            .unwrap();
+        assert_eq!(view.columns, vec!["id", "name"]);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_view_on_view_resolved_out_of_order() {
+        let sql = r#"
+            CREATE VIEW outer_v AS SELECT v.* FROM inner_v v;
+            CREATE VIEW inner_v AS SELECT *, 1 AS flag FROM users;
+            CREATE TABLE users (id INT, name TEXT);
+        "#;
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        let (catalog, diagnostics) = builder.build();
+
+        let inner = catalog.get_view(&QualifiedName::new("inner_v")).unwrap();
+        assert_eq!(inner.columns, vec!["id", "name", "flag"]);
+        let outer = catalog.get_view(&QualifiedName::new("outer_v")).unwrap();
+        assert_eq!(outer.columns, vec!["id", "name", "flag"]);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_unresolvable_view_warns() {
+        let mut builder = SchemaBuilder::new();
+        builder
+            .parse("CREATE VIEW v AS SELECT * FROM missing;")
+            .unwrap();
+        let (catalog, diagnostics) = builder.build();
+
+        assert!(catalog
+            .get_view(&QualifiedName::new("v"))
+            .unwrap()
+            .columns
+            .is_empty());
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        assert_eq!(diagnostics[0].severity, crate::error::Severity::Warning);
+        assert!(diagnostics[0].message.contains("'missing'"));
+    }
+
+    #[test]
+    fn test_foreign_key_actions() {
+        let sql = r#"
+            CREATE TABLE users (id INT PRIMARY KEY);
+            CREATE TABLE orders (
+                id INT PRIMARY KEY,
+                user_id INT,
+                FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+            );
+            ALTER TABLE orders ADD COLUMN owner_id INT;
+            ALTER TABLE orders ADD CONSTRAINT fk_owner FOREIGN KEY (owner_id)
+                REFERENCES users (id) ON DELETE SET NULL ON UPDATE RESTRICT;
+        "#;
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        let (catalog, diagnostics) = builder.build();
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+        let orders = catalog.get_table(&QualifiedName::new("orders")).unwrap();
+        assert_eq!(orders.foreign_keys.len(), 2);
+        assert_eq!(orders.foreign_keys[0].on_delete, ReferentialAction::Cascade);
+        assert_eq!(
+            orders.foreign_keys[0].on_update,
+            ReferentialAction::NoAction
+        );
+        assert_eq!(orders.foreign_keys[1].on_delete, ReferentialAction::SetNull);
+        assert_eq!(
+            orders.foreign_keys[1].on_update,
+            ReferentialAction::Restrict
+        );
+    }
+
+    #[test]
+    fn test_impossible_foreign_key_actions_warn() {
+        let sql = r#"
+            CREATE TABLE users (id INT PRIMARY KEY);
+            CREATE TABLE orders (
+                id INT PRIMARY KEY,
+                user_id INT NOT NULL,
+                editor_id INT,
+                FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE SET NULL,
+                FOREIGN KEY (editor_id) REFERENCES users (id) ON UPDATE SET DEFAULT
+            );
+        "#;
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        let (_, diagnostics) = builder.build();
+
+        assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.kind == DiagnosticKind::InvalidReferentialAction));
+        assert!(diagnostics[0].message.contains("'orders.user_id'"));
+        assert!(diagnostics[0].message.contains("ON DELETE SET NULL"));
+        assert!(diagnostics[1].message.contains("ON UPDATE SET DEFAULT"));
+        assert!(diagnostics[1].message.contains("has no default"));
+    }
+
+    #[test]
+    fn test_indexes() {
+        let sql = r#"
+            CREATE TABLE users (
+                id INT PRIMARY KEY,
+                email VARCHAR(255),
+                name VARCHAR(100),
+                INDEX idx_email (email),
+                KEY (name),
+                UNIQUE KEY uq_email (email)
+            );
+            CREATE UNIQUE INDEX idx_name_email ON users (name, email);
+            CREATE INDEX idx_tmp ON users (id);
+            DROP INDEX idx_tmp;
+        "#;
+        let mut builder = SchemaBuilder::with_dialect(SqlDialect::MySQL);
+        builder.parse(sql).unwrap();
+        let (catalog, _) = builder.build();
+
+        let users = catalog.get_table(&QualifiedName::new("users")).unwrap();
+        let indexes: Vec<(&str, bool)> = users
+            .indexes
+            .iter()
+            .map(|i| (i.name.as_str(), i.unique))
+            .collect();
+        // An unnamed KEY is named after its first column
+        assert_eq!(
+            indexes,
+            vec![
+                ("idx_email", false),
+                ("name", false),
+                ("idx_name_email", true)
+            ]
+        );
+        assert_eq!(users.indexes[2].columns, vec!["name", "email"]);
+        assert_eq!(
+            users.index_names(),
+            vec!["PRIMARY", "idx_email", "name", "idx_name_email", "uq_email"]
+        );
+    }
+
+    #[test]
+    fn test_unindexed_foreign_keys() {
+        let sql = r#"
+            CREATE TABLE users (id SERIAL PRIMARY KEY, org_id INT, UNIQUE (org_id, id));
+            CREATE TABLE orders (
+                id SERIAL PRIMARY KEY,
+                user_id INT REFERENCES users (id),
+                org_id INT,
+                coupon_id INT,
+                CONSTRAINT orders_user_fk FOREIGN KEY (org_id, user_id)
+                    REFERENCES users (org_id, id),
+                FOREIGN KEY (coupon_id) REFERENCES users (id)
+            );
+            CREATE INDEX ON orders (user_id, org_id);
+            CREATE TABLE memberships (
+                user_id INT REFERENCES users (id),
+                org_id INT,
+                PRIMARY KEY (user_id, org_id)
+            );
+        "#;
+        let mut builder = SchemaBuilder::new().with_enabled_rules(["W0017"]);
+        builder.parse(sql).unwrap();
+        let (catalog, diagnostics) = builder.build();
+
+        // An unnamed index has the name PostgreSQL generates
+        let orders = catalog.get_table("orders").unwrap();
+        assert_eq!(
+            orders.index_names(),
+            ["PRIMARY", "orders_user_id_org_id_idx"]
+        );
+
+        // (user_id) and (org_id, user_id) lead the index, in any order
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            ["FOREIGN KEY on 'orders' (coupon_id) has no index on its columns"]
+        );
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::UnindexedForeignKey);
+        assert_eq!(diagnostics[0].severity, crate::error::Severity::Hint);
+        assert!(diagnostics[0]
+            .help
+            .as_deref()
+            .unwrap()
+            .ends_with("CREATE INDEX ON orders (coupon_id)"));
+
+        // Opt-in, and MySQL indexes foreign keys itself
+        for mut builder in [
+            SchemaBuilder::new(),
+            SchemaBuilder::with_dialect(SqlDialect::MySQL).with_enabled_rules(["performance"]),
+        ] {
+            builder
+                .parse(
+                    "CREATE TABLE users (id INT PRIMARY KEY);
+                     CREATE TABLE orders (id INT PRIMARY KEY, user_id INT REFERENCES users (id));",
+                )
+                .unwrap();
+            assert!(builder.build().1.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_search_path_view_references() {
+        let sql = r#"
+            CREATE SCHEMA audit;
+            CREATE SCHEMA billing;
+            CREATE TABLE events (id INT);
+            CREATE TABLE audit.events (id INT);
+            CREATE TABLE billing.invoices (id INT, paid BOOLEAN);
+            CREATE VIEW recent_events AS SELECT * FROM events;
+            CREATE VIEW open_invoices AS
+                WITH i AS (SELECT * FROM invoices) SELECT id FROM i WHERE NOT paid;
+            CREATE VIEW audit_events AS SELECT * FROM audit.events;
+        "#;
+        let mut builder = SchemaBuilder::new().with_enabled_rules(["security"]);
+        builder.parse(sql).unwrap();
+        let (catalog, diagnostics) = builder.build();
+
+        // The view keeps its query
+        let view = catalog.get_view("audit_events").unwrap();
+        assert_eq!(view.query.as_deref(), Some("SELECT * FROM audit.events"));
+
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "View 'recent_events' references 'events' without a schema, but it is defined in schemas 'public', 'audit'",
+                "View 'open_invoices' references 'invoices' without a schema, but it is only defined in schema 'billing'",
+            ]
+        );
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::AmbiguousViewReference);
+        assert!(diagnostics[0]
+            .help
+            .as_deref()
+            .unwrap()
+            .ends_with("(e.g. public.events)"));
+        assert_eq!(diagnostics[1].kind, DiagnosticKind::SearchPathViewReference);
+        assert_eq!(diagnostics[1].severity, crate::error::Severity::Hint);
+
+        // Opt-in
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        assert!(builder.build().1.is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_columns() {
+        let sql = "CREATE TABLE t (\n  id INT PRIMARY KEY,\n  name TEXT,\n  id TEXT NOT NULL\n);";
+        // The error is reported by `build`; parsing goes on
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        builder.parse("CREATE TABLE u (id INT);").unwrap();
+        let (catalog, diagnostics) = builder.build();
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::DuplicateDefinition);
+        assert_eq!(diagnostics[0].severity, crate::error::Severity::Error);
+        assert_eq!(
+            diagnostics[0].message,
+            "Column 'id' is defined more than once in table 't'"
+        );
+        let span = diagnostics[0].span.unwrap();
+        assert_eq!((span.line, span.column), (4, 3));
+
+        // The first definition is kept
+        assert!(catalog.get_table("u").is_some());
+        let t = catalog.get_table("t").unwrap();
+        assert_eq!(t.column_names(), ["id", "name"]);
+        let id = t.get_column("id").unwrap();
+        assert_eq!(id.data_type, SqlType::Integer);
+        assert!(id.is_primary_key);
+
+        // ALTER TABLE ADD COLUMN of an existing name, unless IF NOT EXISTS
+        let mut builder = SchemaBuilder::new();
+        builder
+            .parse("CREATE TABLE t (id INT, name TEXT);")
+            .unwrap();
+        builder
+            .parse("ALTER TABLE t ADD COLUMN IF NOT EXISTS name VARCHAR(10);")
+            .unwrap();
+        builder
+            .parse("ALTER TABLE t ADD COLUMN NAME VARCHAR(10) NOT NULL;")
+            .unwrap();
+        let (catalog, diagnostics) = builder.build();
+        assert_eq!(
+            diagnostics[0].message,
+            "Column 'NAME' is defined more than once in table 't'"
+        );
+        let name = catalog.get_table("t").unwrap().get_column("name").unwrap();
+        assert_eq!(name.data_type, SqlType::Text);
+        assert!(name.nullable);
+    }
+
+    #[test]
+    fn test_drop_and_rename_constraints() {
+        let sql = r#"
+            CREATE TABLE users (id INT PRIMARY KEY, email TEXT UNIQUE);
+            CREATE TABLE accounts (id INT PRIMARY KEY);
+            CREATE TABLE orders (
+                id INT PRIMARY KEY,
+                user_id INT REFERENCES users,
+                total INT CHECK (total >= 0),
+                CONSTRAINT fk_user FOREIGN KEY (user_id) REFERENCES users (id)
+            );
+            ALTER TABLE orders DROP CONSTRAINT fk_user;
+            ALTER TABLE orders ADD CONSTRAINT fk_user FOREIGN KEY (user_id) REFERENCES accounts (id) NOT VALID;
+            ALTER TABLE orders VALIDATE CONSTRAINT fk_user;
+            ALTER TABLE orders DROP CONSTRAINT orders_user_id_fkey;
+            ALTER TABLE orders DROP CONSTRAINT orders_total_check;
+            ALTER TABLE users RENAME CONSTRAINT users_email_key TO uq_users_email;
+            ALTER TABLE users DROP CONSTRAINT IF EXISTS users_missing_key;
+        "#;
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        let (catalog, diagnostics) = builder.build();
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+        let orders = catalog.get_table(&QualifiedName::new("orders")).unwrap();
+        assert_eq!(orders.foreign_keys.len(), 1);
+        assert_eq!(orders.foreign_keys[0].name.as_deref(), Some("fk_user"));
+        assert_eq!(orders.foreign_keys[0].references_table.name, "accounts");
+        assert!(orders.check_constraints.is_empty());
+
+        let users = catalog.get_table(&QualifiedName::new("users")).unwrap();
+        assert_eq!(
+            users.unique_constraints[0].name.as_deref(),
+            Some("uq_users_email")
+        );
+
+        // Dropping the primary key keeps the columns NOT NULL
+        let mut builder = SchemaBuilder::new();
+        builder
+            .parse(
+                "CREATE TABLE t (id INT PRIMARY KEY);
+                 ALTER TABLE t DROP CONSTRAINT t_pkey;
+                 ALTER TABLE t DROP CONSTRAINT t_missing;",
+            )
+            .unwrap();
+        let (catalog, diagnostics) = builder.build();
+        let t = catalog.get_table(&QualifiedName::new("t")).unwrap();
+        assert!(t.primary_key.is_none());
+        assert!(!t.columns["id"].is_primary_key && !t.columns["id"].nullable);
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        assert!(diagnostics[0]
+            .message
+            .contains("constraint 't_missing' which was not found on table 't'"));
+        let span = diagnostics[0].span.unwrap();
+        assert_eq!((span.line, span.column), (3, 48));
+    }
+
+    #[test]
+    fn test_drop_mysql_generated_constraint_names() {
+        let sql = r#"
+            CREATE TABLE users (id INT PRIMARY KEY);
+            CREATE TABLE orders (
+                id INT PRIMARY KEY,
+                user_id INT,
+                buyer_id INT,
+                FOREIGN KEY (user_id) REFERENCES users (id),
+                FOREIGN KEY (buyer_id) REFERENCES users (id)
+            );
+            ALTER TABLE orders DROP CONSTRAINT orders_ibfk_2;
+            ALTER TABLE orders DROP PRIMARY KEY;
+        "#;
+        let mut builder = SchemaBuilder::with_dialect(SqlDialect::MySQL);
+        builder.parse(sql).unwrap();
+        let (catalog, diagnostics) = builder.build();
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+        let orders = catalog.get_table(&QualifiedName::new("orders")).unwrap();
+        assert_eq!(orders.foreign_keys.len(), 1);
+        assert_eq!(orders.foreign_keys[0].columns, vec!["user_id"]);
+        assert!(orders.primary_key.is_none());
+    }
+
+    #[test]
+    fn test_dynamic_sql_concatenation_is_opt_in() {
+        let sql = r#"
+CREATE FUNCTION purge(table_name TEXT) RETURNS void AS $$
+BEGIN
+    -- Quoted with %I only: not flagged
+    EXECUTE format('DELETE FROM %I WHERE created_at < now()', table_name);
+    EXECUTE 'TRUNCATE ' || quote_ident(table_name);
+    EXECUTE 'DELETE FROM ' || table_name || ' WHERE id = $1' USING 1;
+END;
+$$ LANGUAGE plpgsql;
+
+DO $body$
+BEGIN
+    EXECUTE 'ANALYZE ' || 'users';
+    EXECUTE format('VACUUM %s', 'users') || ';';
+END
+$body$;
+"#;
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        assert!(builder.build().1.is_empty());
+
+        let mut builder = SchemaBuilder::new().with_enabled_rules(["security"]);
+        builder.parse(sql).unwrap();
+        let (_, diagnostics) = builder.build();
+        assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.kind == DiagnosticKind::DynamicSqlConcatenation
+                && d.severity == crate::error::Severity::Hint));
+        assert!(diagnostics[0].message.contains("'table_name'"));
+        let span = diagnostics[0].span.unwrap();
+        assert_eq!((span.line, span.column, span.length), (7, 5, 7));
+        assert_eq!(&sql[span.offset..span.offset + span.length], "EXECUTE");
+        assert!(diagnostics[0]
+            .help
+            .as_ref()
+            .unwrap()
+            .contains("quote_ident()"));
+        assert_eq!(diagnostics[1].span.unwrap().line, 14);
+    }
 }
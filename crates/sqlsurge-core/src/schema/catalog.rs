@@ -2,10 +2,15 @@
 
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 use crate::types::SqlType;
 
 /// Schema catalog - holds all table/view information
+///
+/// Tables and views are reference-counted, so cloning a catalog (e.g. to
+/// hand it to worker threads or to overlay a file's DDL) only copies
+/// pointers. Mutable lookups copy a definition on write if it is shared.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Catalog {
     /// Schema name -> Schema
@@ -14,24 +19,28 @@ pub struct Catalog {
     pub default_schema: String,
     /// Enum type definitions (name -> EnumTypeDef)
     pub enums: IndexMap<String, EnumTypeDef>,
+    /// User-defined function signatures (lowercase name, schema-qualified
+    /// outside the default schema -> overloads)
+    #[serde(default)]
+    pub functions: IndexMap<String, Vec<FunctionSig>>,
 }
 
 impl Catalog {
     pub fn new() -> Self {
+        Self::with_default_schema("public")
+    }
+
+    /// Create an empty catalog whose unqualified names live in `schema`
+    /// (see [`SqlDialect::default_schema`](crate::dialect::SqlDialect::default_schema))
+    pub fn with_default_schema(schema: &str) -> Self {
         let mut catalog = Self {
             schemas: IndexMap::new(),
-            default_schema: "public".to_string(),
+            default_schema: schema.to_string(),
             enums: IndexMap::new(),
+            functions: IndexMap::new(),
         };
         // Create default schema
-        catalog.schemas.insert(
-            "public".to_string(),
-            Schema {
-                name: "public".to_string(),
-                tables: IndexMap::new(),
-                views: IndexMap::new(),
-            },
-        );
+        catalog.get_or_create_schema(schema);
         catalog
     }
 
@@ -53,7 +62,8 @@ impl Catalog {
     }
 
     /// Add a table to the catalog
-    pub fn add_table(&mut self, table: TableDef) {
+    pub fn add_table(&mut self, table: impl Into<Arc<TableDef>>) {
+        let table = table.into();
         let schema_name = table
             .name
             .schema
@@ -63,24 +73,53 @@ impl Catalog {
         schema.tables.insert(table.name.name.clone(), table);
     }
 
+    /// Schema a name refers to (its own, or the default schema)
+    fn schema_of(&self, name: NameRef<'_>) -> Option<&Schema> {
+        match name.schema {
+            Some(schema) => self.schemas.get(schema),
+            None => self.schemas.get(&self.default_schema),
+        }
+    }
+
     /// Look up a table by name
-    pub fn get_table(&self, name: &QualifiedName) -> Option<&TableDef> {
-        let schema_name = name.schema.as_ref().unwrap_or(&self.default_schema);
-        self.schemas
-            .get(schema_name)
-            .and_then(|s| s.tables.get(&name.name))
+    ///
+    /// Accepts a [`QualifiedName`] or, for a name in the default schema, a
+    /// plain `&str`.
+    pub fn get_table<'n>(&self, name: impl Into<NameRef<'n>>) -> Option<&TableDef> {
+        let name = name.into();
+        self.schema_of(name)
+            .and_then(|s| s.tables.get(name.name))
+            .map(Arc::as_ref)
+    }
+
+    /// Look up a table by name, sharing the definition
+    pub fn get_table_arc<'n>(&self, name: impl Into<NameRef<'n>>) -> Option<Arc<TableDef>> {
+        let name = name.into();
+        self.schema_of(name)
+            .and_then(|s| s.tables.get(name.name))
+            .cloned()
     }
 
     /// Look up a table by name (mutable)
     pub fn get_table_mut(&mut self, name: &QualifiedName) -> Option<&mut TableDef> {
-        let schema_name = name.schema.as_ref().unwrap_or(&self.default_schema).clone();
+        let schema_name = name.schema.as_ref().unwrap_or(&self.default_schema);
         self.schemas
-            .get_mut(&schema_name)
+            .get_mut(schema_name)
             .and_then(|s| s.tables.get_mut(&name.name))
+            .map(Arc::make_mut)
+    }
+
+    /// Remove a table from the catalog
+    pub fn remove_table(&mut self, name: &QualifiedName) -> Option<TableDef> {
+        let schema_name = name.schema.as_ref().unwrap_or(&self.default_schema);
+        self.schemas
+            .get_mut(schema_name)
+            .and_then(|s| s.tables.shift_remove(&name.name))
+            .map(unwrap_or_clone)
     }
 
     /// Check if a table exists
-    pub fn table_exists(&self, name: &QualifiedName) -> bool {
+    pub fn table_exists<'n>(&self, name: impl Into<NameRef<'n>>) -> bool {
         self.get_table(name).is_some()
     }
 
@@ -94,13 +133,56 @@ impl Catalog {
         self.enums.get(name)
     }
 
+    /// Remove an enum type from the catalog
+    pub fn remove_enum(&mut self, name: &str) -> Option<EnumTypeDef> {
+        self.enums.shift_remove(name)
+    }
+
     /// Check if an enum type exists
     pub fn enum_exists(&self, name: &str) -> bool {
         self.enums.contains_key(name)
     }
 
+    /// Add a function signature to the catalog
+    ///
+    /// The signature's name may be schema-qualified (`audit.log_it`).
+    /// Overloads are kept side by side; a signature with the same argument
+    /// types as an existing one replaces it.
+    pub fn add_function(&mut self, sig: FunctionSig) {
+        let key = self.function_key((&QualifiedName::parse(&sig.name)).into());
+        let overloads = self.functions.entry(key).or_default();
+        match overloads
+            .iter_mut()
+            .find(|o| o.args == sig.args && o.variadic == sig.variadic)
+        {
+            Some(existing) => *existing = sig,
+            None => overloads.push(sig),
+        }
+    }
+
+    /// The declared overloads of a function (case-insensitive)
+    ///
+    /// Like [`Catalog::get_table`], an unqualified name is in the default
+    /// schema.
+    pub fn get_functions<'n>(&self, name: impl Into<NameRef<'n>>) -> &[FunctionSig] {
+        self.functions
+            .get(self.function_key(name.into()).as_str())
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Key of a function in `functions`
+    fn function_key(&self, name: NameRef<'_>) -> String {
+        match name.schema {
+            Some(schema) if schema != self.default_schema => {
+                format!("{}.{}", schema, name.name).to_lowercase()
+            }
+            _ => name.name.to_lowercase(),
+        }
+    }
+
     /// Add a view to the catalog
-    pub fn add_view(&mut self, view: ViewDef) {
+    pub fn add_view(&mut self, view: impl Into<Arc<ViewDef>>) {
+        let view = view.into();
         let schema_name = view
             .name
             .schema
@@ -111,18 +193,136 @@ impl Catalog {
     }
 
     /// Look up a view by name
-    pub fn get_view(&self, name: &QualifiedName) -> Option<&ViewDef> {
+    pub fn get_view<'n>(&self, name: impl Into<NameRef<'n>>) -> Option<&ViewDef> {
+        let name = name.into();
+        self.schema_of(name)
+            .and_then(|s| s.views.get(name.name))
+            .map(Arc::as_ref)
+    }
+
+    /// Look up a view by name, sharing the definition
+    pub fn get_view_arc<'n>(&self, name: impl Into<NameRef<'n>>) -> Option<Arc<ViewDef>> {
+        let name = name.into();
+        self.schema_of(name)
+            .and_then(|s| s.views.get(name.name))
+            .cloned()
+    }
+
+    /// Look up a view by name (mutable)
+    pub fn get_view_mut(&mut self, name: &QualifiedName) -> Option<&mut ViewDef> {
+        let schema_name = name.schema.as_ref().unwrap_or(&self.default_schema);
+        self.schemas
+            .get_mut(schema_name)
+            .and_then(|s| s.views.get_mut(&name.name))
+            .map(Arc::make_mut)
+    }
+
+    /// Remove a view from the catalog
+    pub fn remove_view(&mut self, name: &QualifiedName) -> Option<ViewDef> {
         let schema_name = name.schema.as_ref().unwrap_or(&self.default_schema);
         self.schemas
-            .get(schema_name)
-            .and_then(|s| s.views.get(&name.name))
+            .get_mut(schema_name)
+            .and_then(|s| s.views.shift_remove(&name.name))
+            .map(unwrap_or_clone)
     }
 
     /// Check if a view exists
-    pub fn view_exists(&self, name: &QualifiedName) -> bool {
+    pub fn view_exists<'n>(&self, name: impl Into<NameRef<'n>>) -> bool {
         self.get_view(name).is_some()
     }
 
+    /// Schema-qualified name of the table or view `name` refers to, if it exists
+    pub fn qualify<'n>(&self, name: impl Into<NameRef<'n>>) -> Option<QualifiedName> {
+        let name = name.into();
+        if !self.table_exists(name) && !self.view_exists(name) {
+            return None;
+        }
+        let schema = name.schema.unwrap_or(&self.default_schema);
+        Some(QualifiedName::with_schema(schema, name.name))
+    }
+
+    /// Mark a table, or one of its columns, deprecated with a migration note
+    ///
+    /// Returns `false` if the table or column doesn't exist.
+    pub fn deprecate(
+        &mut self,
+        table: &QualifiedName,
+        column: Option<&str>,
+        note: impl Into<String>,
+    ) -> bool {
+        let Some(table) = self.get_table_mut(table) else {
+            return false;
+        };
+        let deprecated = match column {
+            Some(column) => match table
+                .columns
+                .values_mut()
+                .find(|c| c.name.eq_ignore_ascii_case(column))
+            {
+                Some(column) => &mut column.deprecated,
+                None => return false,
+            },
+            None => &mut table.deprecated,
+        };
+        *deprecated = Some(note.into());
+        true
+    }
+
+    /// Remove an index from whichever table or materialized view of its
+    /// schema defines it
+    pub fn remove_index(&mut self, name: &QualifiedName) -> bool {
+        let schema_name = name.schema.as_ref().unwrap_or(&self.default_schema);
+        let Some(schema) = self.schemas.get_mut(schema_name) else {
+            return false;
+        };
+        for table in schema.tables.values_mut() {
+            if let Some(pos) = table.indexes.iter().position(|i| i.name == name.name) {
+                Arc::make_mut(table).indexes.remove(pos);
+                return true;
+            }
+        }
+        for view in schema.views.values_mut() {
+            if let Some(pos) = view.indexes.iter().position(|i| i.name == name.name) {
+                Arc::make_mut(view).indexes.remove(pos);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Whether any table or materialized view has a secondary index
+    ///
+    /// Without one the catalog carries no index information (a snapshot
+    /// taken without indexes, a schema whose indexes live elsewhere), and
+    /// no column can be told apart as unindexed.
+    pub fn has_indexes(&self) -> bool {
+        self.schemas.values().any(|schema| {
+            schema
+                .tables
+                .values()
+                .any(|table| !table.indexes.is_empty())
+                || schema.views.values().any(|view| !view.indexes.is_empty())
+        })
+    }
+
+    /// Find all schemas containing a table or view with the given (unqualified) name
+    pub fn schemas_containing(&self, name: &str) -> Vec<&str> {
+        self.schemas
+            .iter()
+            .filter(|(_, schema)| {
+                schema.tables.contains_key(name) || schema.views.contains_key(name)
+            })
+            .map(|(schema_name, _)| schema_name.as_str())
+            .collect()
+    }
+
+    /// Whether the catalog defines no table or view, in any schema
+    pub fn is_empty(&self) -> bool {
+        self.schemas
+            .values()
+            .all(|schema| schema.tables.is_empty() && schema.views.is_empty())
+    }
+
     /// Get all table names
     pub fn table_names(&self) -> Vec<QualifiedName> {
         self.schemas
@@ -136,6 +336,19 @@ impl Catalog {
             .collect()
     }
 
+    /// Whether any table, view or column is named `name` (case-insensitive)
+    pub fn defines_identifier(&self, name: &str) -> bool {
+        self.schemas.values().any(|schema| {
+            schema.tables.values().any(|t| {
+                t.name.name.eq_ignore_ascii_case(name)
+                    || t.columns.keys().any(|c| c.eq_ignore_ascii_case(name))
+            }) || schema.views.values().any(|v| {
+                v.name.name.eq_ignore_ascii_case(name)
+                    || v.columns.iter().any(|c| c.eq_ignore_ascii_case(name))
+            })
+        })
+    }
+
     /// Get all table and view names (for typo suggestions)
     pub fn table_or_view_names(&self) -> Vec<QualifiedName> {
         self.schemas
@@ -159,12 +372,17 @@ impl Catalog {
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Schema {
     pub name: String,
-    pub tables: IndexMap<String, TableDef>,
-    pub views: IndexMap<String, ViewDef>,
+    pub tables: IndexMap<String, Arc<TableDef>>,
+    pub views: IndexMap<String, Arc<ViewDef>>,
+}
+
+/// Take the value out of an `Arc`, cloning it if it is still shared
+fn unwrap_or_clone<T: Clone>(arc: Arc<T>) -> T {
+    Arc::try_unwrap(arc).unwrap_or_else(|arc| (*arc).clone())
 }
 
 /// Qualified name (schema.table or just table)
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct QualifiedName {
     pub schema: Option<String>,
     pub name: String,
@@ -205,15 +423,80 @@ impl std::fmt::Display for QualifiedName {
     }
 }
 
+/// A borrowed table or view name, for lookups that shouldn't allocate
+///
+/// Built from a [`QualifiedName`], or from a `&str` for an unqualified name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NameRef<'a> {
+    pub schema: Option<&'a str>,
+    pub name: &'a str,
+}
+
+impl<'a> NameRef<'a> {
+    pub fn new(schema: Option<&'a str>, name: &'a str) -> Self {
+        Self { schema, name }
+    }
+}
+
+impl<'a> From<&'a QualifiedName> for NameRef<'a> {
+    fn from(name: &'a QualifiedName) -> Self {
+        Self::new(name.schema.as_deref(), &name.name)
+    }
+}
+
+impl<'a> From<&'a Arc<QualifiedName>> for NameRef<'a> {
+    fn from(name: &'a Arc<QualifiedName>) -> Self {
+        Self::from(name.as_ref())
+    }
+}
+
+impl<'a> From<&'a str> for NameRef<'a> {
+    fn from(name: &'a str) -> Self {
+        Self::new(None, name)
+    }
+}
+
+impl<'a> From<&'a String> for NameRef<'a> {
+    fn from(name: &'a String) -> Self {
+        Self::new(None, name)
+    }
+}
+
+/// Where a schema object was defined: a named schema source and position
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaOrigin {
+    /// Source name passed to `SchemaBuilder::parse_named` (e.g. a file path)
+    pub file: String,
+    /// Line number (1-indexed)
+    pub line: usize,
+    /// Column number (1-indexed)
+    pub column: usize,
+}
+
 /// Table definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableDef {
     pub name: QualifiedName,
+    /// Columns by name, in table order; change them with [`TableDef::add_column`],
+    /// [`TableDef::drop_column`] and [`TableDef::rename_column`] so that their
+    /// ordinals follow
     pub columns: IndexMap<String, ColumnDef>,
     pub primary_key: Option<PrimaryKeyDef>,
     pub foreign_keys: Vec<ForeignKeyDef>,
     pub unique_constraints: Vec<UniqueConstraintDef>,
     pub check_constraints: Vec<CheckConstraintDef>,
+    /// Secondary indexes (CREATE INDEX, MySQL INDEX/KEY definitions)
+    #[serde(default)]
+    pub indexes: Vec<IndexDef>,
+    /// Table comment (COMMENT ON TABLE, or MySQL table option)
+    #[serde(default)]
+    pub comment: Option<String>,
+    /// Migration note if the table is deprecated (empty if none was given)
+    #[serde(default)]
+    pub deprecated: Option<String>,
+    /// Where the table was created (absent for unnamed sources and snapshots)
+    #[serde(default)]
+    pub origin: Option<SchemaOrigin>,
 }
 
 impl TableDef {
@@ -225,9 +508,19 @@ impl TableDef {
             foreign_keys: Vec::new(),
             unique_constraints: Vec::new(),
             check_constraints: Vec::new(),
+            indexes: Vec::new(),
+            comment: None,
+            deprecated: None,
+            origin: None,
         }
     }
 
+    /// Set the comment; a `DEPRECATED: note` comment also marks the table deprecated
+    pub fn set_comment(&mut self, comment: Option<String>) {
+        self.deprecated = comment.as_deref().and_then(deprecation_note);
+        self.comment = comment;
+    }
+
     /// Get a column by name
     pub fn get_column(&self, name: &str) -> Option<&ColumnDef> {
         // Case-insensitive lookup
@@ -242,9 +535,153 @@ impl TableDef {
         self.get_column(name).is_some()
     }
 
-    /// Get all column names
+    /// Get all column names, in table order
     pub fn column_names(&self) -> Vec<&str> {
-        self.columns.keys().map(|s| s.as_str()).collect()
+        self.ordered_columns()
+            .into_iter()
+            .map(|c| c.name.as_str())
+            .collect()
+    }
+
+    /// The columns in table order (by ordinal), as `SELECT *` lists them
+    ///
+    /// Tables built by hand or loaded from a snapshot without ordinals keep
+    /// the order of `columns`.
+    pub fn ordered_columns(&self) -> Vec<&ColumnDef> {
+        let mut columns: Vec<&ColumnDef> = self.columns.values().collect();
+        columns.sort_by_key(|c| c.ordinal);
+        columns
+    }
+
+    /// Add a column after the others, or replace the column of the same name
+    /// in its position
+    pub fn add_column(&mut self, column: ColumnDef) {
+        self.columns.insert(column.name.clone(), column);
+        self.renumber_columns();
+    }
+
+    /// Add a column at a 0-based position (MySQL `FIRST` / `AFTER col`)
+    pub fn insert_column(&mut self, index: usize, column: ColumnDef) {
+        let index = index.min(self.columns.len());
+        self.columns
+            .shift_insert(index, column.name.clone(), column);
+        self.renumber_columns();
+    }
+
+    /// Remove a column; the columns after it move up
+    pub fn drop_column(&mut self, name: &str) -> Option<ColumnDef> {
+        let dropped = self.columns.shift_remove(name);
+        self.renumber_columns();
+        dropped
+    }
+
+    /// Rename a column, keeping its position; false if there is no such column
+    ///
+    /// Like the database, the table's keys, foreign keys and indexes follow
+    /// the new name.
+    pub fn rename_column(&mut self, old: &str, new: &str) -> bool {
+        let Some((index, _, mut column)) = self.columns.shift_remove_full(old) else {
+            return false;
+        };
+        column.name = new.to_string();
+        self.columns.shift_insert(index, new.to_string(), column);
+
+        let name = &self.name;
+        let foreign_keys = self.foreign_keys.iter_mut().flat_map(|fk| {
+            let referenced = (fk.references_table == *name).then_some(&mut fk.references_columns);
+            fk.columns
+                .iter_mut()
+                .chain(referenced.into_iter().flatten())
+        });
+        let columns = self
+            .primary_key
+            .iter_mut()
+            .flat_map(|pk| pk.columns.iter_mut())
+            .chain(
+                self.unique_constraints
+                    .iter_mut()
+                    .flat_map(|u| u.columns.iter_mut()),
+            )
+            .chain(self.indexes.iter_mut().flat_map(|i| i.columns.iter_mut()))
+            .chain(foreign_keys);
+        for column in columns {
+            if column == old {
+                *column = new.to_string();
+            }
+        }
+        true
+    }
+
+    /// Set each column's ordinal from its position in `columns`
+    fn renumber_columns(&mut self) {
+        for (index, column) in self.columns.values_mut().enumerate() {
+            column.ordinal = index + 1;
+        }
+    }
+
+    /// Names of the indexes an index hint may refer to
+    ///
+    /// Besides declared indexes, named UNIQUE constraints are backed by an
+    /// index of the same name, and MySQL names the primary key index `PRIMARY`.
+    pub fn index_names(&self) -> Vec<&str> {
+        let has_primary_key =
+            self.primary_key.is_some() || self.columns.values().any(|c| c.is_primary_key);
+        has_primary_key
+            .then_some("PRIMARY")
+            .into_iter()
+            .chain(self.indexes.iter().map(|i| i.name.as_str()))
+            .chain(
+                self.unique_constraints
+                    .iter()
+                    .filter_map(|u| u.name.as_deref()),
+            )
+            .collect()
+    }
+
+    /// Whether an index leads with `columns`: its first columns are these
+    /// columns, in any order
+    ///
+    /// The primary key and UNIQUE constraints count, as they are backed by
+    /// an index.
+    pub fn has_index_leading_with<S: AsRef<str>>(&self, columns: &[S]) -> bool {
+        let secondary = self
+            .indexes
+            .iter()
+            .map(|i| i.columns.iter().map(String::as_str).collect());
+        !columns.is_empty()
+            && self
+                .unique_keys()
+                .into_iter()
+                .chain(secondary)
+                .any(|index: Vec<&str>| {
+                    index.len() >= columns.len()
+                        && columns.iter().all(|column| {
+                            index[..columns.len()]
+                                .iter()
+                                .any(|c| c.eq_ignore_ascii_case(column.as_ref()))
+                        })
+                })
+    }
+
+    /// Column sets whose values must be unique: the primary key, then UNIQUE constraints
+    pub fn unique_keys(&self) -> Vec<Vec<&str>> {
+        let primary_key: Vec<&str> = match &self.primary_key {
+            Some(pk) => pk.columns.iter().map(|c| c.as_str()).collect(),
+            None => self
+                .columns
+                .values()
+                .filter(|c| c.is_primary_key)
+                .map(|c| c.name.as_str())
+                .collect(),
+        };
+        std::iter::once(primary_key)
+            .filter(|key| !key.is_empty())
+            .chain(
+                self.unique_constraints
+                    .iter()
+                    .map(|u| u.columns.iter().map(|c| c.as_str()).collect()),
+            )
+            .collect()
     }
 }
 
@@ -252,22 +689,50 @@ impl TableDef {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ColumnDef {
     pub name: String,
+    /// 1-based position in the table, as `SELECT *` and `INSERT` without a
+    /// column list see it (0 until the column is added to a table)
+    ///
+    /// A column added by ALTER TABLE comes after the existing ones, so two
+    /// schema histories with the same columns can order them differently.
+    #[serde(default)]
+    pub ordinal: usize,
     pub data_type: SqlType,
     pub nullable: bool,
     pub default: Option<DefaultValue>,
     pub is_primary_key: bool,
     pub identity: Option<IdentityKind>,
+    /// SERIAL or AUTO_INCREMENT: the database supplies a value when none is given
+    #[serde(default)]
+    pub auto_increment: bool,
+    /// Generation expression for computed columns (GENERATED ALWAYS AS (...))
+    #[serde(default)]
+    pub generated: Option<GeneratedColumn>,
+    /// Column comment (COMMENT ON COLUMN, or MySQL inline COMMENT)
+    #[serde(default)]
+    pub comment: Option<String>,
+    /// Migration note if the column is deprecated (empty if none was given)
+    #[serde(default)]
+    pub deprecated: Option<String>,
+    /// Where the column was defined (absent for unnamed sources and snapshots)
+    #[serde(default)]
+    pub origin: Option<SchemaOrigin>,
 }
 
 impl ColumnDef {
     pub fn new(name: impl Into<String>, data_type: SqlType) -> Self {
         Self {
             name: name.into(),
+            ordinal: 0,
             data_type,
             nullable: true,
             default: None,
             is_primary_key: false,
             identity: None,
+            auto_increment: false,
+            generated: None,
+            comment: None,
+            deprecated: None,
+            origin: None,
         }
     }
 
@@ -281,11 +746,66 @@ impl ColumnDef {
         self
     }
 
+    pub fn with_comment(mut self, comment: impl Into<String>) -> Self {
+        self.set_comment(Some(comment.into()));
+        self
+    }
+
+    /// Set the comment; a `DEPRECATED: note` comment also marks the column deprecated
+    pub fn set_comment(&mut self, comment: Option<String>) {
+        self.deprecated = comment.as_deref().and_then(deprecation_note);
+        self.comment = comment;
+    }
+
+    /// The sequence the column's values come from, lowercased and without a
+    /// schema: the one its `nextval(...)` default names, or for a SERIAL (or
+    /// AUTO_INCREMENT) column of `table` the one SERIAL creates,
+    /// `<table>_<column>_seq`
+    ///
+    /// A SERIAL column is an integer column defaulting to `nextval` of that
+    /// sequence, which is how `pg_dump` writes it, so the two definitions
+    /// are the same when their sequences are. MySQL has no sequences: there
+    /// the name only tells AUTO_INCREMENT columns apart from the others.
+    pub fn value_sequence(&self, table: &str) -> Option<String> {
+        if let Some(DefaultValue::NextVal(call)) = &self.default {
+            let name = call.split('\'').nth(1)?;
+            let name = name.rsplit('.').next().unwrap_or(name).trim_matches('"');
+            return Some(name.to_lowercase());
+        }
+        self.auto_increment
+            .then(|| format!("{}_{}_seq", table, self.name).to_lowercase())
+    }
+
     pub fn primary_key(mut self) -> Self {
         self.is_primary_key = true;
         self.nullable = false;
         self
     }
+
+    /// Whether an INSERT must give the column a value: it is NOT NULL and
+    /// has no default, identity, auto-increment or generation expression
+    pub fn requires_value(&self) -> bool {
+        !self.nullable
+            && self.default.is_none()
+            && self.identity.is_none()
+            && !self.auto_increment
+            && self.generated.is_none()
+    }
+}
+
+/// Migration note of a `DEPRECATED` comment: `'DEPRECATED: use feature_flags'`
+/// -> `use feature_flags`
+pub fn deprecation_note(comment: &str) -> Option<String> {
+    let comment = comment.trim_start();
+    let prefix = comment.get(..10)?;
+    let rest = &comment[10..];
+    if !prefix.eq_ignore_ascii_case("deprecated")
+        || rest.starts_with(|c: char| c.is_alphanumeric() || c == '_')
+    {
+        return None;
+    }
+    let note = rest.trim_start_matches([':', '-', ' ', '\t']).trim_end();
+    Some(note.to_string())
 }
 
 /// Default value for a column
@@ -312,6 +832,36 @@ pub struct ForeignKeyDef {
     pub columns: Vec<String>,
     pub references_table: QualifiedName,
     pub references_columns: Vec<String>,
+    #[serde(default)]
+    pub on_delete: ReferentialAction,
+    #[serde(default)]
+    pub on_update: ReferentialAction,
+    /// Where the referenced table is named (absent for unnamed sources and snapshots)
+    #[serde(default)]
+    pub origin: Option<SchemaOrigin>,
+}
+
+/// Action taken on referencing rows when the referenced row is deleted or updated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ReferentialAction {
+    #[default]
+    NoAction,
+    Restrict,
+    Cascade,
+    SetNull,
+    SetDefault,
+}
+
+impl std::fmt::Display for ReferentialAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReferentialAction::NoAction => write!(f, "NO ACTION"),
+            ReferentialAction::Restrict => write!(f, "RESTRICT"),
+            ReferentialAction::Cascade => write!(f, "CASCADE"),
+            ReferentialAction::SetNull => write!(f, "SET NULL"),
+            ReferentialAction::SetDefault => write!(f, "SET DEFAULT"),
+        }
+    }
 }
 
 /// Unique constraint
@@ -321,6 +871,14 @@ pub struct UniqueConstraintDef {
     pub columns: Vec<String>,
 }
 
+/// Secondary index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexDef {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+}
+
 /// CHECK constraint
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CheckConstraintDef {
@@ -328,6 +886,70 @@ pub struct CheckConstraintDef {
     pub expression: String,
 }
 
+/// Signature of a user-defined function, from `CREATE FUNCTION` or config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionSig {
+    pub name: String,
+    /// Argument types, in order
+    pub args: Vec<SqlType>,
+    /// The last argument takes one or more values (`VARIADIC`)
+    #[serde(default)]
+    pub variadic: bool,
+    pub returns: SqlType,
+}
+
+impl FunctionSig {
+    pub fn new(name: impl Into<String>, args: Vec<SqlType>, returns: SqlType) -> Self {
+        Self {
+            name: name.into(),
+            args,
+            variadic: false,
+            returns,
+        }
+    }
+
+    /// Make the last argument variadic
+    pub fn variadic(mut self) -> Self {
+        self.variadic = true;
+        self
+    }
+
+    /// Whether a call with `count` arguments matches this signature
+    pub fn accepts(&self, count: usize) -> bool {
+        if self.variadic {
+            count >= self.args.len()
+        } else {
+            count == self.args.len()
+        }
+    }
+
+    /// Type of argument `index` (0-based), repeating a variadic last argument
+    pub fn arg_type(&self, index: usize) -> Option<&SqlType> {
+        match self.args.get(index) {
+            Some(arg) => Some(arg),
+            None if self.variadic => self.args.last(),
+            None => None,
+        }
+    }
+
+    /// How a call to the function is written, e.g. `my_score(integer, text)`
+    pub fn display(&self) -> String {
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| {
+                if self.variadic && i + 1 == self.args.len() {
+                    format!("VARIADIC {}", arg.display_name())
+                } else {
+                    arg.display_name()
+                }
+            })
+            .collect();
+        format!("{}({})", self.name, args.join(", "))
+    }
+}
+
 /// Enum type definition (CREATE TYPE ... AS ENUM)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EnumTypeDef {
@@ -342,12 +964,33 @@ pub enum IdentityKind {
     ByDefault,
 }
 
+/// Computed column definition (GENERATED ALWAYS AS (expr) STORED/VIRTUAL)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedColumn {
+    pub expression: String,
+    /// STORED (materialized on write) vs VIRTUAL (computed on read)
+    pub stored: bool,
+}
+
 /// View definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ViewDef {
     pub name: QualifiedName,
     pub columns: Vec<String>,
     pub materialized: bool,
+    /// Tables and views the view's query reads, as written in it (resolved
+    /// against the catalog on lookup)
+    #[serde(default)]
+    pub dependencies: Vec<QualifiedName>,
+    /// The view's query, as SQL text (absent for views not defined in SQL)
+    #[serde(default)]
+    pub query: Option<String>,
+    /// Indexes of a materialized view
+    #[serde(default)]
+    pub indexes: Vec<IndexDef>,
+    /// Where the view was created (absent for unnamed sources and snapshots)
+    #[serde(default)]
+    pub origin: Option<SchemaOrigin>,
 }
 
 #[cfg(test)]
@@ -365,13 +1008,134 @@ mod tests {
         assert_eq!(name.name, "users");
     }
 
+    #[test]
+    fn test_deprecation_note() {
+        assert_eq!(
+            deprecation_note("DEPRECATED: use feature_flags").as_deref(),
+            Some("use feature_flags")
+        );
+        assert_eq!(
+            deprecation_note("  deprecated - drop in v3 ").as_deref(),
+            Some("drop in v3")
+        );
+        assert_eq!(deprecation_note("Deprecated").as_deref(), Some(""));
+        assert_eq!(deprecation_note("deprecated_at timestamp"), None);
+        assert_eq!(deprecation_note("Not deprecated"), None);
+    }
+
+    #[test]
+    fn test_function_overloads() {
+        let mut catalog = Catalog::new();
+        catalog.add_function(FunctionSig::new(
+            "my_score",
+            vec![SqlType::Integer],
+            SqlType::Integer,
+        ));
+        catalog.add_function(FunctionSig::new(
+            "My_Score",
+            vec![SqlType::Integer, SqlType::Text],
+            SqlType::Text,
+        ));
+        // Same arguments: replaces the first overload
+        catalog.add_function(FunctionSig::new(
+            "my_score",
+            vec![SqlType::Integer],
+            SqlType::BigInt,
+        ));
+
+        let overloads = catalog.get_functions("MY_SCORE");
+        assert_eq!(overloads.len(), 2);
+        assert_eq!(overloads[0].returns, SqlType::BigInt);
+        assert!(catalog.get_functions("other").is_empty());
+
+        let concat =
+            FunctionSig::new("cat", vec![SqlType::Text, SqlType::Text], SqlType::Text).variadic();
+        assert!(concat.accepts(2) && concat.accepts(5) && !concat.accepts(1));
+        assert_eq!(concat.arg_type(4), Some(&SqlType::Text));
+        assert_eq!(concat.display(), "cat(text, VARIADIC text)");
+    }
+
     #[test]
     fn test_catalog_add_table() {
         let mut catalog = Catalog::new();
+        assert!(catalog.is_empty());
         let table = TableDef::new(QualifiedName::new("users"));
         catalog.add_table(table);
 
         assert!(catalog.table_exists(&QualifiedName::new("users")));
         assert!(catalog.table_exists(&QualifiedName::with_schema("public", "users")));
+        assert!(!catalog.is_empty());
+    }
+
+    #[test]
+    fn test_clone_shares_tables_until_written() {
+        let users = QualifiedName::new("users");
+        let mut table = TableDef::new(users.clone());
+        table
+            .columns
+            .insert("id".into(), ColumnDef::new("id", SqlType::Integer));
+        let mut catalog = Catalog::new();
+        catalog.add_table(table);
+
+        let mut copy = catalog.clone();
+        assert!(Arc::ptr_eq(
+            &catalog.get_table_arc(&users).unwrap(),
+            &copy.get_table_arc(&users).unwrap()
+        ));
+
+        // Copy on write: the original is unaffected
+        copy.get_table_mut(&users)
+            .unwrap()
+            .columns
+            .insert("name".into(), ColumnDef::new("name", SqlType::Text));
+        assert_eq!(copy.get_table(&users).unwrap().columns.len(), 2);
+        assert_eq!(catalog.get_table(&users).unwrap().columns.len(), 1);
+
+        // Serialized as plain definitions
+        let json = serde_json::to_value(&catalog).unwrap();
+        assert_eq!(
+            json["schemas"]["public"]["tables"]["users"]["name"]["name"],
+            "users"
+        );
+        let back: Catalog = serde_json::from_value(json).unwrap();
+        assert!(back.get_table(&users).unwrap().column_exists("id"));
+    }
+
+    #[test]
+    fn test_schemas_containing() {
+        let mut catalog = Catalog::new();
+        catalog.add_table(TableDef::new(QualifiedName::with_schema(
+            "analytics",
+            "events",
+        )));
+        catalog.add_view(ViewDef {
+            name: QualifiedName::with_schema("staging", "events"),
+            columns: vec![],
+            materialized: false,
+            dependencies: vec![],
+            query: None,
+            indexes: vec![],
+            origin: None,
+        });
+
+        assert_eq!(
+            catalog.schemas_containing("events"),
+            vec!["analytics", "staging"]
+        );
+        assert!(catalog.schemas_containing("users").is_empty());
+    }
+
+    #[test]
+    fn test_foreign_key_actions_default_when_missing() {
+        // Snapshots written before referential actions were tracked
+        let json = r#"{
+            "name": null,
+            "columns": ["user_id"],
+            "references_table": {"schema": null, "name": "users"},
+            "references_columns": ["id"]
+        }"#;
+        let fk: ForeignKeyDef = serde_json::from_str(json).unwrap();
+        assert_eq!(fk.on_delete, ReferentialAction::NoAction);
+        assert_eq!(fk.on_update, ReferentialAction::NoAction);
     }
 }
@@ -0,0 +1,301 @@
+//! Building a catalog in Rust, without SQL
+//!
+//! [`CatalogBuilder`] defines tables, enums, views and functions directly,
+//! for tests and tools that already know the schema. The finished catalog
+//! goes through the same final validation as one built from SQL by
+//! [`SchemaBuilder`], with its foreign key checks on: foreign keys must
+//! reference existing tables and columns, and keys must name columns of
+//! their table.
+//!
+//! ```
+//! use sqlsurge_core::schema::CatalogBuilder;
+//! use sqlsurge_core::types::SqlType;
+//!
+//! let (catalog, diagnostics) = CatalogBuilder::new()
+//!     .table("users", |t| {
+//!         t.column("id", SqlType::Integer, |c| c.primary_key())
+//!             .column("email", SqlType::Text, |c| c.not_null())
+//!     })
+//!     .table("orders", |t| {
+//!         t.column("id", SqlType::Integer, |c| c.primary_key())
+//!             .column("user_id", SqlType::Integer, |c| c)
+//!             .foreign_key(["user_id"], "users", ["id"])
+//!     })
+//!     .enum_type("status", ["active", "inactive"])
+//!     .view("active_users", ["id", "email"])
+//!     .build();
+//! assert!(diagnostics.is_empty());
+//! assert!(catalog.get_table("orders").is_some());
+//! ```
+
+use super::builder::SchemaBuilder;
+use super::catalog::{
+    Catalog, CheckConstraintDef, ColumnDef, EnumTypeDef, ForeignKeyDef, FunctionSig, IndexDef,
+    PrimaryKeyDef, QualifiedName, ReferentialAction, TableDef, UniqueConstraintDef, ViewDef,
+};
+use crate::dialect::SqlDialect;
+use crate::error::Diagnostic;
+use crate::types::SqlType;
+
+/// Builds a [`Catalog`] from definitions given in Rust
+///
+/// Names may be qualified (`"audit.events"`); unqualified ones are in the
+/// dialect's default schema.
+#[derive(Debug)]
+pub struct CatalogBuilder {
+    catalog: Catalog,
+    dialect: SqlDialect,
+}
+
+/// Defines the columns and constraints of one table (see [`CatalogBuilder::table`])
+#[derive(Debug)]
+pub struct TableBuilder {
+    table: TableDef,
+}
+
+impl CatalogBuilder {
+    pub fn new() -> Self {
+        Self::with_dialect(SqlDialect::default())
+    }
+
+    /// Create a builder for a dialect, whose default schema unqualified
+    /// names are in
+    pub fn with_dialect(dialect: SqlDialect) -> Self {
+        Self {
+            catalog: Catalog::with_default_schema(dialect.default_schema()),
+            dialect,
+        }
+    }
+
+    /// Add a table, defined by `define`; a table of the same name is replaced
+    pub fn table(mut self, name: &str, define: impl FnOnce(TableBuilder) -> TableBuilder) -> Self {
+        let table = define(TableBuilder {
+            table: TableDef::new(QualifiedName::parse(name)),
+        });
+        self.catalog.add_table(table.table);
+        self
+    }
+
+    /// Add an enum type with its labels, in order
+    pub fn enum_type<I, S>(mut self, name: &str, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.catalog.add_enum(EnumTypeDef {
+            name: name.to_string(),
+            values: values.into_iter().map(Into::into).collect(),
+        });
+        self
+    }
+
+    /// Add a view with the columns its query returns
+    pub fn view<I, S>(self, name: &str, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.add_view(name, columns, false)
+    }
+
+    /// Add a materialized view with the columns its query returns
+    pub fn materialized_view<I, S>(self, name: &str, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.add_view(name, columns, true)
+    }
+
+    fn add_view<I, S>(mut self, name: &str, columns: I, materialized: bool) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.catalog.add_view(ViewDef {
+            name: QualifiedName::parse(name),
+            columns: columns.into_iter().map(Into::into).collect(),
+            materialized,
+            dependencies: Vec::new(),
+            query: None,
+            indexes: Vec::new(),
+            origin: None,
+        });
+        self
+    }
+
+    /// Add a function signature (an overload with the same arguments is replaced)
+    pub fn function(mut self, sig: FunctionSig) -> Self {
+        self.catalog.add_function(sig);
+        self
+    }
+
+    /// Validate the definitions and return the catalog
+    ///
+    /// Runs the final pass of [`SchemaBuilder::build`]: foreign keys without
+    /// referenced columns reference the primary key of their table, and
+    /// missing tables and columns, impossible referential actions and bad
+    /// defaults are reported. The diagnostics have no position.
+    pub fn build(self) -> (Catalog, Vec<Diagnostic>) {
+        SchemaBuilder::from_catalog(self.catalog, self.dialect)
+            .with_foreign_key_checks(true)
+            .build()
+    }
+}
+
+impl Default for CatalogBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TableBuilder {
+    /// Add a column, adjusted by `define` (e.g. `|c| c.not_null()`)
+    pub fn column(
+        mut self,
+        name: &str,
+        data_type: SqlType,
+        define: impl FnOnce(ColumnDef) -> ColumnDef,
+    ) -> Self {
+        self.table
+            .add_column(define(ColumnDef::new(name, data_type)));
+        self
+    }
+
+    /// Set the table's PRIMARY KEY; call it after adding the key's columns,
+    /// which it marks as primary key columns and NOT NULL
+    pub fn primary_key<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let columns: Vec<String> = columns.into_iter().map(Into::into).collect();
+        for name in &columns {
+            if let Some(column) = self.table.columns.get_mut(name) {
+                column.is_primary_key = true;
+                column.nullable = false;
+            }
+        }
+        self.table.primary_key = Some(PrimaryKeyDef {
+            name: None,
+            columns,
+        });
+        self
+    }
+
+    /// Add a FOREIGN KEY from `columns` to `references_columns` of
+    /// `references_table`; no referenced columns means its primary key
+    pub fn foreign_key<I, S, J, T>(
+        self,
+        columns: I,
+        references_table: &str,
+        references_columns: J,
+    ) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+        J: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.foreign_key_def(ForeignKeyDef {
+            name: None,
+            columns: columns.into_iter().map(Into::into).collect(),
+            references_table: QualifiedName::parse(references_table),
+            references_columns: references_columns.into_iter().map(Into::into).collect(),
+            on_delete: ReferentialAction::NoAction,
+            on_update: ReferentialAction::NoAction,
+            origin: None,
+        })
+    }
+
+    /// Add a foreign key defined in full (name, referential actions)
+    pub fn foreign_key_def(mut self, foreign_key: ForeignKeyDef) -> Self {
+        self.table.foreign_keys.push(foreign_key);
+        self
+    }
+
+    /// Add a UNIQUE constraint
+    pub fn unique<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.table.unique_constraints.push(UniqueConstraintDef {
+            name: None,
+            columns: columns.into_iter().map(Into::into).collect(),
+        });
+        self
+    }
+
+    /// Add a secondary index
+    pub fn index<I, S>(mut self, name: &str, columns: I, unique: bool) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.table.indexes.push(IndexDef {
+            name: name.to_string(),
+            columns: columns.into_iter().map(Into::into).collect(),
+            unique,
+        });
+        self
+    }
+
+    /// Add a CHECK constraint, as SQL expression text
+    pub fn check(mut self, expression: &str) -> Self {
+        self.table.check_constraints.push(CheckConstraintDef {
+            name: None,
+            expression: expression.to_string(),
+        });
+        self
+    }
+
+    /// Set the table comment; a `DEPRECATED: note` comment also marks the
+    /// table deprecated
+    pub fn comment(mut self, comment: &str) -> Self {
+        self.table.set_comment(Some(comment.to_string()));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::DiagnosticKind;
+
+    #[test]
+    fn test_build_validates_references() {
+        let (catalog, diagnostics) = CatalogBuilder::new()
+            .table("users", |t| {
+                t.column("id", SqlType::Integer, |c| c)
+                    .column("email", SqlType::Text, |c| c.not_null())
+                    .primary_key(["id"])
+            })
+            .table("orders", |t| {
+                t.column("id", SqlType::Integer, |c| c.primary_key())
+                    .column("user_id", SqlType::Integer, |c| c.not_null())
+                    .foreign_key(["user_id"], "users", Vec::<String>::new())
+                    .foreign_key(["coupon_id"], "coupons", ["id"])
+                    .foreign_key(["id"], "users", ["uid"])
+                    .unique(["number"])
+            })
+            .build();
+
+        let users = catalog.get_table("users").unwrap();
+        assert!(!users.get_column("id").unwrap().nullable);
+        let orders = catalog.get_table("orders").unwrap();
+        assert_eq!(orders.foreign_keys[0].references_columns, ["id"]);
+
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "FOREIGN KEY on 'orders' references column 'coupon_id' which does not exist in the table",
+                "FOREIGN KEY on 'orders' references table 'coupons' which was not found in schema",
+                "FOREIGN KEY on 'orders' references column 'users.uid' which does not exist",
+                "UNIQUE constraint on 'orders' references column 'number' which does not exist in the table",
+            ]
+        );
+        assert_eq!(diagnostics[1].kind, DiagnosticKind::TableNotFound);
+    }
+}
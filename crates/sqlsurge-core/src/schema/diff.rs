@@ -0,0 +1,248 @@
+//! Differences between two catalogs
+//!
+//! Two schemas with the same tables and columns can still differ in column
+//! order: a column added by ALTER TABLE comes after the existing ones, while
+//! a freshly created table lists it where its CREATE TABLE does. The order
+//! is what `SELECT *` returns and what `INSERT INTO t SELECT * FROM s` and
+//! `INSERT INTO t VALUES (...)` match values against, so comparing
+//! environments (migrations vs. a dump, staging vs. production) should
+//! report it.
+//!
+//! Column types are compared in their canonical form
+//! ([`SqlType::canonical`]): a dump spells `varchar(255)` as `character
+//! varying(255)` and `serial` as `integer DEFAULT nextval('t_id_seq')`,
+//! which are the same definitions.
+
+use serde::Serialize;
+
+use super::catalog::{Catalog, ColumnDef, QualifiedName, TableDef};
+use crate::dialect::SqlDialect;
+use crate::types::SqlType;
+
+/// A table whose shared columns are in a different order in two catalogs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ColumnOrderDifference {
+    pub table: QualifiedName,
+    /// Columns of the table in the first catalog, in table order
+    pub left: Vec<String>,
+    /// Columns of the table in the second catalog, in table order
+    pub right: Vec<String>,
+}
+
+/// A column whose definition differs in two catalogs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ColumnTypeDifference {
+    pub table: QualifiedName,
+    pub column: String,
+    /// Type of the column in the first catalog, with the sequence its
+    /// values come from
+    pub left: String,
+    /// Type of the column in the second catalog, likewise
+    pub right: String,
+}
+
+/// Tables of both catalogs whose common columns are ordered differently
+///
+/// Columns only one side has are listed but don't count as a difference
+/// in order; tables only one side has are skipped.
+pub fn column_order_differences(left: &Catalog, right: &Catalog) -> Vec<ColumnOrderDifference> {
+    let mut differences = Vec::new();
+    for (schema_name, schema) in &left.schemas {
+        let Some(other_schema) = right.schemas.get(schema_name) else {
+            continue;
+        };
+        for (table_name, table) in &schema.tables {
+            let Some(other) = other_schema.tables.get(table_name) else {
+                continue;
+            };
+            if shared_order(table, other) != shared_order(other, table) {
+                differences.push(ColumnOrderDifference {
+                    table: table.name.clone(),
+                    left: column_names(table),
+                    right: column_names(other),
+                });
+            }
+        }
+    }
+    differences
+}
+
+/// Columns of tables in both catalogs whose types or value sequences differ
+///
+/// Types are compared in their canonical form for `dialect`, and a SERIAL
+/// column equals an integer column defaulting to `nextval` of the sequence
+/// SERIAL would create ([`ColumnDef::value_sequence`]). Other defaults,
+/// nullability and constraints aren't compared.
+pub fn column_type_differences(
+    left: &Catalog,
+    right: &Catalog,
+    dialect: SqlDialect,
+) -> Vec<ColumnTypeDifference> {
+    let mut differences = Vec::new();
+    for (schema_name, schema) in &left.schemas {
+        let Some(other_schema) = right.schemas.get(schema_name) else {
+            continue;
+        };
+        for (table_name, table) in &schema.tables {
+            let Some(other) = other_schema.tables.get(table_name) else {
+                continue;
+            };
+            for column in table.ordered_columns() {
+                let Some(other_column) = other.get_column(&column.name) else {
+                    continue;
+                };
+                let definition = |column: &ColumnDef| {
+                    (
+                        column.data_type.canonical(dialect),
+                        column.value_sequence(table_name),
+                    )
+                };
+                let (left_type, left_sequence) = definition(column);
+                let (right_type, right_sequence) = definition(other_column);
+                if left_type != right_type || left_sequence != right_sequence {
+                    differences.push(ColumnTypeDifference {
+                        table: table.name.clone(),
+                        column: column.name.clone(),
+                        left: describe(&left_type, left_sequence.as_deref(), dialect),
+                        right: describe(&right_type, right_sequence.as_deref(), dialect),
+                    });
+                }
+            }
+        }
+    }
+    differences
+}
+
+/// `integer DEFAULT nextval('users_id_seq')`, or `integer AUTO_INCREMENT` under
+/// MySQL, which has no sequences
+fn describe(data_type: &SqlType, sequence: Option<&str>, dialect: SqlDialect) -> String {
+    match (sequence, dialect) {
+        (Some(_), SqlDialect::MySQL) => format!("{} AUTO_INCREMENT", data_type.display_name()),
+        (Some(sequence), SqlDialect::PostgreSQL) => format!(
+            "{} DEFAULT nextval('{}')",
+            data_type.display_name(),
+            sequence
+        ),
+        (None, _) => data_type.display_name(),
+    }
+}
+
+/// Lowercased names of the columns of `table` that `other` also has, in order
+fn shared_order(table: &TableDef, other: &TableDef) -> Vec<String> {
+    table
+        .column_names()
+        .into_iter()
+        .filter(|name| other.column_exists(name))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn column_names(table: &TableDef) -> Vec<String> {
+    table.column_names().into_iter().map(String::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::SchemaBuilder;
+
+    fn catalog(sql: &str) -> Catalog {
+        let mut builder = SchemaBuilder::new();
+        builder.parse(sql).unwrap();
+        builder.build().0
+    }
+
+    #[test]
+    fn test_added_column_differs_from_created_column() {
+        let migrated = catalog(
+            "CREATE TABLE users (id INT, email TEXT);
+             ALTER TABLE users ADD COLUMN name TEXT;
+             CREATE TABLE orders (id INT, total INT);",
+        );
+        let dumped = catalog(
+            "CREATE TABLE users (id INT, name TEXT, email TEXT);
+             CREATE TABLE orders (id INT, total INT, note TEXT);",
+        );
+
+        let differences = column_order_differences(&migrated, &dumped);
+        assert_eq!(
+            differences,
+            vec![ColumnOrderDifference {
+                table: QualifiedName::new("users"),
+                left: vec!["id".into(), "email".into(), "name".into()],
+                right: vec!["id".into(), "name".into(), "email".into()],
+            }]
+        );
+        assert!(column_order_differences(&dumped, &dumped).is_empty());
+    }
+
+    #[test]
+    fn test_type_spellings_compare_equal() {
+        let migrated = catalog(
+            "CREATE TABLE users (
+                 id SERIAL PRIMARY KEY,
+                 email VARCHAR(255),
+                 seen TIMESTAMPTZ,
+                 score NUMERIC(8),
+                 code CHAR,
+                 mood public.Mood,
+                 age INT
+             );",
+        );
+        let dumped = catalog(
+            "CREATE TABLE users (
+                 id integer DEFAULT nextval('public.users_id_seq'::regclass) NOT NULL,
+                 email character varying(255),
+                 seen timestamp(6) with time zone,
+                 score numeric(8,0),
+                 code character(1),
+                 mood mood,
+                 age bigint
+             );",
+        );
+
+        let differences = column_type_differences(&migrated, &dumped, SqlDialect::PostgreSQL);
+        assert_eq!(
+            differences,
+            vec![ColumnTypeDifference {
+                table: QualifiedName::new("users"),
+                column: "age".into(),
+                left: "integer".into(),
+                right: "bigint".into(),
+            }]
+        );
+
+        // An integer without the sequence default isn't SERIAL
+        let plain = catalog("CREATE TABLE users (id INTEGER);");
+        let differences = column_type_differences(&migrated, &plain, SqlDialect::PostgreSQL);
+        assert_eq!(differences.len(), 1);
+        assert_eq!(
+            differences[0].left,
+            "integer DEFAULT nextval('users_id_seq')"
+        );
+        assert_eq!(differences[0].right, "integer");
+    }
+
+    #[test]
+    fn test_auto_increment_differs_under_mysql() {
+        let catalog = |sql: &str| {
+            let mut builder = SchemaBuilder::with_dialect(SqlDialect::MySQL);
+            builder.parse(sql).unwrap();
+            builder.build().0
+        };
+        let migrated = catalog("CREATE TABLE users (id INT AUTO_INCREMENT PRIMARY KEY);");
+        let dumped = catalog("CREATE TABLE users (id INT NOT NULL, PRIMARY KEY (id));");
+
+        let differences = column_type_differences(&migrated, &dumped, SqlDialect::MySQL);
+        assert_eq!(
+            differences,
+            vec![ColumnTypeDifference {
+                table: QualifiedName::new("users"),
+                column: "id".into(),
+                left: "integer AUTO_INCREMENT".into(),
+                right: "integer".into(),
+            }]
+        );
+        assert!(column_type_differences(&migrated, &migrated, SqlDialect::MySQL).is_empty());
+    }
+}
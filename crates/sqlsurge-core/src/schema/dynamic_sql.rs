@@ -0,0 +1,246 @@
+//! Dynamic SQL built by string concatenation (W0014, opt-in)
+//!
+//! `EXECUTE 'SELECT * FROM ' || table_name` in a DO block or function body
+//! runs whatever the variable holds, which is open to SQL injection unless
+//! the value is quoted. Function bodies are dollar-quoted strings the parser
+//! doesn't look into, so their contents are scanned with a small tokenizer:
+//! an `EXECUTE` whose argument concatenates (`||`) anything but string
+//! literals and `quote_ident()`/`quote_literal()`/`quote_nullable()` calls,
+//! or `format()` calls without `%s`, is flagged.
+//!
+//! This is a heuristic, not PL/pgSQL analysis, so the rule is a hint.
+
+use crate::error::{Diagnostic, DiagnosticKind, Span};
+use crate::json::offset_to_line_col;
+
+use super::builder::{find_dollar_tag_end, is_identifier_byte, split_sql_statements};
+
+/// Functions whose result is safe to concatenate into a statement
+const QUOTING_FUNCTIONS: &[&str] = &["quote_ident", "quote_literal", "quote_nullable"];
+
+/// Scan the dollar-quoted strings of a schema source
+pub(super) fn check(sql: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for statement in split_sql_statements(sql) {
+        let offset = statement.as_ptr() as usize - sql.as_ptr() as usize;
+        for token in tokenize(statement, offset) {
+            if let Token::DollarString { body, offset } = token {
+                check_body(sql, body, offset, &mut diagnostics);
+            }
+        }
+    }
+    diagnostics
+}
+
+/// Check a dollar-quoted body, and the bodies nested in it
+fn check_body(sql: &str, body: &str, body_offset: usize, diagnostics: &mut Vec<Diagnostic>) {
+    let tokens = tokenize(body, body_offset);
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Word { text, offset } if text.eq_ignore_ascii_case("execute") => {
+                if let Some(operand) = unsafe_operand(&tokens[i + 1..]) {
+                    diagnostics.push(diagnostic(sql, *offset, operand));
+                }
+            }
+            Token::DollarString { body, offset } => check_body(sql, body, *offset, diagnostics),
+            _ => {}
+        }
+    }
+}
+
+/// The first concatenated operand of an EXECUTE argument that isn't quoted,
+/// with its first word if it has one
+///
+/// The argument ends at `;`, `USING` or `INTO` outside parentheses.
+fn unsafe_operand<'a>(tokens: &[Token<'a>]) -> Option<Option<&'a str>> {
+    let mut operands: Vec<Vec<&Token>> = vec![Vec::new()];
+    let mut depth = 0usize;
+    for token in tokens {
+        match token {
+            Token::Semicolon if depth == 0 => break,
+            Token::Word { text, .. }
+                if depth == 0
+                    && (text.eq_ignore_ascii_case("using")
+                        || text.eq_ignore_ascii_case("into")) =>
+            {
+                break
+            }
+            Token::Concat if depth == 0 => {
+                operands.push(Vec::new());
+                continue;
+            }
+            Token::LParen => depth += 1,
+            Token::RParen => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        operands.last_mut().unwrap().push(token);
+    }
+    if operands.len() < 2 {
+        return None;
+    }
+    operands
+        .iter()
+        .find(|operand| !is_quoted(operand))
+        .map(|operand| operand.iter().find_map(|token| token.text()))
+}
+
+/// A string literal, a quoting function call or a `format()` without `%s`
+fn is_quoted(operand: &[&Token]) -> bool {
+    match operand {
+        [Token::String(_)] | [Token::DollarString { .. }] => true,
+        [Token::Word { text, .. }, Token::LParen, rest @ ..] => {
+            if QUOTING_FUNCTIONS
+                .iter()
+                .any(|f| text.eq_ignore_ascii_case(f))
+            {
+                return true;
+            }
+            text.eq_ignore_ascii_case("format")
+                && matches!(rest.first(), Some(Token::String(template)) if !has_string_placeholder(template))
+        }
+        _ => false,
+    }
+}
+
+/// Whether a format() template inserts an argument unquoted (`%s`, `%1$s`)
+fn has_string_placeholder(template: &str) -> bool {
+    template.contains("%s") || template.contains("$s")
+}
+
+fn diagnostic(sql: &str, offset: usize, operand: Option<&str>) -> Diagnostic {
+    const EXECUTE_LEN: usize = "EXECUTE".len();
+    let (line, column) = offset_to_line_col(sql, offset);
+    let mut span = Span::with_location(line, column, EXECUTE_LEN);
+    span.offset = offset;
+    Diagnostic::hint(
+        DiagnosticKind::DynamicSqlConcatenation,
+        format!(
+            "EXECUTE builds a statement by concatenating {}, which is open to SQL injection",
+            match operand {
+                Some(operand) => format!("'{}'", operand),
+                None => "an unquoted expression".to_string(),
+            }
+        ),
+    )
+    .with_span(span)
+    .with_help(
+        "Build the statement with format() and %I for identifiers or %L for values \
+         (or quote_ident()/quote_literal()), and pass values with EXECUTE ... USING",
+    )
+}
+
+/// Token of a function body, as far as the EXECUTE scan needs
+#[derive(Debug)]
+enum Token<'a> {
+    Word {
+        text: &'a str,
+        offset: usize,
+    },
+    /// Contents of a single-quoted string
+    String(&'a str),
+    /// Contents of a dollar-quoted string and their offset in the source
+    DollarString {
+        body: &'a str,
+        offset: usize,
+    },
+    Concat,
+    LParen,
+    RParen,
+    Semicolon,
+    Other,
+}
+
+impl<'a> Token<'a> {
+    fn text(&self) -> Option<&'a str> {
+        match self {
+            Token::Word { text, .. } => Some(text),
+            _ => None,
+        }
+    }
+}
+
+/// Split `text` (found at `base` in the source) into tokens, skipping
+/// whitespace and comments
+fn tokenize(text: &str, base: usize) -> Vec<Token<'_>> {
+    let bytes = text.as_bytes();
+    let len = bytes.len();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < len {
+        let start = i;
+        match bytes[i] {
+            b if b.is_ascii_whitespace() => i += 1,
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < len && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = text[i + 2..].find("*/").map_or(len, |end| i + 2 + end + 2);
+            }
+            b'\'' => {
+                i += 1;
+                while i < len {
+                    if bytes[i] == b'\'' {
+                        if bytes.get(i + 1) == Some(&b'\'') {
+                            i += 2;
+                            continue;
+                        }
+                        break;
+                    }
+                    i += 1;
+                }
+                tokens.push(Token::String(&text[start + 1..i.min(len)]));
+                i += 1;
+            }
+            b'$' if find_dollar_tag_end(text, i).is_some() => {
+                let tag_end = find_dollar_tag_end(text, i).unwrap();
+                let tag = &text[i..=tag_end];
+                let body_start = tag_end + 1;
+                let body_end = text[body_start..]
+                    .find(tag)
+                    .map_or(len, |end| body_start + end);
+                tokens.push(Token::DollarString {
+                    body: &text[body_start..body_end],
+                    offset: base + body_start,
+                });
+                i = (body_end + tag.len()).min(len);
+            }
+            b'"' => {
+                // Quoted identifier
+                i = text[i + 1..].find('"').map_or(len, |end| i + 1 + end + 1);
+                tokens.push(Token::Other);
+            }
+            b'|' if bytes.get(i + 1) == Some(&b'|') => {
+                tokens.push(Token::Concat);
+                i += 2;
+            }
+            b'(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            b')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            b';' => {
+                tokens.push(Token::Semicolon);
+                i += 1;
+            }
+            b if is_identifier_byte(b) || !b.is_ascii() => {
+                while i < len && (is_identifier_byte(bytes[i]) || !bytes[i].is_ascii()) {
+                    i += 1;
+                }
+                tokens.push(Token::Word {
+                    text: &text[start..i],
+                    offset: base + start,
+                });
+            }
+            _ => {
+                tokens.push(Token::Other);
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
@@ -2,9 +2,22 @@
 
 mod builder;
 mod catalog;
+mod catalog_builder;
+mod diff;
+mod dynamic_sql;
+#[cfg(feature = "sqlx")]
+mod sqlx;
 
 pub use builder::SchemaBuilder;
+pub(crate) use builder::{is_copy_from_stdin, split_sql_statements};
 pub use catalog::{
-    Catalog, CheckConstraintDef, ColumnDef, DefaultValue, EnumTypeDef, ForeignKeyDef, IdentityKind,
-    PrimaryKeyDef, QualifiedName, Schema, TableDef, UniqueConstraintDef, ViewDef,
+    Catalog, CheckConstraintDef, ColumnDef, DefaultValue, EnumTypeDef, ForeignKeyDef, FunctionSig,
+    GeneratedColumn, IdentityKind, IndexDef, NameRef, PrimaryKeyDef, QualifiedName,
+    ReferentialAction, Schema, SchemaOrigin, TableDef, UniqueConstraintDef, ViewDef,
 };
+pub use catalog_builder::{CatalogBuilder, TableBuilder};
+pub use diff::{
+    column_order_differences, column_type_differences, ColumnOrderDifference, ColumnTypeDifference,
+};
+#[cfg(feature = "sqlx")]
+pub use sqlx::SqlxMetadataError;
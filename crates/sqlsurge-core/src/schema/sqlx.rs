@@ -0,0 +1,795 @@
+//! Catalog from sqlx offline metadata (`.sqlx/` or `sqlx-data.json`)
+//!
+//! `cargo sqlx prepare` stores, per query, the result columns the database
+//! described: their names, types and nullability. That isn't a schema, but
+//! a query reading a single table by column name (`SELECT id, email FROM
+//! users`, `SELECT * FROM users`, or `INSERT/UPDATE/DELETE ... RETURNING`)
+//! tells which columns the table has and their types. Such queries are
+//! turned into synthetic table definitions, merged over all queries; joins,
+//! CTEs and computed columns are skipped. Columns come in the order the
+//! queries list them, so only a `SELECT *` gives the table's own order.
+
+use indexmap::IndexMap;
+use serde::Deserialize;
+use sqlparser::ast::{
+    Delete, Expr, FromTable, Insert, ObjectName, SelectItem, SetExpr, Statement, TableFactor,
+    TableWithJoins,
+};
+use sqlparser::parser::Parser;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::dialect::SqlDialect;
+use crate::types::SqlType;
+
+use super::catalog::{Catalog, ColumnDef, SchemaOrigin, TableDef};
+use crate::idents::object_name_to_qualified;
+
+/// File holding all queries in sqlx before 0.7
+const LEGACY_FILE: &str = "sqlx-data.json";
+
+/// Error of [`Catalog::from_sqlx_metadata`]
+#[derive(Debug, thiserror::Error)]
+pub enum SqlxMetadataError {
+    #[error("failed to read '{path}': {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("'{path}' is not sqlx query metadata: {source}")]
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("'{0}' is sqlx metadata for {1}, which sqlsurge doesn't support")]
+    UnsupportedDatabase(PathBuf, String),
+    #[error(
+        "no sqlx query metadata in '{0}'; expected the query-*.json files of \
+         `cargo sqlx prepare` or a sqlx-data.json"
+    )]
+    NoQueries(PathBuf),
+    #[error(
+        "none of the {queries} queries in '{path}' reads columns of a single table, so no \
+         table definitions can be derived from them; pass the schema as SQL instead \
+         (e.g. from the migrations or `pg_dump --schema-only`)"
+    )]
+    NoTables { path: PathBuf, queries: usize },
+}
+
+/// A query of the metadata, as `cargo sqlx prepare` writes it
+#[derive(Debug, Deserialize)]
+struct QueryData {
+    /// Database product (absent before sqlx 0.7, where the file names it)
+    #[serde(default)]
+    db_name: Option<String>,
+    query: String,
+    describe: Describe,
+}
+
+#[derive(Debug, Deserialize)]
+struct Describe {
+    columns: Vec<DescribeColumn>,
+    /// Per column: `Some(false)` if it is never NULL, `None` if unknown
+    #[serde(default)]
+    nullable: Vec<Option<bool>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DescribeColumn {
+    name: String,
+    /// A type name (`"Int4"`) or, for user-defined and MySQL types, an object
+    type_info: serde_json::Value,
+}
+
+impl Catalog {
+    /// Build a catalog from sqlx offline metadata
+    ///
+    /// `dir` is a `.sqlx` directory of `query-*.json` files (sqlx 0.7 and
+    /// later) or a `sqlx-data.json` file, or a directory containing one.
+    /// Tables are derived from the queries reading a single table (see the
+    /// module documentation); it is an error if there are none.
+    pub fn from_sqlx_metadata(dir: &Path) -> Result<Catalog, SqlxMetadataError> {
+        let queries = read_queries(dir)?;
+        if queries.is_empty() {
+            return Err(SqlxMetadataError::NoQueries(dir.to_path_buf()));
+        }
+
+        let mut database = None;
+        let mut tables: IndexMap<String, TableDef> = IndexMap::new();
+        for (path, query) in &queries {
+            let db = Database::from_name(query.db_name.as_deref().unwrap_or("PostgreSQL"))
+                .ok_or_else(|| {
+                    SqlxMetadataError::UnsupportedDatabase(
+                        path.clone(),
+                        query.db_name.clone().unwrap_or_default(),
+                    )
+                })?;
+            database.get_or_insert(db);
+            add_query_columns(&mut tables, db, path, query);
+        }
+        if tables.is_empty() {
+            return Err(SqlxMetadataError::NoTables {
+                path: dir.to_path_buf(),
+                queries: queries.len(),
+            });
+        }
+
+        let dialect = database.map_or(SqlDialect::PostgreSQL, Database::dialect);
+        let mut catalog = Catalog::with_default_schema(dialect.default_schema());
+        for table in tables.into_values() {
+            catalog.add_table(table);
+        }
+        Ok(catalog)
+    }
+}
+
+/// The queries of the metadata with the file each is in, in file name order
+fn read_queries(dir: &Path) -> Result<Vec<(PathBuf, QueryData)>, SqlxMetadataError> {
+    if !dir.is_dir() {
+        return read_legacy_file(dir);
+    }
+    let entries = fs::read_dir(dir).map_err(|source| SqlxMetadataError::Io {
+        path: dir.to_path_buf(),
+        source,
+    })?;
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    let mut queries = Vec::new();
+    for path in paths {
+        if path.file_name().is_some_and(|name| name == LEGACY_FILE) {
+            queries.extend(read_legacy_file(&path)?);
+        } else {
+            let query =
+                serde_json::from_str(&read(&path)?).map_err(|source| SqlxMetadataError::Json {
+                    path: path.clone(),
+                    source,
+                })?;
+            queries.push((path, query));
+        }
+    }
+    Ok(queries)
+}
+
+/// The queries of a `sqlx-data.json`: `{"db": "PostgreSQL", "<hash>": {...}}`
+fn read_legacy_file(path: &Path) -> Result<Vec<(PathBuf, QueryData)>, SqlxMetadataError> {
+    #[derive(Deserialize)]
+    struct LegacyFile {
+        db: String,
+        #[serde(flatten)]
+        queries: IndexMap<String, QueryData>,
+    }
+
+    let file: LegacyFile =
+        serde_json::from_str(&read(path)?).map_err(|source| SqlxMetadataError::Json {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    Ok(file
+        .queries
+        .into_values()
+        .map(|mut query| {
+            query.db_name.get_or_insert_with(|| file.db.clone());
+            (path.to_path_buf(), query)
+        })
+        .collect())
+}
+
+fn read(path: &Path) -> Result<String, SqlxMetadataError> {
+    fs::read_to_string(path).map_err(|source| SqlxMetadataError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Add the columns a query reads from a single table to that table
+fn add_query_columns(
+    tables: &mut IndexMap<String, TableDef>,
+    db: Database,
+    path: &Path,
+    query: &QueryData,
+) {
+    let parser_dialect = db.dialect().parser_dialect();
+    let Ok(statements) = Parser::parse_sql(parser_dialect.as_ref(), &query.query) else {
+        return;
+    };
+    let [statement] = statements.as_slice() else {
+        return;
+    };
+    let Some((table_name, items)) = single_table_output(statement) else {
+        return;
+    };
+    let Some(names) = column_names(db, items, &query.describe.columns) else {
+        return;
+    };
+    if names.iter().all(Option::is_none) {
+        return;
+    }
+
+    let name = object_name_to_qualified(table_name);
+    let table = tables
+        .entry(name.to_string().to_lowercase())
+        .or_insert_with(|| {
+            let mut table = TableDef::new(name);
+            table.origin = Some(SchemaOrigin {
+                file: path.display().to_string(),
+                line: 1,
+                column: 1,
+            });
+            table
+        });
+    for (idx, column_name) in names.into_iter().enumerate() {
+        let Some(column_name) = column_name else {
+            continue;
+        };
+        let not_null = query.describe.nullable.get(idx) == Some(&Some(false));
+        if let Some(existing) = table
+            .columns
+            .values_mut()
+            .find(|c| c.name.eq_ignore_ascii_case(&column_name))
+        {
+            existing.nullable &= !not_null;
+            continue;
+        }
+        let mut column = ColumnDef::new(
+            column_name,
+            type_from_info(db, &query.describe.columns[idx].type_info),
+        );
+        column.nullable = !not_null;
+        table.add_column(column);
+    }
+}
+
+/// The table a statement reads and the items of its output, if it reads
+/// exactly one table and nothing else
+fn single_table_output(statement: &Statement) -> Option<(&ObjectName, &[SelectItem])> {
+    match statement {
+        Statement::Query(query) if query.with.is_none() => match query.body.as_ref() {
+            SetExpr::Select(select) => match select.from.as_slice() {
+                [from] => Some((plain_table(from)?, &select.projection)),
+                _ => None,
+            },
+            _ => None,
+        },
+        Statement::Insert(Insert {
+            table_name,
+            returning: Some(items),
+            ..
+        }) => Some((table_name, items)),
+        Statement::Update {
+            table,
+            from: None,
+            returning: Some(items),
+            ..
+        } => Some((plain_table(table)?, items)),
+        Statement::Delete(Delete {
+            from: FromTable::WithFromKeyword(from) | FromTable::WithoutKeyword(from),
+            using: None,
+            returning: Some(items),
+            ..
+        }) => match from.as_slice() {
+            [from] => Some((plain_table(from)?, items)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn plain_table(from: &TableWithJoins) -> Option<&ObjectName> {
+    match &from.relation {
+        TableFactor::Table {
+            name, args: None, ..
+        } if from.joins.is_empty() => Some(name),
+        _ => None,
+    }
+}
+
+/// The table column behind each described output column, `None` for
+/// computed ones
+///
+/// Gives up (`None`) if the items can't be matched with the columns, e.g.
+/// with two wildcards.
+fn column_names(
+    db: Database,
+    items: &[SelectItem],
+    columns: &[DescribeColumn],
+) -> Option<Vec<Option<String>>> {
+    let is_wildcard = |item: &&SelectItem| {
+        matches!(
+            item,
+            SelectItem::Wildcard(_) | SelectItem::QualifiedWildcard(..)
+        )
+    };
+    let wildcards = items.iter().filter(is_wildcard).count();
+    let explicit = items.len() - wildcards;
+    let wildcard_width = match wildcards {
+        0 if explicit == columns.len() => 0,
+        1 => columns.len().checked_sub(explicit)?,
+        _ => return None,
+    };
+
+    let mut names = Vec::with_capacity(columns.len());
+    for item in items {
+        match item {
+            SelectItem::Wildcard(_) | SelectItem::QualifiedWildcard(..) => {
+                let described = &columns[names.len()..names.len() + wildcard_width];
+                names.extend(described.iter().map(|c| Some(c.name.clone())));
+            }
+            SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => {
+                names.push(column_reference(db, expr));
+            }
+        }
+    }
+    Some(names)
+}
+
+/// The column an output expression is, e.g. `email` or `u.email`
+///
+/// An alias names the output, not the column, so the column is taken from
+/// the expression as written; PostgreSQL folds unquoted names to lowercase.
+fn column_reference(db: Database, expr: &Expr) -> Option<String> {
+    let ident = match expr {
+        Expr::Identifier(ident) => ident,
+        Expr::CompoundIdentifier(idents) => idents.last()?,
+        Expr::Nested(inner) => return column_reference(db, inner),
+        _ => return None,
+    };
+    Some(match (db, ident.quote_style) {
+        (Database::Postgres, None) => ident.value.to_lowercase(),
+        _ => ident.value.clone(),
+    })
+}
+
+/// Database a metadata file was prepared against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Database {
+    Postgres,
+    MySql,
+}
+
+impl Database {
+    /// From the `db_name` of a query (`"PostgreSQL"`, `"MySQL"`)
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "postgresql" | "postgres" => Some(Database::Postgres),
+            "mysql" | "mariadb" => Some(Database::MySql),
+            _ => None,
+        }
+    }
+
+    fn dialect(self) -> SqlDialect {
+        match self {
+            Database::Postgres => SqlDialect::PostgreSQL,
+            Database::MySql => SqlDialect::MySQL,
+        }
+    }
+}
+
+/// sqlx names of PostgreSQL types (`PgType` variants); arrays are the element
+/// name followed by `Array` (`Int4Array`)
+const POSTGRES_TYPES: &[(&str, SqlType)] = &[
+    ("Bool", SqlType::Boolean),
+    ("Bytea", SqlType::Bytea),
+    ("Char", SqlType::Char { length: Some(1) }),
+    ("Name", SqlType::Text),
+    ("Int2", SqlType::SmallInt),
+    ("Int4", SqlType::Integer),
+    ("Int8", SqlType::BigInt),
+    ("Oid", SqlType::BigInt),
+    ("Text", SqlType::Text),
+    ("Bpchar", SqlType::Char { length: None }),
+    ("Varchar", SqlType::Varchar { length: None }),
+    ("Json", SqlType::Json),
+    ("Jsonb", SqlType::Jsonb),
+    ("Float4", SqlType::Real),
+    ("Float8", SqlType::DoublePrecision),
+    (
+        "Numeric",
+        SqlType::Decimal {
+            precision: None,
+            scale: None,
+        },
+    ),
+    (
+        "Money",
+        SqlType::Decimal {
+            precision: None,
+            scale: None,
+        },
+    ),
+    ("Date", SqlType::Date),
+    (
+        "Time",
+        SqlType::Time {
+            precision: None,
+            with_timezone: false,
+        },
+    ),
+    (
+        "Timetz",
+        SqlType::Time {
+            precision: None,
+            with_timezone: true,
+        },
+    ),
+    (
+        "Timestamp",
+        SqlType::Timestamp {
+            precision: None,
+            with_timezone: false,
+        },
+    ),
+    (
+        "Timestamptz",
+        SqlType::Timestamp {
+            precision: None,
+            with_timezone: true,
+        },
+    ),
+    ("Interval", SqlType::Interval),
+    ("Uuid", SqlType::Uuid),
+    ("Unknown", SqlType::Unknown),
+];
+
+/// sqlx names of MySQL column types (`ColumnType` variants)
+const MYSQL_TYPES: &[(&str, SqlType)] = &[
+    ("Tiny", SqlType::TinyInt),
+    ("Short", SqlType::SmallInt),
+    ("Int24", SqlType::MediumInt),
+    ("Long", SqlType::Integer),
+    ("LongLong", SqlType::BigInt),
+    ("Year", SqlType::SmallInt),
+    ("Float", SqlType::Real),
+    ("Double", SqlType::DoublePrecision),
+    (
+        "Decimal",
+        SqlType::Decimal {
+            precision: None,
+            scale: None,
+        },
+    ),
+    (
+        "NewDecimal",
+        SqlType::Decimal {
+            precision: None,
+            scale: None,
+        },
+    ),
+    ("VarChar", SqlType::Varchar { length: None }),
+    ("VarString", SqlType::Varchar { length: None }),
+    ("String", SqlType::Char { length: None }),
+    ("TinyBlob", SqlType::Bytea),
+    ("Blob", SqlType::Bytea),
+    ("MediumBlob", SqlType::Bytea),
+    ("LongBlob", SqlType::Bytea),
+    ("Json", SqlType::Json),
+    ("Date", SqlType::Date),
+    (
+        "Time",
+        SqlType::Time {
+            precision: None,
+            with_timezone: false,
+        },
+    ),
+    (
+        "Datetime",
+        SqlType::Timestamp {
+            precision: None,
+            with_timezone: false,
+        },
+    ),
+    (
+        "Timestamp",
+        SqlType::Timestamp {
+            precision: None,
+            with_timezone: false,
+        },
+    ),
+    ("Null", SqlType::Unknown),
+];
+
+/// MySQL character set number of binary data
+const MYSQL_BINARY_CHARSET: u64 = 63;
+
+/// The type of a sqlx type name; names not in the table are user-defined
+/// types
+fn type_from_name(db: Database, name: &str) -> SqlType {
+    let table = match db {
+        Database::Postgres => POSTGRES_TYPES,
+        Database::MySql => MYSQL_TYPES,
+    };
+    if let Some((_, ty)) = table.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)) {
+        return ty.clone();
+    }
+    match name.strip_suffix("Array") {
+        Some(element) if db == Database::Postgres && !element.is_empty() => {
+            SqlType::Array(Box::new(type_from_name(db, element)))
+        }
+        _ => SqlType::Custom(name.to_lowercase()),
+    }
+}
+
+/// The type of a described column's `type_info`
+///
+/// PostgreSQL types are a name or, for user-defined types, an object like
+/// `{"Custom": {"name": "mood", "kind": ...}}`; MySQL types are an object
+/// like `{"type": "Long", "flags": "NOT_NULL | UNSIGNED", "max_size": 11}`.
+/// Anything else is taken as a user-defined type.
+fn type_from_info(db: Database, info: &serde_json::Value) -> SqlType {
+    use serde_json::Value;
+
+    let Value::Object(object) = info else {
+        return match info.as_str() {
+            Some(name) => type_from_name(db, name),
+            None => SqlType::Custom(info.to_string()),
+        };
+    };
+    if let Some(custom) = object.get("Custom") {
+        return match custom.get("kind") {
+            Some(Value::Object(kind)) if kind.contains_key("Array") => {
+                SqlType::Array(Box::new(type_from_info(db, &kind["Array"])))
+            }
+            Some(Value::Object(kind)) if kind.contains_key("Domain") => {
+                type_from_info(db, &kind["Domain"])
+            }
+            _ => SqlType::Custom(
+                custom
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown")
+                    .to_string(),
+            ),
+        };
+    }
+    if let Some(name) = object.get("DeclareWithName").and_then(Value::as_str) {
+        return SqlType::Custom(name.to_string());
+    }
+    if let Some(oid) = object.get("DeclareWithOid") {
+        return SqlType::Custom(format!("oid {}", oid));
+    }
+    let Some(name) = object.get("type").and_then(Value::as_str) else {
+        return SqlType::Custom(info.to_string());
+    };
+
+    let ty = type_from_name(db, name);
+    let charset = object.get("char_set").and_then(Value::as_u64);
+    let ty = match ty {
+        // TEXT columns are blobs with a character set
+        SqlType::Bytea if charset.is_some_and(|c| c != MYSQL_BINARY_CHARSET) => SqlType::Text,
+        // TINYINT(1)
+        SqlType::TinyInt if object.get("max_size").and_then(Value::as_u64) == Some(1) => {
+            SqlType::Boolean
+        }
+        ty => ty,
+    };
+    let unsigned = object
+        .get("flags")
+        .and_then(Value::as_str)
+        .is_some_and(|flags| flags.contains("UNSIGNED"));
+    if unsigned
+        && matches!(
+            ty,
+            SqlType::TinyInt
+                | SqlType::SmallInt
+                | SqlType::MediumInt
+                | SqlType::Integer
+                | SqlType::BigInt
+        )
+    {
+        SqlType::Unsigned(Box::new(ty))
+    } else {
+        ty
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn metadata_dir(test: &str, files: &[(&str, serde_json::Value)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sqlsurge-sqlx-{}", test));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (name, contents) in files {
+            fs::write(dir.join(name), contents.to_string()).unwrap();
+        }
+        dir
+    }
+
+    fn query(
+        sql: &str,
+        columns: &[(&str, serde_json::Value)],
+        nullable: &[bool],
+    ) -> serde_json::Value {
+        json!({
+            "db_name": "PostgreSQL",
+            "query": sql,
+            "describe": {
+                "columns": columns
+                    .iter()
+                    .enumerate()
+                    .map(|(ordinal, (name, ty))| json!({"ordinal": ordinal, "name": name, "type_info": ty}))
+                    .collect::<Vec<_>>(),
+                "parameters": {"Left": []},
+                "nullable": nullable,
+            },
+            "hash": "0",
+        })
+    }
+
+    #[test]
+    fn test_postgres_type_names() {
+        let db = Database::Postgres;
+        assert_eq!(type_from_name(db, "Int4"), SqlType::Integer);
+        assert_eq!(type_from_name(db, "int8"), SqlType::BigInt);
+        assert_eq!(
+            type_from_name(db, "Timestamptz"),
+            SqlType::Timestamp {
+                precision: None,
+                with_timezone: true
+            }
+        );
+        assert_eq!(
+            type_from_name(db, "TextArray"),
+            SqlType::Array(Box::new(SqlType::Text))
+        );
+        assert_eq!(
+            type_from_name(db, "Inet"),
+            SqlType::Custom("inet".to_string())
+        );
+
+        let mood = json!({"Custom": {"name": "mood", "kind": {"Enum": ["sad", "happy"]}}});
+        assert_eq!(
+            type_from_info(db, &mood),
+            SqlType::Custom("mood".to_string())
+        );
+        let moods = json!({"Custom": {"name": "_mood", "kind": {"Array": mood}}});
+        assert_eq!(
+            type_from_info(db, &moods),
+            SqlType::Array(Box::new(SqlType::Custom("mood".to_string())))
+        );
+        let email = json!({"Custom": {"name": "email", "kind": {"Domain": "Text"}}});
+        assert_eq!(type_from_info(db, &email), SqlType::Text);
+        assert_eq!(
+            type_from_info(db, &json!({"DeclareWithOid": 16385})),
+            SqlType::Custom("oid 16385".to_string())
+        );
+    }
+
+    #[test]
+    fn test_mysql_type_names() {
+        let db = Database::MySql;
+        let info = |ty: &str, flags: &str, char_set: u64, max_size: u64| json!({"type": ty, "flags": flags, "char_set": char_set, "max_size": max_size});
+        assert_eq!(
+            type_from_info(db, &info("LongLong", "NOT_NULL | UNSIGNED", 63, 20)),
+            SqlType::Unsigned(Box::new(SqlType::BigInt))
+        );
+        assert_eq!(
+            type_from_info(db, &info("Blob", "", 224, 262140)),
+            SqlType::Text
+        );
+        assert_eq!(
+            type_from_info(db, &info("Blob", "BINARY", 63, 65535)),
+            SqlType::Bytea
+        );
+        assert_eq!(
+            type_from_info(db, &info("Tiny", "", 63, 1)),
+            SqlType::Boolean
+        );
+        assert_eq!(
+            type_from_info(db, &info("Geometry", "", 63, 0)),
+            SqlType::Custom("geometry".to_string())
+        );
+    }
+
+    #[test]
+    fn test_tables_from_single_table_queries() {
+        let dir = metadata_dir(
+            "tables",
+            &[
+                (
+                    "query-1.json",
+                    query(
+                        "SELECT * FROM users WHERE id = $1",
+                        &[("id", json!("Int4")), ("email", json!("Text"))],
+                        &[false, true],
+                    ),
+                ),
+                (
+                    "query-2.json",
+                    query(
+                        "INSERT INTO orders (user_id) VALUES ($1) RETURNING id, user_id AS owner, total * 2",
+                        &[("id", json!("Int8")), ("owner", json!("Int4")), ("?column?", json!("Numeric"))],
+                        &[false, false, true],
+                    ),
+                ),
+                (
+                    "query-3.json",
+                    query(
+                        "SELECT u.email, o.id FROM users u JOIN orders o ON o.user_id = u.id",
+                        &[("email", json!("Text")), ("id", json!("Int8"))],
+                        &[true, false],
+                    ),
+                ),
+            ],
+        );
+
+        let catalog = Catalog::from_sqlx_metadata(&dir).unwrap();
+        let users = catalog.get_table("users").unwrap();
+        assert_eq!(users.column_names(), vec!["id", "email"]);
+        assert!(!users.get_column("id").unwrap().nullable);
+        assert!(users.get_column("email").unwrap().nullable);
+
+        // Computed columns are skipped; aliases name the output, not the column
+        let orders = catalog.get_table("orders").unwrap();
+        assert_eq!(orders.column_names(), vec!["id", "user_id"]);
+        assert_eq!(orders.get_column("id").unwrap().data_type, SqlType::BigInt);
+    }
+
+    #[test]
+    fn test_metadata_without_tables_is_an_error() {
+        let dir = metadata_dir(
+            "no-tables",
+            &[(
+                "query-1.json",
+                query(
+                    "SELECT count(*) FROM users",
+                    &[("count", json!("Int8"))],
+                    &[true],
+                ),
+            )],
+        );
+        let err = Catalog::from_sqlx_metadata(&dir).unwrap_err();
+        assert!(matches!(
+            err,
+            SqlxMetadataError::NoTables { queries: 1, .. }
+        ));
+        assert!(err.to_string().contains("pass the schema as SQL"));
+
+        let empty = metadata_dir("empty", &[]);
+        assert!(matches!(
+            Catalog::from_sqlx_metadata(&empty),
+            Err(SqlxMetadataError::NoQueries(_))
+        ));
+    }
+
+    #[test]
+    fn test_legacy_sqlx_data_file() {
+        let dir = metadata_dir(
+            "legacy",
+            &[(
+                LEGACY_FILE,
+                json!({
+                    "db": "MySQL",
+                    "abc": {
+                        "query": "SELECT id, name FROM users",
+                        "describe": {
+                            "columns": [
+                                {"ordinal": 0, "name": "id", "type_info": {"type": "Long", "flags": "NOT_NULL", "char_set": 63, "max_size": 11}},
+                                {"ordinal": 1, "name": "name", "type_info": {"type": "VarString", "flags": "", "char_set": 224, "max_size": 400}},
+                            ],
+                            "parameters": {"Right": 0},
+                            "nullable": [false, true],
+                        },
+                    },
+                }),
+            )],
+        );
+
+        let catalog = Catalog::from_sqlx_metadata(&dir.join(LEGACY_FILE)).unwrap();
+        assert_eq!(catalog.default_schema, "");
+        let users = catalog.get_table("users").unwrap();
+        assert_eq!(users.get_column("id").unwrap().data_type, SqlType::Integer);
+        assert_eq!(
+            users.get_column("name").unwrap().data_type,
+            SqlType::Varchar { length: None }
+        );
+        // The directory holding the file works too
+        assert!(Catalog::from_sqlx_metadata(&dir).is_ok());
+    }
+}
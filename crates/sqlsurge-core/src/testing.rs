@@ -0,0 +1,383 @@
+//! Corpus testing support (`test-util` feature)
+//!
+//! A corpus is a directory of cases, one subdirectory each:
+//!
+//! ```text
+//! corpus/
+//!   e0002_column_not_found/
+//!     schema.sql      # optional
+//!     query.sql
+//!     expected.json
+//! ```
+//!
+//! `expected.json` lists the expected diagnostics, plus optional analysis
+//! settings (`dialect`, `enable`, `policy`, `max_depth`) that are kept when
+//! expectations are regenerated:
+//!
+//! ```json
+//! {
+//!   "dialect": "mysql@5.7",
+//!   "enable": ["W0009"],
+//!   "diagnostics": [
+//!     { "code": "E0002", "line": 1, "column": 8, "message": "Column 'nme'" },
+//!     { "file": "schema.sql", "code": "W0003", "line": 1, "column": 14, "message": "reserved" }
+//!   ]
+//! }
+//! ```
+//!
+//! Diagnostics match on code, file, line and column; the expected message
+//! only has to be a substring of the actual one, so rewording a message
+//! doesn't break the corpus. [`CorpusRunner::bless`] rewrites every
+//! `expected.json` from the actual diagnostics instead of comparing.
+//!
+//! ```no_run
+//! use sqlsurge_core::testing::CorpusRunner;
+//!
+//! let report = CorpusRunner::new("tests/corpus")
+//!     .bless(std::env::var_os("SQLSURGE_BLESS").is_some())
+//!     .run()
+//!     .unwrap();
+//! report.assert_ok();
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::dialect::DialectSpec;
+use crate::json::offset_to_line_col;
+use crate::schema::{Catalog, SchemaBuilder};
+use crate::{Analyzer, Diagnostic, WritePolicy};
+
+const SCHEMA_FILE: &str = "schema.sql";
+const QUERY_FILE: &str = "query.sql";
+const EXPECTED_FILE: &str = "expected.json";
+
+/// Contents of a case's `expected.json`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Expectations {
+    /// Dialect, optionally versioned (default: postgresql)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dialect: Option<String>,
+    /// Opt-in rules to enable, by code or name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub enable: Vec<String>,
+    /// Write policy (default: any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<WritePolicy>,
+    /// Maximum nesting depth (default: [`DEFAULT_MAX_DEPTH`](crate::analyzer::DEFAULT_MAX_DEPTH))
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<usize>,
+    pub diagnostics: Vec<ExpectedDiagnostic>,
+}
+
+/// A diagnostic as compared by the corpus runner
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpectedDiagnostic {
+    /// File of the case the diagnostic is in (default: query.sql)
+    #[serde(default = "query_file", skip_serializing_if = "is_query_file")]
+    pub file: String,
+    pub code: String,
+    /// 1-indexed; 0 if the diagnostic has no location
+    pub line: usize,
+    pub column: usize,
+    /// Substring of the actual message
+    pub message: String,
+}
+
+fn query_file() -> String {
+    QUERY_FILE.to_string()
+}
+
+fn is_query_file(file: &str) -> bool {
+    file == QUERY_FILE
+}
+
+impl ExpectedDiagnostic {
+    fn new(file: &str, diagnostic: &Diagnostic, source: &str) -> Self {
+        let (line, column) = match &diagnostic.span {
+            Some(span) if span.line > 0 => (span.line, span.column),
+            Some(span) => offset_to_line_col(source, span.offset),
+            None => (0, 0),
+        };
+        Self {
+            file: file.to_string(),
+            code: diagnostic.code().to_string(),
+            line,
+            column,
+            message: diagnostic.message.clone(),
+        }
+    }
+
+    /// Whether `actual` satisfies this expectation
+    pub fn matches(&self, actual: &ExpectedDiagnostic) -> bool {
+        self.file == actual.file
+            && self.code == actual.code
+            && self.line == actual.line
+            && self.column == actual.column
+            && actual.message.contains(&self.message)
+    }
+}
+
+impl fmt::Display for ExpectedDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}:{}:{} {:?}",
+            self.code, self.file, self.line, self.column, self.message
+        )
+    }
+}
+
+/// What analyzing a case produces, in full (see [`run_case`])
+#[derive(Debug, Clone)]
+pub struct CaseOutput {
+    /// The catalog built from `schema.sql`
+    pub catalog: Catalog,
+    /// Text of `schema.sql` (empty without one)
+    pub schema_sql: String,
+    /// Parse errors and diagnostics of `schema.sql`
+    pub schema: Vec<Diagnostic>,
+    /// Text of `query.sql`
+    pub query_sql: String,
+    /// Diagnostics of `query.sql`
+    pub query: Vec<Diagnostic>,
+}
+
+/// Analyze a case directory, returning its diagnostics in report order
+///
+/// Schema diagnostics (from `schema.sql`) come first, then those of `query.sql`.
+pub fn analyze_case(
+    dir: &Path,
+    expectations: &Expectations,
+) -> Result<Vec<ExpectedDiagnostic>, String> {
+    let output = run_case(dir, expectations)?;
+    let schema = output
+        .schema
+        .iter()
+        .map(|d| ExpectedDiagnostic::new(SCHEMA_FILE, d, &output.schema_sql));
+    let query = output
+        .query
+        .iter()
+        .map(|d| ExpectedDiagnostic::new(QUERY_FILE, d, &output.query_sql));
+    Ok(schema.chain(query).collect())
+}
+
+/// Analyze a case directory like [`analyze_case`], keeping the catalog and
+/// the diagnostics with their help, labels and spans
+pub fn run_case(dir: &Path, expectations: &Expectations) -> Result<CaseOutput, String> {
+    let spec = match &expectations.dialect {
+        Some(dialect) => dialect.parse::<DialectSpec>()?,
+        None => DialectSpec::default(),
+    };
+    let read = |name: &str| fs::read_to_string(dir.join(name));
+    let schema = match read(SCHEMA_FILE) {
+        Ok(schema) => schema,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("{}: {}", SCHEMA_FILE, e)),
+    };
+    let query = read(QUERY_FILE).map_err(|e| format!("{}: {}", QUERY_FILE, e))?;
+
+    let mut builder =
+        SchemaBuilder::with_dialect(spec.dialect).with_enabled_rules(&expectations.enable);
+    let mut schema_diagnostics = builder
+        .parse_named(SCHEMA_FILE, &schema)
+        .err()
+        .unwrap_or_default();
+    let (catalog, diagnostics) = builder.build();
+    schema_diagnostics.extend(diagnostics);
+
+    let mut analyzer =
+        Analyzer::with_dialect(&catalog, spec.dialect).with_enabled_rules(&expectations.enable);
+    if let Some(version) = spec.version {
+        analyzer = analyzer.with_version(version);
+    }
+    if let Some(policy) = expectations.policy {
+        analyzer = analyzer.with_policy(policy);
+    }
+    if let Some(max_depth) = expectations.max_depth {
+        analyzer = analyzer.with_max_depth(max_depth);
+    }
+    let query_diagnostics = analyzer.analyze(&query);
+    Ok(CaseOutput {
+        catalog,
+        schema_sql: schema,
+        schema: schema_diagnostics,
+        query_sql: query,
+        query: query_diagnostics,
+    })
+}
+
+/// Outcome of one case
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseResult {
+    /// Name of the case directory
+    pub name: String,
+    /// Expected diagnostics no actual diagnostic matched
+    pub missing: Vec<ExpectedDiagnostic>,
+    /// Actual diagnostics no expectation matched
+    pub unexpected: Vec<ExpectedDiagnostic>,
+    /// The case couldn't be run (unreadable files, invalid `expected.json`, ...)
+    pub error: Option<String>,
+    /// `expected.json` was rewritten
+    pub blessed: bool,
+}
+
+impl CaseResult {
+    pub fn passed(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty() && self.error.is_none()
+    }
+}
+
+/// Outcome of a corpus run
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorpusReport {
+    pub cases: Vec<CaseResult>,
+}
+
+impl CorpusReport {
+    /// Cases that failed
+    pub fn failures(&self) -> impl Iterator<Item = &CaseResult> {
+        self.cases.iter().filter(|case| !case.passed())
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Panic with the diff of every failed case
+    #[track_caller]
+    pub fn assert_ok(&self) {
+        if !self.is_ok() {
+            panic!("{}", self);
+        }
+    }
+}
+
+impl fmt::Display for CorpusReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let failed = self.failures().count();
+        let blessed = self.cases.iter().filter(|case| case.blessed).count();
+        writeln!(
+            f,
+            "{} case(s): {} passed, {} failed, {} blessed",
+            self.cases.len(),
+            self.cases.len() - failed,
+            failed,
+            blessed
+        )?;
+        for case in self.failures() {
+            writeln!(f, "\n{}:", case.name)?;
+            if let Some(error) = &case.error {
+                writeln!(f, "  error: {}", error)?;
+            }
+            for diagnostic in &case.missing {
+                writeln!(f, "  - {}", diagnostic)?;
+            }
+            for diagnostic in &case.unexpected {
+                writeln!(f, "  + {}", diagnostic)?;
+            }
+        }
+        if failed > 0 {
+            writeln!(
+                f,
+                "\n(- expected but not reported, + reported but not expected; \
+                 set SQLSURGE_BLESS=1 to accept the actual diagnostics)"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs every case of a corpus directory
+#[derive(Debug, Clone)]
+pub struct CorpusRunner {
+    root: PathBuf,
+    bless: bool,
+}
+
+impl CorpusRunner {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            bless: false,
+        }
+    }
+
+    /// Rewrite `expected.json` from the actual diagnostics instead of comparing
+    ///
+    /// The case's analysis settings (`dialect`, `enable`, ...) are kept.
+    pub fn bless(mut self, bless: bool) -> Self {
+        self.bless = bless;
+        self
+    }
+
+    /// Run every case directory (those containing `query.sql`), in name order
+    pub fn run(&self) -> io::Result<CorpusReport> {
+        let mut dirs = Vec::new();
+        for entry in fs::read_dir(&self.root)? {
+            let path = entry?.path();
+            if path.join(QUERY_FILE).is_file() {
+                dirs.push(path);
+            }
+        }
+        dirs.sort();
+        let cases = dirs.iter().map(|dir| self.run_case(dir)).collect();
+        Ok(CorpusReport { cases })
+    }
+
+    /// Run one case directory
+    pub fn run_case(&self, dir: &Path) -> CaseResult {
+        let mut result = CaseResult {
+            name: dir
+                .file_name()
+                .map_or_else(String::new, |n| n.to_string_lossy().into_owned()),
+            missing: Vec::new(),
+            unexpected: Vec::new(),
+            error: None,
+            blessed: false,
+        };
+        if let Err(error) = self.compare(dir, &mut result) {
+            result.error = Some(error);
+        }
+        result
+    }
+
+    fn compare(&self, dir: &Path, result: &mut CaseResult) -> Result<(), String> {
+        let path = dir.join(EXPECTED_FILE);
+        let mut expectations = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str::<Expectations>(&json)
+                .map_err(|e| format!("{}: {}", EXPECTED_FILE, e))?,
+            Err(e) if self.bless && e.kind() == io::ErrorKind::NotFound => Expectations::default(),
+            Err(e) => return Err(format!("{}: {}", EXPECTED_FILE, e)),
+        };
+        let actual = analyze_case(dir, &expectations)?;
+
+        if self.bless {
+            if expectations.diagnostics != actual || !path.exists() {
+                expectations.diagnostics = actual;
+                let json = serde_json::to_string_pretty(&expectations)
+                    .map_err(|e| format!("{}: {}", EXPECTED_FILE, e))?;
+                fs::write(&path, json + "\n").map_err(|e| format!("{}: {}", EXPECTED_FILE, e))?;
+                result.blessed = true;
+            }
+            return Ok(());
+        }
+
+        let mut unmatched: Vec<Option<ExpectedDiagnostic>> = actual.into_iter().map(Some).collect();
+        for expected in expectations.diagnostics {
+            let found = unmatched
+                .iter_mut()
+                .find(|actual| actual.as_ref().is_some_and(|a| expected.matches(a)));
+            match found {
+                Some(actual) => *actual = None,
+                None => result.missing.push(expected),
+            }
+        }
+        result.unexpected = unmatched.into_iter().flatten().collect();
+        Ok(())
+    }
+}
@@ -1,7 +1,11 @@
 //! SQL type system
 
 use serde::{Deserialize, Serialize};
-use sqlparser::ast::DataType;
+use sqlparser::ast::{DataType, TimezoneInfo};
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::Token;
+
+use crate::dialect::SqlDialect;
 
 /// Internal representation of SQL types
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -56,6 +60,9 @@ pub enum SqlType {
     // Array
     Array(Box<SqlType>),
 
+    // MySQL UNSIGNED modifier on an integer type
+    Unsigned(Box<SqlType>),
+
     // Custom/User-defined type
     Custom(String),
 
@@ -64,19 +71,61 @@ pub enum SqlType {
 }
 
 impl SqlType {
+    /// Parse a type name as written in SQL, e.g. `varchar(255)`,
+    /// `timestamptz` or `int[]`
+    ///
+    /// Accepts what the dialect accepts as a column type, including aliases
+    /// (`int4`, `bool`, `float8`, `serial`, `double precision`, ...), and
+    /// maps it as a schema column of that type. Other names are taken as
+    /// user-defined types ([`SqlType::Custom`]). The display name of a type
+    /// parses back to the same type, unless it drops a time precision.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sqlsurge_core::{SqlDialect, SqlType};
+    ///
+    /// let ty = SqlType::parse("numeric(10,2)", SqlDialect::PostgreSQL).unwrap();
+    /// assert_eq!(ty, SqlType::Decimal { precision: Some(10), scale: Some(2) });
+    /// assert!(SqlType::parse("varchar(", SqlDialect::PostgreSQL).is_err());
+    /// ```
+    pub fn parse(name: &str, dialect: SqlDialect) -> Result<SqlType, TypeParseError> {
+        let parser_dialect = dialect.parser_dialect();
+        Parser::new(parser_dialect.as_ref())
+            .try_with_sql(name)
+            .and_then(|mut parser| {
+                let data_type = parser.parse_data_type()?;
+                parser.expect_token(&Token::EOF)?;
+                Ok(data_type)
+            })
+            .map(|data_type| SqlType::from_ast(&data_type))
+            .map_err(|_| TypeParseError {
+                input: name.to_string(),
+            })
+    }
+
     /// Convert from sqlparser's DataType to our internal SqlType
     pub fn from_ast(data_type: &DataType) -> Self {
         match data_type {
-            DataType::TinyInt(_) | DataType::UnsignedTinyInt(_) => SqlType::TinyInt,
-            DataType::SmallInt(_) | DataType::UnsignedSmallInt(_) => SqlType::SmallInt,
+            DataType::TinyInt(_) => SqlType::TinyInt,
+            DataType::SmallInt(_) => SqlType::SmallInt,
             DataType::Int2(_) => SqlType::SmallInt,
-            DataType::MediumInt(_) | DataType::UnsignedMediumInt(_) => SqlType::MediumInt,
-            DataType::Integer(_) | DataType::UnsignedInteger(_) => SqlType::Integer,
-            DataType::Int(_) | DataType::UnsignedInt(_) => SqlType::Integer,
+            DataType::MediumInt(_) => SqlType::MediumInt,
+            DataType::Integer(_) => SqlType::Integer,
+            DataType::Int(_) => SqlType::Integer,
             DataType::Int4(_) => SqlType::Integer,
-            DataType::BigInt(_) | DataType::UnsignedBigInt(_) => SqlType::BigInt,
+            DataType::BigInt(_) => SqlType::BigInt,
             DataType::Int8(_) => SqlType::BigInt,
 
+            // MySQL UNSIGNED integers
+            DataType::UnsignedTinyInt(_) => SqlType::Unsigned(Box::new(SqlType::TinyInt)),
+            DataType::UnsignedSmallInt(_) => SqlType::Unsigned(Box::new(SqlType::SmallInt)),
+            DataType::UnsignedMediumInt(_) => SqlType::Unsigned(Box::new(SqlType::MediumInt)),
+            DataType::UnsignedInteger(_) | DataType::UnsignedInt(_) => {
+                SqlType::Unsigned(Box::new(SqlType::Integer))
+            }
+            DataType::UnsignedBigInt(_) => SqlType::Unsigned(Box::new(SqlType::BigInt)),
+
             DataType::Real => SqlType::Real,
             DataType::Float4 => SqlType::Real,
             DataType::Double => SqlType::DoublePrecision,
@@ -112,14 +161,15 @@ impl SqlType {
 
             DataType::Date => SqlType::Date,
 
+            // TIMETZ / TIMESTAMPTZ are `Tz`
             DataType::Time(precision, tz) => SqlType::Time {
                 precision: *precision,
-                with_timezone: matches!(tz, sqlparser::ast::TimezoneInfo::WithTimeZone),
+                with_timezone: matches!(tz, TimezoneInfo::WithTimeZone | TimezoneInfo::Tz),
             },
 
             DataType::Timestamp(precision, tz) => SqlType::Timestamp {
                 precision: *precision,
-                with_timezone: matches!(tz, sqlparser::ast::TimezoneInfo::WithTimeZone),
+                with_timezone: matches!(tz, TimezoneInfo::WithTimeZone | TimezoneInfo::Tz),
             },
 
             DataType::Datetime(precision) => SqlType::Timestamp {
@@ -173,12 +223,151 @@ impl SqlType {
         }
     }
 
+    /// Whether this is an UNSIGNED integer type
+    pub fn is_unsigned(&self) -> bool {
+        matches!(self, SqlType::Unsigned(_))
+    }
+
+    /// The type with any UNSIGNED modifier removed
+    pub fn without_unsigned(&self) -> &SqlType {
+        match self {
+            SqlType::Unsigned(inner) => inner,
+            other => other,
+        }
+    }
+
+    /// The type with length, precision and UNSIGNED modifiers removed
+    ///
+    /// VARCHAR and TEXT only differ by VARCHAR's length check, so both map
+    /// to unbounded VARCHAR.
+    pub fn without_modifiers(&self) -> SqlType {
+        match self.without_unsigned() {
+            SqlType::Decimal { .. } => SqlType::Decimal {
+                precision: None,
+                scale: None,
+            },
+            SqlType::Char { .. } => SqlType::Char { length: None },
+            SqlType::Varchar { .. } | SqlType::Text => SqlType::Varchar { length: None },
+            SqlType::Time { with_timezone, .. } => SqlType::Time {
+                precision: None,
+                with_timezone: *with_timezone,
+            },
+            SqlType::Timestamp { with_timezone, .. } => SqlType::Timestamp {
+                precision: None,
+                with_timezone: *with_timezone,
+            },
+            other => other.clone(),
+        }
+    }
+
+    /// The type in a canonical form for `dialect`, so that spellings of the
+    /// same type compare equal
+    ///
+    /// Parsing already folds aliases (`int4`, `character varying`,
+    /// `timestamptz`, `decimal`, ...). This also spells out what the dialect
+    /// takes for an omitted modifier and normalizes user-defined type names:
+    ///
+    /// - `char` is `char(1)`
+    /// - PostgreSQL: `numeric(p)` is `numeric(p,0)`; a time precision of 6
+    ///   is the default
+    /// - MySQL: `decimal` is `decimal(10,0)` and `decimal(p)` is
+    ///   `decimal(p,0)`; a time precision of 0 is the default; `real` is
+    ///   `double`
+    /// - user-defined names are lowercased, without a `pg_catalog` or
+    ///   default schema qualifier
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sqlsurge_core::{SqlDialect, SqlType};
+    ///
+    /// let canonical = |name| {
+    ///     let ty = SqlType::parse(name, SqlDialect::MySQL).unwrap();
+    ///     ty.canonical(SqlDialect::MySQL)
+    /// };
+    /// assert_eq!(canonical("decimal"), canonical("numeric(10, 0)"));
+    /// assert_eq!(canonical("datetime(0)"), canonical("datetime"));
+    /// ```
+    pub fn canonical(&self, dialect: SqlDialect) -> SqlType {
+        let default_time_precision = match dialect {
+            SqlDialect::PostgreSQL => 6,
+            SqlDialect::MySQL => 0,
+        };
+        let precision = |p: &Option<u64>| p.filter(|&p| p != default_time_precision);
+        match self {
+            SqlType::Decimal { precision, scale } => match (dialect, precision) {
+                (SqlDialect::MySQL, None) => SqlType::Decimal {
+                    precision: Some(10),
+                    scale: Some(0),
+                },
+                (_, Some(_)) => SqlType::Decimal {
+                    precision: *precision,
+                    scale: scale.or(Some(0)),
+                },
+                _ => self.clone(),
+            },
+            SqlType::Real if dialect == SqlDialect::MySQL => SqlType::DoublePrecision,
+            SqlType::Char { length } => SqlType::Char {
+                length: length.or(Some(1)),
+            },
+            SqlType::Time {
+                precision: p,
+                with_timezone,
+            } => SqlType::Time {
+                precision: precision(p),
+                with_timezone: *with_timezone,
+            },
+            SqlType::Timestamp {
+                precision: p,
+                with_timezone,
+            } => SqlType::Timestamp {
+                precision: precision(p),
+                with_timezone: *with_timezone,
+            },
+            SqlType::Array(inner) => SqlType::Array(Box::new(inner.canonical(dialect))),
+            SqlType::Unsigned(inner) => SqlType::Unsigned(Box::new(inner.canonical(dialect))),
+            SqlType::Custom(name) => {
+                let name = name.to_lowercase();
+                let default_schema = format!("{}.", dialect.default_schema());
+                let bare = name
+                    .strip_prefix("pg_catalog.")
+                    .or_else(|| name.strip_prefix(default_schema.as_str()))
+                    .unwrap_or(&name);
+                SqlType::Custom(bare.to_string())
+            }
+            other => other.clone(),
+        }
+    }
+
+    /// Check compatibility of the canonical forms of both types in `dialect`
+    /// (see [`SqlType::canonical`])
+    pub fn compatibility_in(&self, other: &SqlType, dialect: SqlDialect) -> TypeCompatibility {
+        self.canonical(dialect)
+            .is_compatible_with(&other.canonical(dialect))
+    }
+
     /// Check if this type is compatible with another type
     pub fn is_compatible_with(&self, other: &SqlType) -> TypeCompatibility {
         if self == other {
             return TypeCompatibility::Exact;
         }
 
+        // Signedness only affects the value range, not compatibility
+        if self.is_unsigned() || other.is_unsigned() {
+            return match self
+                .without_unsigned()
+                .is_compatible_with(other.without_unsigned())
+            {
+                TypeCompatibility::Exact => TypeCompatibility::ImplicitCast,
+                compat => compat,
+            };
+        }
+
+        // Lengths and precisions only constrain the value
+        if self.without_modifiers() == other.without_modifiers() {
+            return TypeCompatibility::ImplicitCast;
+        }
+
         use SqlType::*;
         match (self, other) {
             // Numeric type coercion
@@ -248,12 +437,62 @@ impl SqlType {
             SqlType::Json => "json".to_string(),
             SqlType::Jsonb => "jsonb".to_string(),
             SqlType::Array(inner) => format!("{}[]", inner.display_name()),
+            SqlType::Unsigned(inner) => format!("{} unsigned", inner.display_name()),
             SqlType::Custom(name) => name.clone(),
             SqlType::Unknown => "unknown".to_string(),
         }
     }
 }
 
+/// Built-in PostgreSQL types (and common extension types) that sqlparser
+/// parses as custom type names
+const OTHER_BUILTIN_TYPES: &[&str] = &[
+    "bit",
+    "box",
+    "cidr",
+    "circle",
+    "citext",
+    "daterange",
+    "geography",
+    "geometry",
+    "hstore",
+    "inet",
+    "int4range",
+    "int8range",
+    "jsonpath",
+    "line",
+    "lseg",
+    "ltree",
+    "macaddr",
+    "macaddr8",
+    "money",
+    "name",
+    "numrange",
+    "oid",
+    "path",
+    "pg_lsn",
+    "point",
+    "polygon",
+    "regclass",
+    "regconfig",
+    "regproc",
+    "regprocedure",
+    "regrole",
+    "regtype",
+    "tsquery",
+    "tsrange",
+    "tstzrange",
+    "tsvector",
+    "varbit",
+    "xml",
+];
+
+/// Whether a custom type name (optionally schema-qualified) is a built-in type
+pub(crate) fn is_builtin_type_name(name: &str) -> bool {
+    let name = name.rsplit('.').next().unwrap_or(name).to_lowercase();
+    OTHER_BUILTIN_TYPES.contains(&name.as_str())
+}
+
 /// Extract character length from CharacterLength if present
 fn extract_char_length(info: Option<&sqlparser::ast::CharacterLength>) -> Option<u64> {
     info.map(|i| match i {
@@ -262,6 +501,16 @@ fn extract_char_length(info: Option<&sqlparser::ast::CharacterLength>) -> Option
     })
 }
 
+/// Error of [`SqlType::parse`]: the input is not a type name
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "'{input}' is not a type name; expected a type such as integer, bigint, \
+     numeric(10,2), varchar(255), text, boolean, uuid, timestamptz, jsonb or int[]"
+)]
+pub struct TypeParseError {
+    pub input: String,
+}
+
 /// Result of type compatibility check
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TypeCompatibility {
@@ -288,4 +537,201 @@ mod tests {
             TypeCompatibility::Exact
         );
     }
+
+    #[test]
+    fn test_unsigned_types() {
+        let unsigned_int = SqlType::from_ast(&DataType::UnsignedInt(None));
+        assert_eq!(unsigned_int, SqlType::Unsigned(Box::new(SqlType::Integer)));
+        assert!(unsigned_int.is_unsigned());
+        assert_eq!(unsigned_int.display_name(), "integer unsigned");
+        assert_eq!(
+            unsigned_int.is_compatible_with(&SqlType::Integer),
+            TypeCompatibility::ImplicitCast
+        );
+        assert_eq!(
+            unsigned_int.is_compatible_with(&SqlType::BigInt),
+            TypeCompatibility::ImplicitCast
+        );
+        assert_eq!(
+            unsigned_int.is_compatible_with(&SqlType::Text),
+            TypeCompatibility::ExplicitCast
+        );
+    }
+
+    #[test]
+    fn test_parse_aliases() {
+        let pg = SqlDialect::PostgreSQL;
+        let parse = |name| SqlType::parse(name, pg).unwrap();
+        assert_eq!(parse("int4"), SqlType::Integer);
+        assert_eq!(parse("int8"), SqlType::BigInt);
+        assert_eq!(parse("serial"), SqlType::Integer);
+        assert_eq!(parse("bool"), SqlType::Boolean);
+        assert_eq!(parse("float8"), SqlType::DoublePrecision);
+        assert_eq!(parse("DOUBLE PRECISION"), SqlType::DoublePrecision);
+        assert_eq!(
+            parse("character varying(3)"),
+            SqlType::Varchar { length: Some(3) }
+        );
+        assert_eq!(parse("text[]"), SqlType::Array(Box::new(SqlType::Text)));
+        assert_eq!(
+            parse("public.mood"),
+            SqlType::Custom("public.mood".to_string())
+        );
+        let timestamptz = SqlType::Timestamp {
+            precision: None,
+            with_timezone: true,
+        };
+        assert_eq!(parse("timestamptz"), timestamptz);
+        assert_eq!(parse("timestamp with time zone"), timestamptz);
+        assert_eq!(
+            parse("timetz").display_name(),
+            "time with time zone".to_string()
+        );
+        assert_eq!(
+            SqlType::parse("bigint unsigned", SqlDialect::MySQL).unwrap(),
+            SqlType::Unsigned(Box::new(SqlType::BigInt))
+        );
+
+        for bad in ["", "varchar(", "integer integer", "1"] {
+            let err = SqlType::parse(bad, pg).unwrap_err();
+            assert_eq!(err.input, bad);
+            assert!(err.to_string().contains("varchar(255)"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_parse_display_name_round_trip() {
+        let mut types = vec![
+            SqlType::TinyInt,
+            SqlType::SmallInt,
+            SqlType::MediumInt,
+            SqlType::Integer,
+            SqlType::BigInt,
+            SqlType::Decimal {
+                precision: None,
+                scale: None,
+            },
+            SqlType::Decimal {
+                precision: Some(10),
+                scale: None,
+            },
+            SqlType::Decimal {
+                precision: Some(10),
+                scale: Some(2),
+            },
+            SqlType::Real,
+            SqlType::DoublePrecision,
+            SqlType::Char { length: None },
+            SqlType::Char { length: Some(5) },
+            SqlType::Varchar { length: None },
+            SqlType::Varchar { length: Some(255) },
+            SqlType::Text,
+            SqlType::Bytea,
+            SqlType::Date,
+            SqlType::Interval,
+            SqlType::Boolean,
+            SqlType::Uuid,
+            SqlType::Json,
+            SqlType::Jsonb,
+            SqlType::Custom("mood".to_string()),
+        ];
+        for with_timezone in [false, true] {
+            types.push(SqlType::Time {
+                precision: None,
+                with_timezone,
+            });
+            types.push(SqlType::Timestamp {
+                precision: None,
+                with_timezone,
+            });
+        }
+        let arrays: Vec<SqlType> = types
+            .iter()
+            .map(|t| SqlType::Array(Box::new(t.clone())))
+            .collect();
+        types.extend(arrays);
+        let integers = [
+            SqlType::TinyInt,
+            SqlType::SmallInt,
+            SqlType::MediumInt,
+            SqlType::Integer,
+            SqlType::BigInt,
+        ];
+        let unsigned = integers.map(|t| SqlType::Unsigned(Box::new(t)));
+
+        for dialect in [SqlDialect::PostgreSQL, SqlDialect::MySQL] {
+            for ty in types.iter().chain(&unsigned) {
+                let name = ty.display_name();
+                assert_eq!(
+                    SqlType::parse(&name, dialect).as_ref(),
+                    Ok(ty),
+                    "{} ({})",
+                    name,
+                    dialect
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_canonical_spellings() {
+        use SqlDialect::{MySQL, PostgreSQL};
+        let canonical = |name: &str, dialect| {
+            SqlType::parse(name, dialect)
+                .unwrap_or_else(|e| panic!("{}: {:?}", name, e))
+                .canonical(dialect)
+        };
+        let equal = [
+            ("varchar(255)", "character varying(255)", PostgreSQL),
+            ("int", "integer", PostgreSQL),
+            ("int4", "integer", PostgreSQL),
+            ("timestamptz", "timestamp with time zone", PostgreSQL),
+            ("timestamp(6)", "timestamp", PostgreSQL),
+            ("numeric", "decimal", PostgreSQL),
+            ("numeric(10)", "numeric(10, 0)", PostgreSQL),
+            ("char", "char(1)", PostgreSQL),
+            ("pg_catalog.citext", "CITEXT", PostgreSQL),
+            ("public.mood", "mood", PostgreSQL),
+            ("decimal", "decimal(10, 0)", MySQL),
+            ("decimal(8)", "numeric(8, 0)", MySQL),
+            ("real", "double", MySQL),
+            ("datetime(0)", "datetime", MySQL),
+        ];
+        for (a, b, dialect) in equal {
+            assert_eq!(
+                canonical(a, dialect),
+                canonical(b, dialect),
+                "{} = {}",
+                a,
+                b
+            );
+            assert_eq!(
+                SqlType::parse(a, dialect)
+                    .unwrap()
+                    .compatibility_in(&SqlType::parse(b, dialect).unwrap(), dialect),
+                TypeCompatibility::Exact,
+                "{} = {}",
+                a,
+                b
+            );
+        }
+
+        let different = [
+            ("varchar(255)", "varchar(100)", PostgreSQL),
+            ("numeric", "numeric(10, 0)", PostgreSQL),
+            ("timestamp(3)", "timestamp", PostgreSQL),
+            ("real", "double precision", PostgreSQL),
+            ("audit.mood", "mood", PostgreSQL),
+            ("datetime(6)", "datetime", MySQL),
+        ];
+        for (a, b, dialect) in different {
+            assert_ne!(
+                canonical(a, dialect),
+                canonical(b, dialect),
+                "{} != {}",
+                a,
+                b
+            );
+        }
+    }
 }
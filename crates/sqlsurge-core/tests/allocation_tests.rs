@@ -0,0 +1,76 @@
+// Allocation counts of name resolution, measured with a counting allocator
+//
+// Counts are per thread, so tests running in parallel don't disturb each other.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use sqlsurge_core::schema::SchemaBuilder;
+use sqlsurge_core::Analyzer;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+/// `tables` joined tables, filtered by `subqueries` correlated subqueries
+fn query(tables: usize, subqueries: usize) -> String {
+    let mut sql = String::from("SELECT t0.id FROM t t0");
+    for i in 1..tables {
+        sql.push_str(&format!(" JOIN t t{i} ON t{i}.id = t0.id"));
+    }
+    sql.push_str(" WHERE t0.id > 0");
+    for _ in 0..subqueries {
+        sql.push_str(" AND EXISTS (SELECT 1 FROM t s WHERE s.id = t0.id)");
+    }
+    sql
+}
+
+#[test]
+fn test_subquery_scope_cost_is_independent_of_scope_size() {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse("CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT);")
+        .unwrap();
+    let (catalog, _) = builder.build();
+    let measure = |tables, subqueries| {
+        let sql = query(tables, subqueries);
+        // At most 50 levels deep, analysis stays on this thread, where
+        // allocations are counted
+        let mut analyzer = Analyzer::new(&catalog).with_max_depth(50);
+        allocations(|| {
+            assert!(analyzer.analyze(&sql).is_empty());
+        })
+    };
+
+    // Entering a subquery saves the scope; with shared scope entries that
+    // costs (about) the same whether 2 or 30 tables are in scope
+    let small = measure(2, 20) - measure(2, 0);
+    let large = measure(30, 20) - measure(30, 0);
+    assert!(
+        large <= small + 20,
+        "20 subqueries cost {} allocations with 2 tables in scope, {} with 30",
+        small,
+        large
+    );
+}
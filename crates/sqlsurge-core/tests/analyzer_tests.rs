@@ -1,9 +1,16 @@
 // Integration tests for SQL analyzer
-use sqlsurge_core::analyzer::Analyzer;
-use sqlsurge_core::dialect::SqlDialect;
-use sqlsurge_core::error::DiagnosticKind;
-use sqlsurge_core::schema::{Catalog, IdentityKind, QualifiedName, SchemaBuilder};
+use sqlparser::dialect::PostgreSqlDialect;
+use sqlparser::parser::Parser;
+use sqlsurge_core::analyzer::{
+    classify, Analyzer, Phase, StatementClass, WritePolicy, DEFAULT_MAX_DEPTH,
+};
+use sqlsurge_core::dialect::{DialectSpec, DialectVersion, SqlDialect};
+use sqlsurge_core::error::{DiagnosticKind, DiagnosticOrigin, Severity};
+use sqlsurge_core::schema::{
+    Catalog, CatalogBuilder, FunctionSig, IdentityKind, QualifiedName, SchemaBuilder,
+};
 use sqlsurge_core::types::SqlType;
+use std::time::Duration;
 
 fn setup_catalog() -> Catalog {
     let schema_sql = r#"
@@ -84,6 +91,41 @@ fn test_table_alias_not_found() {
     assert!(diagnostics[0].message.contains("'x'"));
 }
 
+#[test]
+fn test_unknown_alias_reported_once() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze("SELECT x.id, x.name, x.email FROM users u WHERE x.id > 1");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    let diag = &diagnostics[0];
+    assert_eq!(diag.kind, DiagnosticKind::TableNotFound);
+    assert_eq!(diag.span.as_ref().unwrap().column, 8);
+    assert_eq!(diag.help.as_deref(), Some("Did you mean 'u'?"));
+    let columns: Vec<usize> = diag.labels.iter().map(|l| l.span.column).collect();
+    assert_eq!(columns, vec![14, 22, 49]);
+    assert!(diag.labels[0].message.contains("also referenced here"));
+
+    // References in subqueries of the statement are grouped too; a table
+    // name that was aliased points at the alias
+    let diagnostics = analyzer.analyze(
+        "SELECT users.id FROM users u WHERE EXISTS (SELECT 1 FROM orders o WHERE o.user_id = users.id)",
+    );
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].labels.len(), 1);
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("table 'users' is aliased as 'u'; use the alias: 'u.id'")
+    );
+
+    // Different unknown names are separate errors, without a suggestion
+    // when nothing in scope is close
+    let diagnostics = analyzer.analyze("SELECT x.id, customers.id FROM users u");
+    assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+    assert!(diagnostics.iter().all(|d| d.labels.is_empty()));
+    assert_eq!(diagnostics[1].help, None);
+}
+
 #[test]
 fn test_ambiguous_column() {
     let catalog = setup_catalog();
@@ -97,6 +139,109 @@ fn test_ambiguous_column() {
     assert!(diagnostics[0].message.contains("ambiguous"));
 }
 
+#[test]
+fn test_ambiguous_column_in_self_join_names_aliases() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze("SELECT name FROM users u2 JOIN users u1 ON u1.id = u2.id");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::AmbiguousColumn);
+    assert!(
+        diagnostics[0].message.contains("(found in tables: u1, u2)"),
+        "{}",
+        diagnostics[0].message
+    );
+}
+
+#[test]
+fn test_ambiguous_column_help_lists_qualifications() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // Each candidate by the name the query gives it, quoted where needed,
+    // with a label on where the FROM clause names it
+    let diagnostics =
+        analyzer.analyze("SELECT id FROM users \"U\" JOIN orders o ON o.user_id = \"U\".id");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("Qualify the column with a table name: \"U\".id or o.id")
+    );
+    let labels: Vec<(&str, usize)> = diagnostics[0]
+        .labels
+        .iter()
+        .map(|l| (l.message.as_str(), l.span.column))
+        .collect();
+    assert_eq!(
+        labels,
+        [
+            ("'\"U\"' has a column 'id'", 22),
+            ("'o' has a column 'id'", 38)
+        ]
+    );
+
+    // CTEs and derived tables go by their names
+    let diagnostics = analyzer.analyze(
+        "WITH recent AS (SELECT id FROM orders) \
+         SELECT id FROM recent, (SELECT id FROM users) d",
+    );
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("Qualify the column with a table name: d.id or recent.id")
+    );
+    assert_eq!(diagnostics[0].labels.len(), 2);
+
+    // At most four are suggested
+    let diagnostics = analyzer.analyze(
+        "SELECT name FROM users a, users b, users c, users d, users e \
+         WHERE a.id = b.id AND b.id = c.id AND c.id = d.id AND d.id = e.id",
+    );
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("Qualify the column with a table name: a.name, b.name, c.name, d.name, ...")
+    );
+    assert_eq!(diagnostics[0].labels.len(), 5);
+}
+
+#[test]
+fn test_duplicate_table_reference() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics =
+        analyzer.analyze("SELECT total FROM orders JOIN orders ON orders.id = orders.id");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::DuplicateTableReference);
+    assert!(diagnostics[0]
+        .message
+        .contains("Table name 'orders' specified more than once; use an alias"));
+
+    let diagnostics = analyzer.analyze("SELECT u.id FROM users u JOIN orders u ON true");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(diagnostics[0].message.contains("Table alias 'u'"));
+    // Two tables sharing an alias are each given their own
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("Give each reference a distinct alias, e.g. users AS u ... orders AS o")
+    );
+    let diagnostics = analyzer.analyze("SELECT a.id FROM orders a JOIN orders a ON true");
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("Give each reference a distinct alias, e.g. orders AS orders1 ... orders AS orders2")
+    );
+
+    // Aliased self-joins and a subquery reusing an outer table are fine
+    assert!(analyzer
+        .analyze("SELECT u1.id FROM users u1 JOIN users u2 ON u1.id = u2.id")
+        .is_empty());
+    assert!(analyzer
+        .analyze("SELECT id FROM users WHERE id IN (SELECT user_id FROM orders JOIN users ON users.id = orders.user_id)")
+        .is_empty());
+}
+
 #[test]
 fn test_ambiguous_column_resolved_with_qualifier() {
     let catalog = setup_catalog();
@@ -121,6 +266,7 @@ fn test_parse_error() {
     let diagnostics = analyzer.analyze("SELECT FROM WHERE");
     assert!(!diagnostics.is_empty());
     assert_eq!(diagnostics[0].kind, DiagnosticKind::ParseError);
+    assert_eq!(diagnostics[0].origin, Some(DiagnosticOrigin::Query));
 }
 
 #[test]
@@ -226,6 +372,227 @@ fn test_insert_column_count_mismatch_fewer_values() {
     assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnCountMismatch);
 }
 
+#[test]
+fn test_insert_default_values() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // users.name is NOT NULL without a default; id is SERIAL
+    let diagnostics = analyzer.analyze("INSERT INTO users DEFAULT VALUES");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::PotentialNullViolation);
+    assert!(diagnostics[0].message.contains("'name'"));
+
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(
+            "CREATE TABLE events (
+                id SERIAL PRIMARY KEY,
+                seq BIGINT GENERATED ALWAYS AS IDENTITY,
+                created_at TIMESTAMP NOT NULL DEFAULT now(),
+                note TEXT
+            );",
+        )
+        .unwrap();
+    let (catalog, _) = builder.build();
+    let mut analyzer = Analyzer::new(&catalog);
+    let diagnostics = analyzer.analyze("INSERT INTO events DEFAULT VALUES");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+#[test]
+fn test_insert_default_markers() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // DEFAULT counts as a value and fits any column that has a default
+    let diagnostics = analyzer.analyze("INSERT INTO users VALUES (DEFAULT, 'a', DEFAULT)");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let diagnostics = analyzer.analyze("INSERT INTO users VALUES (DEFAULT, 'a')");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnCountMismatch);
+
+    // ...but a NOT NULL column without one receives NULL
+    let diagnostics = analyzer.analyze("INSERT INTO users VALUES (DEFAULT, DEFAULT, DEFAULT)");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::PotentialNullViolation);
+    let span = diagnostics[0].span.unwrap();
+    assert_eq!((span.line, span.column), (1, 36));
+
+    // Rows mixing markers and literals
+    let diagnostics =
+        analyzer.analyze("INSERT INTO users (id, name) VALUES (DEFAULT, 'a'), (2, DEFAULT)");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::PotentialNullViolation);
+}
+
+#[test]
+fn test_insert_select_nullable_into_not_null() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // The users side of a LEFT JOIN may be NULL
+    let diagnostics = analyzer.analyze(
+        "INSERT INTO users (id, name) SELECT o.id, u.name FROM orders o LEFT JOIN users u ON u.id = o.user_id",
+    );
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::PotentialNullViolation);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert!(diagnostics[0].message.contains("'name'"));
+    let span = diagnostics[0].span.unwrap();
+    assert_eq!((span.line, span.column, span.length), (1, 24, 4));
+    assert!(diagnostics[0]
+        .help
+        .as_ref()
+        .unwrap()
+        .contains("COALESCE(u.name"));
+
+    // Nullable columns and NULL literals
+    let diagnostics = analyzer.analyze("INSERT INTO users (id, name) SELECT id, email FROM users");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    let diagnostics =
+        analyzer.analyze("INSERT INTO orders (id, user_id) SELECT id, NULL FROM users");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("Select a value for 'user_id' instead of NULL")
+    );
+
+    // Without a column list: the selected value, or the table for a NULL literal
+    let diagnostics = analyzer.analyze("INSERT INTO users SELECT id, email, email FROM users");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].span.unwrap().column, 30);
+    let diagnostics = analyzer.analyze("INSERT INTO users SELECT id, NULL, email FROM users");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    let span = diagnostics[0].span.unwrap();
+    assert_eq!((span.line, span.column, span.length), (1, 13, 5));
+
+    // Function calls and parameters aren't traced
+    let diagnostics =
+        analyzer.analyze("INSERT INTO users (id, name) SELECT id, upper(email) FROM users");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let diagnostics = analyzer.analyze("INSERT INTO users (id, name) SELECT id, $1 FROM users");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+#[test]
+fn test_insert_select_coalesce_fallback_is_not_null() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze(
+        "INSERT INTO users (id, name) SELECT o.id, COALESCE(u.name, 'unknown') FROM orders o LEFT JOIN users u ON u.id = o.user_id",
+    );
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    // Every argument may be NULL
+    let diagnostics = analyzer
+        .analyze("INSERT INTO users (id, name) SELECT id, COALESCE(email, NULL) FROM users");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+}
+
+#[test]
+fn test_insert_select_case_nullability() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze(
+        "INSERT INTO users (id, name) SELECT id, CASE WHEN email IS NULL THEN 'none' ELSE email END FROM users",
+    );
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+
+    let diagnostics = analyzer.analyze(
+        "INSERT INTO users (id, name) SELECT id, CASE WHEN id > 10 THEN name ELSE 'other' END FROM users",
+    );
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    // Without ELSE, rows matching no branch get NULL
+    let diagnostics = analyzer.analyze(
+        "INSERT INTO users (id, name) SELECT id, CASE WHEN id > 10 THEN name END FROM users",
+    );
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::PotentialNullViolation);
+}
+
+#[test]
+fn test_insert_select_where_is_not_null() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze(
+        "INSERT INTO users (id, name) SELECT o.id, u.name FROM orders o LEFT JOIN users u ON u.id = o.user_id WHERE u.name IS NOT NULL AND o.total > 0",
+    );
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let diagnostics = analyzer.analyze(
+        "INSERT INTO users (id, name) SELECT id, email FROM users WHERE (email IS NOT NULL)",
+    );
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    // Under OR a row may pass with a NULL
+    let diagnostics = analyzer.analyze(
+        "INSERT INTO users (id, name) SELECT id, email FROM users WHERE email IS NOT NULL OR id = 1",
+    );
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+}
+
+#[test]
+fn test_zero_column_table() {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse("CREATE TABLE markers (); CREATE TABLE users (id INT, name TEXT);")
+        .unwrap();
+    let (catalog, diagnostics) = builder.build();
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    assert!(catalog.get_table("markers").unwrap().columns.is_empty());
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // A wildcard over it expands to nothing
+    let columns = analyzer
+        .infer_output_schema("SELECT * FROM markers")
+        .unwrap();
+    assert!(columns.is_empty());
+    let columns = analyzer
+        .infer_output_schema("SELECT m.*, u.* FROM markers m, users u")
+        .unwrap();
+    let names: Vec<_> = columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["id", "name"]);
+
+    for sql in [
+        "INSERT INTO markers DEFAULT VALUES",
+        "INSERT INTO markers DEFAULT VALUES RETURNING *",
+        "SELECT count(*) FROM markers",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert!(diagnostics.is_empty(), "{}: {:?}", sql, diagnostics);
+    }
+
+    // Only DEFAULT VALUES inserts into it
+    for sql in [
+        "INSERT INTO markers VALUES (1)",
+        "INSERT INTO markers SELECT id FROM users",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert_eq!(diagnostics.len(), 1, "{}: {:?}", sql, diagnostics);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnCountMismatch);
+        assert_eq!(
+            diagnostics[0].help.as_deref(),
+            Some(
+                "Table 'markers' has no columns; insert rows into it with \
+                 INSERT INTO markers DEFAULT VALUES"
+            )
+        );
+    }
+    let diagnostics = analyzer.analyze("SELECT id FROM markers");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+    let diagnostics = analyzer.analyze("INSERT INTO users SELECT * FROM markers");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(
+        diagnostics[0].message,
+        "INSERT query returns 0 column(s) but 2 column(s) were specified"
+    );
+}
+
 // ========== UPDATE Tests ==========
 
 #[test]
@@ -273,6 +640,148 @@ fn test_update_where_column_not_found() {
     assert!(diagnostics[0].message.contains("user_id"));
 }
 
+#[test]
+fn test_update_tuple_assignment() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    for sql in [
+        "UPDATE users SET (name, email) = ('a', 'b') WHERE id = 1",
+        "UPDATE users SET (name, email) = (SELECT 'a', 'b') WHERE id = 1",
+        "UPDATE users SET (name, email) = ROW('a', 'b') WHERE id = 1",
+        "UPDATE users SET (name) = ('a') WHERE id = 1",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert!(diagnostics.is_empty(), "{}: {:?}", sql, diagnostics);
+    }
+
+    let diagnostics = analyzer.analyze("UPDATE users SET (name, mail) = ('a', 'b')");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+    assert!(diagnostics[0].message.contains("mail"));
+
+    let diagnostics =
+        analyzer.analyze("SELECT 1;\nUPDATE users SET (name, email) = ('a', 'b', 'c');");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnCountMismatch);
+    assert_eq!(
+        diagnostics[0].message,
+        "UPDATE assigns 3 value(s) to 2 column(s)"
+    );
+    // Literals have no position: reported at the column list
+    let span = diagnostics[0].span.unwrap();
+    assert_eq!((span.line, span.column), (2, 19));
+
+    // A parenthesized or bare value is one value, ROW(...) one per argument
+    for (sql, values) in [
+        ("UPDATE users SET (name, email) = ('a')", 1),
+        ("UPDATE users SET (name, email) = upper('a')", 1),
+        ("UPDATE users SET (name, email) = ROW('a', 'b', 'c')", 3),
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert_eq!(diagnostics.len(), 1, "{}: {:?}", sql, diagnostics);
+        assert_eq!(
+            diagnostics[0].message,
+            format!("UPDATE assigns {} value(s) to 2 column(s)", values)
+        );
+    }
+
+    let diagnostics =
+        analyzer.analyze("UPDATE users SET (name, email) = (SELECT user_id FROM orders LIMIT 1)");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnCountMismatch);
+    assert_eq!(
+        diagnostics[0].message,
+        "UPDATE subquery returns 1 column(s) but 2 column(s) were specified"
+    );
+}
+
+#[test]
+fn test_insert_set() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::with_dialect(&catalog, SqlDialect::MySQL);
+
+    let diagnostics = analyzer.analyze("INSERT INTO users SET name = 'a', email = 'b'");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    let sql = "INSERT IGNORE users\n  SET name = 'a', mail = 'b'";
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+    assert!(diagnostics[0].message.contains("mail"));
+    let span = diagnostics[0].span.unwrap();
+    assert_eq!((span.line, span.column), (2, 19));
+
+    // With an ON DUPLICATE KEY UPDATE tail, the SET list is still checked
+    let diagnostics =
+        analyzer.analyze("INSERT INTO users SET name = 'x' ON DUPLICATE KEY UPDATE name = 'y'");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let sql =
+        "INSERT INTO users SET nme = 'x', email = 'y'\nON DUPLICATE KEY UPDATE name = VALUES(name)";
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+    let span = diagnostics[0].span.unwrap();
+    assert_eq!((span.line, span.column), (1, 23));
+
+    let mut analyzer = Analyzer::new(&catalog);
+    let diagnostics = analyzer.analyze("INSERT INTO users SET name = 'a'");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::UnsupportedFeature);
+    assert_eq!(
+        diagnostics[0].message,
+        "INSERT ... SET is not supported by PostgreSQL"
+    );
+}
+
+/// accounts(id UUID) and their ledger lines, keyed by account number
+fn ledger_catalog() -> Catalog {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(
+            "CREATE TABLE accounts (id UUID PRIMARY KEY, number INTEGER NOT NULL, name TEXT);
+             CREATE TABLE ledger (id SERIAL PRIMARY KEY, account INTEGER NOT NULL, note TEXT);",
+        )
+        .unwrap();
+    builder.build().0
+}
+
+#[test]
+fn test_update_from_join_type_mismatch() {
+    let catalog = ledger_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    assert_resolves(
+        &mut analyzer,
+        "UPDATE ledger SET note = a.name FROM accounts a WHERE ledger.account = a.number",
+    );
+
+    // The join condition compares uuid with integer
+    let diagnostics = analyzer.analyze(
+        "UPDATE ledger SET note = a.name FROM accounts a WHERE ledger.account = a.id AND a.name = 'x'",
+    );
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::JoinTypeMismatch);
+    assert_eq!(diagnostics[0].span.unwrap().column, 55);
+
+    // Unqualified columns are attributed to the one table that has them
+    let diagnostics = analyzer
+        .analyze("UPDATE ledger SET note = 'x' FROM accounts WHERE (account = accounts.id)");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::JoinTypeMismatch);
+
+    // Comparisons within one relation, or with a literal, are ordinary ones
+    let diagnostics = analyzer.analyze(
+        "UPDATE ledger SET note = 'x' FROM accounts a WHERE ledger.account = a.number \
+         AND a.id = 1 AND ledger.note = ledger.account",
+    );
+    let kinds: Vec<_> = diagnostics.iter().map(|d| d.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![DiagnosticKind::TypeMismatch, DiagnosticKind::TypeMismatch]
+    );
+}
+
 // ========== DELETE Tests ==========
 
 #[test]
@@ -310,6 +819,33 @@ fn test_delete_where_column_not_found() {
     assert!(diagnostics[0].message.contains("user_id"));
 }
 
+#[test]
+fn test_delete_using_join_type_mismatch() {
+    let catalog = ledger_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    assert_resolves(
+        &mut analyzer,
+        "DELETE FROM ledger USING accounts a WHERE ledger.account = a.number AND a.name = 'closed'",
+    );
+
+    let diagnostics =
+        analyzer.analyze("DELETE FROM ledger l USING accounts a WHERE a.id = l.account");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::JoinTypeMismatch);
+    assert_eq!(
+        diagnostics[0].message,
+        "JOIN condition type mismatch: uuid vs integer"
+    );
+
+    // Under OR, a comparison isn't a join condition
+    let diagnostics = analyzer.analyze(
+        "DELETE FROM ledger l USING accounts a WHERE l.account = a.number OR a.id = l.account",
+    );
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TypeMismatch);
+}
+
 // ========== Subquery Tests ==========
 
 #[test]
@@ -402,36 +938,183 @@ fn test_cte_column_not_found() {
     assert!(diagnostics[0].message.contains("name"));
 }
 
-// ========== CHECK Constraint Tests ==========
-
 #[test]
-fn test_check_constraint_table_level() {
-    let schema_sql = r#"
-            CREATE TABLE products (
-                id SERIAL PRIMARY KEY,
-                name TEXT NOT NULL,
-                price DECIMAL(10, 2) NOT NULL,
-                CONSTRAINT price_positive CHECK (price > 0)
-            );
-        "#;
+fn test_cte_visible_in_nested_derived_tables() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
 
-    let mut builder = SchemaBuilder::new();
-    builder.parse(schema_sql).unwrap();
-    let (catalog, _) = builder.build();
+    let sql = "WITH a AS (SELECT id FROM users), \
+               b AS (SELECT * FROM (SELECT * FROM (SELECT id FROM a) s1) s2) \
+               SELECT id FROM b";
+    assert!(analyzer.analyze(sql).is_empty());
+    let columns = analyzer.infer_output_schema(sql).unwrap();
+    assert_eq!(columns[0].data_type, SqlType::Integer);
 
-    let table = catalog.get_table(&QualifiedName::new("products")).unwrap();
-    assert_eq!(table.check_constraints.len(), 1);
-    assert_eq!(
-        table.check_constraints[0].name.as_deref(),
-        Some("price_positive")
+    // An unknown column is still caught two levels down
+    let diagnostics = analyzer.analyze(
+        "WITH a AS (SELECT id FROM users) SELECT * FROM (SELECT * FROM (SELECT nme FROM a) s1) s2",
     );
-    assert!(table.check_constraints[0].expression.contains("price"));
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+}
 
-    // Queries against the table should still work
+#[test]
+fn test_cte_not_visible_outside_its_query() {
+    let catalog = setup_catalog();
     let mut analyzer = Analyzer::new(&catalog);
-    let diagnostics = analyzer.analyze("SELECT id, name, price FROM products");
-    assert!(
-        diagnostics.is_empty(),
+
+    let diagnostics =
+        analyzer.analyze("SELECT * FROM (WITH x AS (SELECT 1 AS n) SELECT n FROM x) s, x");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TableNotFound);
+}
+
+#[test]
+fn test_recursive_cte_with_derived_table_over_itself() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let sql = "WITH RECURSIVE r(n) AS ( \
+                   SELECT 1 UNION ALL SELECT n + 1 FROM (SELECT n FROM r) sub WHERE n < 10 \
+               ) SELECT n FROM r";
+    assert!(analyzer.analyze(sql).is_empty());
+    let columns = analyzer.infer_output_schema(sql).unwrap();
+    assert_eq!(columns[0].name, "n");
+    assert_eq!(columns[0].data_type, SqlType::Integer);
+}
+
+#[test]
+fn test_data_modifying_cte_archival() {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(
+            "CREATE TABLE orders (id INTEGER PRIMARY KEY, total INTEGER, created_at TIMESTAMP);
+             CREATE TABLE orders_archive (id INTEGER PRIMARY KEY, total INTEGER, created_at TIMESTAMP);
+             CREATE TABLE order_ids (id INTEGER PRIMARY KEY);",
+        )
+        .unwrap();
+    let (catalog, _) = builder.build();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let archive = |target: &str| {
+        format!(
+            "WITH moved AS (\n  DELETE FROM orders WHERE created_at < now() - interval '1 year' RETURNING *\n) \
+             INSERT INTO {} SELECT * FROM moved",
+            target
+        )
+    };
+    let diagnostics = analyzer.analyze(&archive("orders_archive"));
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    let diagnostics = analyzer.analyze(&archive("order_ids"));
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnCountMismatch);
+    assert_eq!(
+        diagnostics[0].message,
+        "INSERT query returns 3 column(s) but 1 column(s) were specified"
+    );
+
+    // The DELETE is checked in place
+    let diagnostics = analyzer.analyze(
+        "WITH moved AS (\n  DELETE FROM orders WHERE craeted_at < now() RETURNING id\n) \
+         SELECT id, total FROM moved",
+    );
+    assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+    assert!(diagnostics[0].message.contains("craeted_at"));
+    let span = diagnostics[0].span.unwrap();
+    assert_eq!((span.line, span.column), (2, 28));
+    assert!(diagnostics[1].message.contains("total"));
+
+    let columns = analyzer
+        .infer_output_schema(
+            "WITH moved AS (DELETE FROM orders WHERE id = 1 RETURNING *) SELECT * FROM moved",
+        )
+        .unwrap();
+    let names: Vec<_> = columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["id", "total", "created_at"]);
+
+    // A DELETE body makes the statement a write
+    let mut analyzer = Analyzer::new(&catalog).with_policy(WritePolicy::ReadOnly);
+    let diagnostics = analyzer.analyze(
+        "WITH moved AS (DELETE FROM orders WHERE id = 1 RETURNING id) SELECT id FROM moved",
+    );
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::PolicyViolation);
+}
+
+#[test]
+fn test_data_modifying_cte_returning() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // UPDATE with explicit RETURNING columns
+    let diagnostics = analyzer.analyze(
+        "WITH paid AS (UPDATE orders SET total = 0 WHERE id = 1 RETURNING id AS order_id, user_id) \
+         SELECT order_id, user_id FROM paid",
+    );
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let diagnostics = analyzer.analyze(
+        "WITH paid AS (UPDATE orders SET total = 0 RETURNING id AS order_id) SELECT total FROM paid",
+    );
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+
+    // INSERT with RETURNING *, joined with the table it inserted into
+    let sql = "WITH added AS (INSERT INTO users (name) VALUES ('a') RETURNING *) \
+               SELECT added.email, o.total FROM added JOIN orders o ON o.user_id = added.id";
+    assert!(analyzer.analyze(sql).is_empty());
+
+    // The body and its RETURNING clause are validated
+    let diagnostics = analyzer.analyze(
+        "WITH gone AS (DELETE FROM users WHERE nme = 'a' RETURNING id, mail) SELECT id FROM gone",
+    );
+    let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(messages.len(), 2, "{:?}", messages);
+    assert!(messages[0].contains("nme") && messages[1].contains("mail"));
+
+    // The CTE's relations aren't in the outer FROM clause
+    let sql = "WITH gone AS (DELETE FROM orders WHERE total = 0 RETURNING user_id) \
+               SELECT u.id FROM users u JOIN gone g ON g.user_id = u.id, orders";
+    assert!(analyzer.analyze(sql).is_empty());
+
+    // RETURNING of a plain statement sees its own tables
+    let diagnostics = analyzer.analyze("UPDATE orders SET total = 1 RETURNING id, totl");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(diagnostics[0].message.contains("totl"));
+    let diagnostics = analyzer.analyze("INSERT INTO users AS u (name) VALUES ('a') RETURNING u.id");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+// ========== CHECK Constraint Tests ==========
+
+#[test]
+fn test_check_constraint_table_level() {
+    let schema_sql = r#"
+            CREATE TABLE products (
+                id SERIAL PRIMARY KEY,
+                name TEXT NOT NULL,
+                price DECIMAL(10, 2) NOT NULL,
+                CONSTRAINT price_positive CHECK (price > 0)
+            );
+        "#;
+
+    let mut builder = SchemaBuilder::new();
+    builder.parse(schema_sql).unwrap();
+    let (catalog, _) = builder.build();
+
+    let table = catalog.get_table(&QualifiedName::new("products")).unwrap();
+    assert_eq!(table.check_constraints.len(), 1);
+    assert_eq!(
+        table.check_constraints[0].name.as_deref(),
+        Some("price_positive")
+    );
+    assert!(table.check_constraints[0].expression.contains("price"));
+
+    // Queries against the table should still work
+    let mut analyzer = Analyzer::new(&catalog);
+    let diagnostics = analyzer.analyze("SELECT id, name, price FROM products");
+    assert!(
+        diagnostics.is_empty(),
         "Valid query on table with CHECK constraint should have no errors: {:?}",
         diagnostics
     );
@@ -597,6 +1280,104 @@ fn test_view_definition_and_query() {
     );
 }
 
+/// orders, a materialized view of their totals per user and a plain view
+fn materialized_view_catalog(unique_index: bool) -> Catalog {
+    let mut schema_sql = String::from(
+        r#"
+            CREATE TABLE orders (id INT PRIMARY KEY, user_id INT NOT NULL, total INT);
+            CREATE INDEX orders_user_id ON orders (user_id);
+            CREATE MATERIALIZED VIEW order_totals AS
+                SELECT user_id, SUM(total) AS total FROM orders GROUP BY user_id;
+            CREATE VIEW big_orders AS SELECT id FROM orders WHERE total > 100;
+        "#,
+    );
+    if unique_index {
+        schema_sql.push_str("CREATE UNIQUE INDEX order_totals_user ON order_totals (user_id);");
+    }
+    let mut builder = SchemaBuilder::new();
+    builder.parse(&schema_sql).unwrap();
+    let (catalog, _) = builder.build();
+    catalog
+}
+
+#[test]
+fn test_materialized_view_write() {
+    let catalog = materialized_view_catalog(false);
+    let mut analyzer = Analyzer::new(&catalog);
+
+    for sql in [
+        "INSERT INTO order_totals (user_id, total) VALUES (1, 2)",
+        "UPDATE order_totals SET total = 0 WHERE user_id = 1",
+        "DELETE FROM order_totals WHERE user_id = 1",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert_eq!(diagnostics.len(), 1, "{}: {:?}", sql, diagnostics);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::RelationKindMismatch);
+        assert_eq!(
+            diagnostics[0].message,
+            "Cannot change materialized view 'order_totals'"
+        );
+    }
+
+    let diagnostics = analyzer.analyze("SELECT user_id, total FROM order_totals");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+#[test]
+fn test_refresh_materialized_view() {
+    let catalog = materialized_view_catalog(false);
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics =
+        analyzer.analyze("REFRESH MATERIALIZED VIEW order_totals;\nSELECT id FROM orders;");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    let diagnostics = analyzer.analyze("REFRESH MATERIALIZED VIEW order_total");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TableNotFound);
+    assert_eq!(
+        diagnostics[0].message,
+        "Materialized view 'order_total' not found"
+    );
+
+    for (sql, message) in [
+        (
+            "REFRESH MATERIALIZED VIEW orders",
+            "'orders' is a table, not a materialized view",
+        ),
+        (
+            "REFRESH MATERIALIZED VIEW big_orders",
+            "'big_orders' is a view, not a materialized view",
+        ),
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::RelationKindMismatch);
+        assert_eq!(diagnostics[0].message, message);
+    }
+
+    // CONCURRENTLY needs a unique index on the view
+    let sql = "REFRESH MATERIALIZED VIEW CONCURRENTLY order_totals";
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].kind,
+        DiagnosticKind::RefreshWithoutUniqueIndex
+    );
+    assert_eq!(diagnostics[0].span.unwrap().column, 40);
+
+    let indexed = materialized_view_catalog(true);
+    let diagnostics = Analyzer::new(&indexed).analyze(sql);
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    // A refresh writes the view
+    let diagnostics = Analyzer::new(&catalog)
+        .with_policy(WritePolicy::ReadOnly)
+        .analyze("REFRESH MATERIALIZED VIEW order_totals");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::PolicyViolation);
+}
+
 #[test]
 fn test_view_column_not_found() {
     let schema_sql = r#"
@@ -716,6 +1497,97 @@ fn test_view_join_with_table() {
     );
 }
 
+#[test]
+fn test_view_columns_of_union_distinct_and_values() {
+    let schema_sql = r#"
+            CREATE TABLE orders (id SERIAL PRIMARY KEY, user_id INTEGER, total DECIMAL(10, 2));
+
+            CREATE VIEW totals AS
+                SELECT user_id, SUM(total) AS total FROM orders GROUP BY user_id
+                UNION ALL SELECT 0, 0;
+            CREATE VIEW buyers AS SELECT DISTINCT o.user_id FROM orders o;
+            CREATE VIEW statuses AS VALUES ('new', 1), ('paid', 2);
+            CREATE VIEW recent AS
+                WITH r AS (SELECT * FROM orders ORDER BY id DESC LIMIT 10) SELECT * FROM r;
+        "#;
+
+    let mut builder = SchemaBuilder::new();
+    builder.parse(schema_sql).unwrap();
+    let (catalog, diagnostics) = builder.build();
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let columns = |name: &str| {
+        catalog
+            .get_view(&QualifiedName::new(name))
+            .unwrap()
+            .columns
+            .clone()
+    };
+    assert_eq!(columns("totals"), ["user_id", "total"]);
+    assert_eq!(columns("buyers"), ["user_id"]);
+    assert_eq!(columns("statuses"), ["column1", "column2"]);
+    assert_eq!(columns("recent"), ["id", "user_id", "total"]);
+
+    let mut analyzer = Analyzer::new(&catalog);
+    for sql in [
+        "SELECT user_id, total FROM totals",
+        "SELECT user_id FROM buyers",
+        "SELECT column1, column2 FROM statuses",
+        "SELECT total FROM recent",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert!(diagnostics.is_empty(), "{}: {:?}", sql, diagnostics);
+    }
+    for (sql, column) in [
+        ("SELECT amount FROM totals", "amount"),
+        ("SELECT id FROM buyers", "id"),
+        ("SELECT status FROM statuses", "status"),
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert_eq!(diagnostics.len(), 1, "{}: {:?}", sql, diagnostics);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+        assert!(
+            diagnostics[0].message.contains(column),
+            "{}",
+            diagnostics[0].message
+        );
+    }
+}
+
+#[test]
+fn test_view_of_unknown_columns() {
+    let schema_sql = r#"
+            CREATE TABLE orders (id SERIAL PRIMARY KEY, user_id INTEGER);
+            CREATE VIEW feed AS SELECT * FROM read_feed('orders') AS f;
+            CREATE VIEW later AS SELECT * FROM pending_feed;
+            CREATE VIEW pending_feed AS SELECT * FROM read_feed('pending') AS f;
+        "#;
+
+    let mut builder = SchemaBuilder::new();
+    builder.parse(schema_sql).unwrap();
+    let (catalog, diagnostics) = builder.build();
+    let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        [
+            "Columns of view 'feed' could not be determined; queries may use any column of it",
+            "Columns of view 'pending_feed' could not be determined; queries may use any column of it",
+            "Columns of view 'later' could not be determined; queries may use any column of it",
+        ]
+    );
+    assert!(diagnostics.iter().all(|d| d.severity == Severity::Warning));
+
+    // Any column of them resolves, alone or next to a table
+    let mut analyzer = Analyzer::new(&catalog);
+    for sql in [
+        "SELECT payload FROM feed",
+        "SELECT l.anything FROM later l",
+        "SELECT o.id, f.payload FROM orders o JOIN feed f ON f.order_id = o.id",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert!(diagnostics.is_empty(), "{}: {:?}", sql, diagnostics);
+    }
+}
+
 // ========== ALTER TABLE Tests ==========
 
 #[test]
@@ -939,6 +1811,81 @@ fn test_derived_table_join() {
     );
 }
 
+#[test]
+fn test_derived_table_on_right_of_join_sees_left_alias() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze(
+        "SELECT u.id, s.cnt FROM users u \
+         JOIN (SELECT user_id, COUNT(*) cnt FROM orders GROUP BY user_id) s ON s.user_id = u.id",
+    );
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    // The derived table's own scope doesn't include the outer relations
+    let diagnostics = analyzer.analyze(
+        "SELECT u.id FROM users u \
+         JOIN (SELECT user_id FROM orders WHERE orders.user_id = u.id) s ON s.user_id = u.id",
+    );
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TableNotFound);
+}
+
+#[test]
+fn test_join_chain_mixing_tables_and_derived_tables() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze(
+        "SELECT u.name, s.cnt, o.total, t.biggest FROM users u \
+         JOIN (SELECT user_id, COUNT(*) cnt FROM orders GROUP BY user_id) s ON s.user_id = u.id \
+         JOIN orders o ON o.user_id = s.user_id AND o.user_id = u.id \
+         LEFT JOIN (SELECT user_id, MAX(total) biggest FROM orders GROUP BY user_id) t \
+           ON t.user_id = o.user_id AND t.user_id = u.id",
+    );
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    // Errors in a later ON clause still point at the right column
+    let diagnostics = analyzer.analyze(
+        "SELECT u.id FROM users u \
+         JOIN (SELECT user_id FROM orders) s ON s.user_id = u.id \
+         JOIN orders o ON o.user_id = s.missing",
+    );
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+    assert!(diagnostics[0].message.contains("missing"));
+
+    // An ON clause only sees the relations to its left
+    let diagnostics = analyzer.analyze(
+        "SELECT u.id FROM users u \
+         JOIN orders o ON o2.id = u.id \
+         JOIN orders o2 ON o2.user_id = o.user_id",
+    );
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TableNotFound);
+    assert_eq!(diagnostics[0].span.unwrap().column, 43);
+}
+
+#[test]
+fn test_lateral_derived_table_sees_outer_relations() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze(
+        "SELECT u.name, l.total FROM users u \
+         JOIN LATERAL (SELECT total FROM orders WHERE orders.user_id = u.id LIMIT 1) l ON true",
+    );
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    // Relations to the left stay visible after the LATERAL subquery
+    let diagnostics = analyzer.analyze(
+        "SELECT u.name FROM users u \
+         CROSS JOIN LATERAL (SELECT total FROM orders WHERE orders.user_id = u.id) l \
+         JOIN orders o ON o.user_id = u.id AND o.total = l.total",
+    );
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
 #[test]
 fn test_derived_table_with_alias_expression() {
     let catalog = setup_catalog();
@@ -997,9 +1944,12 @@ fn test_mysql_schema_parsing() {
     assert!(!id_col.nullable, "AUTO_INCREMENT column should be NOT NULL");
     assert!(id_col.is_primary_key);
 
-    // TINYINT column
+    // TINYINT UNSIGNED column
     let age_col = table.get_column("age").unwrap();
-    assert_eq!(age_col.data_type, SqlType::TinyInt);
+    assert_eq!(
+        age_col.data_type,
+        SqlType::Unsigned(Box::new(SqlType::TinyInt))
+    );
 
     // ENUM column
     let status_col = table.get_column("status").unwrap();
@@ -1009,9 +1959,12 @@ fn test_mysql_schema_parsing() {
         status_col.data_type
     );
 
-    // MEDIUMINT column
+    // MEDIUMINT UNSIGNED column
     let count_col = table.get_column("login_count").unwrap();
-    assert_eq!(count_col.data_type, SqlType::MediumInt);
+    assert_eq!(
+        count_col.data_type,
+        SqlType::Unsigned(Box::new(SqlType::MediumInt))
+    );
 }
 
 #[test]
@@ -1213,7 +2166,7 @@ fn test_large_join_four_tables() {
     let mut analyzer = Analyzer::new(&catalog);
 
     let diagnostics = analyzer.analyze(
-        "SELECT u.name, o.id, p.name, oi.quantity
+        "SELECT u.name, o.id, p.name AS product, oi.quantity
             FROM users u
             JOIN orders o ON u.id = o.user_id
             JOIN order_items oi ON o.id = oi.order_id
@@ -1322,6 +2275,52 @@ fn test_ambiguous_column_in_complex_join() {
     assert!(diagnostics[0].message.contains("ambiguous"));
 }
 
+#[test]
+fn test_unqualified_column_in_one_of_joined_cte_and_tables() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    for sql in [
+        "WITH o AS (SELECT id, user_id FROM orders) \
+         SELECT user_id FROM o JOIN users ON users.id = o.user_id",
+        // Three relations, the column in exactly one of them
+        "SELECT total FROM users u JOIN orders o ON o.user_id = u.id \
+         JOIN (SELECT id AS oid FROM orders) s ON s.oid = o.id",
+        "WITH recent AS (SELECT id AS order_id FROM orders) \
+         SELECT email, order_id FROM users JOIN orders ON orders.user_id = users.id \
+         JOIN recent ON recent.order_id = orders.id",
+        // A schema-qualified name is the catalog table, not the CTE it shadows
+        "WITH orders AS (SELECT 1 AS x) SELECT user_id FROM public.orders",
+        "WITH users AS (SELECT id FROM public.users) \
+         SELECT name FROM users JOIN public.users u ON u.id = users.id",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert!(diagnostics.is_empty(), "{}: {:?}", sql, diagnostics);
+    }
+}
+
+#[test]
+fn test_ambiguous_column_names_view_and_table_by_alias() {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(
+            "CREATE TABLE users (id INT PRIMARY KEY, name TEXT NOT NULL);
+             CREATE VIEW active_users AS SELECT id, name FROM users;",
+        )
+        .unwrap();
+    let (catalog, _) = builder.build();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics =
+        analyzer.analyze("SELECT name FROM public.active_users v JOIN users u ON u.id = v.id");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::AmbiguousColumn);
+    assert_eq!(
+        diagnostics[0].message,
+        "Column 'name' is ambiguous (found in tables: u, v)"
+    );
+}
+
 #[test]
 fn test_union_column_count_validation() {
     let catalog = setup_catalog();
@@ -1385,3 +2384,3904 @@ fn test_natural_join() {
         diagnostics
     );
 }
+
+// ========== Alias Column List Tests ==========
+
+#[test]
+fn test_derived_table_alias_too_many_columns() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze("SELECT * FROM (SELECT id FROM users) AS t(a, b)");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnCountMismatch);
+    assert!(diagnostics[0].message.contains("1 column(s) available"));
+    assert!(diagnostics[0].span.is_some());
+}
+
+#[test]
+fn test_derived_table_alias_partial_list_postgres() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // PostgreSQL renames the leading columns and keeps the rest
+    let diagnostics =
+        analyzer.analyze("SELECT t.a, t.name FROM (SELECT id, name FROM users) AS t(a)");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+#[test]
+fn test_derived_table_alias_partial_list_mysql() {
+    let catalog = setup_mysql_catalog();
+    let mut analyzer = Analyzer::with_dialect(&catalog, SqlDialect::MySQL);
+
+    let diagnostics = analyzer.analyze("SELECT * FROM (SELECT id, username FROM users) AS t(a)");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnCountMismatch);
+}
+
+#[test]
+fn test_cte_alias_too_many_columns() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze("WITH t(a, b, c) AS (SELECT id FROM users) SELECT a FROM t");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnCountMismatch);
+    assert!(diagnostics[0].message.contains("CTE 't'"));
+}
+
+#[test]
+fn test_cte_alias_counts_wildcard_columns() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // users has three columns, so three aliases match the expanded wildcard
+    let diagnostics =
+        analyzer.analyze("WITH t(a, b, c) AS (SELECT * FROM users) SELECT a, b, c FROM t");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    let diagnostics =
+        analyzer.analyze("WITH t(a, b, c, d) AS (SELECT * FROM users) SELECT a FROM t");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnCountMismatch);
+}
+
+#[test]
+fn test_alias_count_skipped_for_unknown_width() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // Wildcard over an unknown table-valued function cannot be expanded
+    let diagnostics =
+        analyzer.analyze("SELECT * FROM (SELECT * FROM my_func(1) AS f) AS t(a, b, c, d, e)");
+    assert!(
+        diagnostics
+            .iter()
+            .all(|d| d.kind != DiagnosticKind::ColumnCountMismatch),
+        "{:?}",
+        diagnostics
+    );
+}
+
+#[test]
+fn test_table_function_alias_count() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze("SELECT n FROM generate_series(1, 10) AS g(n)");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    let diagnostics = analyzer.analyze("SELECT n FROM generate_series(1, 10) AS g(n, m)");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnCountMismatch);
+}
+
+// ========== Cross-Schema Lookup Tests ==========
+
+fn setup_multi_schema_catalog() -> Catalog {
+    let schema_sql = r#"
+            CREATE TABLE users (id INTEGER PRIMARY KEY);
+            CREATE TABLE analytics.events (id INTEGER, name TEXT);
+            CREATE TABLE analytics.sessions (id INTEGER);
+            CREATE VIEW reporting.sessions AS SELECT id FROM analytics.sessions;
+            CREATE VIEW reporting.daily AS SELECT id FROM analytics.events;
+        "#;
+
+    let mut builder = SchemaBuilder::new();
+    builder.parse(schema_sql).unwrap();
+    let (catalog, _) = builder.build();
+    catalog
+}
+
+#[test]
+fn test_table_in_other_schema() {
+    let catalog = setup_multi_schema_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze("SELECT * FROM events");
+    // Table not found error should be first
+    assert!(!diagnostics.is_empty());
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TableNotFound);
+    assert_eq!(
+        diagnostics[0].message,
+        "Table 'events' not found in schema 'public'; a table with that name exists in schema 'analytics'"
+    );
+    assert!(diagnostics[0]
+        .help
+        .as_ref()
+        .unwrap()
+        .contains("analytics.events"));
+}
+
+#[test]
+fn test_table_in_multiple_other_schemas() {
+    let catalog = setup_multi_schema_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze("SELECT * FROM sessions");
+    // Table not found error should be first
+    assert!(!diagnostics.is_empty());
+    assert!(diagnostics[0]
+        .message
+        .contains("exists in schemas 'analytics', 'reporting'"));
+    let help = diagnostics[0].help.as_ref().unwrap();
+    assert!(help.contains("analytics.sessions"));
+    assert!(help.contains("reporting.sessions"));
+}
+
+#[test]
+fn test_table_in_other_database_mysql() {
+    // MySQL's default schema is the current database, which has no name
+    let mut builder = SchemaBuilder::with_dialect(SqlDialect::MySQL);
+    builder
+        .parse("CREATE TABLE users (id INT); CREATE TABLE shop.orders (id INT);")
+        .unwrap();
+    let (catalog, _) = builder.build();
+    let mut analyzer = Analyzer::with_dialect(&catalog, SqlDialect::MySQL);
+
+    let diagnostics = analyzer.analyze("SELECT 1 FROM orders");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(
+        diagnostics[0].message,
+        "Table 'orders' not found; a table with that name exists in schema 'shop'"
+    );
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("Qualify the name (shop.orders) or add the schema to the search path")
+    );
+
+    let diagnostics = analyzer.analyze("SELECT 1 FROM shop.users");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(
+        diagnostics[0].message,
+        "Table 'users' not found in schema 'shop'; a table with that name exists in the default schema"
+    );
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("Qualify the name (users) or add the schema to the search path")
+    );
+}
+
+#[test]
+fn test_view_in_other_schema() {
+    let catalog = setup_multi_schema_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze("SELECT * FROM daily");
+    // Table not found error should be first
+    assert!(!diagnostics.is_empty());
+    assert!(diagnostics[0]
+        .message
+        .contains("a view with that name exists in schema 'reporting'"));
+}
+
+#[test]
+fn test_dml_target_in_other_schema() {
+    let catalog = setup_multi_schema_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    for sql in [
+        "INSERT INTO events (id) VALUES (1)",
+        "UPDATE events SET name = 'x'",
+        "DELETE FROM events",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert_eq!(diagnostics.len(), 1, "{}: {:?}", sql, diagnostics);
+        assert!(
+            diagnostics[0].message.contains("schema 'analytics'"),
+            "{}: {:?}",
+            sql,
+            diagnostics
+        );
+    }
+
+    // Qualified references resolve normally
+    let diagnostics = analyzer.analyze("SELECT id FROM analytics.events");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+fn setup_same_name_catalog() -> Catalog {
+    let schema_sql = r#"
+            CREATE TABLE invoices (id INTEGER PRIMARY KEY, total INTEGER, legacy_code TEXT);
+            CREATE SCHEMA billing;
+            CREATE TABLE billing.invoices (
+                id INTEGER PRIMARY KEY,
+                amount INTEGER,
+                legacy_id INTEGER REFERENCES public.invoices (id)
+            );
+        "#;
+
+    let mut builder = SchemaBuilder::new();
+    builder.parse(schema_sql).unwrap();
+    let (catalog, _) = builder.build();
+    catalog
+}
+
+#[test]
+fn test_dml_targets_with_same_name_in_two_schemas() {
+    let catalog = setup_same_name_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    for sql in [
+        "INSERT INTO billing.invoices (id, amount) SELECT id, total FROM public.invoices",
+        "UPDATE billing.invoices SET amount = public.invoices.total FROM public.invoices \
+         WHERE billing.invoices.legacy_id = public.invoices.id",
+        "UPDATE billing.invoices SET amount = 0 FROM invoices \
+         WHERE billing.invoices.legacy_id = public.invoices.id AND legacy_code IS NULL",
+        "DELETE FROM billing.invoices USING public.invoices \
+         WHERE billing.invoices.legacy_id = public.invoices.id AND public.invoices.total = 0",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert!(diagnostics.is_empty(), "{}: {:?}", sql, diagnostics);
+    }
+
+    // Columns of the other schema's table are reported against the target,
+    // by the name the statement used
+    for sql in [
+        "INSERT INTO billing.invoices (id, total) VALUES (1, 2)",
+        "UPDATE billing.invoices SET total = 1",
+        "UPDATE billing.invoices SET amount = 1 FROM public.invoices \
+         WHERE billing.invoices.total = public.invoices.total",
+        "DELETE FROM billing.invoices USING public.invoices \
+         WHERE billing.invoices.total = public.invoices.total",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert_eq!(diagnostics.len(), 1, "{}: {:?}", sql, diagnostics);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+        assert_eq!(
+            diagnostics[0].message, "Column 'total' not found in table 'billing.invoices'",
+            "{}",
+            sql
+        );
+    }
+
+    // A schema-qualified reference to a table that isn't in the FROM clause
+    let diagnostics =
+        analyzer.analyze("UPDATE invoices SET total = 1 WHERE billing.invoices.id = 1");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TableNotFound);
+    assert_eq!(
+        diagnostics[0].message,
+        "Table 'billing.invoices' not found in FROM clause"
+    );
+}
+
+#[test]
+fn test_join_of_same_name_tables_in_two_schemas() {
+    let catalog = setup_same_name_catalog();
+    let mut analyzer = Analyzer::new(&catalog).with_enabled_rules(["W0005"]);
+
+    let diagnostics = analyzer.analyze(
+        "SELECT billing.invoices.*, public.invoices.total \
+         FROM billing.invoices JOIN public.invoices ON billing.invoices.legacy_id = public.invoices.id \
+         WHERE billing.invoices.legacy_id = public.invoices.id",
+    );
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    let diagnostics = analyzer.analyze(
+        "SELECT invoices.amount, id FROM billing.invoices, public.invoices \
+         WHERE billing.invoices.amount > public.invoices.total",
+    );
+    let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Comparison 'billing.invoices.amount > public.invoices.total' relates columns of \
+             different tables outside a join",
+            "Table reference 'invoices' is ambiguous (found in: billing.invoices, public.invoices)",
+            "Column 'id' is ambiguous (found in tables: billing.invoices, public.invoices)",
+        ]
+    );
+    assert_eq!(
+        diagnostics[1].help.as_deref(),
+        Some("Qualify the reference with the schema: billing.invoices.amount")
+    );
+
+    // The foreign key relates the two tables across schemas
+    let diagnostics = analyzer.analyze(
+        "SELECT 1 FROM billing.invoices, public.invoices \
+         WHERE billing.invoices.legacy_id = public.invoices.id",
+    );
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+// ========== Column Comment Tests ==========
+
+#[test]
+fn test_did_you_mean_includes_column_comment() {
+    let schema_sql = r#"
+        CREATE TABLE users (id SERIAL PRIMARY KEY, email TEXT, name TEXT);
+        COMMENT ON COLUMN users.email IS 'primary contact address';
+    "#;
+    let mut builder = SchemaBuilder::new();
+    builder.parse(schema_sql).unwrap();
+    let (catalog, _) = builder.build();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze("SELECT emial FROM users");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("Did you mean 'email'? (email: primary contact address)")
+    );
+
+    // Columns without a comment keep the plain suggestion
+    let diagnostics = analyzer.analyze("SELECT nmae FROM users");
+    assert_eq!(diagnostics[0].help.as_deref(), Some("Did you mean 'name'?"));
+}
+
+// ========== MySQL Integer Attribute Tests ==========
+
+#[test]
+fn test_mysql_tinyint1_is_boolean() {
+    let catalog = setup_mysql_catalog();
+    let posts = catalog.get_table(&QualifiedName::new("posts")).unwrap();
+    assert_eq!(
+        posts.get_column("is_published").unwrap().data_type,
+        SqlType::Boolean
+    );
+
+    // Comparisons with integers stay valid, since BOOLEAN is TINYINT(1) in MySQL
+    let mut analyzer = Analyzer::with_dialect(&catalog, SqlDialect::MySQL);
+    for sql in [
+        "SELECT id FROM posts WHERE is_published = 1",
+        "SELECT id FROM posts WHERE is_published = TRUE",
+        "SELECT id FROM posts WHERE is_published",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert!(diagnostics.is_empty(), "{}: {:?}", sql, diagnostics);
+    }
+}
+
+#[test]
+fn test_mysql_tinyint1_as_boolean_disabled() {
+    let mut builder =
+        SchemaBuilder::with_dialect(SqlDialect::MySQL).with_tinyint1_as_boolean(false);
+    builder
+        .parse("CREATE TABLE posts (id INT, is_published TINYINT(1));")
+        .unwrap();
+    let (catalog, _) = builder.build();
+
+    let posts = catalog.get_table(&QualifiedName::new("posts")).unwrap();
+    assert_eq!(
+        posts.get_column("is_published").unwrap().data_type,
+        SqlType::TinyInt
+    );
+}
+
+#[test]
+fn test_mysql_negative_literal_on_unsigned_column() {
+    let catalog = setup_mysql_catalog();
+    let mut analyzer = Analyzer::with_dialect(&catalog, SqlDialect::MySQL);
+
+    let diagnostics = analyzer.analyze("SELECT id FROM users WHERE age = -1");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TypeMismatch);
+    assert_eq!(diagnostics[0].severity, sqlsurge_core::Severity::Warning);
+    assert!(diagnostics[0].message.contains("tinyint unsigned"));
+    // At the column, as the literal has no span
+    let span = diagnostics[0].span.unwrap();
+    assert_eq!((span.line, span.column), (1, 28));
+
+    let diagnostics = analyzer.analyze(
+        "INSERT INTO users (username, email, login_count) VALUES ('a', 'a@example.com', -5)",
+    );
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(diagnostics[0].message.contains("login_count"));
+    let span = diagnostics[0].span.unwrap();
+    assert_eq!((span.line, span.column), (1, 37));
+
+    let diagnostics = analyzer.analyze("UPDATE users SET login_count = -1 WHERE id = 1");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TypeMismatch);
+    assert!(diagnostics[0].message.contains("login_count"));
+    let span = diagnostics[0].span.unwrap();
+    assert_eq!((span.line, span.column), (1, 18));
+
+    // Non-negative values and signed columns are fine
+    let diagnostics = analyzer.analyze("SELECT id FROM users WHERE age > 0 AND id > -1");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+// ========== Generated Column Tests ==========
+
+fn setup_generated_catalog() -> Catalog {
+    let schema_sql = r#"
+        CREATE TABLE people (
+            id SERIAL PRIMARY KEY,
+            first_name TEXT,
+            last_name TEXT,
+            full_name TEXT GENERATED ALWAYS AS (first_name || ' ' || last_name) STORED
+        );
+    "#;
+    let mut builder = SchemaBuilder::new();
+    builder.parse(schema_sql).unwrap();
+    let (catalog, _) = builder.build();
+    catalog
+}
+
+#[test]
+fn test_insert_into_generated_column() {
+    let catalog = setup_generated_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer
+        .analyze("INSERT INTO people (first_name, last_name, full_name) VALUES ('a', 'b', 'a b')");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(
+        diagnostics[0].kind,
+        DiagnosticKind::GeneratedColumnAssignment
+    );
+    assert!(diagnostics[0].message.contains("full_name"));
+
+    // Without a column list, the value lines up with the generated column
+    let diagnostics = analyzer.analyze("INSERT INTO people VALUES (1, 'a', 'b', 'a b')");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(
+        diagnostics[0].kind,
+        DiagnosticKind::GeneratedColumnAssignment
+    );
+
+    // DEFAULT is accepted
+    let diagnostics = analyzer.analyze("INSERT INTO people VALUES (1, 'a', 'b', DEFAULT)");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let diagnostics =
+        analyzer.analyze("INSERT INTO people (first_name, last_name) VALUES ('a', 'b')");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+#[test]
+fn test_update_generated_column() {
+    let catalog = setup_generated_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze("UPDATE people SET full_name = 'x' WHERE id = 1");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(
+        diagnostics[0].kind,
+        DiagnosticKind::GeneratedColumnAssignment
+    );
+
+    let diagnostics = analyzer.analyze("UPDATE people SET full_name = DEFAULT, first_name = 'x'");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+#[test]
+fn test_insert_identity_overriding() {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(
+            "CREATE TABLE accounts (id INT GENERATED ALWAYS AS IDENTITY, name TEXT);
+             CREATE TABLE events (id INT GENERATED BY DEFAULT AS IDENTITY, name TEXT);",
+        )
+        .unwrap();
+    let (catalog, _) = builder.build();
+    let mut analyzer = Analyzer::new(&catalog);
+    let kinds = |diagnostics: &[sqlsurge_core::Diagnostic]| -> Vec<(DiagnosticKind, Severity)> {
+        diagnostics.iter().map(|d| (d.kind, d.severity)).collect()
+    };
+
+    // GENERATED ALWAYS rejects values unless OVERRIDING SYSTEM VALUE
+    let diagnostics = analyzer.analyze("INSERT INTO accounts (id, name) VALUES (1, 'x')");
+    assert_eq!(
+        kinds(&diagnostics),
+        [(DiagnosticKind::GeneratedColumnAssignment, Severity::Error)]
+    );
+    assert!(diagnostics[0]
+        .message
+        .contains("GENERATED ALWAYS AS IDENTITY"));
+    assert_eq!(diagnostics[0].span.unwrap().column, 23);
+    let diagnostics =
+        analyzer.analyze("INSERT INTO accounts (id, name) OVERRIDING SYSTEM VALUE VALUES (1, 'x')");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let diagnostics = analyzer.analyze("INSERT INTO accounts (id, name) VALUES (DEFAULT, 'x')");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    // BY DEFAULT takes the values, unless OVERRIDING USER VALUE
+    let diagnostics = analyzer.analyze("INSERT INTO events (id, name) VALUES (1, 'x')");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let diagnostics =
+        analyzer.analyze("INSERT INTO events (id, name) OVERRIDING SYSTEM VALUE VALUES (1, 'x')");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let diagnostics =
+        analyzer.analyze("INSERT INTO events AS e\nOVERRIDING USER VALUE VALUES (1, 'x')");
+    assert_eq!(
+        kinds(&diagnostics),
+        [(DiagnosticKind::IgnoredIdentityValue, Severity::Info)]
+    );
+    let diagnostics =
+        analyzer.analyze("INSERT INTO accounts (id, name) OVERRIDING USER VALUE VALUES (1, 'x')");
+    assert_eq!(
+        kinds(&diagnostics),
+        [(DiagnosticKind::IgnoredIdentityValue, Severity::Info)]
+    );
+    let diagnostics =
+        analyzer.analyze("INSERT INTO events (name) OVERRIDING USER VALUE VALUES ('x')");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+// ========== Duplicate Insert Key Tests ==========
+
+fn setup_unique_catalog() -> Catalog {
+    let (catalog, diagnostics) = CatalogBuilder::new()
+        .table("roles", |t| {
+            t.column("id", SqlType::Integer, |c| c.primary_key())
+                .column("name", SqlType::Text, |c| c.not_null())
+                .unique(["name"])
+        })
+        .table("memberships", |t| {
+            t.column("user_id", SqlType::Integer, |c| c.not_null())
+                .column("role_id", SqlType::Integer, |c| c.not_null())
+                .column("note", SqlType::Text, |c| c)
+                .primary_key(["user_id", "role_id"])
+        })
+        .build();
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    catalog
+}
+
+#[test]
+fn test_duplicate_insert_key_primary_key() {
+    let catalog = setup_unique_catalog();
+    let mut analyzer = Analyzer::new(&catalog).with_enabled_rules(["W0001"]);
+
+    let diagnostics = analyzer
+        .analyze("INSERT INTO roles (id, name) VALUES (1, 'admin'), (2, 'user'), (1, 'auditor')");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::DuplicateInsertKey);
+    assert!(diagnostics[0].message.contains("Row 3"));
+    assert!(diagnostics[0].message.contains("row 1"));
+    let span = diagnostics[0].span.as_ref().unwrap();
+    assert_eq!((span.line, span.column, span.length), (1, 20, 2));
+}
+
+#[test]
+fn test_duplicate_insert_key_unique_column() {
+    let catalog = setup_unique_catalog();
+    let mut analyzer = Analyzer::new(&catalog).with_enabled_rules(["duplicate-insert-key"]);
+
+    let diagnostics =
+        analyzer.analyze("INSERT INTO roles (id, name) VALUES (1, 'admin'), (2, 'admin')");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(diagnostics[0].message.contains("(name)"));
+    let span = diagnostics[0].span.as_ref().unwrap();
+    assert_eq!((span.line, span.column, span.length), (1, 24, 4));
+
+    // Without a column list, the warning points at the table name
+    let diagnostics = analyzer.analyze("INSERT INTO roles VALUES (1, 'admin'), (2, 'admin')");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    let span = diagnostics[0].span.as_ref().unwrap();
+    assert_eq!((span.line, span.column, span.length), (1, 13, 5));
+}
+
+#[test]
+fn test_duplicate_insert_key_composite() {
+    let catalog = setup_unique_catalog();
+    let mut analyzer = Analyzer::new(&catalog).with_enabled_rules(["W0001"]);
+
+    // Only the full (user_id, role_id) pair must be unique
+    let diagnostics = analyzer
+        .analyze("INSERT INTO memberships (user_id, role_id) VALUES (1, 1), (1, 2), (2, 1)");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    let diagnostics = analyzer
+        .analyze("INSERT INTO memberships (user_id, role_id) VALUES (1, 1), (1, 2), (1, 1)");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(diagnostics[0].message.contains("(user_id, role_id)"));
+}
+
+#[test]
+fn test_duplicate_insert_key_ignores_non_literals() {
+    let catalog = setup_unique_catalog();
+    let mut analyzer = Analyzer::new(&catalog).with_enabled_rules(["W0001"]);
+
+    for sql in [
+        "INSERT INTO roles (id, name) VALUES ($1, 'a'), ($1, 'b')",
+        "INSERT INTO roles (id, name) VALUES (DEFAULT, 'a'), (DEFAULT, 'b')",
+        "INSERT INTO roles (id, name) VALUES (1 + 1, 'a'), (1 + 1, 'b')",
+        "INSERT INTO roles (id, name) VALUES (NULL, 'a'), (NULL, 'b')",
+    ] {
+        // DEFAULT for roles.id is E0004, which isn't what this test is about
+        let diagnostics: Vec<_> = analyzer
+            .analyze(sql)
+            .into_iter()
+            .filter(|d| d.kind == DiagnosticKind::DuplicateInsertKey)
+            .collect();
+        assert!(diagnostics.is_empty(), "{}: {:?}", sql, diagnostics);
+    }
+}
+
+#[test]
+fn test_duplicate_insert_key_disabled_by_default() {
+    let catalog = setup_unique_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics =
+        analyzer.analyze("INSERT INTO roles (id, name) VALUES (1, 'admin'), (1, 'admin')");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+// ========== Session State Tests ==========
+
+#[test]
+fn test_session_temp_table_visible_after_create() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let sql = r#"
+        BEGIN;
+        SET LOCAL statement_timeout = '5s';
+        CREATE TEMPORARY TABLE renames (user_id INTEGER, new_name TEXT);
+        INSERT INTO renames (user_id, new_name) VALUES (1, 'alice');
+        UPDATE users SET name = renames.new_name FROM renames WHERE users.id = renames.user_id;
+        DROP TABLE renames;
+        COMMIT;
+    "#;
+    let diagnostics = analyzer.analyze(sql);
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+#[test]
+fn test_session_temp_table_used_before_create() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let sql = r#"
+        SELECT user_id FROM staging;
+        CREATE TEMP TABLE staging (user_id INTEGER);
+        SELECT user_id FROM staging;
+    "#;
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TableNotFound);
+    assert!(
+        diagnostics.iter().all(|d| d.span.unwrap().line == 2),
+        "{:?}",
+        diagnostics
+    );
+}
+
+#[test]
+fn test_session_table_used_after_drop() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let sql = r#"
+        CREATE TEMP TABLE staging (user_id INTEGER);
+        DROP TABLE staging;
+        SELECT user_id FROM staging;
+    "#;
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TableNotFound);
+    assert!(
+        diagnostics.iter().all(|d| d.span.unwrap().line == 4),
+        "{:?}",
+        diagnostics
+    );
+
+    // Dropping a catalog table only affects the rest of this file
+    let diagnostics = analyzer.analyze("DROP TABLE orders; SELECT id FROM orders;");
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TableNotFound);
+    let diagnostics = analyzer.analyze("SELECT id FROM orders");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+#[test]
+fn test_session_alter_table_in_file() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let sql = r#"
+        ALTER TABLE users ADD COLUMN nickname TEXT;
+        SELECT nickname FROM users;
+    "#;
+    let diagnostics = analyzer.analyze(sql);
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+#[test]
+fn test_session_temp_table_on_commit_drop() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let sql = r#"
+        BEGIN;
+        CREATE TEMP TABLE scratch (id INTEGER) ON COMMIT DROP;
+        SELECT id FROM scratch;
+        COMMIT;
+        SELECT id FROM scratch;
+    "#;
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TableNotFound);
+    assert!(
+        diagnostics.iter().all(|d| d.span.unwrap().line == 6),
+        "{:?}",
+        diagnostics
+    );
+}
+
+#[test]
+fn test_session_create_table_as_select() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let sql = r#"
+        CREATE TABLE tmp_report AS
+            SELECT u.id, u.name AS user_name, COUNT(o.id) AS order_count
+            FROM users u LEFT JOIN orders o ON o.user_id = u.id
+            GROUP BY u.id, u.name;
+        INSERT INTO tmp_report (id, user_name, order_count) VALUES (1, 'alice', 2);
+        SELECT user_nme FROM tmp_report;
+    "#;
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+    assert_eq!(diagnostics[0].message, "Column 'user_nme' not found");
+    assert_eq!(diagnostics[0].span.unwrap().line, 7);
+
+    // The projection itself is checked
+    let diagnostics = analyzer.analyze("CREATE TABLE t AS SELECT nme FROM users WHERE id = 'x'");
+    let kinds: Vec<_> = diagnostics.iter().map(|d| d.kind).collect();
+    assert_eq!(
+        kinds,
+        [DiagnosticKind::ColumnNotFound, DiagnosticKind::TypeMismatch]
+    );
+}
+
+#[test]
+fn test_session_select_into() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let sql = r#"
+        SELECT id, name INTO TEMP temp_results FROM users WHERE id > 1;
+        INSERT INTO temp_results (id, name) VALUES (1, 'alice');
+        SELECT nme FROM temp_results;
+    "#;
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].message, "Column 'nme' not found");
+    assert_eq!(diagnostics[0].span.unwrap().line, 4);
+}
+
+#[test]
+fn test_session_table_from_query_name_collision() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze("CREATE TABLE users AS SELECT 1 AS x; SELECT x FROM users");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::DuplicateDefinition);
+    assert_eq!(diagnostics[0].severity, sqlsurge_core::Severity::Warning);
+    assert_eq!(diagnostics[0].message, "Table 'users' already exists");
+
+    // IF NOT EXISTS keeps the existing table
+    let diagnostics =
+        analyzer.analyze("CREATE TABLE IF NOT EXISTS users AS SELECT 1 AS x; SELECT x FROM users");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+}
+
+// ========== Nesting Depth Tests ==========
+
+#[test]
+fn test_long_or_chain_does_not_overflow() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let conditions: Vec<String> = (0..5000).map(|i| format!("id = {}", i)).collect();
+    let sql = format!("SELECT name FROM users WHERE {}", conditions.join(" OR "));
+    let diagnostics = analyzer.analyze(&sql);
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    // Errors deep inside the chain are still found
+    let sql = format!(
+        "SELECT name FROM users WHERE {} OR nope = 1",
+        conditions.join(" OR ")
+    );
+    let diagnostics = analyzer.analyze(&sql);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+}
+
+#[test]
+fn test_long_arithmetic_chain_does_not_overflow() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let terms: Vec<String> = (0..5000).map(|_| "id".to_string()).collect();
+    let sql = format!("SELECT name FROM users WHERE {} = 1", terms.join(" + "));
+    let diagnostics = analyzer.analyze(&sql);
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+#[test]
+fn test_nesting_depth_limit() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog).with_max_depth(4);
+
+    let diagnostics = analyzer.analyze("SELECT name FROM users WHERE ((((((nope = 1))))))");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::NestingTooDeep);
+    assert_eq!(diagnostics[0].severity, sqlsurge_core::Severity::Warning);
+
+    // Within the limit, analysis is complete
+    let diagnostics = analyzer.analyze("SELECT name FROM users WHERE (nope = 1)");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+}
+
+#[test]
+fn test_parser_recursion_limit() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // Past the parser's recursion limit the statement is skipped with a
+    // warning, not reported as a syntax error
+    let sql = format!(
+        "SELECT {}1{}; SELECT nope FROM users",
+        "(".repeat(DEFAULT_MAX_DEPTH + 10),
+        ")".repeat(DEFAULT_MAX_DEPTH + 10)
+    );
+    let diagnostics = analyzer.analyze(&sql);
+    assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::NestingTooDeep);
+    assert_eq!(diagnostics[0].severity, sqlsurge_core::Severity::Warning);
+    assert_eq!(diagnostics[1].kind, DiagnosticKind::ColumnNotFound);
+}
+
+#[test]
+fn test_default_max_depth_boundary() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+    let nested = |n: usize| {
+        format!(
+            "SELECT name FROM users WHERE {}nope{} = 1",
+            "(".repeat(n),
+            ")".repeat(n)
+        )
+    };
+
+    // Up to the default, analysis is complete
+    let diagnostics = analyzer.analyze(&nested(DEFAULT_MAX_DEPTH - 3));
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+
+    // One level more is reported as too deep, not as a syntax error
+    let diagnostics = analyzer.analyze(&nested(DEFAULT_MAX_DEPTH - 2));
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::NestingTooDeep);
+    assert!(diagnostics[0]
+        .message
+        .contains(&format!("limit of {} levels", DEFAULT_MAX_DEPTH)));
+
+    // Streamed statements are analyzed with the same stack
+    let mut found = Vec::new();
+    analyzer.analyze_streaming(
+        &format!(
+            "{};\n{}",
+            nested(DEFAULT_MAX_DEPTH - 3),
+            nested(DEFAULT_MAX_DEPTH - 2)
+        ),
+        |diagnostic| found.push(diagnostic),
+    );
+    assert_eq!(found.len(), 2, "{:?}", found);
+    assert_eq!(found[0].kind, DiagnosticKind::ColumnNotFound);
+    assert_eq!(found[1].kind, DiagnosticKind::NestingTooDeep);
+    assert_eq!(found[1].statement.as_ref().unwrap().index, 1);
+}
+
+#[test]
+fn test_max_depth_above_default() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog).with_max_depth(1000);
+    let nested = |n: usize| {
+        format!(
+            "SELECT name FROM users WHERE {}nope{} = 1",
+            "(".repeat(n),
+            ")".repeat(n)
+        )
+    };
+
+    // A higher limit is honored, by the parser and the analysis alike
+    let diagnostics = analyzer.analyze(&nested(990));
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+
+    let diagnostics = analyzer.analyze(&nested(1000));
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::NestingTooDeep);
+    assert!(diagnostics[0].message.contains("limit of 1000 levels"));
+}
+
+#[test]
+fn test_statement_size_limit() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let list = (0..100_000)
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let sql = format!(
+        "SELECT name FROM users WHERE id IN ({});\nSELECT nope FROM users;",
+        list
+    );
+    let start = std::time::Instant::now();
+    let diagnostics = analyzer.analyze(&sql);
+    assert!(
+        start.elapsed() < std::time::Duration::from_secs(5),
+        "took {:?}",
+        start.elapsed()
+    );
+    assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::StatementTooLarge);
+    assert_eq!(diagnostics[0].severity, sqlsurge_core::Severity::Warning);
+    assert_eq!(diagnostics[0].span.unwrap().offset, 0);
+    // The statements after the oversized one are still analyzed
+    assert_eq!(diagnostics[1].kind, DiagnosticKind::ColumnNotFound);
+
+    // The limit is configurable
+    let mut analyzer = Analyzer::new(&catalog).with_max_statement_bytes(20);
+    let diagnostics = analyzer.analyze("SELECT id FROM users WHERE id = 1; SELECT 1");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::StatementTooLarge);
+}
+
+#[test]
+fn test_statement_by_statement_parsing_is_linear() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // A parse error makes the analyzer parse each statement on its own
+    let sql = format!(
+        "SELECT FROM WHERE;\n{}  SELECT nope FROM users;\nSELECT (1 2;",
+        "SELECT id FROM users;\n".repeat(20_000)
+    );
+    let start = std::time::Instant::now();
+    let diagnostics = analyzer.analyze(&sql);
+    assert!(
+        start.elapsed() < std::time::Duration::from_secs(10),
+        "took {:?}",
+        start.elapsed()
+    );
+    assert_eq!(diagnostics.len(), 3, "{:?}", diagnostics);
+    let missing = diagnostics
+        .iter()
+        .find(|d| d.kind == DiagnosticKind::ColumnNotFound)
+        .unwrap();
+    let span = missing.span.unwrap();
+    assert_eq!((span.line, span.column), (20_002, 10));
+    // Parser errors name the position in the input
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| d.message.ends_with("found: 2 at Line: 20003, Column: 11")),
+        "{:?}",
+        diagnostics
+    );
+}
+
+#[test]
+fn test_input_too_large() {
+    let catalog = setup_catalog();
+    let sql = "SELECT nope FROM users;\nSELECT id FROM users;\nSELECT id FROM orders;";
+
+    // Over either limit, the input is skipped with one warning
+    let mut analyzer = Analyzer::new(&catalog).with_max_file_size(40);
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::InputTooLarge);
+    assert_eq!(
+        diagnostics[0].message,
+        "Input of 68 bytes exceeds the size limit of 40 bytes (`max_file_size`) and was not analyzed"
+    );
+    assert_eq!(diagnostics[0].span.unwrap().offset, 0);
+
+    let mut analyzer = Analyzer::new(&catalog).with_max_statements_per_file(2);
+    let mut streamed = Vec::new();
+    analyzer.analyze_streaming(sql, |d| streamed.push(d));
+    assert_eq!(streamed.len(), 1, "{:?}", streamed);
+    assert!(streamed[0]
+        .message
+        .starts_with("Input of 3 statements exceeds"));
+
+    // Within both, it is analyzed
+    let mut analyzer = Analyzer::new(&catalog)
+        .with_max_file_size(5 * 1024 * 1024)
+        .with_max_statements_per_file(3);
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+}
+
+// ========== Set Operation ORDER BY Tests ==========
+
+#[test]
+fn test_union_order_by_output_alias() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze(
+        "SELECT id AS user_id FROM users UNION SELECT user_id FROM orders ORDER BY user_id",
+    );
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    let diagnostics = analyzer.analyze(
+        "SELECT id, name FROM users UNION ALL SELECT id, user_id FROM orders ORDER BY 2, 1",
+    );
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+#[test]
+fn test_union_order_by_column_not_in_output() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // `email` exists in users, but is not an output column of the UNION
+    let diagnostics =
+        analyzer.analyze("SELECT id FROM users UNION SELECT user_id FROM orders ORDER BY email");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+    assert!(diagnostics[0].message.contains("output column"));
+    assert!(diagnostics[0].help.as_ref().unwrap().contains("(id)"));
+
+    // Output columns are named by the left arm only
+    let diagnostics =
+        analyzer.analyze("SELECT id FROM users UNION SELECT user_id FROM orders ORDER BY user_id");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+}
+
+#[test]
+fn test_union_order_by_qualified_column() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics =
+        analyzer.analyze("SELECT id FROM users UNION SELECT id FROM orders ORDER BY users.id");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(diagnostics[0].help.as_ref().unwrap().contains("'id'"));
+}
+
+#[test]
+fn test_union_order_by_expression() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // PostgreSQL only accepts output column names and positions
+    let diagnostics =
+        analyzer.analyze("SELECT id FROM users UNION SELECT id FROM orders ORDER BY id + 1");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+    assert!(diagnostics[0].message.contains("'id + 1'"));
+    assert!(diagnostics[0].help.as_ref().unwrap().contains("(id)"));
+    let span = diagnostics[0].span.unwrap();
+    assert_eq!((span.line, span.column), (1, 59));
+
+    let diagnostics =
+        analyzer.analyze("SELECT name FROM users UNION SELECT 'x' ORDER BY lower(name)");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+
+    // A plain query can sort by any expression
+    let diagnostics = analyzer.analyze("SELECT id FROM users ORDER BY id + 1");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+#[test]
+fn test_order_by_ordinal_out_of_range() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer
+        .analyze("SELECT id, name FROM users UNION SELECT id, user_id FROM orders ORDER BY 3");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(diagnostics[0].message.contains("position 3"));
+    let span = diagnostics[0].span.unwrap();
+    assert_eq!((span.line, span.column, span.end_column), (1, 1, 64));
+
+    let diagnostics = analyzer.analyze("SELECT id FROM users ORDER BY 2");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    let span = diagnostics[0].span.unwrap();
+    assert_eq!((span.line, span.column, span.end_column), (1, 1, 21));
+
+    let diagnostics = analyzer.analyze("SELECT * FROM users ORDER BY 3");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+// ========== Output Schema Tests ==========
+
+#[test]
+fn test_output_schema_where_is_not_null() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let columns = analyzer
+        .infer_output_schema(
+            "SELECT u.*, o.total FROM orders o LEFT JOIN users u ON u.id = o.user_id WHERE u.email IS NOT NULL AND total IS NOT NULL",
+        )
+        .unwrap();
+    let nullable: Vec<(&str, bool)> = columns
+        .iter()
+        .map(|c| (c.name.as_str(), c.nullable))
+        .collect();
+    assert_eq!(
+        nullable,
+        vec![
+            ("id", true),
+            ("name", true),
+            ("email", false),
+            ("total", false)
+        ]
+    );
+}
+
+fn output_shape(columns: &[sqlsurge_core::OutputColumn]) -> Vec<(&str, SqlType, bool)> {
+    columns
+        .iter()
+        .map(|c| (c.name.as_str(), c.data_type.clone(), c.nullable))
+        .collect()
+}
+
+#[test]
+fn test_output_schema_columns_and_expressions() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let columns = analyzer
+        .infer_output_schema(
+            "SELECT id, name AS user_name, id + 1, count(*) AS n FROM users GROUP BY id, name",
+        )
+        .unwrap();
+    assert_eq!(
+        output_shape(&columns),
+        vec![
+            ("id", SqlType::Integer, false),
+            ("user_name", SqlType::Varchar { length: Some(100) }, false),
+            ("?column?", SqlType::Integer, false),
+            ("n", SqlType::BigInt, false),
+        ]
+    );
+}
+
+#[test]
+fn test_output_schema_wildcard_and_outer_join() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let columns = analyzer
+        .infer_output_schema(
+            "SELECT u.*, o.total FROM users u LEFT JOIN orders o ON o.user_id = u.id",
+        )
+        .unwrap();
+    let names: Vec<_> = columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["id", "name", "email", "total"]);
+    // The right side of a LEFT JOIN is nullable
+    assert!(columns[3].nullable);
+    assert!(!columns[0].nullable);
+}
+
+#[test]
+fn test_output_schema_cte_and_derived_table() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let columns = analyzer
+        .infer_output_schema(
+            "WITH totals AS (SELECT user_id, sum(total) AS spent FROM orders GROUP BY user_id) \
+             SELECT t.* FROM (SELECT user_id AS uid, spent FROM totals) AS t(customer)",
+        )
+        .unwrap();
+    let names: Vec<_> = columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["customer", "spent"]);
+    assert_eq!(columns[0].data_type, SqlType::Integer);
+    assert!(columns[1].nullable);
+}
+
+#[test]
+fn test_output_schema_cast_and_unknown() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let columns = analyzer
+        .infer_output_schema("SELECT CAST(id AS TEXT), my_func(name) FROM users")
+        .unwrap();
+    assert_eq!(
+        output_shape(&columns),
+        vec![
+            ("id", SqlType::Text, false),
+            ("my_func", SqlType::Unknown, true),
+        ]
+    );
+}
+
+#[test]
+fn test_output_schema_last_statement() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let columns = analyzer
+        .infer_output_schema(
+            "SELECT id FROM users; \
+             CREATE TEMP TABLE t (x BIGINT NOT NULL); \
+             SELECT x FROM t; \
+             DROP TABLE t;",
+        )
+        .unwrap();
+    assert_eq!(output_shape(&columns), vec![("x", SqlType::BigInt, false)]);
+
+    // No query
+    let columns = analyzer
+        .infer_output_schema("UPDATE users SET name = 'x'")
+        .unwrap();
+    assert!(columns.is_empty());
+}
+
+#[test]
+fn test_output_schema_errors() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer
+        .infer_output_schema("SELECT nope FROM users")
+        .unwrap_err();
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+
+    // Wildcard over an unknown table function without a column list
+    let diagnostics = analyzer
+        .infer_output_schema("SELECT f.* FROM my_func(1, 10) AS f")
+        .unwrap_err();
+    assert!(diagnostics[0].help.as_ref().unwrap().contains("alias"));
+}
+
+// ========== Feature Gate Tests ==========
+
+fn analyze_versioned(
+    dialect: SqlDialect,
+    version: Option<DialectVersion>,
+    sql: &str,
+) -> Vec<String> {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::with_dialect(&catalog, dialect);
+    if let Some(version) = version {
+        analyzer = analyzer.with_version(version);
+    }
+    analyzer
+        .analyze(sql)
+        .into_iter()
+        .filter(|d| d.kind == DiagnosticKind::UnsupportedFeature)
+        .map(|d| d.message)
+        .collect()
+}
+
+#[test]
+fn test_feature_gate_mysql_57() {
+    let mysql57 = Some(DialectVersion::new(5, 7));
+
+    let messages = analyze_versioned(
+        SqlDialect::MySQL,
+        mysql57,
+        "WITH u AS (SELECT id FROM users) SELECT id FROM u",
+    );
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].contains("MySQL 8.0 or later"));
+    assert!(messages[0].contains("target is MySQL 5.7"));
+
+    let messages = analyze_versioned(
+        SqlDialect::MySQL,
+        mysql57,
+        "SELECT id, row_number() OVER (ORDER BY id), rank() OVER (ORDER BY id) FROM users",
+    );
+    assert_eq!(messages.len(), 1, "reported once per statement");
+    assert!(messages[0].starts_with("Window function"));
+}
+
+#[test]
+fn test_feature_gate_available() {
+    let sql = "WITH u AS (SELECT id FROM users) \
+               SELECT id, row_number() OVER (ORDER BY id) FROM u";
+    assert!(analyze_versioned(SqlDialect::MySQL, Some(DialectVersion::new(8, 0)), sql).is_empty());
+    // Without a version, the latest release is assumed
+    assert!(analyze_versioned(SqlDialect::MySQL, None, sql).is_empty());
+}
+
+#[test]
+fn test_feature_gate_postgresql() {
+    let merge = "MERGE INTO users u USING orders o ON u.id = o.user_id \
+                 WHEN MATCHED THEN UPDATE SET name = 'x'";
+    let messages = analyze_versioned(
+        SqlDialect::PostgreSQL,
+        Some(DialectVersion::new(12, 0)),
+        merge,
+    );
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].contains("PostgreSQL 15.0 or later"));
+    assert!(analyze_versioned(
+        SqlDialect::PostgreSQL,
+        Some(DialectVersion::new(15, 0)),
+        merge
+    )
+    .is_empty());
+
+    let identity = "CREATE TEMP TABLE t (id INTEGER GENERATED ALWAYS AS IDENTITY)";
+    let messages = analyze_versioned(
+        SqlDialect::PostgreSQL,
+        Some(DialectVersion::new(9, 6)),
+        identity,
+    );
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].starts_with("Identity column"));
+}
+
+#[test]
+fn test_feature_gate_schema_identity_column() {
+    let schema = "CREATE TABLE accounts (\n    id INTEGER GENERATED BY DEFAULT AS IDENTITY,\n    name TEXT\n);";
+    let mut builder =
+        SchemaBuilder::with_dialect(SqlDialect::PostgreSQL).with_version(DialectVersion::new(9, 6));
+    let diagnostics = builder.parse(schema).unwrap_err();
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::UnsupportedFeature);
+    assert!(diagnostics[0].message.contains("PostgreSQL 10.0 or later"));
+    let span = diagnostics[0].span.unwrap();
+    assert_eq!((span.line, span.column), (2, 5));
+
+    let mut builder = SchemaBuilder::with_dialect(SqlDialect::PostgreSQL)
+        .with_version(DialectVersion::new(10, 0));
+    assert!(builder.parse(schema).is_ok());
+}
+
+#[test]
+fn test_feature_gate_never_supported() {
+    // MERGE is not available in any MySQL version
+    let messages = analyze_versioned(
+        SqlDialect::MySQL,
+        Some(DialectVersion::new(8, 4)),
+        "MERGE INTO users u USING orders o ON u.id = o.user_id \
+         WHEN MATCHED THEN DELETE",
+    );
+    assert_eq!(messages, vec!["MERGE statement is not supported by MySQL"]);
+}
+
+#[test]
+fn test_dialect_spec_parse() {
+    let spec: DialectSpec = "mysql@5.7".parse().unwrap();
+    assert_eq!(spec.dialect, SqlDialect::MySQL);
+    assert_eq!(spec.version, Some(DialectVersion::new(5, 7)));
+
+    let spec: DialectSpec = "postgresql@16".parse().unwrap();
+    assert_eq!(spec.version, Some(DialectVersion::new(16, 0)));
+
+    let spec: DialectSpec = "postgres".parse().unwrap();
+    assert_eq!(spec.version, None);
+
+    assert!("mysql@abc".parse::<DialectSpec>().is_err());
+    assert!("oracle@19".parse::<DialectSpec>().is_err());
+}
+
+// ========== Set-Returning Function Tests ==========
+
+fn setup_posts_catalog() -> Catalog {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(
+            "CREATE TABLE posts (
+                id SERIAL PRIMARY KEY,
+                tags TEXT[] NOT NULL,
+                scores INTEGER[],
+                items JSONB
+            );",
+        )
+        .unwrap();
+    builder.build().0
+}
+
+#[test]
+fn test_srf_alias_in_group_by_and_order_by() {
+    let catalog = setup_posts_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer
+        .analyze("SELECT unnest(tags) AS tag, count(*) FROM posts GROUP BY tag ORDER BY tag");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    let diagnostics =
+        analyzer.analyze("SELECT unnest(scores) AS score FROM posts ORDER BY score + 1");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    // Unknown names in GROUP BY are still reported
+    let diagnostics = analyzer.analyze("SELECT unnest(tags) AS tag FROM posts GROUP BY tga");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+}
+
+#[test]
+fn test_srf_alias_type_checked() {
+    let catalog = setup_posts_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // unnest(text[]) yields text
+    let diagnostics = analyzer.analyze("SELECT unnest(tags) AS tag FROM posts ORDER BY tag + 1");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TypeMismatch);
+
+    let diagnostics = analyzer.analyze(
+        "SELECT generate_series(1, 10) AS n FROM posts GROUP BY n HAVING count(*) > 1 ORDER BY n = 'x'",
+    );
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TypeMismatch);
+}
+
+#[test]
+fn test_srf_output_types() {
+    let catalog = setup_posts_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let columns = analyzer
+        .infer_output_schema(
+            "SELECT unnest(tags) AS tag, jsonb_array_elements(items) ->> 'sku', \
+             jsonb_array_elements(items) AS item, generate_series(1, 3) AS n FROM posts",
+        )
+        .unwrap();
+    assert_eq!(
+        output_shape(&columns),
+        vec![
+            ("tag", SqlType::Text, true),
+            ("?column?", SqlType::Text, true),
+            ("item", SqlType::Jsonb, false),
+            ("n", SqlType::Integer, false),
+        ]
+    );
+}
+
+// ========== Severity Override Tests ==========
+
+#[test]
+fn test_rule_severity_override() {
+    use sqlsurge_core::{Severity, SeverityOrOff};
+
+    let catalog = setup_catalog();
+    let sql = "SELECT nme FROM users";
+
+    let mut analyzer = Analyzer::new(&catalog).with_rule_severity([("E0002", SeverityOrOff::Hint)]);
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Hint);
+
+    // By name, case-insensitively
+    let mut analyzer =
+        Analyzer::new(&catalog).with_rule_severity([("Column-Not-Found", SeverityOrOff::Warning)]);
+    assert_eq!(analyzer.analyze(sql)[0].severity, Severity::Warning);
+
+    let mut analyzer =
+        Analyzer::new(&catalog).with_rule_severity([("column-not-found", SeverityOrOff::Off)]);
+    assert!(analyzer.analyze(sql).is_empty());
+}
+
+#[test]
+fn test_disabled_rules_are_off() {
+    let catalog = setup_catalog();
+
+    let mut analyzer = Analyzer::new(&catalog).with_disabled_rules(["e0002"]);
+    assert!(analyzer.analyze("SELECT nme FROM users").is_empty());
+
+    // An explicit severity given later wins over `disable`
+    let mut analyzer = Analyzer::new(&catalog)
+        .with_disabled_rules(["E0002"])
+        .with_rule_severity([("E0002", sqlsurge_core::SeverityOrOff::Error)]);
+    assert_eq!(analyzer.analyze("SELECT nme FROM users").len(), 1);
+}
+
+#[test]
+fn test_rule_severity_enables_opt_in_rule() {
+    let catalog = setup_unique_catalog();
+    let sql = "INSERT INTO roles (id, name) VALUES (1, 'a'), (1, 'b')";
+
+    let mut analyzer = Analyzer::new(&catalog);
+    assert!(analyzer.analyze(sql).is_empty());
+
+    let mut analyzer = Analyzer::new(&catalog)
+        .with_rule_severity([("W0001", sqlsurge_core::SeverityOrOff::Error)]);
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::DuplicateInsertKey);
+    assert_eq!(diagnostics[0].severity, sqlsurge_core::Severity::Error);
+}
+
+#[test]
+fn test_severity_or_off_parse() {
+    use sqlsurge_core::SeverityOrOff;
+
+    assert_eq!("hint".parse::<SeverityOrOff>(), Ok(SeverityOrOff::Hint));
+    assert_eq!("OFF".parse::<SeverityOrOff>(), Ok(SeverityOrOff::Off));
+    assert_eq!("warn".parse::<SeverityOrOff>(), Ok(SeverityOrOff::Warning));
+    assert!("fatal".parse::<SeverityOrOff>().is_err());
+    assert_eq!(SeverityOrOff::Off.severity(), None);
+}
+
+// ========== Completion Tests ==========
+
+/// Complete at the `|` marker in `sql`
+fn complete_at(catalog: &Catalog, sql: &str) -> Vec<sqlsurge_core::completion::Candidate> {
+    let offset = sql.find('|').unwrap();
+    let sql = sql.replace('|', "");
+    sqlsurge_core::completion::candidates(&sql, offset, catalog, SqlDialect::PostgreSQL)
+}
+
+fn labels(candidates: &[sqlsurge_core::completion::Candidate]) -> Vec<&str> {
+    candidates.iter().map(|c| c.label.as_str()).collect()
+}
+
+#[test]
+fn test_completion_in_join_on() {
+    use sqlsurge_core::completion::CandidateKind;
+
+    let catalog = setup_catalog();
+
+    let items = complete_at(&catalog, "SELECT * FROM users u JOIN orders o ON o.|");
+    assert_eq!(labels(&items), vec!["id", "user_id", "total"]);
+    assert!(items.iter().all(|c| c.kind == CandidateKind::Column));
+    assert_eq!(items[1].detail.as_deref(), Some("integer (orders)"));
+
+    // Unqualified: columns of every joined relation, filtered by the typed prefix
+    let items = complete_at(
+        &catalog,
+        "SELECT * FROM users u JOIN orders o ON u.id = us| WHERE",
+    );
+    assert_eq!(labels(&items), vec!["user_id"]);
+
+    let items = complete_at(&catalog, "SELECT * FROM users u JOIN orders o ON u.id = |");
+    assert_eq!(
+        labels(&items),
+        vec!["id", "name", "email", "id", "user_id", "total"]
+    );
+}
+
+#[test]
+fn test_completion_in_cte_body() {
+    use sqlsurge_core::completion::CandidateKind;
+
+    let catalog = setup_catalog();
+
+    // Inside the CTE only its own FROM clause is in scope
+    let items = complete_at(
+        &catalog,
+        "WITH recent AS (SELECT | FROM orders) SELECT * FROM recent JOIN users ON true",
+    );
+    assert_eq!(labels(&items), vec!["id", "user_id", "total"]);
+
+    // The CTE's columns are visible through its alias
+    let items = complete_at(
+        &catalog,
+        "WITH recent AS (SELECT user_id, total AS amount FROM orders) SELECT r.| FROM recent r",
+    );
+    assert_eq!(labels(&items), vec!["user_id", "amount"]);
+    assert_eq!(items[1].detail.as_deref(), Some("numeric(10,2) (recent)"));
+
+    // CTE names are offered after FROM
+    let items = complete_at(&catalog, "WITH recent AS (SELECT 1) SELECT * FROM |");
+    assert_eq!(items[0].kind, CandidateKind::Cte);
+    assert_eq!(labels(&items), vec!["recent", "orders", "users"]);
+}
+
+#[test]
+fn test_completion_tables_and_keywords() {
+    use sqlsurge_core::completion::CandidateKind;
+
+    let catalog = setup_catalog();
+
+    let items = complete_at(&catalog, "SELECT 1; SELECT * FROM us|");
+    assert_eq!(labels(&items), vec!["users"]);
+    assert_eq!(items[0].kind, CandidateKind::Table);
+    assert_eq!(items[0].detail.as_deref(), Some("table"));
+
+    let items = complete_at(&catalog, "SEL|");
+    assert_eq!(labels(&items), vec!["SELECT"]);
+    assert_eq!(items[0].kind, CandidateKind::Keyword);
+
+    let items = complete_at(&catalog, "SELECT * FROM users u W|");
+    assert_eq!(labels(&items), vec!["WHERE"]);
+}
+
+#[test]
+fn test_completion_schema_qualified_tables() {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(
+            "CREATE TABLE users (id INTEGER);
+             CREATE TABLE billing.invoices (id INTEGER, amount INTEGER);",
+        )
+        .unwrap();
+    let (catalog, _) = builder.build();
+
+    // Tables outside the default schema keep their qualifier
+    let items = complete_at(&catalog, "SELECT * FROM |");
+    assert_eq!(labels(&items), vec!["billing.invoices", "users"]);
+    let items = complete_at(&catalog, "SELECT * FROM inv|");
+    assert_eq!(labels(&items), vec!["billing.invoices"]);
+
+    let items = complete_at(&catalog, "SELECT i.| FROM billing.invoices i");
+    assert_eq!(labels(&items), vec!["id", "amount"]);
+    let items = complete_at(&catalog, "UPDATE billing.invoices SET am|");
+    assert_eq!(labels(&items), vec!["amount"]);
+}
+
+#[test]
+fn test_completion_incomplete_statements() {
+    let catalog = setup_catalog();
+
+    // Parses once the open parenthesis is closed
+    let items = complete_at(
+        &catalog,
+        "SELECT * FROM users WHERE id IN (SELECT user_id FROM orders WHERE to|",
+    );
+    assert_eq!(labels(&items), vec!["total"]);
+
+    // Doesn't parse at all: falls back to the FROM/UPDATE heuristic
+    let items = complete_at(&catalog, "UPDATE users SET na|");
+    assert_eq!(labels(&items), vec!["name"]);
+
+    // Correlated subquery sees the outer relation
+    let items = complete_at(
+        &catalog,
+        "SELECT * FROM users u WHERE EXISTS (SELECT 1 FROM orders o WHERE o.user_id = u.|)",
+    );
+    assert_eq!(labels(&items), vec!["id", "name", "email"]);
+}
+
+#[test]
+fn test_completion_enum_values() {
+    use sqlsurge_core::completion::CandidateKind;
+
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(
+            "CREATE TYPE mood AS ENUM ('happy', 'sad', 'hungry');
+             CREATE TABLE people (id INTEGER, mood mood);",
+        )
+        .unwrap();
+    let (catalog, _) = builder.build();
+
+    let items = complete_at(&catalog, "SELECT * FROM people p WHERE p.mood = 'h|");
+    assert_eq!(labels(&items), vec!["happy", "hungry"]);
+    assert_eq!(items[0].kind, CandidateKind::EnumValue);
+    assert_eq!(items[0].detail.as_deref(), Some("mood"));
+
+    let items = complete_at(
+        &catalog,
+        "SELECT * FROM people WHERE mood IN ('happy', '|')",
+    );
+    assert_eq!(labels(&items), vec!["happy", "sad", "hungry"]);
+}
+
+// ========== Reserved Keyword Tests ==========
+
+#[test]
+fn test_reserved_table_and_column_names_warn() {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(r#"CREATE TABLE "user" (id INT, "order" INT, name TEXT);"#)
+        .unwrap();
+    let (_, diagnostics) = builder.build();
+
+    assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+    assert!(diagnostics
+        .iter()
+        .all(|d| d.kind == DiagnosticKind::ReservedIdentifier));
+    assert!(diagnostics[0].message.contains("Table 'user'"));
+    assert!(diagnostics[1].message.contains("Column 'user.order'"));
+    // Each points at the name
+    let spans: Vec<_> = diagnostics
+        .iter()
+        .map(|d| d.span.map(|s| (s.line, s.column, s.end_column)))
+        .collect();
+    assert_eq!(spans, vec![Some((1, 14, 20)), Some((1, 30, 37))]);
+    assert!(diagnostics[1]
+        .help
+        .as_deref()
+        .unwrap()
+        .contains("\"order\""));
+
+    // `user` is not reserved in MySQL, `order` is
+    let mut builder = SchemaBuilder::with_dialect(SqlDialect::MySQL);
+    builder
+        .parse("CREATE TABLE user (id INT, `order` INT);")
+        .unwrap();
+    let (_, diagnostics) = builder.build();
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(diagnostics[0].help.as_deref().unwrap().contains("`order`"));
+}
+
+#[test]
+fn test_parse_error_suggests_quoting_reserved_column() {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(r#"CREATE TABLE items (id INT, "from" INT);"#)
+        .unwrap();
+    let (catalog, _) = builder.build();
+
+    let mut analyzer = Analyzer::new(&catalog);
+    let diagnostics = analyzer.analyze("SELECT from FROM items");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ParseError);
+    assert!(diagnostics[0]
+        .help
+        .as_deref()
+        .unwrap()
+        .contains("quote it as \"from\""));
+
+    // Parse errors unrelated to schema names get no such help
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+    let diagnostics = analyzer.analyze("SELECT from FROM users");
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ParseError);
+    assert!(diagnostics[0].help.is_none());
+}
+
+#[test]
+fn test_dialect_reserved_words() {
+    assert!(SqlDialect::PostgreSQL.is_reserved("select"));
+    assert!(SqlDialect::PostgreSQL.is_reserved("User"));
+    assert!(!SqlDialect::PostgreSQL.is_reserved("name"));
+    assert!(!SqlDialect::MySQL.is_reserved("user"));
+    assert!(SqlDialect::MySQL.is_reserved("RANK"));
+    assert_eq!(SqlDialect::MySQL.quote_identifier("a`b"), "`a``b`");
+}
+
+// ========== Analyzer Pass Tests ==========
+
+#[test]
+fn test_analyzer_passes() {
+    use sqlsurge_core::AnalyzerPasses;
+
+    let catalog = setup_catalog();
+    let sql = "SELECT nme FROM users WHERE id = 'text'";
+
+    let mut analyzer = Analyzer::new(&catalog);
+    let kinds: Vec<_> = analyzer.analyze(sql).into_iter().map(|d| d.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![DiagnosticKind::ColumnNotFound, DiagnosticKind::TypeMismatch]
+    );
+
+    // Types only: the scope from name resolution is still used
+    let mut analyzer = Analyzer::new(&catalog).with_passes(AnalyzerPasses {
+        names: false,
+        ..AnalyzerPasses::default()
+    });
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TypeMismatch);
+
+    // Names only
+    let mut analyzer = Analyzer::new(&catalog).with_passes(AnalyzerPasses {
+        types: false,
+        ..AnalyzerPasses::default()
+    });
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+}
+
+// ========== Identifier Display Tests ==========
+
+#[test]
+fn test_diagnostics_show_identifiers_as_written() {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(r#"CREATE TABLE "Users" ("userName" TEXT, id INT);"#)
+        .unwrap();
+    let (catalog, _) = builder.build();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // The missing column as written, the catalog table quoted only as needed
+    let diagnostics = analyzer.analyze(r#"SELECT u."userNme" FROM "Users" u"#);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].message,
+        r#"Column '"userNme"' not found in table '"Users"'"#
+    );
+
+    let diagnostics = analyzer.analyze(r#"SELECT nme FROM "Users""#);
+    assert_eq!(diagnostics[0].message, "Column 'nme' not found");
+
+    let diagnostics = analyzer.analyze(r#"SELECT * FROM public."Missing""#);
+    assert_eq!(
+        diagnostics[0].message,
+        r#"Table 'public."Missing"' not found"#
+    );
+}
+
+// ========== Index Hint Tests ==========
+
+fn setup_mysql_indexed_catalog() -> Catalog {
+    let mut builder = SchemaBuilder::with_dialect(SqlDialect::MySQL);
+    builder
+        .parse(
+            r#"
+            CREATE TABLE users (
+                id INT PRIMARY KEY,
+                email VARCHAR(255) NOT NULL,
+                INDEX idx_email (email)
+            );
+            CREATE TABLE orders (id INT PRIMARY KEY, user_id INT NOT NULL);
+            CREATE INDEX idx_user_id ON orders (user_id);
+            CREATE TABLE audit_log (message TEXT);
+            "#,
+        )
+        .unwrap();
+    builder.build().0
+}
+
+#[test]
+fn test_index_hints_and_straight_join_parse() {
+    let catalog = setup_mysql_indexed_catalog();
+    let mut analyzer = Analyzer::with_dialect(&catalog, SqlDialect::MySQL);
+
+    let diagnostics = analyzer.analyze(
+        "SELECT /*+ BKA(o) */ u.email FROM users u USE INDEX (idx_email) \
+         STRAIGHT_JOIN orders o FORCE INDEX FOR JOIN (idx_user_id, PRIMARY) ON o.user_id = u.id",
+    );
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    let diagnostics = analyzer.analyze(
+        "SELECT STRAIGHT_JOIN email FROM users IGNORE INDEX FOR ORDER BY (idx_email) ORDER BY email",
+    );
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+#[test]
+fn test_index_hint_unknown_index() {
+    let catalog = setup_mysql_indexed_catalog();
+    let mut analyzer = Analyzer::with_dialect(&catalog, SqlDialect::MySQL);
+
+    let diagnostics = analyzer.analyze("SELECT id\nFROM users USE INDEX (idx_emial) WHERE id = 1");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::IndexNotFound);
+    assert_eq!(diagnostics[0].severity, sqlsurge_core::Severity::Error);
+    assert_eq!(
+        diagnostics[0].message,
+        "Index 'idx_emial' not found on table 'users'"
+    );
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("Did you mean 'idx_email'?")
+    );
+    // The span points at the index name in the original text
+    let span = diagnostics[0].span.unwrap();
+    assert_eq!((span.line, span.column), (2, 23));
+
+    // Columns after the hint are still checked
+    let diagnostics = analyzer.analyze("SELECT id FROM users USE INDEX (idx_email) WHERE nme = 1");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+}
+
+#[test]
+fn test_index_hint_on_table_without_indexes_is_info() {
+    let catalog = setup_mysql_indexed_catalog();
+    let mut analyzer = Analyzer::with_dialect(&catalog, SqlDialect::MySQL);
+
+    let diagnostics = analyzer.analyze("SELECT message FROM audit_log FORCE INDEX (idx_message)");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::IndexNotFound);
+    assert_eq!(diagnostics[0].severity, sqlsurge_core::Severity::Info);
+}
+
+#[test]
+fn test_parse_error_does_not_hide_other_statements() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze("SELECT FROM WHERE;\nSELECT nme FROM users;");
+    assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ParseError);
+    assert_eq!(diagnostics[1].kind, DiagnosticKind::ColumnNotFound);
+    assert_eq!(diagnostics[1].span.unwrap().line, 2);
+}
+
+// ========== Cross-Table Comparison Tests ==========
+
+#[test]
+fn test_cross_table_comparison_outside_join() {
+    let catalog = setup_catalog();
+    let sql = "SELECT u.name FROM users u JOIN orders o ON o.user_id = u.id WHERE o.total > u.id";
+
+    // Opt-in
+    let mut analyzer = Analyzer::new(&catalog);
+    assert!(analyzer.analyze(sql).is_empty());
+
+    let mut analyzer = Analyzer::new(&catalog).with_enabled_rules(["W0005"]);
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::CrossTableComparison);
+    assert_eq!(diagnostics[0].severity, sqlsurge_core::Severity::Info);
+    assert_eq!(
+        diagnostics[0].message,
+        "Comparison 'o.total > u.id' relates columns of different tables outside a join"
+    );
+
+    // Also an unrelated equality between tables that are already joined
+    let diagnostics = analyzer
+        .analyze("SELECT u.name FROM users u JOIN orders o ON o.user_id = u.id WHERE o.id = u.id");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+}
+
+#[test]
+fn test_cross_table_comparison_not_reported() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog).with_enabled_rules(["cross-table-comparison"]);
+
+    for sql in [
+        // Literals and parameters
+        "SELECT u.name FROM users u JOIN orders o ON o.user_id = u.id WHERE o.total > 100 AND u.id = $1",
+        // The join relation repeated
+        "SELECT u.name FROM users u JOIN orders o ON o.user_id = u.id WHERE u.id = o.user_id",
+        // Implicit join
+        "SELECT u.name FROM users u, orders o WHERE o.user_id = u.id AND o.total > 10",
+        // Same table
+        "SELECT a.name FROM users a JOIN users b ON a.id = b.id WHERE a.name <> b.email",
+        // Correlated subquery
+        "SELECT name FROM users u WHERE EXISTS (SELECT 1 FROM orders o WHERE o.user_id = u.id)",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert!(diagnostics.is_empty(), "{}: {:?}", sql, diagnostics);
+    }
+}
+
+#[test]
+fn test_cross_table_comparison_foreign_key_and_file_disable() {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(
+            "CREATE TABLE users (id INT PRIMARY KEY, created_at DATE);
+             CREATE TABLE orders (id INT, user_id INT REFERENCES users, placed_at DATE);",
+        )
+        .unwrap();
+    let (catalog, _) = builder.build();
+    let mut analyzer = Analyzer::new(&catalog).with_enabled_rules(["W0005"]);
+
+    // Related by the declared foreign key
+    let sql = "SELECT o.id FROM orders o JOIN users u ON true WHERE o.user_id = u.id";
+    assert!(analyzer.analyze(sql).is_empty());
+
+    let sql = "SELECT o.id FROM orders o JOIN users u ON o.user_id = u.id \
+               WHERE o.placed_at < u.created_at";
+    assert_eq!(analyzer.analyze(sql).len(), 1);
+    let sql = format!("-- sqlsurge-disable W0005\n{}", sql);
+    assert!(analyzer.analyze(&sql).is_empty());
+}
+
+// ========== Aggregate Tests ==========
+
+#[test]
+fn test_count_distinct_multiple_arguments() {
+    let catalog = setup_catalog();
+    let sql = "SELECT COUNT(DISTINCT name, email) FROM users";
+
+    let mut analyzer = Analyzer::new(&catalog);
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(
+        diagnostics[0].kind,
+        DiagnosticKind::MultiArgumentCountDistinct
+    );
+    assert!(analyzer
+        .analyze("SELECT COUNT(DISTINCT (name, email)) FROM users")
+        .is_empty());
+
+    // MySQL accepts several arguments
+    let mut analyzer = Analyzer::with_dialect(&catalog, SqlDialect::MySQL);
+    assert!(analyzer.analyze(sql).is_empty());
+}
+
+#[test]
+fn test_order_by_inside_aggregate_arguments() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    assert!(analyzer
+        .analyze("SELECT array_agg(name ORDER BY id DESC), string_agg(email, ',' ORDER BY name) FROM users")
+        .is_empty());
+
+    let diagnostics =
+        analyzer.analyze("SELECT array_agg(name ORDER BY craeted_at DESC) FROM users");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+    assert!(diagnostics[0].message.contains("craeted_at"));
+    assert_eq!(diagnostics[0].span.unwrap().column, 32);
+
+    let diagnostics = analyzer.analyze("SELECT string_agg(name, ',' ORDER BY naem) FROM users");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+    assert!(diagnostics[0].message.contains("naem"));
+
+    // With DISTINCT, PostgreSQL only orders by aggregated arguments
+    assert!(analyzer
+        .analyze("SELECT array_agg(DISTINCT name ORDER BY name) FROM users")
+        .is_empty());
+    let diagnostics = analyzer.analyze("SELECT array_agg(DISTINCT name ORDER BY id) FROM users");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::DistinctOrderBy);
+    assert_eq!(
+        diagnostics[0].message,
+        "ORDER BY expression 'id' must appear in the arguments of array_agg(DISTINCT ...)"
+    );
+}
+
+#[test]
+fn test_within_group_order_by() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    assert!(analyzer
+        .analyze("SELECT percentile_cont(0.5) WITHIN GROUP (ORDER BY total) FROM orders")
+        .is_empty());
+
+    let diagnostics = analyzer
+        .analyze("SELECT user_id, percentile_cont(0.5) WITHIN GROUP (ORDER BY totl) FROM orders GROUP BY user_id");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+    assert!(diagnostics[0].message.contains("totl"));
+}
+
+#[test]
+fn test_aggregate_filter_in_mysql() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::with_dialect(&catalog, SqlDialect::MySQL);
+
+    let diagnostics = analyzer.analyze(
+        "SELECT COUNT(*) FILTER (WHERE email IS NULL) FROM users;\nSELECT nme FROM users;",
+    );
+    assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::UnsupportedFeature);
+    assert_eq!(
+        diagnostics[0].message,
+        "Aggregate FILTER clause is not supported by MySQL"
+    );
+    assert_eq!(diagnostics[0].span.unwrap().column, 17);
+    assert_eq!(diagnostics[1].kind, DiagnosticKind::ColumnNotFound);
+}
+
+#[test]
+fn test_count_not_null_column() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze("SELECT COUNT(name), COUNT(u.id) FROM users u");
+    assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::CountNotNullColumn);
+    assert_eq!(diagnostics[0].severity, sqlsurge_core::Severity::Info);
+    assert_eq!(
+        diagnostics[0].message,
+        "COUNT(name) counts every row: 'name' is NOT NULL"
+    );
+
+    for sql in [
+        // Nullable column
+        "SELECT COUNT(email) FROM users",
+        "SELECT COUNT(DISTINCT name) FROM users",
+        // The nullable side of an outer join
+        "SELECT u.name, COUNT(o.id) FROM users u LEFT JOIN orders o ON o.user_id = u.id GROUP BY u.name",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert!(diagnostics.is_empty(), "{}: {:?}", sql, diagnostics);
+    }
+}
+
+#[test]
+fn test_count_nullable_column_in_arithmetic() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze("SELECT COUNT(email) * 100 / COUNT(*) FROM users");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::CountSkipsNulls);
+    assert_eq!(diagnostics[0].span.unwrap().column, 8);
+
+    // Not in arithmetic
+    assert!(analyzer
+        .analyze("SELECT COUNT(email) FROM users")
+        .is_empty());
+    assert!(analyzer
+        .analyze("SELECT id FROM users WHERE id > (SELECT COUNT(email) FROM users)")
+        .is_empty());
+}
+
+// ========== Window Function Placement Tests ==========
+
+fn assert_misplaced_window(sql: &str, clause: &str) {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1, "{}: {:?}", sql, diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::MisplacedWindowFunction);
+    assert_eq!(
+        diagnostics[0].message,
+        format!("Window functions are not allowed in {}", clause)
+    );
+}
+
+#[test]
+fn test_window_function_in_where() {
+    assert_misplaced_window(
+        "SELECT id FROM users WHERE row_number() OVER (ORDER BY id) = 1",
+        "WHERE",
+    );
+    assert_misplaced_window(
+        "UPDATE orders SET total = 0 WHERE rank() OVER (ORDER BY total) > 10",
+        "WHERE",
+    );
+    assert_misplaced_window(
+        "DELETE FROM orders WHERE rank() OVER (ORDER BY total) > 10",
+        "WHERE",
+    );
+
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+    let diagnostics =
+        analyzer.analyze("SELECT id FROM users WHERE id = 1 AND row_number() OVER w = 1");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].span.unwrap().column, 39);
+    assert!(diagnostics[0]
+        .help
+        .as_deref()
+        .unwrap()
+        .contains("subquery or CTE"));
+}
+
+#[test]
+fn test_window_function_in_group_by_having_and_join() {
+    assert_misplaced_window(
+        "SELECT count(*) FROM users GROUP BY rank() OVER (ORDER BY id)",
+        "GROUP BY",
+    );
+    assert_misplaced_window(
+        "SELECT name FROM users GROUP BY name HAVING sum(id) OVER () > 1",
+        "HAVING",
+    );
+    assert_misplaced_window(
+        "SELECT u.id FROM users u JOIN orders o ON o.user_id = u.id AND row_number() OVER (PARTITION BY o.user_id) = 1",
+        "JOIN conditions",
+    );
+}
+
+#[test]
+fn test_window_function_allowed_positions() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+    for sql in [
+        "SELECT id, row_number() OVER (ORDER BY id) FROM users",
+        "SELECT id FROM users ORDER BY rank() OVER (ORDER BY name)",
+        "SELECT name, count(*), rank() OVER (ORDER BY count(*) DESC) FROM users GROUP BY name",
+        // A subquery in WHERE has its own select list
+        "SELECT id FROM users WHERE id IN (SELECT user_id FROM (SELECT user_id, row_number() OVER (PARTITION BY user_id) AS rn FROM orders) t WHERE rn = 1)",
+        "SELECT id FROM users WHERE id = (SELECT max(id) OVER () FROM users LIMIT 1)",
+        // ...and so does a derived table in a JOIN
+        "SELECT u.id FROM users u JOIN (SELECT user_id, rank() OVER (ORDER BY total) AS r FROM orders) o ON o.user_id = u.id WHERE o.r = 1",
+        "WITH ranked AS (SELECT id, row_number() OVER (ORDER BY id) AS rn FROM users) SELECT id FROM ranked WHERE rn = 1",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert!(diagnostics.is_empty(), "{}: {:?}", sql, diagnostics);
+    }
+}
+
+// ========== USING / NATURAL Join Tests ==========
+
+#[test]
+fn test_using_join_wildcard_merges_columns() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // users (3) + orders (3) - USING (1) = 5 columns, the merged one first
+    let sql = "SELECT * FROM users JOIN orders USING (id)";
+    let columns = analyzer.infer_output_schema(sql).unwrap();
+    let names: Vec<_> = columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["id", "name", "email", "user_id", "total"]);
+    assert!(analyzer
+        .analyze(
+            "WITH j(a, b, c, d, e) AS (SELECT * FROM users JOIN orders USING (id)) SELECT e FROM j"
+        )
+        .is_empty());
+    let diagnostics = analyzer.analyze(
+        "WITH j(a, b, c, d, e, f) AS (SELECT * FROM users JOIN orders USING (id)) SELECT a FROM j",
+    );
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+
+    // NATURAL JOIN merges every common column
+    let diagnostics = analyzer
+        .analyze("SELECT d.user_id, d.nme FROM (SELECT * FROM users NATURAL JOIN orders) d");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+    let columns = analyzer
+        .infer_output_schema("SELECT * FROM users u LEFT JOIN orders o USING (id)")
+        .unwrap();
+    // The merged column is NULL only when both sides are
+    assert!(!columns[0].nullable);
+    assert!(columns[3].nullable);
+}
+
+#[test]
+fn test_using_join_column_not_ambiguous() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog).with_disabled_rules(["duplicate-output-column"]);
+
+    for sql in [
+        "SELECT id, u.id, o.id FROM users u JOIN orders o USING (id)",
+        "SELECT id FROM users NATURAL JOIN orders WHERE id > 1",
+        "SELECT id, user_id FROM users u JOIN orders o USING (id) GROUP BY id, user_id",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert!(diagnostics.is_empty(), "{}: {:?}", sql, diagnostics);
+    }
+
+    let diagnostics = analyzer.analyze("SELECT id FROM users u JOIN orders o ON o.id = u.id");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::AmbiguousColumn);
+}
+
+// ========== Implicit Cast Tests ==========
+
+fn setup_cast_catalog() -> Catalog {
+    let varchar = |length| SqlType::Varchar {
+        length: Some(length),
+    };
+    CatalogBuilder::new()
+        .table("accounts", |t| {
+            t.column("id", SqlType::BigInt, |c| c.primary_key())
+                .column("code", varchar(50), |c| c)
+                .column("label", SqlType::Text, |c| c)
+        })
+        .table("events", |t| {
+            t.column("account_id", SqlType::Integer, |c| c)
+                .column("code", varchar(100), |c| c)
+                .column("kind", SqlType::Char { length: Some(4) }, |c| c)
+        })
+        .build()
+        .0
+}
+
+#[test]
+fn test_implicit_cast_is_pedantic() {
+    use sqlsurge_core::analyzer::Strictness;
+
+    let catalog = setup_cast_catalog();
+    let sql = "SELECT a.id FROM accounts a, events e WHERE e.account_id = a.id";
+
+    let mut analyzer = Analyzer::new(&catalog);
+    assert!(analyzer.analyze(sql).is_empty());
+
+    let mut analyzer = Analyzer::new(&catalog).with_strictness(Strictness::Pedantic);
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ImplicitCast);
+    assert_eq!(diagnostics[0].severity, sqlsurge_core::Severity::Warning);
+    assert_eq!(
+        diagnostics[0].message,
+        "Comparison of integer with bigint relies on an implicit cast"
+    );
+
+    // The fix appends a cast to the narrower side
+    let fix = &diagnostics[0].fixes[0];
+    assert_eq!(fix.replacement, "::bigint");
+    assert_eq!((fix.span.line, fix.span.column), (1, 57));
+    let mut fixed = sql.to_string();
+    fixed.insert_str(fix.span.column - 1, &fix.replacement);
+    assert_eq!(
+        fixed,
+        "SELECT a.id FROM accounts a, events e WHERE e.account_id::bigint = a.id"
+    );
+    assert!(analyzer.analyze(&fixed).is_empty());
+
+    // Also in JOIN conditions, and enabled by code like other opt-in rules
+    let mut analyzer = Analyzer::new(&catalog).with_enabled_rules(["W0008"]);
+    let diagnostics =
+        analyzer.analyze("SELECT a.id FROM accounts a JOIN events e ON a.code = e.kind");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].fixes[0].replacement, "::varchar(50)");
+}
+
+#[test]
+fn test_implicit_cast_not_reported() {
+    use sqlsurge_core::analyzer::Strictness;
+
+    let catalog = setup_cast_catalog();
+    let mut analyzer = Analyzer::new(&catalog).with_strictness(Strictness::Pedantic);
+
+    for sql in [
+        // Literals, NULL and parameters
+        "SELECT id FROM accounts WHERE id = 1 OR id = $1 OR code = 'x' OR code <> NULL",
+        // Same type family, different length
+        "SELECT a.id FROM accounts a JOIN events e ON a.code = e.code",
+        "SELECT id FROM accounts WHERE code = label",
+        // Explicit cast
+        "SELECT a.id FROM accounts a, events e WHERE e.account_id::bigint = a.id",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert!(diagnostics.is_empty(), "{}: {:?}", sql, diagnostics);
+    }
+
+    // MySQL has no `::`, so there is only help
+    let mut analyzer =
+        Analyzer::with_dialect(&catalog, SqlDialect::MySQL).with_strictness(Strictness::Pedantic);
+    let diagnostics =
+        analyzer.analyze("SELECT a.id FROM accounts a, events e WHERE e.account_id = a.id");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(diagnostics[0].fixes.is_empty());
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("Cast explicitly: CAST(e.account_id AS bigint)")
+    );
+}
+
+// ========== Schema Origin Tests ==========
+
+#[test]
+fn test_column_not_found_points_at_table_definition() {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse_named(
+            "schema/users.sql",
+            "-- Users\nCREATE TABLE users (id INT, name TEXT);",
+        )
+        .unwrap();
+    let (catalog, _) = builder.build();
+
+    let mut analyzer = Analyzer::new(&catalog);
+    for sql in [
+        "SELECT u.nickname FROM users u",
+        "SELECT nickname FROM users",
+        "UPDATE users SET nickname = 'x'",
+        "INSERT INTO users (nickname) VALUES ('x')",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert_eq!(diagnostics.len(), 1, "{}: {:?}", sql, diagnostics);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+        let label = &diagnostics[0].labels[0];
+        assert_eq!(label.message, "table 'users' defined here");
+        assert_eq!(label.file.as_deref(), Some("schema/users.sql"));
+        assert_eq!((label.span.line, label.span.column), (2, 14));
+    }
+}
+
+#[test]
+fn test_column_not_found_lists_columns_without_origin() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze("SELECT nickname FROM users");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(diagnostics[0].labels.is_empty());
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("Table 'users' has columns: id, name, email")
+    );
+
+    // A suggestion is more useful than the list
+    let diagnostics = analyzer.analyze("SELECT nme FROM users");
+    assert_eq!(diagnostics[0].help.as_deref(), Some("Did you mean 'name'?"));
+
+    // The list is capped
+    let columns: Vec<String> = (1..=20).map(|i| format!("c{} INT", i)).collect();
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(&format!("CREATE TABLE wide ({});", columns.join(", ")))
+        .unwrap();
+    let (catalog, _) = builder.build();
+    let mut analyzer = Analyzer::new(&catalog);
+    let diagnostics = analyzer.analyze("SELECT w.missing FROM wide w");
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("Table 'wide' has columns: c1, c2, c3, c4, c5, c6, c7, c8, c9, c10, c11, c12, c13, c14, c15, ... (5 more)")
+    );
+}
+
+#[test]
+fn test_column_suggestion_budget() {
+    let columns: Vec<String> = (0..1000)
+        .map(|i| format!("metric_{:04}_value INT", i))
+        .collect();
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(&format!("CREATE TABLE wide ({});", columns.join(", ")))
+        .unwrap();
+    let (catalog, _) = builder.build();
+    let sql = "SELECT metric_0999_valeu, metric_0999_valeu + 1 FROM wide";
+
+    // Every reference to a typo gets the suggestion
+    let mut analyzer = Analyzer::new(&catalog);
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+    for diag in &diagnostics {
+        assert_eq!(
+            diag.help.as_deref(),
+            Some("Did you mean 'metric_0999_value'?")
+        );
+    }
+
+    // Columns past the budget are not compared: the best match among the
+    // first 100 is suggested
+    let mut analyzer = Analyzer::new(&catalog).with_suggestion_budget(100);
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("Did you mean 'metric_0099_value'?")
+    );
+}
+
+// ========== Placeholder Tests ==========
+
+#[test]
+fn test_psql_placeholders() {
+    use sqlsurge_core::analyzer::PlaceholderStyle;
+
+    let catalog = setup_catalog();
+    let sql = "SELECT id FROM users WHERE id = :user_id AND email = :'email'";
+
+    // A parse error unless enabled
+    let mut analyzer = Analyzer::new(&catalog);
+    assert_eq!(analyzer.analyze(sql)[0].kind, DiagnosticKind::ParseError);
+
+    let mut analyzer = Analyzer::new(&catalog).with_placeholders([PlaceholderStyle::Psql]);
+    for sql in [
+        sql,
+        // Casts, slices, strings and comments are left alone
+        "SELECT id::text, ':x' FROM users WHERE name = :name -- :comment\n",
+        // A schema qualifier resolves through the search path
+        "SELECT u.name FROM :'schema_name'.users u WHERE u.id = :id",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert!(diagnostics.is_empty(), "{}: {:?}", sql, diagnostics);
+    }
+
+    // Diagnostics point into the original text
+    let sql = "SELECT u.nme FROM :'schema_name'.users u WHERE u.id = :id";
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    let span = diagnostics[0].span.unwrap();
+    assert_eq!((span.line, span.column), (1, 10));
+    let sql = "SELECT id FROM users WHERE id = :'user_id' AND nme = 'x'";
+    let span = analyzer.analyze(sql)[0].span.unwrap();
+    assert_eq!(span.column, sql.find("nme").unwrap() + 1);
+
+    // Input without placeholders is analyzed as is
+    let sql = "SELECT nme FROM users;\nSELECT id FROM users WHERE id = 'x'";
+    let with = analyzer.analyze(sql);
+    let without = Analyzer::new(&catalog).analyze(sql);
+    assert_eq!(format!("{:?}", with), format!("{:?}", without));
+}
+
+#[test]
+fn test_placeholders_in_name_positions() {
+    use sqlsurge_core::analyzer::PlaceholderStyle;
+
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog)
+        .with_placeholders([PlaceholderStyle::Psql, PlaceholderStyle::Flyway]);
+
+    // The dummy table itself is not reported
+    let diagnostics = analyzer.analyze("INSERT INTO :\"audit_table\" SELECT id FROM users");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let diagnostics =
+        analyzer.analyze("SELECT o.total FROM ${schema}.orders o WHERE o.user_id = ${user_id}");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    // Its columns are unknown, so any reference to them resolves
+    for sql in [
+        "SELECT id FROM :\"tbl\"",
+        "SELECT * FROM :\"tbl\"",
+        "SELECT t.* FROM :\"tbl\" t",
+        "SELECT t.anything, u.name FROM :\"tbl\" t JOIN users u ON u.id = t.user_id",
+        "UPDATE ${tbl} SET anything = 1 WHERE other = 2",
+        "INSERT INTO ${tbl} (anything) VALUES (1)",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert!(diagnostics.is_empty(), "{}: {:?}", sql, diagnostics);
+    }
+    // Other relations are still checked
+    let sql = "SELECT t.id, u.nme FROM :\"tbl\" t JOIN users u ON u.id = t.user_id";
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+    assert_eq!(
+        diagnostics[0].span.unwrap().column,
+        sql.find("nme").unwrap() + 1
+    );
+
+    // A dummy longer than its placeholder shifts the line; spans are mapped back
+    let mut analyzer = Analyzer::new(&catalog)
+        .with_placeholders([PlaceholderStyle::Flyway])
+        .with_placeholder_identifier("a_rather_long_dummy_table");
+    let sql = "SELECT u.nme FROM users u JOIN ${t} ON TRUE WHERE u.emial = ${v}";
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+    let columns: Vec<usize> = diagnostics.iter().map(|d| d.span.unwrap().column).collect();
+    assert_eq!(
+        columns,
+        [sql.find("nme").unwrap() + 1, sql.find("emial").unwrap() + 1]
+    );
+}
+
+// ========== Table Function Tests ==========
+
+fn assert_resolves(analyzer: &mut Analyzer, sql: &str) {
+    let diagnostics = analyzer.analyze(sql);
+    assert!(diagnostics.is_empty(), "{}: {:?}", sql, diagnostics);
+}
+
+fn assert_column_not_found(analyzer: &mut Analyzer, sql: &str) {
+    let diagnostics = analyzer.analyze(sql);
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| d.kind == DiagnosticKind::ColumnNotFound),
+        "{}: {:?}",
+        sql,
+        diagnostics
+    );
+}
+
+#[test]
+fn test_scalar_table_function_naming() {
+    let catalog = setup_catalog();
+    // Both spellings of the column are selected, which repeats its name
+    let mut analyzer = Analyzer::new(&catalog).with_disabled_rules(["duplicate-output-column"]);
+
+    // No alias: the column takes the function name
+    assert_resolves(
+        &mut analyzer,
+        "SELECT generate_series, generate_series.generate_series FROM generate_series(1, 10)",
+    );
+    assert_column_not_found(&mut analyzer, "SELECT n FROM generate_series(1, 10)");
+
+    // AS x: the column takes the alias
+    assert_resolves(
+        &mut analyzer,
+        "SELECT x, x.x FROM generate_series(1, 10) AS x",
+    );
+    assert_column_not_found(
+        &mut analyzer,
+        "SELECT generate_series FROM generate_series(1, 10) AS x",
+    );
+
+    // AS t(a): the column takes the listed name
+    assert_resolves(
+        &mut analyzer,
+        "SELECT a, t.a FROM generate_series(1, 10) AS t(a)",
+    );
+    assert_column_not_found(
+        &mut analyzer,
+        "SELECT t.t FROM generate_series(1, 10) AS t(a)",
+    );
+
+    // Other FROM items stay resolvable
+    assert_resolves(
+        &mut analyzer,
+        "SELECT name, n FROM users CROSS JOIN LATERAL generate_series(1, users.id) AS n",
+    );
+    assert_column_not_found(
+        &mut analyzer,
+        "SELECT nme FROM users, generate_series(1, 10)",
+    );
+}
+
+#[test]
+fn test_multi_column_table_function_naming() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // No alias: the function's own column names
+    assert_resolves(
+        &mut analyzer,
+        "SELECT key, jsonb_each.value FROM jsonb_each('{\"a\": 1}')",
+    );
+    assert_column_not_found(&mut analyzer, "SELECT jsonb_each FROM jsonb_each('{}')");
+
+    // AS e: the alias names the relation, not the columns
+    assert_resolves(
+        &mut analyzer,
+        "SELECT e.key, value FROM jsonb_each('{}') AS e",
+    );
+    assert_column_not_found(&mut analyzer, "SELECT e.e FROM jsonb_each('{}') AS e");
+
+    // AS e(k): renames the leading column only
+    assert_resolves(
+        &mut analyzer,
+        "SELECT k, e.value FROM jsonb_each('{}') AS e(k)",
+    );
+    assert_column_not_found(&mut analyzer, "SELECT key FROM jsonb_each('{}') AS e(k)");
+
+    // AS e(k, v)
+    assert_resolves(
+        &mut analyzer,
+        "SELECT k, v FROM jsonb_each('{}') AS e(k, v)",
+    );
+    let diagnostics = analyzer.analyze("SELECT k FROM jsonb_each('{}') AS e(k, v, w)");
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnCountMismatch);
+}
+
+#[test]
+fn test_table_function_output_schema() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let columns = analyzer
+        .infer_output_schema("SELECT * FROM generate_series(1, 10)")
+        .unwrap();
+    assert_eq!(
+        output_shape(&columns),
+        vec![("generate_series", SqlType::Integer, false)]
+    );
+
+    let columns = analyzer
+        .infer_output_schema("SELECT * FROM jsonb_each_text('{}') AS e(k)")
+        .unwrap();
+    assert_eq!(
+        output_shape(&columns),
+        vec![("k", SqlType::Text, false), ("value", SqlType::Text, true)]
+    );
+
+    let columns = analyzer
+        .infer_output_schema("SELECT m FROM regexp_matches('a1', '[a-z]\\d') AS m")
+        .unwrap();
+    assert_eq!(
+        output_shape(&columns),
+        vec![("m", SqlType::Array(Box::new(SqlType::Text)), false)]
+    );
+
+    let columns = analyzer
+        .infer_output_schema("SELECT * FROM unnest(ARRAY[1], ARRAY['a']) WITH ORDINALITY")
+        .unwrap();
+    let names: Vec<_> = columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["unnest", "unnest", "ordinality"]);
+}
+
+// ========== Row Order Tests ==========
+
+#[test]
+fn test_distinct_order_by_not_in_select_list() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze("SELECT DISTINCT name FROM users ORDER BY email");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::DistinctOrderBy);
+    assert_eq!(diagnostics[0].code(), "E0014");
+    let span = diagnostics[0].span.as_ref().unwrap();
+    assert_eq!((span.column, span.end_column), (42, 47));
+
+    let diagnostics =
+        analyzer.analyze("SELECT DISTINCT u.name FROM users u ORDER BY lower(u.email), u.name");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(diagnostics[0].message.contains("lower(u.email)"));
+
+    // Selected expressions, aliases, positions and wildcard columns are fine
+    for sql in [
+        "SELECT DISTINCT name FROM users ORDER BY name DESC",
+        "SELECT DISTINCT u.name FROM users u ORDER BY name",
+        "SELECT DISTINCT name FROM users u ORDER BY u.name",
+        "SELECT DISTINCT lower(email) AS e FROM users ORDER BY e",
+        "SELECT DISTINCT LOWER(email) FROM users ORDER BY lower(email)",
+        "SELECT DISTINCT name, email FROM users ORDER BY 2",
+        "SELECT DISTINCT * FROM users ORDER BY email",
+        "SELECT name FROM users ORDER BY email",
+    ] {
+        assert_resolves(&mut analyzer, sql);
+    }
+
+    // MySQL is not checked
+    let mut analyzer = Analyzer::with_dialect(&catalog, SqlDialect::MySQL);
+    assert_resolves(
+        &mut analyzer,
+        "SELECT DISTINCT name FROM users ORDER BY email",
+    );
+}
+
+#[test]
+fn test_unordered_limit() {
+    let catalog = setup_catalog();
+    let sql = "SELECT name FROM users LIMIT 10";
+
+    // Opt-in
+    let mut analyzer = Analyzer::new(&catalog);
+    assert!(analyzer.analyze(sql).is_empty());
+
+    let mut analyzer = Analyzer::new(&catalog).with_enabled_rules(["W0009"]);
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::UnorderedLimit);
+    assert_eq!(diagnostics[0].severity, sqlsurge_core::Severity::Warning);
+    assert_eq!(diagnostics[0].span.as_ref().unwrap().column, 1);
+
+    let diagnostics =
+        analyzer.analyze("SELECT * FROM orders WHERE user_id = $1 FETCH FIRST 5 ROWS ONLY");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(diagnostics[0].message.starts_with("FETCH FIRST"));
+
+    // Subqueries are checked too
+    let diagnostics =
+        analyzer.analyze("SELECT name FROM users WHERE id IN (SELECT user_id FROM orders LIMIT 3)");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+
+    for sql in [
+        "SELECT name FROM users ORDER BY id LIMIT 10",
+        // A primary key equality selects at most one row
+        "SELECT name FROM users WHERE id = 1 LIMIT 1",
+        "SELECT u.name FROM users u WHERE u.id = $1 AND u.name <> '' LIMIT 1",
+    ] {
+        assert_resolves(&mut analyzer, sql);
+    }
+
+    // Not a unique key, or not an equality with a value
+    for sql in [
+        "SELECT name FROM users WHERE name = 'a' LIMIT 1",
+        "SELECT name FROM users WHERE id = 1 OR id = 2 LIMIT 1",
+        "SELECT u.name FROM users u JOIN orders o ON o.user_id = u.id WHERE u.id = 1 LIMIT 1",
+    ] {
+        assert_eq!(analyzer.analyze(sql).len(), 1, "{}", sql);
+    }
+}
+
+#[test]
+fn test_row_limit_values() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    for sql in [
+        "SELECT name FROM users ORDER BY id LIMIT 10 OFFSET 5",
+        "SELECT name FROM users ORDER BY id OFFSET 5 ROWS FETCH FIRST 10 ROWS ONLY",
+        "SELECT name FROM users ORDER BY id LIMIT $1 OFFSET $2",
+        "SELECT name FROM users ORDER BY id LIMIT ALL",
+        "SELECT name FROM users ORDER BY id LIMIT '10'",
+        "SELECT name FROM users ORDER BY id LIMIT (SELECT count(*) FROM orders)",
+    ] {
+        assert_resolves(&mut analyzer, sql);
+    }
+
+    let diagnostics = analyzer.analyze("SELECT name FROM users ORDER BY id LIMIT -1");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::InvalidRowLimit);
+    assert_eq!(diagnostics[0].code(), "E0016");
+    assert!(diagnostics[0].message.contains("must not be negative"));
+    // Points at the LIMIT keyword
+    let span = diagnostics[0].span.as_ref().unwrap();
+    assert_eq!((span.line, span.column, span.length), (1, 36, 5));
+
+    let diagnostics = analyzer.analyze("SELECT name FROM users ORDER BY id LIMIT 'ten'");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(diagnostics[0].message.contains("must be an integer"));
+
+    // PostgreSQL rounds a fractional count
+    let diagnostics = analyzer.analyze("SELECT name FROM users ORDER BY id LIMIT 2.5");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].severity, sqlsurge_core::Severity::Warning);
+
+    let diagnostics = analyzer.analyze("SELECT name FROM users ORDER BY id LIMIT id + 1");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(diagnostics[0]
+        .message
+        .contains("cannot reference column 'id'"));
+    assert_eq!(diagnostics[0].span.as_ref().unwrap().column, 42);
+
+    // Each query's clause is found, not the first LIMIT in the statement
+    let diagnostics = analyzer.analyze(
+        "SELECT name FROM users WHERE id IN (SELECT user_id FROM orders ORDER BY user_id LIMIT 1)\nORDER BY id OFFSET -5",
+    );
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    let span = diagnostics[0].span.as_ref().unwrap();
+    assert_eq!((span.line, span.column), (2, 13));
+}
+
+#[test]
+fn test_row_limit_dialect_syntax() {
+    use sqlsurge_core::AnalyzerPasses;
+
+    let catalog = setup_catalog();
+
+    // MySQL's LIMIT offset, count doesn't parse under PostgreSQL
+    let mut analyzer = Analyzer::new(&catalog);
+    let diagnostics = analyzer.analyze("SELECT name FROM users ORDER BY id LIMIT 5, 10");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::UnsupportedFeature);
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("Use LIMIT 10 OFFSET 5")
+    );
+
+    let mut analyzer = Analyzer::with_dialect(&catalog, SqlDialect::MySQL);
+    for sql in [
+        "SELECT name FROM users ORDER BY id LIMIT 5, 10",
+        "SELECT name FROM users ORDER BY id LIMIT 10 OFFSET 5",
+        "SELECT name FROM users ORDER BY id LIMIT ?",
+    ] {
+        assert_resolves(&mut analyzer, sql);
+    }
+
+    let diagnostics =
+        analyzer.analyze("SELECT name FROM users ORDER BY id FETCH FIRST 10 ROWS ONLY");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::UnsupportedFeature);
+    assert_eq!(diagnostics[0].help.as_deref(), Some("Use LIMIT 10"));
+    assert_eq!(diagnostics[0].span.as_ref().unwrap().column, 36);
+
+    let diagnostics = analyzer.analyze("SELECT name FROM users ORDER BY id OFFSET 5");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(diagnostics[0].message.contains("OFFSET without LIMIT"));
+
+    // MySQL takes only literals and parameters
+    let diagnostics = analyzer.analyze("SELECT name FROM users ORDER BY id LIMIT 1 + 1");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::InvalidRowLimit);
+
+    // Feature checks can be turned off; value checks stay
+    let mut analyzer =
+        Analyzer::with_dialect(&catalog, SqlDialect::MySQL).with_passes(AnalyzerPasses {
+            features: false,
+            ..AnalyzerPasses::default()
+        });
+    assert_resolves(
+        &mut analyzer,
+        "SELECT name FROM users ORDER BY id FETCH FIRST 10 ROWS ONLY",
+    );
+}
+
+// ========== Dependency Tests ==========
+
+#[test]
+fn test_collect_dependencies() {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);
+             CREATE SCHEMA billing;
+             CREATE TABLE billing.invoices (id INTEGER, user_id INTEGER);
+             CREATE VIEW active_users AS SELECT * FROM users WHERE id > 0;
+             CREATE VIEW user_invoices AS
+                 WITH recent AS (SELECT * FROM billing.invoices)
+                 SELECT a.name FROM active_users a JOIN recent r ON r.user_id = a.id;",
+        )
+        .unwrap();
+    let (catalog, _) = builder.build();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let names = |deps: std::collections::BTreeSet<QualifiedName>| -> Vec<String> {
+        deps.iter().map(|n| n.to_string()).collect()
+    };
+    assert_eq!(
+        names(analyzer.collect_dependencies("SELECT name FROM user_invoices")),
+        vec![
+            "billing.invoices",
+            "public.active_users",
+            "public.user_invoices",
+            "public.users"
+        ]
+    );
+
+    // DML targets and subqueries count; CTEs, unknown tables and tables
+    // created by the input itself don't
+    let sql = "CREATE TEMP TABLE scratch (id INTEGER);
+               INSERT INTO scratch SELECT id FROM users;
+               WITH users AS (SELECT 1 AS id) SELECT * FROM users, missing;
+               DELETE FROM billing.invoices WHERE user_id IN (SELECT id FROM scratch);";
+    assert_eq!(
+        names(analyzer.collect_dependencies(sql)),
+        vec!["billing.invoices", "public.users"]
+    );
+}
+
+#[test]
+fn test_collect_dependencies_view_cycle() {
+    // Schema files aren't ordered, so views may (invalidly) refer to each other
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(
+            "CREATE TABLE users (id INTEGER);
+             CREATE VIEW a AS SELECT id FROM b JOIN users USING (id);
+             CREATE VIEW b AS SELECT id FROM a;",
+        )
+        .unwrap();
+    let (catalog, _) = builder.build();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let deps = analyzer.collect_dependencies("SELECT * FROM b");
+    assert_eq!(deps.len(), 3, "{:?}", deps);
+}
+
+// ========== Qualified Wildcard Tests ==========
+
+#[test]
+fn test_qualified_wildcard_relation_kinds() {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+             CREATE VIEW user_names AS SELECT id, name AS display_name FROM users;",
+        )
+        .unwrap();
+    let (catalog, _) = builder.build();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let cases = [
+        ("table", "SELECT u.* FROM users u"),
+        ("schema-qualified table", "SELECT public.users.* FROM users"),
+        ("view", "SELECT v.* FROM user_names v"),
+        (
+            "CTE",
+            "WITH c AS (SELECT id, name FROM users) SELECT c.* FROM c",
+        ),
+        (
+            "derived table",
+            "SELECT d.* FROM (SELECT id, name FROM users) d",
+        ),
+        (
+            "derived table with alias list",
+            "SELECT d.* FROM (SELECT id, name FROM users) AS d(user_id)",
+        ),
+    ];
+    for (kind, projection) in cases {
+        // The wildcard's columns are visible through an enclosing derived table
+        let sql = format!("SELECT w.id FROM ({}) w", projection);
+        let diagnostics = analyzer.analyze(&sql);
+        let expect_id = kind != "derived table with alias list";
+        assert_eq!(
+            diagnostics.is_empty(),
+            expect_id,
+            "{}: {:?}",
+            kind,
+            diagnostics
+        );
+
+        let sql = format!("SELECT w.nope FROM ({}) w", projection);
+        assert_column_not_found(&mut analyzer, &sql);
+    }
+    assert_resolves(
+        &mut analyzer,
+        "SELECT w.user_id, w.name FROM (SELECT d.* FROM (SELECT id, name FROM users) AS d(user_id)) w",
+    );
+    assert_resolves(
+        &mut analyzer,
+        "SELECT w.display_name FROM (SELECT v.* FROM user_names v) w",
+    );
+
+    // A qualified wildcard contributes every column of its relation
+    let columns = analyzer
+        .infer_output_schema("WITH c AS (SELECT id, name FROM users) SELECT c.*, 1 AS one FROM c")
+        .unwrap();
+    let names: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["id", "name", "one"]);
+}
+
+#[test]
+fn test_qualified_wildcard_unknown_qualifier() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let table_not_found = |analyzer: &mut Analyzer, sql: &str| {
+        let diagnostics = analyzer.analyze(sql);
+        assert_eq!(diagnostics.len(), 1, "{}: {:?}", sql, diagnostics);
+        assert_eq!(
+            diagnostics[0].kind,
+            DiagnosticKind::TableNotFound,
+            "{}",
+            sql
+        );
+        diagnostics.into_iter().next().unwrap()
+    };
+
+    table_not_found(&mut analyzer, "SELECT x.* FROM users u");
+    table_not_found(&mut analyzer, "SELECT other.users.* FROM users");
+    table_not_found(
+        &mut analyzer,
+        "WITH c AS (SELECT id FROM users) SELECT public.c.* FROM c",
+    );
+    table_not_found(
+        &mut analyzer,
+        "SELECT public.d.* FROM (SELECT id FROM users) d",
+    );
+
+    // Once aliased, the table is only reachable through its alias
+    let diag = table_not_found(&mut analyzer, "SELECT public.users.* FROM users u");
+    assert_eq!(
+        diag.help.as_deref(),
+        Some("table 'users' is aliased as 'u'; use the alias: 'u.*'")
+    );
+    assert_eq!(diag.span.unwrap().column, 15);
+}
+
+#[test]
+fn test_unknown_cte_columns_are_a_fallback() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // A CTE whose columns can't be determined doesn't make a column of
+    // another relation ambiguous
+    let diagnostics =
+        analyzer.analyze("WITH c AS (SELECT * FROM my_func() f) SELECT name FROM users, c");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    // Columns no other relation has may still come from it
+    let diagnostics =
+        analyzer.analyze("WITH c AS (SELECT * FROM my_func() f) SELECT anything FROM users, c");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    // Relations of known columns are still ambiguous
+    let diagnostics =
+        analyzer.analyze("WITH c AS (SELECT * FROM my_func() f) SELECT id FROM users, orders, c");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::AmbiguousColumn);
+}
+
+#[test]
+fn test_table_name_hidden_by_alias() {
+    let catalog = setup_catalog();
+    let queries = [
+        "SELECT users.id FROM users u",
+        "SELECT u.id FROM users u WHERE users.name = 'a'",
+        "SELECT u.id FROM users u JOIN orders o ON users.id = o.user_id",
+        "SELECT users.* FROM users u",
+    ];
+
+    // PostgreSQL: once aliased, the table's own name is out of scope
+    let mut analyzer = Analyzer::new(&catalog);
+    for sql in queries {
+        let diagnostics = analyzer.analyze(sql);
+        assert_eq!(diagnostics.len(), 1, "{}: {:?}", sql, diagnostics);
+        let diag = &diagnostics[0];
+        assert_eq!(diag.kind, DiagnosticKind::TableNotFound, "{}", sql);
+        assert!(diag.message.contains("is aliased"), "{}", diag.message);
+        assert!(
+            diag.help
+                .as_deref()
+                .unwrap()
+                .starts_with("table 'users' is aliased as 'u'; use the alias"),
+            "{:?}",
+            diag.help
+        );
+        assert_eq!(
+            diag.span.unwrap().column,
+            sql.find("users.").unwrap() + 1,
+            "{}",
+            sql
+        );
+    }
+
+    // A self-join leaves the name ambiguous
+    let diagnostics = analyzer.analyze("SELECT users.id FROM users a JOIN users b ON a.id = b.id");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("table 'users' is aliased as 'a', 'b'; use one of the aliases")
+    );
+
+    // MySQL accepts the name, and still checks the column
+    let mut analyzer = Analyzer::with_dialect(&catalog, SqlDialect::MySQL);
+    for sql in queries {
+        let diagnostics = analyzer.analyze(sql);
+        assert!(diagnostics.is_empty(), "{}: {:?}", sql, diagnostics);
+    }
+    let diagnostics = analyzer.analyze("SELECT users.nope FROM users u");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColumnNotFound);
+    let diagnostics = analyzer.analyze("SELECT users.id FROM users a JOIN users b ON a.id = b.id");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TableNotFound);
+}
+
+// ========== Deprecation Tests ==========
+
+fn deprecation_catalog() -> Catalog {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, legacy_flags INTEGER, flags INTEGER);
+             CREATE TABLE old_audit (id INTEGER);
+             COMMENT ON COLUMN users.legacy_flags IS 'DEPRECATED: use flags';
+             COMMENT ON TABLE old_audit IS 'Deprecated';",
+        )
+        .unwrap();
+    builder.build().0
+}
+
+fn deprecation_warnings(analyzer: &mut Analyzer, sql: &str) -> Vec<(usize, String)> {
+    analyzer
+        .analyze(sql)
+        .into_iter()
+        .filter(|d| d.kind == DiagnosticKind::DeprecatedUsage)
+        .map(|d| (d.span.unwrap().column, d.help.unwrap_or_default()))
+        .collect()
+}
+
+#[test]
+fn test_deprecated_column_usage() {
+    let catalog = deprecation_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let use_flags = |column| vec![(column, "use flags".to_string())];
+    assert_eq!(
+        deprecation_warnings(&mut analyzer, "SELECT legacy_flags FROM users"),
+        use_flags(8)
+    );
+    assert_eq!(
+        deprecation_warnings(
+            &mut analyzer,
+            "SELECT u.id FROM users u WHERE u.legacy_flags = 1"
+        ),
+        use_flags(34)
+    );
+    assert_eq!(
+        deprecation_warnings(
+            &mut analyzer,
+            "INSERT INTO users (id, legacy_flags) VALUES (1, 2)"
+        ),
+        use_flags(24)
+    );
+    assert_eq!(
+        deprecation_warnings(&mut analyzer, "UPDATE users SET legacy_flags = 0"),
+        use_flags(18)
+    );
+
+    // Columns of the same name from a CTE or subquery aren't the deprecated one
+    assert_eq!(
+        deprecation_warnings(
+            &mut analyzer,
+            "WITH users AS (SELECT 1 AS legacy_flags) SELECT legacy_flags FROM users"
+        ),
+        vec![]
+    );
+    assert_eq!(
+        deprecation_warnings(&mut analyzer, "SELECT id, flags FROM users"),
+        vec![]
+    );
+}
+
+#[test]
+fn test_deprecated_table_usage() {
+    let mut catalog = deprecation_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze("SELECT id FROM old_audit");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].severity, sqlsurge_core::Severity::Warning);
+    assert_eq!(diagnostics[0].code(), "W0010");
+    assert_eq!(diagnostics[0].message, "Table 'old_audit' is deprecated");
+
+    // Marked programmatically, e.g. from configuration
+    assert!(catalog.deprecate(&QualifiedName::new("users"), Some("FLAGS"), "use bitmask"));
+    assert!(!catalog.deprecate(&QualifiedName::new("users"), Some("nope"), ""));
+    let mut analyzer = Analyzer::new(&catalog);
+    assert_eq!(
+        deprecation_warnings(&mut analyzer, "SELECT flags FROM users"),
+        vec![(8, "use bitmask".to_string())]
+    );
+}
+
+// ========== Write Policy Tests ==========
+
+fn classify_sql(sql: &str) -> StatementClass {
+    let statements = Parser::parse_sql(&PostgreSqlDialect {}, sql).unwrap();
+    classify(&statements[0])
+}
+
+#[test]
+fn test_classify_statements() {
+    for (sql, class) in [
+        ("SELECT id FROM users", StatementClass::Read),
+        ("VALUES (1), (2)", StatementClass::Read),
+        ("COPY users TO STDOUT", StatementClass::Read),
+        (
+            "INSERT INTO users (name) VALUES ('a')",
+            StatementClass::Write,
+        ),
+        ("DELETE FROM users", StatementClass::Write),
+        ("TRUNCATE orders", StatementClass::Write),
+        ("EXPLAIN ANALYZE DELETE FROM users", StatementClass::Write),
+        ("EXPLAIN DELETE FROM users", StatementClass::Other),
+        ("SELECT id INTO archive FROM users", StatementClass::Ddl),
+        ("CREATE INDEX idx ON users (name)", StatementClass::Ddl),
+        ("DROP TABLE orders", StatementClass::Ddl),
+        ("BEGIN", StatementClass::Other),
+    ] {
+        assert_eq!(classify_sql(sql), class, "{}", sql);
+    }
+}
+
+#[test]
+fn test_classify_data_modifying_cte() {
+    // The outer SELECT reads, but the CTE writes
+    for sql in [
+        "WITH moved AS (UPDATE users SET name = 'x' RETURNING id) SELECT id FROM moved",
+        "WITH added AS (INSERT INTO users (name) VALUES ('a') RETURNING id) SELECT id FROM added",
+        "WITH a AS (SELECT id FROM users), \
+              b AS (WITH c AS (UPDATE users SET name = 'x' RETURNING id) SELECT id FROM c) \
+         SELECT id FROM a UNION SELECT id FROM b",
+    ] {
+        assert_eq!(classify_sql(sql), StatementClass::Write, "{}", sql);
+    }
+    assert_eq!(
+        classify_sql("WITH a AS (SELECT id FROM users) SELECT id FROM a"),
+        StatementClass::Read
+    );
+}
+
+#[test]
+fn test_write_policy_violations() {
+    let catalog = setup_catalog();
+    let sql = "SELECT id FROM users;\n\
+               WITH moved AS (UPDATE users SET name = 'x' RETURNING id) SELECT id FROM moved;\n\
+               CREATE INDEX idx ON users (name);";
+
+    let mut analyzer = Analyzer::new(&catalog);
+    assert!(analyzer.analyze(sql).is_empty());
+
+    let mut analyzer = Analyzer::new(&catalog).with_policy(WritePolicy::ReadOnly);
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+    assert!(diagnostics
+        .iter()
+        .all(|d| d.kind == DiagnosticKind::PolicyViolation && d.code() == "E0015"));
+    assert_eq!(
+        diagnostics[0].message,
+        "Data-modifying statements are not allowed by the 'read-only' policy"
+    );
+    assert_eq!(diagnostics[0].span.unwrap().line, 2);
+    assert_eq!(
+        diagnostics[1].message,
+        "Schema changes are not allowed by the 'read-only' policy"
+    );
+
+    let mut analyzer = Analyzer::new(&catalog).with_policy(WritePolicy::DdlForbidden);
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].span.unwrap().line, 3);
+
+    assert_eq!("read-only".parse(), Ok(WritePolicy::ReadOnly));
+    assert!("readonly".parse::<WritePolicy>().is_err());
+}
+
+// ========== Function Signature Tests ==========
+
+fn function_catalog() -> Catalog {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+             CREATE FUNCTION add_one(x integer) RETURNS integer LANGUAGE sql AS $$ SELECT x + 1 $$;
+             CREATE FUNCTION touch() RETURNS trigger LANGUAGE plpgsql AS $$ BEGIN RETURN NEW; END $$;",
+        )
+        .unwrap();
+    let (mut catalog, _) = builder.build();
+    catalog.add_function(FunctionSig::new(
+        "my_score",
+        vec![SqlType::Integer, SqlType::Text],
+        SqlType::Decimal {
+            precision: None,
+            scale: None,
+        },
+    ));
+    catalog
+        .add_function(FunctionSig::new("join_all", vec![SqlType::Text], SqlType::Text).variadic());
+    catalog
+}
+
+#[test]
+fn test_declared_function_argument_count() {
+    let catalog = function_catalog();
+    assert_eq!(catalog.get_functions("add_one").len(), 1);
+    // Trigger functions can't be called and aren't recorded
+    assert!(catalog.get_functions("touch").is_empty());
+
+    let mut analyzer = Analyzer::new(&catalog);
+    let diagnostics = analyzer.analyze("SELECT add_one(id, 2) FROM users");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::FunctionArgumentCount);
+    assert_eq!(diagnostics[0].span.unwrap().column, 8);
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some("Declared as add_one(integer)")
+    );
+
+    let diagnostics = analyzer.analyze("SELECT name FROM users WHERE my_score(id) > 1");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::FunctionArgumentCount);
+
+    // A variadic last argument takes one or more values
+    assert_resolves(&mut analyzer, "SELECT join_all(name, name, 'x') FROM users");
+    let diagnostics = analyzer.analyze("SELECT join_all() FROM users");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(
+        diagnostics[0].message.contains("at least 1"),
+        "{}",
+        diagnostics[0].message
+    );
+
+    // Undeclared functions stay unchecked
+    assert_resolves(&mut analyzer, "SELECT other_fn(id, name, 1) FROM users");
+}
+
+#[test]
+fn test_declared_function_argument_types() {
+    let catalog = function_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze("SELECT my_score(name, name) FROM users");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TypeMismatch);
+    assert_eq!(
+        diagnostics[0].message,
+        "Argument 1 of function 'my_score' must be integer, found text"
+    );
+    assert_eq!(diagnostics[0].span.unwrap().column, 17);
+
+    // Quoted literals convert to any type; integers widen implicitly
+    assert_resolves(&mut analyzer, "SELECT my_score('1', name) FROM users");
+    assert_resolves(&mut analyzer, "SELECT my_score(id, 'x') FROM users");
+
+    // The return type takes part in type checking
+    let diagnostics = analyzer.analyze("SELECT id FROM users WHERE add_one(id) = name");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TypeMismatch);
+    let columns = analyzer
+        .infer_output_schema("SELECT add_one(id) AS a, my_score(id, name) AS s FROM users")
+        .unwrap();
+    assert_eq!(columns[0].data_type, SqlType::Integer);
+    assert!(matches!(columns[1].data_type, SqlType::Decimal { .. }));
+
+    // MySQL converts arguments like assignments; only the count is checked
+    let mut analyzer = Analyzer::with_dialect(&catalog, SqlDialect::MySQL);
+    assert_resolves(&mut analyzer, "SELECT my_score(name, name) FROM users");
+    let diagnostics = analyzer.analyze("SELECT my_score(name) FROM users");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::FunctionArgumentCount);
+}
+
+#[test]
+fn test_declared_function_lookup() {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(
+            "CREATE TABLE products (id INTEGER PRIMARY KEY, price NUMERIC(10, 2));
+             CREATE FUNCTION round(x money) RETURNS money LANGUAGE sql AS $$ SELECT x $$;
+             CREATE SCHEMA audit;
+             CREATE FUNCTION audit.log_it(x integer) RETURNS void LANGUAGE sql AS $$ SELECT 1 $$;",
+        )
+        .unwrap();
+    let (catalog, _) = builder.build();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // Declaring an overload of a built-in doesn't replace its signatures
+    assert_resolves(&mut analyzer, "SELECT round(price, 2) FROM products");
+
+    // Calls are matched by schema
+    let diagnostics = analyzer.analyze("SELECT audit.log_it(1, 2)");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::FunctionArgumentCount);
+    assert_resolves(&mut analyzer, "SELECT audit.log_it(1)");
+    assert_resolves(&mut analyzer, "SELECT other.log_it(1, 2)");
+    assert_resolves(&mut analyzer, "SELECT log_it(1, 2)");
+}
+
+// ========== Batch Report Tests ==========
+
+#[test]
+fn test_analyze_batch_report() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let report = analyzer.analyze_batch([
+        ("ok.sql", "SELECT id FROM users"),
+        ("bad.sql", "SELECT nme FROM users; SELECT x.id FROM users"),
+        ("deep.sql", "SELECT 1"),
+    ]);
+    assert_eq!(report.inputs().len(), 3);
+    assert!(report.inputs()[0].diagnostics.is_empty());
+    assert!(report.has_errors());
+    assert_eq!(
+        report.worst_severity(),
+        Some(sqlsurge_core::Severity::Error)
+    );
+    assert_eq!(report.severity_counts().error, 2);
+    assert_eq!(report.rule_counts().get("E0001"), Some(&1));
+    assert_eq!(report.rule_counts().get("E0002"), Some(&1));
+    assert!(report.diagnostics().all(|(name, _)| name == "bad.sql"));
+
+    // Inputs are analyzed independently, like separate `analyze` calls
+    let report = analyzer.analyze_batch([
+        ("a.sql", "CREATE TEMP TABLE t (x INT); SELECT x FROM t"),
+        ("b.sql", "SELECT x FROM t"),
+    ]);
+    assert!(report.inputs()[0].diagnostics.is_empty());
+    assert_eq!(report.rule_counts().get("E0001"), Some(&1));
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["has_errors"], true);
+    assert_eq!(
+        json["diagnostics"]["error"],
+        report.severity_counts().error as u64
+    );
+    assert_eq!(json["rules"]["E0001"], 1);
+    assert_eq!(json["inputs"][1]["name"], "b.sql");
+}
+
+// ========== Duplicate Output Column Tests ==========
+
+#[test]
+fn test_duplicate_output_column() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // The span is on the second occurrence
+    let sql = "SELECT id, name AS id FROM users";
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::DuplicateOutputColumn);
+    assert_eq!(
+        diagnostics[0].message,
+        "Output column 'id' appears more than once"
+    );
+    assert_eq!(
+        diagnostics[0].span.unwrap().column,
+        sql.rfind("id").unwrap() + 1
+    );
+    assert!(diagnostics[0].help.as_ref().unwrap().contains("AS id_2"));
+
+    let diagnostics =
+        analyzer.analyze("SELECT u.id, o.id FROM users u JOIN orders o ON o.user_id = u.id");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(diagnostics[0].message.contains("from 'u' and 'o'"));
+    assert!(diagnostics[0].help.as_ref().unwrap().contains("AS o_id"));
+
+    // Only the final output of a set operation is checked
+    let diagnostics = analyzer
+        .analyze("SELECT id, name FROM users UNION ALL SELECT id, user_id AS id FROM orders");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let diagnostics =
+        analyzer.analyze("SELECT id, id FROM users UNION ALL SELECT id, user_id FROM orders");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+
+    // Suppressible like any rule
+    let mut analyzer = Analyzer::new(&catalog).with_disabled_rules(["W0015"]);
+    assert!(analyzer
+        .analyze("SELECT id, name AS id FROM users")
+        .is_empty());
+}
+
+#[test]
+fn test_duplicate_output_column_from_wildcard() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let diagnostics = analyzer.analyze("SELECT * FROM users u JOIN orders o ON o.user_id = u.id");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::DuplicateOutputColumn);
+    assert_eq!(
+        diagnostics[0].message,
+        "Output column 'id' appears more than once, from 'u' and 'o'"
+    );
+    assert!(diagnostics[0]
+        .help
+        .as_ref()
+        .unwrap()
+        .contains("instead of *"));
+
+    let diagnostics = analyzer.analyze("SELECT u.*, o.id FROM users u JOIN orders o ON TRUE");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert!(diagnostics[0].message.contains("from 'u' and 'o'"));
+
+    // USING and NATURAL joins output the merged column once
+    for sql in [
+        "SELECT * FROM users JOIN orders USING (id)",
+        "SELECT * FROM users NATURAL JOIN orders",
+        // Duplicates inside a derived table don't reach the output
+        "SELECT s.total FROM (SELECT o.total, o.id, u.id FROM orders o, users u) s",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert!(diagnostics.is_empty(), "{}: {:?}", sql, diagnostics);
+    }
+}
+
+// ========== Statement Attribution Tests ==========
+
+#[test]
+fn test_diagnostics_name_their_statement() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let sql = "SELECT id FROM users;\n\nSELECT nme\nFROM users;\nSELECT total FROM orders;\n";
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    let statement = diagnostics[0].statement.unwrap();
+    assert_eq!(statement.index, 1);
+    assert_eq!(
+        &sql[statement.span.offset..statement.span.offset + statement.span.length],
+        "SELECT nme\nFROM users"
+    );
+    assert_eq!((statement.span.line, statement.span.column), (3, 1));
+    assert_eq!(statement.span.end_line, 4);
+
+    // Also for a parse error, found by the per-statement fallback
+    let sql = "SELECT id FROM users;\nSELEC name FROM users;\nSELECT total FROM orders;";
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ParseError);
+    let statement = diagnostics[0].statement.unwrap();
+    assert_eq!(statement.index, 1);
+    assert_eq!(statement.span.offset, 22);
+    assert_eq!(statement.span.line, 2);
+
+    // Statements after a failed one keep their position in the input
+    let diagnostics = analyzer.analyze("SELEC 1;\nSELECT nme FROM users;");
+    assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+    assert_eq!(diagnostics[1].kind, DiagnosticKind::ColumnNotFound);
+    assert_eq!(diagnostics[1].statement.unwrap().index, 1);
+}
+
+// ========== Schema-less Tests ==========
+
+#[test]
+fn test_analyze_without_catalog() {
+    let mut analyzer = Analyzer::without_catalog(SqlDialect::PostgreSQL);
+    assert!(analyzer.is_schemaless());
+
+    // Names, types and nullability aren't checked
+    let diagnostics = analyzer.analyze(
+        "SELECT nme, id + 'x' FROM users u JOIN orderz o ON o.user_id = u.id;\n\
+         INSERT INTO users (id) VALUES (NULL);\n\
+         CREATE TEMP TABLE t (x INT); SELECT y FROM t;",
+    );
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    // Parse errors and structural rules still are
+    let diagnostics = analyzer.analyze(
+        "SELECT id FROM users WHERE row_number() OVER () > 1;\n\
+         SELECT id FROM orders JOIN orders ON true;\n\
+         SELECT id FROM users LIMIT -1;\n\
+         SELEC 1;",
+    );
+    let kinds: Vec<_> = diagnostics.iter().map(|d| d.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            DiagnosticKind::ParseError,
+            DiagnosticKind::MisplacedWindowFunction,
+            DiagnosticKind::DuplicateTableReference,
+            DiagnosticKind::InvalidRowLimit,
+        ],
+        "{:?}",
+        diagnostics
+    );
+}
+
+// ========== Streaming Tests ==========
+
+#[test]
+fn test_analyze_streaming_matches_analyze() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let sql = "CREATE TEMP TABLE t (x INT);\n\
+               SELECT x FROM t; SELECT nme FROM users;\n\
+               SELECT id\n  FROM orders\n  WHERE totl > 1;\n\
+               SELEC 1;\n\
+               INSERT INTO users (nam) VALUES ('a');\n";
+    let mut expected = analyzer.analyze(sql);
+    // Parse errors come first from `analyze`, in their statement's place
+    // when streaming
+    expected.sort_by_key(|d| d.statement.map(|s| s.index));
+    let mut streamed = Vec::new();
+    analyzer.analyze_streaming(sql, |diagnostic| streamed.push(diagnostic));
+
+    assert_eq!(expected.len(), 4, "{:?}", expected);
+    assert_eq!(streamed.len(), expected.len(), "{:?}", streamed);
+    for (streamed, expected) in streamed.iter().zip(&expected) {
+        assert_eq!(streamed.kind, expected.kind);
+        assert_eq!(streamed.message, expected.message);
+        assert_eq!(streamed.span, expected.span);
+        assert_eq!(streamed.statement, expected.statement);
+    }
+}
+
+// ========== Profiling Tests ==========
+
+#[test]
+fn test_profiling() {
+    let catalog = setup_catalog();
+    let sql = "SELECT id FROM users; UPDATE orders SET total = 0; SELECT nme FROM users";
+
+    let mut analyzer = Analyzer::new(&catalog);
+    analyzer.analyze(sql);
+    assert!(analyzer.profile().is_none());
+
+    let mut analyzer = Analyzer::new(&catalog).with_profiling(true);
+    let diagnostics = analyzer.analyze(sql);
+    // Profiling doesn't change the diagnostics
+    assert_eq!(diagnostics.len(), 1);
+    let profile = analyzer.profile().unwrap();
+    assert_eq!(profile.statements.len(), 3);
+    assert!(profile.parse > Duration::ZERO);
+    assert_eq!(
+        profile.total(),
+        Phase::ALL.iter().map(|&p| profile.phase_total(p)).sum()
+    );
+
+    // The report covers the last input only
+    analyzer.analyze("SELECT 1");
+    assert_eq!(analyzer.profile().unwrap().statements.len(), 1);
+}
+
+#[test]
+fn test_ast_cache_keeps_diagnostics() {
+    let catalog = setup_catalog();
+    let inputs = [
+        "SELECT nme FROM users;\nSELECT id FROM users WHERE id = 'x';",
+        "SELECT nme FROM users;\nSELECT id FROM users WHERE id = 'x';",
+        // Same statements, moved down a line
+        "\nSELECT nme FROM users;\nSELECT id FROM users WHERE id = 'x';",
+        "SELECT nme FROM users;\nSELEC oops;\nINSERT INTO users (id) VALUES (1);",
+        "CREATE TEMP TABLE t (a INT);\nSELECT b FROM t;",
+        "CREATE TEMP TABLE t (b INT);\nSELECT b FROM t;",
+    ];
+    let rendered = |diagnostics: Vec<sqlsurge_core::Diagnostic>| format!("{:?}", diagnostics);
+
+    let mut plain = Analyzer::new(&catalog);
+    let mut cached = Analyzer::new(&catalog)
+        .with_ast_cache(100)
+        .with_profiling(true);
+    let mut counts = Vec::new();
+    for sql in inputs {
+        assert_eq!(
+            rendered(cached.analyze(sql)),
+            rendered(plain.analyze(sql)),
+            "{}",
+            sql
+        );
+        let profile = cached.profile().unwrap();
+        counts.push((profile.cache_hits, profile.cache_misses));
+    }
+    assert_eq!(counts, vec![(0, 2), (2, 0), (0, 2), (1, 2), (0, 2), (1, 1)]);
+
+    // Without a cache nothing is counted
+    let mut profiled = Analyzer::new(&catalog).with_profiling(true);
+    profiled.analyze(inputs[0]);
+    let profile = profiled.profile().unwrap();
+    assert_eq!((profile.cache_hits, profile.cache_misses), (0, 0));
+}
+
+// ========== Performance Hint Tests ==========
+
+#[test]
+fn test_non_sargable_predicate() {
+    let catalog = setup_catalog();
+    let sql = "SELECT id FROM users WHERE lower(email) = 'a@example.com'";
+
+    // Opt-in, as a group
+    let mut analyzer = Analyzer::new(&catalog);
+    assert!(analyzer.analyze(sql).is_empty());
+
+    let mut analyzer = Analyzer::new(&catalog).with_enabled_rules(["performance"]);
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::NonSargablePredicate);
+    assert_eq!(diagnostics[0].severity, sqlsurge_core::Severity::Hint);
+    assert_eq!(
+        diagnostics[0].message,
+        "Column 'email' is wrapped in lower(), so an index on it can't be used"
+    );
+    assert_eq!(diagnostics[0].span.unwrap().column, 28);
+    assert_eq!(
+        diagnostics[0].help.as_deref(),
+        Some(
+            "Store 'email' in one case, or compare it with a case-insensitive collation, \
+             or create an expression index on (lower(email))"
+        )
+    );
+
+    // The help depends on the transformation
+    for (sql, help) in [
+        (
+            "SELECT id FROM users WHERE name::date = '2024-01-01'",
+            "Compare 'name' with the range of the day: >= the date AND < the next day, \
+             or create an expression index on (name::DATE)",
+        ),
+        (
+            "SELECT id FROM users u WHERE u.id::text = $1",
+            "Compare 'u.id' itself and cast the value instead, \
+             or create an expression index on (u.id::TEXT)",
+        ),
+        (
+            "SELECT id FROM orders WHERE abs(total) > 100",
+            "Compare 'total' itself and apply the inverse of abs() to the value, \
+             or create an expression index on (abs(total))",
+        ),
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert_eq!(diagnostics.len(), 1, "{}: {:?}", sql, diagnostics);
+        assert_eq!(diagnostics[0].help.as_deref(), Some(help), "{}", sql);
+    }
+
+    // Casts, parameters, and UPDATE / DELETE
+    for sql in [
+        "SELECT id FROM users u WHERE u.id::text = $1",
+        "SELECT id FROM users WHERE 10 < CAST(id AS BIGINT)",
+        "UPDATE users SET name = 'x' WHERE upper(name) = 'A'",
+        "DELETE FROM orders WHERE abs(total) > 100",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert_eq!(diagnostics.len(), 1, "{}: {:?}", sql, diagnostics);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::NonSargablePredicate);
+    }
+
+    for sql in [
+        // The transformation is on the value side
+        "SELECT id FROM users WHERE email = lower('A@example.com')",
+        // Compared with another column
+        "SELECT u.id FROM users u JOIN orders o ON o.user_id = u.id WHERE lower(u.name) = lower(o.total::text)",
+        // Not a column of the FROM clause
+        "SELECT id FROM users WHERE lower('x') = 'x'",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert!(diagnostics.is_empty(), "{}: {:?}", sql, diagnostics);
+    }
+}
+
+#[test]
+fn test_coerced_column_comparison() {
+    let mut builder = SchemaBuilder::with_dialect(SqlDialect::MySQL);
+    builder
+        .parse("CREATE TABLE items (id INT PRIMARY KEY, code VARCHAR(20) NOT NULL);")
+        .unwrap();
+    let (catalog, _) = builder.build();
+    let mut analyzer = Analyzer::with_dialect(&catalog, SqlDialect::MySQL)
+        .with_enabled_rules(["coerced-column-comparison"]);
+
+    // Also a type mismatch (E0003), which is reported instead
+    let diagnostics = analyzer.analyze("SELECT id FROM items WHERE code = 123");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::TypeMismatch);
+
+    // The hint explains the cost when E0003 is off
+    let mut analyzer = Analyzer::with_dialect(&catalog, SqlDialect::MySQL)
+        .with_enabled_rules(["coerced-column-comparison"])
+        .with_rule_severity([("E0003", sqlsurge_core::SeverityOrOff::Off)]);
+    let diagnostics = analyzer.analyze("SELECT id FROM items WHERE code = 123");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::CoercedColumnComparison);
+    assert_eq!(diagnostics[0].code(), "W0013");
+    assert_eq!(
+        diagnostics[0].message,
+        "Comparing varchar(20) column 'code' with the number 123 converts the column on every row"
+    );
+    assert!(diagnostics[0].help.as_deref().unwrap().ends_with("'123'"));
+
+    for sql in [
+        // The string is converted, once
+        "SELECT id FROM items WHERE id = '123'",
+        "SELECT id FROM items WHERE code = '123'",
+        "SELECT id FROM items WHERE code = ?",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert!(
+            diagnostics
+                .iter()
+                .all(|d| d.kind != DiagnosticKind::CoercedColumnComparison),
+            "{}: {:?}",
+            sql,
+            diagnostics
+        );
+    }
+
+    // MySQL semantics only
+    let mut analyzer = Analyzer::new(&catalog).with_enabled_rules(["W0013"]);
+    let diagnostics = analyzer.analyze("SELECT id FROM items WHERE code = 123");
+    assert!(diagnostics
+        .iter()
+        .all(|d| d.kind != DiagnosticKind::CoercedColumnComparison));
+}
+
+#[test]
+fn test_unindexed_join_column() {
+    let schema = "CREATE TABLE users (id SERIAL PRIMARY KEY, email TEXT UNIQUE, name TEXT);
+         CREATE TABLE orders (id SERIAL PRIMARY KEY, user_id INT REFERENCES users (id), \
+         email TEXT, created_at TIMESTAMP);";
+    let sql = "SELECT u.name FROM users u JOIN orders o ON o.user_id = u.id";
+
+    // Without any index declared there is no index information
+    let mut builder = SchemaBuilder::new();
+    builder.parse(schema).unwrap();
+    let (catalog, _) = builder.build();
+    let mut analyzer = Analyzer::new(&catalog).with_enabled_rules(["performance"]);
+    assert!(analyzer.analyze(sql).is_empty());
+
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse(&format!(
+            "{schema} CREATE INDEX ON orders (created_at, user_id);"
+        ))
+        .unwrap();
+    let (catalog, _) = builder.build();
+    let mut analyzer = Analyzer::new(&catalog);
+    assert!(analyzer.analyze(sql).is_empty());
+
+    let mut analyzer = Analyzer::new(&catalog).with_enabled_rules(["unindexed-join-column"]);
+    let diagnostics = analyzer.analyze(sql);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::UnindexedJoinColumn);
+    assert_eq!(diagnostics[0].severity, sqlsurge_core::Severity::Hint);
+    assert_eq!(
+        diagnostics[0].message,
+        "Join column 'o.user_id' is not the leading column of any index on 'orders'"
+    );
+    assert_eq!(diagnostics[0].span.unwrap().column, 45);
+
+    // Both sides, each column once
+    let diagnostics = analyzer.analyze(
+        "SELECT 1 FROM users u JOIN orders o ON o.email = u.name AND o.email = u.email \
+         JOIN orders o2 ON o2.email = u.name",
+    );
+    let columns: Vec<_> = diagnostics
+        .iter()
+        .map(|d| d.message.split('\'').nth(1).unwrap())
+        .collect();
+    assert_eq!(columns, ["o.email", "u.name"], "{:?}", diagnostics);
+
+    for sql in [
+        // Leading column of an index, and a UNIQUE column
+        "SELECT 1 FROM orders o JOIN orders p ON p.created_at = o.created_at",
+        "SELECT 1 FROM users u JOIN orders o ON u.email = o.id",
+        // Not an equality between columns
+        "SELECT 1 FROM users u JOIN orders o ON o.user_id > u.id",
+        "SELECT 1 FROM users u JOIN orders o ON o.user_id = 1",
+    ] {
+        let diagnostics = analyzer.analyze(sql);
+        assert!(
+            diagnostics
+                .iter()
+                .all(|d| d.kind != DiagnosticKind::UnindexedJoinColumn),
+            "{}: {:?}",
+            sql,
+            diagnostics
+        );
+    }
+
+    // InnoDB indexes foreign keys
+    let mut builder = SchemaBuilder::with_dialect(SqlDialect::MySQL);
+    builder
+        .parse(
+            "CREATE TABLE users (id INT PRIMARY KEY, name TEXT, INDEX idx_name (name));
+             CREATE TABLE orders (id INT PRIMARY KEY, user_id INT, \
+             FOREIGN KEY (user_id) REFERENCES users (id));",
+        )
+        .unwrap();
+    let (catalog, _) = builder.build();
+    let mut analyzer =
+        Analyzer::with_dialect(&catalog, SqlDialect::MySQL).with_enabled_rules(["W0018"]);
+    let diagnostics = analyzer.analyze(sql);
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+#[test]
+fn test_copy_from_stdin_data_block() {
+    let catalog = setup_catalog();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    let sql = "COPY users (id, name, email) FROM stdin;\n1\tAlice\t\\N\n2\tBob\tbob@example.com\n\\.\n\nSELECT id FROM users;\n";
+    let diagnostics = analyzer.analyze(sql);
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
@@ -0,0 +1 @@
+SELECT id FROM user_accounts;
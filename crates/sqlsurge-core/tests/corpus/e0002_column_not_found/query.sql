@@ -0,0 +1 @@
+SELECT nme FROM users;
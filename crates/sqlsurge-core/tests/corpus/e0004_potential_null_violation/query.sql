@@ -0,0 +1,2 @@
+INSERT INTO users DEFAULT VALUES;
+INSERT INTO users VALUES (DEFAULT, DEFAULT, DEFAULT);
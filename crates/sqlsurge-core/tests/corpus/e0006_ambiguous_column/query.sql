@@ -0,0 +1 @@
+SELECT id FROM users JOIN orders ON orders.user_id = users.id;
@@ -0,0 +1,3 @@
+SELECT users.id
+FROM users
+JOIN orders ON orders.user_id = users.name;
@@ -0,0 +1 @@
+INSERT INTO people (first_name, last_name, full_name) VALUES ('a', 'b', 'a b');
@@ -0,0 +1,5 @@
+CREATE TABLE people (
+    first_name TEXT,
+    last_name TEXT,
+    full_name TEXT GENERATED ALWAYS AS (first_name || ' ' || last_name) STORED
+);
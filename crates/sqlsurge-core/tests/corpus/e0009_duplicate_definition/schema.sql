@@ -0,0 +1,2 @@
+CREATE TABLE users (id INTEGER);
+CREATE TABLE users (id INTEGER, name TEXT);
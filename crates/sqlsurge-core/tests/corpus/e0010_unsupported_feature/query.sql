@@ -0,0 +1,2 @@
+WITH recent AS (SELECT id FROM users)
+SELECT id FROM recent;
@@ -0,0 +1 @@
+SELECT users.id FROM users JOIN users ON users.id = users.id;
@@ -0,0 +1,2 @@
+SELECT id
+FROM users USE INDEX (idx_emial) WHERE id = 1;
@@ -0,0 +1,5 @@
+CREATE TABLE users (
+    id INT PRIMARY KEY,
+    email VARCHAR(255),
+    INDEX idx_email (email)
+);
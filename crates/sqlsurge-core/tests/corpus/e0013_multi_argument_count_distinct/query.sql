@@ -0,0 +1 @@
+SELECT COUNT(DISTINCT name, email) FROM users;
@@ -0,0 +1 @@
+SELECT DISTINCT name FROM users ORDER BY email;
@@ -0,0 +1,2 @@
+SELECT id FROM users;
+DELETE FROM orders;
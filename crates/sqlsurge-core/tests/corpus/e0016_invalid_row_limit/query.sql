@@ -0,0 +1,3 @@
+SELECT name FROM users ORDER BY id LIMIT -1;
+SELECT name FROM users ORDER BY id LIMIT 'ten';
+SELECT name FROM users ORDER BY id LIMIT 10 OFFSET id;
@@ -0,0 +1,3 @@
+SELECT display_name() FROM users;
+SELECT display_name(id, name, 'x') FROM users;
+SELECT display_name(id), display_name(id, 'guest') FROM users;
@@ -0,0 +1,7 @@
+CREATE TABLE users (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) NOT NULL
+);
+
+CREATE FUNCTION display_name(user_id INTEGER, fallback TEXT DEFAULT 'anonymous') RETURNS TEXT
+LANGUAGE sql AS $$ SELECT name FROM users WHERE id = user_id $$;
@@ -0,0 +1,3 @@
+SELECT id FROM orders WHERE row_number() OVER (PARTITION BY user_id ORDER BY id) = 1;
+SELECT user_id FROM orders GROUP BY rank() OVER (ORDER BY total), user_id;
+SELECT id, row_number() OVER (PARTITION BY user_id ORDER BY id) AS rn FROM orders ORDER BY rn;
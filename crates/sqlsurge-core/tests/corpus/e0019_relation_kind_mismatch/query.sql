@@ -0,0 +1,3 @@
+UPDATE order_totals SET total = 0 WHERE user_id = 1;
+REFRESH MATERIALIZED VIEW orders;
+REFRESH MATERIALIZED VIEW order_totals;
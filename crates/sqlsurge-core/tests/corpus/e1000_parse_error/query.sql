@@ -0,0 +1 @@
+SELEC id FROM users;
@@ -0,0 +1,4 @@
+SELECT u.name, o.total
+FROM users u
+JOIN orders o ON o.user_id = u.id
+WHERE u.email IS NOT NULL;
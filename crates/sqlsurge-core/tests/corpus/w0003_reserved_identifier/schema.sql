@@ -0,0 +1 @@
+CREATE TABLE "user" (id INT, "order" INT, name TEXT);
@@ -0,0 +1,5 @@
+CREATE TABLE users (id INTEGER PRIMARY KEY);
+CREATE TABLE orders (
+    id INTEGER PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE SET NULL
+);
@@ -0,0 +1,4 @@
+SELECT u.name
+FROM users u
+JOIN orders o ON o.user_id = u.id
+WHERE o.total > u.id;
@@ -0,0 +1 @@
+SELECT COUNT(name) FROM users;
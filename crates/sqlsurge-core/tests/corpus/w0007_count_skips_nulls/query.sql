@@ -0,0 +1 @@
+SELECT COUNT(email) * 100 / COUNT(*) FROM users;
@@ -0,0 +1,3 @@
+SELECT a.id
+FROM accounts a, events e
+WHERE e.account_id = a.id;
@@ -0,0 +1,2 @@
+CREATE TABLE accounts (id BIGINT PRIMARY KEY, label TEXT);
+CREATE TABLE events (account_id INTEGER, kind CHAR(4));
@@ -0,0 +1 @@
+SELECT name FROM users LIMIT 10;
@@ -0,0 +1,3 @@
+SELECT id, name
+FROM users
+WHERE lower(email) = $1;
@@ -0,0 +1,3 @@
+SELECT id
+FROM items
+WHERE code = -1;
@@ -0,0 +1,4 @@
+CREATE TABLE items (
+    id INT PRIMARY KEY,
+    code VARCHAR(20) NOT NULL
+);
@@ -0,0 +1 @@
+SELECT id FROM audit_log;
@@ -0,0 +1,8 @@
+CREATE TABLE audit_log (id SERIAL PRIMARY KEY, created_at TIMESTAMP NOT NULL);
+
+CREATE FUNCTION purge(table_name TEXT) RETURNS void AS $$
+BEGIN
+    EXECUTE format('DELETE FROM %I WHERE created_at < now()', table_name);
+    EXECUTE 'DELETE FROM ' || table_name;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,2 @@
+SELECT id, name AS id FROM users;
+SELECT * FROM users u JOIN orders o ON o.user_id = u.id;
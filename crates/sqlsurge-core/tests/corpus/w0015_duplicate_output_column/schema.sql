@@ -0,0 +1,2 @@
+CREATE TABLE users (id SERIAL PRIMARY KEY, name TEXT NOT NULL);
+CREATE TABLE orders (id SERIAL PRIMARY KEY, user_id INTEGER NOT NULL REFERENCES users (id));
@@ -0,0 +1,2 @@
+INSERT INTO events (id, name) OVERRIDING USER VALUE VALUES (42, 'signup');
+INSERT INTO events (name) OVERRIDING USER VALUE VALUES ('login');
@@ -0,0 +1 @@
+CREATE TABLE events (id INT GENERATED BY DEFAULT AS IDENTITY, name TEXT NOT NULL);
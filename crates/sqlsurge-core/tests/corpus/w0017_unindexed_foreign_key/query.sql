@@ -0,0 +1 @@
+SELECT id, total FROM orders;
@@ -0,0 +1,3 @@
+SELECT u.name, o.created_at
+FROM users u
+JOIN orders o ON o.user_id = u.id;
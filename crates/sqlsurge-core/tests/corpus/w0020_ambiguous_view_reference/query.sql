@@ -0,0 +1 @@
+SELECT id, kind FROM recent_events;
@@ -0,0 +1,17 @@
+CREATE SCHEMA audit;
+
+CREATE TABLE events (
+    id SERIAL PRIMARY KEY,
+    kind TEXT NOT NULL
+);
+
+CREATE TABLE audit.events (
+    id SERIAL PRIMARY KEY,
+    kind TEXT NOT NULL
+);
+
+CREATE VIEW recent_events AS
+    SELECT id, kind FROM events;
+
+CREATE VIEW audit_kinds AS
+    SELECT DISTINCT kind FROM audit.events;
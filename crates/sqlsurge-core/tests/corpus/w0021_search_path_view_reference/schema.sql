@@ -0,0 +1,12 @@
+CREATE SCHEMA billing;
+
+CREATE TABLE billing.invoices (
+    id SERIAL PRIMARY KEY,
+    paid BOOLEAN NOT NULL
+);
+
+CREATE VIEW open_invoices AS
+    SELECT id FROM invoices WHERE NOT paid;
+
+CREATE VIEW paid_invoices AS
+    SELECT id FROM billing.invoices WHERE paid;
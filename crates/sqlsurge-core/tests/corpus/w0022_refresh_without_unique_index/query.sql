@@ -0,0 +1,2 @@
+REFRESH MATERIALIZED VIEW CONCURRENTLY order_totals;
+REFRESH MATERIALIZED VIEW order_totals;
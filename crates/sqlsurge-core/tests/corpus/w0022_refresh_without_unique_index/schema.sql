@@ -0,0 +1,10 @@
+CREATE TABLE orders (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    total DECIMAL(10, 2)
+);
+
+CREATE INDEX orders_user_id ON orders (user_id);
+
+CREATE MATERIALIZED VIEW order_totals AS
+SELECT user_id, SUM(total) AS total FROM orders GROUP BY user_id;
@@ -0,0 +1,116 @@
+// Regression corpus under tests/corpus: one case per diagnostic kind plus a
+// clean case
+//
+// Run with SQLSURGE_BLESS=1 to regenerate the expected.json files after an
+// intentional change, then review the diff.
+use std::path::PathBuf;
+
+use sqlsurge_core::testing::{run_case, CorpusRunner, Expectations};
+
+#[test]
+fn test_corpus() {
+    let root = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus");
+    let report = CorpusRunner::new(root)
+        .bless(std::env::var_os("SQLSURGE_BLESS").is_some())
+        .run()
+        .unwrap();
+    assert_eq!(report.cases.len(), 41, "{}", report);
+    report.assert_ok();
+}
+
+/// Catalog and full diagnostics (help, labels, spans) of every corpus case,
+/// compared byte for byte with tests/corpus.snap.json
+///
+/// The corpus cases only match messages by substring; this pins the schema
+/// builder's and the resolvers' output exactly, for refactors that must not
+/// change it. Regenerate with SQLSURGE_BLESS=1 like the corpus.
+#[test]
+fn test_corpus_snapshot() {
+    let root = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus"));
+    let mut dirs: Vec<PathBuf> = std::fs::read_dir(&root)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.join("query.sql").is_file())
+        .collect();
+    dirs.sort();
+
+    let mut cases = serde_json::Map::new();
+    for dir in dirs {
+        let expected = std::fs::read_to_string(dir.join("expected.json")).unwrap();
+        let expectations: Expectations = serde_json::from_str(&expected).unwrap();
+        let output = run_case(&dir, &expectations).unwrap();
+
+        let name = dir.file_name().unwrap().to_string_lossy().into_owned();
+        cases.insert(
+            name,
+            serde_json::json!({
+                "catalog": output.catalog,
+                "schema": output.schema,
+                "query": output.query,
+            }),
+        );
+    }
+
+    let actual = serde_json::to_string_pretty(&cases).unwrap() + "\n";
+    let path = PathBuf::from(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/corpus.snap.json"
+    ));
+    if std::env::var_os("SQLSURGE_BLESS").is_some() {
+        std::fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap();
+    pretty_assertions::assert_str_eq!(expected, actual);
+}
+
+/// Write a one-case corpus with `expected` as its expected.json
+fn temp_corpus(test: &str, expected: &str) -> PathBuf {
+    let root = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(test);
+    let _ = std::fs::remove_dir_all(&root);
+    let case = root.join("case");
+    std::fs::create_dir_all(&case).unwrap();
+    std::fs::write(
+        case.join("schema.sql"),
+        "CREATE TABLE users (id INT, name TEXT);",
+    )
+    .unwrap();
+    std::fs::write(
+        case.join("query.sql"),
+        "SELECT nme FROM users;\nSELECT x FROM users;",
+    )
+    .unwrap();
+    std::fs::write(case.join("expected.json"), expected).unwrap();
+    root
+}
+
+#[test]
+fn test_corpus_runner_diff() {
+    let root = temp_corpus(
+        "corpus_runner_diff",
+        r#"{"diagnostics": [
+            {"code": "E0002", "line": 1, "column": 8, "message": "'nme'"},
+            {"code": "E0002", "line": 2, "column": 9, "message": "'x'"}
+        ]}"#,
+    );
+    let report = CorpusRunner::new(&root).run().unwrap();
+    assert!(!report.is_ok());
+    let case = &report.cases[0];
+    // Messages match by substring; positions must be exact
+    assert_eq!(case.missing.len(), 1);
+    assert_eq!(case.missing[0].column, 9);
+    assert_eq!(case.unexpected.len(), 1);
+    assert_eq!(case.unexpected[0].column, 8);
+    assert!(
+        report.to_string().contains("  + E0002 query.sql:2:8"),
+        "{}",
+        report
+    );
+
+    // Blessing accepts the actual diagnostics
+    let report = CorpusRunner::new(&root).bless(true).run().unwrap();
+    assert!(report.cases[0].blessed);
+    let expected = std::fs::read_to_string(root.join("case/expected.json")).unwrap();
+    assert!(expected.contains("Column 'x' not found"), "{}", expected);
+    CorpusRunner::new(&root).run().unwrap().assert_ok();
+}
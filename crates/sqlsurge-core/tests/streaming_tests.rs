@@ -0,0 +1,60 @@
+// Memory use of streaming analysis on a very large file
+//
+// Peak RSS is per process, so this lives in its own test binary. It takes a
+// while in debug builds and is ignored by default:
+//
+//     cargo test --release -p sqlsurge-core --test streaming_tests -- --ignored
+use sqlsurge_core::schema::SchemaBuilder;
+use sqlsurge_core::Analyzer;
+
+const STATEMENTS: usize = 100_000;
+
+/// A seed file of `statements` INSERTs, every 1000th naming a missing column
+fn seed_file(statements: usize) -> String {
+    let mut sql = String::new();
+    for i in 0..statements {
+        let column = if i % 1000 == 999 { "nme" } else { "name" };
+        sql.push_str(&format!(
+            "INSERT INTO users (id, {column}, email) VALUES ({i}, 'user {i}', 'user{i}@example.com');\n"
+        ));
+    }
+    sql
+}
+
+/// Peak resident set size of the process in KiB, where the OS reports it
+fn peak_rss_kib() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+#[test]
+#[ignore]
+fn test_streaming_large_file_in_bounded_memory() {
+    let mut builder = SchemaBuilder::new();
+    builder
+        .parse("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, email TEXT);")
+        .unwrap();
+    let (catalog, _) = builder.build();
+    let mut analyzer = Analyzer::new(&catalog);
+
+    // First, so the peak isn't already raised by the whole-input sample
+    let sql = seed_file(STATEMENTS);
+    let before = peak_rss_kib();
+    let mut count = 0;
+    analyzer.analyze_streaming(&sql, |_| count += 1);
+    assert_eq!(count, STATEMENTS / 1000);
+
+    // Coarse: the input is about 9 MiB, and its ASTs would take many times that
+    if let (Some(before), Some(after)) = (before, peak_rss_kib()) {
+        let grown = after.saturating_sub(before);
+        assert!(grown < 64 * 1024, "peak RSS grew by {grown} KiB");
+    }
+
+    // Same diagnostics as the whole-input path, on a sample small enough for it
+    let sample = seed_file(5_000);
+    let mut streamed = 0;
+    analyzer.analyze_streaming(&sample, |_| streamed += 1);
+    assert_eq!(streamed, 5);
+    assert_eq!(analyzer.analyze(&sample).len(), streamed);
+}
@@ -0,0 +1,87 @@
+#ifndef SQLSURGE_H
+#define SQLSURGE_H
+
+/* Generated by cbindgen from crates/sqlsurge-ffi. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Outcome of an FFI call
+typedef enum SqlsurgeStatus {
+  SQLSURGE_STATUS_OK = 0,
+  // A required pointer argument was NULL
+  SQLSURGE_STATUS_NULL_ARGUMENT = 1,
+  // A string argument was not valid UTF-8
+  SQLSURGE_STATUS_INVALID_UTF8 = 2,
+  // The schema has errors, e.g. conflicting definitions; the error
+  // document lists them (statements that don't parse are skipped, as in
+  // the CLI)
+  SQLSURGE_STATUS_SCHEMA_ERROR = 3,
+  // The dialect is not one of postgresql, mysql (optionally `@version`)
+  SQLSURGE_STATUS_UNKNOWN_DIALECT = 4,
+  // sqlsurge panicked; this is a bug
+  SQLSURGE_STATUS_PANIC = 5,
+} SqlsurgeStatus;
+
+// Opaque handle to a schema catalog
+typedef struct SqlsurgeCatalog SqlsurgeCatalog;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Build a catalog from schema SQL (CREATE TABLE, CREATE VIEW, ...)
+//
+// `dialect` is e.g. "postgresql" or "mysql"; NULL means PostgreSQL. It
+// selects the parser and the catalog's default schema, so pass the dialect
+// the queries are analyzed in. Returns NULL on failure;
+// [`sqlsurge_last_error`] and [`sqlsurge_last_error_message`] then describe
+// the error.
+//
+// # Safety
+// `schema_sql` and `dialect` must be NULL or valid NUL-terminated strings.
+SqlsurgeCatalog *sqlsurge_catalog_from_sql(const char *schema_sql, const char *dialect);
+
+// Analyze query SQL against a catalog
+//
+// `dialect` is e.g. "postgresql" or "mysql@5.7"; NULL means PostgreSQL. Returns the JSON report (`{"version", "file", "dialect",
+// "diagnostics"}`), or an error document (`{"version", "error": {"code",
+// "status", "message"}}`) if the call itself failed. Query syntax errors are
+// reported as E1000 diagnostics, not as call failures. Never returns NULL.
+//
+// # Safety
+// `catalog` must be NULL or a handle from [`sqlsurge_catalog_from_sql`]
+// that has not been freed. `query_sql` and `dialect` must be NULL or valid
+// NUL-terminated strings.
+char *sqlsurge_analyze(const SqlsurgeCatalog *catalog,
+                       const char *query_sql,
+                       const char *dialect);
+
+// Status of the last call on this thread ([`SqlsurgeStatus::Ok`] if it succeeded)
+SqlsurgeStatus sqlsurge_last_error(void);
+
+// Error document of the last call on this thread, or NULL if it succeeded
+//
+// The caller frees the result with [`sqlsurge_free`].
+char *sqlsurge_last_error_message(void);
+
+// Free a string returned by sqlsurge (NULL is ignored)
+//
+// # Safety
+// `s` must be NULL or a string returned by sqlsurge that has not been freed.
+void sqlsurge_free(char *s);
+
+// Free a catalog (NULL is ignored)
+//
+// # Safety
+// `catalog` must be NULL or a handle from [`sqlsurge_catalog_from_sql`]
+// that has not been freed.
+void sqlsurge_catalog_free(SqlsurgeCatalog *catalog);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SQLSURGE_H */
@@ -0,0 +1,316 @@
+//! sqlsurge-ffi: C ABI for sqlsurge
+//!
+//! A minimal interface for tools written in other languages: build a catalog
+//! from schema SQL once, then analyze queries against it. Diagnostics come
+//! back as the same versioned JSON document the CLI prints for
+//! `--format json` ([`sqlsurge_core::json::JsonReport`]).
+//!
+//! - Strings passed in are NUL-terminated UTF-8.
+//! - Strings returned are owned by the caller and must be released with
+//!   [`sqlsurge_free`]; catalogs with [`sqlsurge_catalog_free`].
+//! - No panic crosses the boundary: every entry point catches unwinds and
+//!   reports them as [`SqlsurgeStatus::Panic`].
+//!
+//! The C header is generated with cbindgen into `include/sqlsurge.h`.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use serde::Serialize;
+use sqlsurge_core::dialect::DialectSpec;
+use sqlsurge_core::json::{JsonDiagnostic, JsonReport, FORMAT_VERSION};
+use sqlsurge_core::schema::SchemaBuilder;
+use sqlsurge_core::{Analyzer, Catalog, Diagnostic};
+
+/// File name reported for schema SQL passed to [`sqlsurge_catalog_from_sql`]
+const SCHEMA_FILE: &str = "<schema>";
+/// File name reported for query SQL passed to [`sqlsurge_analyze`]
+const QUERY_FILE: &str = "<query>";
+
+/// Outcome of an FFI call
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlsurgeStatus {
+    Ok = 0,
+    /// A required pointer argument was NULL
+    NullArgument = 1,
+    /// A string argument was not valid UTF-8
+    InvalidUtf8 = 2,
+    /// The schema has errors, e.g. conflicting definitions; the error
+    /// document lists them (statements that don't parse are skipped, as in
+    /// the CLI)
+    SchemaError = 3,
+    /// The dialect is not one of postgresql, mysql (optionally `@version`)
+    UnknownDialect = 4,
+    /// sqlsurge panicked; this is a bug
+    Panic = 5,
+}
+
+impl SqlsurgeStatus {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::NullArgument => "null-argument",
+            Self::InvalidUtf8 => "invalid-utf8",
+            Self::SchemaError => "schema-error",
+            Self::UnknownDialect => "unknown-dialect",
+            Self::Panic => "panic",
+        }
+    }
+}
+
+/// Opaque handle to a schema catalog
+pub struct SqlsurgeCatalog {
+    catalog: Catalog,
+}
+
+/// Error document returned in place of a report
+#[derive(Debug, Serialize)]
+struct JsonError<'a> {
+    version: u32,
+    error: JsonErrorBody<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonErrorBody<'a> {
+    /// Numeric [`SqlsurgeStatus`]
+    code: u32,
+    /// Status name, e.g. "schema-error"
+    status: &'static str,
+    message: &'a str,
+    /// Schema diagnostics for [`SqlsurgeStatus::SchemaError`]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    diagnostics: Vec<JsonDiagnostic<'a>>,
+}
+
+/// A failed call: status, message and (for schema errors) diagnostics
+struct Failure {
+    status: SqlsurgeStatus,
+    message: String,
+    diagnostics: Vec<Diagnostic>,
+    source: String,
+}
+
+impl Failure {
+    fn new(status: SqlsurgeStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+            diagnostics: Vec::new(),
+            source: String::new(),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let error = JsonError {
+            version: FORMAT_VERSION,
+            error: JsonErrorBody {
+                code: self.status as u32,
+                status: self.status.name(),
+                message: &self.message,
+                diagnostics: self
+                    .diagnostics
+                    .iter()
+                    .map(|d| JsonDiagnostic::new(SCHEMA_FILE, d, &self.source))
+                    .collect(),
+            },
+        };
+        serde_json::to_string(&error).unwrap_or_default()
+    }
+}
+
+thread_local! {
+    /// Status and error document of the last failed call on this thread
+    static LAST_ERROR: RefCell<Option<(SqlsurgeStatus, String)>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(failure: Option<&Failure>) {
+    let error = failure.map(|f| (f.status, f.to_json()));
+    LAST_ERROR.with(|last| *last.borrow_mut() = error);
+}
+
+/// Run `f`, turning a panic into a [`SqlsurgeStatus::Panic`] failure
+fn guard<T>(f: impl FnOnce() -> Result<T, Failure>) -> Result<T, Failure> {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(Failure::new(
+            SqlsurgeStatus::Panic,
+            format!("sqlsurge panicked: {}", message),
+        ))
+    })
+}
+
+/// Borrow a C string argument
+///
+/// # Safety
+/// `ptr` must be NULL or point to a NUL-terminated string valid for `'a`.
+unsafe fn str_arg<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, Failure> {
+    if ptr.is_null() {
+        return Err(Failure::new(
+            SqlsurgeStatus::NullArgument,
+            format!("'{}' must not be NULL", name),
+        ));
+    }
+    CStr::from_ptr(ptr).to_str().map_err(|_| {
+        Failure::new(
+            SqlsurgeStatus::InvalidUtf8,
+            format!("'{}' is not valid UTF-8", name),
+        )
+    })
+}
+
+/// Read an optional dialect argument; NULL means the default (PostgreSQL)
+///
+/// # Safety
+/// `ptr` must be NULL or point to a NUL-terminated string.
+unsafe fn dialect_arg(ptr: *const c_char) -> Result<DialectSpec, Failure> {
+    if ptr.is_null() {
+        return Ok(DialectSpec::default());
+    }
+    str_arg(ptr, "dialect")?
+        .parse::<DialectSpec>()
+        .map_err(|e| Failure::new(SqlsurgeStatus::UnknownDialect, e))
+}
+
+/// Hand a string to the caller
+fn into_c_string(s: String) -> *mut c_char {
+    // Only JSON is returned, and JSON escapes NUL
+    CString::new(s).map_or(std::ptr::null_mut(), CString::into_raw)
+}
+
+/// Build a catalog from schema SQL (CREATE TABLE, CREATE VIEW, ...)
+///
+/// `dialect` is e.g. "postgresql" or "mysql"; NULL means PostgreSQL. It
+/// selects the parser and the catalog's default schema, so pass the dialect
+/// the queries are analyzed in. Returns NULL on failure;
+/// [`sqlsurge_last_error`] and [`sqlsurge_last_error_message`] then describe
+/// the error.
+///
+/// # Safety
+/// `schema_sql` and `dialect` must be NULL or valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn sqlsurge_catalog_from_sql(
+    schema_sql: *const c_char,
+    dialect: *const c_char,
+) -> *mut SqlsurgeCatalog {
+    let result = guard(|| {
+        let sql = str_arg(schema_sql, "schema_sql")?;
+        let spec = dialect_arg(dialect)?;
+        let mut builder = SchemaBuilder::with_dialect(spec.dialect);
+        if let Err(diagnostics) = builder.parse_named(SCHEMA_FILE, sql) {
+            return Err(Failure {
+                status: SqlsurgeStatus::SchemaError,
+                message: format!("schema has {} error(s)", diagnostics.len()),
+                diagnostics,
+                source: sql.to_string(),
+            });
+        }
+        Ok(builder.build().0)
+    });
+    match result {
+        Ok(catalog) => {
+            set_last_error(None);
+            Box::into_raw(Box::new(SqlsurgeCatalog { catalog }))
+        }
+        Err(failure) => {
+            set_last_error(Some(&failure));
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Analyze query SQL against a catalog
+///
+/// `dialect` is e.g. "postgresql" or "mysql@5.7"; NULL means PostgreSQL. Returns the JSON report (`{"version", "file", "dialect",
+/// "diagnostics"}`), or an error document (`{"version", "error": {"code",
+/// "status", "message"}}`) if the call itself failed. Query syntax errors are
+/// reported as E1000 diagnostics, not as call failures. Never returns NULL.
+///
+/// # Safety
+/// `catalog` must be NULL or a handle from [`sqlsurge_catalog_from_sql`]
+/// that has not been freed. `query_sql` and `dialect` must be NULL or valid
+/// NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn sqlsurge_analyze(
+    catalog: *const SqlsurgeCatalog,
+    query_sql: *const c_char,
+    dialect: *const c_char,
+) -> *mut c_char {
+    let result = guard(|| {
+        let catalog = catalog.as_ref().ok_or_else(|| {
+            Failure::new(SqlsurgeStatus::NullArgument, "'catalog' must not be NULL")
+        })?;
+        let sql = str_arg(query_sql, "query_sql")?;
+        let spec = dialect_arg(dialect)?;
+
+        let mut analyzer = Analyzer::with_dialect(&catalog.catalog, spec.dialect);
+        if let Some(version) = spec.version {
+            analyzer = analyzer.with_version(version);
+        }
+        let diagnostics = analyzer.analyze(sql);
+        let dialect = spec.to_string();
+        let report = JsonReport {
+            version: FORMAT_VERSION,
+            file: QUERY_FILE,
+            dialect: Some(&dialect),
+            diagnostics: diagnostics
+                .iter()
+                .map(|d| JsonDiagnostic::new(QUERY_FILE, d, sql))
+                .collect(),
+        };
+        Ok(serde_json::to_string(&report).expect("report serializes"))
+    });
+    match result {
+        Ok(json) => {
+            set_last_error(None);
+            into_c_string(json)
+        }
+        Err(failure) => {
+            set_last_error(Some(&failure));
+            into_c_string(failure.to_json())
+        }
+    }
+}
+
+/// Status of the last call on this thread ([`SqlsurgeStatus::Ok`] if it succeeded)
+#[no_mangle]
+pub extern "C" fn sqlsurge_last_error() -> SqlsurgeStatus {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(SqlsurgeStatus::Ok, |e| e.0))
+}
+
+/// Error document of the last call on this thread, or NULL if it succeeded
+///
+/// The caller frees the result with [`sqlsurge_free`].
+#[no_mangle]
+pub extern "C" fn sqlsurge_last_error_message() -> *mut c_char {
+    let json = LAST_ERROR.with(|last| last.borrow().as_ref().map(|e| e.1.clone()));
+    json.map_or(std::ptr::null_mut(), into_c_string)
+}
+
+/// Free a string returned by sqlsurge (NULL is ignored)
+///
+/// # Safety
+/// `s` must be NULL or a string returned by sqlsurge that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sqlsurge_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Free a catalog (NULL is ignored)
+///
+/// # Safety
+/// `catalog` must be NULL or a handle from [`sqlsurge_catalog_from_sql`]
+/// that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sqlsurge_catalog_free(catalog: *mut SqlsurgeCatalog) {
+    if !catalog.is_null() {
+        drop(Box::from_raw(catalog));
+    }
+}
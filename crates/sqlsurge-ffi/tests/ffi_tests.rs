@@ -0,0 +1,144 @@
+//! Tests of the C ABI, called from Rust and from Python (ctypes)
+
+use std::ffi::{c_char, CStr, CString};
+use std::path::PathBuf;
+use std::process::Command;
+
+use sqlsurge_ffi::*;
+
+/// Take ownership of a returned string and parse it as JSON
+fn take_json(ptr: *mut c_char) -> serde_json::Value {
+    assert!(!ptr.is_null());
+    let json = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+    unsafe { sqlsurge_free(ptr) };
+    serde_json::from_str(&json).unwrap()
+}
+
+fn catalog(schema: &str) -> *mut SqlsurgeCatalog {
+    catalog_in(schema, None)
+}
+
+fn catalog_in(schema: &str, dialect: Option<&str>) -> *mut SqlsurgeCatalog {
+    let schema = CString::new(schema).unwrap();
+    let dialect = dialect.map(|d| CString::new(d).unwrap());
+    let dialect_ptr = dialect.as_ref().map_or(std::ptr::null(), |d| d.as_ptr());
+    unsafe { sqlsurge_catalog_from_sql(schema.as_ptr(), dialect_ptr) }
+}
+
+fn analyze(catalog: *const SqlsurgeCatalog, sql: &str, dialect: Option<&str>) -> serde_json::Value {
+    let sql = CString::new(sql).unwrap();
+    let dialect = dialect.map(|d| CString::new(d).unwrap());
+    let dialect_ptr = dialect.as_ref().map_or(std::ptr::null(), |d| d.as_ptr());
+    take_json(unsafe { sqlsurge_analyze(catalog, sql.as_ptr(), dialect_ptr) })
+}
+
+const SCHEMA: &str = "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL);";
+
+#[test]
+fn test_analyze_returns_json_report() {
+    let catalog = catalog(SCHEMA);
+    assert!(!catalog.is_null());
+    assert_eq!(sqlsurge_last_error(), SqlsurgeStatus::Ok);
+
+    let report = analyze(catalog, "SELECT nme FROM users;", Some("mysql@8.0"));
+    assert_eq!(report["version"], 1);
+    assert_eq!(report["dialect"], "mysql@8.0");
+    assert_eq!(report["diagnostics"][0]["code"], "E0002");
+    assert_eq!(report["diagnostics"][0]["column"], 8);
+
+    // Query syntax errors are diagnostics, not call failures
+    let report = analyze(catalog, "SELEC id FROM users;", None);
+    assert_eq!(report["diagnostics"][0]["code"], "E1000");
+    assert_eq!(sqlsurge_last_error(), SqlsurgeStatus::Ok);
+
+    unsafe { sqlsurge_catalog_free(catalog) };
+}
+
+#[test]
+fn test_catalog_in_dialect() {
+    let schema =
+        "CREATE TABLE `users` (`id` INT UNSIGNED AUTO_INCREMENT PRIMARY KEY) ENGINE=InnoDB;";
+    let catalog = catalog_in(schema, Some("mysql"));
+    assert!(!catalog.is_null());
+
+    // The table is defined: only the column is unknown
+    let report = analyze(catalog, "SELECT nme FROM users;", Some("mysql"));
+    let codes: Vec<_> = report["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| d["code"].clone())
+        .collect();
+    assert_eq!(codes, ["E0002"], "{}", report);
+
+    unsafe { sqlsurge_catalog_free(catalog) };
+}
+
+#[test]
+fn test_call_failures_return_error_documents() {
+    let schema = unsafe { sqlsurge_catalog_from_sql(std::ptr::null(), std::ptr::null()) };
+    assert!(schema.is_null());
+    assert_eq!(sqlsurge_last_error(), SqlsurgeStatus::NullArgument);
+    let error = take_json(sqlsurge_last_error_message());
+    assert_eq!(error["error"]["code"], 1);
+    assert_eq!(error["error"]["message"], "'schema_sql' must not be NULL");
+
+    let report = analyze(std::ptr::null(), "SELECT 1", None);
+    assert_eq!(report["error"]["status"], "null-argument");
+
+    let catalog = catalog(SCHEMA);
+    let report = analyze(catalog, "SELECT 1", Some("oracle"));
+    assert_eq!(report["error"]["status"], "unknown-dialect");
+    assert_eq!(sqlsurge_last_error(), SqlsurgeStatus::UnknownDialect);
+
+    // The documented dialects only
+    assert!(catalog_in(SCHEMA, Some("sqlite")).is_null());
+    assert_eq!(sqlsurge_last_error(), SqlsurgeStatus::UnknownDialect);
+
+    let invalid = b"SELECT \xff\0";
+    let report =
+        take_json(unsafe { sqlsurge_analyze(catalog, invalid.as_ptr().cast(), std::ptr::null()) });
+    assert_eq!(report["error"]["status"], "invalid-utf8");
+
+    unsafe {
+        sqlsurge_catalog_free(catalog);
+        sqlsurge_free(std::ptr::null_mut());
+    }
+}
+
+/// The shared library cargo builds for this crate: `target/<profile>/` when
+/// the cdylib was built on its own, `deps/` when only `cargo test` built it
+fn cdylib_path() -> Option<PathBuf> {
+    let exe = std::env::current_exe().unwrap();
+    let deps = exe.parent().unwrap();
+    let name = format!(
+        "{}sqlsurge_ffi{}",
+        std::env::consts::DLL_PREFIX,
+        std::env::consts::DLL_SUFFIX
+    );
+    [deps.parent().unwrap().join(&name), deps.join(&name)]
+        .into_iter()
+        .find(|path| path.exists())
+}
+
+#[test]
+fn test_python_ctypes_example() {
+    let script = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/python/example.py");
+    let Some(library) = cdylib_path() else {
+        eprintln!("libsqlsurge_ffi not built; skipping the ctypes example");
+        return;
+    };
+    let output = match Command::new("python3").arg(&script).arg(library).output() {
+        Ok(output) => output,
+        Err(_) => {
+            eprintln!("python3 not found; skipping the ctypes example");
+            return;
+        }
+    };
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "ok");
+}
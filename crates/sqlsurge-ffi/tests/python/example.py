@@ -0,0 +1,76 @@
+"""Analyze a query through the sqlsurge C ABI with ctypes.
+
+Usage: python3 example.py path/to/libsqlsurge_ffi.so
+"""
+
+import ctypes
+import json
+import sys
+
+
+class Sqlsurge:
+    def __init__(self, path):
+        lib = ctypes.CDLL(path)
+        lib.sqlsurge_catalog_from_sql.argtypes = [ctypes.c_char_p, ctypes.c_char_p]
+        lib.sqlsurge_catalog_from_sql.restype = ctypes.c_void_p
+        lib.sqlsurge_analyze.argtypes = [ctypes.c_void_p, ctypes.c_char_p, ctypes.c_char_p]
+        # Returned strings must go back to sqlsurge_free, so keep the raw pointer
+        lib.sqlsurge_analyze.restype = ctypes.c_void_p
+        lib.sqlsurge_last_error.restype = ctypes.c_int
+        lib.sqlsurge_last_error_message.restype = ctypes.c_void_p
+        lib.sqlsurge_free.argtypes = [ctypes.c_void_p]
+        lib.sqlsurge_catalog_free.argtypes = [ctypes.c_void_p]
+        self.lib = lib
+
+    def _take_json(self, ptr):
+        try:
+            return json.loads(ctypes.string_at(ptr).decode("utf-8"))
+        finally:
+            self.lib.sqlsurge_free(ptr)
+
+    def catalog(self, schema_sql, dialect=None):
+        dialect = dialect.encode("utf-8") if dialect is not None else None
+        handle = self.lib.sqlsurge_catalog_from_sql(schema_sql.encode("utf-8"), dialect)
+        if not handle:
+            status = self.lib.sqlsurge_last_error()
+            error = self._take_json(self.lib.sqlsurge_last_error_message())
+            raise RuntimeError(status, error)
+        return handle
+
+    def analyze(self, catalog, query_sql, dialect=None):
+        dialect = dialect.encode("utf-8") if dialect is not None else None
+        return self._take_json(
+            self.lib.sqlsurge_analyze(catalog, query_sql.encode("utf-8"), dialect)
+        )
+
+    def free_catalog(self, catalog):
+        self.lib.sqlsurge_catalog_free(catalog)
+
+
+def main():
+    sqlsurge = Sqlsurge(sys.argv[1])
+
+    catalog = sqlsurge.catalog("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL);")
+    report = sqlsurge.analyze(catalog, "SELECT nme FROM users;", "postgresql")
+    assert report["version"] == 1, report
+    assert [d["code"] for d in report["diagnostics"]] == ["E0002"], report
+    assert report["diagnostics"][0]["line"] == 1, report
+
+    report = sqlsurge.analyze(catalog, "SELECT id FROM users;")
+    assert report["diagnostics"] == [], report
+
+    error = sqlsurge.analyze(catalog, "SELECT 1", "oracle")
+    assert error["error"]["status"] == "unknown-dialect", error
+    sqlsurge.free_catalog(catalog)
+
+    # Failed calls set a thread-local status and error document
+    assert sqlsurge.lib.sqlsurge_catalog_from_sql(None, None) is None
+    assert sqlsurge.lib.sqlsurge_last_error() == 1
+    error = sqlsurge._take_json(sqlsurge.lib.sqlsurge_last_error_message())
+    assert error["error"]["status"] == "null-argument", error
+
+    print("ok")
+
+
+if __name__ == "__main__":
+    main()